@@ -1,5 +1,9 @@
+// To run this example execute: cargo run --example agent --features command-executor
+
+#[cfg(feature = "command-executor")]
 use std::sync::Arc;
 
+#[cfg(feature = "command-executor")]
 use langchain_rust::{
     agent::{AgentExecutor, ConversationalAgentBuilder},
     chain::{options::ChainCallOptions, Chain},
@@ -9,6 +13,7 @@ use langchain_rust::{
     tools::CommandExecutor,
 };
 
+#[cfg(feature = "command-executor")]
 #[tokio::main]
 async fn main() {
     let llm = OpenAI::default().with_model(OpenAIModel::Gpt4Turbo);
@@ -33,3 +38,10 @@ async fn main() {
         Err(e) => panic!("Error invoking LLMChain: {:?}", e),
     }
 }
+
+#[cfg(not(feature = "command-executor"))]
+fn main() {
+    println!("This example requires the 'command-executor' feature to be enabled.");
+    println!("Please run the command as follows:");
+    println!("cargo run --example agent --features command-executor");
+}
@@ -0,0 +1,26 @@
+// To run a GGUF model locally execute: cargo run --example llm_candle --features candle -- <model.gguf>
+
+#[cfg(feature = "candle")]
+use langchain_rust::{
+    language_models::llm::LLM,
+    llm::candle::{Candle, ChatTemplate},
+};
+
+#[cfg(feature = "candle")]
+#[tokio::main]
+async fn main() {
+    let model = std::env::args()
+        .nth(1)
+        .unwrap_or("qwen2.5-0.5b-instruct-q4_k_m.gguf".to_string());
+    let llm = Candle::new(model).with_chat_template(ChatTemplate::ChatMl);
+
+    let response = llm.invoke("Why is the sky blue?").await.unwrap();
+    println!("{}", response);
+}
+
+#[cfg(not(feature = "candle"))]
+fn main() {
+    println!("This example requires the 'candle' feature to be enabled.");
+    println!("Please run the command as follows:");
+    println!("cargo run --example llm_candle --features candle -- <model.gguf>");
+}
@@ -1,9 +1,26 @@
-use langchain_rust::llm::OpenAIConfig;
+// To use the native Ollama API execute: cargo run --example llm_ollama --features ollama
 
-use langchain_rust::{language_models::llm::LLM, llm::openai::OpenAI};
+#[cfg(feature = "ollama")]
+use langchain_rust::{language_models::llm::LLM, llm::ollama::Ollama};
 
+#[cfg(feature = "ollama")]
 #[tokio::main]
 async fn main() {
+    let ollama = Ollama::default().with_model("llama3");
+    ollama.pull_model("llama3").await.unwrap();
+
+    let response = ollama.invoke("hola").await.unwrap();
+    println!("{}", response);
+}
+
+#[cfg(not(feature = "ollama"))]
+#[tokio::main]
+async fn main() {
+    use langchain_rust::{
+        language_models::llm::LLM,
+        llm::{openai::OpenAI, OpenAIConfig},
+    };
+
     //Since Ollmama is OpenAi compatible
     //You can call Ollama this way:
     let ollama = OpenAI::default()
@@ -1,6 +1,11 @@
+// To run this example execute: cargo run --example open_ai_tools_agent --features command-executor
+
+#[cfg(feature = "command-executor")]
 use std::{error::Error, sync::Arc};
 
+#[cfg(feature = "command-executor")]
 use async_trait::async_trait;
+#[cfg(feature = "command-executor")]
 use langchain_rust::{
     agent::{AgentExecutor, OpenAiToolAgentBuilder},
     chain::{options::ChainCallOptions, Chain},
@@ -10,9 +15,12 @@ use langchain_rust::{
     tools::{CommandExecutor, SerpApi, Tool},
 };
 
+#[cfg(feature = "command-executor")]
 use serde_json::Value;
+#[cfg(feature = "command-executor")]
 struct Date {}
 
+#[cfg(feature = "command-executor")]
 #[async_trait]
 impl Tool for Date {
     fn name(&self) -> String {
@@ -26,6 +34,7 @@ impl Tool for Date {
     }
 }
 
+#[cfg(feature = "command-executor")]
 #[tokio::main]
 async fn main() {
     let llm = OpenAI::default();
@@ -56,3 +65,10 @@ async fn main() {
         Err(e) => panic!("Error invoking LLMChain: {:?}", e),
     }
 }
+
+#[cfg(not(feature = "command-executor"))]
+fn main() {
+    println!("This example requires the 'command-executor' feature to be enabled.");
+    println!("Please run the command as follows:");
+    println!("cargo run --example open_ai_tools_agent --features command-executor");
+}
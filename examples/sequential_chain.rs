@@ -32,7 +32,7 @@ async fn main() {
         .build()
         .unwrap();
 
-    let sequential_chain = sequential_chain!(get_name_chain, get_slogan_chain);
+    let sequential_chain = sequential_chain!(get_name_chain, get_slogan_chain).unwrap();
 
     print!("Please enter a product: ");
     io::stdout().flush().unwrap(); // Display prompt to terminal
@@ -115,12 +115,8 @@ async fn add_documents_to_index(store: &Store) -> Result<Vec<String>, Box<dyn Er
 
     let doc4 = Document::new("Capital of France is Paris.");
 
-    let opts = VecStoreOptions {
-        name_space: None,
-        score_threshold: None,
-        filters: None,
-        embedder: Some(store.embedder.clone()),
-    };
+    let mut opts = VecStoreOptions::new();
+    opts.embedder = Some(store.embedder.clone());
 
     let result = store
         .add_documents(&vec![doc1, doc2, doc3, doc4], &opts)
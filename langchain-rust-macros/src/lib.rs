@@ -0,0 +1,248 @@
+//! The procedural macros of langchain-rust, re-exported by it.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Error, Expr, ExprLit, FnArg, ItemFn, Lit, LitStr, Meta, Pat,
+    ReturnType, Type,
+};
+
+/// Turns an async function into a tool, named as the function and described by its doc
+/// comment. The tool is a unit struct named after the function, e.g. `WebSearchTool` for
+/// `web_search`, implementing `langchain_rust::tools::StructuredTool`, and so `Tool`.
+///
+/// The parameters are the arguments of the tool, described by their doc comments, and
+/// are gathered in a struct deriving `Deserialize` and `JsonSchema`, e.g.
+/// `WebSearchToolArgs`. Their types must implement `Deserialize` and `JsonSchema`, and
+/// the `Option` ones are not required. The function returns a `Into<String>` or a `Result` of it, whose error is
+/// returned by the tool.
+///
+/// The name and the description can be set with `#[tool(name = "...", description = "...")]`.
+///
+/// # Example
+/// ```rust,ignore
+/// use langchain_rust::tools::tool;
+///
+/// /// Searches the web for the query.
+/// #[tool]
+/// async fn web_search(
+///     /// The search query
+///     query: String,
+///     /// The maximum number of results, 5 by default
+///     limit: Option<u32>,
+/// ) -> Result<String, Box<dyn std::error::Error>> {
+///     search(&query, limit.unwrap_or(5)).await
+/// }
+///
+/// let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(WebSearchTool)];
+/// ```
+#[proc_macro_attribute]
+pub fn tool(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut name = None;
+    let mut description = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse::<LitStr>()?.value());
+            Ok(())
+        } else if meta.path.is_ident("description") {
+            description = Some(meta.value()?.parse::<LitStr>()?.value());
+            Ok(())
+        } else {
+            Err(meta.error("unsupported tool property, expected `name` or `description`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+    let function = parse_macro_input!(item as ItemFn);
+    expand(function, name, description)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct Argument {
+    ident: syn::Ident,
+    ty: Type,
+    description: Option<String>,
+}
+
+fn expand(
+    mut function: ItemFn,
+    name: Option<String>,
+    description: Option<String>,
+) -> syn::Result<TokenStream2> {
+    let sig = &function.sig;
+    if sig.asyncness.is_none() {
+        return Err(Error::new_spanned(
+            sig.fn_token,
+            "a tool function must be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "a tool function can't be generic",
+        ));
+    }
+
+    let mut arguments = Vec::new();
+    for input in function.sig.inputs.iter_mut() {
+        let input = match input {
+            FnArg::Typed(input) => input,
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new_spanned(
+                    receiver,
+                    "a tool function can't take `self`",
+                ))
+            }
+        };
+        let ident = match input.pat.as_ref() {
+            Pat::Ident(pat) => pat.ident.clone(),
+            pat => {
+                return Err(Error::new_spanned(
+                    pat,
+                    "the arguments of a tool function must be identifiers",
+                ))
+            }
+        };
+        if let Type::Reference(reference) = input.ty.as_ref() {
+            return Err(Error::new_spanned(
+                reference,
+                "the arguments of a tool function must be owned",
+            ));
+        }
+        // The doc comments of the arguments are only for the macro, rustc rejects them.
+        let description = doc_comment(&input.attrs);
+        input.attrs.retain(|attr| !attr.path().is_ident("doc"));
+        arguments.push(Argument {
+            ident,
+            ty: (*input.ty).clone(),
+            description,
+        });
+    }
+
+    let function_ident = &function.sig.ident;
+    let name = name.unwrap_or_else(|| function_ident.to_string());
+    let description = match description.or_else(|| doc_comment(&function.attrs)) {
+        Some(description) => description,
+        None => {
+            return Err(Error::new_spanned(
+                function_ident,
+                "document the tool function or set its description with `#[tool(description = \"...\")]`",
+            ))
+        }
+    };
+    let struct_ident = format_ident!("{}Tool", pascal_case(&function_ident.to_string()));
+    let struct_doc = format!("The tool of [`{}`].", function_ident);
+    let vis = &function.vis;
+
+    let args_ident = format_ident!("{}Args", struct_ident);
+    let args_doc = format!("The arguments of [`{}`].", function_ident);
+    let argument_idents: Vec<&syn::Ident> = arguments.iter().map(|a| &a.ident).collect();
+    let argument_types: Vec<&Type> = arguments.iter().map(|a| &a.ty).collect();
+    let argument_docs: Vec<TokenStream2> = arguments
+        .iter()
+        .map(|a| match &a.description {
+            Some(description) => quote!(#[doc = #description]),
+            None => quote!(),
+        })
+        .collect();
+
+    let call = quote!(#function_ident(#(#argument_idents),*).await);
+    let output = match &function.sig.output {
+        ReturnType::Type(_, ty) if is_result(ty) => quote!(#call?),
+        _ => call,
+    };
+
+    let private = quote!(::langchain_rust::__private);
+    let serde_crate = "::langchain_rust::__private::serde";
+    let schemars_crate = "::langchain_rust::schemars";
+    Ok(quote! {
+        #function
+
+        #[doc = #args_doc]
+        #[derive(#private::serde::Deserialize, ::langchain_rust::schemars::JsonSchema)]
+        #[serde(crate = #serde_crate)]
+        #[schemars(crate = #schemars_crate)]
+        #vis struct #args_ident {
+            #(
+                #argument_docs
+                #argument_idents: #argument_types,
+            )*
+        }
+
+        #[doc = #struct_doc]
+        #[derive(Clone, Copy, Debug, Default)]
+        #vis struct #struct_ident;
+
+        #[#private::async_trait]
+        impl ::langchain_rust::tools::StructuredTool for #struct_ident {
+            type Args = #args_ident;
+
+            fn name(&self) -> ::std::string::String {
+                #name.to_string()
+            }
+
+            fn description(&self) -> ::std::string::String {
+                #description.to_string()
+            }
+
+            async fn run(
+                &self,
+                args: #args_ident,
+            ) -> ::std::result::Result<
+                ::std::string::String,
+                ::std::boxed::Box<dyn ::std::error::Error>,
+            > {
+                let #args_ident { #(#argument_idents),* } = args;
+                ::std::result::Result::Ok(::std::convert::Into::into(#output))
+            }
+        }
+    })
+}
+
+/// The lines of the doc comment, trimmed, or `None` without doc comment.
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn last_segment_is(ty: &Type, ident: &str) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == ident),
+        _ => false,
+    }
+}
+
+fn is_result(ty: &Type) -> bool {
+    last_segment_is(ty, "Result")
+}
+
+fn pascal_case(snake_case: &str) -> String {
+    snake_case
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
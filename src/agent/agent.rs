@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use tokio::sync::mpsc;
 
 use crate::{
     prompt::PromptArgs,
@@ -18,5 +19,17 @@ pub trait Agent: Send + Sync {
         inputs: PromptArgs,
     ) -> Result<AgentEvent, AgentError>;
 
+    /// Plans like `plan`, sending the tokens of the answer to `tokens` as they are
+    /// generated. By default the agent plans with `plan` and sends no token, then
+    /// `AgentExecutor::stream` streams the answer as one token once the agent finishes.
+    async fn plan_stream(
+        &self,
+        intermediate_steps: &[(AgentAction, String)],
+        inputs: PromptArgs,
+        _tokens: &mpsc::UnboundedSender<String>,
+    ) -> Result<AgentEvent, AgentError> {
+        self.plan(intermediate_steps, inputs).await
+    }
+
     fn get_tools(&self) -> Vec<Arc<dyn Tool>>;
 }
@@ -1,31 +1,53 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
 
 use async_trait::async_trait;
-use serde_json::json;
-use tokio::sync::Mutex;
+use futures::Stream;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, Mutex};
 
 use crate::{
-    chain::{chain_trait::Chain, ChainError},
+    callbacks::{CallbackManager, RunType, StdOutCallbackHandler},
+    chain::{
+        chain_trait::{Chain, DEFAULT_OUTPUT_KEY, DEFAULT_RESULT_KEY},
+        ChainError,
+    },
     language_models::GenerateResult,
     memory::SimpleMemory,
     prompt::PromptArgs,
     schemas::{
         agent::{AgentAction, AgentEvent},
-        memory::BaseMemory,
+        memory::{save_memory_context, BaseMemory},
+        StreamData,
     },
     tools::Tool,
 };
 
 use super::{agent::Agent, AgentError};
 
+/// The output key of the steps of the agent, see `with_return_intermediate_steps`.
+pub const INTERMEDIATE_STEPS_KEY: &str = "intermediate_steps";
+
+/// The name of the planning steps in the stream of the executor.
+const AGENT_STEP: &str = "agent";
+
+/// Runs an agent, calling the tools of its actions until it answers.
+///
+/// `stream` streams the progress of the run: every planning of the agent is a step, from
+/// `StreamData::step_start` to `StreamData::step_end` with the log of its actions, every
+/// tool call goes from `StreamData::tool_start` to `StreamData::tool_end` with its
+/// observation, and the tokens of the answer are streamed as the agent generates them,
+/// see `Agent::plan_stream`. The answer of an agent which doesn't stream is streamed as
+/// one token once it finishes.
 pub struct AgentExecutor<A>
 where
     A: Agent,
 {
-    agent: A,
+    agent: Arc<A>,
     max_iterations: Option<i32>,
     break_if_error: bool,
+    return_intermediate_steps: bool,
     pub memory: Option<Arc<Mutex<dyn BaseMemory>>>,
+    callbacks: CallbackManager,
 }
 
 impl<A> AgentExecutor<A>
@@ -34,10 +56,12 @@ where
 {
     pub fn from_agent(agent: A) -> Self {
         Self {
-            agent,
+            agent: Arc::new(agent),
             max_iterations: Some(10),
             break_if_error: false,
+            return_intermediate_steps: false,
             memory: None,
+            callbacks: CallbackManager::new(),
         }
     }
 
@@ -56,6 +80,28 @@ where
         self
     }
 
+    /// Adds the actions of the agent and the observations of the tools to the outputs of
+    /// `execute`, under `intermediate_steps`.
+    pub fn with_return_intermediate_steps(mut self, return_intermediate_steps: bool) -> Self {
+        self.return_intermediate_steps = return_intermediate_steps;
+        self
+    }
+
+    /// Handlers notified of the run of the executor, the tools it calls and, as they
+    /// are nested in it, the runs of the agent.
+    pub fn with_callbacks(mut self, callbacks: CallbackManager) -> Self {
+        self.callbacks = callbacks;
+        self
+    }
+
+    /// Prints the steps of the agent to the standard output.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        if verbose {
+            self.callbacks = self.callbacks.with_handler(StdOutCallbackHandler::new());
+        }
+        self
+    }
+
     fn get_name_to_tools(&self) -> HashMap<String, Arc<dyn Tool>> {
         let mut name_to_tool = HashMap::new();
         for tool in self.agent.get_tools().iter() {
@@ -66,16 +112,42 @@ where
     }
 }
 
-#[async_trait]
-impl<A> Chain for AgentExecutor<A>
+// Implemented by hand, the clones share the agent.
+impl<A> Clone for AgentExecutor<A>
+where
+    A: Agent,
+{
+    fn clone(&self) -> Self {
+        Self {
+            agent: self.agent.clone(),
+            max_iterations: self.max_iterations,
+            break_if_error: self.break_if_error,
+            return_intermediate_steps: self.return_intermediate_steps,
+            memory: self.memory.clone(),
+            callbacks: self.callbacks.clone(),
+        }
+    }
+}
+
+impl<A> AgentExecutor<A>
 where
     A: Agent + Send + Sync,
 {
-    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
-        let mut input_variables = input_variables.clone();
+    /// Runs the agent until it answers or reaches `max_iterations`, returning the answer
+    /// and the steps taken. The progress is sent to `events`, see `stream`.
+    async fn run(
+        &self,
+        input_variables: PromptArgs,
+        events: Option<&mpsc::UnboundedSender<StreamData>>,
+    ) -> Result<(GenerateResult, Vec<(AgentAction, String)>), ChainError> {
+        let emit = |data: StreamData| {
+            if let Some(events) = events {
+                let _ = events.send(data);
+            }
+        };
+        let mut input_variables = input_variables;
         let name_to_tools = self.get_name_to_tools();
         let mut steps: Vec<(AgentAction, String)> = Vec::new();
-        log::debug!("steps: {:?}", steps);
         if let Some(memory) = &self.memory {
             let memory = memory.lock().await;
             input_variables.insert("chat_history".to_string(), json!(memory.messages()));
@@ -87,15 +159,42 @@ where
         }
 
         loop {
-            let agent_event = self
-                .agent
-                .plan(&steps, input_variables.clone())
-                .await
-                .map_err(|e| {
-                    ChainError::AgentError(format!("Error in agent planning: {}", e.to_string()))
-                })?;
+            emit(StreamData::step_start(AGENT_STEP));
+            // The tokens the agent sends are streamed while it plans.
+            let mut streamed = false;
+            let plan = match events {
+                Some(_) => {
+                    let (tokens, mut received) = mpsc::unbounded_channel();
+                    let plan = self
+                        .agent
+                        .plan_stream(&steps, input_variables.clone(), &tokens);
+                    tokio::pin!(plan);
+                    let mut emit_token = |token: String| {
+                        streamed = true;
+                        emit(StreamData::new(json!(token), token));
+                    };
+                    loop {
+                        tokio::select! {
+                            biased;
+                            Some(token) = received.recv() => emit_token(token),
+                            event = &mut plan => {
+                                while let Ok(token) = received.try_recv() {
+                                    emit_token(token);
+                                }
+                                break event;
+                            }
+                        }
+                    }
+                }
+                None => self.agent.plan(&steps, input_variables.clone()).await,
+            };
+            let agent_event = plan.map_err(|e| {
+                ChainError::AgentError(format!("Error in agent planning: {}", e.to_string()))
+            })?;
             match agent_event {
                 AgentEvent::Action(actions) => {
+                    let log: Vec<&str> = actions.iter().map(|action| action.log.as_str()).collect();
+                    emit(StreamData::step_end(AGENT_STEP, log.join("\n")));
                     for action in actions {
                         log::debug!("Action: {:?}", action.tool_input);
                         let tool = name_to_tools
@@ -105,7 +204,12 @@ where
                             })
                             .map_err(|e| ChainError::AgentError(e.to_string()))?;
 
-                        let observation_result = tool.call(&action.tool_input).await;
+                        emit(StreamData::tool_start(&action.tool, &action.tool_input));
+                        let observation_result = self
+                            .callbacks
+                            .start_run(action.tool.clone(), RunType::Tool)
+                            .trace_tool(&action.tool_input, tool.call(&action.tool_input))
+                            .await;
 
                         let observation = match observation_result {
                             Ok(result) => result,
@@ -126,36 +230,258 @@ where
                                 }
                             }
                         };
+                        emit(StreamData::tool_end(&action.tool, &observation));
 
                         steps.push((action, observation));
                     }
                 }
                 AgentEvent::Finish(finish) => {
+                    emit(StreamData::step_end(AGENT_STEP, &finish.output));
+                    if !streamed {
+                        emit(StreamData::new(json!(finish.output), &finish.output));
+                    }
                     if let Some(memory) = &self.memory {
-                        let mut memory = memory.lock().await;
-                        memory.add_user_message(&input_variables["input"]);
-                        memory.add_ai_message(&finish.output);
+                        save_memory_context(memory, &input_variables, &finish.output).await;
                     }
-                    return Ok(GenerateResult {
+                    let result = GenerateResult {
                         generation: finish.output,
                         ..Default::default()
-                    });
+                    };
+                    return Ok((result, steps));
                 }
             }
 
             if let Some(max_iterations) = self.max_iterations {
                 if steps.len() >= max_iterations as usize {
-                    return Ok(GenerateResult {
+                    emit(StreamData::new(
+                        json!("Max iterations reached"),
+                        "Max iterations reached",
+                    ));
+                    let result = GenerateResult {
                         generation: "Max iterations reached".to_string(),
                         ..Default::default()
-                    });
+                    };
+                    return Ok((result, steps));
                 }
             }
         }
     }
+}
+
+#[async_trait]
+impl<A> Chain for AgentExecutor<A>
+where
+    A: Agent + Send + Sync + 'static,
+{
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        let run = self.callbacks.start_run("AgentExecutor", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain(&inputs, async move {
+            self.run(input_variables, None)
+                .await
+                .map(|(result, _)| result)
+        })
+        .await
+    }
+
+    async fn execute(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<HashMap<String, Value>, ChainError> {
+        let run = self.callbacks.start_run("AgentExecutor", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain_outputs(&inputs, async move {
+            let (result, steps) = self.run(input_variables, None).await?;
+            let mut output = HashMap::from([
+                (DEFAULT_OUTPUT_KEY.to_string(), json!(result.generation)),
+                (DEFAULT_RESULT_KEY.to_string(), json!(result)),
+            ]);
+            if self.return_intermediate_steps {
+                let steps: Vec<Value> = steps
+                    .into_iter()
+                    .map(|(action, observation)| {
+                        json!({ "action": action, "observation": observation })
+                    })
+                    .collect();
+                output.insert(INTERMEDIATE_STEPS_KEY.to_string(), json!(steps));
+            }
+            Ok(output)
+        })
+        .await
+    }
 
     async fn invoke(&self, input_variables: PromptArgs) -> Result<String, ChainError> {
         let result = self.call(input_variables).await?;
         Ok(result.generation)
     }
+
+    async fn stream(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
+    {
+        let run = self.callbacks.start_run("AgentExecutor", RunType::Chain);
+        run.on_chain_start(&input_variables);
+        // The agent runs in the stream, with a clone of the executor.
+        let executor = self.clone();
+        let scope = run.clone();
+        let stream = async_stream::stream! {
+            let (sender, mut receiver) = mpsc::unbounded_channel();
+            let call = scope.scope(executor.run(input_variables, Some(&sender)));
+            tokio::pin!(call);
+            let result = loop {
+                tokio::select! {
+                    biased;
+                    Some(data) = receiver.recv() => yield Ok(data),
+                    result = &mut call => break result,
+                }
+            };
+            while let Ok(data) = receiver.try_recv() {
+                yield Ok(data);
+            }
+            if let Err(e) = result {
+                yield Err(e);
+            }
+        };
+        Ok(run.trace_chain_stream(Box::pin(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use futures::StreamExt;
+
+    use crate::{
+        agent::OpenAiToolAgentBuilder,
+        language_models::options::CallOptions,
+        llm::FakeLLM,
+        prompt_args,
+        schemas::{agent::AgentFinish, StreamKind, ToolCall},
+    };
+
+    use super::*;
+
+    struct UppercaseTool;
+
+    #[async_trait]
+    impl Tool for UppercaseTool {
+        fn name(&self) -> String {
+            "uppercase".to_string()
+        }
+
+        fn description(&self) -> String {
+            "Uppercases the input".to_string()
+        }
+
+        async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+            Ok(input.as_str().unwrap_or_default().to_uppercase())
+        }
+    }
+
+    /// Uppercases the input with the tool, then answers with the observation.
+    struct UppercaseAgent;
+
+    #[async_trait]
+    impl Agent for UppercaseAgent {
+        async fn plan(
+            &self,
+            intermediate_steps: &[(AgentAction, String)],
+            inputs: PromptArgs,
+        ) -> Result<AgentEvent, AgentError> {
+            match intermediate_steps.last() {
+                None => Ok(AgentEvent::Action(vec![AgentAction {
+                    tool: "uppercase".to_string(),
+                    tool_input: inputs["input"].as_str().unwrap().to_string(),
+                    log: "I need to uppercase the input".to_string(),
+                }])),
+                Some((_, observation)) => Ok(AgentEvent::Finish(AgentFinish {
+                    output: observation.clone(),
+                })),
+            }
+        }
+
+        fn get_tools(&self) -> Vec<Arc<dyn Tool>> {
+            vec![Arc::new(UppercaseTool)]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_executor_stream() {
+        let executor = AgentExecutor::from_agent(UppercaseAgent);
+        let events: Vec<StreamData> = executor
+            .stream(prompt_args! {"input" => "hello"})
+            .await
+            .unwrap()
+            .map(|data| data.unwrap())
+            .collect()
+            .await;
+
+        let kinds: Vec<StreamKind> = events.iter().map(|data| data.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                StreamKind::StepStart,
+                StreamKind::StepEnd,
+                StreamKind::ToolStart,
+                StreamKind::ToolEnd,
+                StreamKind::StepStart,
+                StreamKind::StepEnd,
+                StreamKind::Token,
+            ]
+        );
+        assert_eq!(events[1].value["output"], "I need to uppercase the input");
+        assert_eq!(events[2].value["tool"], "uppercase");
+        assert_eq!(events[3].value["output"], "HELLO");
+        assert_eq!(events[6].content, "HELLO");
+    }
+
+    #[tokio::test]
+    async fn test_agent_executor_streams_the_answer_tokens() {
+        let llm = FakeLLM::from_results(vec![
+            GenerateResult {
+                tool_calls: vec![ToolCall::new("call_1", "uppercase", r#""hello""#)],
+                ..Default::default()
+            },
+            GenerateResult {
+                generation: "It is HELLO".to_string(),
+                ..Default::default()
+            },
+        ])
+        .with_options(CallOptions::new().with_streaming_func(|_| async { Ok(()) }));
+        let agent = OpenAiToolAgentBuilder::new()
+            .tools(&[Arc::new(UppercaseTool)])
+            .build(llm)
+            .unwrap();
+        let events: Vec<StreamData> = AgentExecutor::from_agent(agent)
+            .stream(prompt_args! {"input" => "hello"})
+            .await
+            .unwrap()
+            .map(|data| data.unwrap())
+            .collect()
+            .await;
+
+        let kinds: Vec<StreamKind> = events.iter().map(|data| data.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                StreamKind::StepStart,
+                StreamKind::StepEnd,
+                StreamKind::ToolStart,
+                StreamKind::ToolEnd,
+                StreamKind::StepStart,
+                StreamKind::Token,
+                StreamKind::Token,
+                StreamKind::Token,
+                StreamKind::StepEnd,
+            ]
+        );
+        assert_eq!(events[3].value["output"], "HELLO");
+        let answer: String = events[5..8]
+            .iter()
+            .map(|data| data.content.as_str())
+            .collect();
+        assert_eq!(answer, "It is HELLO");
+    }
 }
@@ -10,5 +10,14 @@ pub use chat::*;
 mod open_ai_tools;
 pub use open_ai_tools::*;
 
+mod open_ai_functions;
+pub use open_ai_functions::*;
+
+mod react;
+pub use react::*;
+
+mod plan_and_execute;
+pub use plan_and_execute::*;
+
 mod error;
 pub use error::*;
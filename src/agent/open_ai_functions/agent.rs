@@ -0,0 +1,242 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use crate::{
+    agent::{Agent, AgentError},
+    callbacks::{CallbackHandler, CallbackManager, RunInfo},
+    language_models::llm::LLM,
+    prompt::{FormatPrompter, MessageFormatterStruct, PromptArgs},
+    schemas::{
+        agent::{AgentAction, AgentEvent, AgentFinish, LogTools},
+        messages::Message,
+        FunctionCallResponse, ToolCall,
+    },
+    tools::Tool,
+};
+
+/// An agent calling its tools through the function calling of the model: the tools are
+/// given to the LLM as function definitions, and the calls are read from the `tool_calls`
+/// of its response instead of being parsed out of the text.
+pub struct OpenAIFunctionsAgent {
+    pub(crate) llm: Box<dyn LLM>,
+    pub(crate) prompt: MessageFormatterStruct,
+    pub(crate) tools: Vec<Arc<dyn Tool>>,
+}
+
+#[async_trait]
+impl Agent for OpenAIFunctionsAgent {
+    async fn plan(
+        &self,
+        intermediate_steps: &[(AgentAction, String)],
+        inputs: PromptArgs,
+    ) -> Result<AgentEvent, AgentError> {
+        let mut inputs = inputs;
+        let scratchpad = tool_calls_scratchpad(intermediate_steps)?;
+        inputs.insert("agent_scratchpad".to_string(), json!(scratchpad));
+        let prompt = self.prompt.format_prompt(inputs)?;
+
+        let output = self.llm.generate(&prompt.to_chat_messages()).await?;
+        if output.tool_calls.is_empty() {
+            return Ok(AgentEvent::Finish(AgentFinish {
+                output: output.generation,
+            }));
+        }
+        Ok(AgentEvent::Action(tool_calls_to_actions(
+            &output.tool_calls,
+        )?))
+    }
+
+    /// Sends the tokens of the LLM when it streams its generation, e.g. with a
+    /// `streaming_func`: the answer, and the text the LLM writes with its tool calls.
+    async fn plan_stream(
+        &self,
+        intermediate_steps: &[(AgentAction, String)],
+        inputs: PromptArgs,
+        tokens: &mpsc::UnboundedSender<String>,
+    ) -> Result<AgentEvent, AgentError> {
+        CallbackManager::new()
+            .with_handler(SendTokens(tokens.clone()))
+            .scope(self.plan(intermediate_steps, inputs))
+            .await
+    }
+
+    fn get_tools(&self) -> Vec<Arc<dyn Tool>> {
+        self.tools.clone()
+    }
+}
+
+/// Sends the tokens of the LLM runs to `Agent::plan_stream`.
+pub(crate) struct SendTokens(pub(crate) mpsc::UnboundedSender<String>);
+
+impl CallbackHandler for SendTokens {
+    fn on_llm_new_token(&self, _run: &RunInfo, token: &str) {
+        let _ = self.0.send(token.to_string());
+    }
+}
+
+/// The actions of the tool calls of a response. Their logs keep all the calls of the
+/// response, as the model expects them back with the observations.
+pub(crate) fn tool_calls_to_actions(
+    tool_calls: &[ToolCall],
+) -> Result<Vec<AgentAction>, serde_json::Error> {
+    let tools = serde_json::to_string(
+        &tool_calls
+            .iter()
+            .map(FunctionCallResponse::from)
+            .collect::<Vec<_>>(),
+    )?;
+    tool_calls
+        .iter()
+        .map(|tool_call| {
+            let log = LogTools {
+                tool_id: tool_call.id.clone(),
+                tools: tools.clone(),
+            };
+            Ok(AgentAction {
+                tool: tool_call.name.clone(),
+                tool_input: tool_call.arguments_string(),
+                log: serde_json::to_string(&log)?,
+            })
+        })
+        .collect()
+}
+
+/// The messages of the steps taken: the AI message with the tool calls of each response,
+/// followed by a tool message with the observation of each call.
+pub(crate) fn tool_calls_scratchpad(
+    intermediate_steps: &[(AgentAction, String)],
+) -> Result<Vec<Message>, serde_json::Error> {
+    let mut thoughts: Vec<Message> = Vec::new();
+    let mut last_tools: Option<String> = None;
+
+    for (action, observation) in intermediate_steps {
+        let LogTools { tool_id, tools } = serde_json::from_str(&action.log)?;
+
+        // The actions of a response share their tool calls, the AI message is only added once.
+        if last_tools.as_ref() != Some(&tools) {
+            let calls: Vec<FunctionCallResponse> = serde_json::from_str(&tools)?;
+            thoughts.push(Message::new_ai_message("").with_tool_calls(json!(calls)));
+            last_tools = Some(tools);
+        }
+        thoughts.push(Message::new_tool_message(observation, tool_id));
+    }
+
+    Ok(thoughts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use serde_json::Value;
+
+    use crate::{
+        agent::{AgentExecutor, OpenAIFunctionsAgentBuilder},
+        chain::Chain,
+        language_models::{FinishReason, GenerateResult},
+        llm::FakeLLM,
+        prompt_args,
+        schemas::MessageType,
+    };
+
+    use super::*;
+
+    struct Calculator;
+
+    #[async_trait]
+    impl Tool for Calculator {
+        fn name(&self) -> String {
+            "calculator".to_string()
+        }
+
+        fn description(&self) -> String {
+            "Useful to make calculations".to_string()
+        }
+
+        async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+            assert_eq!(input, r#"{"expression":"2+2"}"#);
+            Ok("4".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openai_functions_agent_loop() {
+        // Calls the calculator, then answers with its result.
+        let llm = FakeLLM::from_results(vec![
+            GenerateResult {
+                tool_calls: vec![ToolCall::new(
+                    "call_1",
+                    "calculator",
+                    r#"{"expression": "2+2"}"#,
+                )],
+                finish_reason: Some(FinishReason::ToolCalls),
+                ..Default::default()
+            },
+            GenerateResult {
+                generation: "It's 4".to_string(),
+                finish_reason: Some(FinishReason::Stop),
+                ..Default::default()
+            },
+        ]);
+        let agent = OpenAIFunctionsAgentBuilder::new()
+            .tools(&[Arc::new(Calculator)])
+            .build(llm.clone())
+            .unwrap();
+        let executor = AgentExecutor::from_agent(agent).with_return_intermediate_steps(true);
+
+        let output = executor
+            .execute(prompt_args! {"input" => "What's 2+2?"})
+            .await
+            .unwrap();
+        assert_eq!(output["output"], "It's 4");
+        let steps = output["intermediate_steps"].as_array().unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0]["action"]["tool"], "calculator");
+
+        let observation = llm.calls()[1]
+            .iter()
+            .find(|message| matches!(message.message_type, MessageType::ToolMessage))
+            .map(|message| message.content.text());
+        assert_eq!(observation.as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn test_tool_calls_scratchpad() {
+        let first = tool_calls_to_actions(&[
+            ToolCall::new("call_1", "calculator", r#"{"expression": "2+2"}"#),
+            ToolCall::new("call_2", "calculator", r#"{"expression": "3+3"}"#),
+        ])
+        .unwrap();
+        let second =
+            tool_calls_to_actions(&[ToolCall::new("call_3", "calculator", "not json")]).unwrap();
+        assert_eq!(first[0].tool_input, r#"{"expression":"2+2"}"#);
+        assert_eq!(second[0].tool_input, "not json");
+
+        let steps: Vec<(AgentAction, String)> = first
+            .into_iter()
+            .chain(second)
+            .zip(["4", "6", "error"].map(String::from))
+            .collect();
+        let messages = tool_calls_scratchpad(&steps).unwrap();
+
+        let types: Vec<String> = messages
+            .iter()
+            .map(|message| format!("{:?}", message.message_type))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "AIMessage",
+                "ToolMessage",
+                "ToolMessage",
+                "AIMessage",
+                "ToolMessage"
+            ]
+        );
+        assert_eq!(messages[0].tool_calls.as_ref().unwrap()[1]["id"], "call_2");
+        assert_eq!(messages[4].id.as_deref(), Some("call_3"));
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use crate::{
+    agent::{open_ai_tools::prompt::PREFIX, AgentError, OpenAiToolAgent},
+    language_models::{llm::LLM, options::CallOptions},
+    schemas::FunctionDefinition,
+    tools::Tool,
+};
+
+use super::OpenAIFunctionsAgent;
+
+pub struct OpenAIFunctionsAgentBuilder {
+    tools: Option<Vec<Arc<dyn Tool>>>,
+    prefix: Option<String>,
+    options: Option<CallOptions>,
+}
+
+impl OpenAIFunctionsAgentBuilder {
+    pub fn new() -> Self {
+        Self {
+            tools: None,
+            prefix: None,
+            options: None,
+        }
+    }
+
+    pub fn tools(mut self, tools: &[Arc<dyn Tool>]) -> Self {
+        self.tools = Some(tools.to_vec());
+        self
+    }
+
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Options of the LLM, the definitions of the tools are added to them.
+    pub fn options(mut self, options: CallOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    pub fn build<L: LLM + 'static>(self, llm: L) -> Result<OpenAIFunctionsAgent, AgentError> {
+        let tools = self.tools.unwrap_or_default();
+        let prefix = self.prefix.unwrap_or_else(|| PREFIX.to_string());
+        let mut llm = llm;
+
+        let functions = tools
+            .iter()
+            .map(FunctionDefinition::from_langchain_tool)
+            .collect::<Vec<FunctionDefinition>>();
+        llm.add_options(
+            self.options
+                .unwrap_or_else(|| CallOptions::new().with_max_tokens(1000))
+                .with_functions(functions),
+        );
+
+        Ok(OpenAIFunctionsAgent {
+            llm: Box::new(llm),
+            prompt: OpenAiToolAgent::create_prompt(&prefix)?,
+            tools,
+        })
+    }
+}
+
+impl Default for OpenAIFunctionsAgentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
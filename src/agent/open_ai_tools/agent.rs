@@ -2,16 +2,20 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use serde_json::json;
+use tokio::sync::mpsc;
 
 use crate::{
-    agent::{Agent, AgentError},
+    agent::{
+        open_ai_functions::{tool_calls_scratchpad, tool_calls_to_actions, SendTokens},
+        Agent, AgentError,
+    },
+    callbacks::CallbackManager,
     chain::Chain,
     fmt_message, fmt_placeholder, fmt_template, message_formatter,
     prompt::{HumanMessagePromptTemplate, MessageFormatterStruct, PromptArgs},
     schemas::{
-        agent::{AgentAction, AgentEvent, AgentFinish, LogTools},
+        agent::{AgentAction, AgentEvent, AgentFinish},
         messages::Message,
-        FunctionCallResponse,
     },
     template_jinja2,
     tools::Tool,
@@ -36,31 +40,6 @@ impl OpenAiToolAgent {
 
         Ok(prompt)
     }
-
-    fn construct_scratchpad(
-        &self,
-        intermediate_steps: &[(AgentAction, String)],
-    ) -> Result<Vec<Message>, AgentError> {
-        let mut thoughts: Vec<Message> = Vec::new();
-
-        for (action, observation) in intermediate_steps {
-            // Deserialize directly and embed in method calls to streamline code.
-            // Extract the tool ID and tool calls from the log.
-            let LogTools { tool_id, tools } = serde_json::from_str(&action.log)?;
-            let tools: Vec<FunctionCallResponse> = serde_json::from_str(&tools)?;
-
-            // For the first action, add an AI message with all tools called in this session.
-            if thoughts.is_empty() {
-                thoughts.push(Message::new_ai_message("").with_tool_calls(json!(tools)));
-            }
-
-            // Add a tool message for each observation. Observation is the ouput of the tool call.
-            // tool_id is the id of the tool.
-            thoughts.push(Message::new_tool_message(observation, tool_id));
-        }
-
-        Ok(thoughts)
-    }
 }
 
 #[async_trait]
@@ -71,29 +50,31 @@ impl Agent for OpenAiToolAgent {
         inputs: PromptArgs,
     ) -> Result<AgentEvent, AgentError> {
         let mut inputs = inputs.clone();
-        let scratchpad = self.construct_scratchpad(&intermediate_steps)?;
+        let scratchpad = tool_calls_scratchpad(intermediate_steps)?;
         inputs.insert("agent_scratchpad".to_string(), json!(scratchpad));
-        let output = self.chain.call(inputs).await?.generation;
-        match serde_json::from_str::<Vec<FunctionCallResponse>>(&output) {
-            Ok(tools) => {
-                let mut actions: Vec<AgentAction> = Vec::new();
-                for tool in tools {
-                    //Log tools will be send as log
-                    let log: LogTools = LogTools {
-                        tool_id: tool.id.clone(),
-                        tools: output.clone(), //We send the complete tools ouput, we will need it in
-                                               //the open ai call
-                    };
-                    actions.push(AgentAction {
-                        tool: tool.function.name.clone(),
-                        tool_input: tool.function.arguments.clone(),
-                        log: serde_json::to_string(&log)?, //We send this as string to minimise changes
-                    });
-                }
-                return Ok(AgentEvent::Action(actions));
-            }
-            Err(_) => return Ok(AgentEvent::Finish(AgentFinish { output })),
+        let output = self.chain.call(inputs).await?;
+        if output.tool_calls.is_empty() {
+            return Ok(AgentEvent::Finish(AgentFinish {
+                output: output.generation,
+            }));
         }
+        Ok(AgentEvent::Action(tool_calls_to_actions(
+            &output.tool_calls,
+        )?))
+    }
+
+    /// Sends the tokens of the LLM when it streams its generation, e.g. with a
+    /// `streaming_func`: the answer, and the text the LLM writes with its tool calls.
+    async fn plan_stream(
+        &self,
+        intermediate_steps: &[(AgentAction, String)],
+        inputs: PromptArgs,
+        tokens: &mpsc::UnboundedSender<String>,
+    ) -> Result<AgentEvent, AgentError> {
+        CallbackManager::new()
+            .with_handler(SendTokens(tokens.clone()))
+            .scope(self.plan(intermediate_steps, inputs))
+            .await
     }
 
     fn get_tools(&self) -> Vec<Arc<dyn Tool>> {
@@ -4,4 +4,4 @@ pub use builder::*;
 mod agent;
 pub use agent::*;
 
-mod prompt;
+pub(crate) mod prompt;
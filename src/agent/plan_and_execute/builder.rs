@@ -0,0 +1,120 @@
+use crate::{
+    agent::AgentError,
+    callbacks::CallbackManager,
+    chain::{chain_trait::Chain, llm_chain::LLMChainBuilder},
+    language_models::llm::LLM,
+    message_formatter,
+    prompt::{HumanMessagePromptTemplate, MessageOrTemplate},
+    schemas::messages::Message,
+    template_jinja2,
+};
+
+use super::{
+    prompt::{PLANNER_PROMPT, REPLANNER_PROMPT},
+    PlanAndExecuteAgent,
+};
+
+pub struct PlanAndExecuteAgentBuilder {
+    executor: Option<Box<dyn Chain>>,
+    planner_prompt: Option<String>,
+    replanner_prompt: Option<String>,
+    max_replans: usize,
+    callbacks: Option<CallbackManager>,
+}
+
+impl Default for PlanAndExecuteAgentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlanAndExecuteAgentBuilder {
+    pub fn new() -> Self {
+        Self {
+            executor: None,
+            planner_prompt: None,
+            replanner_prompt: None,
+            max_replans: 2,
+            callbacks: None,
+        }
+    }
+
+    /// The sub-agent executing the steps, called with the objective, the completed steps
+    /// and the current step as its `input`, e.g. an `AgentExecutor`.
+    pub fn executor<C: Into<Box<dyn Chain>>>(mut self, executor: C) -> Self {
+        self.executor = Some(executor.into());
+        self
+    }
+
+    /// The system prompt of the planner, which answers the numbered list of the steps to
+    /// achieve the input.
+    pub fn planner_prompt<S: Into<String>>(mut self, planner_prompt: S) -> Self {
+        self.planner_prompt = Some(planner_prompt.into());
+        self
+    }
+
+    /// The template of the replanner, with the `{{input}}`, the `{{completed_steps}}`,
+    /// the `{{failed_step}}` and its `{{error}}`.
+    pub fn replanner_prompt<S: Into<String>>(mut self, replanner_prompt: S) -> Self {
+        self.replanner_prompt = Some(replanner_prompt.into());
+        self
+    }
+
+    /// The number of times the plan can be updated after a failed step, 2 by default.
+    pub fn max_replans(mut self, max_replans: usize) -> Self {
+        self.max_replans = max_replans;
+        self
+    }
+
+    /// Handlers notified of the runs of the agent and of its plan, see
+    /// `CallbackHandler::on_plan`.
+    pub fn callbacks(mut self, callbacks: CallbackManager) -> Self {
+        self.callbacks = Some(callbacks);
+        self
+    }
+
+    /// Builds the agent, planning and replanning with `llm`.
+    pub fn build<L: LLM + 'static>(self, llm: L) -> Result<PlanAndExecuteAgent, AgentError> {
+        let executor = self
+            .executor
+            .ok_or_else(|| AgentError::MissingObject("Executor must be set".into()))?;
+        let planner_prompt = self
+            .planner_prompt
+            .unwrap_or_else(|| PLANNER_PROMPT.to_string());
+        let replanner_prompt = self
+            .replanner_prompt
+            .unwrap_or_else(|| REPLANNER_PROMPT.to_string());
+
+        let llm: Box<dyn LLM> = Box::new(llm);
+        let planner = LLMChainBuilder::new()
+            .prompt(message_formatter![
+                MessageOrTemplate::Message(Message::new_system_message(planner_prompt)),
+                MessageOrTemplate::Template(
+                    HumanMessagePromptTemplate::new(template_jinja2!("{{input}}", "input")).into()
+                ),
+            ])
+            .llm(llm.clone_box())
+            .build()?;
+        let replanner = LLMChainBuilder::new()
+            .prompt(message_formatter![MessageOrTemplate::Template(
+                HumanMessagePromptTemplate::new(template_jinja2!(
+                    replanner_prompt,
+                    "input",
+                    "completed_steps",
+                    "failed_step",
+                    "error"
+                ))
+                .into()
+            ),])
+            .llm(llm)
+            .build()?;
+
+        Ok(PlanAndExecuteAgent {
+            planner: Box::new(planner),
+            replanner: Box::new(replanner),
+            executor,
+            max_replans: self.max_replans,
+            callbacks: self.callbacks.unwrap_or_default(),
+        })
+    }
+}
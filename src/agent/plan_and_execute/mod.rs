@@ -0,0 +1,6 @@
+mod builder;
+mod plan_and_execute_agent;
+mod prompt;
+
+pub use builder::*;
+pub use plan_and_execute_agent::*;
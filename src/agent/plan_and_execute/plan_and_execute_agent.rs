@@ -0,0 +1,335 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    callbacks::{CallbackManager, RunManager, RunType},
+    chain::{
+        chain_trait::{Chain, DEFAULT_OUTPUT_KEY, DEFAULT_RESULT_KEY},
+        ChainError,
+    },
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    prompt_args,
+};
+
+/// The output key of the final plan, with the results of its steps, in the outputs of
+/// `execute`.
+pub const PLAN_KEY: &str = "plan";
+
+/// A step of a plan, with its result once executed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub step: String,
+    pub result: Option<String>,
+}
+
+/// The plan of a `PlanAndExecuteAgent` for its objective, notified to the callbacks
+/// every time it changes, see `CallbackHandler::on_plan`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub objective: String,
+    pub steps: Vec<PlanStep>,
+    /// The number of times the plan was updated after a failed step.
+    pub replans: usize,
+}
+
+impl Plan {
+    pub fn completed_steps(&self) -> impl Iterator<Item = &PlanStep> {
+        self.steps.iter().filter(|step| step.result.is_some())
+    }
+
+    /// The completed steps with their results, as given to the executor and the replanner.
+    fn format_completed_steps(&self) -> String {
+        let steps: Vec<String> = self
+            .completed_steps()
+            .enumerate()
+            .map(|(i, step)| {
+                format!(
+                    "{}. {}\nResult: {}",
+                    i + 1,
+                    step.step,
+                    step.result.as_deref().unwrap_or_default()
+                )
+            })
+            .collect();
+        if steps.is_empty() {
+            "None".to_string()
+        } else {
+            steps.join("\n")
+        }
+    }
+}
+
+/// The steps of the numbered list of `text`, or of its non-empty lines if it has no
+/// numbered list. A `Plan:` header is skipped.
+pub fn parse_plan_steps(text: &str) -> Vec<String> {
+    static NUMBERED: OnceLock<Regex> = OnceLock::new();
+    let numbered = NUMBERED.get_or_init(|| Regex::new(r"^\s*\d+[.)]\s+(.+)$").unwrap());
+    let steps: Vec<String> = text
+        .lines()
+        .filter_map(|line| numbered.captures(line))
+        .map(|captures| captures[1].trim().to_string())
+        .collect();
+    if !steps.is_empty() {
+        return steps;
+    }
+    text.lines()
+        .map(|line| line.trim().trim_start_matches(['-', '*']).trim())
+        .filter(|line| !line.is_empty() && !line.eq_ignore_ascii_case("plan:"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// An agent for long tasks: a planner LLM first writes the steps to achieve the `input`,
+/// then every step is executed by a sub-agent with the tools, e.g. an `AgentExecutor`,
+/// given the objective and the results of the previous steps. When a step fails, the
+/// replanner updates the remaining steps, up to `max_replans` times. The answer is the
+/// result of the last step.
+///
+/// The sub-agent only fails on the errors of its tools with `with_break_if_error(true)`,
+/// otherwise it reports them to its model.
+///
+/// # Example
+/// ```rust,ignore
+/// let executor = AgentExecutor::from_agent(
+///     ReActAgentBuilder::new().tools(&tools).build(llm.clone())?,
+/// )
+/// .with_break_if_error(true);
+/// let agent = PlanAndExecuteAgentBuilder::new()
+///     .executor(executor)
+///     .max_replans(3)
+///     .build(llm)?;
+/// let answer = agent.invoke(prompt_args! {"input" => "Compare the weather of Lima and Paris"}).await?;
+/// ```
+pub struct PlanAndExecuteAgent {
+    pub(crate) planner: Box<dyn Chain>,
+    pub(crate) replanner: Box<dyn Chain>,
+    pub(crate) executor: Box<dyn Chain>,
+    pub(crate) max_replans: usize,
+    pub(crate) callbacks: CallbackManager,
+}
+
+impl PlanAndExecuteAgent {
+    async fn new_steps(
+        &self,
+        chain: &dyn Chain,
+        input_variables: PromptArgs,
+    ) -> Result<Vec<PlanStep>, ChainError> {
+        let output = chain.invoke(input_variables).await?;
+        let steps: Vec<PlanStep> = parse_plan_steps(&output)
+            .into_iter()
+            .map(|step| PlanStep { step, result: None })
+            .collect();
+        if steps.is_empty() {
+            return Err(ChainError::AgentError(format!(
+                "The planner returned no steps: {}",
+                output
+            )));
+        }
+        Ok(steps)
+    }
+
+    /// Plans, then executes the steps, returning the result of the last one and the plan.
+    async fn run(
+        &self,
+        run: &RunManager,
+        input_variables: PromptArgs,
+    ) -> Result<(GenerateResult, Plan), ChainError> {
+        let objective = match input_variables.get("input") {
+            Some(Value::String(input)) => input.clone(),
+            Some(input) => input.to_string(),
+            None => return Err(ChainError::MissingInputVariable("input".to_string())),
+        };
+        let mut plan = Plan {
+            steps: self
+                .new_steps(self.planner.as_ref(), input_variables)
+                .await?,
+            objective,
+            replans: 0,
+        };
+        run.on_plan(&plan);
+
+        let mut index = 0;
+        let mut result = GenerateResult::default();
+        while index < plan.steps.len() {
+            let step = plan.steps[index].step.clone();
+            let input = format!(
+                "Objective: {}\n\nCompleted steps:\n{}\n\nCurrent step: {}",
+                plan.objective,
+                plan.format_completed_steps(),
+                step
+            );
+            match self.executor.call(prompt_args! {"input" => input}).await {
+                Ok(step_result) => {
+                    plan.steps[index].result = Some(step_result.generation.clone());
+                    result = step_result;
+                    index += 1;
+                }
+                Err(e) if plan.replans < self.max_replans => {
+                    log::debug!("Replanning after the step {} failed: {}", step, e);
+                    let steps = self
+                        .new_steps(
+                            self.replanner.as_ref(),
+                            prompt_args! {
+                                "input" => plan.objective,
+                                "completed_steps" => plan.format_completed_steps(),
+                                "failed_step" => step,
+                                "error" => e.to_string(),
+                            },
+                        )
+                        .await?;
+                    plan.steps.truncate(index);
+                    plan.steps.extend(steps);
+                    plan.replans += 1;
+                }
+                Err(e) => return Err(e),
+            }
+            run.on_plan(&plan);
+        }
+        Ok((result, plan))
+    }
+}
+
+#[async_trait]
+impl Chain for PlanAndExecuteAgent {
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        let run = self
+            .callbacks
+            .start_run("PlanAndExecuteAgent", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain(&inputs, async {
+            self.run(&run, input_variables)
+                .await
+                .map(|(result, _)| result)
+        })
+        .await
+    }
+
+    async fn execute(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<HashMap<String, Value>, ChainError> {
+        let run = self
+            .callbacks
+            .start_run("PlanAndExecuteAgent", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain_outputs(&inputs, async {
+            let (result, plan) = self.run(&run, input_variables).await?;
+            Ok(HashMap::from([
+                (DEFAULT_OUTPUT_KEY.to_string(), json!(result.generation)),
+                (DEFAULT_RESULT_KEY.to_string(), json!(result)),
+                (PLAN_KEY.to_string(), json!(plan)),
+            ]))
+        })
+        .await
+    }
+
+    fn get_input_keys(&self) -> Vec<String> {
+        vec!["input".to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{
+        agent::PlanAndExecuteAgentBuilder,
+        callbacks::{CallbackHandler, RunInfo},
+        llm::FakeLLM,
+    };
+
+    /// Executes the steps, failing those starting with "Break".
+    struct StepExecutor;
+
+    #[async_trait]
+    impl Chain for StepExecutor {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            let input = input_variables["input"].as_str().unwrap();
+            let step = input.split("Current step: ").last().unwrap();
+            if step.starts_with("Break") {
+                return Err(ChainError::AgentError("the tool failed".to_string()));
+            }
+            Ok(GenerateResult {
+                generation: format!("done: {}", step),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct Plans(Mutex<Vec<Plan>>);
+
+    impl CallbackHandler for Plans {
+        fn on_plan(&self, _run: &RunInfo, plan: &Plan) {
+            self.0.lock().unwrap().push(plan.clone());
+        }
+    }
+
+    #[test]
+    fn test_parse_plan_steps() {
+        assert_eq!(
+            parse_plan_steps("Plan:\n1. Search the weather\n2) Answer\n"),
+            vec!["Search the weather", "Answer"]
+        );
+        assert_eq!(
+            parse_plan_steps("Plan:\n- Search\n- Answer"),
+            vec!["Search", "Answer"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plan_and_execute_agent() {
+        let llm = FakeLLM::from_responses(vec![
+            "Plan:\n1. Look up the weather\n2. Break the tool\n3. Answer",
+            "1. Answer with the weather",
+        ]);
+        let plans = Arc::new(Plans::default());
+        let agent = PlanAndExecuteAgentBuilder::new()
+            .executor(StepExecutor)
+            .callbacks(CallbackManager::new().with_shared_handler(plans.clone()))
+            .build(llm.clone())
+            .unwrap();
+
+        let outputs = agent
+            .execute(prompt_args! {"input" => "What's the weather?"})
+            .await
+            .unwrap();
+        assert_eq!(outputs[DEFAULT_OUTPUT_KEY], "done: Answer with the weather");
+        let plan: Plan = serde_json::from_value(outputs[PLAN_KEY].clone()).unwrap();
+        assert_eq!(plan.replans, 1);
+        assert_eq!(
+            plan.steps,
+            vec![
+                PlanStep {
+                    step: "Look up the weather".to_string(),
+                    result: Some("done: Look up the weather".to_string()),
+                },
+                PlanStep {
+                    step: "Answer with the weather".to_string(),
+                    result: Some("done: Answer with the weather".to_string()),
+                },
+            ]
+        );
+        let replanner_prompt = llm.calls()[1][0].content.text();
+        assert!(replanner_prompt.contains("The step \"Break the tool\" failed"));
+        assert!(replanner_prompt.contains("Result: done: Look up the weather"));
+        // Planned, then the first step, the replanning and the last step.
+        assert_eq!(plans.0.lock().unwrap().len(), 4);
+
+        let agent = PlanAndExecuteAgentBuilder::new()
+            .executor(StepExecutor)
+            .max_replans(0)
+            .build(FakeLLM::new("1. Break the tool"))
+            .unwrap();
+        assert!(matches!(
+            agent.invoke(prompt_args! {"input" => "Break"}).await,
+            Err(ChainError::AgentError(_))
+        ));
+    }
+}
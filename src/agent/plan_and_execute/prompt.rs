@@ -0,0 +1,12 @@
+pub const PLANNER_PROMPT: &str = r#"Let's first understand the problem and devise a plan to solve the problem. Please output the plan starting with the header 'Plan:' and then followed by a numbered list of steps. Please make the plan the minimum number of steps required to accurately complete the task. If the task is a question, the final step should almost always be 'Given the above steps taken, please respond to the users original question'."#;
+
+pub const REPLANNER_PROMPT: &str = r#"Your objective was:
+{{input}}
+
+The steps completed so far, with their results:
+{{completed_steps}}
+
+The step "{{failed_step}}" failed with the error:
+{{error}}
+
+Update the plan: output the numbered list of the remaining steps to achieve the objective, without the completed steps."#;
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use crate::{
+    agent::AgentError,
+    chain::{llm_chain::LLMChainBuilder, options::ChainCallOptions},
+    language_models::llm::LLM,
+    tools::Tool,
+};
+
+use super::{
+    output_parser::ReActOutputParser,
+    prompt::{FORMAT_INSTRUCTIONS, PREFIX, SUFFIX},
+    ReActAgent,
+};
+
+pub struct ReActAgentBuilder {
+    tools: Option<Vec<Arc<dyn Tool>>>,
+    prefix: Option<String>,
+    format_instructions: Option<String>,
+    suffix: Option<String>,
+    options: Option<ChainCallOptions>,
+}
+
+impl Default for ReActAgentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReActAgentBuilder {
+    pub fn new() -> Self {
+        Self {
+            tools: None,
+            prefix: None,
+            format_instructions: None,
+            suffix: None,
+            options: None,
+        }
+    }
+
+    pub fn tools(mut self, tools: &[Arc<dyn Tool>]) -> Self {
+        self.tools = Some(tools.to_vec());
+        self
+    }
+
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// The description of the Thought/Action/Observation format, `{{tool_names}}` is
+    /// replaced by the names of the tools.
+    pub fn format_instructions<S: Into<String>>(mut self, format_instructions: S) -> Self {
+        self.format_instructions = Some(format_instructions.into());
+        self
+    }
+
+    /// The template of the human message, with the `{{input}}` and the
+    /// `{{agent_scratchpad}}` of the previous steps.
+    pub fn suffix<S: Into<String>>(mut self, suffix: S) -> Self {
+        self.suffix = Some(suffix.into());
+        self
+    }
+
+    /// The options of the llm calls. The model should stop at `\nObservation:`, pass the
+    /// stop words when setting them.
+    pub fn options(mut self, options: ChainCallOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    pub fn build<L: LLM + 'static>(self, llm: L) -> Result<ReActAgent, AgentError> {
+        let tools = self.tools.unwrap_or_default();
+        let prefix = self.prefix.unwrap_or_else(|| PREFIX.to_string());
+        let format_instructions = self
+            .format_instructions
+            .unwrap_or_else(|| FORMAT_INSTRUCTIONS.to_string());
+        let suffix = self.suffix.unwrap_or_else(|| SUFFIX.to_string());
+
+        let prompt = ReActAgent::create_prompt(&tools, &prefix, &format_instructions, &suffix)?;
+        let default_options = ChainCallOptions::default()
+            .with_max_tokens(1000)
+            .with_stop_words(vec!["\nObservation:".to_string()]);
+        let chain = Box::new(
+            LLMChainBuilder::new()
+                .prompt(prompt)
+                .llm(llm)
+                .options(self.options.unwrap_or(default_options))
+                .build()?,
+        );
+
+        Ok(ReActAgent {
+            chain,
+            tools,
+            output_parser: ReActOutputParser::new(),
+        })
+    }
+}
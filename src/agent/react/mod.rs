@@ -0,0 +1,8 @@
+mod builder;
+mod output_parser;
+mod prompt;
+mod react_agent;
+
+pub use builder::*;
+pub use output_parser::*;
+pub use react_agent::*;
@@ -0,0 +1,97 @@
+use regex::Regex;
+
+use crate::{
+    agent::AgentError,
+    schemas::agent::{AgentAction, AgentEvent, AgentFinish},
+};
+
+const FINAL_ANSWER_ACTION: &str = "Final Answer:";
+const OBSERVATION: &str = "\nObservation:";
+
+/// Parses the completions of a `ReActAgent`: an `Action` and its `Action Input`, or the
+/// `Final Answer`.
+pub struct ReActOutputParser {
+    action_regex: Regex,
+}
+
+impl Default for ReActOutputParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReActOutputParser {
+    pub fn new() -> Self {
+        Self {
+            action_regex: Regex::new(
+                r"(?s)Action\s*\d*\s*:[\s]*(.*?)[\s]*Action\s*\d*\s*Input\s*\d*\s*:[\s]*(.*)",
+            )
+            .unwrap(),
+        }
+    }
+
+    pub fn parse(&self, text: &str) -> Result<AgentEvent, AgentError> {
+        log::debug!("Parsing to Agent Action: {}", text);
+        // The observations written by the model after its action are hallucinated, the
+        // real one is added by the executor.
+        let text = text.split(OBSERVATION).next().unwrap_or_default();
+        let final_answer = text.find(FINAL_ANSWER_ACTION);
+        match (self.action_regex.captures(text), final_answer) {
+            (Some(_), Some(_)) => Err(AgentError::OtherError(format!(
+                "Parsing LLM output produced both a final answer and an action: {}",
+                text
+            ))),
+            (Some(captures), None) => {
+                let tool = captures[1].trim().replace(' ', "_");
+                let tool_input = captures[2].trim().trim_matches('"').to_string();
+                Ok(AgentEvent::Action(vec![AgentAction {
+                    tool,
+                    tool_input,
+                    log: text.to_string(),
+                }]))
+            }
+            (None, Some(index)) => Ok(AgentEvent::Finish(AgentFinish {
+                output: text[index + FINAL_ANSWER_ACTION.len()..].trim().to_string(),
+            })),
+            (None, None) => Err(AgentError::OtherError(format!(
+                "Could not parse LLM output: {}",
+                text
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_react_output() {
+        let parser = ReActOutputParser::new();
+
+        let event = parser
+            .parse(
+                "I should add the numbers\nAction: Calculator\nAction Input: \"2+2\"\nObservation: 4\nFinal Answer: 4",
+            )
+            .unwrap();
+        let AgentEvent::Action(actions) = event else {
+            panic!("Expected an action");
+        };
+        assert_eq!(actions[0].tool, "Calculator");
+        assert_eq!(actions[0].tool_input, "2+2");
+        assert_eq!(
+            actions[0].log,
+            "I should add the numbers\nAction: Calculator\nAction Input: \"2+2\""
+        );
+
+        let event = parser
+            .parse(" I now know the final answer\nFinal Answer: It's 4")
+            .unwrap();
+        assert!(matches!(event, AgentEvent::Finish(finish) if finish.output == "It's 4"));
+
+        assert!(parser.parse("I don't know").is_err());
+        assert!(parser
+            .parse("Action: Calculator\nAction Input: 2+2\nFinal Answer: 4")
+            .is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::{
+    agent::{agent::Agent, AgentError},
+    chain::chain_trait::Chain,
+    message_formatter,
+    prompt::{
+        HumanMessagePromptTemplate, MessageFormatterStruct, MessageOrTemplate, PromptArgs,
+        PromptFromatter,
+    },
+    prompt_args,
+    schemas::{
+        agent::{AgentAction, AgentEvent},
+        messages::Message,
+    },
+    template_jinja2,
+    tools::Tool,
+};
+
+use super::output_parser::ReActOutputParser;
+
+/// An agent following the ReAct loop in plain text: the model writes a `Thought`, then
+/// the `Action` to take and its `Action Input`, and reads the `Observation` of the tool
+/// until it can give the `Final Answer`. Unlike `OpenAiToolAgent` it doesn't need a
+/// model supporting tool calls.
+///
+/// # Example
+/// ```rust,ignore
+/// let agent = ReActAgentBuilder::new()
+///     .tools(&[Arc::new(CommandExecutor::default())])
+///     .build(llm)?;
+/// let executor = AgentExecutor::from_agent(agent).with_max_iterations(5);
+/// let answer = executor.invoke(prompt_args! {"input" => "What's in /tmp?"}).await?;
+/// ```
+pub struct ReActAgent {
+    pub(crate) chain: Box<dyn Chain>,
+    pub(crate) tools: Vec<Arc<dyn Tool>>,
+    pub(crate) output_parser: ReActOutputParser,
+}
+
+impl ReActAgent {
+    pub fn create_prompt(
+        tools: &[Arc<dyn Tool>],
+        prefix: &str,
+        format_instructions: &str,
+        suffix: &str,
+    ) -> Result<MessageFormatterStruct, AgentError> {
+        let tool_string = tools
+            .iter()
+            .map(|tool| format!("{}: {}", tool.name(), tool.description()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let tool_names = tools
+            .iter()
+            .map(|tool| tool.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let format_instructions = template_jinja2!(format_instructions, "tool_names")
+            .format(prompt_args! {"tool_names" => tool_names})?;
+        let system_prompt = format!("{}\n\n{}\n\n{}", prefix, tool_string, format_instructions);
+
+        let formatter = message_formatter![
+            MessageOrTemplate::Message(Message::new_system_message(system_prompt)),
+            MessageOrTemplate::MessagesPlaceholder("chat_history".to_string()),
+            MessageOrTemplate::Template(
+                HumanMessagePromptTemplate::new(template_jinja2!(
+                    suffix,
+                    "input",
+                    "agent_scratchpad"
+                ))
+                .into()
+            ),
+        ];
+        Ok(formatter)
+    }
+
+    /// The previous thoughts and actions of the model, each followed by its observation.
+    fn construct_scratchpad(&self, intermediate_steps: &[(AgentAction, String)]) -> String {
+        intermediate_steps
+            .iter()
+            .map(|(action, observation)| {
+                format!("{}\nObservation: {}\nThought:", action.log, observation)
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Agent for ReActAgent {
+    async fn plan(
+        &self,
+        intermediate_steps: &[(AgentAction, String)],
+        inputs: PromptArgs,
+    ) -> Result<AgentEvent, AgentError> {
+        let scratchpad = self.construct_scratchpad(intermediate_steps);
+        let mut inputs = inputs;
+        inputs.insert("agent_scratchpad".to_string(), json!(scratchpad));
+        let output = self.chain.call(inputs).await?.generation;
+        self.output_parser.parse(&output)
+    }
+
+    fn get_tools(&self) -> Vec<Arc<dyn Tool>> {
+        self.tools.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{
+        agent::{
+            react::prompt::{FORMAT_INSTRUCTIONS, PREFIX, SUFFIX},
+            AgentExecutor, ReActAgentBuilder,
+        },
+        chain::ChainError,
+        language_models::GenerateResult,
+        llm::openai::{OpenAI, OpenAIModel},
+        prompt::MessageFormatter,
+    };
+
+    struct Calculator;
+
+    #[async_trait]
+    impl Tool for Calculator {
+        fn name(&self) -> String {
+            "calculator".to_string()
+        }
+
+        fn description(&self) -> String {
+            "Useful to make calculations".to_string()
+        }
+
+        async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+            assert_eq!(input, "2+2");
+            Ok("4".to_string())
+        }
+    }
+
+    /// Calls the calculator, then answers with its observation.
+    struct ScriptedChain;
+
+    #[async_trait]
+    impl Chain for ScriptedChain {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            let scratchpad = input_variables["agent_scratchpad"].as_str().unwrap();
+            let generation = match scratchpad.split_once("Observation: ") {
+                None => " I should add the numbers\nAction: calculator\nAction Input: 2+2",
+                Some((_, observation)) if observation.starts_with('4') => {
+                    " I now know the final answer\nFinal Answer: 4"
+                }
+                Some(_) => "Final Answer: I don't know",
+            };
+            Ok(GenerateResult {
+                generation: generation.to_string(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_react_agent_loop() {
+        let agent = ReActAgent {
+            chain: Box::new(ScriptedChain),
+            tools: vec![Arc::new(Calculator)],
+            output_parser: ReActOutputParser::new(),
+        };
+        let executor = AgentExecutor::from_agent(agent).with_return_intermediate_steps(true);
+
+        let output = executor
+            .execute(prompt_args! {"input" => "What's 2+2?"})
+            .await
+            .unwrap();
+        assert_eq!(output["output"], "4");
+        let steps = output["intermediate_steps"].as_array().unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0]["action"]["tool"], "calculator");
+        assert_eq!(steps[0]["observation"], "4");
+    }
+
+    #[test]
+    fn test_create_prompt() {
+        let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(Calculator)];
+        let prompt =
+            ReActAgent::create_prompt(&tools, PREFIX, FORMAT_INSTRUCTIONS, SUFFIX).unwrap();
+        let messages = prompt
+            .format_messages(prompt_args! {
+                "chat_history" => Vec::<Message>::new(),
+                "input" => "What's 2+2?",
+                "agent_scratchpad" => "",
+            })
+            .unwrap();
+        assert!(messages[0]
+            .content
+            .text()
+            .contains("calculator: Useful to make calculations"));
+        assert!(messages[0]
+            .content
+            .text()
+            .contains("should be one of [calculator]"));
+        assert!(messages[1]
+            .content
+            .text()
+            .ends_with("Question: What's 2+2?\nThought:"));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_invoke_react_agent() {
+        let llm = OpenAI::default().with_model(OpenAIModel::Gpt4.to_string());
+        let agent = ReActAgentBuilder::new()
+            .tools(&[Arc::new(Calculator)])
+            .build(llm)
+            .unwrap();
+        let executor = AgentExecutor::from_agent(agent).with_max_iterations(3);
+        let result = executor
+            .invoke(prompt_args! {"input" => "What's 2+2? Use the calculator"})
+            .await
+            .unwrap();
+        println!("Result: {:?}", result);
+    }
+}
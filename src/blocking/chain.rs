@@ -0,0 +1,71 @@
+use crate::{
+    chain::{Chain, ChainError},
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+};
+
+use super::block_on;
+
+/// Blocking versions of the methods of `Chain`, see the `blocking` module.
+pub trait ChainBlocking: Chain {
+    fn call_blocking(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        block_on(self.call(input_variables))
+    }
+
+    fn invoke_blocking(&self, input_variables: PromptArgs) -> Result<String, ChainError> {
+        block_on(self.invoke(input_variables))
+    }
+
+    /// Calls the chain on every input, up to `max_concurrency` at the same time, see
+    /// `Chain::batch`.
+    fn batch_blocking(
+        &self,
+        inputs: Vec<PromptArgs>,
+        max_concurrency: usize,
+    ) -> Vec<Result<GenerateResult, ChainError>> {
+        block_on(self.batch(inputs, max_concurrency))
+    }
+}
+
+impl<C: Chain + ?Sized> ChainBlocking for C {}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::prompt_args;
+
+    struct EchoChain;
+
+    #[async_trait]
+    impl Chain for EchoChain {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            Ok(GenerateResult {
+                generation: input_variables["input"].as_str().unwrap().to_string(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn test_chain_blocking() {
+        let chain: Box<dyn Chain> = Box::new(EchoChain);
+        assert_eq!(
+            chain
+                .invoke_blocking(prompt_args! {"input" => "hello"})
+                .unwrap(),
+            "hello"
+        );
+
+        let results = EchoChain.batch_blocking(
+            vec![prompt_args! {"input" => "a"}, prompt_args! {"input" => "b"}],
+            2,
+        );
+        let generations: Vec<_> = results
+            .into_iter()
+            .map(|result| result.unwrap().generation)
+            .collect();
+        assert_eq!(generations, vec!["a", "b"]);
+    }
+}
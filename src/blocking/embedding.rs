@@ -0,0 +1,19 @@
+use crate::embedding::{Embedder, EmbedderError};
+
+use super::block_on;
+
+/// Blocking versions of the methods of `Embedder`, see the `blocking` module.
+pub trait EmbedderBlocking: Embedder {
+    fn embed_documents_blocking(
+        &self,
+        documents: &[String],
+    ) -> Result<Vec<Vec<f64>>, EmbedderError> {
+        block_on(self.embed_documents(documents))
+    }
+
+    fn embed_query_blocking(&self, text: &str) -> Result<Vec<f64>, EmbedderError> {
+        block_on(self.embed_query(text))
+    }
+}
+
+impl<E: Embedder + ?Sized> EmbedderBlocking for E {}
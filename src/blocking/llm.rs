@@ -0,0 +1,19 @@
+use crate::{
+    language_models::{llm::LLM, GenerateResult, LLMError},
+    schemas::Message,
+};
+
+use super::block_on;
+
+/// Blocking versions of the methods of `LLM`, see the `blocking` module.
+pub trait LLMBlocking: LLM {
+    fn generate_blocking(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        block_on(self.generate(messages))
+    }
+
+    fn invoke_blocking(&self, prompt: &str) -> Result<String, LLMError> {
+        block_on(self.invoke(prompt))
+    }
+}
+
+impl<L: LLM + ?Sized> LLMBlocking for L {}
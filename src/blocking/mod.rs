@@ -0,0 +1,39 @@
+//! Blocking versions of the async API, for CLI tools and codebases without an async
+//! runtime.
+//!
+//! The traits are implemented for every chain, LLM, embedder, vector store, retriever
+//! and tool: import them and call the `_blocking` methods. The futures run on a tokio
+//! runtime shared by the whole process, started on the first call.
+//!
+//! # Example
+//! ```rust,ignore
+//! use langchain_rust::blocking::{ChainBlocking, VectorStoreBlocking};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let documents = store.similarity_search_blocking("vacations", 2, &VecStoreOptions::default())?;
+//!     let answer = chain.invoke_blocking(prompt_args! {"input" => "How many vacation days do I have?"})?;
+//!     println!("{}", answer);
+//!     Ok(())
+//! }
+//! ```
+
+// The methods return the same errors as the async API.
+#![allow(clippy::result_large_err)]
+
+mod runtime;
+pub use runtime::*;
+
+mod chain;
+pub use chain::*;
+
+mod llm;
+pub use llm::*;
+
+mod embedding;
+pub use embedding::*;
+
+mod vectorstore;
+pub use vectorstore::*;
+
+mod tools;
+pub use tools::*;
@@ -0,0 +1,57 @@
+use std::{future::Future, sync::OnceLock};
+
+use tokio::runtime::{Builder, Handle, Runtime, RuntimeFlavor};
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .enable_all()
+            .thread_name("langchain-rust-blocking")
+            .build()
+            .expect("Failed to start the tokio runtime of the blocking API")
+    })
+}
+
+/// Runs `future` to completion, blocking the current thread.
+///
+/// Outside of a tokio runtime the future runs on the runtime of the blocking API, so
+/// the tasks it spawns (e.g. the exports of the tracers) keep running after it returns.
+/// Inside a multi thread runtime the worker is turned into a blocking thread for the
+/// duration of the call.
+///
+/// # Panics
+///
+/// When called from a current thread runtime, e.g. a `#[tokio::test]`, which can't be
+/// blocked: use the async API there.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| handle.block_on(future))
+        }
+        Ok(_) => panic!(
+            "The blocking API can't be called from a current thread runtime, use the async API instead"
+        ),
+        Err(_) => runtime().block_on(future),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_on_outside_runtime() {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        block_on(async {
+            tokio::spawn(async move { sender.send(1 + 1) });
+        });
+        // The spawned task outlives the call.
+        assert_eq!(block_on(receiver).unwrap(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_block_on_inside_multi_thread_runtime() {
+        assert_eq!(block_on(async { "ok" }), "ok");
+    }
+}
@@ -0,0 +1,14 @@
+use std::error::Error;
+
+use crate::tools::Tool;
+
+use super::block_on;
+
+/// Blocking version of `Tool::call`, see the `blocking` module.
+pub trait ToolBlocking: Tool {
+    fn call_blocking(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        block_on(self.call(input))
+    }
+}
+
+impl<T: Tool + ?Sized> ToolBlocking for T {}
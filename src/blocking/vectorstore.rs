@@ -0,0 +1,42 @@
+use std::error::Error;
+
+use crate::{
+    schemas::{Document, Retriever},
+    vectorstore::{VecStoreOptions, VectorStore},
+};
+
+use super::block_on;
+
+/// Blocking versions of the methods of `VectorStore`, see the `blocking` module.
+pub trait VectorStoreBlocking: VectorStore {
+    fn add_documents_blocking(
+        &self,
+        docs: &[Document],
+        opt: &VecStoreOptions,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        block_on(self.add_documents(docs, opt))
+    }
+
+    fn similarity_search_blocking(
+        &self,
+        query: &str,
+        limit: usize,
+        opt: &VecStoreOptions,
+    ) -> Result<Vec<Document>, Box<dyn Error>> {
+        block_on(self.similarity_search(query, limit, opt))
+    }
+}
+
+impl<V: VectorStore + ?Sized> VectorStoreBlocking for V {}
+
+/// Blocking version of `Retriever::get_relevant_documents`, see the `blocking` module.
+pub trait RetrieverBlocking: Retriever {
+    fn get_relevant_documents_blocking(
+        &self,
+        query: &str,
+    ) -> Result<Vec<Document>, Box<dyn Error>> {
+        block_on(self.get_relevant_documents(query))
+    }
+}
+
+impl<R: Retriever + ?Sized> RetrieverBlocking for R {}
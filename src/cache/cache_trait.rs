@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+use crate::language_models::GenerateResult;
+
+use super::CacheError;
+
+/// Cache of the results of the LLMs, used by `CachedLLM`.
+///
+/// The results are looked up by the prompt, the messages sent as json, and by the
+/// `llm_string`, which identifies the model and the options it is called with, so the
+/// same prompt sent to another model isn't served from the cache.
+#[async_trait]
+pub trait LLMCache: Send + Sync {
+    async fn lookup(
+        &self,
+        prompt: &str,
+        llm_string: &str,
+    ) -> Result<Option<GenerateResult>, CacheError>;
+
+    async fn update(
+        &self,
+        prompt: &str,
+        llm_string: &str,
+        result: &GenerateResult,
+    ) -> Result<(), CacheError>;
+
+    async fn clear(&self) -> Result<(), CacheError>;
+}
+
+/// The key of an exact match cache, the hash of the prompt and the llm string.
+pub fn cache_key(prompt: &str, llm_string: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(llm_string.as_bytes());
+    hasher.update([0]);
+    hasher.update(prompt.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,223 @@
+use std::{collections::BTreeMap, pin::Pin, sync::Arc};
+
+use async_trait::async_trait;
+use futures::Stream;
+use serde_json::json;
+
+use crate::{
+    language_models::{llm::LLM, options::CallOptions, GenerateResult, LLMError},
+    schemas::{Message, StreamData},
+};
+
+use super::LLMCache;
+
+/// Wraps an LLM to serve the results of the prompts it has already answered from a cache.
+///
+/// The results are cached by the messages, the model and the options of the calls. The
+/// wrapper can't know the model of the LLM, it is set with `with_model`, the type of the
+/// LLM being used instead; the LLMs of the same type sharing a cache need a model. An
+/// error of the cache doesn't fail the call, the LLM is called instead.
+///
+/// The cached results are given to the `streaming_func` of the options, and the streams of
+/// cached results, as a single chunk with the whole generation. The streamed results
+/// aren't cached.
+///
+/// # Example
+/// ```rust,ignore
+/// let llm = CachedLLM::new(OpenAI::default(), InMemoryCache::new(1000)).with_model("gpt-3.5-turbo");
+/// let first = llm.invoke("Capital of France?").await?;
+/// // Served from the cache
+/// let second = llm.invoke("Capital of France?").await?;
+/// ```
+pub struct CachedLLM {
+    llm: Box<dyn LLM>,
+    cache: Arc<dyn LLMCache>,
+    model: String,
+    llm_type: &'static str,
+    options: CallOptions,
+}
+
+impl CachedLLM {
+    pub fn new<L: Into<Box<dyn LLM>>, C: LLMCache + 'static>(llm: L, cache: C) -> Self {
+        Self::with_shared_cache(llm, Arc::new(cache))
+    }
+
+    /// Uses a cache shared with other LLMs.
+    pub fn with_shared_cache<L: Into<Box<dyn LLM>>>(llm: L, cache: Arc<dyn LLMCache>) -> Self {
+        Self {
+            llm: llm.into(),
+            cache,
+            model: String::new(),
+            llm_type: std::any::type_name::<L>(),
+            options: CallOptions::default(),
+        }
+    }
+
+    /// The model of the LLM, part of the cache keys so that the LLMs sharing the cache
+    /// don't get the results of each other. Default: the type of the LLM
+    pub fn with_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Identifies the model and the options changing its results.
+    fn llm_string(&self) -> String {
+        let options = &self.options;
+        let model = if self.model.is_empty() {
+            self.llm_type
+        } else {
+            &self.model
+        };
+        json!({
+            "model": model,
+            "max_tokens": options.max_tokens,
+            "temperature": options.temperature,
+            "stop_words": options.stop_words,
+            "top_k": options.top_k,
+            "top_p": options.top_p,
+            "seed": options.seed,
+            "min_length": options.min_length,
+            "max_length": options.max_length,
+            "n": options.n,
+            "candidate_count": options.candidate_count,
+            "repetition_penalty": options.repetition_penalty,
+            "frequency_penalty": options.frequency_penalty,
+            "presence_penalty": options.presence_penalty,
+            "logit_bias": options
+                .logit_bias
+                .as_ref()
+                .map(|logit_bias| logit_bias.iter().collect::<BTreeMap<_, _>>()),
+            "functions": options.functions.as_ref().map(|functions| {
+                functions
+                    .iter()
+                    .map(|f| json!([f.name, f.description, f.parameters]))
+                    .collect::<Vec<_>>()
+            }),
+            "function_call_behavior": options
+                .function_call_behavior
+                .map(|behavior| format!("{:?}", behavior)),
+        })
+        .to_string()
+    }
+
+    async fn lookup(&self, prompt: &str, llm_string: &str) -> Option<GenerateResult> {
+        match self.cache.lookup(prompt, llm_string).await {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("Error looking up the LLM cache: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Clone for CachedLLM {
+    fn clone(&self) -> Self {
+        Self {
+            llm: self.llm.clone_box(),
+            cache: self.cache.clone(),
+            model: self.model.clone(),
+            llm_type: self.llm_type,
+            options: self.options.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl LLM for CachedLLM {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let prompt = serde_json::to_string(messages)?;
+        let llm_string = self.llm_string();
+        if let Some(result) = self.lookup(&prompt, &llm_string).await {
+            log::debug!("LLM cache hit");
+            if let Some(func) = &self.options.streaming_func {
+                let mut func = func.lock().await;
+                let _ = func(result.generation.clone()).await;
+            }
+            return Ok(result);
+        }
+
+        let result = self.llm.generate(messages).await?;
+        if let Err(e) = self.cache.update(&prompt, &llm_string, &result).await {
+            log::warn!("Error updating the LLM cache: {}", e);
+        }
+        Ok(result)
+    }
+
+    async fn stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
+        let prompt = serde_json::to_string(messages)?;
+        match self.lookup(&prompt, &self.llm_string()).await {
+            Some(result) => Ok(Box::pin(futures::stream::once(async move {
+                Ok(StreamData::new(json!(result), result.generation.clone()))
+            }))),
+            None => self.llm.stream(messages).await,
+        }
+    }
+
+    fn add_options(&mut self, options: CallOptions) {
+        self.options.merge_options(options.clone());
+        self.llm.add_options(options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use crate::{cache::InMemoryCache, language_models::LLMWithFallbacks, llm::FakeLLM};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cached_llm() {
+        let llm = FakeLLM::from_responses(vec!["1", "2", "3"]);
+        let mut cached = CachedLLM::new(llm.clone(), InMemoryCache::default()).with_model("gpt-4");
+
+        assert_eq!(cached.invoke("Hi").await.unwrap(), "1");
+        assert_eq!(cached.invoke("Hi").await.unwrap(), "1");
+        assert_eq!(cached.invoke("Hello").await.unwrap(), "2");
+
+        let chunks: Vec<_> = cached
+            .stream(&[Message::new_human_message("Hi")])
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].as_ref().unwrap().content, "1");
+
+        // Other options are other keys.
+        cached.add_options(CallOptions::new().with_temperature(0.9));
+        assert_eq!(cached.invoke("Hi").await.unwrap(), "3");
+        assert_eq!(cached.clone().invoke("Hi").await.unwrap(), "3");
+        assert_eq!(llm.call_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cached_llm_streaming_func_and_llm_type() {
+        let cache: Arc<dyn LLMCache> = Arc::new(InMemoryCache::default());
+        let llm = FakeLLM::new("1");
+        let mut cached = CachedLLM::with_shared_cache(llm.clone(), cache.clone());
+        let tokens = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let streamed = tokens.clone();
+        cached.add_options(
+            CallOptions::new().with_streaming_func(move |token: String| {
+                streamed.lock().unwrap().push(token);
+                async { Ok(()) }
+            }),
+        );
+
+        assert_eq!(cached.invoke("Hi").await.unwrap(), "1");
+        assert_eq!(cached.invoke("Hi").await.unwrap(), "1");
+        // The LLM streams the first answer, the cache replays the second one.
+        assert_eq!(llm.call_count(), 1);
+        assert_eq!(*tokens.lock().unwrap(), vec!["1", "1"]);
+
+        // Another type of LLM doesn't get the results of the first one.
+        let other = CachedLLM::with_shared_cache(LLMWithFallbacks::new(FakeLLM::new("2")), cache);
+        assert_eq!(other.invoke("Hi").await.unwrap(), "2");
+    }
+}
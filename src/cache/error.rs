@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CacheError {
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[cfg(feature = "redis")]
+    #[error(transparent)]
+    RedisError(#[from] redis::RedisError),
+
+    #[error("Vector store error: {0}")]
+    VectorStoreError(String),
+
+    #[error("Error: {0}")]
+    OtherError(String),
+}
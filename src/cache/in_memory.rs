@@ -0,0 +1,154 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+
+use crate::language_models::GenerateResult;
+
+use super::{cache_key, CacheError, LLMCache};
+
+const DEFAULT_CAPACITY: usize = 1000;
+
+#[derive(Default)]
+struct Entries {
+    /// The results by key, with the tick of their last use.
+    results: HashMap<String, (GenerateResult, u64)>,
+    /// The keys by the tick of their last use, the least recently used first.
+    uses: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Entries {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        if let Some((_, used)) = self.results.get_mut(key) {
+            self.uses.remove(used);
+            *used = self.tick;
+            self.uses.insert(self.tick, key.to_string());
+        }
+    }
+}
+
+/// Cache keeping the results in memory, evicting the least recently used ones past its
+/// capacity. Clones share the same results.
+#[derive(Clone)]
+pub struct InMemoryCache {
+    capacity: usize,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl InMemoryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Arc::new(Mutex::new(Entries::default())),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries().results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for InMemoryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl LLMCache for InMemoryCache {
+    async fn lookup(
+        &self,
+        prompt: &str,
+        llm_string: &str,
+    ) -> Result<Option<GenerateResult>, CacheError> {
+        let key = cache_key(prompt, llm_string);
+        let mut entries = self.entries();
+        entries.touch(&key);
+        Ok(entries.results.get(&key).map(|(result, _)| result.clone()))
+    }
+
+    async fn update(
+        &self,
+        prompt: &str,
+        llm_string: &str,
+        result: &GenerateResult,
+    ) -> Result<(), CacheError> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        let key = cache_key(prompt, llm_string);
+        let mut entries = self.entries();
+        entries.tick += 1;
+        let tick = entries.tick;
+        if let Some((_, used)) = entries.results.insert(key.clone(), (result.clone(), tick)) {
+            entries.uses.remove(&used);
+        }
+        entries.uses.insert(tick, key);
+
+        while entries.results.len() > self.capacity {
+            let Some((_, key)) = entries.uses.pop_first() else {
+                break;
+            };
+            entries.results.remove(&key);
+        }
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        let mut entries = self.entries();
+        entries.results.clear();
+        entries.uses.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(generation: &str) -> GenerateResult {
+        GenerateResult {
+            generation: generation.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cache_lru() {
+        let cache = InMemoryCache::new(2);
+        cache.update("a", "gpt-4", &result("A")).await.unwrap();
+        cache.update("b", "gpt-4", &result("B")).await.unwrap();
+        assert!(cache.lookup("a", "gpt-3.5").await.unwrap().is_none());
+
+        // "a" is used, so "b" is the one evicted.
+        assert_eq!(
+            cache
+                .lookup("a", "gpt-4")
+                .await
+                .unwrap()
+                .unwrap()
+                .generation,
+            "A"
+        );
+        cache.update("c", "gpt-4", &result("C")).await.unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.lookup("b", "gpt-4").await.unwrap().is_none());
+        assert!(cache.lookup("a", "gpt-4").await.unwrap().is_some());
+        assert!(cache.lookup("c", "gpt-4").await.unwrap().is_some());
+
+        cache.clear().await.unwrap();
+        assert!(cache.is_empty());
+    }
+}
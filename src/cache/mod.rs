@@ -0,0 +1,19 @@
+mod cache_trait;
+pub use cache_trait::*;
+
+mod error;
+pub use error::*;
+
+mod in_memory;
+pub use in_memory::*;
+
+mod semantic;
+pub use semantic::*;
+
+mod cached_llm;
+pub use cached_llm::*;
+
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "redis")]
+pub use redis::*;
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::{aio::MultiplexedConnection, AsyncCommands};
+
+use crate::language_models::GenerateResult;
+
+use super::{cache_key, CacheError, LLMCache};
+
+/// Cache keeping the results, as json, in Redis under `{prefix}{hash}`, so they are shared
+/// by every process using it. With a ttl the results expire after it.
+pub struct RedisCache {
+    client: redis::Client,
+    prefix: String,
+    ttl: Option<Duration>,
+}
+
+impl RedisCache {
+    pub fn new(client: redis::Client) -> Self {
+        Self {
+            client,
+            prefix: "llm_cache:".to_string(),
+            ttl: None,
+        }
+    }
+
+    pub fn from_url(url: &str) -> Result<Self, CacheError> {
+        Ok(Self::new(redis::Client::open(url)?))
+    }
+
+    pub fn with_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn key(&self, prompt: &str, llm_string: &str) -> String {
+        format!("{}{}", self.prefix, cache_key(prompt, llm_string))
+    }
+
+    async fn connection(&self) -> Result<MultiplexedConnection, CacheError> {
+        Ok(self.client.get_multiplexed_async_connection().await?)
+    }
+}
+
+#[async_trait]
+impl LLMCache for RedisCache {
+    async fn lookup(
+        &self,
+        prompt: &str,
+        llm_string: &str,
+    ) -> Result<Option<GenerateResult>, CacheError> {
+        let value: Option<String> = self
+            .connection()
+            .await?
+            .get(self.key(prompt, llm_string))
+            .await?;
+        Ok(value
+            .map(|value| serde_json::from_str(&value))
+            .transpose()?)
+    }
+
+    async fn update(
+        &self,
+        prompt: &str,
+        llm_string: &str,
+        result: &GenerateResult,
+    ) -> Result<(), CacheError> {
+        let key = self.key(prompt, llm_string);
+        let value = serde_json::to_string(result)?;
+        let mut connection = self.connection().await?;
+        let _: () = match self.ttl {
+            Some(ttl) => connection.set_ex(key, value, ttl.as_secs().max(1)).await?,
+            None => connection.set(key, value).await?,
+        };
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        let mut connection = self.connection().await?;
+        let keys: Vec<String> = {
+            let mut keys = Vec::new();
+            let mut iter = connection
+                .scan_match::<_, String>(format!("{}*", self.prefix))
+                .await?;
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+        if !keys.is_empty() {
+            let _: () = connection.del(keys).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_redis_cache() {
+        let cache = RedisCache::from_url("redis://127.0.0.1/")
+            .unwrap()
+            .with_prefix("test-llm-cache:")
+            .with_ttl(Duration::from_secs(60));
+        let result = GenerateResult {
+            generation: "Paris".to_string(),
+            ..Default::default()
+        };
+        cache
+            .update("Capital of France?", "gpt-4", &result)
+            .await
+            .unwrap();
+
+        let cached = cache.lookup("Capital of France?", "gpt-4").await.unwrap();
+        assert_eq!(cached.unwrap().generation, "Paris");
+        assert!(cache
+            .lookup("Capital of France?", "gpt-3.5")
+            .await
+            .unwrap()
+            .is_none());
+
+        cache.clear().await.unwrap();
+        assert!(cache
+            .lookup("Capital of France?", "gpt-4")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
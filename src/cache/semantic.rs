@@ -0,0 +1,204 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::{
+    language_models::GenerateResult,
+    schemas::{Document, Message},
+    vectorstore::{VecStoreOptions, VectorStore},
+};
+
+use super::{CacheError, LLMCache};
+
+const DEFAULT_SCORE_THRESHOLD: f32 = 0.95;
+const LLM_STRING_KEY: &str = "llm_string";
+const RESULT_KEY: &str = "result";
+// Candidates fetched, in case the vector store ignores the filter on the llm string.
+const CANDIDATES: usize = 4;
+
+/// Cache serving the results of the prompts similar to the new ones, not only of the same
+/// ones, e.g. for the questions users word differently.
+///
+/// The text of the prompts is embedded in the vector store, with the result and the llm
+/// string in the metadata. A result is served when the score of its prompt is at least
+/// the threshold, a similarity in `[0, 1]` for most stores.
+///
+/// # Example
+/// ```rust,ignore
+/// let store = InMemoryVectorStore::new(OpenAiEmbedder::default());
+/// let cache = SemanticCache::new(store).with_score_threshold(0.9);
+/// let llm = CachedLLM::new(OpenAI::default(), cache).with_model("gpt-3.5-turbo");
+/// ```
+pub struct SemanticCache {
+    vector_store: Box<dyn VectorStore>,
+    score_threshold: f32,
+    name_space: Option<String>,
+    // The ids of the results added, deleted by `clear`.
+    ids: Arc<Mutex<Vec<String>>>,
+}
+
+impl SemanticCache {
+    pub fn new<V: Into<Box<dyn VectorStore>>>(vector_store: V) -> Self {
+        Self {
+            vector_store: vector_store.into(),
+            score_threshold: DEFAULT_SCORE_THRESHOLD,
+            name_space: None,
+            ids: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// The minimum score of the similar prompts. Default: 0.95
+    pub fn with_score_threshold(mut self, score_threshold: f32) -> Self {
+        self.score_threshold = score_threshold;
+        self
+    }
+
+    /// Name space of the results in the vector store.
+    pub fn with_name_space<S: Into<String>>(mut self, name_space: S) -> Self {
+        self.name_space = Some(name_space.into());
+        self
+    }
+
+    fn options(&self) -> VecStoreOptions {
+        let mut options = VecStoreOptions::new();
+        options.name_space = self.name_space.clone();
+        options
+    }
+
+    fn ids(&self) -> std::sync::MutexGuard<'_, Vec<String>> {
+        self.ids.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The text of the messages of the prompt, which is embedded instead of their json.
+fn prompt_text(prompt: &str) -> String {
+    match serde_json::from_str::<Vec<Message>>(prompt) {
+        Ok(messages) => messages
+            .iter()
+            .map(|message| message.content.text())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Err(_) => prompt.to_string(),
+    }
+}
+
+#[async_trait]
+impl LLMCache for SemanticCache {
+    async fn lookup(
+        &self,
+        prompt: &str,
+        llm_string: &str,
+    ) -> Result<Option<GenerateResult>, CacheError> {
+        let options = self
+            .options()
+            .with_score_threshold(self.score_threshold)
+            .with_filters(json!({ LLM_STRING_KEY: llm_string }));
+        let documents = self
+            .vector_store
+            .similarity_search(&prompt_text(prompt), CANDIDATES, &options)
+            .await
+            .map_err(|e| CacheError::VectorStoreError(e.to_string()))?;
+
+        let result = documents
+            .into_iter()
+            .filter(|document| document.score >= self.score_threshold as f64)
+            .find(|document| document.metadata.get(LLM_STRING_KEY) == Some(&json!(llm_string)))
+            .and_then(|mut document| document.metadata.remove(RESULT_KEY));
+        match result {
+            Some(Value::String(result)) => Ok(Some(serde_json::from_str(&result)?)),
+            Some(result) => Ok(Some(serde_json::from_value(result)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn update(
+        &self,
+        prompt: &str,
+        llm_string: &str,
+        result: &GenerateResult,
+    ) -> Result<(), CacheError> {
+        let document = Document::new(prompt_text(prompt)).with_metadata(
+            [
+                (LLM_STRING_KEY.to_string(), json!(llm_string)),
+                // As a string, as some stores only keep flat metadata.
+                (
+                    RESULT_KEY.to_string(),
+                    json!(serde_json::to_string(result)?),
+                ),
+            ]
+            .into(),
+        );
+        let ids = self
+            .vector_store
+            .add_documents(&[document], &self.options())
+            .await
+            .map_err(|e| CacheError::VectorStoreError(e.to_string()))?;
+        self.ids().extend(ids);
+        Ok(())
+    }
+
+    /// Deletes the results added by this cache from the vector store.
+    async fn clear(&self) -> Result<(), CacheError> {
+        let ids = std::mem::take(&mut *self.ids());
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.vector_store
+            .delete(&ids, &self.options())
+            .await
+            .map_err(|e| CacheError::VectorStoreError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{embedding::FakeEmbedder, vectorstore::InMemoryVectorStore};
+
+    use super::*;
+
+    fn prompt(question: &str) -> String {
+        serde_json::to_string(&[Message::new_human_message(question)]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_semantic_cache() {
+        let cache = SemanticCache::new(InMemoryVectorStore::new(
+            FakeEmbedder::default().with_topics(vec![
+                vec!["refund", "money back"],
+                vec!["shipping", "delivery"],
+            ]),
+        ));
+        let result = GenerateResult {
+            generation: "Refunds take 5 days".to_string(),
+            ..Default::default()
+        };
+        cache
+            .update(&prompt("How do I get a refund?"), "gpt-4", &result)
+            .await
+            .unwrap();
+
+        let cached = cache
+            .lookup(&prompt("Can I have my money back?"), "gpt-4")
+            .await
+            .unwrap();
+        assert_eq!(cached.unwrap().generation, "Refunds take 5 days");
+        assert!(cache
+            .lookup(&prompt("How long is the shipping?"), "gpt-4")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(cache
+            .lookup(&prompt("Can I have my money back?"), "gpt-3.5")
+            .await
+            .unwrap()
+            .is_none());
+
+        cache.clear().await.unwrap();
+        assert!(cache
+            .lookup(&prompt("How do I get a refund?"), "gpt-4")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
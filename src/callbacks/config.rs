@@ -0,0 +1,151 @@
+use std::{collections::HashMap, future::Future};
+
+use serde_json::Value;
+
+use super::{current_config, enter_scope, CallbackManager};
+
+const USER_METADATA_KEY: &str = "user";
+
+/// Correlates the runs of a call with the request that triggered it, e.g. in a multi
+/// tenant service.
+///
+/// The first run started with the config takes `run_id`, and is the child of
+/// `parent_run_id`. The tags and the metadata are inherited by every nested run and
+/// surfaced to the callbacks in `RunInfo`.
+///
+/// # Example
+/// ```rust,ignore
+/// let config = RunConfig::new()
+///     .with_run_id(request_id)
+///     .with_tag("production")
+///     .with_metadata("tenant", "acme")
+///     .with_user(user_id); // Also sent to OpenAI as the `user` of the requests.
+/// let result = chain.call_with_config(input_variables, config).await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RunConfig {
+    pub run_id: Option<String>,
+    pub parent_run_id: Option<String>,
+    pub tags: Vec<String>,
+    pub metadata: HashMap<String, Value>,
+    /// Values selected for the configurable fields and alternatives of the runnables,
+    /// by id.
+    pub configurable: HashMap<String, Value>,
+    pub callbacks: CallbackManager,
+}
+
+impl RunConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_run_id<S: Into<String>>(mut self, run_id: S) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    pub fn with_parent_run_id<S: Into<String>>(mut self, parent_run_id: S) -> Self {
+        self.parent_run_id = Some(parent_run_id.into());
+        self
+    }
+
+    pub fn with_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags.extend(tags);
+        self
+    }
+
+    pub fn with_metadata<K: Into<String>, V: Into<Value>>(mut self, key: K, value: V) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Identifies the end user, sent to the providers supporting it (e.g. the `user`
+    /// field of OpenAI).
+    pub fn with_user<S: Into<String>>(self, user: S) -> Self {
+        self.with_metadata(USER_METADATA_KEY, user.into())
+    }
+
+    /// Selects `value` for the configurable field or alternatives with id `key`, see
+    /// `Runnable::configurable_fields`.
+    pub fn with_configurable<K: Into<String>, V: Into<Value>>(mut self, key: K, value: V) -> Self {
+        self.configurable.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_callbacks(mut self, callbacks: CallbackManager) -> Self {
+        self.callbacks = callbacks;
+        self
+    }
+
+    pub fn user(&self) -> Option<&str> {
+        self.metadata
+            .get(USER_METADATA_KEY)
+            .and_then(|user| user.as_str())
+    }
+
+    /// Executes `future` with this config.
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        enter_scope(&self.callbacks, self, future).await
+    }
+
+    /// The tags, metadata and configurable values of the run currently executing.
+    /// Providers use it to forward the metadata they support.
+    pub fn current() -> Self {
+        current_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        callbacks::{manager::tests::RecordingHandler, RunType},
+        language_models::GenerateResult,
+        prompt::PromptArgs,
+    };
+
+    #[tokio::test]
+    async fn test_run_config_propagates_to_runs() {
+        let handler = Arc::new(RecordingHandler::default());
+        let config = RunConfig::new()
+            .with_run_id("request-1")
+            .with_parent_run_id("upstream")
+            .with_tag("tests")
+            .with_user("user-1")
+            .with_callbacks(CallbackManager::new().with_shared_handler(handler.clone()));
+
+        config
+            .scope(async {
+                let chain = CallbackManager::new().start_run("chain", RunType::Chain);
+                chain
+                    .trace_chain(&PromptArgs::new(), async {
+                        assert_eq!(RunConfig::current().user(), Some("user-1"));
+                        CallbackManager::new()
+                            .start_run("llm", RunType::Llm)
+                            .trace_llm(&[], async { Ok(GenerateResult::default()) })
+                            .await?;
+                        Ok(GenerateResult::default())
+                    })
+                    .await
+            })
+            .await
+            .unwrap();
+
+        let events = handler.events.lock().unwrap();
+        let (_, chain) = &events[0];
+        assert_eq!(chain.run_id, "request-1");
+        assert_eq!(chain.parent_run_id.as_deref(), Some("upstream"));
+        let (_, llm) = &events[1];
+        assert_ne!(llm.run_id, "request-1");
+        assert_eq!(llm.parent_run_id.as_deref(), Some("request-1"));
+        assert_eq!(llm.tags, vec!["tests".to_string()]);
+        assert_eq!(llm.metadata["user"], "user-1");
+    }
+}
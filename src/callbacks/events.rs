@@ -0,0 +1,258 @@
+use std::{future::Future, pin::Pin};
+
+use futures::Stream;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::{
+    chain::ChainError,
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    schemas::{Document, Message},
+};
+
+use super::{CallbackHandler, CallbackManager, RunInfo};
+
+/// An event of a run, as streamed by `Chain::stream_events`.
+///
+/// Events serialize with an `event` tag, e.g.
+/// `{"event": "llm_new_token", "run": {...}, "token": "Hi"}`, so they can be forwarded as
+/// they are through SSE or WebSockets.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RunEvent {
+    ChainStart {
+        run: RunInfo,
+        inputs: PromptArgs,
+    },
+    ChainEnd {
+        run: RunInfo,
+        output: GenerateResult,
+    },
+    LlmStart {
+        run: RunInfo,
+        messages: Vec<Message>,
+    },
+    LlmNewToken {
+        run: RunInfo,
+        token: String,
+    },
+    LlmEnd {
+        run: RunInfo,
+        output: GenerateResult,
+    },
+    ToolStart {
+        run: RunInfo,
+        input: String,
+    },
+    ToolEnd {
+        run: RunInfo,
+        output: String,
+    },
+    RetrieverStart {
+        run: RunInfo,
+        query: String,
+    },
+    RetrieverEnd {
+        run: RunInfo,
+        documents: Vec<Document>,
+    },
+    Error {
+        run: RunInfo,
+        error: String,
+    },
+    /// The last event, with the result of the call.
+    Output {
+        output: GenerateResult,
+    },
+}
+
+impl RunEvent {
+    /// The run of the event, `None` for the final output.
+    pub fn run(&self) -> Option<&RunInfo> {
+        match self {
+            RunEvent::ChainStart { run, .. }
+            | RunEvent::ChainEnd { run, .. }
+            | RunEvent::LlmStart { run, .. }
+            | RunEvent::LlmNewToken { run, .. }
+            | RunEvent::LlmEnd { run, .. }
+            | RunEvent::ToolStart { run, .. }
+            | RunEvent::ToolEnd { run, .. }
+            | RunEvent::RetrieverStart { run, .. }
+            | RunEvent::RetrieverEnd { run, .. }
+            | RunEvent::Error { run, .. } => Some(run),
+            RunEvent::Output { .. } => None,
+        }
+    }
+}
+
+/// Handler passing every event, as a `RunEvent`, to a function.
+///
+/// # Example
+/// ```rust,ignore
+/// let callbacks = CallbackManager::new().with_handler(EventHandler::new(|event| {
+///     println!("{}", serde_json::to_string(&event).unwrap());
+/// }));
+/// ```
+pub struct EventHandler<F> {
+    on_event: F,
+}
+
+impl<F: Fn(RunEvent) + Send + Sync> EventHandler<F> {
+    pub fn new(on_event: F) -> Self {
+        Self { on_event }
+    }
+
+    fn send(&self, event: RunEvent) {
+        (self.on_event)(event)
+    }
+}
+
+impl<F: Fn(RunEvent) + Send + Sync> CallbackHandler for EventHandler<F> {
+    fn on_chain_start(&self, run: &RunInfo, inputs: &PromptArgs) {
+        self.send(RunEvent::ChainStart {
+            run: run.clone(),
+            inputs: inputs.clone(),
+        });
+    }
+
+    fn on_chain_end(&self, run: &RunInfo, output: &GenerateResult) {
+        self.send(RunEvent::ChainEnd {
+            run: run.clone(),
+            output: output.clone(),
+        });
+    }
+
+    fn on_llm_start(&self, run: &RunInfo, messages: &[Message]) {
+        self.send(RunEvent::LlmStart {
+            run: run.clone(),
+            messages: messages.to_vec(),
+        });
+    }
+
+    fn on_llm_new_token(&self, run: &RunInfo, token: &str) {
+        self.send(RunEvent::LlmNewToken {
+            run: run.clone(),
+            token: token.to_string(),
+        });
+    }
+
+    fn on_llm_end(&self, run: &RunInfo, result: &GenerateResult) {
+        self.send(RunEvent::LlmEnd {
+            run: run.clone(),
+            output: result.clone(),
+        });
+    }
+
+    fn on_tool_start(&self, run: &RunInfo, input: &str) {
+        self.send(RunEvent::ToolStart {
+            run: run.clone(),
+            input: input.to_string(),
+        });
+    }
+
+    fn on_tool_end(&self, run: &RunInfo, output: &str) {
+        self.send(RunEvent::ToolEnd {
+            run: run.clone(),
+            output: output.to_string(),
+        });
+    }
+
+    fn on_retriever_start(&self, run: &RunInfo, query: &str) {
+        self.send(RunEvent::RetrieverStart {
+            run: run.clone(),
+            query: query.to_string(),
+        });
+    }
+
+    fn on_retriever_end(&self, run: &RunInfo, documents: &[Document]) {
+        self.send(RunEvent::RetrieverEnd {
+            run: run.clone(),
+            documents: documents.to_vec(),
+        });
+    }
+
+    fn on_error(&self, run: &RunInfo, error: &str) {
+        self.send(RunEvent::Error {
+            run: run.clone(),
+            error: error.to_string(),
+        });
+    }
+}
+
+/// Executes `call` streaming the events of every run nested in it, followed by
+/// `RunEvent::Output` with its result. The call makes progress as the stream is polled.
+pub fn stream_events<'a, F>(
+    call: F,
+) -> Pin<Box<dyn Stream<Item = Result<RunEvent, ChainError>> + Send + 'a>>
+where
+    F: Future<Output = Result<GenerateResult, ChainError>> + Send + 'a,
+{
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    // The receiver is only dropped together with the call.
+    let callbacks = CallbackManager::new().with_handler(EventHandler::new(move |event| {
+        let _ = sender.send(event);
+    }));
+
+    Box::pin(async_stream::stream! {
+        let call = callbacks.scope(call);
+        tokio::pin!(call);
+        let result = loop {
+            tokio::select! {
+                biased;
+                Some(event) = receiver.recv() => yield Ok(event),
+                result = &mut call => break result,
+            }
+        };
+        while let Ok(event) = receiver.try_recv() {
+            yield Ok(event);
+        }
+        match result {
+            Ok(output) => yield Ok(RunEvent::Output { output }),
+            Err(e) => yield Err(e),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::callbacks::RunType;
+
+    #[tokio::test]
+    async fn test_stream_events() {
+        let events: Vec<_> = stream_events(async {
+            let run = CallbackManager::new().start_run("chain", RunType::Chain);
+            run.trace_chain(&PromptArgs::new(), async {
+                let llm = CallbackManager::new().start_run("llm", RunType::Llm);
+                llm.on_llm_new_token("Hi");
+                Ok(GenerateResult {
+                    generation: "Hi".to_string(),
+                    ..Default::default()
+                })
+            })
+            .await
+        })
+        .collect()
+        .await;
+
+        let events: Vec<_> = events.into_iter().map(|e| e.unwrap()).collect();
+        assert_eq!(events.len(), 4);
+        assert!(matches!(&events[0], RunEvent::ChainStart { run, .. } if run.name == "chain"));
+        match &events[1] {
+            RunEvent::LlmNewToken { run, token } => {
+                assert_eq!(token, "Hi");
+                assert_eq!(run.parent_run_id, events[0].run().map(|r| r.run_id.clone()));
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+        assert!(matches!(&events[2], RunEvent::ChainEnd { .. }));
+        assert!(matches!(&events[3], RunEvent::Output { output } if output.generation == "Hi"));
+
+        let json = serde_json::to_value(&events[1]).unwrap();
+        assert_eq!(json["event"], "llm_new_token");
+        assert_eq!(json["token"], "Hi");
+    }
+}
@@ -0,0 +1,92 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    agent::Plan,
+    guardrails::InjectionDetection,
+    language_models::{GenerateResult, TokenUsage},
+    prompt::PromptArgs,
+    schemas::{Document, Message},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunType {
+    Chain,
+    Llm,
+    Tool,
+    Retriever,
+    Embedding,
+}
+
+/// Identifies a run (a chain, llm, tool or retriever invocation) in the callbacks.
+///
+/// Runs started while another run is executing are its children, `parent_run_id`
+/// allows rebuilding the whole call tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub run_id: String,
+    pub parent_run_id: Option<String>,
+    pub name: String,
+    pub run_type: RunType,
+    pub start_time: SystemTime,
+    /// Set with a `RunConfig`, inherited by the children.
+    pub tags: Vec<String>,
+    pub metadata: HashMap<String, Value>,
+}
+
+/// Receives the events of the runs. Every method has an empty default implementation,
+/// so a handler only implements the events it cares about.
+///
+/// The methods are called inline, a handler doing IO (e.g. exporting traces) should
+/// hand off the work to a background task instead of blocking.
+///
+/// # Example
+/// ```rust,ignore
+/// struct PrintTokens;
+///
+/// impl CallbackHandler for PrintTokens {
+///     fn on_llm_new_token(&self, _run: &RunInfo, token: &str) {
+///         print!("{}", token);
+///     }
+/// }
+///
+/// let callbacks = CallbackManager::new().with_handler(PrintTokens);
+/// let result = chain.call_with_callbacks(input_variables, callbacks).await?;
+/// ```
+pub trait CallbackHandler: Send + Sync {
+    fn on_chain_start(&self, _run: &RunInfo, _inputs: &PromptArgs) {}
+
+    fn on_chain_end(&self, _run: &RunInfo, _output: &GenerateResult) {}
+
+    fn on_llm_start(&self, _run: &RunInfo, _messages: &[Message]) {}
+
+    fn on_llm_new_token(&self, _run: &RunInfo, _token: &str) {}
+
+    fn on_llm_end(&self, _run: &RunInfo, _result: &GenerateResult) {}
+
+    /// The name of the tool is the name of the run.
+    fn on_tool_start(&self, _run: &RunInfo, _input: &str) {}
+
+    fn on_tool_end(&self, _run: &RunInfo, _output: &str) {}
+
+    fn on_retriever_start(&self, _run: &RunInfo, _query: &str) {}
+
+    fn on_retriever_end(&self, _run: &RunInfo, _documents: &[Document]) {}
+
+    /// A call to the embedding model named as the run completed. `tokens` are `None` when
+    /// the provider doesn't report them.
+    fn on_embedding_end(&self, _run: &RunInfo, _tokens: Option<&TokenUsage>) {}
+
+    /// A likely prompt injection was found by an `InjectionSanitizer`.
+    fn on_injection_detected(&self, _run: &RunInfo, _detection: &InjectionDetection) {}
+
+    /// The plan of a `PlanAndExecuteAgent` was made or changed: a step completed or the
+    /// remaining steps were replanned.
+    fn on_plan(&self, _run: &RunInfo, _plan: &Plan) {}
+
+    /// Called instead of the `*_end` method when the run fails.
+    fn on_error(&self, _run: &RunInfo, _error: &str) {}
+}
@@ -0,0 +1,410 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    runtime,
+    schemas::{Document, Message},
+};
+
+use super::{CallbackHandler, RunInfo, RunType};
+
+const DEFAULT_HOST: &str = "https://cloud.langfuse.com";
+
+enum Export {
+    Event(Value),
+    Flush(oneshot::Sender<()>),
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Exports the runs to [Langfuse](https://langfuse.com): the root run is the trace, the
+/// LLM runs are generations (with their model, token usage and prompt) and the other
+/// runs are spans.
+///
+/// Events are sent in batches by a background task, call `flush` before the program
+/// exits to wait for the pending ones.
+///
+/// # Example
+/// ```rust,ignore
+/// let langfuse = LangfuseHandler::default().with_prompt("qa-prompt", 3);
+/// let callbacks = CallbackManager::new().with_handler(langfuse.clone());
+/// chain.call_with_callbacks(input_variables, callbacks).await?;
+///
+/// // Trace ids are the ids of the root runs.
+/// langfuse.score(&trace_id, "user-feedback", 1.0, None);
+/// langfuse.flush().await;
+/// ```
+#[derive(Clone)]
+pub struct LangfuseHandler {
+    public_key: String,
+    secret_key: String,
+    host: String,
+    prompt: Option<(String, u32)>,
+    client: reqwest::Client,
+    /// Trace id of the running runs.
+    traces: Arc<Mutex<HashMap<String, String>>>,
+    sender: Arc<Mutex<Option<mpsc::UnboundedSender<Export>>>>,
+}
+
+impl LangfuseHandler {
+    pub fn new<S: Into<String>>(public_key: S, secret_key: S) -> Self {
+        Self {
+            public_key: public_key.into(),
+            secret_key: secret_key.into(),
+            host: DEFAULT_HOST.to_string(),
+            prompt: None,
+            client: reqwest::Client::new(),
+            traces: Arc::new(Mutex::new(HashMap::new())),
+            sender: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Url of a self-hosted Langfuse.
+    pub fn with_host<S: Into<String>>(mut self, host: S) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Links the generations to a version of a prompt managed in Langfuse.
+    pub fn with_prompt<S: Into<String>>(mut self, name: S, version: u32) -> Self {
+        self.prompt = Some((name.into(), version));
+        self
+    }
+
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Scores a trace, e.g. with the feedback of the user.
+    pub fn score<S: Into<String>>(
+        &self,
+        trace_id: &str,
+        name: S,
+        value: f64,
+        comment: Option<String>,
+    ) {
+        self.send_event(
+            "score-create",
+            json!({
+                "id": uuid::Uuid::new_v4().to_string(),
+                "traceId": trace_id,
+                "name": name.into(),
+                "value": value,
+                "comment": comment,
+            }),
+        );
+    }
+
+    /// Waits until every event notified so far has been sent.
+    pub async fn flush(&self) {
+        let (sender, receiver) = oneshot::channel();
+        if self.send(Export::Flush(sender)) {
+            let _ = receiver.await;
+        }
+    }
+
+    fn send(&self, export: Export) -> bool {
+        let mut sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        if sender.is_none() {
+            if !runtime::can_spawn() {
+                log::warn!("LangfuseHandler requires a tokio runtime, the run is not exported");
+                return false;
+            }
+            let (tx, rx) = mpsc::unbounded_channel();
+            runtime::spawn(export_events(
+                self.client.clone(),
+                format!("{}/api/public/ingestion", self.host),
+                self.public_key.clone(),
+                self.secret_key.clone(),
+                rx,
+            ));
+            *sender = Some(tx);
+        }
+        sender
+            .as_ref()
+            .map(|sender| sender.send(export).is_ok())
+            .unwrap_or_default()
+    }
+
+    fn send_event(&self, event_type: &str, body: Value) {
+        self.send(Export::Event(json!({
+            "id": uuid::Uuid::new_v4().to_string(),
+            "timestamp": format_time(runtime::now()),
+            "type": event_type,
+            "body": body,
+        })));
+    }
+
+    fn traces(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        self.traces.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn start(&self, run: &RunInfo, input: Value) {
+        let parent = run.parent_run_id.as_ref().and_then(|parent| {
+            let trace_id = self.traces().get(parent).cloned()?;
+            Some((parent.clone(), trace_id))
+        });
+        self.traces().insert(
+            run.run_id.clone(),
+            parent
+                .as_ref()
+                .map(|(_, trace_id)| trace_id.clone())
+                .unwrap_or_else(|| run.run_id.clone()),
+        );
+
+        let Some((parent_id, trace_id)) = parent else {
+            self.send_event(
+                "trace-create",
+                json!({
+                    "id": run.run_id,
+                    "name": run.name,
+                    "input": input,
+                    "timestamp": format_time(run.start_time),
+                    "tags": run.tags,
+                    "metadata": run.metadata,
+                    "userId": run.metadata.get("user"),
+                }),
+            );
+            return;
+        };
+
+        // The children of the root run are the top level observations of the trace.
+        let parent_observation_id = (parent_id != trace_id).then_some(parent_id);
+        let mut body = json!({
+            "id": run.run_id,
+            "traceId": trace_id,
+            "parentObservationId": parent_observation_id,
+            "name": run.name,
+            "input": input,
+            "startTime": format_time(run.start_time),
+        });
+        if run.run_type == RunType::Llm {
+            body["model"] = json!(run.name);
+            if let Some((name, version)) = &self.prompt {
+                body["promptName"] = json!(name);
+                body["promptVersion"] = json!(version);
+            }
+            self.send_event("generation-create", body);
+        } else {
+            self.send_event("span-create", body);
+        }
+    }
+
+    fn end(&self, run: &RunInfo, mut update: Value) {
+        let Some(trace_id) = self.traces().remove(&run.run_id) else {
+            return;
+        };
+
+        if trace_id == run.run_id {
+            // Traces are upserted by id.
+            update["id"] = json!(run.run_id);
+            self.send_event("trace-create", update);
+            return;
+        }
+
+        update["id"] = json!(run.run_id);
+        update["traceId"] = json!(trace_id);
+        update["endTime"] = json!(format_time(runtime::now()));
+        if run.run_type == RunType::Llm {
+            self.send_event("generation-update", update);
+        } else {
+            self.send_event("span-update", update);
+        }
+    }
+}
+
+impl Default for LangfuseHandler {
+    fn default() -> Self {
+        let mut handler = Self::new(
+            std::env::var("LANGFUSE_PUBLIC_KEY").unwrap_or_default(),
+            std::env::var("LANGFUSE_SECRET_KEY").unwrap_or_default(),
+        );
+        if let Ok(host) = std::env::var("LANGFUSE_HOST") {
+            handler = handler.with_host(host);
+        }
+        handler
+    }
+}
+
+async fn export_events(
+    client: reqwest::Client,
+    url: String,
+    public_key: String,
+    secret_key: String,
+    mut receiver: mpsc::UnboundedReceiver<Export>,
+) {
+    while let Some(export) = receiver.recv().await {
+        let mut batch = Vec::new();
+        let mut flushes = Vec::new();
+        let mut next = Some(export);
+        // Sends every event already notified in a single request.
+        while let Some(export) = next {
+            match export {
+                Export::Event(event) => batch.push(event),
+                Export::Flush(done) => flushes.push(done),
+            }
+            next = receiver.try_recv().ok();
+        }
+
+        if !batch.is_empty() {
+            let result = client
+                .post(&url)
+                .basic_auth(&public_key, Some(&secret_key))
+                .json(&json!({ "batch": batch }))
+                .send()
+                .await;
+            match result {
+                Ok(response) if !response.status().is_success() => {
+                    log::warn!("Langfuse rejected the events: {}", response.status())
+                }
+                Err(e) => log::warn!("Error exporting the events to Langfuse: {}", e),
+                _ => {}
+            }
+        }
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+}
+
+impl CallbackHandler for LangfuseHandler {
+    fn on_chain_start(&self, run: &RunInfo, inputs: &PromptArgs) {
+        self.start(run, json!(inputs));
+    }
+
+    fn on_chain_end(&self, run: &RunInfo, output: &GenerateResult) {
+        self.end(run, json!({ "output": output.generation }));
+    }
+
+    fn on_llm_start(&self, run: &RunInfo, messages: &[Message]) {
+        self.start(run, json!(messages));
+    }
+
+    fn on_llm_end(&self, run: &RunInfo, result: &GenerateResult) {
+        let mut update = json!({ "output": result.generation });
+        if let Some(tokens) = &result.tokens {
+            update["usage"] = json!({
+                "input": tokens.prompt_tokens,
+                "output": tokens.completion_tokens,
+                "total": tokens.total_tokens,
+                "unit": "TOKENS",
+            });
+        }
+        self.end(run, update);
+    }
+
+    fn on_tool_start(&self, run: &RunInfo, input: &str) {
+        self.start(run, json!(input));
+    }
+
+    fn on_tool_end(&self, run: &RunInfo, output: &str) {
+        self.end(run, json!({ "output": output }));
+    }
+
+    fn on_retriever_start(&self, run: &RunInfo, query: &str) {
+        self.start(run, json!(query));
+    }
+
+    fn on_retriever_end(&self, run: &RunInfo, documents: &[Document]) {
+        self.end(run, json!({ "output": documents }));
+    }
+
+    fn on_error(&self, run: &RunInfo, error: &str) {
+        self.end(
+            run,
+            json!({
+                "level": "ERROR",
+                "statusMessage": error,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{callbacks::CallbackManager, language_models::TokenUsage};
+
+    #[tokio::test]
+    async fn test_langfuse_exports_trace_and_generation() {
+        let batches = Arc::new(Mutex::new(Vec::<Value>::new()));
+        let received = batches.clone();
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/public/ingestion")
+            .match_header("authorization", "Basic cGs6c2s=")
+            .with_status(207)
+            .with_body_from_request(move |request| {
+                let batch = serde_json::from_slice(request.body().unwrap()).unwrap();
+                received.lock().unwrap().push(batch);
+                vec![]
+            })
+            .create_async()
+            .await;
+
+        let langfuse = LangfuseHandler::new("pk", "sk")
+            .with_host(server.url())
+            .with_prompt("qa", 2);
+        let chain = CallbackManager::new()
+            .with_handler(langfuse.clone())
+            .start_run("LLMChain", RunType::Chain);
+        chain
+            .trace_chain(&PromptArgs::new(), async {
+                CallbackManager::new()
+                    .start_run("gpt-4", RunType::Llm)
+                    .trace_llm(&[], async {
+                        Ok(GenerateResult {
+                            generation: "Hello".to_string(),
+                            tokens: Some(TokenUsage::new(1, 2)),
+                            ..Default::default()
+                        })
+                    })
+                    .await?;
+                Ok(GenerateResult::default())
+            })
+            .await
+            .unwrap();
+        langfuse.score(chain.run_id(), "correctness", 1.0, None);
+        langfuse.flush().await;
+        mock.assert_async().await;
+
+        let events: Vec<Value> = batches
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|batch| batch["batch"].as_array().unwrap().clone())
+            .collect();
+        let types: Vec<_> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+        assert_eq!(
+            types,
+            vec![
+                "trace-create",
+                "generation-create",
+                "generation-update",
+                "trace-create",
+                "score-create"
+            ]
+        );
+
+        let generation = &events[1]["body"];
+        assert_eq!(generation["traceId"], chain.run_id());
+        assert_eq!(generation["parentObservationId"], Value::Null);
+        assert_eq!(generation["model"], "gpt-4");
+        assert_eq!(generation["promptName"], "qa");
+        assert_eq!(generation["promptVersion"], 2);
+        assert_eq!(events[2]["body"]["usage"]["total"], 3);
+        assert_eq!(events[4]["body"]["traceId"], chain.run_id());
+    }
+}
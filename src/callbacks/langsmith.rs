@@ -0,0 +1,400 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    runtime,
+    schemas::{Document, Message},
+};
+
+use super::{CallbackHandler, RunInfo, RunType};
+
+const DEFAULT_ENDPOINT: &str = "https://api.smith.langchain.com";
+const DEFAULT_PROJECT: &str = "default";
+
+enum Export {
+    Create(Value),
+    Update(String, Value),
+    Flush(oneshot::Sender<()>),
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// The `dotted_order` segment of a run, ordering the runs of a trace by their start time.
+fn dotted_order_segment(run: &RunInfo) -> String {
+    format!(
+        "{}{}",
+        DateTime::<Utc>::from(run.start_time).format("%Y%m%dT%H%M%S%6fZ"),
+        run.run_id
+    )
+}
+
+fn run_type(run_type: RunType) -> &'static str {
+    match run_type {
+        RunType::Chain => "chain",
+        RunType::Llm => "llm",
+        RunType::Tool => "tool",
+        RunType::Retriever => "retriever",
+        RunType::Embedding => "embedding",
+    }
+}
+
+fn llm_outputs(result: &GenerateResult) -> Value {
+    let mut outputs = json!({
+        "generations": [{ "text": result.generation }],
+    });
+    if let Some(tokens) = &result.tokens {
+        outputs["llm_output"] = json!({ "token_usage": tokens });
+    }
+    outputs
+}
+
+/// Exports the runs to [LangSmith](https://smith.langchain.com), so they show up in the
+/// same projects as the traces of the Python and JS LangChain.
+///
+/// Runs are created when they start and updated with their outputs when they end, with
+/// the `trace_id` and `dotted_order` LangSmith builds the run trees with. The requests are
+/// sent by a background task, call `flush` before the program exits to wait for the
+/// pending ones.
+///
+/// # Example
+/// ```rust,ignore
+/// let tracer = LangSmithTracer::default().with_project("my-service");
+/// let callbacks = CallbackManager::new().with_handler(tracer.clone());
+/// let result = chain.call_with_callbacks(input_variables, callbacks).await?;
+/// tracer.flush().await;
+/// ```
+#[derive(Clone)]
+pub struct LangSmithTracer {
+    api_key: String,
+    endpoint: String,
+    project: String,
+    client: reqwest::Client,
+    sender: Arc<Mutex<Option<mpsc::UnboundedSender<Export>>>>,
+    /// The trace id and dotted order of the running runs, inherited by their children.
+    runs: Arc<Mutex<HashMap<String, (String, String)>>>,
+}
+
+impl LangSmithTracer {
+    pub fn new<S: Into<String>>(api_key: S) -> Self {
+        Self {
+            api_key: api_key.into(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            project: DEFAULT_PROJECT.to_string(),
+            client: reqwest::Client::new(),
+            sender: Arc::new(Mutex::new(None)),
+            runs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn with_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    pub fn with_endpoint<S: Into<String>>(mut self, endpoint: S) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Project (session) the runs are recorded in.
+    pub fn with_project<S: Into<String>>(mut self, project: S) -> Self {
+        self.project = project.into();
+        self
+    }
+
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Waits until every run notified so far has been sent.
+    pub async fn flush(&self) {
+        let (sender, receiver) = oneshot::channel();
+        if self.send(Export::Flush(sender)) {
+            let _ = receiver.await;
+        }
+    }
+
+    /// Sends the export to the background task, starting it on the first export.
+    fn send(&self, export: Export) -> bool {
+        let mut sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        if sender.is_none() {
+            if !runtime::can_spawn() {
+                log::warn!("LangSmithTracer requires a tokio runtime, the run is not exported");
+                return false;
+            }
+            let (tx, rx) = mpsc::unbounded_channel();
+            runtime::spawn(export_runs(
+                self.client.clone(),
+                self.endpoint.clone(),
+                self.api_key.clone(),
+                rx,
+            ));
+            *sender = Some(tx);
+        }
+        sender
+            .as_ref()
+            .map(|sender| sender.send(export).is_ok())
+            .unwrap_or_default()
+    }
+
+    fn create_run(&self, run: &RunInfo, inputs: Value) {
+        let (trace_id, dotted_order) = {
+            let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+            let parent = run
+                .parent_run_id
+                .as_ref()
+                .and_then(|parent| runs.get(parent));
+            let segment = dotted_order_segment(run);
+            let (trace_id, dotted_order) = match parent {
+                Some((trace_id, dotted_order)) => {
+                    (trace_id.clone(), format!("{}.{}", dotted_order, segment))
+                }
+                None => (run.run_id.clone(), segment),
+            };
+            runs.insert(run.run_id.clone(), (trace_id.clone(), dotted_order.clone()));
+            (trace_id, dotted_order)
+        };
+
+        self.send(Export::Create(json!({
+            "id": run.run_id,
+            "trace_id": trace_id,
+            "dotted_order": dotted_order,
+            "parent_run_id": run.parent_run_id,
+            "name": run.name,
+            "run_type": run_type(run.run_type),
+            "inputs": inputs,
+            "start_time": format_time(run.start_time),
+            "session_name": self.project,
+            "tags": run.tags,
+            "extra": { "metadata": run.metadata },
+        })));
+    }
+
+    fn end_run(&self, run: &RunInfo, outputs: Value) {
+        self.update_run(
+            run,
+            json!({
+                "outputs": outputs,
+                "end_time": format_time(runtime::now()),
+            }),
+        );
+    }
+
+    fn update_run(&self, run: &RunInfo, mut update: Value) {
+        let ids = self
+            .runs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&run.run_id);
+        if let Some((trace_id, dotted_order)) = ids {
+            update["trace_id"] = json!(trace_id);
+            update["dotted_order"] = json!(dotted_order);
+        }
+        self.send(Export::Update(run.run_id.clone(), update));
+    }
+}
+
+impl Default for LangSmithTracer {
+    fn default() -> Self {
+        let api_key = std::env::var("LANGSMITH_API_KEY")
+            .or_else(|_| std::env::var("LANGCHAIN_API_KEY"))
+            .unwrap_or_default();
+        let mut tracer = Self::new(api_key);
+        if let Ok(endpoint) = std::env::var("LANGCHAIN_ENDPOINT") {
+            tracer = tracer.with_endpoint(endpoint);
+        }
+        if let Ok(project) = std::env::var("LANGCHAIN_PROJECT") {
+            tracer = tracer.with_project(project);
+        }
+        tracer
+    }
+}
+
+async fn export_runs(
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    mut receiver: mpsc::UnboundedReceiver<Export>,
+) {
+    while let Some(export) = receiver.recv().await {
+        let request = match export {
+            Export::Create(run) => client.post(format!("{}/runs", endpoint)).json(&run),
+            Export::Update(run_id, run) => client
+                .patch(format!("{}/runs/{}", endpoint, run_id))
+                .json(&run),
+            Export::Flush(done) => {
+                let _ = done.send(());
+                continue;
+            }
+        };
+        match request.header("x-api-key", &api_key).send().await {
+            Ok(response) if !response.status().is_success() => {
+                log::warn!("LangSmith rejected the run: {}", response.status())
+            }
+            Err(e) => log::warn!("Error exporting the run to LangSmith: {}", e),
+            _ => {}
+        }
+    }
+}
+
+impl CallbackHandler for LangSmithTracer {
+    fn on_chain_start(&self, run: &RunInfo, inputs: &PromptArgs) {
+        self.create_run(run, json!(inputs));
+    }
+
+    fn on_chain_end(&self, run: &RunInfo, output: &GenerateResult) {
+        self.end_run(run, json!({ "output": output.generation }));
+    }
+
+    fn on_llm_start(&self, run: &RunInfo, messages: &[Message]) {
+        self.create_run(run, json!({ "messages": messages }));
+    }
+
+    fn on_llm_end(&self, run: &RunInfo, result: &GenerateResult) {
+        self.end_run(run, llm_outputs(result));
+    }
+
+    fn on_tool_start(&self, run: &RunInfo, input: &str) {
+        self.create_run(run, json!({ "input": input }));
+    }
+
+    fn on_tool_end(&self, run: &RunInfo, output: &str) {
+        self.end_run(run, json!({ "output": output }));
+    }
+
+    fn on_retriever_start(&self, run: &RunInfo, query: &str) {
+        self.create_run(run, json!({ "query": query }));
+    }
+
+    fn on_retriever_end(&self, run: &RunInfo, documents: &[Document]) {
+        self.end_run(run, json!({ "documents": documents }));
+    }
+
+    fn on_error(&self, run: &RunInfo, error: &str) {
+        self.update_run(
+            run,
+            json!({
+                "error": error,
+                "end_time": format_time(runtime::now()),
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+
+    use super::*;
+    use crate::{callbacks::CallbackManager, language_models::TokenUsage};
+
+    #[tokio::test]
+    async fn test_langsmith_exports_runs() {
+        let mut server = mockito::Server::new_async().await;
+        let create = server
+            .mock("POST", "/runs")
+            .match_header("x-api-key", "key")
+            .match_body(Matcher::PartialJson(json!({
+                "name": "gpt-4",
+                "run_type": "llm",
+                "session_name": "tests",
+            })))
+            .create_async()
+            .await;
+        let update = server
+            .mock("PATCH", Matcher::Regex("^/runs/.+$".to_string()))
+            .match_body(Matcher::PartialJson(json!({
+                "outputs": {
+                    "generations": [{ "text": "Hello" }],
+                    "llm_output": { "token_usage": { "total_tokens": 3 } }
+                }
+            })))
+            .create_async()
+            .await;
+
+        let tracer = LangSmithTracer::new("key")
+            .with_endpoint(server.url())
+            .with_project("tests");
+        let run = CallbackManager::new()
+            .with_handler(tracer.clone())
+            .start_run("gpt-4", RunType::Llm);
+        run.trace_llm(&[Message::new_human_message("Hi")], async {
+            Ok(GenerateResult {
+                generation: "Hello".to_string(),
+                tokens: Some(TokenUsage::new(1, 2)),
+                ..Default::default()
+            })
+        })
+        .await
+        .unwrap();
+        tracer.flush().await;
+
+        create.assert_async().await;
+        update.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_langsmith_run_tree() {
+        let mut server = mockito::Server::new_async().await;
+        let tracer = LangSmithTracer::new("key").with_endpoint(server.url());
+        let chain = CallbackManager::new()
+            .with_handler(tracer.clone())
+            .start_run("LLMChain", RunType::Chain);
+        let llm = chain
+            .scope(async { CallbackManager::new().start_run("gpt-4", RunType::Llm) })
+            .await;
+
+        let create_chain = server
+            .mock("POST", "/runs")
+            .match_body(Matcher::PartialJson(json!({
+                "id": chain.run_id(),
+                "trace_id": chain.run_id(),
+                "dotted_order": dotted_order_segment(chain.info()),
+            })))
+            .create_async()
+            .await;
+        let create_llm = server
+            .mock("POST", "/runs")
+            .match_body(Matcher::PartialJson(json!({
+                "id": llm.run_id(),
+                "parent_run_id": chain.run_id(),
+                "trace_id": chain.run_id(),
+                "dotted_order": format!(
+                    "{}.{}",
+                    dotted_order_segment(chain.info()),
+                    dotted_order_segment(llm.info())
+                ),
+            })))
+            .create_async()
+            .await;
+        let error = server
+            .mock("PATCH", format!("/runs/{}", llm.run_id()).as_str())
+            .match_body(Matcher::PartialJson(json!({
+                "error": "rate limited",
+                "trace_id": chain.run_id(),
+            })))
+            .create_async()
+            .await;
+
+        chain.on_chain_start(&PromptArgs::new());
+        llm.on_llm_start(&[Message::new_human_message("Hi")]);
+        llm.on_error("rate limited");
+        tracer.flush().await;
+
+        create_chain.assert_async().await;
+        create_llm.assert_async().await;
+        error.assert_async().await;
+    }
+}
@@ -0,0 +1,712 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use tracing::{Instrument, Span};
+
+use crate::{
+    agent::Plan,
+    chain::{ChainError, DEFAULT_RESULT_KEY},
+    embedding::EmbedderError,
+    guardrails::InjectionDetection,
+    language_models::{GenerateResult, LLMError, TokenUsage},
+    prompt::PromptArgs,
+    runtime,
+    schemas::{Document, Message, StreamData},
+};
+
+use super::{
+    spans::{record_end, record_error, run_span},
+    CallbackHandler, RunConfig, RunInfo, RunType,
+};
+
+tokio::task_local! {
+    static CURRENT_RUN: RunManager;
+}
+
+fn push_unique(handlers: &mut Vec<Arc<dyn CallbackHandler>>, handler: &Arc<dyn CallbackHandler>) {
+    if !handlers.iter().any(|h| Arc::ptr_eq(h, handler)) {
+        handlers.push(handler.clone());
+    }
+}
+
+/// A set of callback handlers.
+///
+/// Runs started with `start_run` notify the handlers of the manager plus the handlers
+/// inherited from the run they are nested in, so setting the handlers on the outermost
+/// chain is enough to observe every chain, llm, tool and retriever it calls.
+#[derive(Clone, Default)]
+pub struct CallbackManager {
+    handlers: Vec<Arc<dyn CallbackHandler>>,
+}
+
+impl fmt::Debug for CallbackManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackManager")
+            .field("handlers", &self.handlers.len())
+            .finish()
+    }
+}
+
+impl CallbackManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_handler<H: CallbackHandler + 'static>(self, handler: H) -> Self {
+        self.with_shared_handler(Arc::new(handler))
+    }
+
+    /// Adds a handler that is also kept by the caller, e.g. to read its state afterwards.
+    pub fn with_shared_handler(mut self, handler: Arc<dyn CallbackHandler>) -> Self {
+        push_unique(&mut self.handlers, &handler);
+        self
+    }
+
+    pub fn handlers(&self) -> &[Arc<dyn CallbackHandler>] {
+        &self.handlers
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Adds the handlers of `other` that are not already in this manager.
+    pub fn merge(&mut self, other: &CallbackManager) {
+        for handler in other.handlers.iter() {
+            push_unique(&mut self.handlers, handler);
+        }
+    }
+
+    /// Starts a new run. If called while another run is executing, the new run is its
+    /// child and inherits its handlers, tags and metadata.
+    pub fn start_run<S: Into<String>>(&self, name: S, run_type: RunType) -> RunManager {
+        let context = CURRENT_RUN.try_with(|run| run.clone()).ok();
+        let mut handlers = context
+            .as_ref()
+            .map(|context| context.handlers.clone())
+            .unwrap_or_default();
+        for handler in self.handlers.iter() {
+            push_unique(&mut handlers, handler);
+        }
+        let configurable = context
+            .as_ref()
+            .map(|context| context.configurable.clone())
+            .unwrap_or_default();
+
+        let (run_id, parent_run_id, tags, metadata) = match context {
+            Some(context) if context.is_scope() => (
+                context.take_next_run_id(),
+                context.info.parent_run_id,
+                context.info.tags,
+                context.info.metadata,
+            ),
+            Some(context) => (
+                None,
+                Some(context.info.run_id),
+                context.info.tags,
+                context.info.metadata,
+            ),
+            None => (None, None, Vec::new(), HashMap::new()),
+        };
+        let info = RunInfo {
+            run_id: run_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            parent_run_id,
+            name: name.into(),
+            run_type,
+            start_time: runtime::now(),
+            tags,
+            metadata,
+        };
+        RunManager {
+            span: run_span(&info),
+            info,
+            handlers,
+            configurable,
+            next_run_id: None,
+        }
+    }
+
+    /// Executes `future` with the handlers of this manager inherited by every run started
+    /// inside of it.
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        enter_scope(self, &RunConfig::default(), future).await
+    }
+}
+
+/// Executes `future` in a scope that is not a run: the runs started inside of it are
+/// children of the run executing the scope, or of `config.parent_run_id`.
+pub(crate) async fn enter_scope<F: Future>(
+    callbacks: &CallbackManager,
+    config: &RunConfig,
+    future: F,
+) -> F::Output {
+    let context = CURRENT_RUN.try_with(|run| run.clone()).ok();
+    let mut handlers = context
+        .as_ref()
+        .map(|context| context.handlers.clone())
+        .unwrap_or_default();
+    for handler in callbacks.handlers.iter() {
+        push_unique(&mut handlers, handler);
+    }
+    let mut configurable = context
+        .as_ref()
+        .map(|context| context.configurable.clone())
+        .unwrap_or_default();
+    configurable.extend(config.configurable.clone());
+
+    let (mut parent_run_id, mut tags, mut metadata) = match context {
+        Some(context) if context.is_scope() => (
+            context.info.parent_run_id,
+            context.info.tags,
+            context.info.metadata,
+        ),
+        Some(context) => (
+            Some(context.info.run_id),
+            context.info.tags,
+            context.info.metadata,
+        ),
+        None => (None, Vec::new(), HashMap::new()),
+    };
+    if config.parent_run_id.is_some() {
+        parent_run_id = config.parent_run_id.clone();
+    }
+    for tag in config.tags.iter() {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    metadata.extend(config.metadata.clone());
+
+    let scope = RunManager {
+        info: RunInfo {
+            run_id: String::new(),
+            parent_run_id,
+            name: String::new(),
+            run_type: RunType::Chain,
+            start_time: runtime::now(),
+            tags,
+            metadata,
+        },
+        handlers,
+        configurable,
+        span: Span::none(),
+        next_run_id: Some(Arc::new(Mutex::new(config.run_id.clone()))),
+    };
+    CURRENT_RUN.scope(scope, future).await
+}
+
+/// Returns the run currently executing, if any.
+pub fn current_run() -> Option<RunManager> {
+    CURRENT_RUN
+        .try_with(|run| run.clone())
+        .ok()
+        .filter(|run| !run.is_scope())
+}
+
+/// Returns the tags, metadata and configurable values of the current run or scope.
+pub(crate) fn current_config() -> RunConfig {
+    CURRENT_RUN
+        .try_with(|run| RunConfig {
+            tags: run.info.tags.clone(),
+            metadata: run.info.metadata.clone(),
+            configurable: run.configurable.clone(),
+            ..Default::default()
+        })
+        .unwrap_or_default()
+}
+
+/// A started run. Notifies the handlers of its events.
+///
+/// Every run also has a `tracing` span, entered while its body executes, carrying its
+/// ids, latency and token usage. Subscribers see the runs without any handler.
+#[derive(Clone)]
+pub struct RunManager {
+    info: RunInfo,
+    handlers: Vec<Arc<dyn CallbackHandler>>,
+    /// Configurable values of the `RunConfig`s the run is nested in.
+    configurable: HashMap<String, Value>,
+    span: Span,
+    /// Only set for scopes: the id of the first run started in the scope.
+    next_run_id: Option<Arc<Mutex<Option<String>>>>,
+}
+
+impl RunManager {
+    fn is_scope(&self) -> bool {
+        self.next_run_id.is_some()
+    }
+
+    fn take_next_run_id(&self) -> Option<String> {
+        self.next_run_id
+            .as_ref()
+            .and_then(|id| id.lock().unwrap_or_else(|e| e.into_inner()).take())
+    }
+
+    pub fn info(&self) -> &RunInfo {
+        &self.info
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.info.run_id
+    }
+
+    pub fn handlers(&self) -> &[Arc<dyn CallbackHandler>] {
+        &self.handlers
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn on_chain_start(&self, inputs: &PromptArgs) {
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_chain_start(&self.info, inputs));
+    }
+
+    pub fn on_chain_end(&self, output: &GenerateResult) {
+        record_end(&self.span, &self.info, Some(output));
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_chain_end(&self.info, output));
+    }
+
+    pub fn on_llm_start(&self, messages: &[Message]) {
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_llm_start(&self.info, messages));
+    }
+
+    pub fn on_llm_new_token(&self, token: &str) {
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_llm_new_token(&self.info, token));
+    }
+
+    pub fn on_llm_end(&self, result: &GenerateResult) {
+        record_end(&self.span, &self.info, Some(result));
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_llm_end(&self.info, result));
+    }
+
+    pub fn on_tool_start(&self, input: &str) {
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_tool_start(&self.info, input));
+    }
+
+    pub fn on_tool_end(&self, output: &str) {
+        record_end(&self.span, &self.info, None);
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_tool_end(&self.info, output));
+    }
+
+    pub fn on_retriever_start(&self, query: &str) {
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_retriever_start(&self.info, query));
+    }
+
+    pub fn on_retriever_end(&self, documents: &[Document]) {
+        record_end(&self.span, &self.info, None);
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_retriever_end(&self.info, documents));
+    }
+
+    pub fn on_embedding_end(&self, tokens: Option<&TokenUsage>) {
+        let result = GenerateResult {
+            tokens: tokens.cloned(),
+            ..Default::default()
+        };
+        record_end(&self.span, &self.info, Some(&result));
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_embedding_end(&self.info, tokens));
+    }
+
+    pub fn on_injection_detected(&self, detection: &InjectionDetection) {
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_injection_detected(&self.info, detection));
+    }
+
+    pub fn on_plan(&self, plan: &Plan) {
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_plan(&self.info, plan));
+    }
+
+    pub fn on_error(&self, error: &str) {
+        record_error(&self.span, &self.info, error);
+        self.handlers
+            .iter()
+            .for_each(|h| h.on_error(&self.info, error));
+    }
+
+    /// Executes `future` as the body of this run: runs started inside of it are its
+    /// children.
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        CURRENT_RUN
+            .scope(self.clone(), future.instrument(self.span.clone()))
+            .await
+    }
+
+    /// Executes the body of a chain, notifying its start, end or error.
+    pub async fn trace_chain<F>(
+        &self,
+        inputs: &PromptArgs,
+        future: F,
+    ) -> Result<GenerateResult, ChainError>
+    where
+        F: Future<Output = Result<GenerateResult, ChainError>>,
+    {
+        self.on_chain_start(inputs);
+        let result = self.scope(future).await;
+        match &result {
+            Ok(output) => self.on_chain_end(output),
+            Err(e) => self.on_error(&e.to_string()),
+        }
+        result
+    }
+
+    /// Like `trace_chain`, for the output of `Chain::execute`: the end of the run is
+    /// notified with the result under `generate_result`.
+    pub async fn trace_chain_outputs<F>(
+        &self,
+        inputs: &PromptArgs,
+        future: F,
+    ) -> Result<HashMap<String, Value>, ChainError>
+    where
+        F: Future<Output = Result<HashMap<String, Value>, ChainError>>,
+    {
+        self.on_chain_start(inputs);
+        let result = self.scope(future).await;
+        match &result {
+            Ok(outputs) => {
+                let output = outputs
+                    .get(DEFAULT_RESULT_KEY)
+                    .and_then(|result| serde_json::from_value(result.clone()).ok())
+                    .unwrap_or_default();
+                self.on_chain_end(&output)
+            }
+            Err(e) => self.on_error(&e.to_string()),
+        }
+        result
+    }
+
+    /// Executes a call to a LLM, notifying its start, end or error.
+    pub async fn trace_llm<F>(
+        &self,
+        messages: &[Message],
+        future: F,
+    ) -> Result<GenerateResult, LLMError>
+    where
+        F: Future<Output = Result<GenerateResult, LLMError>>,
+    {
+        self.on_llm_start(messages);
+        let result = self.scope(future).await;
+        match &result {
+            Ok(output) => self.on_llm_end(output),
+            Err(e) => self.on_error(&e.to_string()),
+        }
+        result
+    }
+
+    /// Wraps the stream of a LLM, notifying every token and, when the stream is
+    /// exhausted, the end of the run with the whole generation.
+    pub fn trace_llm_stream(
+        self,
+        messages: &[Message],
+        stream: Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>,
+    ) -> Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>> {
+        self.on_llm_start(messages);
+        self.trace_stream(stream, true, Self::on_llm_end)
+    }
+
+    /// Wraps the stream of a chain, notifying the end of the run with the whole generation
+    /// when the stream is exhausted. The start of the run must already be notified, so the
+    /// runs creating the stream are its children.
+    pub fn trace_chain_stream(
+        self,
+        stream: Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>,
+    ) -> Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>> {
+        self.trace_stream(stream, false, Self::on_chain_end)
+    }
+
+    fn trace_stream<E: fmt::Display + Send + 'static>(
+        self,
+        mut stream: Pin<Box<dyn Stream<Item = Result<StreamData, E>> + Send>>,
+        notify_tokens: bool,
+        on_end: fn(&Self, &GenerateResult),
+    ) -> Pin<Box<dyn Stream<Item = Result<StreamData, E>> + Send>> {
+        Box::pin(async_stream::stream! {
+            let mut generation = String::new();
+            let mut failed = false;
+            while let Some(item) = stream.next().await {
+                match &item {
+                    Ok(data) if data.is_token() => {
+                        if notify_tokens && !data.content.is_empty() {
+                            self.on_llm_new_token(&data.content);
+                        }
+                        generation.push_str(&data.content);
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        self.on_error(&e.to_string());
+                        failed = true;
+                    }
+                }
+                yield item;
+            }
+            if !failed {
+                on_end(
+                    &self,
+                    &GenerateResult {
+                        generation,
+                        ..Default::default()
+                    },
+                );
+            }
+        })
+    }
+
+    /// Executes a tool, notifying its start, end or error.
+    pub async fn trace_tool<F>(&self, input: &str, future: F) -> Result<String, Box<dyn Error>>
+    where
+        F: Future<Output = Result<String, Box<dyn Error>>>,
+    {
+        self.on_tool_start(input);
+        let result = self.scope(future).await;
+        match &result {
+            Ok(output) => self.on_tool_end(output),
+            Err(e) => self.on_error(&e.to_string()),
+        }
+        result
+    }
+
+    /// Executes a retrieval, notifying its start, end or error.
+    pub async fn trace_retriever<F>(
+        &self,
+        query: &str,
+        future: F,
+    ) -> Result<Vec<Document>, Box<dyn Error>>
+    where
+        F: Future<Output = Result<Vec<Document>, Box<dyn Error>>>,
+    {
+        self.on_retriever_start(query);
+        let result = self.scope(future).await;
+        match &result {
+            Ok(documents) => self.on_retriever_end(documents),
+            Err(e) => self.on_error(&e.to_string()),
+        }
+        result
+    }
+
+    /// Executes a call to an embedding model, notifying its end, with the tokens reported
+    /// by the provider, or its error.
+    pub async fn trace_embedding<T, F>(&self, future: F) -> Result<T, EmbedderError>
+    where
+        F: Future<Output = Result<(T, Option<TokenUsage>), EmbedderError>>,
+    {
+        match self.scope(future).await {
+            Ok((output, tokens)) => {
+                self.on_embedding_end(tokens.as_ref());
+                Ok(output)
+            }
+            Err(e) => {
+                self.on_error(&e.to_string());
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::prompt_args;
+
+    /// Records every event along with its run.
+    #[derive(Default)]
+    pub(crate) struct RecordingHandler {
+        pub(crate) events: Mutex<Vec<(String, RunInfo)>>,
+    }
+
+    impl RecordingHandler {
+        pub(crate) fn names(&self) -> Vec<String> {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(event, run)| format!("{}:{}", event, run.name))
+                .collect()
+        }
+
+        fn record(&self, event: &str, run: &RunInfo) {
+            self.events
+                .lock()
+                .unwrap()
+                .push((event.to_string(), run.clone()));
+        }
+    }
+
+    impl CallbackHandler for RecordingHandler {
+        fn on_chain_start(&self, run: &RunInfo, _inputs: &PromptArgs) {
+            self.record("chain_start", run)
+        }
+        fn on_chain_end(&self, run: &RunInfo, _output: &GenerateResult) {
+            self.record("chain_end", run)
+        }
+        fn on_llm_start(&self, run: &RunInfo, _messages: &[Message]) {
+            self.record("llm_start", run)
+        }
+        fn on_llm_new_token(&self, run: &RunInfo, _token: &str) {
+            self.record("llm_new_token", run)
+        }
+        fn on_llm_end(&self, run: &RunInfo, _result: &GenerateResult) {
+            self.record("llm_end", run)
+        }
+        fn on_tool_start(&self, run: &RunInfo, _input: &str) {
+            self.record("tool_start", run)
+        }
+        fn on_tool_end(&self, run: &RunInfo, _output: &str) {
+            self.record("tool_end", run)
+        }
+        fn on_error(&self, run: &RunInfo, _error: &str) {
+            self.record("error", run)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nested_runs_inherit_handlers() {
+        let handler = Arc::new(RecordingHandler::default());
+        let callbacks = CallbackManager::new().with_shared_handler(handler.clone());
+
+        let chain = callbacks.start_run("outer", RunType::Chain);
+        let result = chain
+            .trace_chain(&prompt_args! {"input" => "hi"}, async {
+                // Started without handlers, the ones of the parent are inherited.
+                let llm = CallbackManager::new().start_run("llm", RunType::Llm);
+                llm.trace_llm(&[], async { Ok(GenerateResult::default()) })
+                    .await?;
+
+                let tool = CallbackManager::new().start_run("tool", RunType::Tool);
+                let _ = tool.trace_tool("input", async { Err("boom".into()) }).await;
+                Ok(GenerateResult::default())
+            })
+            .await;
+        assert!(result.is_ok());
+
+        assert_eq!(
+            handler.names(),
+            vec![
+                "chain_start:outer",
+                "llm_start:llm",
+                "llm_end:llm",
+                "tool_start:tool",
+                "error:tool",
+                "chain_end:outer"
+            ]
+        );
+
+        let events = handler.events.lock().unwrap();
+        let outer_id = events[0].1.run_id.clone();
+        assert!(events[0].1.parent_run_id.is_none());
+        assert_eq!(events[1].1.parent_run_id, Some(outer_id.clone()));
+        assert_eq!(events[3].1.parent_run_id, Some(outer_id));
+    }
+
+    #[tokio::test]
+    async fn test_scope_and_no_duplicated_handlers() {
+        let handler = Arc::new(RecordingHandler::default());
+        let callbacks = CallbackManager::new().with_shared_handler(handler.clone());
+
+        callbacks
+            .scope(async {
+                // The same handler set again is only notified once.
+                let run = callbacks.start_run("chain", RunType::Chain);
+                assert!(run.info().parent_run_id.is_none());
+                run.on_chain_start(&PromptArgs::new());
+            })
+            .await;
+        assert_eq!(handler.names(), vec!["chain_start:chain"]);
+
+        let run = CallbackManager::new().start_run("orphan", RunType::Chain);
+        assert!(run.handlers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trace_llm_stream() {
+        let handler = Arc::new(RecordingHandler::default());
+        let run = CallbackManager::new()
+            .with_shared_handler(handler.clone())
+            .start_run("llm", RunType::Llm);
+
+        let stream = futures::stream::iter(vec![
+            Ok(StreamData::new(serde_json::json!({}), "Hel")),
+            Ok(StreamData::new(serde_json::json!({}), "lo")),
+        ]);
+        let stream = run.trace_llm_stream(&[], Box::pin(stream));
+        let tokens: Vec<_> = stream.collect().await;
+        assert_eq!(tokens.len(), 2);
+
+        assert_eq!(
+            handler.names(),
+            vec![
+                "llm_start:llm",
+                "llm_new_token:llm",
+                "llm_new_token:llm",
+                "llm_end:llm"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_with_callbacks() {
+        use crate::{
+            chain::{Chain, LLMChainBuilder},
+            llm::FakeLLM,
+            message_formatter,
+            prompt::{HumanMessagePromptTemplate, MessageOrTemplate},
+            template_fstring,
+        };
+
+        let chain = LLMChainBuilder::new()
+            .prompt(message_formatter![MessageOrTemplate::Template(
+                HumanMessagePromptTemplate::new(template_fstring!("{input}", "input")).into()
+            )])
+            .llm(FakeLLM::new("hi"))
+            .build()
+            .unwrap();
+
+        let handler = Arc::new(RecordingHandler::default());
+        let callbacks = CallbackManager::new().with_shared_handler(handler.clone());
+        let result = chain
+            .call_with_callbacks(prompt_args! {"input" => "hello"}, callbacks)
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "hi");
+
+        assert_eq!(
+            handler.names(),
+            vec![
+                "chain_start:LLMChain",
+                "llm_start:fake",
+                "llm_end:fake",
+                "chain_end:LLMChain"
+            ]
+        );
+    }
+}
@@ -0,0 +1,33 @@
+mod handler;
+pub use handler::*;
+
+mod manager;
+pub use manager::*;
+
+mod config;
+pub use config::*;
+
+mod spans;
+
+mod langsmith;
+pub use langsmith::*;
+
+mod langfuse;
+pub use langfuse::*;
+
+mod usage;
+pub use usage::*;
+
+mod events;
+pub use events::*;
+
+mod stdout;
+pub use stdout::*;
+
+#[cfg(feature = "opentelemetry")]
+mod otel;
+#[cfg(feature = "opentelemetry")]
+pub use otel::*;
+/// `OpenTelemetryHandler` under the name of the callback handlers of LangChain.
+#[cfg(feature = "opentelemetry")]
+pub type OpenTelemetryCallbackHandler = OpenTelemetryHandler;
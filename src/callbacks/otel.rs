@@ -0,0 +1,291 @@
+use std::{collections::HashMap, sync::Mutex, time::SystemTime};
+
+use opentelemetry::{
+    global::{self, BoxedTracer, ObjectSafeTracer},
+    metrics::{Histogram, Meter},
+    trace::{SpanKind, Status, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+
+use crate::{
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    runtime,
+    schemas::{Document, Message},
+};
+
+use super::{CallbackHandler, RunInfo, RunType};
+
+const INSTRUMENTATION_NAME: &str = "langchain-rust";
+
+/// Exports the runs as OpenTelemetry spans and metrics following the GenAI semantic
+/// conventions (`gen_ai.*` attributes), so any OTLP backend receives the telemetry of
+/// the LLM calls.
+///
+/// LLM runs are `chat {model}` client spans with the model and the token usage, and
+/// record the `gen_ai.client.token.usage` and `gen_ai.client.operation.duration`
+/// histograms. Tools are `execute_tool {name}` spans and chains and retrievers are
+/// internal spans, all nested as the runs are.
+///
+/// # Example
+/// ```rust,ignore
+/// // Uses the global tracer and meter providers.
+/// let callbacks = CallbackManager::new().with_handler(OpenTelemetryHandler::new().with_system("openai"));
+/// let result = chain.call_with_callbacks(input_variables, callbacks).await?;
+/// ```
+pub struct OpenTelemetryHandler {
+    tracer: BoxedTracer,
+    system: String,
+    token_usage: Histogram<u64>,
+    operation_duration: Histogram<f64>,
+    contexts: Mutex<HashMap<String, Context>>,
+}
+
+fn instruments(meter: &Meter) -> (Histogram<u64>, Histogram<f64>) {
+    let token_usage = meter
+        .u64_histogram("gen_ai.client.token.usage")
+        .with_unit("{token}")
+        .with_description("Measures number of input and output tokens used")
+        .build();
+    let operation_duration = meter
+        .f64_histogram("gen_ai.client.operation.duration")
+        .with_unit("s")
+        .with_description("GenAI operation duration")
+        .build();
+    (token_usage, operation_duration)
+}
+
+impl OpenTelemetryHandler {
+    pub fn new() -> Self {
+        let (token_usage, operation_duration) = instruments(&global::meter(INSTRUMENTATION_NAME));
+        Self {
+            tracer: global::tracer(INSTRUMENTATION_NAME),
+            system: "langchain".to_string(),
+            token_usage,
+            operation_duration,
+            contexts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Uses this tracer instead of the global one.
+    pub fn with_tracer<T>(mut self, tracer: T) -> Self
+    where
+        T: ObjectSafeTracer + Send + Sync + 'static,
+    {
+        self.tracer = BoxedTracer::new(Box::new(tracer));
+        self
+    }
+
+    /// Uses this meter instead of the global one.
+    pub fn with_meter(mut self, meter: Meter) -> Self {
+        (self.token_usage, self.operation_duration) = instruments(&meter);
+        self
+    }
+
+    /// Value of `gen_ai.system`, the provider of the models (e.g. `openai`, `anthropic`).
+    pub fn with_system<S: Into<String>>(mut self, system: S) -> Self {
+        self.system = system.into();
+        self
+    }
+
+    fn contexts(&self) -> std::sync::MutexGuard<'_, HashMap<String, Context>> {
+        self.contexts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn start_span(&self, run: &RunInfo, attributes: Vec<KeyValue>) {
+        let (name, kind) = match run.run_type {
+            RunType::Llm => (format!("chat {}", run.name), SpanKind::Client),
+            RunType::Embedding => (format!("embeddings {}", run.name), SpanKind::Client),
+            RunType::Tool => (format!("execute_tool {}", run.name), SpanKind::Internal),
+            RunType::Chain | RunType::Retriever => (run.name.clone(), SpanKind::Internal),
+        };
+
+        let mut contexts = self.contexts();
+        let parent = run
+            .parent_run_id
+            .as_ref()
+            .and_then(|parent| contexts.get(parent).cloned())
+            .unwrap_or_else(Context::current);
+
+        let mut span_attributes = vec![
+            KeyValue::new("langchain.run.id", run.run_id.clone()),
+            KeyValue::new("langchain.run.type", format!("{:?}", run.run_type)),
+        ];
+        span_attributes.extend(attributes);
+        let span = self
+            .tracer
+            .span_builder(name)
+            .with_kind(kind)
+            .with_start_time(run.start_time)
+            .with_attributes(span_attributes)
+            .start_with_context(&self.tracer, &parent);
+        contexts.insert(run.run_id.clone(), parent.with_span(span));
+    }
+
+    fn end_span(&self, run: &RunInfo, attributes: Vec<KeyValue>, error: Option<&str>) {
+        let Some(cx) = self.contexts().remove(&run.run_id) else {
+            return;
+        };
+        let span = cx.span();
+        span.set_attributes(attributes);
+        if let Some(error) = error {
+            span.set_attribute(KeyValue::new("error.type", "_OTHER"));
+            span.set_status(Status::error(error.to_string()));
+        }
+        span.end();
+    }
+
+    fn model_attributes(&self, run: &RunInfo) -> Vec<KeyValue> {
+        vec![
+            KeyValue::new("gen_ai.operation.name", "chat"),
+            KeyValue::new("gen_ai.system", self.system.clone()),
+            KeyValue::new("gen_ai.request.model", run.name.clone()),
+        ]
+    }
+
+    fn record_duration(&self, run: &RunInfo, error: bool) {
+        let duration = runtime::now()
+            .duration_since(run.start_time)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let mut attributes = self.model_attributes(run);
+        if error {
+            attributes.push(KeyValue::new("error.type", "_OTHER"));
+        }
+        self.operation_duration.record(duration, &attributes);
+    }
+}
+
+impl Default for OpenTelemetryHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallbackHandler for OpenTelemetryHandler {
+    fn on_chain_start(&self, run: &RunInfo, _inputs: &PromptArgs) {
+        self.start_span(run, vec![]);
+    }
+
+    fn on_chain_end(&self, run: &RunInfo, _output: &GenerateResult) {
+        self.end_span(run, vec![], None);
+    }
+
+    fn on_llm_start(&self, run: &RunInfo, _messages: &[Message]) {
+        self.start_span(run, self.model_attributes(run));
+    }
+
+    fn on_llm_end(&self, run: &RunInfo, result: &GenerateResult) {
+        let mut attributes = vec![];
+        if let Some(tokens) = &result.tokens {
+            attributes.push(KeyValue::new(
+                "gen_ai.usage.input_tokens",
+                tokens.prompt_tokens as i64,
+            ));
+            attributes.push(KeyValue::new(
+                "gen_ai.usage.output_tokens",
+                tokens.completion_tokens as i64,
+            ));
+
+            let model_attributes = self.model_attributes(run);
+            for (token_type, count) in [
+                ("input", tokens.prompt_tokens),
+                ("output", tokens.completion_tokens),
+            ] {
+                let mut attributes = model_attributes.clone();
+                attributes.push(KeyValue::new("gen_ai.token.type", token_type));
+                self.token_usage.record(count as u64, &attributes);
+            }
+        }
+        self.record_duration(run, false);
+        self.end_span(run, attributes, None);
+    }
+
+    fn on_tool_start(&self, run: &RunInfo, _input: &str) {
+        self.start_span(
+            run,
+            vec![
+                KeyValue::new("gen_ai.operation.name", "execute_tool"),
+                KeyValue::new("gen_ai.tool.name", run.name.clone()),
+            ],
+        );
+    }
+
+    fn on_tool_end(&self, run: &RunInfo, _output: &str) {
+        self.end_span(run, vec![], None);
+    }
+
+    fn on_retriever_start(&self, run: &RunInfo, _query: &str) {
+        self.start_span(run, vec![]);
+    }
+
+    fn on_retriever_end(&self, run: &RunInfo, documents: &[Document]) {
+        self.end_span(
+            run,
+            vec![KeyValue::new(
+                "langchain.retriever.documents",
+                documents.len() as i64,
+            )],
+            None,
+        );
+    }
+
+    fn on_error(&self, run: &RunInfo, error: &str) {
+        if run.run_type == RunType::Llm {
+            self.record_duration(run, true);
+        }
+        self.end_span(run, vec![], Some(error));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::{testing::trace::InMemorySpanExporter, trace::TracerProvider};
+
+    use super::*;
+    use crate::{callbacks::CallbackManager, language_models::TokenUsage};
+
+    #[tokio::test]
+    async fn test_opentelemetry_spans() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let handler = OpenTelemetryHandler::new()
+            .with_tracer(provider.tracer("test"))
+            .with_system("openai");
+
+        let chain = CallbackManager::new()
+            .with_handler(handler)
+            .start_run("LLMChain", RunType::Chain);
+        chain
+            .trace_chain(&PromptArgs::new(), async {
+                CallbackManager::new()
+                    .start_run("gpt-4", RunType::Llm)
+                    .trace_llm(&[], async {
+                        Ok(GenerateResult {
+                            tokens: Some(TokenUsage::new(3, 5)),
+                            ..Default::default()
+                        })
+                    })
+                    .await?;
+                Ok(GenerateResult::default())
+            })
+            .await
+            .unwrap();
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 2);
+        let (llm, chain) = (&spans[0], &spans[1]);
+        assert_eq!(llm.name, "chat gpt-4");
+        assert_eq!(chain.name, "LLMChain");
+        assert_eq!(llm.parent_span_id, chain.span_context.span_id());
+        assert!(llm
+            .attributes
+            .contains(&KeyValue::new("gen_ai.usage.output_tokens", 5)));
+        assert!(llm
+            .attributes
+            .contains(&KeyValue::new("gen_ai.request.model", "gpt-4")));
+    }
+}
@@ -0,0 +1,129 @@
+use tracing::{field, Span};
+
+use crate::{language_models::GenerateResult, runtime};
+
+use super::RunInfo;
+
+/// Creates the span of a run. Spans are created while the parent run is executing, so
+/// subscribers receive the same call tree as the callback handlers.
+pub(crate) fn run_span(run: &RunInfo) -> Span {
+    tracing::info_span!(
+        "langchain_run",
+        otel.name = %run.name,
+        run_name = %run.name,
+        run_type = ?run.run_type,
+        run_id = %run.run_id,
+        parent_run_id = run.parent_run_id.as_deref().unwrap_or(""),
+        prompt_tokens = field::Empty,
+        completion_tokens = field::Empty,
+        total_tokens = field::Empty,
+        latency_ms = field::Empty,
+        error = field::Empty,
+    )
+}
+
+fn latency_ms(run: &RunInfo) -> u128 {
+    runtime::now()
+        .duration_since(run.start_time)
+        .map(|latency| latency.as_millis())
+        .unwrap_or_default()
+}
+
+/// Records the latency and the token usage of a successful run.
+pub(crate) fn record_end(span: &Span, run: &RunInfo, result: Option<&GenerateResult>) {
+    let latency = latency_ms(run);
+    span.record("latency_ms", latency as u64);
+    if let Some(tokens) = result.and_then(|result| result.tokens.as_ref()) {
+        span.record("prompt_tokens", tokens.prompt_tokens);
+        span.record("completion_tokens", tokens.completion_tokens);
+        span.record("total_tokens", tokens.total_tokens);
+    }
+    tracing::debug!(parent: span, latency_ms = latency as u64, "{} run finished", run.name);
+}
+
+pub(crate) fn record_error(span: &Span, run: &RunInfo, error: &str) {
+    let latency = latency_ms(run);
+    span.record("latency_ms", latency as u64);
+    span.record("error", error);
+    tracing::error!(parent: span, latency_ms = latency as u64, error, "{} run failed", run.name);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::{
+        field::{self, Visit},
+        span,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+    use crate::{
+        callbacks::{CallbackManager, RunType},
+        language_models::GenerateResult,
+        prompt::PromptArgs,
+    };
+
+    struct RunName(String);
+
+    type SpanTree = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+    /// Records `(run_name, parent run_name)` of every span.
+    #[derive(Clone, Default)]
+    struct TreeLayer(SpanTree);
+
+    impl<S: tracing::Subscriber + for<'a> LookupSpan<'a>> Layer<S> for TreeLayer {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+            struct Visitor(Option<String>);
+            impl Visit for Visitor {
+                fn record_str(&mut self, field: &field::Field, value: &str) {
+                    if field.name() == "run_name" {
+                        self.0 = Some(value.to_string());
+                    }
+                }
+                fn record_debug(&mut self, field: &field::Field, value: &dyn std::fmt::Debug) {
+                    if field.name() == "run_name" {
+                        self.0 = Some(format!("{:?}", value));
+                    }
+                }
+            }
+            let mut visitor = Visitor(None);
+            attrs.record(&mut visitor);
+            let name = visitor.0.unwrap_or_default();
+
+            let span = ctx.span(id).unwrap();
+            let parent = span
+                .parent()
+                .and_then(|parent| parent.extensions().get::<RunName>().map(|n| n.0.clone()));
+            span.extensions_mut().insert(RunName(name.clone()));
+            self.0.lock().unwrap().push((name, parent));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spans_follow_the_runs() {
+        let layer = TreeLayer::default();
+        let _guard = tracing_subscriber::registry()
+            .with(layer.clone())
+            .set_default();
+
+        let chain = CallbackManager::new().start_run("chain", RunType::Chain);
+        chain
+            .trace_chain(&PromptArgs::new(), async {
+                let llm = CallbackManager::new().start_run("gpt-4", RunType::Llm);
+                llm.trace_llm(&[], async { Ok(GenerateResult::default()) })
+                    .await?;
+                Ok(GenerateResult::default())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *layer.0.lock().unwrap(),
+            vec![
+                ("chain".to_string(), None),
+                ("gpt-4".to_string(), Some("chain".to_string()))
+            ]
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde_json::Value;
+
+use crate::{
+    agent::Plan,
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    schemas::{Document, Message},
+};
+
+use super::{CallbackHandler, EventHandler, RunEvent, RunInfo};
+
+type SharedWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+fn write_to(writer: &SharedWriter, text: &str) {
+    let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = writer.write_all(text.as_bytes());
+    let _ = writer.flush();
+}
+
+/// Pretty-prints the prompts, completions and tool calls of the runs, indented by their
+/// depth in the call tree. This is the verbose mode of the chains.
+///
+/// # Example
+/// ```rust,ignore
+/// let chain = LLMChainBuilder::new()
+///     .prompt(prompt)
+///     .llm(llm)
+///     .options(ChainCallOptions::new().with_verbose(true))
+///     .build()?;
+/// ```
+pub struct StdOutCallbackHandler {
+    writer: SharedWriter,
+    depths: Mutex<HashMap<String, usize>>,
+}
+
+impl StdOutCallbackHandler {
+    pub fn new() -> Self {
+        Self::with_writer(io::stdout())
+    }
+
+    /// Prints to `writer` instead of the standard output.
+    pub fn with_writer<W: Write + Send + 'static>(writer: W) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+            depths: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn depths(&self) -> std::sync::MutexGuard<'_, HashMap<String, usize>> {
+        self.depths.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn start(&self, run: &RunInfo) -> String {
+        let mut depths = self.depths();
+        let depth = run
+            .parent_run_id
+            .as_ref()
+            .and_then(|parent| depths.get(parent))
+            .map(|depth| depth + 1)
+            .unwrap_or_default();
+        depths.insert(run.run_id.clone(), depth);
+        "  ".repeat(depth)
+    }
+
+    fn indent(&self, run: &RunInfo) -> String {
+        "  ".repeat(self.depths().get(&run.run_id).copied().unwrap_or_default())
+    }
+
+    fn end(&self, run: &RunInfo) -> String {
+        let indent = self.indent(run);
+        self.depths().remove(&run.run_id);
+        indent
+    }
+
+    fn print(&self, indent: &str, text: &str) {
+        let mut output = String::new();
+        for line in text.lines() {
+            output.push_str(indent);
+            output.push_str(line);
+            output.push('\n');
+        }
+        write_to(&self.writer, &output);
+    }
+}
+
+impl Default for StdOutCallbackHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallbackHandler for StdOutCallbackHandler {
+    fn on_chain_start(&self, run: &RunInfo, inputs: &PromptArgs) {
+        let indent = self.start(run);
+        let mut keys: Vec<_> = inputs.keys().collect();
+        keys.sort();
+        let mut text = format!("> Entering new {} chain...", run.name);
+        for key in keys {
+            let value = match &inputs[key] {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            text.push_str(&format!("\n  {}: {}", key, value));
+        }
+        self.print(&indent, &text);
+    }
+
+    fn on_chain_end(&self, run: &RunInfo, output: &GenerateResult) {
+        let indent = self.end(run);
+        self.print(
+            &indent,
+            &format!("> Finished {} chain: {}", run.name, output.generation),
+        );
+    }
+
+    fn on_llm_start(&self, run: &RunInfo, messages: &[Message]) {
+        let indent = self.start(run);
+        let mut text = format!("> Prompt to {}:", run.name);
+        for message in messages {
+            text.push_str(&format!(
+                "\n  {:?}: {}",
+                message.message_type, message.content
+            ));
+        }
+        self.print(&indent, &text);
+    }
+
+    fn on_llm_end(&self, run: &RunInfo, result: &GenerateResult) {
+        let indent = self.end(run);
+        let mut text = format!("> Completion: {}", result.generation);
+        if let Some(tokens) = &result.tokens {
+            text.push_str(&format!(
+                "\n  (prompt tokens: {}, completion tokens: {})",
+                tokens.prompt_tokens, tokens.completion_tokens
+            ));
+        }
+        self.print(&indent, &text);
+    }
+
+    fn on_tool_start(&self, run: &RunInfo, input: &str) {
+        let indent = self.start(run);
+        self.print(&indent, &format!("> Tool {}: {}", run.name, input));
+    }
+
+    fn on_tool_end(&self, run: &RunInfo, output: &str) {
+        let indent = self.end(run);
+        self.print(&indent, &format!("> Observation: {}", output));
+    }
+
+    fn on_retriever_start(&self, run: &RunInfo, query: &str) {
+        let indent = self.start(run);
+        self.print(
+            &indent,
+            &format!("> Retrieving with {}: {}", run.name, query),
+        );
+    }
+
+    fn on_retriever_end(&self, run: &RunInfo, documents: &[Document]) {
+        let indent = self.end(run);
+        self.print(
+            &indent,
+            &format!("> Retrieved {} documents", documents.len()),
+        );
+    }
+
+    fn on_plan(&self, run: &RunInfo, plan: &Plan) {
+        let indent = format!("{}  ", self.indent(run));
+        let mut text = "> Plan:".to_string();
+        for (i, step) in plan.steps.iter().enumerate() {
+            let status = if step.result.is_some() { "x" } else { " " };
+            text.push_str(&format!("\n  [{}] {}. {}", status, i + 1, step.step));
+        }
+        self.print(&indent, &text);
+    }
+
+    fn on_error(&self, run: &RunInfo, error: &str) {
+        let indent = self.end(run);
+        self.print(&indent, &format!("> Error in {}: {}", run.name, error));
+    }
+}
+
+/// Writes every event of the runs as a line of json (see `RunEvent`), e.g. to keep a log
+/// of the runs that can be processed later.
+pub struct JsonLinesCallbackHandler {
+    events: EventHandler<Box<dyn Fn(RunEvent) + Send + Sync>>,
+}
+
+impl JsonLinesCallbackHandler {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        let writer: SharedWriter = Arc::new(Mutex::new(Box::new(writer)));
+        Self {
+            events: EventHandler::new(Box::new(move |event| {
+                if let Ok(mut line) = serde_json::to_string(&event) {
+                    line.push('\n');
+                    write_to(&writer, &line);
+                }
+            })),
+        }
+    }
+
+    /// Appends the events to the file at `path`, creating it if needed.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(file))
+    }
+}
+
+impl CallbackHandler for JsonLinesCallbackHandler {
+    fn on_chain_start(&self, run: &RunInfo, inputs: &PromptArgs) {
+        self.events.on_chain_start(run, inputs)
+    }
+
+    fn on_chain_end(&self, run: &RunInfo, output: &GenerateResult) {
+        self.events.on_chain_end(run, output)
+    }
+
+    fn on_llm_start(&self, run: &RunInfo, messages: &[Message]) {
+        self.events.on_llm_start(run, messages)
+    }
+
+    fn on_llm_new_token(&self, run: &RunInfo, token: &str) {
+        self.events.on_llm_new_token(run, token)
+    }
+
+    fn on_llm_end(&self, run: &RunInfo, result: &GenerateResult) {
+        self.events.on_llm_end(run, result)
+    }
+
+    fn on_tool_start(&self, run: &RunInfo, input: &str) {
+        self.events.on_tool_start(run, input)
+    }
+
+    fn on_tool_end(&self, run: &RunInfo, output: &str) {
+        self.events.on_tool_end(run, output)
+    }
+
+    fn on_retriever_start(&self, run: &RunInfo, query: &str) {
+        self.events.on_retriever_start(run, query)
+    }
+
+    fn on_retriever_end(&self, run: &RunInfo, documents: &[Document]) {
+        self.events.on_retriever_end(run, documents)
+    }
+
+    fn on_error(&self, run: &RunInfo, error: &str) {
+        self.events.on_error(run, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        callbacks::{CallbackManager, RunType},
+        prompt_args,
+    };
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    async fn run_chain(callbacks: CallbackManager) {
+        let chain = callbacks.start_run("LLMChain", RunType::Chain);
+        chain
+            .trace_chain(&prompt_args! {"input" => "Hi"}, async {
+                CallbackManager::new()
+                    .start_run("gpt-4", RunType::Llm)
+                    .trace_llm(&[Message::new_human_message("Hi")], async {
+                        Ok(GenerateResult {
+                            generation: "Hello".to_string(),
+                            ..Default::default()
+                        })
+                    })
+                    .await?;
+                Ok(GenerateResult {
+                    generation: "Hello".to_string(),
+                    ..Default::default()
+                })
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stdout_handler() {
+        let buffer = Buffer::default();
+        run_chain(
+            CallbackManager::new().with_handler(StdOutCallbackHandler::with_writer(buffer.clone())),
+        )
+        .await;
+
+        assert_eq!(
+            buffer.text(),
+            "> Entering new LLMChain chain...\n  input: Hi\n  > Prompt to gpt-4:\n    HumanMessage: Hi\n  > Completion: Hello\n> Finished LLMChain chain: Hello\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_lines_handler() {
+        let buffer = Buffer::default();
+        run_chain(
+            CallbackManager::new().with_handler(JsonLinesCallbackHandler::new(buffer.clone())),
+        )
+        .await;
+
+        let events: Vec<Value> = buffer
+            .text()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let names: Vec<_> = events
+            .iter()
+            .map(|e| e["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["chain_start", "llm_start", "llm_end", "chain_end"]
+        );
+        assert_eq!(
+            events[1]["run"]["parent_run_id"],
+            events[0]["run"]["run_id"]
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::language_models::{GenerateResult, TokenUsage};
+
+use super::{CallbackHandler, RunInfo};
+
+/// Price of a model in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    pub fn new(input: f64, output: f64) -> Self {
+        Self { input, output }
+    }
+
+    pub fn cost(&self, tokens: &TokenUsage) -> f64 {
+        (tokens.prompt_tokens as f64 * self.input + tokens.completion_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+// Published prices at the time of writing, override them with `with_price`.
+const DEFAULT_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-3.5-turbo", 0.5, 1.5),
+    ("gpt-4", 30.0, 60.0),
+    ("gpt-4-turbo", 10.0, 30.0),
+    ("gpt-4-turbo-preview", 10.0, 30.0),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-opus", 15.0, 75.0),
+    ("text-embedding-3-small", 0.02, 0.0),
+    ("text-embedding-3-large", 0.13, 0.0),
+    ("text-embedding-ada-002", 0.1, 0.0),
+];
+
+/// Usage of a single model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+    pub requests: u32,
+    /// `None` when the price of the model is unknown.
+    pub cost: Option<f64>,
+}
+
+/// Usage aggregated by a `UsageTracker`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+    pub successful_requests: u32,
+    /// Cost in USD of the requests to the models with a known price.
+    pub total_cost: f64,
+    pub models: HashMap<String, ModelUsage>,
+}
+
+impl UsageSummary {
+    /// The tokens of every model.
+    pub fn token_usage(&self) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            total_tokens: self.total_tokens,
+        }
+    }
+}
+
+/// Aggregates the token usage and the cost of every LLM and embedding call of the runs it
+/// is attached to, including the calls of nested chains, agents and retrievers.
+///
+/// # Example
+/// ```rust,ignore
+/// let usage = UsageTracker::new();
+/// let callbacks = CallbackManager::new().with_handler(usage.clone());
+/// chain.call_with_callbacks(input_variables, callbacks).await?;
+///
+/// let summary = usage.summary();
+/// println!("{} tokens, ${:.4}", summary.total_tokens, summary.total_cost);
+/// ```
+///
+/// To get the usage of a single call, see `Chain::call_with_usage`.
+#[derive(Clone)]
+pub struct UsageTracker {
+    prices: HashMap<String, ModelPrice>,
+    summary: Arc<Mutex<UsageSummary>>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self {
+            prices: DEFAULT_PRICES
+                .iter()
+                .map(|(model, input, output)| (model.to_string(), ModelPrice::new(*input, *output)))
+                .collect(),
+            summary: Arc::new(Mutex::new(UsageSummary::default())),
+        }
+    }
+
+    /// Sets the price of a model. Models with a version suffix (e.g. `gpt-4o-2024-08-06`)
+    /// use the price of the longest model name they start with.
+    pub fn with_price<S: Into<String>>(mut self, model: S, price: ModelPrice) -> Self {
+        self.prices.insert(model.into(), price);
+        self
+    }
+
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        self.prices.get(model).copied().or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, price)| *price)
+        })
+    }
+
+    /// A tracker with the same prices and an empty summary, e.g. to track the usage of
+    /// each call separately.
+    pub fn fork(&self) -> Self {
+        Self {
+            prices: self.prices.clone(),
+            summary: Arc::new(Mutex::new(UsageSummary::default())),
+        }
+    }
+
+    pub fn summary(&self) -> UsageSummary {
+        self.lock().clone()
+    }
+
+    pub fn reset(&self) {
+        *self.lock() = UsageSummary::default();
+    }
+
+    /// Adds the usage of a call to `model`.
+    pub fn record(&self, model: &str, tokens: &TokenUsage) {
+        let cost = self.price(model).map(|price| price.cost(tokens));
+
+        let mut summary = self.lock();
+        summary.prompt_tokens += tokens.prompt_tokens;
+        summary.completion_tokens += tokens.completion_tokens;
+        summary.total_tokens += tokens.total_tokens;
+        summary.successful_requests += 1;
+        summary.total_cost += cost.unwrap_or_default();
+
+        let usage = summary.models.entry(model.to_string()).or_default();
+        usage.prompt_tokens += tokens.prompt_tokens;
+        usage.completion_tokens += tokens.completion_tokens;
+        usage.total_tokens += tokens.total_tokens;
+        usage.requests += 1;
+        if let Some(cost) = cost {
+            usage.cost = Some(usage.cost.unwrap_or_default() + cost);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, UsageSummary> {
+        self.summary.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallbackHandler for UsageTracker {
+    fn on_llm_end(&self, run: &RunInfo, result: &GenerateResult) {
+        if let Some(tokens) = &result.tokens {
+            self.record(&run.name, tokens);
+        }
+    }
+
+    fn on_embedding_end(&self, run: &RunInfo, tokens: Option<&TokenUsage>) {
+        if let Some(tokens) = tokens {
+            self.record(&run.name, tokens);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{
+        callbacks::{CallbackManager, RunType},
+        chain::{Chain, ChainError},
+        prompt::PromptArgs,
+    };
+
+    #[tokio::test]
+    async fn test_usage_tracker() {
+        let usage = UsageTracker::new().with_price("my-model", ModelPrice::new(1.0, 2.0));
+        let callbacks = CallbackManager::new().with_handler(usage.clone());
+
+        for model in ["my-model", "my-model", "gpt-4o-2024-08-06", "unknown"] {
+            callbacks
+                .start_run(model, RunType::Llm)
+                .trace_llm(&[], async {
+                    Ok(GenerateResult {
+                        tokens: Some(TokenUsage::new(1_000, 500)),
+                        ..Default::default()
+                    })
+                })
+                .await
+                .unwrap();
+        }
+
+        let summary = usage.summary();
+        assert_eq!(summary.successful_requests, 4);
+        assert_eq!(summary.total_tokens, 6_000);
+        assert_eq!(summary.models["my-model"].requests, 2);
+        assert_eq!(summary.models["my-model"].cost, Some(0.004));
+        // Priced as gpt-4o.
+        assert_eq!(summary.models["gpt-4o-2024-08-06"].cost, Some(0.0075));
+        assert_eq!(summary.models["unknown"].cost, None);
+        assert!((summary.total_cost - 0.0115).abs() < 1e-9);
+
+        usage.reset();
+        assert_eq!(usage.summary(), UsageSummary::default());
+    }
+
+    #[tokio::test]
+    async fn test_usage_tracker_embeddings() {
+        let usage = UsageTracker::new();
+        CallbackManager::new()
+            .with_handler(usage.clone())
+            .start_run("text-embedding-3-small", RunType::Embedding)
+            .trace_embedding(async { Ok((vec![vec![0.0]], Some(TokenUsage::new(1_000, 0)))) })
+            .await
+            .unwrap();
+
+        let summary = usage.summary();
+        assert_eq!(summary.successful_requests, 1);
+        assert_eq!(summary.prompt_tokens, 1_000);
+        assert_eq!(summary.models["text-embedding-3-small"].cost, Some(0.00002));
+    }
+
+    /// Calls two models, as an agent would.
+    struct TwoModelsChain;
+
+    #[async_trait]
+    impl Chain for TwoModelsChain {
+        async fn call(&self, _input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            let mut result = GenerateResult::default();
+            for (model, tokens) in [("my-model", 100), ("other-model", 10)] {
+                result = CallbackManager::new()
+                    .start_run(model, RunType::Llm)
+                    .trace_llm(&[], async {
+                        Ok(GenerateResult {
+                            generation: model.to_string(),
+                            tokens: Some(TokenUsage::new(tokens, tokens)),
+                            ..Default::default()
+                        })
+                    })
+                    .await?;
+            }
+            // Like the agents, only the tokens of the last call are in the result.
+            Ok(result)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_with_usage() {
+        let usage = UsageTracker::new().with_price("my-model", ModelPrice::new(1.0, 2.0));
+        let (result, summary) = TwoModelsChain
+            .call_with_usage(PromptArgs::new(), &usage)
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "other-model");
+        let tokens = result.tokens.unwrap();
+        assert_eq!(tokens.prompt_tokens, 110);
+        assert_eq!(tokens.total_tokens, 220);
+        assert_eq!(summary.models.len(), 2);
+        assert_eq!(summary.models["my-model"].cost, Some(0.0003));
+        assert_eq!(summary.models["other-model"].cost, None);
+
+        // Each call has its own summary.
+        let (_, summary) = TwoModelsChain
+            .call_with_usage(PromptArgs::new(), &usage)
+            .await
+            .unwrap();
+        assert_eq!(summary.successful_requests, 2);
+        assert_eq!(usage.summary(), UsageSummary::default());
+    }
+}
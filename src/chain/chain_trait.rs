@@ -1,15 +1,23 @@
 use std::{collections::HashMap, pin::Pin};
 
 use async_trait::async_trait;
-use futures::Stream;
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
 
-use crate::{language_models::GenerateResult, prompt::PromptArgs, schemas::StreamData};
+use crate::{
+    callbacks::{stream_events, CallbackManager, RunConfig, RunEvent, UsageSummary, UsageTracker},
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    schemas::StreamData,
+};
 
 use super::ChainError;
 
 pub(crate) const DEFAULT_OUTPUT_KEY: &str = "output";
 pub(crate) const DEFAULT_RESULT_KEY: &str = "generate_result";
+/// The number of calls `Chain::call_batch` runs at the same time.
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
 
 #[async_trait]
 pub trait Chain: Sync + Send {
@@ -84,6 +92,9 @@ pub trait Chain: Sync + Send {
     /// The key for the generated output is specified by the `get_output_keys`
     /// method (default key is `output`).
     ///
+    /// The chains with several outputs return all of them, e.g. the `source_documents` of
+    /// a `RetrievalQAChain` next to its answer, see `get_output`.
+    ///
     /// # Example
     ///
     /// ```rust,ignore
@@ -126,6 +137,120 @@ pub trait Chain: Sync + Send {
         output.insert(DEFAULT_RESULT_KEY.to_string(), json!(result));
         Ok(output)
     }
+    /// Call the `Chain` notifying `callbacks` of its run and of every chain, llm, tool and
+    /// retriever run nested in it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let callbacks = CallbackManager::new().with_handler(PrintTokens);
+    /// let result = chain.call_with_callbacks(input_variables, callbacks).await?;
+    /// ```
+    async fn call_with_callbacks(
+        &self,
+        input_variables: PromptArgs,
+        callbacks: CallbackManager,
+    ) -> Result<GenerateResult, ChainError> {
+        callbacks.scope(self.call(input_variables)).await
+    }
+
+    /// Call the `Chain` tracking the token usage and the cost of every LLM call of its run,
+    /// nested chains and agents included, with the prices of `usage`. The `tokens` of the
+    /// result are the total of the run, and the summary has the cost per model.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let (result, summary) = agent_executor
+    ///     .call_with_usage(input_variables, &UsageTracker::default())
+    ///     .await?;
+    /// bill(customer, summary.total_cost);
+    /// ```
+    async fn call_with_usage(
+        &self,
+        input_variables: PromptArgs,
+        usage: &UsageTracker,
+    ) -> Result<(GenerateResult, UsageSummary), ChainError> {
+        let tracker = usage.fork();
+        let callbacks = CallbackManager::new().with_handler(tracker.clone());
+        let mut result = callbacks.scope(self.call(input_variables)).await?;
+        let summary = tracker.summary();
+        if summary.successful_requests > 0 {
+            result.tokens = Some(summary.token_usage());
+        }
+        Ok((result, summary))
+    }
+
+    /// Call the `Chain` with a `RunConfig`: its run id, tags and metadata are surfaced to the
+    /// callbacks and to the providers of every run nested in it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let config = RunConfig::new().with_run_id(request_id).with_user(user_id);
+    /// let result = chain.call_with_config(input_variables, config).await?;
+    /// ```
+    async fn call_with_config(
+        &self,
+        input_variables: PromptArgs,
+        config: RunConfig,
+    ) -> Result<GenerateResult, ChainError> {
+        config.scope(self.call(input_variables)).await
+    }
+
+    /// Call the `Chain` on every input, running up to `max_concurrency` calls at the same
+    /// time. The results are in the order of the inputs, a failed call doesn't stop the
+    /// others.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let inputs = questions
+    ///     .iter()
+    ///     .map(|question| prompt_args! {"input" => question})
+    ///     .collect();
+    /// for result in chain.batch(inputs, 8).await {
+    ///     println!("{:?}", result.map(|result| result.generation));
+    /// }
+    /// ```
+    async fn batch(
+        &self,
+        inputs: Vec<PromptArgs>,
+        max_concurrency: usize,
+    ) -> Vec<Result<GenerateResult, ChainError>> {
+        futures::stream::iter(inputs)
+            .map(|input_variables| self.call(input_variables))
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Same as `batch`, with `DEFAULT_BATCH_CONCURRENCY` calls at the same time. Use `batch`
+    /// to choose the concurrency.
+    async fn call_batch(&self, inputs: Vec<PromptArgs>) -> Vec<Result<GenerateResult, ChainError>> {
+        self.batch(inputs, DEFAULT_BATCH_CONCURRENCY).await
+    }
+
+    /// Call the `Chain` and get a stream of the events of its run and of every chain, llm,
+    /// tool and retriever run nested in it, ending with `RunEvent::Output`. Every event
+    /// carries its run, so a frontend can rebuild the call tree.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut events = agent_executor.stream_events(input_variables);
+    /// while let Some(event) = events.next().await {
+    ///     let event = event?;
+    ///     println!("{}", serde_json::to_string(&event)?);
+    /// }
+    /// ```
+    fn stream_events<'a>(
+        &'a self,
+        input_variables: PromptArgs,
+    ) -> Pin<Box<dyn Stream<Item = Result<RunEvent, ChainError>> + Send + 'a>> {
+        stream_events(self.call(input_variables))
+    }
+
     /// Stream the `Chain` and get an asynchronous stream of chain generations.
     /// The input is a set of variables passed as a `PromptArgs` hashmap.
     /// If the chain have memroy, the tream method will not be able to automaticaly
@@ -186,6 +311,7 @@ pub trait Chain: Sync + Send {
         return vec![];
     }
 
+    /// The keys of the outputs of `execute`, the first one is the key of the generation.
     fn get_output_keys(&self) -> Vec<String> {
         log::info!("Using defualt implementation");
         return vec![
@@ -195,6 +321,26 @@ pub trait Chain: Sync + Send {
     }
 }
 
+/// The output `key` of the outputs of `Chain::execute`, deserialized.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let output = retrieval_qa.execute(prompt_args! {"question" => question}).await?;
+/// let answer: String = get_output(&output, "output")?;
+/// let documents: Vec<Document> = get_output(&output, "source_documents")?;
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn get_output<T: DeserializeOwned>(
+    output: &HashMap<String, Value>,
+    key: &str,
+) -> Result<T, ChainError> {
+    let value = output
+        .get(key)
+        .ok_or_else(|| ChainError::OtherError(format!("Missing output key: {}", key)))?;
+    Ok(serde_json::from_value(value.clone())?)
+}
+
 impl<C> From<C> for Box<dyn Chain>
 where
     C: Chain + 'static,
@@ -203,3 +349,61 @@ where
         Box::new(chain)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::prompt_args;
+
+    #[derive(Default)]
+    struct SlowChain {
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Chain for SlowChain {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            let input = input_variables["input"].as_u64().unwrap();
+            tokio::time::sleep(Duration::from_millis(10 * (input % 3))).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            if input == 5 {
+                return Err(ChainError::OtherError("five".to_string()));
+            }
+            Ok(GenerateResult {
+                generation: input.to_string(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_batch() {
+        let chain = SlowChain::default();
+        let inputs = (0..10).map(|i| prompt_args! {"input" => i}).collect();
+        let results = chain.call_batch(inputs).await;
+
+        assert_eq!(results.len(), 10);
+        for (i, result) in results.iter().enumerate() {
+            match result {
+                Ok(result) => assert_eq!(result.generation, i.to_string()),
+                Err(error) => {
+                    assert_eq!(i, 5);
+                    assert!(matches!(error, ChainError::OtherError(_)));
+                }
+            }
+        }
+        assert!(results[5].is_err());
+        assert_eq!(
+            chain.max_running.load(Ordering::SeqCst),
+            DEFAULT_BATCH_CONCURRENCY
+        );
+    }
+}
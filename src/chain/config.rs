@@ -0,0 +1,211 @@
+// The configs fail with the errors of the chain builders.
+#![allow(clippy::result_large_err)]
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{language_models::llm::LLM, prompt::PromptConfig};
+
+use super::{
+    options::ChainCallOptions, Chain, ChainError, LLMChainBuilder, SequentialChainBuilder,
+};
+
+/// An `LLMChain` defined in a config file.
+#[derive(Serialize, Deserialize)]
+pub struct LLMChainConfig {
+    pub prompt: PromptConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<ChainCallOptions>,
+}
+
+/// A `SequentialChain` defined in a config file.
+#[derive(Serialize, Deserialize)]
+pub struct SequentialChainConfig {
+    pub chains: Vec<ChainConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_keys: Option<Vec<String>>,
+}
+
+/// A chain defined in a config file, so that its prompts can be edited without
+/// recompiling, e.g. in yaml:
+///
+/// ```yaml
+/// type: sequential_chain
+/// chains:
+///   - type: llm_chain
+///     output_key: synopsis
+///     prompt:
+///       type: template
+///       template: "Write the synopsis of a play titled {title}"
+///       input_variables: [title]
+///   - type: llm_chain
+///     options:
+///       temperature: 0.2
+///     prompt:
+///       type: template
+///       template: "Write a review of this synopsis: {synopsis}"
+///       input_variables: [synopsis]
+/// ```
+///
+/// The LLM is given when the chain is built, every `llm_chain` getting a copy of it.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChainConfig {
+    LlmChain(Box<LLMChainConfig>),
+    SequentialChain(SequentialChainConfig),
+}
+
+impl ChainConfig {
+    pub fn from_json(json: &str) -> Result<Self, ChainError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, ChainError> {
+        serde_yaml::from_str(yaml).map_err(|e| ChainError::OtherError(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<String, ChainError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> Result<String, ChainError> {
+        serde_yaml::to_string(self).map_err(|e| ChainError::OtherError(e.to_string()))
+    }
+
+    /// Builds the chain, with copies of `llm`.
+    pub fn build(self, llm: &dyn LLM) -> Result<Box<dyn Chain>, ChainError> {
+        match self {
+            ChainConfig::LlmChain(config) => {
+                let mut builder = LLMChainBuilder::new()
+                    .prompt(config.prompt.into_prompt())
+                    .llm(llm.clone_box());
+                if let Some(output_key) = config.output_key {
+                    builder = builder.output_key(output_key);
+                }
+                if let Some(options) = config.options {
+                    builder = builder.options(options);
+                }
+                Ok(Box::new(builder.build()?))
+            }
+            ChainConfig::SequentialChain(config) => {
+                let mut builder = SequentialChainBuilder::new();
+                for chain in config.chains {
+                    builder = builder.add_boxed_chain(chain.build(llm)?);
+                }
+                if let Some(input_keys) = config.input_keys {
+                    builder = builder.input_keys(input_keys);
+                }
+                Ok(Box::new(builder.build()?))
+            }
+        }
+    }
+}
+
+/// Loads the chain of the config file, in json or, with the `yaml` feature, in yaml
+/// (`.yaml` or `.yml` files), see `ChainConfig`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let chain = load_chain_from_config("chains/review.yaml", &OpenAI::default())?;
+/// let review = chain.invoke(prompt_args! {"title" => "Tragedy at sunset"}).await?;
+/// ```
+pub fn load_chain_from_config<P: AsRef<Path>>(
+    path: P,
+    llm: &dyn LLM,
+) -> Result<Box<dyn Chain>, ChainError> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| ChainError::OtherError(format!("{}: {}", path.display(), e)))?;
+    let config = match path.extension().and_then(|extension| extension.to_str()) {
+        #[cfg(feature = "yaml")]
+        Some("yaml" | "yml") => ChainConfig::from_yaml(&content)?,
+        #[cfg(not(feature = "yaml"))]
+        Some("yaml" | "yml") => {
+            return Err(ChainError::OtherError(
+                "Loading yaml configs requires the yaml feature".into(),
+            ))
+        }
+        _ => ChainConfig::from_json(&content)?,
+    };
+    config.build(llm)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{llm::FakeLLM, prompt_args};
+
+    use super::*;
+
+    fn config_json() -> serde_json::Value {
+        json!({
+            "type": "sequential_chain",
+            "chains": [
+                {
+                    "type": "llm_chain",
+                    "output_key": "synopsis",
+                    "prompt": {
+                        "type": "template",
+                        "template": "synopsis of {title}",
+                        "input_variables": ["title"],
+                        "template_format": "f-string"
+                    }
+                },
+                {
+                    "type": "llm_chain",
+                    "options": {
+                        "temperature": 0.5,
+                        "timeout_ms": 1000,
+                        "response_format": {"type": "text"}
+                    },
+                    "prompt": {
+                        "type": "template",
+                        "template": "review of {{ synopsis }}",
+                        "input_variables": ["synopsis"],
+                        "template_format": "jinja2"
+                    }
+                }
+            ]
+        })
+    }
+
+    #[tokio::test]
+    async fn test_load_chain_from_config() {
+        let path = std::env::temp_dir().join(format!("chain-config-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, config_json().to_string()).unwrap();
+
+        let chain = load_chain_from_config(&path, &FakeLLM::echo()).unwrap();
+        assert_eq!(chain.get_input_keys(), vec!["title"]);
+        let output = chain
+            .invoke(prompt_args! {"title" => "Hamlet"})
+            .await
+            .unwrap();
+        assert_eq!(output, "review of synopsis of Hamlet");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_chain_config_round_trip() {
+        let config = ChainConfig::from_json(&config_json().to_string()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&config.to_json().unwrap()).unwrap();
+        assert_eq!(json, config_json());
+
+        assert!(ChainConfig::from_json(r#"{"type": "unknown_chain"}"#).is_err());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_chain_config_yaml() {
+        let config = ChainConfig::from_json(&config_json().to_string()).unwrap();
+        let yaml = config.to_yaml().unwrap();
+        let config = ChainConfig::from_yaml(&yaml).unwrap();
+        assert!(config.build(&FakeLLM::echo()).is_ok());
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use tokio::sync::Mutex;
 
@@ -8,13 +8,21 @@ use crate::{
     },
     language_models::llm::LLM,
     memory::SimpleMemory,
+    message_formatter,
     output_parsers::OutputParser,
-    prompt::{FormatPrompter, HumanMessagePromptTemplate},
+    prompt::{
+        FormatPrompter, HumanMessagePromptTemplate, MessageOrTemplate, PromptArgs, PromptTemplate,
+        SystemMessagePromptTemplate, TemplateFormat,
+    },
     schemas::memory::BaseMemory,
     template_fstring,
 };
 
-use super::{prompt::DEFAULT_TEMPLATE, ConversationalChain, DEFAULT_INPUT_VARIABLE};
+use super::{
+    prompt::DEFAULT_TEMPLATE, ConversationalChain, MemoryFactory, Sessions, DEFAULT_INPUT_VARIABLE,
+};
+
+const DEFAULT_MAX_SESSIONS: usize = 1000;
 
 pub struct ConversationalChainBuilder {
     llm: Option<Box<dyn LLM>>,
@@ -24,6 +32,10 @@ pub struct ConversationalChainBuilder {
     output_parser: Option<Box<dyn OutputParser<String>>>,
     input_key: Option<String>,
     prompt: Option<Box<dyn FormatPrompter>>,
+    persona: Option<PromptTemplate>,
+    persona_variables: PromptArgs,
+    memory_factory: Option<MemoryFactory>,
+    max_sessions: usize,
 }
 
 impl ConversationalChainBuilder {
@@ -36,6 +48,10 @@ impl ConversationalChainBuilder {
             output_parser: None,
             input_key: None,
             prompt: None,
+            persona: None,
+            persona_variables: HashMap::new(),
+            memory_factory: None,
+            max_sessions: DEFAULT_MAX_SESSIONS,
         }
     }
 
@@ -78,13 +94,59 @@ impl ConversationalChainBuilder {
         self
     }
 
+    /// The template of the system prompt. The prompt of the chain becomes the persona, the
+    /// messages of the history and the input, replacing `prompt`.
+    pub fn persona(mut self, persona: PromptTemplate) -> Self {
+        self.persona = Some(persona);
+        self
+    }
+
+    /// The variables of the prompt, the same for every call. The inputs of a call override
+    /// them.
+    pub fn persona_variables(mut self, persona_variables: PromptArgs) -> Self {
+        self.persona_variables = persona_variables;
+        self
+    }
+
+    /// Creates the memory of a new session, given its id. A `SimpleMemory` by default.
+    pub fn session_memory<F>(mut self, memory_factory: F) -> Self
+    where
+        F: Fn(&str) -> Arc<Mutex<dyn BaseMemory>> + Send + Sync + 'static,
+    {
+        self.memory_factory = Some(Arc::new(memory_factory));
+        self
+    }
+
+    /// The number of sessions whose memory is kept, the least recently used one being
+    /// dropped beyond it. Default: 1000
+    pub fn max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions;
+        self
+    }
+
     pub fn build(self) -> Result<ConversationalChain, ChainError> {
         let llm = self
             .llm
             .ok_or_else(|| ChainError::MissingObject("LLM must be set".into()))?;
-        let prompt = match self.prompt {
-            Some(prompt) => prompt,
-            None => Box::new(HumanMessagePromptTemplate::new(template_fstring!(
+        let input_key = self
+            .input_key
+            .unwrap_or_else(|| DEFAULT_INPUT_VARIABLE.to_string());
+        let history_messages = self.persona.is_some();
+        let prompt: Box<dyn FormatPrompter> = match (self.persona, self.prompt) {
+            (Some(persona), _) => Box::new(message_formatter![
+                MessageOrTemplate::Template(SystemMessagePromptTemplate::new(persona).into()),
+                MessageOrTemplate::MessagesPlaceholder("history".to_string()),
+                MessageOrTemplate::Template(
+                    HumanMessagePromptTemplate::new(PromptTemplate::new(
+                        format!("{{{{{}}}}}", input_key),
+                        vec![input_key.clone()],
+                        TemplateFormat::Jinja2,
+                    ))
+                    .into()
+                ),
+            ]),
+            (None, Some(prompt)) => prompt,
+            (None, None) => Box::new(HumanMessagePromptTemplate::new(template_fstring!(
                 DEFAULT_TEMPLATE,
                 "history",
                 "input"
@@ -114,9 +176,13 @@ impl ConversationalChainBuilder {
         Ok(ConversationalChain {
             llm: llm_chain,
             memory,
-            input_key: self
-                .input_key
-                .unwrap_or_else(|| DEFAULT_INPUT_VARIABLE.to_string()),
+            input_key,
+            persona_variables: self.persona_variables,
+            history_messages,
+            memory_factory: self
+                .memory_factory
+                .unwrap_or_else(|| Arc::new(|_| SimpleMemory::new().into())),
+            sessions: Mutex::new(Sessions::new(self.max_sessions)),
         })
     }
 }
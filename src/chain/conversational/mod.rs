@@ -4,21 +4,33 @@ use async_stream::stream;
 use async_trait::async_trait;
 use futures::Stream;
 use futures_util::{pin_mut, StreamExt};
+use serde_json::{json, Value};
 use tokio::sync::Mutex;
 
 use crate::{
+    callbacks::{CallbackManager, RunType},
     language_models::GenerateResult,
     prompt::PromptArgs,
     prompt_args,
-    schemas::{memory::BaseMemory, messages::Message, StreamData},
+    schemas::{
+        memory::{save_memory_context, turn_inputs, BaseMemory},
+        StreamData,
+    },
 };
 
 const DEFAULT_INPUT_VARIABLE: &str = "input";
 
+/// The input key of the session of a call of `ConversationalChain` as a `Chain`, the calls
+/// without it use the `memory` of the chain.
+pub const SESSION_ID_KEY: &str = "session_id";
+
 use super::{chain_trait::Chain, llm_chain::LLMChain, ChainError};
 
 pub mod builder;
 mod prompt;
+mod sessions;
+
+use sessions::{MemoryFactory, Sessions};
 
 ///This is only usefull when you dont modify the original prompt
 pub struct ConversationalChainPromptBuilder {
@@ -44,10 +56,40 @@ impl ConversationalChainPromptBuilder {
     }
 }
 
+/// A chain to chat with an LLM, the history of the conversation being added to the
+/// prompt.
+///
+/// With a persona, the system prompt, the history is given to the LLM as the messages
+/// between the persona and the input. The chain can serve many users with `chat`, each
+/// session having its own memory, created on its first message by `session_memory` of the
+/// builder. Only the `max_sessions` most recently used memories are kept, the next message
+/// of a dropped session starting a new conversation unless its memory is persisted, e.g.
+/// by a `ChatHistoryMemory` of a store. The concurrent turns of a session see the same
+/// history.
+///
+/// # Example
+/// ```rust,ignore
+/// let chain = ConversationalChainBuilder::new()
+///     .llm(OpenAI::default())
+///     .persona(template_jinja2!("You are {{name}}, a pirate.", "name"))
+///     .persona_variables(prompt_args! {"name" => "Jack"})
+///     .session_memory(|_| ConversationBufferWindowMemory::new(10).into())
+///     .build()?;
+/// let answer = chain.chat("user-1", "Hi, who are you?").await?;
+///
+/// let mut stream = chain.stream_chat("user-1", "Where is your ship?").await?;
+/// while let Some(data) = stream.next().await {
+///     print!("{}", data?.content);
+/// }
+/// ```
 pub struct ConversationalChain {
     llm: LLMChain,
     input_key: String,
     pub memory: Arc<Mutex<dyn BaseMemory>>,
+    persona_variables: PromptArgs,
+    history_messages: bool,
+    memory_factory: MemoryFactory,
+    sessions: Mutex<Sessions>,
 }
 
 //Conversational Chain is a simple chain to interact with ai as a string of messages
@@ -55,28 +97,90 @@ impl ConversationalChain {
     pub fn pompt_builder(&self) -> ConversationalChainPromptBuilder {
         ConversationalChainPromptBuilder::new()
     }
+
+    /// Answers the message in the session, saving the turn in its memory.
+    pub async fn chat(&self, session_id: &str, message: &str) -> Result<String, ChainError> {
+        self.call(prompt_args! {
+            self.input_key.clone() => message,
+            SESSION_ID_KEY => session_id,
+        })
+        .await
+        .map(|result| result.generation)
+    }
+
+    /// Streams the answer to the message in the session. The turn is saved in its memory
+    /// once the stream completes.
+    pub async fn stream_chat(
+        &self,
+        session_id: &str,
+        message: &str,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
+    {
+        self.stream(prompt_args! {
+            self.input_key.clone() => message,
+            SESSION_ID_KEY => session_id,
+        })
+        .await
+    }
+
+    /// The memory of the session, created if it has none yet.
+    pub async fn session_memory(&self, session_id: &str) -> Arc<Mutex<dyn BaseMemory>> {
+        self.sessions
+            .lock()
+            .await
+            .get_or_create(session_id, &self.memory_factory)
+    }
+
+    /// Forgets the session, its next message starts a new conversation.
+    pub async fn end_session(&self, session_id: &str) {
+        self.sessions.lock().await.remove(session_id);
+    }
+
+    /// The memory of the call, the inputs of its turn and the variables of the prompt, with
+    /// the history.
+    async fn prepare(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<(Arc<Mutex<dyn BaseMemory>>, PromptArgs, PromptArgs), ChainError> {
+        if !input_variables.contains_key(&self.input_key) {
+            return Err(ChainError::MissingInputVariable(self.input_key.clone()));
+        }
+        let mut input_variables = input_variables;
+        let memory = match input_variables.remove(SESSION_ID_KEY) {
+            Some(Value::String(session_id)) => self.session_memory(&session_id).await,
+            Some(session_id) => self.session_memory(&session_id.to_string()).await,
+            None => self.memory.clone(),
+        };
+        let turn = turn_inputs(&input_variables, &self.input_key);
+
+        let history = {
+            let memory = memory.lock().await;
+            if self.history_messages {
+                json!(memory.messages())
+            } else {
+                memory.to_string().into()
+            }
+        };
+        let mut prompt_variables = self.persona_variables.clone();
+        prompt_variables.extend(input_variables);
+        prompt_variables.insert("history".to_string(), history);
+        Ok((memory, turn, prompt_variables))
+    }
 }
 
 #[async_trait]
 impl Chain for ConversationalChain {
     async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
-        let input_variable = &input_variables
-            .get(&self.input_key)
-            .ok_or(ChainError::MissingInputVariable(self.input_key.clone()))?;
-        let human_message = Message::new_human_message(input_variable);
+        let run = CallbackManager::new().start_run("ConversationalChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain(&inputs, async move {
+            let (memory, turn, input_variables) = self.prepare(input_variables).await?;
+            let result = self.llm.call(input_variables).await?;
 
-        let history = {
-            let memory = self.memory.lock().await;
-            memory.to_string()
-        };
-        let mut input_variables = input_variables;
-        input_variables.insert("history".to_string(), history.into());
-        let result = self.llm.call(input_variables.clone()).await?;
-
-        let mut memory = self.memory.lock().await;
-        memory.add_message(human_message);
-        memory.add_message(Message::new_ai_message(&result.generation));
-        Ok(result)
+            save_memory_context(&memory, &turn, &result.generation).await;
+            Ok(result)
+        })
+        .await
     }
 
     async fn stream(
@@ -84,24 +188,11 @@ impl Chain for ConversationalChain {
         input_variables: PromptArgs,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
     {
-        let input_variable = &input_variables
-            .get(&self.input_key)
-            .ok_or(ChainError::MissingInputVariable(self.input_key.clone()))?;
-        let human_message = Message::new_human_message(input_variable);
-
-        let history = {
-            let memory = self.memory.lock().await;
-            memory.to_string()
-        };
-
-        let mut input_variables = input_variables;
-        input_variables.insert("history".to_string(), history.into());
+        let (memory, turn, input_variables) = self.prepare(input_variables).await?;
 
         let complete_ai_message = Arc::new(Mutex::new(String::new()));
         let complete_ai_message_clone = complete_ai_message.clone();
 
-        let memory = self.memory.clone();
-
         let stream = self.llm.stream(input_variables).await?;
         let output_stream = stream! {
             pin_mut!(stream);
@@ -120,9 +211,8 @@ impl Chain for ConversationalChain {
                 }
             }
 
-            let mut memory = memory.lock().await;
-            memory.add_message(human_message);
-            memory.add_message(Message::new_ai_message(&complete_ai_message.lock().await));
+            let answer = complete_ai_message.lock().await.clone();
+            save_memory_context(&memory, &turn, &answer).await;
         };
 
         Ok(Box::pin(output_stream))
@@ -137,12 +227,69 @@ impl Chain for ConversationalChain {
 mod tests {
     use crate::{
         chain::conversational::builder::ConversationalChainBuilder,
-        llm::openai::{OpenAI, OpenAIModel},
-        prompt_args,
+        llm::{
+            openai::{OpenAI, OpenAIModel},
+            FakeLLM,
+        },
+        memory::{ConversationBufferWindowMemory, ConversationSummaryMemory},
+        prompt_args, template_jinja2,
     };
 
     use super::*;
 
+    #[tokio::test]
+    async fn test_memory_summarizes_the_turns() {
+        let memory: Arc<Mutex<dyn BaseMemory>> =
+            ConversationSummaryMemory::new(FakeLLM::new("Ana said hi")).into();
+        let chain = ConversationalChainBuilder::new()
+            .llm(FakeLLM::new("Hello Ana"))
+            .memory(memory.clone())
+            .build()
+            .unwrap();
+
+        chain
+            .invoke(prompt_args! {"input" => "Hi, I'm Ana"})
+            .await
+            .unwrap();
+        assert_eq!(memory.lock().await.to_string(), "system: Ana said hi");
+    }
+
+    #[tokio::test]
+    async fn test_persona_and_sessions() {
+        let llm = FakeLLM::from_responses(vec!["Ahoy Ana", "Ahoy Bob", "Your name is Ana"]);
+        let chain = ConversationalChainBuilder::new()
+            .llm(llm.clone())
+            .persona(template_jinja2!("You are {{name}}, a pirate.", "name"))
+            .persona_variables(prompt_args! {"name" => "Jack"})
+            .session_memory(|_| ConversationBufferWindowMemory::new(10).into())
+            .build()
+            .unwrap();
+
+        assert_eq!(chain.chat("ana", "I'm Ana").await.unwrap(), "Ahoy Ana");
+        assert_eq!(chain.chat("bob", "I'm Bob").await.unwrap(), "Ahoy Bob");
+        let answer: Vec<String> = chain
+            .stream_chat("ana", "What's my name?")
+            .await
+            .unwrap()
+            .map(|data| data.unwrap().content)
+            .collect()
+            .await;
+        assert_eq!(answer.concat(), "Your name is Ana");
+
+        let messages = &llm.calls()[2];
+        assert_eq!(messages[0].content, "You are Jack, a pirate.");
+        assert_eq!(messages[1].content, "I'm Ana");
+        assert_eq!(messages[2].content, "Ahoy Ana");
+        assert_eq!(messages[3].content, "What's my name?");
+        let memory = chain.session_memory("ana").await;
+        assert_eq!(memory.lock().await.messages().len(), 4);
+        assert!(chain.memory.lock().await.messages().is_empty());
+
+        chain.end_session("ana").await;
+        let memory = chain.session_memory("ana").await;
+        assert!(memory.lock().await.messages().is_empty());
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_invoke_conversational() {
@@ -0,0 +1,87 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::Mutex;
+
+use crate::schemas::memory::BaseMemory;
+
+/// Creates the memory of a new session, given its id.
+pub(crate) type MemoryFactory = Arc<dyn Fn(&str) -> Arc<Mutex<dyn BaseMemory>> + Send + Sync>;
+
+/// The memories of the sessions of a chain. Beyond `max_sessions`, the memory of the least
+/// recently used session is dropped.
+pub(crate) struct Sessions {
+    memories: HashMap<String, (Arc<Mutex<dyn BaseMemory>>, u64)>,
+    max_sessions: usize,
+    uses: u64,
+}
+
+impl Sessions {
+    pub(crate) fn new(max_sessions: usize) -> Self {
+        Self {
+            memories: HashMap::new(),
+            max_sessions: max_sessions.max(1),
+            uses: 0,
+        }
+    }
+
+    /// The memory of the session, created with the factory if it has none yet.
+    pub(crate) fn get_or_create(
+        &mut self,
+        session_id: &str,
+        factory: &MemoryFactory,
+    ) -> Arc<Mutex<dyn BaseMemory>> {
+        self.uses += 1;
+        if let Some((memory, last_use)) = self.memories.get_mut(session_id) {
+            *last_use = self.uses;
+            return memory.clone();
+        }
+        if self.memories.len() >= self.max_sessions {
+            let least_recent = self
+                .memories
+                .iter()
+                .min_by_key(|(_, (_, last_use))| *last_use)
+                .map(|(session_id, _)| session_id.clone());
+            if let Some(least_recent) = least_recent {
+                self.memories.remove(&least_recent);
+            }
+        }
+        let memory = factory(session_id);
+        self.memories
+            .insert(session_id.to_string(), (memory.clone(), self.uses));
+        memory
+    }
+
+    pub(crate) fn remove(&mut self, session_id: &str) {
+        self.memories.remove(session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::SimpleMemory;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sessions_drop_the_least_recently_used() {
+        let factory: MemoryFactory = Arc::new(|_| SimpleMemory::new().into());
+        let mut sessions = Sessions::new(2);
+
+        let ana = sessions.get_or_create("ana", &factory);
+        ana.lock().await.add_user_message(&"I'm Ana");
+        sessions.get_or_create("bob", &factory);
+        sessions.get_or_create("ana", &factory);
+        sessions.get_or_create("eve", &factory);
+
+        assert_eq!(
+            sessions
+                .get_or_create("ana", &factory)
+                .lock()
+                .await
+                .messages()
+                .len(),
+            1
+        );
+        assert!(!sessions.memories.contains_key("bob"));
+    }
+}
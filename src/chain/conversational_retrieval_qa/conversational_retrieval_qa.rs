@@ -8,12 +8,16 @@ use serde_json::{json, Value};
 use tokio::sync::Mutex;
 
 use crate::{
+    callbacks::{CallbackManager, RunType},
     chain::{
         Chain, ChainError, CondenseQuestionPromptBuilder, StuffQAPromptBuilder, DEFAULT_RESULT_KEY,
     },
     language_models::{GenerateResult, TokenUsage},
     prompt::PromptArgs,
-    schemas::{BaseMemory, Message, Retriever, StreamData},
+    schemas::{
+        memory::{save_memory_context, turn_inputs},
+        BaseMemory, Message, Retriever, StreamData,
+    },
 };
 // _conversationalRetrievalQADefaultInputKey             = "question"
 // _conversationalRetrievalQADefaultSourceDocumentKey    = "source_documents"
@@ -78,74 +82,82 @@ impl Chain for ConversationalRetrieverChain {
         &self,
         input_variables: PromptArgs,
     ) -> Result<HashMap<String, Value>, ChainError> {
-        let mut token_usage: Option<TokenUsage> = None;
-        let input_variable = &input_variables
-            .get(&self.input_key)
-            .ok_or(ChainError::MissingInputVariable(self.input_key.clone()))?;
-
-        let human_message = Message::new_human_message(input_variable);
-        let history = {
-            let memory = self.memory.lock().await;
-            memory.messages()
-        };
-
-        let (question, token) = self.get_question(&history, &human_message.content).await?;
-        if let Some(token) = token {
-            token_usage = Some(token);
-        }
-
-        let documents = self
-            .retriever
-            .get_relevant_documents(&question)
-            .await
-            .map_err(|e| ChainError::RetrieverError(e.to_string()))?;
-
-        let mut output = self
-            .combine_documents_chain
-            .call(
-                StuffQAPromptBuilder::new()
-                    .documents(&documents)
-                    .question(question.clone())
-                    .build(),
-            )
-            .await?;
+        let run = CallbackManager::new().start_run("ConversationalRetrieverChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain_outputs(&inputs, async move {
+            let mut token_usage: Option<TokenUsage> = None;
+            let input_variable = &input_variables
+                .get(&self.input_key)
+                .ok_or(ChainError::MissingInputVariable(self.input_key.clone()))?;
+
+            let human_message = Message::new_human_message(input_variable);
+            let history = {
+                let memory = self.memory.lock().await;
+                memory.messages()
+            };
+
+            let (question, token) = self
+                .get_question(&history, &human_message.content.text())
+                .await?;
+            if let Some(token) = token {
+                token_usage = Some(token);
+            }
 
-        match &output.tokens {
-            Some(tokens) => {
-                if let Some(mut token_usage) = token_usage {
-                    token_usage.add(&tokens);
-                    output.tokens = Some(token_usage)
+            let documents = self
+                .retriever
+                .get_relevant_documents(&question)
+                .await
+                .map_err(|e| ChainError::RetrieverError(e.to_string()))?;
+
+            let mut output = self
+                .combine_documents_chain
+                .call(
+                    StuffQAPromptBuilder::new()
+                        .documents(&documents)
+                        .question(question.clone())
+                        .build(),
+                )
+                .await?;
+
+            match &output.tokens {
+                Some(tokens) => {
+                    if let Some(mut token_usage) = token_usage {
+                        token_usage.add(&tokens);
+                        output.tokens = Some(token_usage)
+                    }
                 }
+                None => {}
             }
-            None => {}
-        }
 
-        {
-            let mut memory = self.memory.lock().await;
-            memory.add_message(human_message);
-            memory.add_message(Message::new_ai_message(&output.generation));
-        }
+            save_memory_context(
+                &self.memory,
+                &turn_inputs(&input_variables, &self.input_key),
+                &output.generation,
+            )
+            .await;
 
-        let mut result = HashMap::new();
-        result.insert(self.output_key.clone(), json!(output.generation));
+            let mut result = HashMap::new();
+            result.insert(self.output_key.clone(), json!(output.generation));
 
-        result.insert(DEFAULT_RESULT_KEY.to_string(), json!(output));
+            result.insert(DEFAULT_RESULT_KEY.to_string(), json!(output));
 
-        if self.return_source_documents {
-            result.insert(
-                CONVERSATIONAL_RETRIEVAL_QA_DEFAULT_SOURCE_DOCUMENT_KEY.to_string(),
-                json!(documents),
-            );
-        }
+            if self.return_source_documents {
+                result.insert(
+                    CONVERSATIONAL_RETRIEVAL_QA_DEFAULT_SOURCE_DOCUMENT_KEY.to_string(),
+                    json!(documents),
+                );
+            }
 
-        if self.rephrase_question {
-            result.insert(
-                CONVERSATIONAL_RETRIEVAL_QA_DEFAULT_GENERATED_QUESTION_KEY.to_string(),
-                json!(question),
-            );
-        }
+            if self.rephrase_question {
+                result.insert(
+                    CONVERSATIONAL_RETRIEVAL_QA_DEFAULT_GENERATED_QUESTION_KEY.to_string(),
+                    json!(question),
+                );
+            }
 
-        Ok(result)
+            Ok(result)
+        })
+        .await
     }
 
     async fn stream(
@@ -163,7 +175,9 @@ impl Chain for ConversationalRetrieverChain {
             memory.messages()
         };
 
-        let (question, _) = self.get_question(&history, &human_message.content).await?;
+        let (question, _) = self
+            .get_question(&history, &human_message.content.text())
+            .await?;
 
         let documents = self
             .retriever
@@ -182,6 +196,7 @@ impl Chain for ConversationalRetrieverChain {
             .await?;
 
         let memory = self.memory.clone();
+        let turn = turn_inputs(&input_variables, &self.input_key);
         let complete_ai_message = Arc::new(Mutex::new(String::new()));
         let complete_ai_message_clone = complete_ai_message.clone();
         let output_stream = stream! {
@@ -201,9 +216,8 @@ impl Chain for ConversationalRetrieverChain {
                 }
             }
 
-            let mut memory = memory.lock().await;
-            memory.add_message(human_message);
-            memory.add_message(Message::new_ai_message(&complete_ai_message.lock().await));
+            let answer = complete_ai_message.lock().await.clone();
+            save_memory_context(&memory, &turn, &answer).await;
         };
 
         Ok(Box::pin(output_stream))
@@ -214,7 +228,7 @@ impl Chain for ConversationalRetrieverChain {
     }
 
     fn get_output_keys(&self) -> Vec<String> {
-        let mut keys = Vec::new();
+        let mut keys = vec![self.output_key.clone()];
         if self.return_source_documents {
             keys.push(CONVERSATIONAL_RETRIEVAL_QA_DEFAULT_SOURCE_DOCUMENT_KEY.to_string());
         }
@@ -223,7 +237,6 @@ impl Chain for ConversationalRetrieverChain {
             keys.push(CONVERSATIONAL_RETRIEVAL_QA_DEFAULT_GENERATED_QUESTION_KEY.to_string());
         }
 
-        keys.push(self.output_key.clone());
         keys.push(DEFAULT_RESULT_KEY.to_string());
 
         return keys;
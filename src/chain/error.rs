@@ -1,6 +1,14 @@
+use std::time::Duration;
+
 use thiserror::Error;
 
-use crate::{language_models::LLMError, output_parsers::OutputParserError, prompt::PromptError};
+use crate::{
+    guardrails::GuardReport,
+    language_models::{LLMError, LLMErrorClass},
+    output_parsers::OutputParserError,
+    prompt::PromptError,
+    tokenizers::ContextLengthExceeded,
+};
 
 #[derive(Error, Debug)]
 pub enum ChainError {
@@ -39,4 +47,36 @@ pub enum ChainError {
 
     #[error("Agent error: {0}")]
     AgentError(String),
+
+    #[error("Guardrail violation: {0}")]
+    GuardrailViolation(GuardReport),
+
+    /// The content was flagged by a moderation chain.
+    #[error("Content blocked: {}", categories.join(", "))]
+    ContentBlocked { categories: Vec<String> },
+
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(#[from] ContextLengthExceeded),
+
+    #[error("Timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error("Cancelled")]
+    Cancelled,
+}
+
+impl ChainError {
+    /// The class of the error if it is transient, see `LLMError::class`.
+    pub fn class(&self) -> Option<LLMErrorClass> {
+        match self {
+            ChainError::LLMError(e) => e.class(),
+            ChainError::Timeout(_) => Some(LLMErrorClass::Timeout),
+            _ => None,
+        }
+    }
+
+    /// Whether calling the chain again could succeed, see `LLMError::is_transient`.
+    pub fn is_transient(&self) -> bool {
+        self.class().is_some()
+    }
 }
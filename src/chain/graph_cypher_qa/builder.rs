@@ -0,0 +1,108 @@
+use crate::{
+    chain::{llm_chain::LLMChainBuilder, options::ChainCallOptions, ChainError},
+    graphstore::GraphStore,
+    language_models::llm::LLM,
+    prompt::{HumanMessagePromptTemplate, PromptTemplate},
+    template_jinja2,
+};
+
+use super::{
+    chain::GraphCypherQAChain,
+    prompt::{DEFAULT_CYPHER_GENERATION_TEMPLATE, DEFAULT_GRAPH_QA_TEMPLATE},
+};
+
+pub struct GraphCypherQAChainBuilder {
+    llm: Option<Box<dyn LLM>>,
+    graph: Option<Box<dyn GraphStore>>,
+    cypher_prompt: Option<PromptTemplate>,
+    qa_prompt: Option<PromptTemplate>,
+    top_k: Option<usize>,
+    options: Option<ChainCallOptions>,
+}
+
+impl Default for GraphCypherQAChainBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphCypherQAChainBuilder {
+    pub fn new() -> Self {
+        Self {
+            llm: None,
+            graph: None,
+            cypher_prompt: None,
+            qa_prompt: None,
+            top_k: None,
+            options: None,
+        }
+    }
+
+    pub fn llm<L: Into<Box<dyn LLM>>>(mut self, llm: L) -> Self {
+        self.llm = Some(llm.into());
+        self
+    }
+
+    pub fn graph<G: Into<Box<dyn GraphStore>>>(mut self, graph: G) -> Self {
+        self.graph = Some(graph.into());
+        self
+    }
+
+    /// The prompt writing the Cypher query, with the `schema` and `question` variables.
+    pub fn cypher_prompt(mut self, cypher_prompt: PromptTemplate) -> Self {
+        self.cypher_prompt = Some(cypher_prompt);
+        self
+    }
+
+    /// The prompt of the answer, with the `context` and `question` variables.
+    pub fn qa_prompt(mut self, qa_prompt: PromptTemplate) -> Self {
+        self.qa_prompt = Some(qa_prompt);
+        self
+    }
+
+    /// The maximum number of rows of the result given to the LLM. Default: 10
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    /// The options of the answer, e.g. its streaming function.
+    pub fn options(mut self, options: ChainCallOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn build(self) -> Result<GraphCypherQAChain, ChainError> {
+        let llm = self
+            .llm
+            .ok_or_else(|| ChainError::MissingObject("LLM must be set".into()))?;
+        let graph = self
+            .graph
+            .ok_or_else(|| ChainError::MissingObject("Graph must be set".into()))?;
+
+        let cypher_prompt = self.cypher_prompt.unwrap_or_else(|| {
+            template_jinja2!(DEFAULT_CYPHER_GENERATION_TEMPLATE, "schema", "question")
+        });
+        let qa_prompt = self
+            .qa_prompt
+            .unwrap_or_else(|| template_jinja2!(DEFAULT_GRAPH_QA_TEMPLATE, "context", "question"));
+
+        let cypher_chain = LLMChainBuilder::new()
+            .prompt(HumanMessagePromptTemplate::new(cypher_prompt))
+            .llm(llm.clone_box());
+        let mut qa_chain = LLMChainBuilder::new()
+            .prompt(HumanMessagePromptTemplate::new(qa_prompt))
+            .llm(llm);
+        if let Some(options) = self.options {
+            qa_chain = qa_chain.options(options);
+        }
+
+        Ok(GraphCypherQAChain {
+            cypher_chain: cypher_chain.build()?,
+            qa_chain: qa_chain.build()?,
+            graph,
+            top_k: self.top_k.unwrap_or(10),
+        })
+    }
+}
@@ -0,0 +1,238 @@
+use std::{collections::HashMap, pin::Pin};
+
+use async_trait::async_trait;
+use futures::Stream;
+use serde_json::Value;
+
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    chain::{chain_trait::Chain, llm_chain::LLMChain, ChainError},
+    graphstore::GraphStore,
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    prompt_args,
+    schemas::StreamData,
+};
+
+use super::GRAPH_CYPHER_QA_INPUT_KEY;
+
+/// Answers questions about a knowledge graph: the LLM writes a Cypher query from the
+/// schema of the graph, the query is run read-only, see `GraphStore::query_read_only`,
+/// and the LLM answers from its rows.
+///
+/// The input variable name is `query`.
+///
+/// # Example
+/// ```rust,ignore
+/// let graph = Neo4jGraphStore::connect("127.0.0.1:7687", "neo4j", "password").await?;
+/// let chain = GraphCypherQAChainBuilder::new()
+///     .llm(OpenAI::default())
+///     .graph(graph)
+///     .top_k(10)
+///     .build()?;
+/// let answer = chain
+///     .invoke(prompt_args! {"query" => "Which prizes did Marie Curie win?"})
+///     .await?;
+/// ```
+pub struct GraphCypherQAChain {
+    pub(crate) cypher_chain: LLMChain,
+    pub(crate) qa_chain: LLMChain,
+    pub(crate) graph: Box<dyn GraphStore>,
+    pub(crate) top_k: usize,
+}
+
+impl GraphCypherQAChain {
+    /// The Cypher query answering the question, as written by the LLM.
+    pub async fn generate_cypher(&self, question: &str) -> Result<String, ChainError> {
+        let schema = self
+            .graph
+            .get_schema()
+            .await
+            .map_err(|e| ChainError::DatabaseError(e.to_string()))?;
+        let output = self
+            .cypher_chain
+            .invoke(prompt_args! {
+                "schema" => schema,
+                "question" => question,
+            })
+            .await?;
+        Ok(clean_cypher_query(&output).to_string())
+    }
+
+    /// The inputs of the answer, with the rows of the query as context.
+    async fn qa_inputs(&self, input_variables: &PromptArgs) -> Result<PromptArgs, ChainError> {
+        let question = match input_variables.get(GRAPH_CYPHER_QA_INPUT_KEY) {
+            Some(Value::String(question)) => question.clone(),
+            Some(question) => question.to_string(),
+            None => {
+                return Err(ChainError::MissingInputVariable(
+                    GRAPH_CYPHER_QA_INPUT_KEY.to_string(),
+                ))
+            }
+        };
+
+        let cypher = self.generate_cypher(&question).await?;
+        log::debug!("GraphCypherQAChain query: {}", cypher);
+        let mut rows = self
+            .graph
+            .query_read_only(&cypher, HashMap::new())
+            .await
+            .map_err(|e| ChainError::DatabaseError(e.to_string()))?;
+        rows.truncate(self.top_k);
+
+        Ok(prompt_args! {
+            "context" => serde_json::to_string(&rows)?,
+            "question" => question,
+        })
+    }
+}
+
+/// The query without the markdown fence the models often add.
+fn clean_cypher_query(generation: &str) -> &str {
+    let query = generation.trim();
+    let query = query
+        .strip_prefix("```cypher")
+        .or_else(|| query.strip_prefix("```"))
+        .and_then(|query| query.strip_suffix("```"))
+        .unwrap_or(query);
+    query.trim()
+}
+
+#[async_trait]
+impl Chain for GraphCypherQAChain {
+    fn get_input_keys(&self) -> Vec<String> {
+        vec![GRAPH_CYPHER_QA_INPUT_KEY.to_string()]
+    }
+
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        let run = CallbackManager::new().start_run("GraphCypherQAChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain(&inputs, async move {
+            let qa_inputs = self.qa_inputs(&input_variables).await?;
+            self.qa_chain.call(qa_inputs).await
+        })
+        .await
+    }
+
+    async fn stream(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
+    {
+        let qa_inputs = self.qa_inputs(&input_variables).await?;
+        self.qa_chain.stream(qa_inputs).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        chain::GraphCypherQAChainBuilder,
+        graphstore::{GraphDocument, GraphStoreError, Node, Relationship},
+        llm::FakeLLM,
+    };
+
+    /// Answers every query with the same rows, keeping the queries.
+    #[derive(Clone, Default)]
+    struct MockGraphStore {
+        queries: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl GraphStore for MockGraphStore {
+        async fn add_graph_documents(
+            &self,
+            _documents: &[GraphDocument],
+            _include_source: bool,
+        ) -> Result<(), GraphStoreError> {
+            Ok(())
+        }
+
+        async fn query(
+            &self,
+            query: &str,
+            _params: HashMap<String, Value>,
+        ) -> Result<Vec<HashMap<String, Value>>, GraphStoreError> {
+            self.queries.lock().unwrap().push(query.to_string());
+            Ok(vec![
+                HashMap::from([("prize".to_string(), json!("Nobel Prize in Physics"))]),
+                HashMap::from([("prize".to_string(), json!("Nobel Prize in Chemistry"))]),
+            ])
+        }
+
+        async fn get_schema(&self) -> Result<String, GraphStoreError> {
+            Ok("Node types: Award, Person\nRelationships:\n(:Person)-[:WON]->(:Award)".into())
+        }
+
+        async fn search_nodes(
+            &self,
+            _text: &str,
+            _limit: usize,
+        ) -> Result<Vec<Node>, GraphStoreError> {
+            Ok(Vec::new())
+        }
+
+        async fn neighbors(
+            &self,
+            _node_ids: &[String],
+            _limit: usize,
+        ) -> Result<Vec<Relationship>, GraphStoreError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_graph_cypher_qa_chain() {
+        let graph = MockGraphStore::default();
+        let llm = FakeLLM::from_responses(vec![
+            "```cypher\nMATCH (:Person {id: 'Marie Curie'})-[:WON]->(a:Award) RETURN a.id AS prize\n```",
+            "Marie Curie won the Nobel Prize in Physics",
+        ]);
+        let chain = GraphCypherQAChainBuilder::new()
+            .llm(llm.clone())
+            .graph(graph.clone())
+            .top_k(1)
+            .build()
+            .unwrap();
+
+        let answer = chain
+            .invoke(prompt_args! {"query" => "Which prizes did Marie Curie win?"})
+            .await
+            .unwrap();
+        assert_eq!(answer, "Marie Curie won the Nobel Prize in Physics");
+        assert_eq!(
+            graph.queries.lock().unwrap()[0],
+            "MATCH (:Person {id: 'Marie Curie'})-[:WON]->(a:Award) RETURN a.id AS prize"
+        );
+        let calls = llm.calls();
+        assert!(calls[0][0]
+            .content
+            .text()
+            .contains("(:Person)-[:WON]->(:Award)"));
+        assert!(calls[1][0]
+            .content
+            .text()
+            .contains(r#"[{"prize":"Nobel Prize in Physics"}]"#));
+    }
+
+    #[tokio::test]
+    async fn test_graph_cypher_qa_chain_read_only() {
+        let graph = MockGraphStore::default();
+        let chain = GraphCypherQAChainBuilder::new()
+            .llm(FakeLLM::new("MATCH (n) DETACH DELETE n"))
+            .graph(graph.clone())
+            .build()
+            .unwrap();
+
+        let result = chain
+            .invoke(prompt_args! {"query" => "Delete everything"})
+            .await;
+        assert!(matches!(result, Err(ChainError::DatabaseError(_))));
+        assert!(graph.queries.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,9 @@
+mod builder;
+mod chain;
+mod prompt;
+
+pub use builder::*;
+pub use chain::*;
+pub use prompt::*;
+
+const GRAPH_CYPHER_QA_INPUT_KEY: &str = "query";
@@ -0,0 +1,26 @@
+pub const DEFAULT_CYPHER_GENERATION_TEMPLATE: &str = r#"Task: Generate a Cypher statement to query a graph database.
+Instructions:
+Use only the node types, relationship types and properties provided in the schema.
+Do not use any other node types, relationship types or properties.
+Only read the graph, never create, update or delete data.
+
+Schema:
+{{schema}}
+
+Do not include any explanations or apologies in your response.
+Do not include any text except the generated Cypher statement.
+
+The question is:
+{{question}}"#;
+
+pub const DEFAULT_GRAPH_QA_TEMPLATE: &str = r#"You are an assistant that helps to form nice and human understandable answers.
+The information part contains the results of a query of a knowledge graph answering the question, that you must use to construct the answer.
+The provided information is authoritative, never doubt it or try to use your own knowledge to correct it.
+Make the answer sound as a response to the question, without mentioning that it is based on the given information.
+If the provided information is empty, say that you don't know the answer.
+
+Information:
+{{context}}
+
+Question: {{question}}
+Helpful Answer:"#;
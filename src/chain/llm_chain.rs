@@ -1,17 +1,29 @@
-use std::pin::Pin;
+use std::{pin::Pin, sync::Arc};
 
 use async_trait::async_trait;
 use futures::Stream;
 use futures_util::TryStreamExt;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use tokio::sync::Mutex;
 
 use crate::{
-    language_models::{llm::LLM, GenerateResult},
-    output_parsers::{OutputParser, SimpleParser},
+    callbacks::{CallbackManager, RunType},
+    language_models::{llm::LLM, options::CallOptions, GenerateResult},
+    output_parsers::{OutputParser, OutputParserError, SimpleParser},
     prompt::{FormatPrompter, PromptArgs},
-    schemas::StreamData,
+    schemas::{
+        memory::{save_memory_context, BaseMemory, DEFAULT_MEMORY_KEY},
+        Message, ResponseFormat, StreamData,
+    },
+    tokenizers::ContextWindow,
 };
 
-use super::{chain_trait::Chain, options::ChainCallOptions, ChainError};
+use super::{
+    chain_trait::Chain,
+    options::{CallLimits, ChainCallOptions},
+    ChainError,
+};
 
 pub struct LLMChainBuilder {
     prompt: Option<Box<dyn FormatPrompter>>,
@@ -19,6 +31,9 @@ pub struct LLMChainBuilder {
     output_key: Option<String>,
     options: Option<ChainCallOptions>,
     output_parser: Option<Box<dyn OutputParser<String>>>,
+    memory: Option<Arc<Mutex<dyn BaseMemory>>>,
+    memory_key: Option<String>,
+    context_window: Option<ContextWindow>,
 }
 
 impl LLMChainBuilder {
@@ -29,6 +44,9 @@ impl LLMChainBuilder {
             options: None,
             output_key: None,
             output_parser: None,
+            memory: None,
+            memory_key: None,
+            context_window: None,
         }
     }
     pub fn options(mut self, options: ChainCallOptions) -> Self {
@@ -59,6 +77,28 @@ impl LLMChainBuilder {
         self
     }
 
+    /// Loads the history of the memory in the inputs of the prompt, under its memory key,
+    /// and saves every turn of `call` and `invoke` in it.
+    pub fn memory(mut self, memory: Arc<Mutex<dyn BaseMemory>>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// The input variable filled by the memory, which isn't an input key of the chain. It
+    /// has to be the `memory_key` of the memory.
+    /// Default: `history`
+    pub fn memory_key<S: Into<String>>(mut self, memory_key: S) -> Self {
+        self.memory_key = Some(memory_key.into());
+        self
+    }
+
+    /// Checks the prompts against the context window of the model before calling it,
+    /// failing with `ChainError::ContextLengthExceeded` or trimming the history.
+    pub fn context_window(mut self, context_window: ContextWindow) -> Self {
+        self.context_window = Some(context_window);
+        self
+    }
+
     pub fn build(self) -> Result<LLMChain, ChainError> {
         let prompt = self
             .prompt
@@ -68,7 +108,15 @@ impl LLMChainBuilder {
             .llm
             .ok_or_else(|| ChainError::MissingObject("LLM must be set".into()))?;
 
-        if let Some(options) = self.options {
+        let mut callbacks = CallbackManager::new();
+        let mut memory = self.memory;
+        let mut limits = CallLimits::default();
+        if let Some(mut options) = self.options {
+            memory = memory.or(options.memory.take());
+            limits = CallLimits::from_options(&options);
+            if let Some(chain_callbacks) = &options.callbacks {
+                callbacks = chain_callbacks.clone();
+            }
             let llm_options = ChainCallOptions::to_llm_options(options);
             llm.add_options(llm_options);
         }
@@ -80,6 +128,13 @@ impl LLMChainBuilder {
             output_parser: self
                 .output_parser
                 .unwrap_or_else(|| Box::new(SimpleParser::default())),
+            callbacks,
+            memory,
+            memory_key: self
+                .memory_key
+                .unwrap_or_else(|| DEFAULT_MEMORY_KEY.to_string()),
+            context_window: self.context_window,
+            limits,
         };
 
         Ok(chain)
@@ -91,12 +146,118 @@ pub struct LLMChain {
     llm: Box<dyn LLM>,
     output_key: String,
     output_parser: Box<dyn OutputParser<String>>,
+    callbacks: CallbackManager,
+    memory: Option<Arc<Mutex<dyn BaseMemory>>>,
+    memory_key: String,
+    context_window: Option<ContextWindow>,
+    limits: CallLimits,
+}
+
+impl LLMChain {
+    /// Calls the chain with its answer constrained to the JSON schema of `T`, with the
+    /// structured outputs of the LLM, and deserializes it. The LLMs without a native
+    /// JSON mode ignore the response format, the prompt should then ask for the JSON.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// #[derive(Deserialize, JsonSchema)]
+    /// struct Person {
+    ///     name: String,
+    ///     age: u32,
+    /// }
+    ///
+    /// let person: Person = chain.call_typed(prompt_args! {"input" => text}).await?;
+    /// ```
+    pub async fn call_typed<T: DeserializeOwned + JsonSchema>(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<T, ChainError> {
+        let mut llm = self.llm.clone_box();
+        llm.add_options(CallOptions::new().with_response_format(ResponseFormat::from_type::<T>()));
+        let output = self.call_llm(llm.as_ref(), input_variables).await?;
+        serde_json::from_str(&output.generation).map_err(|e| {
+            OutputParserError::ParsingError(format!(
+                "The output doesn't match the schema: {}. Output: {}",
+                e, output.generation
+            ))
+            .into()
+        })
+    }
+
+    async fn call_llm(
+        &self,
+        llm: &dyn LLM,
+        input_variables: PromptArgs,
+    ) -> Result<GenerateResult, ChainError> {
+        let run = self.callbacks.start_run("LLMChain", RunType::Chain);
+        let deadline = self.limits.deadline();
+        run.trace_chain(
+            &input_variables,
+            self.limits.run(deadline, async {
+                let messages = self.prompt_messages(input_variables.clone()).await?;
+                let mut output = llm.generate(&messages).await?;
+                output.generation = self.output_parser.parse(&output.generation).await?;
+                self.save_context(&input_variables, &output.generation)
+                    .await;
+
+                Ok(output)
+            }),
+        )
+        .await
+    }
+
+    /// The messages of the prompt, fitted in the context window if there is one.
+    async fn prompt_messages(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<Vec<Message>, ChainError> {
+        let prompt_variables = self.with_memory_variables(input_variables).await;
+        let prompt = self.prompt.format_prompt_async(prompt_variables).await?;
+        log::debug!("Prompt: {:?}", prompt);
+        let messages = prompt.to_chat_messages();
+        match &self.context_window {
+            Some(context_window) => Ok(context_window.fit(messages)?),
+            None => Ok(messages),
+        }
+    }
+
+    /// Adds the variables of the memory to the inputs, without overriding those set by
+    /// the caller.
+    async fn with_memory_variables(&self, mut input_variables: PromptArgs) -> PromptArgs {
+        if let Some(memory) = &self.memory {
+            let memory = memory.lock().await;
+            if memory.memory_key() != self.memory_key {
+                log::warn!(
+                    "The memory key {} of the memory isn't the memory key {} of the chain",
+                    memory.memory_key(),
+                    self.memory_key
+                );
+            }
+            for (key, value) in memory.load_memory_variables() {
+                input_variables.entry(key).or_insert(value);
+            }
+        }
+        input_variables
+    }
+
+    async fn save_context(&self, input_variables: &PromptArgs, output: &str) {
+        if let Some(memory) = &self.memory {
+            save_memory_context(memory, input_variables, output).await;
+        }
+    }
 }
 
 #[async_trait]
 impl Chain for LLMChain {
     fn get_input_keys(&self) -> Vec<String> {
-        return self.prompt.get_input_variables();
+        let input_keys = self.prompt.get_input_variables();
+        match &self.memory {
+            Some(_) => input_keys
+                .into_iter()
+                .filter(|key| *key != self.memory_key)
+                .collect(),
+            None => input_keys,
+        }
     }
 
     fn get_output_keys(&self) -> Vec<String> {
@@ -104,23 +265,24 @@ impl Chain for LLMChain {
     }
 
     async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
-        let prompt = self.prompt.format_prompt(input_variables.clone())?;
-        log::debug!("Prompt: {:?}", prompt);
-        let mut output = self.llm.generate(&prompt.to_chat_messages()).await?;
-        output.generation = self.output_parser.parse(&output.generation).await?;
-
-        Ok(output)
+        self.call_llm(self.llm.as_ref(), input_variables).await
     }
 
     async fn invoke(&self, input_variables: PromptArgs) -> Result<String, ChainError> {
-        let prompt = self.prompt.format_prompt(input_variables.clone())?;
-        log::debug!("Prompt: {:?}", prompt);
-        let output = self
-            .llm
-            .generate(&prompt.to_chat_messages())
-            .await?
-            .generation;
-        Ok(output)
+        let run = self.callbacks.start_run("LLMChain", RunType::Chain);
+        let deadline = self.limits.deadline();
+        run.trace_chain(
+            &input_variables,
+            self.limits.run(deadline, async {
+                let messages = self.prompt_messages(input_variables.clone()).await?;
+                let output = self.llm.generate(&messages).await?;
+                self.save_context(&input_variables, &output.generation)
+                    .await;
+                Ok(output)
+            }),
+        )
+        .await
+        .map(|output| output.generation)
     }
 
     async fn stream(
@@ -128,29 +290,194 @@ impl Chain for LLMChain {
         input_variables: PromptArgs,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
     {
-        let prompt = self.prompt.format_prompt(input_variables.clone())?;
-        log::debug!("Prompt: {:?}", prompt);
-        let llm_stream = self.llm.stream(&prompt.to_chat_messages()).await?;
+        let run = self.callbacks.start_run("LLMChain", RunType::Chain);
+        run.on_chain_start(&input_variables);
+        // The timeout covers the request and the whole stream.
+        let deadline = self.limits.deadline();
+        // Started inside the scope of the chain, so the run of the llm is its child.
+        let llm_stream = run
+            .scope(self.limits.run(deadline, async {
+                // The streamed turns are not saved in the memory, the chain doesn't see
+                // the whole output.
+                let messages = self.prompt_messages(input_variables.clone()).await?;
+                self.llm.stream(&messages).await.map_err(ChainError::from)
+            }))
+            .await
+            .inspect_err(|e| run.on_error(&e.to_string()))?;
 
         // Map the errors from LLMError to ChainError
         let mapped_stream = llm_stream.map_err(ChainError::from);
 
-        Ok(Box::pin(mapped_stream))
+        Ok(run.trace_chain_stream(self.limits.stream(deadline, Box::pin(mapped_stream))))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+
     use crate::{
-        chain::options::ChainCallOptions,
-        llm::openai::{OpenAI, OpenAIModel},
+        callbacks::{tests::RecordingHandler, CallbackManager},
+        chain::options::{CancellationToken, ChainCallOptions},
+        fmt_placeholder, fmt_template,
+        llm::{
+            openai::{OpenAI, OpenAIModel},
+            FakeLLM,
+        },
+        memory::ConversationBufferMemory,
         message_formatter,
         prompt::{HumanMessagePromptTemplate, MessageOrTemplate},
-        prompt_args, template_fstring,
+        prompt_args,
+        schemas::Message,
+        template_fstring,
     };
 
     use super::*;
 
+    #[tokio::test]
+    async fn test_chain_with_memory() {
+        let memory: Arc<Mutex<dyn BaseMemory>> = ConversationBufferMemory::new().into();
+        let chain = LLMChainBuilder::new()
+            .prompt(message_formatter![MessageOrTemplate::Template(
+                HumanMessagePromptTemplate::new(template_fstring!(
+                    "{history}|{input}",
+                    "history",
+                    "input"
+                ))
+                .into()
+            ),])
+            .llm(FakeLLM::echo())
+            .memory(memory.clone())
+            .build()
+            .unwrap();
+        {
+            // The input keys don't depend on the memory being locked.
+            let _memory = memory.lock().await;
+            assert_eq!(chain.get_input_keys(), vec!["input".to_string()]);
+        }
+
+        let output = chain
+            .invoke(prompt_args! {"input" => "first"})
+            .await
+            .unwrap();
+        assert_eq!(output, "|first");
+        let output = chain
+            .invoke(prompt_args! {"input" => "second"})
+            .await
+            .unwrap();
+        assert_eq!(output, "human: first\nai: |first|second");
+        assert_eq!(memory.lock().await.messages().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_chain_context_window() {
+        let long = "Hello world ".repeat(10);
+        let build = |context_window: ContextWindow| {
+            LLMChainBuilder::new()
+                .prompt(message_formatter![
+                    fmt_placeholder!("chat_history"),
+                    fmt_template!(HumanMessagePromptTemplate::new(template_fstring!(
+                        "{input}", "input"
+                    )))
+                ])
+                .llm(FakeLLM::echo())
+                .context_window(context_window)
+                .build()
+                .unwrap()
+        };
+        let input_variables = prompt_args! {
+            "chat_history" => vec![
+                Message::new_human_message(&long),
+                Message::new_ai_message(&long),
+            ],
+            "input" => "second",
+        };
+
+        let chain = build(ContextWindow::new("gpt-4").with_max_tokens(40));
+        assert!(matches!(
+            chain.invoke(input_variables.clone()).await,
+            Err(ChainError::ContextLengthExceeded(_))
+        ));
+
+        let chain = build(
+            ContextWindow::new("gpt-4")
+                .with_max_tokens(40)
+                .with_trim_history(true),
+        );
+        let output = chain.invoke(input_variables).await.unwrap();
+        assert_eq!(output, format!("{}\nsecond", long));
+    }
+
+    fn slow_chain(llm: FakeLLM, options: ChainCallOptions) -> LLMChain {
+        LLMChainBuilder::new()
+            .prompt(HumanMessagePromptTemplate::new(template_fstring!(
+                "{input}", "input"
+            )))
+            .llm(llm)
+            .options(options)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chain_timeout() {
+        let handler = Arc::new(RecordingHandler::default());
+        let llm = FakeLLM::new("Hello")
+            .with_latency(Duration::from_secs(1))
+            .with_options(
+                CallOptions::new()
+                    .with_callbacks(CallbackManager::new().with_shared_handler(handler.clone())),
+            );
+        let chain = slow_chain(
+            llm,
+            ChainCallOptions::new().with_timeout(Duration::from_millis(20)),
+        );
+        assert!(matches!(
+            chain.invoke(prompt_args! {"input" => "Hi"}).await,
+            Err(ChainError::Timeout(_))
+        ));
+
+        let chain = slow_chain(
+            FakeLLM::new("Hello world").with_stream_delay(Duration::from_secs(1)),
+            ChainCallOptions::new().with_timeout(Duration::from_millis(20)),
+        );
+        let mut stream = chain.stream(prompt_args! {"input" => "Hi"}).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap().content, "Hello ");
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(ChainError::Timeout(_)))
+        ));
+        assert!(stream.next().await.is_none());
+
+        // The call to the LLM was dropped, not left running.
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert_eq!(handler.names(), vec!["llm_start:fake"]);
+    }
+
+    #[tokio::test]
+    async fn test_chain_cancellation() {
+        let token = CancellationToken::new();
+        let chain = slow_chain(
+            FakeLLM::new("Hello world").with_stream_delay(Duration::from_secs(1)),
+            ChainCallOptions::new().with_cancellation_token(token.clone()),
+        );
+
+        let mut stream = chain.stream(prompt_args! {"input" => "Hi"}).await.unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+        token.cancel();
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(ChainError::Cancelled))
+        ));
+
+        assert!(matches!(
+            chain.call(prompt_args! {"input" => "Hi"}).await,
+            Err(ChainError::Cancelled)
+        ));
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_invoke_chain() {
@@ -185,4 +512,77 @@ mod tests {
             result.err()
         )
     }
+
+    #[tokio::test]
+    async fn test_invoke_chain_with_fake_llm() {
+        let formatter = message_formatter![MessageOrTemplate::Template(
+            HumanMessagePromptTemplate::new(
+                template_fstring!("Mi nombre es: {nombre} ", "nombre",)
+            )
+            .into()
+        ),];
+        let llm = FakeLLM::new("Hola luis");
+        let chain = LLMChainBuilder::new()
+            .prompt(formatter)
+            .llm(llm.clone())
+            .build()
+            .unwrap();
+
+        let result = chain.invoke(prompt_args! {"nombre" => "luis"}).await;
+        assert_eq!(result.unwrap(), "Hola luis");
+        assert_eq!(llm.calls()[0][0].content, "Mi nombre es: luis ");
+    }
+
+    #[tokio::test]
+    async fn test_call_typed() {
+        #[derive(serde::Deserialize, JsonSchema)]
+        struct Person {
+            name: String,
+            age: u32,
+        }
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": {"name": "Person", "strict": true},
+                },
+            })))
+            .with_body(
+                serde_json::json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion",
+                    "created": 1700000000,
+                    "model": "gpt-4o",
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": "{\"name\": \"Luis\", \"age\": 30}"},
+                        "finish_reason": "stop",
+                    }],
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let chain = LLMChainBuilder::new()
+            .prompt(HumanMessagePromptTemplate::new(template_fstring!(
+                "Extract the person: {input}",
+                "input"
+            )))
+            .llm(OpenAI::new(
+                crate::llm::OpenAIConfig::new().with_api_base(server.url()),
+            ))
+            .build()
+            .unwrap();
+
+        let person: Person = chain
+            .call_typed(prompt_args! {"input" => "Luis is 30"})
+            .await
+            .unwrap();
+        mock.assert_async().await;
+        assert_eq!(person.name, "Luis");
+        assert_eq!(person.age, 30);
+    }
 }
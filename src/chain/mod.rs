@@ -13,6 +13,9 @@ pub use sequential::*;
 pub mod sql_datbase;
 pub use sql_datbase::*;
 
+mod graph_cypher_qa;
+pub use graph_cypher_qa::*;
+
 mod stuff_documents;
 pub use stuff_documents::*;
 
@@ -22,6 +25,24 @@ pub use question_answering::*;
 mod conversational_retrieval_qa;
 pub use conversational_retrieval_qa::*;
 
+mod retrieval_qa;
+pub use retrieval_qa::*;
+
+mod router;
+pub use router::*;
+
+mod summarize;
+pub use summarize::*;
+
+mod moderation;
+pub use moderation::*;
+
+mod openai_assistant;
+pub use openai_assistant::*;
+
+mod config;
+pub use config::*;
+
 mod error;
 pub use error::*;
 
@@ -0,0 +1,275 @@
+use async_openai::{
+    config::{Config, OpenAIConfig},
+    types::{CreateModerationRequestArgs, TextModerationModel},
+    Client,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{
+    guardrails::Validator,
+    language_models::{GenerateResult, LLMError},
+    prompt::PromptArgs,
+    runtime::send,
+};
+
+use super::{Chain, ChainError};
+
+/// Checks a text with the moderation API of OpenAI, failing with
+/// `ChainError::ContentBlocked` when the text is flagged, and returning it unchanged
+/// otherwise. Put it before or after a chain in a `SequentialChain` to moderate its input
+/// or its output, or use it as a `Validator` of a `Guard`, which fails with
+/// `ChainError::ContentBlocked` too with the `Block` action.
+///
+/// # Example
+/// ```rust,ignore
+/// let moderation = OpenAIModerationChain::default().with_output_key("question");
+/// let chain = sequential_chain!(moderation, llm_chain);
+///
+/// match chain.invoke(prompt_args! {"input" => question}).await {
+///     Err(ChainError::ContentBlocked { categories }) => println!("Blocked: {:?}", categories),
+///     result => println!("{}", result?),
+/// }
+/// ```
+pub struct OpenAIModerationChain<C: Config> {
+    config: C,
+    model: TextModerationModel,
+    input_key: String,
+    output_key: String,
+}
+
+impl<C: Config> OpenAIModerationChain<C> {
+    pub fn new(config: C) -> Self {
+        Self {
+            config,
+            model: TextModerationModel::Latest,
+            input_key: "input".to_string(),
+            output_key: "output".to_string(),
+        }
+    }
+
+    pub fn with_model(mut self, model: TextModerationModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = input_key.into();
+        self
+    }
+
+    pub fn with_output_key<S: Into<String>>(mut self, output_key: S) -> Self {
+        self.output_key = output_key.into();
+        self
+    }
+
+    /// The flagged categories of `text`, e.g. `hate` or `self-harm/intent`, empty when the
+    /// text is not flagged.
+    pub async fn flagged_categories(&self, text: &str) -> Result<Vec<String>, ChainError> {
+        let request = CreateModerationRequestArgs::default()
+            .input(text)
+            .model(self.model)
+            .build()
+            .map_err(LLMError::from)?;
+        let response = send(
+            Client::with_config(self.config.clone())
+                .moderations()
+                .create(request),
+        )
+        .await
+        .map_err(LLMError::from)?;
+
+        let mut categories = Vec::new();
+        for result in response.results.into_iter().filter(|result| result.flagged) {
+            if let Value::Object(flags) = serde_json::to_value(result.categories)? {
+                categories.extend(
+                    flags
+                        .into_iter()
+                        .filter(|(_, flagged)| *flagged == Value::Bool(true))
+                        .map(|(category, _)| category),
+                );
+            }
+        }
+        Ok(categories)
+    }
+}
+
+impl Default for OpenAIModerationChain<OpenAIConfig> {
+    fn default() -> Self {
+        Self::new(OpenAIConfig::default())
+    }
+}
+
+#[async_trait]
+impl<C: Config + Send + Sync + 'static> Chain for OpenAIModerationChain<C> {
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        let text = input_variables
+            .get(&self.input_key)
+            .and_then(Value::as_str)
+            .ok_or_else(|| ChainError::MissingInputVariable(self.input_key.clone()))?;
+        let categories = self.flagged_categories(text).await?;
+        if !categories.is_empty() {
+            return Err(ChainError::ContentBlocked { categories });
+        }
+        Ok(GenerateResult {
+            generation: text.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn get_input_keys(&self) -> Vec<String> {
+        vec![self.input_key.clone()]
+    }
+
+    fn get_output_keys(&self) -> Vec<String> {
+        vec![self.output_key.clone()]
+    }
+}
+
+#[async_trait]
+impl<C: Config + Send + Sync + 'static> Validator for OpenAIModerationChain<C> {
+    fn name(&self) -> String {
+        "openai_moderation".to_string()
+    }
+
+    async fn validate(&self, text: &str) -> Result<Option<String>, ChainError> {
+        let categories = self.flagged_categories(text).await?;
+        if categories.is_empty() {
+            return Ok(None);
+        }
+        Err(ChainError::ContentBlocked { categories })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        chain::LLMChainBuilder,
+        guardrails::{Guard, GuardAction},
+        llm::FakeLLM,
+        message_formatter,
+        prompt::{HumanMessagePromptTemplate, MessageOrTemplate},
+        prompt_args, template_fstring,
+    };
+
+    use super::*;
+
+    fn moderation_response(flagged: bool) -> String {
+        let categories = [
+            "hate",
+            "hate/threatening",
+            "harassment",
+            "harassment/threatening",
+            "self-harm",
+            "self-harm/intent",
+            "self-harm/instructions",
+            "sexual",
+            "sexual/minors",
+            "violence",
+            "violence/graphic",
+        ];
+        let flags: serde_json::Map<String, Value> = categories
+            .iter()
+            .map(|category| {
+                let flag = flagged && category.starts_with("violence");
+                (category.to_string(), json!(flag))
+            })
+            .collect();
+        let scores: serde_json::Map<String, Value> = categories
+            .iter()
+            .map(|category| (category.to_string(), json!(0.5)))
+            .collect();
+        json!({
+            "id": "modr-1",
+            "model": "text-moderation-007",
+            "results": [{"flagged": flagged, "categories": flags, "category_scores": scores}],
+        })
+        .to_string()
+    }
+
+    fn moderation(server: &mockito::Server) -> OpenAIModerationChain<OpenAIConfig> {
+        OpenAIModerationChain::new(
+            OpenAIConfig::new()
+                .with_api_base(server.url())
+                .with_api_key("key"),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_moderation_chain_blocks_flagged_content() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/moderations")
+            .with_body(moderation_response(true))
+            .create_async()
+            .await;
+
+        let result = moderation(&server)
+            .call(prompt_args! {"input" => "something violent"})
+            .await;
+        let Err(ChainError::ContentBlocked { mut categories }) = result else {
+            panic!("Expected blocked content, got {:?}", result);
+        };
+        categories.sort();
+        assert_eq!(categories, vec!["violence", "violence/graphic"]);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_moderation_chain_passes_content() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/moderations")
+            .with_body(moderation_response(false))
+            .create_async()
+            .await;
+
+        let moderation = moderation(&server);
+        let output = moderation
+            .invoke(prompt_args! {"input" => "Hello"})
+            .await
+            .unwrap();
+        assert_eq!(output, "Hello");
+        assert_eq!(moderation.validate("Hello").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_moderation_validator_in_guard() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/moderations")
+            .with_body(moderation_response(true))
+            .create_async()
+            .await;
+        let guard = |action| {
+            let chain = LLMChainBuilder::new()
+                .prompt(message_formatter![MessageOrTemplate::Template(
+                    HumanMessagePromptTemplate::new(template_fstring!("{input}", "input")).into()
+                )])
+                .llm(FakeLLM::new("Hi"))
+                .build()
+                .unwrap();
+            Guard::new(chain).with_input_validator(moderation(&server), action)
+        };
+
+        let result = guard(GuardAction::Block)
+            .call(prompt_args! {"input" => "something violent"})
+            .await;
+        assert!(
+            matches!(result, Err(ChainError::ContentBlocked { .. })),
+            "Expected blocked content, got {:?}",
+            result
+        );
+
+        let (result, report) = guard(GuardAction::Log)
+            .call_with_report(prompt_args! {"input" => "something violent"})
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "Hi");
+        assert!(report.violations[0]
+            .message
+            .starts_with("Flagged as violence"));
+    }
+}
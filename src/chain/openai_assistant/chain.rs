@@ -0,0 +1,671 @@
+use std::{collections::HashMap, pin::Pin, sync::Arc, time::Duration};
+
+use async_openai::config::{Config, OpenAIConfig};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use reqwest_eventsource::Event;
+use serde_json::{json, Value};
+
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    chain::{Chain, ChainError, DEFAULT_OUTPUT_KEY, DEFAULT_RESULT_KEY},
+    language_models::{GenerateResult, LLMError, LLMRetryPolicy},
+    prompt::PromptArgs,
+    runtime,
+    schemas::StreamData,
+    tools::Tool,
+};
+
+use super::client::{
+    check_status, content_text, AssistantsClient, OpenAIAssistant, Run, RunToolCall,
+};
+
+/// The input variable of the thread to continue, and the output with the thread of the
+/// call.
+pub const OPENAI_ASSISTANT_THREAD_ID_KEY: &str = "thread_id";
+pub const OPENAI_ASSISTANT_RUN_ID_KEY: &str = "run_id";
+
+/// Talks to an assistant of the OpenAI Assistants API: the input is added to a thread, a
+/// run of the assistant is polled until it completes, and its messages are the answer.
+/// The function calls of the run are answered with the `Tool`s of the chain.
+///
+/// The conversation is kept by OpenAI in the thread: without a `thread_id` input
+/// variable a new thread is created, and `execute` returns its `thread_id` next to the
+/// answer, to continue it. `stream` streams the run instead of polling it.
+///
+/// # Example
+/// ```rust,ignore
+/// let chain = OpenAIAssistantChain::create_assistant(
+///     OpenAIConfig::default(),
+///     "gpt-4o",
+///     "Math tutor",
+///     "You are a personal math tutor, use the calculator.",
+///     &[Arc::new(Calculator::default())],
+/// )
+/// .await?;
+///
+/// let output = chain.execute(prompt_args! {"input" => "What is 3 to the 4th?"}).await?;
+/// let thread_id = output["thread_id"].clone();
+/// let answer = chain
+///     .invoke(prompt_args! {"input" => "And to the 5th?", "thread_id" => thread_id})
+///     .await?;
+/// ```
+#[derive(Clone)]
+pub struct OpenAIAssistantChain<C: Config> {
+    client: AssistantsClient<C>,
+    assistant_id: String,
+    tools: Vec<Arc<dyn Tool>>,
+    additional_instructions: Option<String>,
+    poll_interval: Duration,
+    input_key: String,
+    output_key: String,
+}
+
+impl<C: Config> OpenAIAssistantChain<C> {
+    /// The chain of an existing assistant.
+    pub fn new<S: Into<String>>(config: C, assistant_id: S) -> Self {
+        Self {
+            client: AssistantsClient::new(config),
+            assistant_id: assistant_id.into(),
+            tools: Vec::new(),
+            additional_instructions: None,
+            poll_interval: Duration::from_millis(500),
+            input_key: "input".to_string(),
+            output_key: DEFAULT_OUTPUT_KEY.to_string(),
+        }
+    }
+
+    /// Creates an assistant with the functions of `tools`, and its chain calling them.
+    pub async fn create_assistant(
+        config: C,
+        model: &str,
+        name: &str,
+        instructions: &str,
+        tools: &[Arc<dyn Tool>],
+    ) -> Result<Self, ChainError> {
+        let functions: Vec<Value> = tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool.name(),
+                        "description": tool.description(),
+                        "parameters": tool.parameters(),
+                    },
+                })
+            })
+            .collect();
+        let client = AssistantsClient::new(config.clone());
+        let assistant = client
+            .create_assistant(json!({
+                "model": model,
+                "name": name,
+                "instructions": instructions,
+                "tools": functions,
+            }))
+            .await?;
+        Ok(Self::new(config, assistant.id).with_tools(tools))
+    }
+
+    /// The tools answering the function calls of the assistant, by name. The assistant
+    /// must have the functions, see `create_assistant`.
+    pub fn with_tools(mut self, tools: &[Arc<dyn Tool>]) -> Self {
+        self.tools = tools.to_vec();
+        self
+    }
+
+    /// Instructions added to the instructions of the assistant for the runs of the chain.
+    pub fn with_additional_instructions<S: Into<String>>(mut self, instructions: S) -> Self {
+        self.additional_instructions = Some(instructions.into());
+        self
+    }
+
+    /// How often a run is checked until it completes. Default: 500ms
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Retries the requests to the API rejected with a transient error.
+    pub fn with_retry_policy(mut self, retry_policy: LLMRetryPolicy) -> Self {
+        self.client.set_retry_policy(retry_policy);
+        self
+    }
+
+    pub fn with_input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = input_key.into();
+        self
+    }
+
+    pub fn with_output_key<S: Into<String>>(mut self, output_key: S) -> Self {
+        self.output_key = output_key.into();
+        self
+    }
+
+    pub fn assistant_id(&self) -> &str {
+        &self.assistant_id
+    }
+
+    pub async fn retrieve_assistant(&self) -> Result<OpenAIAssistant, ChainError> {
+        Ok(self.client.retrieve_assistant(&self.assistant_id).await?)
+    }
+
+    /// Creates an empty thread, e.g. to stream its first run.
+    pub async fn create_thread(&self) -> Result<String, ChainError> {
+        Ok(self.client.create_thread().await?.id)
+    }
+
+    pub async fn delete_thread(&self, thread_id: &str) -> Result<(), ChainError> {
+        Ok(self.client.delete_thread(thread_id).await?)
+    }
+
+    fn run_body(&self) -> Value {
+        let mut body = json!({ "assistant_id": self.assistant_id });
+        if let Some(instructions) = &self.additional_instructions {
+            body["additional_instructions"] = json!(instructions);
+        }
+        body
+    }
+
+    /// Adds the input to its thread, created if there is none, returning the thread id.
+    async fn add_input(&self, input_variables: &PromptArgs) -> Result<String, ChainError> {
+        let input = match input_variables.get(&self.input_key) {
+            Some(Value::String(input)) => input.clone(),
+            Some(input) => input.to_string(),
+            None => return Err(ChainError::MissingInputVariable(self.input_key.clone())),
+        };
+        let thread_id = match input_variables
+            .get(OPENAI_ASSISTANT_THREAD_ID_KEY)
+            .and_then(Value::as_str)
+        {
+            Some(thread_id) => thread_id.to_string(),
+            None => self.create_thread().await?,
+        };
+        self.client.create_message(&thread_id, &input).await?;
+        Ok(thread_id)
+    }
+
+    /// Calls the tool of a function call of the run. The errors of the tool are its
+    /// output, so the assistant can recover from them.
+    async fn call_tool(&self, call: &RunToolCall) -> Result<String, ChainError> {
+        let name = &call.function.name;
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.name() == *name)
+            .ok_or_else(|| ChainError::AgentError(format!("Tool {} not found", name)))?;
+        let output = match CallbackManager::new()
+            .start_run(name.clone(), RunType::Tool)
+            .trace_tool(
+                &call.function.arguments,
+                tool.call(&call.function.arguments),
+            )
+            .await
+        {
+            Ok(output) => output,
+            Err(e) => format!("The tool return the following error: {}", e),
+        };
+        Ok(output)
+    }
+
+    async fn tool_outputs(&self, run: &Run) -> Result<Vec<Value>, ChainError> {
+        let mut outputs = Vec::new();
+        for call in run.tool_calls() {
+            let output = self.call_tool(call).await?;
+            outputs.push(json!({ "tool_call_id": call.id, "output": output }));
+        }
+        Ok(outputs)
+    }
+
+    /// Polls the run until it completes, answering its function calls. The run is
+    /// cancelled if they fail, so the thread can take new messages.
+    async fn complete_run(&self, thread_id: &str, mut run: Run) -> Result<Run, ChainError> {
+        loop {
+            if run.is_pending() {
+                runtime::sleep(self.poll_interval).await;
+                run = self.client.retrieve_run(thread_id, &run.id).await?;
+                continue;
+            }
+            match run.status.as_str() {
+                "completed" => return Ok(run),
+                "requires_action" => {
+                    let outputs = match self.tool_outputs(&run).await {
+                        Ok(outputs) => outputs,
+                        Err(e) => {
+                            let _ = self.client.cancel_run(thread_id, &run.id).await;
+                            return Err(e);
+                        }
+                    };
+                    run = self
+                        .client
+                        .submit_tool_outputs(thread_id, &run.id, &outputs)
+                        .await?;
+                }
+                _ => return Err(run.error().into()),
+            }
+        }
+    }
+}
+
+impl Default for OpenAIAssistantChain<OpenAIConfig> {
+    /// The chain of the assistant of the `OPENAI_ASSISTANT_ID` environment variable.
+    fn default() -> Self {
+        Self::new(
+            OpenAIConfig::default(),
+            std::env::var("OPENAI_ASSISTANT_ID").unwrap_or_default(),
+        )
+    }
+}
+
+#[async_trait]
+impl<C: Config + Send + Sync + 'static> Chain for OpenAIAssistantChain<C> {
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        let output = self.execute(input_variables).await?;
+        let result: GenerateResult = serde_json::from_value(output[DEFAULT_RESULT_KEY].clone())?;
+        Ok(result)
+    }
+
+    async fn execute(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<HashMap<String, Value>, ChainError> {
+        let run = CallbackManager::new().start_run("OpenAIAssistantChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain_outputs(&inputs, async move {
+            let thread_id = self.add_input(&input_variables).await?;
+            let run = self.client.create_run(&thread_id, &self.run_body()).await?;
+            let run = self.complete_run(&thread_id, run).await?;
+            let answer = self.client.run_answer(&thread_id, &run.id).await?;
+
+            let result = GenerateResult {
+                tokens: run.token_usage(),
+                generation: answer,
+                ..Default::default()
+            };
+            Ok(HashMap::from([
+                (self.output_key.clone(), json!(result.generation)),
+                (OPENAI_ASSISTANT_THREAD_ID_KEY.to_string(), json!(thread_id)),
+                (OPENAI_ASSISTANT_RUN_ID_KEY.to_string(), json!(run.id)),
+                (DEFAULT_RESULT_KEY.to_string(), json!(result)),
+            ]))
+        })
+        .await
+    }
+
+    /// Streams the text deltas of the messages of the run, and the calls of the tools
+    /// between `StreamData::tool_start` and `StreamData::tool_end`.
+    async fn stream(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
+    {
+        let run = CallbackManager::new().start_run("OpenAIAssistantChain", RunType::Chain);
+        run.on_chain_start(&input_variables);
+        let thread_id = run.scope(self.add_input(&input_variables)).await?;
+        let mut events = self.client.stream_run(&thread_id, &self.run_body())?;
+        // The tools run in the stream, inside the scope of its run.
+        let chain = self.clone();
+        let scope = run.clone();
+        let stream = runtime::send_stream(async_stream::stream! {
+            while let Some(event) = events.next().await {
+                let message = match event {
+                    Ok(Event::Open) => continue,
+                    Ok(Event::Message(message)) => message,
+                    Err(reqwest_eventsource::Error::InvalidStatusCode(_, response)) => {
+                        if let Err(e) = check_status(response).await {
+                            yield Err(e.into());
+                        }
+                        break;
+                    }
+                    // The event source would reconnect at the end of the stream.
+                    Err(reqwest_eventsource::Error::StreamEnded) => break,
+                    Err(e) => {
+                        yield Err(LLMError::OtherError(e.to_string()).into());
+                        break;
+                    }
+                };
+                match message.event.as_str() {
+                    "thread.message.delta" => {
+                        let delta: Value = match serde_json::from_str(&message.data) {
+                            Ok(delta) => delta,
+                            Err(e) => {
+                                yield Err(e.into());
+                                break;
+                            }
+                        };
+                        let text = delta["delta"]["content"]
+                            .as_array()
+                            .map(|content| content_text(content))
+                            .unwrap_or_default();
+                        if !text.is_empty() {
+                            yield Ok(StreamData::new(delta, text));
+                        }
+                    }
+                    "thread.run.requires_action" => {
+                        let run: Run = match serde_json::from_str(&message.data) {
+                            Ok(run) => run,
+                            Err(e) => {
+                                yield Err(e.into());
+                                break;
+                            }
+                        };
+                        let mut outputs = Vec::new();
+                        for call in run.tool_calls() {
+                            yield Ok(StreamData::tool_start(
+                                &call.function.name,
+                                &call.function.arguments,
+                            ));
+                            match scope.scope(chain.call_tool(call)).await {
+                                Ok(output) => {
+                                    yield Ok(StreamData::tool_end(&call.function.name, &output));
+                                    outputs.push(json!({ "tool_call_id": call.id, "output": output }));
+                                }
+                                Err(e) => {
+                                    let _ = chain.client.cancel_run(&thread_id, &run.id).await;
+                                    yield Err(e);
+                                    return;
+                                }
+                            }
+                        }
+                        events.close();
+                        events = match chain.client.stream_tool_outputs(&thread_id, &run.id, &outputs) {
+                            Ok(events) => events,
+                            Err(e) => {
+                                yield Err(e.into());
+                                return;
+                            }
+                        };
+                    }
+                    "thread.run.failed" | "thread.run.cancelled" | "thread.run.expired"
+                    | "thread.run.incomplete" => {
+                        match serde_json::from_str::<Run>(&message.data) {
+                            Ok(run) => yield Err(run.error().into()),
+                            Err(e) => yield Err(e.into()),
+                        }
+                        break;
+                    }
+                    "error" => {
+                        yield Err(LLMError::OtherError(message.data).into());
+                        break;
+                    }
+                    "done" => break,
+                    _ => {}
+                }
+            }
+            events.close();
+        });
+        Ok(run.trace_chain_stream(Box::pin(stream)))
+    }
+
+    fn get_input_keys(&self) -> Vec<String> {
+        vec![self.input_key.clone()]
+    }
+
+    fn get_output_keys(&self) -> Vec<String> {
+        vec![
+            self.output_key.clone(),
+            OPENAI_ASSISTANT_THREAD_ID_KEY.to_string(),
+            OPENAI_ASSISTANT_RUN_ID_KEY.to_string(),
+            DEFAULT_RESULT_KEY.to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use mockito::Matcher;
+
+    use super::*;
+    use crate::{prompt_args, schemas::StreamKind};
+
+    /// Adds the numbers `a` and `b`.
+    struct AddTool;
+
+    #[async_trait]
+    impl Tool for AddTool {
+        fn name(&self) -> String {
+            "add".to_string()
+        }
+
+        fn description(&self) -> String {
+            "Adds two numbers".to_string()
+        }
+
+        async fn parse_input(&self, input: &str) -> Value {
+            serde_json::from_str(input).unwrap_or_default()
+        }
+
+        async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+            let a = input["a"].as_f64().ok_or("a is missing")?;
+            let b = input["b"].as_f64().ok_or("b is missing")?;
+            Ok((a + b).to_string())
+        }
+    }
+
+    fn requires_action() -> Value {
+        json!({
+            "id": "run_1",
+            "status": "requires_action",
+            "required_action": {
+                "type": "submit_tool_outputs",
+                "submit_tool_outputs": {
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "add", "arguments": "{\"a\": 2, \"b\": 2}"},
+                    }],
+                },
+            },
+        })
+    }
+
+    async fn chain(server: &mockito::Server) -> OpenAIAssistantChain<OpenAIConfig> {
+        OpenAIAssistantChain::new(
+            OpenAIConfig::new()
+                .with_api_base(server.url())
+                .with_api_key("key"),
+            "asst_1",
+        )
+        .with_tools(&[Arc::new(AddTool)])
+        .with_poll_interval(Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn test_openai_assistant_chain() {
+        let mut server = mockito::Server::new_async().await;
+        let thread = server
+            .mock("POST", "/threads")
+            .match_header("OpenAI-Beta", "assistants=v2")
+            .with_body(json!({"id": "thread_1"}).to_string())
+            .create_async()
+            .await;
+        let message = server
+            .mock("POST", "/threads/thread_1/messages")
+            .match_body(Matcher::Json(
+                json!({"role": "user", "content": "What is 2 + 2?"}),
+            ))
+            .with_body(json!({"id": "msg_1"}).to_string())
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/threads/thread_1/runs")
+            .match_body(Matcher::Json(json!({"assistant_id": "asst_1"})))
+            .with_body(requires_action().to_string())
+            .create_async()
+            .await;
+        let outputs = server
+            .mock("POST", "/threads/thread_1/runs/run_1/submit_tool_outputs")
+            .match_body(Matcher::Json(json!({
+                "tool_outputs": [{"tool_call_id": "call_1", "output": "4"}],
+                "stream": false,
+            })))
+            .with_body(json!({"id": "run_1", "status": "in_progress"}).to_string())
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/threads/thread_1/runs/run_1")
+            .with_body(
+                json!({
+                    "id": "run_1",
+                    "status": "completed",
+                    "usage": {"prompt_tokens": 20, "completion_tokens": 5, "total_tokens": 25},
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/threads/thread_1/messages")
+            .match_query(Matcher::UrlEncoded("run_id".into(), "run_1".into()))
+            .with_body(
+                json!({
+                    "object": "list",
+                    "data": [{
+                        "id": "msg_2",
+                        "role": "assistant",
+                        "content": [{"type": "text", "text": {"value": "2 + 2 is 4", "annotations": []}}],
+                    }],
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let chain = chain(&server).await;
+        let output = chain
+            .execute(prompt_args! {"input" => "What is 2 + 2?"})
+            .await
+            .unwrap();
+        assert_eq!(output[DEFAULT_OUTPUT_KEY], "2 + 2 is 4");
+        assert_eq!(output[OPENAI_ASSISTANT_THREAD_ID_KEY], "thread_1");
+        assert_eq!(output[OPENAI_ASSISTANT_RUN_ID_KEY], "run_1");
+        assert_eq!(output[DEFAULT_RESULT_KEY]["tokens"]["total_tokens"], 25);
+
+        // The thread is continued.
+        let result = chain
+            .call(prompt_args! {"input" => "What is 2 + 2?", "thread_id" => "thread_1"})
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "2 + 2 is 4");
+        thread.assert_async().await;
+        message.assert_async().await;
+        outputs.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_openai_assistant_chain_errors() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/threads/thread_1/messages")
+            .with_body(json!({"id": "msg_1"}).to_string())
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/threads/thread_1/runs")
+            .with_body(
+                json!({
+                    "id": "run_1",
+                    "status": "failed",
+                    "last_error": {"code": "rate_limit_exceeded", "message": "Slow down"},
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let chain = chain(&server).await;
+        let result = chain
+            .invoke(prompt_args! {"input" => "Hi", "thread_id" => "thread_1"})
+            .await;
+        assert!(
+            matches!(
+                &result,
+                Err(ChainError::LLMError(LLMError::RateLimited { .. }))
+            ),
+            "{:?}",
+            result
+        );
+        assert!(result.unwrap_err().is_transient());
+
+        server
+            .mock("POST", "/assistants")
+            .with_status(401)
+            .with_body(
+                json!({"error": {"message": "Incorrect API key", "type": "invalid_request_error", "code": "invalid_api_key"}})
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let result = OpenAIAssistantChain::create_assistant(
+            OpenAIConfig::new().with_api_base(server.url()),
+            "gpt-4o",
+            "Calculator",
+            "Add the numbers",
+            &[Arc::new(AddTool)],
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(ChainError::LLMError(LLMError::AuthenticationFailed(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_openai_assistant_chain_stream() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/threads/thread_1/messages")
+            .with_body(json!({"id": "msg_1"}).to_string())
+            .create_async()
+            .await;
+        let event = |event: &str, data: Value| format!("event: {}\ndata: {}\n\n", event, data);
+        server
+            .mock("POST", "/threads/thread_1/runs")
+            .match_body(Matcher::PartialJson(json!({"stream": true})))
+            .with_header("content-type", "text/event-stream")
+            .with_body(event("thread.run.requires_action", requires_action()))
+            .create_async()
+            .await;
+        let delta = |text: &str| json!({"delta": {"content": [{"index": 0, "type": "text", "text": {"value": text}}]}});
+        server
+            .mock("POST", "/threads/thread_1/runs/run_1/submit_tool_outputs")
+            .match_body(Matcher::PartialJson(json!({"stream": true})))
+            .with_header("content-type", "text/event-stream")
+            .with_body(format!(
+                "{}{}{}event: done\ndata: [DONE]\n\n",
+                event("thread.run.step.created", json!({"id": "step_1"})),
+                event("thread.message.delta", delta("2 + 2 ")),
+                event("thread.message.delta", delta("is 4")),
+            ))
+            .create_async()
+            .await;
+
+        let chain = chain(&server).await;
+        let events: Vec<StreamData> = chain
+            .stream(prompt_args! {"input" => "What is 2 + 2?", "thread_id" => "thread_1"})
+            .await
+            .unwrap()
+            .map(|data| data.unwrap())
+            .collect()
+            .await;
+
+        let kinds: Vec<StreamKind> = events.iter().map(|data| data.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                StreamKind::ToolStart,
+                StreamKind::ToolEnd,
+                StreamKind::Token,
+                StreamKind::Token,
+            ]
+        );
+        assert_eq!(events[1].value["output"], "4");
+        let answer: String = events.iter().map(|data| data.content.as_str()).collect();
+        assert_eq!(answer, "2 + 2 is 4");
+    }
+}
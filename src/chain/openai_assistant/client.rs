@@ -0,0 +1,349 @@
+//! A client of the Assistants API v2. async-openai only has the types of the v1 API,
+//! which was retired, so the requests are sent as JSON with the config of async-openai.
+
+use async_openai::{
+    config::{Config, OPENAI_BETA_HEADER},
+    error::{ApiError, OpenAIError},
+};
+use reqwest::{header::HeaderValue, Method, RequestBuilder, Response};
+use reqwest_eventsource::EventSource;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    language_models::{retry_after, send_with_retry, LLMError, LLMRetryPolicy, TokenUsage},
+    runtime,
+};
+
+/// An assistant of the Assistants API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIAssistant {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub model: String,
+    #[serde(default)]
+    pub instructions: Option<String>,
+    /// The tools of the assistant, e.g. `{"type": "code_interpreter"}` or the functions.
+    #[serde(default)]
+    pub tools: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Thread {
+    pub(crate) id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Run {
+    pub(crate) id: String,
+    pub(crate) status: String,
+    #[serde(default)]
+    pub(crate) required_action: Option<RequiredAction>,
+    #[serde(default)]
+    pub(crate) last_error: Option<RunError>,
+    #[serde(default)]
+    pub(crate) usage: Option<RunUsage>,
+}
+
+impl Run {
+    /// Whether the run is still being processed, so it has to be polled.
+    pub(crate) fn is_pending(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "queued" | "in_progress" | "cancelling"
+        )
+    }
+
+    /// The tool calls the run waits for.
+    pub(crate) fn tool_calls(&self) -> &[RunToolCall] {
+        match &self.required_action {
+            Some(action) => &action.submit_tool_outputs.tool_calls,
+            None => &[],
+        }
+    }
+
+    pub(crate) fn token_usage(&self) -> Option<TokenUsage> {
+        self.usage
+            .as_ref()
+            .map(|usage| TokenUsage::new(usage.prompt_tokens, usage.completion_tokens))
+    }
+
+    /// The error of a run which didn't complete.
+    pub(crate) fn error(&self) -> LLMError {
+        let message = match &self.last_error {
+            Some(error) => format!("Run {} {}: {}", self.id, self.status, error.message),
+            None => format!("Run {} {}", self.id, self.status),
+        };
+        match self.last_error.as_ref().map(|error| error.code.as_str()) {
+            Some("rate_limit_exceeded") => LLMError::RateLimited {
+                retry_after: None,
+                message,
+            },
+            Some("server_error") => LLMError::ProviderUnavailable {
+                status: None,
+                message,
+            },
+            _ => LLMError::OtherError(message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RequiredAction {
+    pub(crate) submit_tool_outputs: SubmitToolOutputs,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct SubmitToolOutputs {
+    pub(crate) tool_calls: Vec<RunToolCall>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RunToolCall {
+    pub(crate) id: String,
+    pub(crate) function: RunFunctionCall,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RunFunctionCall {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) arguments: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RunError {
+    #[serde(default)]
+    pub(crate) code: String,
+    #[serde(default)]
+    pub(crate) message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RunUsage {
+    pub(crate) prompt_tokens: u32,
+    pub(crate) completion_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThreadMessage {
+    role: String,
+    #[serde(default)]
+    content: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct List<T> {
+    data: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct WrappedError {
+    error: ApiError,
+}
+
+/// The text of the `text` parts of the content of a message or of a message delta.
+pub(crate) fn content_text(content: &[Value]) -> String {
+    content
+        .iter()
+        .filter(|part| part["type"] == "text")
+        .filter_map(|part| part["text"]["value"].as_str())
+        .collect()
+}
+
+#[derive(Clone)]
+pub(crate) struct AssistantsClient<C: Config> {
+    config: C,
+    client: reqwest::Client,
+    retry_policy: Option<LLMRetryPolicy>,
+}
+
+impl<C: Config> AssistantsClient<C> {
+    pub(crate) fn new(config: C) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            retry_policy: None,
+        }
+    }
+
+    pub(crate) fn set_retry_policy(&mut self, retry_policy: LLMRetryPolicy) {
+        self.retry_policy = Some(retry_policy);
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut headers = self.config.headers();
+        headers.insert(
+            OPENAI_BETA_HEADER,
+            HeaderValue::from_static("assistants=v2"),
+        );
+        self.client
+            .request(method, self.config.url(path))
+            .query(&self.config.query())
+            .headers(headers)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, LLMError> {
+        runtime::send(async {
+            let res = send_with_retry(self.retry_policy.as_ref(), request).await?;
+            Ok(check_status(res).await?.json().await?)
+        })
+        .await
+    }
+
+    pub(crate) async fn create_assistant(&self, body: Value) -> Result<OpenAIAssistant, LLMError> {
+        self.send(self.request(Method::POST, "/assistants").json(&body))
+            .await
+    }
+
+    pub(crate) async fn retrieve_assistant(
+        &self,
+        assistant_id: &str,
+    ) -> Result<OpenAIAssistant, LLMError> {
+        self.send(self.request(Method::GET, &format!("/assistants/{}", assistant_id)))
+            .await
+    }
+
+    pub(crate) async fn create_thread(&self) -> Result<Thread, LLMError> {
+        self.send(self.request(Method::POST, "/threads").json(&json!({})))
+            .await
+    }
+
+    pub(crate) async fn delete_thread(&self, thread_id: &str) -> Result<(), LLMError> {
+        self.send::<Value>(self.request(Method::DELETE, &format!("/threads/{}", thread_id)))
+            .await?;
+        Ok(())
+    }
+
+    pub(crate) async fn create_message(
+        &self,
+        thread_id: &str,
+        content: &str,
+    ) -> Result<(), LLMError> {
+        self.send::<Value>(
+            self.request(Method::POST, &format!("/threads/{}/messages", thread_id))
+                .json(&json!({ "role": "user", "content": content })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    fn create_run_request(&self, thread_id: &str, body: &Value) -> RequestBuilder {
+        self.request(Method::POST, &format!("/threads/{}/runs", thread_id))
+            .json(body)
+    }
+
+    pub(crate) async fn create_run(&self, thread_id: &str, body: &Value) -> Result<Run, LLMError> {
+        self.send(self.create_run_request(thread_id, body)).await
+    }
+
+    pub(crate) async fn retrieve_run(
+        &self,
+        thread_id: &str,
+        run_id: &str,
+    ) -> Result<Run, LLMError> {
+        self.send(self.request(
+            Method::GET,
+            &format!("/threads/{}/runs/{}", thread_id, run_id),
+        ))
+        .await
+    }
+
+    fn submit_tool_outputs_request(
+        &self,
+        thread_id: &str,
+        run_id: &str,
+        tool_outputs: &[Value],
+        stream: bool,
+    ) -> RequestBuilder {
+        self.request(
+            Method::POST,
+            &format!("/threads/{}/runs/{}/submit_tool_outputs", thread_id, run_id),
+        )
+        .json(&json!({ "tool_outputs": tool_outputs, "stream": stream }))
+    }
+
+    pub(crate) async fn submit_tool_outputs(
+        &self,
+        thread_id: &str,
+        run_id: &str,
+        tool_outputs: &[Value],
+    ) -> Result<Run, LLMError> {
+        self.send(self.submit_tool_outputs_request(thread_id, run_id, tool_outputs, false))
+            .await
+    }
+
+    pub(crate) async fn cancel_run(&self, thread_id: &str, run_id: &str) -> Result<(), LLMError> {
+        self.send::<Value>(self.request(
+            Method::POST,
+            &format!("/threads/{}/runs/{}/cancel", thread_id, run_id),
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// The text of the messages of the assistant added by the run, oldest first.
+    pub(crate) async fn run_answer(
+        &self,
+        thread_id: &str,
+        run_id: &str,
+    ) -> Result<String, LLMError> {
+        let messages: List<ThreadMessage> = self
+            .send(
+                self.request(Method::GET, &format!("/threads/{}/messages", thread_id))
+                    .query(&[("run_id", run_id), ("order", "asc")]),
+            )
+            .await?;
+        Ok(messages
+            .data
+            .iter()
+            .filter(|message| message.role == "assistant")
+            .map(|message| content_text(&message.content))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    /// The server-sent events of a new run, `body` gets `"stream": true`.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn stream_run(
+        &self,
+        thread_id: &str,
+        body: &Value,
+    ) -> Result<EventSource, LLMError> {
+        let mut body = body.clone();
+        body["stream"] = Value::Bool(true);
+        EventSource::new(self.create_run_request(thread_id, &body))
+            .map_err(|e| LLMError::OtherError(e.to_string()))
+    }
+
+    /// The server-sent events of a run going on with the outputs of its tool calls.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn stream_tool_outputs(
+        &self,
+        thread_id: &str,
+        run_id: &str,
+        tool_outputs: &[Value],
+    ) -> Result<EventSource, LLMError> {
+        EventSource::new(self.submit_tool_outputs_request(thread_id, run_id, tool_outputs, true))
+            .map_err(|e| LLMError::OtherError(e.to_string()))
+    }
+}
+
+pub(crate) async fn check_status(res: Response) -> Result<Response, LLMError> {
+    let status = res.status().as_u16();
+    if res.status().is_success() {
+        return Ok(res);
+    }
+    let retry_after = retry_after(res.headers());
+    let body = res.bytes().await?;
+    let Ok(wrapped) = serde_json::from_slice::<WrappedError>(&body) else {
+        let message = String::from_utf8_lossy(&body).to_string();
+        return Err(LLMError::from_status(status, retry_after, &message)
+            .unwrap_or(LLMError::OtherError(message)));
+    };
+    if let Some(error) = LLMError::from_status(status, retry_after, &wrapped.error.message) {
+        return Err(error);
+    }
+    Err(OpenAIError::ApiError(wrapped.error).into())
+}
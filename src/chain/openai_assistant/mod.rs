@@ -0,0 +1,5 @@
+mod chain;
+mod client;
+
+pub use chain::*;
+pub use client::OpenAIAssistant;
@@ -1,7 +1,17 @@
-use futures::Future;
-use std::pin::Pin;
+use futures::{Future, Stream, StreamExt};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::HashMap, pin::Pin, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+pub use tokio_util::sync::CancellationToken;
 
-use crate::language_models::options::CallOptions;
+use crate::{
+    callbacks::{CallbackManager, StdOutCallbackHandler},
+    language_models::{options::CallOptions, LLMRetryPolicy},
+    runtime::{self, Instant},
+    schemas::{memory::BaseMemory, ResponseFormat, StreamData},
+};
+
+use super::ChainError;
 
 pub struct ChainCallOptions {
     pub max_tokens: Option<u16>,
@@ -16,6 +26,18 @@ pub struct ChainCallOptions {
     pub min_length: Option<usize>,
     pub max_length: Option<usize>,
     pub repetition_penalty: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    pub logit_bias: Option<HashMap<u32, f32>>,
+    pub response_format: Option<ResponseFormat>,
+    pub callbacks: Option<CallbackManager>,
+    pub retry: Option<LLMRetryPolicy>,
+    /// The memory of the chain, see `LLMChainBuilder::memory`.
+    pub memory: Option<Arc<Mutex<dyn BaseMemory>>>,
+    /// The maximum duration of a call of the chain, see `with_timeout`.
+    pub timeout: Option<Duration>,
+    /// Aborts the calls of the chain when cancelled, see `with_cancellation_token`.
+    pub cancellation_token: Option<CancellationToken>,
 }
 
 impl Default for ChainCallOptions {
@@ -37,6 +59,15 @@ impl ChainCallOptions {
             min_length: None,
             max_length: None,
             repetition_penalty: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            logit_bias: None,
+            response_format: None,
+            callbacks: None,
+            retry: None,
+            memory: None,
+            timeout: None,
+            cancellation_token: None,
         }
     }
 
@@ -69,6 +100,24 @@ impl ChainCallOptions {
         if let Some(repetition_penalty) = options.repetition_penalty {
             llm_option = llm_option.with_repetition_penalty(repetition_penalty);
         }
+        if let Some(frequency_penalty) = options.frequency_penalty {
+            llm_option = llm_option.with_frequency_penalty(frequency_penalty);
+        }
+        if let Some(presence_penalty) = options.presence_penalty {
+            llm_option = llm_option.with_presence_penalty(presence_penalty);
+        }
+        if let Some(logit_bias) = options.logit_bias {
+            llm_option = llm_option.with_logit_bias(logit_bias);
+        }
+        if let Some(response_format) = options.response_format {
+            llm_option = llm_option.with_response_format(response_format);
+        }
+        if let Some(callbacks) = options.callbacks {
+            llm_option = llm_option.with_callbacks(callbacks);
+        }
+        if let Some(retry) = options.retry {
+            llm_option = llm_option.with_retry(retry);
+        }
 
         if let Some(streaming_func) = options.streaming_func {
             llm_option = llm_option.with_streaming_func(streaming_func)
@@ -130,4 +179,229 @@ impl ChainCallOptions {
         self.repetition_penalty = Some(repetition_penalty);
         self
     }
+
+    pub fn with_frequency_penalty(mut self, frequency_penalty: f32) -> Self {
+        self.frequency_penalty = Some(frequency_penalty);
+        self
+    }
+
+    pub fn with_presence_penalty(mut self, presence_penalty: f32) -> Self {
+        self.presence_penalty = Some(presence_penalty);
+        self
+    }
+
+    /// Biases the sampling of tokens, by token id. The providers without this parameter
+    /// fail with `LLMError::UnsupportedOption`, as for the other sampling options.
+    pub fn with_logit_bias(mut self, logit_bias: HashMap<u32, f32>) -> Self {
+        self.logit_bias = Some(logit_bias);
+        self
+    }
+
+    /// The format of the answers of the LLM, e.g. a JSON of a schema, for the models
+    /// supporting it. See also `LLMChain::call_typed`.
+    pub fn with_response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.response_format = Some(response_format);
+        self
+    }
+
+    pub fn with_callbacks(mut self, callbacks: CallbackManager) -> Self {
+        self.callbacks = Some(callbacks);
+        self
+    }
+
+    /// Retries the calls to the LLM failing transiently, see `LLMRetryPolicy`.
+    pub fn with_retry(mut self, retry: LLMRetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    pub fn with_memory(mut self, memory: Arc<Mutex<dyn BaseMemory>>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Fails the calls of the chain lasting longer than `timeout` with
+    /// `ChainError::Timeout`, a stream failing once `timeout` has elapsed since it was
+    /// requested. The request to the LLM is dropped, so its connection is closed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fails the calls of the chain, and ends its streams, with `ChainError::Cancelled`
+    /// once the token is cancelled, e.g. when the client of a web handler disconnects.
+    /// The request to the LLM is dropped, so its connection is closed.
+    pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// Prints the prompts, completions and tool calls of the chain to the standard output.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        if verbose {
+            let callbacks = self.callbacks.take().unwrap_or_default();
+            self.callbacks = Some(callbacks.with_handler(StdOutCallbackHandler::new()));
+        }
+        self
+    }
+}
+
+/// The options of `ChainCallOptions` which can be written in a config file.
+#[derive(Default, Serialize, Deserialize)]
+struct SerializedOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_words: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repetition_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logit_bias: Option<HashMap<u32, f32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+}
+
+/// Only the plain options and the response format are serialized: the streaming function,
+/// callbacks, retry policy, memory and cancellation token are skipped.
+impl Serialize for ChainCallOptions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedOptions {
+            max_tokens: self.max_tokens,
+            temperature: self.temperature,
+            stop_words: self.stop_words.clone(),
+            top_k: self.top_k,
+            top_p: self.top_p,
+            seed: self.seed,
+            min_length: self.min_length,
+            max_length: self.max_length,
+            repetition_penalty: self.repetition_penalty,
+            frequency_penalty: self.frequency_penalty,
+            presence_penalty: self.presence_penalty,
+            logit_bias: self.logit_bias.clone(),
+            timeout_ms: self.timeout.map(|timeout| timeout.as_millis() as u64),
+            response_format: self.response_format.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ChainCallOptions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let options = SerializedOptions::deserialize(deserializer)?;
+        Ok(Self {
+            max_tokens: options.max_tokens,
+            temperature: options.temperature,
+            stop_words: options.stop_words,
+            top_k: options.top_k,
+            top_p: options.top_p,
+            seed: options.seed,
+            min_length: options.min_length,
+            max_length: options.max_length,
+            repetition_penalty: options.repetition_penalty,
+            frequency_penalty: options.frequency_penalty,
+            presence_penalty: options.presence_penalty,
+            logit_bias: options.logit_bias,
+            timeout: options.timeout_ms.map(Duration::from_millis),
+            response_format: options.response_format,
+            ..Self::new()
+        })
+    }
+}
+
+/// The timeout and cancellation token of the calls of a chain.
+#[derive(Clone, Default)]
+pub(crate) struct CallLimits {
+    timeout: Option<Duration>,
+    cancellation_token: Option<CancellationToken>,
+}
+
+impl CallLimits {
+    pub(crate) fn from_options(options: &ChainCallOptions) -> Self {
+        Self {
+            timeout: options.timeout,
+            cancellation_token: options.cancellation_token.clone(),
+        }
+    }
+
+    /// When a call starting now times out.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.timeout.map(|timeout| Instant::now() + timeout)
+    }
+
+    /// Runs the future, dropping it when the deadline passes or the token is cancelled.
+    pub(crate) async fn run<T, F>(
+        &self,
+        deadline: Option<Instant>,
+        future: F,
+    ) -> Result<T, ChainError>
+    where
+        F: Future<Output = Result<T, ChainError>>,
+    {
+        if deadline.is_none() && self.cancellation_token.is_none() {
+            return future.await;
+        }
+        tokio::select! {
+            biased;
+            _ = cancelled(self.cancellation_token.as_ref()) => Err(ChainError::Cancelled),
+            _ = sleep_until(deadline) => Err(ChainError::Timeout(self.timeout.unwrap_or_default())),
+            result = future => result,
+        }
+    }
+
+    /// The stream, ending with an error when the deadline passes or the token is cancelled.
+    pub(crate) fn stream(
+        &self,
+        deadline: Option<Instant>,
+        stream: Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>,
+    ) -> Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>> {
+        if deadline.is_none() && self.cancellation_token.is_none() {
+            return stream;
+        }
+        let limits = self.clone();
+        let mut stream = stream;
+        Box::pin(async_stream::stream! {
+            loop {
+                match limits.run(deadline, async { Ok(stream.next().await) }).await {
+                    Ok(Some(item)) => yield item,
+                    Ok(None) => break,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                }
+            }
+        })
+    }
+}
+
+async fn cancelled(cancellation_token: Option<&CancellationToken>) {
+    match cancellation_token {
+        Some(cancellation_token) => cancellation_token.cancelled().await,
+        None => futures::future::pending().await,
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => runtime::sleep_until(deadline).await,
+        None => futures::future::pending().await,
+    }
 }
@@ -0,0 +1,135 @@
+use crate::{
+    chain::{Chain, ChainError, StuffDocumentBuilder, DEFAULT_OUTPUT_KEY},
+    language_models::llm::LLM,
+    prompt::FormatPrompter,
+    schemas::Retriever,
+};
+
+use super::RetrievalQAChain;
+
+const RETRIEVAL_QA_DEFAULT_INPUT_KEY: &str = "question";
+
+///Retrieval QA Chain Builder
+/// # Usage
+/// ## Convensional way
+/// ```rust,ignore
+/// let chain = RetrievalQAChainBuilder::new()
+///     .llm(llm)
+///     .retriever(RetrieverMock {})
+///     .build()
+///     .expect("Error building RetrievalQAChain");
+///
+/// ```
+/// ## Custom way
+/// ```rust,ignore
+///
+/// let llm = Box::new(OpenAI::default().with_model(OpenAIModel::Gpt35.to_string()));
+/// let combine_documents_chain = StuffDocument::load_stuff_qa(llm.clone_box());
+/// let chain = RetrievalQAChainBuilder::new()
+///     .combine_documents_chain(Box::new(combine_documents_chain))
+///     .retriever(RetrieverMock {})
+///     .build()
+///     .expect("Error building RetrievalQAChain");
+/// ```
+///
+pub struct RetrievalQAChainBuilder {
+    llm: Option<Box<dyn LLM>>,
+    retriever: Option<Box<dyn Retriever>>,
+    combine_documents_chain: Option<Box<dyn Chain>>,
+    prompt: Option<Box<dyn FormatPrompter>>,
+    return_source_documents: bool,
+    input_key: String,
+    output_key: String,
+}
+
+impl RetrievalQAChainBuilder {
+    pub fn new() -> Self {
+        RetrievalQAChainBuilder {
+            llm: None,
+            retriever: None,
+            combine_documents_chain: None,
+            prompt: None,
+            return_source_documents: true,
+            input_key: RETRIEVAL_QA_DEFAULT_INPUT_KEY.to_string(),
+            output_key: DEFAULT_OUTPUT_KEY.to_string(),
+        }
+    }
+
+    pub fn retriever<R: Into<Box<dyn Retriever>>>(mut self, retriever: R) -> Self {
+        self.retriever = Some(retriever.into());
+        self
+    }
+
+    ///If you want to add a custom prompt,keep in mind which variables are obligatory:
+    /// `context` and `question`.
+    pub fn prompt<P: Into<Box<dyn FormatPrompter>>>(mut self, prompt: P) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = input_key.into();
+        self
+    }
+
+    pub fn output_key<S: Into<String>>(mut self, output_key: S) -> Self {
+        self.output_key = output_key.into();
+        self
+    }
+
+    pub fn llm<L: Into<Box<dyn LLM>>>(mut self, llm: L) -> Self {
+        self.llm = Some(llm.into());
+        self
+    }
+
+    ///Chain designed to take the documents and the question and generate an output
+    pub fn combine_documents_chain<C: Into<Box<dyn Chain>>>(
+        mut self,
+        combine_documents_chain: C,
+    ) -> Self {
+        self.combine_documents_chain = Some(combine_documents_chain.into());
+        self
+    }
+
+    pub fn return_source_documents(mut self, return_source_documents: bool) -> Self {
+        self.return_source_documents = return_source_documents;
+        self
+    }
+
+    pub fn build(mut self) -> Result<RetrievalQAChain, ChainError> {
+        if let Some(llm) = self.llm {
+            let combine_documents_chain = {
+                let mut builder = StuffDocumentBuilder::new().llm(llm);
+                if let Some(prompt) = self.prompt {
+                    builder = builder.prompt(prompt);
+                }
+                builder.build()?
+            };
+            self.combine_documents_chain = Some(Box::new(combine_documents_chain));
+        }
+
+        let retriever = self
+            .retriever
+            .ok_or_else(|| ChainError::MissingObject("Retriever must be set".into()))?;
+
+        let combine_documents_chain = self.combine_documents_chain.ok_or_else(|| {
+            ChainError::MissingObject(
+                "Combine documents chain must be set or llm must be set".into(),
+            )
+        })?;
+
+        Ok(RetrievalQAChain {
+            retriever,
+            combine_documents_chain,
+            return_source_documents: self.return_source_documents,
+            input_key: self.input_key,
+            output_key: self.output_key,
+        })
+    }
+}
+
+impl Default for RetrievalQAChainBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,5 @@
+mod builder;
+pub use builder::*;
+
+mod retrieval_qa;
+pub use retrieval_qa::*;
@@ -0,0 +1,203 @@
+use futures::Stream;
+use std::{collections::HashMap, pin::Pin};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    chain::{Chain, ChainError, StuffQAPromptBuilder, DEFAULT_RESULT_KEY},
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    schemas::{Document, Retriever, StreamData},
+};
+
+pub const RETRIEVAL_QA_DEFAULT_SOURCE_DOCUMENT_KEY: &str = "source_documents";
+
+/// Answers a question with the documents found by a retriever, stuffed in the prompt of
+/// the combine documents chain.
+///
+/// `execute` returns the documents used under `source_documents` next to the answer.
+pub struct RetrievalQAChain {
+    pub(crate) retriever: Box<dyn Retriever>,
+    pub(crate) combine_documents_chain: Box<dyn Chain>,
+    pub(crate) return_source_documents: bool,
+    pub(crate) input_key: String,  //Default is `question`
+    pub(crate) output_key: String, //default is output
+}
+
+impl RetrievalQAChain {
+    async fn get_documents(
+        &self,
+        input_variables: &PromptArgs,
+    ) -> Result<(String, Vec<Document>), ChainError> {
+        let question = match input_variables.get(&self.input_key) {
+            Some(Value::String(question)) => question.clone(),
+            Some(question) => question.to_string(),
+            None => return Err(ChainError::MissingInputVariable(self.input_key.clone())),
+        };
+
+        let documents = self
+            .retriever
+            .get_relevant_documents(&question)
+            .await
+            .map_err(|e| ChainError::RetrieverError(e.to_string()))?;
+
+        Ok((question, documents))
+    }
+}
+
+#[async_trait]
+impl Chain for RetrievalQAChain {
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        let output = self.execute(input_variables).await?;
+        let result: GenerateResult = serde_json::from_value(output[DEFAULT_RESULT_KEY].clone())?;
+        Ok(result)
+    }
+
+    async fn execute(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<HashMap<String, Value>, ChainError> {
+        let run = CallbackManager::new().start_run("RetrievalQAChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain_outputs(&inputs, async move {
+            let (question, documents) = self.get_documents(&input_variables).await?;
+
+            let output = self
+                .combine_documents_chain
+                .call(
+                    StuffQAPromptBuilder::new()
+                        .documents(&documents)
+                        .question(question)
+                        .build(),
+                )
+                .await?;
+
+            let mut result = HashMap::new();
+            result.insert(self.output_key.clone(), json!(output.generation));
+            result.insert(DEFAULT_RESULT_KEY.to_string(), json!(output));
+
+            if self.return_source_documents {
+                result.insert(
+                    RETRIEVAL_QA_DEFAULT_SOURCE_DOCUMENT_KEY.to_string(),
+                    json!(documents),
+                );
+            }
+
+            Ok(result)
+        })
+        .await
+    }
+
+    async fn stream(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
+    {
+        let (question, documents) = self.get_documents(&input_variables).await?;
+
+        self.combine_documents_chain
+            .stream(
+                StuffQAPromptBuilder::new()
+                    .documents(&documents)
+                    .question(question)
+                    .build(),
+            )
+            .await
+    }
+
+    fn get_input_keys(&self) -> Vec<String> {
+        vec![self.input_key.clone()]
+    }
+
+    fn get_output_keys(&self) -> Vec<String> {
+        let mut keys = vec![self.output_key.clone()];
+        if self.return_source_documents {
+            keys.push(RETRIEVAL_QA_DEFAULT_SOURCE_DOCUMENT_KEY.to_string());
+        }
+
+        keys.push(DEFAULT_RESULT_KEY.to_string());
+
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use crate::{
+        chain::{get_output, RetrievalQAChainBuilder, DEFAULT_OUTPUT_KEY},
+        llm::FakeLLM,
+        prompt_args,
+    };
+
+    use super::*;
+
+    struct RetrieverTest {}
+    #[async_trait]
+    impl Retriever for RetrieverTest {
+        async fn get_relevant_documents(
+            &self,
+            question: &str,
+        ) -> Result<Vec<Document>, Box<dyn Error>> {
+            Ok(vec![
+                Document::new(format!("Documents about: {}", question)),
+                Document::new("Luis lives in Peru"),
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrieval_qa_returns_source_documents() {
+        let chain = RetrievalQAChainBuilder::new()
+            .llm(FakeLLM::echo())
+            .retriever(RetrieverTest {})
+            .build()
+            .expect("Error building RetrievalQAChain");
+
+        let output = chain
+            .execute(prompt_args! {"question" => "Where does Luis live?"})
+            .await
+            .unwrap();
+
+        let answer = output[DEFAULT_OUTPUT_KEY].as_str().unwrap();
+        assert!(answer.contains("Documents about: Where does Luis live?"));
+        assert!(answer.contains("Luis lives in Peru"));
+        assert!(answer.contains("Question:Where does Luis live?"));
+        assert_eq!(
+            chain.get_output_keys(),
+            vec![
+                DEFAULT_OUTPUT_KEY,
+                RETRIEVAL_QA_DEFAULT_SOURCE_DOCUMENT_KEY,
+                DEFAULT_RESULT_KEY
+            ]
+        );
+
+        let documents: Vec<Document> =
+            get_output(&output, RETRIEVAL_QA_DEFAULT_SOURCE_DOCUMENT_KEY).unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1].page_content, "Luis lives in Peru");
+    }
+
+    #[tokio::test]
+    async fn test_retrieval_qa_without_source_documents() {
+        let chain = RetrievalQAChainBuilder::new()
+            .llm(FakeLLM::echo())
+            .retriever(RetrieverTest {})
+            .return_source_documents(false)
+            .build()
+            .unwrap();
+
+        let output = chain
+            .execute(prompt_args! {"question" => "Where does Luis live?"})
+            .await
+            .unwrap();
+        assert!(!output.contains_key(RETRIEVAL_QA_DEFAULT_SOURCE_DOCUMENT_KEY));
+        assert!(matches!(
+            chain.invoke(prompt_args! {"query" => "Where?"}).await,
+            Err(ChainError::MissingInputVariable(_))
+        ));
+    }
+}
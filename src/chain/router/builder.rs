@@ -0,0 +1,131 @@
+use crate::{
+    chain::{options::ChainCallOptions, Chain, ChainError, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt::FormatPrompter,
+    semantic_router::RouteLayer,
+    template_jinja2,
+};
+
+use super::{prompt::DEFAULT_ROUTER_TEMPLATE, Destination, RouterChain, RouterClassifier};
+
+pub struct RouterChainBuilder {
+    llm: Option<Box<dyn LLM>>,
+    route_layer: Option<RouteLayer>,
+    options: Option<ChainCallOptions>,
+    prompt: Option<Box<dyn FormatPrompter>>,
+    destinations: Vec<Destination>,
+    default_chain: Option<Box<dyn Chain>>,
+    input_key: Option<String>,
+}
+
+impl RouterChainBuilder {
+    pub fn new() -> Self {
+        Self {
+            llm: None,
+            route_layer: None,
+            options: None,
+            prompt: None,
+            destinations: Vec::new(),
+            default_chain: None,
+            input_key: None,
+        }
+    }
+
+    /// Routes with an LLM, given the names and descriptions of the destinations.
+    pub fn llm<L: Into<Box<dyn LLM>>>(mut self, llm: L) -> Self {
+        self.llm = Some(llm.into());
+        self
+    }
+
+    /// Routes with the embeddings of a `RouteLayer` instead of an LLM, the names of its
+    /// routes being the names of the destinations.
+    pub fn route_layer(mut self, route_layer: RouteLayer) -> Self {
+        self.route_layer = Some(route_layer);
+        self
+    }
+
+    pub fn options(mut self, options: ChainCallOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    /// The prompt of the LLM router, with the `destinations` and `input` variables. It must
+    /// ask for a json with the `destination` and, optionally, the `next_inputs`.
+    pub fn prompt<P: Into<Box<dyn FormatPrompter>>>(mut self, prompt: P) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn add_destination<S: Into<String>, C: Chain + 'static>(
+        mut self,
+        name: S,
+        description: S,
+        chain: C,
+    ) -> Self {
+        self.destinations.push(Destination {
+            name: name.into(),
+            description: description.into(),
+            chain: Box::new(chain),
+        });
+        self
+    }
+
+    /// The chain of the inputs no destination suits.
+    pub fn default_chain<C: Chain + 'static>(mut self, chain: C) -> Self {
+        self.default_chain = Some(Box::new(chain));
+        self
+    }
+
+    /// The input variable routed. Default: `input`
+    pub fn input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = Some(input_key.into());
+        self
+    }
+
+    pub fn build(self) -> Result<RouterChain, ChainError> {
+        if self.destinations.is_empty() {
+            return Err(ChainError::MissingObject(
+                "At least one destination must be added".into(),
+            ));
+        }
+
+        let classifier = match (self.route_layer, self.llm) {
+            (Some(route_layer), _) => RouterClassifier::RouteLayer(route_layer),
+            (None, Some(llm)) => {
+                let prompt = match self.prompt {
+                    Some(prompt) => prompt,
+                    None => Box::new(template_jinja2!(
+                        DEFAULT_ROUTER_TEMPLATE,
+                        "destinations",
+                        "input"
+                    )),
+                };
+                RouterClassifier::Llm(
+                    LLMChainBuilder::new()
+                        .prompt(prompt)
+                        .options(self.options.unwrap_or_default())
+                        .llm(llm)
+                        .build()?,
+                )
+            }
+            (None, None) => {
+                return Err(ChainError::MissingObject(
+                    "LLM or RouteLayer must be set".into(),
+                ))
+            }
+        };
+
+        Ok(RouterChain {
+            classifier,
+            destinations: self.destinations,
+            default_chain: self.default_chain,
+            input_key: self.input_key.unwrap_or_else(|| "input".to_string()),
+        })
+    }
+}
+
+impl Default for RouterChainBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
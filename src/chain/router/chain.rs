@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    chain::{Chain, ChainError, LLMChain, DEFAULT_OUTPUT_KEY},
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    prompt_args,
+    semantic_router::RouteLayer,
+};
+
+/// The name an LLM router answers when no destination suits the input.
+pub(crate) const DEFAULT_DESTINATION: &str = "DEFAULT";
+const DESTINATION_KEY: &str = "destination";
+
+pub(crate) struct Destination {
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) chain: Box<dyn Chain>,
+}
+
+/// How the destination of an input is picked.
+pub(crate) enum RouterClassifier {
+    /// Asks an LLM, given the names and descriptions of the destinations.
+    Llm(LLMChain),
+    /// Picks the route of the utterances most similar to the input, the names of the
+    /// routes being the names of the destinations.
+    RouteLayer(RouteLayer),
+}
+
+/// Where an input is sent.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Route {
+    /// The name of the destination, `None` for the default chain.
+    pub destination: Option<String>,
+    /// The input revised by the router, given to the destination instead of the original.
+    pub next_inputs: Option<String>,
+}
+
+/// Forwards each input to the destination chain picked for it by an LLM or by a
+/// `RouteLayer`, or to the default chain when none suits it. Build it with
+/// `RouterChainBuilder`.
+///
+/// The output of `execute` is the output of the destination, with the name of the
+/// destination under `destination` (`null` for the default chain).
+pub struct RouterChain {
+    pub(crate) classifier: RouterClassifier,
+    pub(crate) destinations: Vec<Destination>,
+    pub(crate) default_chain: Option<Box<dyn Chain>>,
+    pub(crate) input_key: String,
+}
+
+impl RouterChain {
+    /// Picks the destination of the input, without calling it.
+    pub async fn route(&self, input_variables: &PromptArgs) -> Result<Route, ChainError> {
+        let input = match input_variables.get(&self.input_key) {
+            Some(Value::String(input)) => input.clone(),
+            Some(input) => input.to_string(),
+            None => return Err(ChainError::MissingInputVariable(self.input_key.clone())),
+        };
+
+        let route = match &self.classifier {
+            RouterClassifier::Llm(llm_chain) => {
+                let destinations = self
+                    .destinations
+                    .iter()
+                    .map(|destination| format!("{}: {}", destination.name, destination.description))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let output = llm_chain
+                    .invoke(prompt_args! {
+                        "destinations" => destinations,
+                        "input" => input,
+                    })
+                    .await?;
+                parse_route(&output)
+            }
+            RouterClassifier::RouteLayer(route_layer) => Route {
+                destination: route_layer
+                    .call(input)
+                    .await
+                    .map_err(|e| ChainError::OtherError(e.to_string()))?
+                    .map(|choice| choice.route),
+                next_inputs: None,
+            },
+        };
+
+        // An unknown destination is handled as no destination.
+        let destination = route.destination.filter(|name| {
+            let known = self.destinations.iter().any(|d| &d.name == name);
+            if !known && name != DEFAULT_DESTINATION {
+                log::warn!("Unknown destination {}, using the default chain", name);
+            }
+            known
+        });
+        Ok(Route {
+            destination,
+            ..route
+        })
+    }
+
+    async fn route_inputs(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<(Route, &dyn Chain, PromptArgs), ChainError> {
+        let route = self.route(&input_variables).await?;
+        let chain = match &route.destination {
+            Some(name) => self
+                .destinations
+                .iter()
+                .find(|destination| &destination.name == name)
+                .map(|destination| destination.chain.as_ref()),
+            None => self.default_chain.as_deref(),
+        }
+        .ok_or_else(|| {
+            ChainError::OtherError(
+                "No destination suits the input and there is no default chain".into(),
+            )
+        })?;
+
+        let mut input_variables = input_variables;
+        if let Some(next_inputs) = &route.next_inputs {
+            input_variables.insert(self.input_key.clone(), json!(next_inputs));
+        }
+        Ok((route, chain, input_variables))
+    }
+}
+
+/// The route of the json the LLM was asked for, or of its whole answer when it only
+/// answered the name of the destination.
+fn parse_route(output: &str) -> Route {
+    let re = Regex::new(r"```(?:json)?\s*([\s\S]+?)\s*```").expect("valid regex");
+    let json = re
+        .captures(output)
+        .and_then(|caps| caps.get(1))
+        .map_or(output, |json| json.as_str());
+    serde_json::from_str(json.trim()).unwrap_or_else(|_| Route {
+        destination: Some(output.trim().trim_matches('"').to_string()),
+        next_inputs: None,
+    })
+}
+
+#[async_trait]
+impl Chain for RouterChain {
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        let (_, chain, input_variables) = self.route_inputs(input_variables).await?;
+        chain.call(input_variables).await
+    }
+
+    fn get_input_keys(&self) -> Vec<String> {
+        vec![self.input_key.clone()]
+    }
+
+    fn get_output_keys(&self) -> Vec<String> {
+        vec![DEFAULT_OUTPUT_KEY.to_string(), DESTINATION_KEY.to_string()]
+    }
+
+    async fn execute(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<HashMap<String, Value>, ChainError> {
+        let run = CallbackManager::new().start_run("RouterChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain_outputs(&inputs, async move {
+            let (route, chain, input_variables) = self.route_inputs(input_variables).await?;
+            log::debug!("Routing to {:?}", route.destination);
+            let mut output = chain.execute(input_variables).await?;
+            output.insert(DESTINATION_KEY.to_string(), json!(route.destination));
+            Ok(output)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chain::RouterChainBuilder, llm::FakeLLM};
+
+    use super::*;
+
+    /// Answers its name and the input.
+    struct NamedChain(&'static str);
+
+    #[async_trait]
+    impl Chain for NamedChain {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            Ok(GenerateResult {
+                generation: format!("{}: {}", self.0, input_variables["input"].as_str().unwrap()),
+                ..Default::default()
+            })
+        }
+    }
+
+    fn router(llm: FakeLLM) -> RouterChain {
+        RouterChainBuilder::new()
+            .llm(llm)
+            .add_destination("physics", "Good for physics", NamedChain("physics"))
+            .add_destination("math", "Good for math", NamedChain("math"))
+            .default_chain(NamedChain("default"))
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_router_chain() {
+        let llm = FakeLLM::new(
+            "```json\n{\"destination\": \"math\", \"next_inputs\": \"What is 2 + 2?\"}\n```",
+        );
+        let chain = router(llm.clone());
+        let output = chain
+            .execute(prompt_args! {"input" => "2+2?"})
+            .await
+            .unwrap();
+        assert_eq!(output["output"], "math: What is 2 + 2?");
+        assert_eq!(output["destination"], "math");
+        assert!(llm.calls()[0][0]
+            .content
+            .text()
+            .contains("physics: Good for physics"));
+
+        let chain = router(FakeLLM::new("physics"));
+        assert_eq!(
+            chain
+                .invoke(prompt_args! {"input" => "Why?"})
+                .await
+                .unwrap(),
+            "physics: Why?"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_router_chain_default() {
+        for answer in [
+            r#"{"destination": "DEFAULT", "next_inputs": "Hi"}"#,
+            "chemistry",
+        ] {
+            let chain = router(FakeLLM::new(answer));
+            let output = chain.execute(prompt_args! {"input" => "Hi"}).await.unwrap();
+            assert_eq!(output["output"], "default: Hi");
+            assert_eq!(output["destination"], Value::Null);
+        }
+    }
+
+    #[test]
+    fn test_router_chain_builder() {
+        assert!(RouterChainBuilder::new()
+            .llm(FakeLLM::new("math"))
+            .build()
+            .is_err());
+        assert!(RouterChainBuilder::new()
+            .add_destination("math", "Good for math", NamedChain("math"))
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,6 @@
+mod builder;
+mod chain;
+mod prompt;
+
+pub use builder::*;
+pub use chain::*;
@@ -1,44 +1,116 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, sync::Arc};
 
-use crate::chain::Chain;
+use crate::chain::{Chain, ChainError, DEFAULT_OUTPUT_KEY, DEFAULT_RESULT_KEY};
 
 use super::SequentialChain;
 
 pub struct SequentialChainBuilder {
-    chains: Vec<Box<dyn Chain>>,
+    chains: Vec<Arc<dyn Chain>>,
+    input_keys: Option<Vec<String>>,
+}
+
+/// The keys a chain of the sequence writes its outputs to, the key of its generation
+/// first.
+pub(crate) fn output_keys(chain: &dyn Chain) -> Vec<String> {
+    let keys: Vec<String> = chain
+        .get_output_keys()
+        .into_iter()
+        .filter(|key| key != DEFAULT_RESULT_KEY)
+        .collect();
+    if keys.is_empty() {
+        vec![DEFAULT_OUTPUT_KEY.to_string()]
+    } else {
+        keys
+    }
+}
+
+/// The key a chain of the sequence writes its generation to.
+pub(crate) fn output_key(chain: &dyn Chain) -> String {
+    output_keys(chain).swap_remove(0)
 }
 
 impl SequentialChainBuilder {
     pub fn new() -> Self {
-        Self { chains: Vec::new() }
+        Self {
+            chains: Vec::new(),
+            input_keys: None,
+        }
     }
 
     pub fn add_chain<C: Chain + 'static>(mut self, chain: C) -> Self {
-        self.chains.push(Box::new(chain));
+        self.chains.push(Arc::new(chain));
         self
     }
 
-    pub fn build(self) -> SequentialChain {
-        let outputs: HashSet<String> = self
-            .chains
-            .iter()
-            .flat_map(|c| c.get_output_keys())
-            .collect();
+    pub fn add_boxed_chain(mut self, chain: Box<dyn Chain>) -> Self {
+        self.chains.push(Arc::from(chain));
+        self
+    }
 
-        let input_keys: HashSet<String> = self
+    /// Declares the input variables given to the sequence, so `build` checks that every
+    /// input key of the chains is one of them or the output of an earlier chain. Otherwise
+    /// the inputs are the keys not produced by an earlier chain.
+    pub fn input_keys<S: Into<String>>(mut self, input_keys: Vec<S>) -> Self {
+        self.input_keys = Some(input_keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Builds the chain, failing with `ChainError::MissingInputVariable` when a chain
+    /// needs a key that is only produced by a later chain, or that is neither a declared
+    /// input nor produced by an earlier chain. A chain produces all its output keys, e.g.
+    /// the `source_documents` of a `RetrievalQAChain`.
+    pub fn build(self) -> Result<SequentialChain, ChainError> {
+        let outputs: Vec<Vec<String>> = self
             .chains
             .iter()
-            .flat_map(|c| c.get_input_keys())
+            .map(|chain| output_keys(chain.as_ref()))
             .collect();
 
-        SequentialChain {
+        let mut available: HashSet<String> = self.input_keys.iter().flatten().cloned().collect();
+        let mut inferred_inputs: Vec<String> = Vec::new();
+        for (i, chain) in self.chains.iter().enumerate() {
+            for key in chain.get_input_keys() {
+                if available.contains(&key) {
+                    continue;
+                }
+                if outputs[i + 1..]
+                    .iter()
+                    .flatten()
+                    .any(|output| *output == key)
+                {
+                    return Err(ChainError::MissingInputVariable(format!(
+                        "{} of chain {} is only produced by a later chain",
+                        key, i
+                    )));
+                }
+                if self.input_keys.is_some() {
+                    return Err(ChainError::MissingInputVariable(format!(
+                        "{} of chain {} is neither an input nor produced by an earlier chain",
+                        key, i
+                    )));
+                }
+                inferred_inputs.push(key.clone());
+                available.insert(key);
+            }
+            available.extend(outputs[i].iter().cloned());
+        }
+
+        Ok(SequentialChain {
             chains: self.chains,
-            input_keys,
+            input_keys: self.input_keys.unwrap_or(inferred_inputs),
             outputs,
-        }
+        })
+    }
+}
+
+impl Default for SequentialChainBuilder {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+/// Builds a `SequentialChain` of the chains, returning the result of
+/// `SequentialChainBuilder::build`.
 #[macro_export]
 macro_rules! sequential_chain {
     ( $( $chain:expr ),* $(,)? ) => {
@@ -1,19 +1,53 @@
-use std::collections::{HashMap, HashSet};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
 
 use async_trait::async_trait;
+use futures::{Stream, StreamExt};
 use serde_json::{json, Value};
 
 use crate::{
-    chain::{Chain, ChainError, DEFAULT_OUTPUT_KEY, DEFAULT_RESULT_KEY},
+    callbacks::{CallbackManager, RunType},
+    chain::{Chain, ChainError, DEFAULT_RESULT_KEY},
     language_models::{GenerateResult, TokenUsage},
     prompt::PromptArgs,
+    schemas::StreamData,
 };
 
-//THIS IS EXPERIMENTAL
+use super::output_key;
+
+/// Runs chains in order, adding the outputs of each chain to the input variables of the
+/// next ones: its generation under its output key and its other outputs, e.g. the
+/// `source_documents` of a `RetrievalQAChain`, under theirs. Build it with
+/// `SequentialChainBuilder` or the `sequential_chain!` macro.
+///
+/// `stream` wraps every chain in a step, named by its output key, from
+/// `StreamData::step_start` to `StreamData::step_end` with its generation: the chains run
+/// in turn, then the tokens of the last chain are streamed.
 pub struct SequentialChain {
-    pub(crate) chains: Vec<Box<dyn Chain>>,
-    pub(crate) input_keys: HashSet<String>,
-    pub(crate) outputs: HashSet<String>,
+    pub(crate) chains: Vec<Arc<dyn Chain>>,
+    pub(crate) input_keys: Vec<String>,
+    pub(crate) outputs: Vec<Vec<String>>,
+}
+
+/// The result of a chain of the sequence and the outputs it routes, from the outputs of
+/// its `execute`.
+#[allow(clippy::result_large_err)]
+fn route_outputs(
+    mut output: HashMap<String, Value>,
+    output_key: &str,
+) -> Result<(GenerateResult, HashMap<String, Value>), ChainError> {
+    let result = match output.remove(DEFAULT_RESULT_KEY) {
+        Some(result) => serde_json::from_value(result)?,
+        None => GenerateResult {
+            generation: output
+                .get(output_key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            ..Default::default()
+        },
+    };
+    output.insert(output_key.to_string(), json!(result.generation));
+    Ok((result, output))
 }
 
 #[async_trait]
@@ -33,66 +67,324 @@ impl Chain for SequentialChain {
             .map(|result| result.generation)
     }
     fn get_input_keys(&self) -> Vec<String> {
-        self.outputs.iter().cloned().collect()
+        self.input_keys.clone()
+    }
+
+    /// The output keys of the last chain first, its first key has the final generation.
+    fn get_output_keys(&self) -> Vec<String> {
+        self.outputs.iter().rev().flatten().cloned().collect()
     }
 
     async fn execute(
         &self,
         input_variables: PromptArgs,
     ) -> Result<HashMap<String, Value>, ChainError> {
-        let mut input_variables = input_variables;
-        let mut final_token_usage: Option<TokenUsage> = None;
-        let mut output_result = HashMap::new();
-        let mut final_result = GenerateResult::default();
-        for chain in self.chains.iter() {
-            let output = chain.execute(input_variables.clone()).await?;
-            //Get the oput key for the chain result
-            let output_key = chain
-                .get_output_keys()
-                .get(0)
-                .unwrap_or(&DEFAULT_OUTPUT_KEY.to_string())
-                .clone();
-            //Get the ouput complete result
-            let result = output
-                .get(DEFAULT_RESULT_KEY)
-                .unwrap_or(&json!(GenerateResult::default()))
-                .clone();
-            let result: GenerateResult = serde_json::from_value(result)?;
-            log::debug!("{}", result.generation);
-            //Insert the output chain to the final output
-            output_result.insert(output_key.clone(), json!(result.generation.clone()));
-            input_variables.insert(output_key, json!(result.generation.clone()));
-
-            //add the generation to keep track of the final generation
-            final_result.generation = result.generation;
-            //Add to the token if it exist
-            if let Some(token) = &result.tokens {
-                match final_token_usage {
-                    Some(token_usage) => {
-                        final_token_usage = Some(token_usage.sum(&token));
-                    }
-                    None => {
-                        final_token_usage = Some(token.clone());
+        let run = CallbackManager::new().start_run("SequentialChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain_outputs(&inputs, async move {
+            let mut input_variables = input_variables;
+            let mut final_token_usage: Option<TokenUsage> = None;
+            let mut output_result = HashMap::new();
+            let mut final_result = GenerateResult::default();
+            for chain in self.chains.iter() {
+                let output = chain.execute(input_variables.clone()).await?;
+                //Get the ouput complete result and the outputs of the chain
+                let (result, outputs) = route_outputs(output, &output_key(chain.as_ref()))?;
+                log::debug!("{}", result.generation);
+                //Insert the outputs of the chain to the final output
+                input_variables.extend(outputs.clone());
+                output_result.extend(outputs);
+
+                //add the generation to keep track of the final generation
+                final_result.generation = result.generation;
+                //Add to the token if it exist
+                if let Some(token) = &result.tokens {
+                    match final_token_usage {
+                        Some(token_usage) => {
+                            final_token_usage = Some(token_usage.sum(&token));
+                        }
+                        None => {
+                            final_token_usage = Some(token.clone());
+                        }
                     }
                 }
             }
-        }
 
-        //add the filan token count to the result
-        final_result.tokens = final_token_usage;
-        output_result.insert(DEFAULT_RESULT_KEY.to_string(), json!(final_result));
-        Ok(output_result)
+            //add the filan token count to the result
+            final_result.tokens = final_token_usage;
+            output_result.insert(DEFAULT_RESULT_KEY.to_string(), json!(final_result));
+            Ok(output_result)
+        })
+        .await
+    }
+
+    async fn stream(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
+    {
+        let run = CallbackManager::new().start_run("SequentialChain", RunType::Chain);
+        run.on_chain_start(&input_variables);
+        // The chains run in the stream, inside the scope of its run.
+        let chains = self.chains.clone();
+        let scope = run.clone();
+        let stream = async_stream::stream! {
+            let mut input_variables = input_variables;
+            let last = chains.len().saturating_sub(1);
+            for (i, chain) in chains.iter().enumerate() {
+                let output_key = output_key(chain.as_ref());
+                yield Ok(StreamData::step_start(output_key.clone()));
+                if i < last {
+                    let result = match scope.scope(chain.execute(input_variables.clone())).await {
+                        Ok(output) => route_outputs(output, &output_key),
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok((result, outputs)) => {
+                            input_variables.extend(outputs);
+                            yield Ok(StreamData::step_end(output_key, result.generation));
+                        }
+                        Err(e) => {
+                            yield Err(e);
+                            return;
+                        }
+                    }
+                } else {
+                    let mut chain_stream =
+                        match scope.scope(chain.stream(input_variables.clone())).await {
+                            Ok(chain_stream) => chain_stream,
+                            Err(e) => {
+                                yield Err(e);
+                                return;
+                            }
+                        };
+                    let mut generation = String::new();
+                    while let Some(item) = chain_stream.next().await {
+                        match item {
+                            Ok(data) => {
+                                if data.is_token() {
+                                    generation.push_str(&data.content);
+                                }
+                                yield Ok(data);
+                            }
+                            Err(e) => {
+                                yield Err(e);
+                                return;
+                            }
+                        }
+                    }
+                    yield Ok(StreamData::step_end(output_key, generation));
+                }
+            }
+        };
+        Ok(run.trace_chain_stream(Box::pin(stream)))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::{
-        chain::{Chain, LLMChainBuilder},
+        chain::{get_output, LLMChainBuilder, SequentialChainBuilder},
         llm::openai::OpenAI,
-        prompt_args, sequential_chain, template_fstring,
+        prompt_args,
+        schemas::StreamKind,
+        sequential_chain, template_fstring,
     };
 
+    /// Joins its inputs with `-`.
+    struct JoinChain {
+        inputs: Vec<&'static str>,
+        output: &'static str,
+    }
+
+    #[async_trait]
+    impl Chain for JoinChain {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            let values: Vec<&str> = self
+                .inputs
+                .iter()
+                .map(|input| input_variables[*input].as_str().unwrap())
+                .collect();
+            Ok(GenerateResult {
+                generation: values.join("-"),
+                ..Default::default()
+            })
+        }
+
+        fn get_input_keys(&self) -> Vec<String> {
+            self.inputs.iter().map(|input| input.to_string()).collect()
+        }
+
+        fn get_output_keys(&self) -> Vec<String> {
+            vec![self.output.to_string()]
+        }
+
+        /// Streams the values as tokens, with the separators.
+        async fn stream(
+            &self,
+            input_variables: PromptArgs,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
+        {
+            let generation = self.invoke(input_variables).await?;
+            let tokens: Vec<Result<StreamData, ChainError>> = generation
+                .split_inclusive('-')
+                .map(|token| StreamData::new(json!(token), token))
+                .map(Ok)
+                .collect();
+            Ok(Box::pin(futures::stream::iter(tokens)))
+        }
+    }
+
+    fn join(inputs: &[&'static str], output: &'static str) -> JoinChain {
+        JoinChain {
+            inputs: inputs.to_vec(),
+            output,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sequential_pipes_outputs() {
+        let chain = sequential_chain!(join(&["a", "b"], "ab"), join(&["ab", "c"], "abc")).unwrap();
+        assert_eq!(chain.get_input_keys(), vec!["a", "b", "c"]);
+        assert_eq!(chain.get_output_keys(), vec!["abc", "ab"]);
+
+        let output = chain
+            .execute(prompt_args! {"a" => "1", "b" => "2", "c" => "3"})
+            .await
+            .unwrap();
+        assert_eq!(output["ab"], "1-2");
+        assert_eq!(output["abc"], "1-2-3");
+        assert_eq!(
+            chain
+                .invoke(prompt_args! {"a" => "1", "b" => "2", "c" => "3"})
+                .await
+                .unwrap(),
+            "1-2-3"
+        );
+    }
+
+    /// Splits `ab` at its first `-`, the first part is the generation.
+    struct SplitChain;
+
+    #[async_trait]
+    impl Chain for SplitChain {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            let output = self.execute(input_variables).await?;
+            Ok(serde_json::from_value(output[DEFAULT_RESULT_KEY].clone())?)
+        }
+
+        async fn execute(
+            &self,
+            input_variables: PromptArgs,
+        ) -> Result<HashMap<String, Value>, ChainError> {
+            let (first, rest) = input_variables["ab"]
+                .as_str()
+                .unwrap()
+                .split_once('-')
+                .unwrap();
+            let result = GenerateResult {
+                generation: first.to_string(),
+                ..Default::default()
+            };
+            Ok(HashMap::from([
+                ("first".to_string(), json!(first)),
+                ("rest".to_string(), json!(rest)),
+                (DEFAULT_RESULT_KEY.to_string(), json!(result)),
+            ]))
+        }
+
+        fn get_input_keys(&self) -> Vec<String> {
+            vec!["ab".to_string()]
+        }
+
+        fn get_output_keys(&self) -> Vec<String> {
+            vec![
+                "first".to_string(),
+                "rest".to_string(),
+                DEFAULT_RESULT_KEY.to_string(),
+            ]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sequential_routes_all_outputs() {
+        let chain = sequential_chain!(
+            join(&["a", "b"], "ab"),
+            SplitChain,
+            join(&["rest", "first"], "swapped")
+        )
+        .unwrap();
+        assert_eq!(chain.get_input_keys(), vec!["a", "b"]);
+        assert_eq!(
+            chain.get_output_keys(),
+            vec!["swapped", "first", "rest", "ab"]
+        );
+
+        let output = chain
+            .execute(prompt_args! {"a" => "1", "b" => "2"})
+            .await
+            .unwrap();
+        assert_eq!(get_output::<String>(&output, "rest").unwrap(), "2");
+        assert_eq!(output["first"], "1");
+        assert_eq!(output["swapped"], "2-1");
+        assert!(get_output::<String>(&output, "missing").is_err());
+
+        assert!(matches!(
+            sequential_chain!(join(&["rest"], "r"), SplitChain, join(&["a", "b"], "ab")),
+            Err(ChainError::MissingInputVariable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sequential_stream() {
+        let chain = sequential_chain!(join(&["a", "b"], "ab"), join(&["ab", "c"], "abc")).unwrap();
+        let events: Vec<StreamData> = chain
+            .stream(prompt_args! {"a" => "1", "b" => "2", "c" => "3"})
+            .await
+            .unwrap()
+            .map(|data| data.unwrap())
+            .collect()
+            .await;
+
+        let kinds: Vec<StreamKind> = events.iter().map(|data| data.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                StreamKind::StepStart,
+                StreamKind::StepEnd,
+                StreamKind::StepStart,
+                StreamKind::Token,
+                StreamKind::Token,
+                StreamKind::Token,
+                StreamKind::StepEnd,
+            ]
+        );
+        assert_eq!(events[0].value["step"], "ab");
+        assert_eq!(events[1].value["output"], "1-2");
+        assert_eq!(events[6].value["step"], "abc");
+        assert_eq!(events[6].value["output"], "1-2-3");
+        let generation: String = events.iter().map(|data| data.content.as_str()).collect();
+        assert_eq!(generation, "1-2-3");
+    }
+
+    #[test]
+    fn test_sequential_validates_keys() {
+        assert!(matches!(
+            sequential_chain!(join(&["ab"], "abc"), join(&["a"], "ab")),
+            Err(ChainError::MissingInputVariable(_))
+        ));
+
+        let builder = || {
+            SequentialChainBuilder::new()
+                .add_chain(join(&["a", "b"], "ab"))
+                .add_chain(join(&["ab", "c"], "abc"))
+        };
+        assert!(builder().input_keys(vec!["a", "b"]).build().is_err());
+        let chain = builder().input_keys(vec!["a", "b", "c"]).build().unwrap();
+        assert_eq!(chain.get_input_keys(), vec!["a", "b", "c"]);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_sequential() {
@@ -118,7 +410,7 @@ mod tests {
             .build()
             .expect("Failed to build LLMChain");
 
-        let chain = sequential_chain!(chain1, chain2);
+        let chain = sequential_chain!(chain1, chain2).unwrap();
         let result = chain
             .execute(prompt_args! {"input"=>"medias","palabra"=>"arroz"})
             .await;
@@ -6,7 +6,7 @@ use crate::{
     output_parsers::OutputParser,
     prompt::HumanMessagePromptTemplate,
     template_jinja2,
-    tools::SQLDatabase,
+    tools::{SQLDatabase, DEFAULT_MAX_ROWS},
 };
 
 use super::{
@@ -22,6 +22,8 @@ pub struct SQLDatabaseChainBuilder {
     database: Option<SQLDatabase>,
     output_key: Option<String>,
     output_parser: Option<Box<dyn OutputParser<String>>>,
+    max_rows: Option<usize>,
+    read_only: Option<bool>,
 }
 
 impl SQLDatabaseChainBuilder {
@@ -33,6 +35,8 @@ impl SQLDatabaseChainBuilder {
             database: None,
             output_key: None,
             output_parser: None,
+            max_rows: None,
+            read_only: None,
         }
     }
 
@@ -69,6 +73,19 @@ impl SQLDatabaseChainBuilder {
         self
     }
 
+    /// The maximum number of rows of the result given to the LLM. Default: 50
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    /// Whether the queries run in a read-only transaction, so the database rejects any
+    /// write. The engine has to support it, see `Engine::query_with_limit`. Default: true
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = Some(read_only);
+        self
+    }
+
     pub fn build(self) -> Result<SQLDatabaseChain, ChainError> {
         let llm = self
             .llm
@@ -109,6 +126,8 @@ impl SQLDatabaseChainBuilder {
             llmchain: llm_chain,
             top_k,
             database,
+            max_rows: self.max_rows.unwrap_or(DEFAULT_MAX_ROWS),
+            read_only: self.read_only.unwrap_or(true),
         })
     }
 }
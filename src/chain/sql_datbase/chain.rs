@@ -5,12 +5,13 @@ use futures::Stream;
 use serde_json::Value;
 
 use crate::{
+    callbacks::{CallbackManager, RunType},
     chain::{chain_trait::Chain, llm_chain::LLMChain, ChainError},
     language_models::{GenerateResult, TokenUsage},
     prompt::PromptArgs,
     prompt_args,
     schemas::StreamData,
-    tools::SQLDatabase,
+    tools::{check_read_only, SQLDatabase},
 };
 
 use super::{
@@ -44,6 +45,8 @@ pub struct SQLDatabaseChain {
     pub(crate) llmchain: LLMChain,
     pub(crate) top_k: usize,
     pub(crate) database: SQLDatabase,
+    pub(crate) max_rows: usize,
+    pub(crate) read_only: bool,
 }
 
 /// SQLChain let you interact with a db in human lenguage
@@ -130,11 +133,14 @@ impl SQLDatabaseChain {
             token_usage = Some(tokens);
         }
 
-        let sql_query = output.generation.trim();
+        let sql_query = clean_sql_query(&output.generation);
         log::debug!("output: {:?}", sql_query);
+        if self.read_only {
+            check_read_only(sql_query).map_err(ChainError::DatabaseError)?;
+        }
         let query_result = self
             .database
-            .query(sql_query)
+            .query_with_limit(sql_query, self.max_rows, self.read_only)
             .await
             .map_err(|e| ChainError::DatabaseError(e.to_string()))?;
 
@@ -149,6 +155,17 @@ impl SQLDatabaseChain {
     }
 }
 
+/// The query without the markdown fence and the trailing semicolon the models often add.
+fn clean_sql_query(generation: &str) -> &str {
+    let query = generation.trim();
+    let query = query
+        .strip_prefix("```sql")
+        .or_else(|| query.strip_prefix("```"))
+        .and_then(|query| query.strip_suffix("```"))
+        .unwrap_or(query);
+    query.trim().trim_end_matches(';').trim_end()
+}
+
 #[async_trait]
 impl Chain for SQLDatabaseChain {
     fn get_input_keys(&self) -> Vec<String> {
@@ -156,31 +173,37 @@ impl Chain for SQLDatabaseChain {
     }
 
     async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
-        let (llm_inputs, mut token_usage) = self.call_builder_chains(&input_variables).await?;
-        let output = self.llmchain.call(llm_inputs).await?;
-        if let Some(tokens) = output.tokens {
-            if let Some(general_result) = token_usage.as_mut() {
-                general_result.completion_tokens += tokens.completion_tokens;
-                general_result.total_tokens += tokens.total_tokens;
+        let run = CallbackManager::new().start_run("SQLDatabaseChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain(&inputs, async move {
+            let (llm_inputs, mut token_usage) = self.call_builder_chains(&input_variables).await?;
+            let output = self.llmchain.call(llm_inputs).await?;
+            if let Some(tokens) = output.tokens {
+                if let Some(general_result) = token_usage.as_mut() {
+                    general_result.completion_tokens += tokens.completion_tokens;
+                    general_result.total_tokens += tokens.total_tokens;
+                }
             }
-        }
 
-        let strs: Vec<&str> = output
-            .generation
-            .split("\n\n")
-            .next()
-            .unwrap_or("")
-            .split("Answer:")
-            .collect();
-        let mut output = strs[0];
-        if strs.len() > 1 {
-            output = strs[1];
-        }
-        output = output.trim();
-        Ok(GenerateResult {
-            generation: output.to_string(),
-            tokens: token_usage,
+            let strs: Vec<&str> = output
+                .generation
+                .split("\n\n")
+                .next()
+                .unwrap_or("")
+                .split("Answer:")
+                .collect();
+            let mut output = strs[0];
+            if strs.len() > 1 {
+                output = strs[1];
+            }
+            output = output.trim();
+            Ok(GenerateResult {
+                generation: output.to_string(),
+                tokens: token_usage,
+                ..Default::default()
+            })
         })
+        .await
     }
 
     async fn invoke(&self, input_variables: PromptArgs) -> Result<String, ChainError> {
@@ -198,3 +221,126 @@ impl Chain for SQLDatabaseChain {
         self.llmchain.stream(llm_inputs).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use crate::{
+        chain::SQLDatabaseChainBuilder,
+        llm::FakeLLM,
+        tools::{Dialect, Engine, SQLDatabaseBuilder},
+    };
+
+    use super::*;
+
+    struct MockEngine {}
+
+    #[async_trait]
+    impl Engine for MockEngine {
+        fn dialect(&self) -> Dialect {
+            Dialect::SQLite
+        }
+
+        async fn query(
+            &self,
+            _query: &str,
+        ) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn Error>> {
+            let rows = (0..5).map(|i| vec![format!("user{}", i)]).collect();
+            Ok((vec!["name".to_string()], rows))
+        }
+
+        async fn query_with_limit(
+            &self,
+            query: &str,
+            max_rows: usize,
+            read_only: bool,
+        ) -> Result<(Vec<String>, Vec<Vec<String>>, bool), Box<dyn Error>> {
+            if read_only && query.contains("setval") {
+                return Err("cannot execute setval() in a read-only transaction".into());
+            }
+            let (cols, mut rows) = self.query(query).await?;
+            let truncated = rows.len() > max_rows;
+            rows.truncate(max_rows);
+            Ok((cols, rows, truncated))
+        }
+
+        async fn table_names(&self) -> Result<Vec<String>, Box<dyn Error>> {
+            Ok(vec!["users".to_string()])
+        }
+
+        async fn table_info(&self, table: &str) -> Result<String, Box<dyn Error>> {
+            Ok(format!("CREATE TABLE {} (name text)", table))
+        }
+
+        fn close(&self) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+    }
+
+    async fn chain(llm: FakeLLM) -> SQLDatabaseChain {
+        let database = SQLDatabaseBuilder::new(MockEngine {})
+            .custom_sample_rows_number(0)
+            .build()
+            .await
+            .unwrap();
+        SQLDatabaseChainBuilder::new()
+            .llm(llm)
+            .top_k(4)
+            .max_rows(2)
+            .database(database)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_clean_sql_query() {
+        assert_eq!(
+            clean_sql_query("```sql\nSELECT name FROM users;\n```"),
+            "SELECT name FROM users"
+        );
+        assert_eq!(clean_sql_query(" SELECT 1 "), "SELECT 1");
+    }
+
+    #[tokio::test]
+    async fn test_sql_database_chain() {
+        let llm = FakeLLM::from_responses(vec![
+            "```sql\nSELECT name FROM users;\n```",
+            "Answer: user0 and user1",
+        ]);
+        let chain = chain(llm.clone()).await;
+
+        let answer = chain
+            .invoke(prompt_args! {"query" => "Who are the users?"})
+            .await
+            .unwrap();
+        assert_eq!(answer, "user0 and user1");
+
+        let prompts: Vec<String> = llm
+            .calls()
+            .iter()
+            .map(|messages| messages[0].content.text())
+            .collect();
+        assert!(prompts[0].contains("CREATE TABLE users"));
+        assert!(prompts[1].contains("SELECT name FROM users"));
+        assert!(prompts[1].contains("user1\n(only the first 2 rows are shown)"));
+        assert!(!prompts[1].contains("user2"));
+    }
+
+    #[tokio::test]
+    async fn test_sql_database_chain_read_only() {
+        let result = chain(FakeLLM::new("DROP TABLE users"))
+            .await
+            .invoke(prompt_args! {"query" => "Drop the users"})
+            .await;
+        assert!(matches!(result, Err(ChainError::DatabaseError(_))));
+
+        let result = chain(FakeLLM::new("SELECT setval('users_id_seq', 1)"))
+            .await
+            .invoke(prompt_args! {"query" => "Reset the user ids"})
+            .await;
+        assert!(
+            matches!(result, Err(ChainError::DatabaseError(e)) if e.contains("read-only transaction"))
+        );
+    }
+}
@@ -5,6 +5,7 @@ use futures::Stream;
 use serde_json::Value;
 
 use crate::{
+    callbacks::{CallbackManager, RunType},
     chain::{
         load_stuff_qa, options::ChainCallOptions, Chain, ChainError, LLMChain, StuffQAPromptBuilder,
     },
@@ -117,24 +118,29 @@ impl StuffDocument {
 #[async_trait]
 impl Chain for StuffDocument {
     async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
-        let docs = input_variables
-            .get(&self.input_key)
-            .ok_or_else(|| ChainError::MissingInputVariable(self.input_key.clone()))?;
-
-        let documents: Vec<Document> = serde_json::from_value(docs.clone()).map_err(|e| {
-            ChainError::IncorrectInputVariable {
-                source: e,
-                expected_type: "Vec<Document>".to_string(),
-            }
-        })?;
-
-        let mut input_values = input_variables.clone();
-        input_values.insert(
-            self.document_variable_name.clone(),
-            Value::String(self.join_documents(documents)),
-        );
-
-        self.llm_chain.call(input_values).await
+        let run = CallbackManager::new().start_run("StuffDocument", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain(&inputs, async move {
+            let docs = input_variables
+                .get(&self.input_key)
+                .ok_or_else(|| ChainError::MissingInputVariable(self.input_key.clone()))?;
+
+            let documents: Vec<Document> = serde_json::from_value(docs.clone()).map_err(|e| {
+                ChainError::IncorrectInputVariable {
+                    source: e,
+                    expected_type: "Vec<Document>".to_string(),
+                }
+            })?;
+
+            let mut input_values = input_variables.clone();
+            input_values.insert(
+                self.document_variable_name.clone(),
+                Value::String(self.join_documents(documents)),
+            );
+
+            self.llm_chain.call(input_values).await
+        })
+        .await
     }
 
     async fn stream(
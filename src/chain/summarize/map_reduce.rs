@@ -0,0 +1,270 @@
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use serde_json::Value;
+
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    chain::{Chain, ChainError, LLMChain},
+    language_models::{GenerateResult, TokenUsage},
+    prompt::PromptArgs,
+    schemas::Document,
+    tokenizers::count_text_tokens,
+};
+
+use super::{SUMMARIZE_DEFAULT_DOCUMENT_VARIABLE_NAME, SUMMARIZE_DEFAULT_INPUT_KEY};
+
+const DEFAULT_MAX_CONCURRENCY: usize = 4;
+const DEFAULT_SEPARATOR: &str = "\n\n";
+
+/// Runs the map chain on each document of the `Vec<Document>` input, concurrently, then
+/// the reduce chain on their joined results, e.g. to summarize documents too long for a
+/// single prompt.
+///
+/// With `with_token_max`, the results too long to be reduced at once are first collapsed:
+/// the reduce chain is run on the groups of results fitting in `token_max`, then on the
+/// groups of their results, until they all fit.
+///
+/// Both chains get the other input variables too, the document, or the joined results,
+/// being under the document variable (`text` by default).
+pub struct MapReduceChain {
+    map_chain: LLMChain,
+    reduce_chain: LLMChain,
+    input_key: String,
+    document_variable_name: String,
+    separator: String,
+    max_concurrency: usize,
+    token_max: Option<usize>,
+}
+
+impl MapReduceChain {
+    pub fn new(map_chain: LLMChain, reduce_chain: LLMChain) -> Self {
+        Self {
+            map_chain,
+            reduce_chain,
+            input_key: SUMMARIZE_DEFAULT_INPUT_KEY.to_string(),
+            document_variable_name: SUMMARIZE_DEFAULT_DOCUMENT_VARIABLE_NAME.to_string(),
+            separator: DEFAULT_SEPARATOR.to_string(),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            token_max: None,
+        }
+    }
+
+    /// The input variable with the documents. Default: `input_documents`
+    pub fn with_input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = input_key.into();
+        self
+    }
+
+    /// The variable of the prompts with the document or the joined results. Default: `text`
+    pub fn with_document_variable_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.document_variable_name = name.into();
+        self
+    }
+
+    /// The separator of the results of the map chain given to the reduce chain.
+    pub fn with_separator<S: Into<String>>(mut self, separator: S) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// The maximum number of documents mapped at the same time. Default: 4
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// The maximum number of tokens of the joined results given to the reduce chain, the
+    /// results being collapsed until they fit. The tokens are counted with the
+    /// `cl100k_base` encoding of tiktoken.
+    pub fn with_token_max(mut self, token_max: usize) -> Self {
+        self.token_max = Some(token_max);
+        self
+    }
+
+    /// Runs the chain on each text, concurrently, adding up the tokens used.
+    async fn run_each(
+        &self,
+        chain: &LLMChain,
+        input_variables: &PromptArgs,
+        texts: Vec<String>,
+        tokens: &mut Option<TokenUsage>,
+    ) -> Result<Vec<String>, ChainError> {
+        // `buffered` keeps the results in the order of the texts.
+        let results: Vec<GenerateResult> = futures::stream::iter(texts)
+            .map(|text| {
+                let mut input_values = input_variables.clone();
+                input_values.insert(self.document_variable_name.clone(), Value::String(text));
+                chain.call(input_values)
+            })
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await?;
+        for result in results.iter() {
+            if let Some(usage) = &result.tokens {
+                tokens.get_or_insert_with(TokenUsage::default).add(usage);
+            }
+        }
+        Ok(results
+            .into_iter()
+            .map(|result| result.generation)
+            .collect())
+    }
+
+    /// The consecutive results whose joined texts fit in `token_max`.
+    fn collapse_groups(&self, results: Vec<String>, token_max: usize) -> Vec<String> {
+        let mut groups: Vec<String> = Vec::new();
+        let mut group: Vec<String> = Vec::new();
+        for result in results {
+            group.push(result);
+            if group.len() > 1 && count_text_tokens("", &group.join(&self.separator)) > token_max {
+                let last = group.pop().unwrap_or_default();
+                groups.push(group.join(&self.separator));
+                group = vec![last];
+            }
+        }
+        if !group.is_empty() {
+            groups.push(group.join(&self.separator));
+        }
+        groups
+    }
+}
+
+#[async_trait]
+impl Chain for MapReduceChain {
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        let run = CallbackManager::new().start_run("MapReduceChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain(&inputs, async move {
+            let docs = input_variables
+                .get(&self.input_key)
+                .ok_or_else(|| ChainError::MissingInputVariable(self.input_key.clone()))?;
+            let documents: Vec<Document> = serde_json::from_value(docs.clone()).map_err(|e| {
+                ChainError::IncorrectInputVariable {
+                    source: e,
+                    expected_type: "Vec<Document>".to_string(),
+                }
+            })?;
+
+            // Each call only gets its own document, not all of them.
+            let mut input_variables = input_variables;
+            input_variables.remove(&self.input_key);
+
+            let mut tokens: Option<TokenUsage> = None;
+            let texts = documents
+                .into_iter()
+                .map(|document| document.page_content)
+                .collect();
+            let mut results = self
+                .run_each(&self.map_chain, &input_variables, texts, &mut tokens)
+                .await?;
+
+            if let Some(token_max) = self.token_max {
+                while results.len() > 1
+                    && count_text_tokens("", &results.join(&self.separator)) > token_max
+                {
+                    let groups = self.collapse_groups(results.clone(), token_max);
+                    if groups.len() == results.len() {
+                        return Err(ChainError::OtherError(format!(
+                            "The results of the map chain can't be collapsed in {} tokens",
+                            token_max
+                        )));
+                    }
+                    results = self
+                        .run_each(&self.reduce_chain, &input_variables, groups, &mut tokens)
+                        .await?;
+                }
+            }
+
+            let mut input_values = input_variables;
+            input_values.insert(
+                self.document_variable_name.clone(),
+                Value::String(results.join(&self.separator)),
+            );
+            let mut result = self.reduce_chain.call(input_values).await?;
+            if let Some(usage) = &result.tokens {
+                tokens.get_or_insert_with(TokenUsage::default).add(usage);
+            }
+            result.tokens = tokens;
+            Ok(result)
+        })
+        .await
+    }
+
+    fn get_input_keys(&self) -> Vec<String> {
+        vec![self.input_key.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::{chain::LLMChainBuilder, llm::FakeLLM, prompt_args, template_fstring};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_map_reduce_chain() {
+        let llm = FakeLLM::echo()
+            .with_token_usage(TokenUsage::new(1, 1))
+            .with_latency(Duration::from_millis(10));
+        let chain = |template: &str| {
+            LLMChainBuilder::new()
+                .prompt(template_fstring!(template, "text"))
+                .llm(llm.clone())
+                .build()
+                .unwrap()
+        };
+        let chain = MapReduceChain::new(chain("map({text})"), chain("reduce({text})"))
+            .with_separator(",")
+            .with_max_concurrency(2);
+
+        let documents: Vec<Document> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(Document::new)
+            .collect();
+        let result = chain
+            .call(prompt_args! {"input_documents" => json!(documents)})
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "reduce(map(a),map(b),map(c),map(d))");
+        assert_eq!(result.tokens.unwrap().total_tokens, 10);
+        assert_eq!(llm.max_concurrent_calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_map_reduce_chain_collapse() {
+        let reduce_llm = FakeLLM::new("s");
+        let chain = |template: &str, llm: FakeLLM| {
+            LLMChainBuilder::new()
+                .prompt(template_fstring!(template, "text"))
+                .llm(llm)
+                .build()
+                .unwrap()
+        };
+        let chain = MapReduceChain::new(
+            chain("m({text})", FakeLLM::echo()),
+            chain("r({text})", reduce_llm.clone()),
+        )
+        .with_separator(",")
+        .with_token_max(9);
+
+        let documents: Vec<Document> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(Document::new)
+            .collect();
+        let result = chain
+            .call(prompt_args! {"input_documents" => json!(documents)})
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "s");
+        let reduced: Vec<String> = reduce_llm
+            .calls()
+            .iter()
+            .map(|messages| messages[0].content.text())
+            .collect();
+        assert_eq!(reduced, vec!["r(m(a),m(b),m(c))", "r(m(d))", "r(s,s)"]);
+    }
+}
@@ -0,0 +1,136 @@
+mod map_reduce;
+pub use map_reduce::*;
+
+mod refine;
+pub use refine::*;
+
+mod prompt;
+
+use crate::{
+    language_models::llm::LLM,
+    prompt::{PromptTemplate, TemplateFormat},
+};
+
+use super::{Chain, LLMChain, LLMChainBuilder, StuffDocument};
+
+pub(crate) const SUMMARIZE_DEFAULT_INPUT_KEY: &str = "input_documents";
+pub(crate) const SUMMARIZE_DEFAULT_DOCUMENT_VARIABLE_NAME: &str = "text";
+
+/// How `load_summarize_chain` summarizes the documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummarizeChainType {
+    /// All the documents in a single prompt, for the documents fitting in the context.
+    Stuff,
+    /// Each document summarized on its own, then the summaries combined.
+    MapReduce,
+    /// The summary of the first document refined with each of the next ones.
+    Refine,
+}
+
+fn summarize_llm_chain(llm: Box<dyn LLM>, template: &str, variables: &[&str]) -> LLMChain {
+    let prompt = PromptTemplate::new(
+        template.to_string(),
+        variables
+            .iter()
+            .map(|variable| variable.to_string())
+            .collect(),
+        TemplateFormat::Jinja2,
+    );
+    LLMChainBuilder::new()
+        .prompt(prompt)
+        .llm(llm)
+        .build()
+        .unwrap() //Its safe to unwrap here because the prompt and the LLM are set.
+}
+
+/// A chain summarizing the `Vec<Document>` of the `input_documents` input, with the default
+/// prompts of the type of chain.
+///
+/// # Example
+/// ```rust,ignore
+/// let chain = load_summarize_chain(OpenAI::default(), SummarizeChainType::MapReduce);
+/// let summary = chain
+///     .invoke(prompt_args! {"input_documents" => documents})
+///     .await?;
+/// ```
+pub fn load_summarize_chain<L: Into<Box<dyn LLM>>>(
+    llm: L,
+    chain_type: SummarizeChainType,
+) -> Box<dyn Chain> {
+    let llm = llm.into();
+    match chain_type {
+        // The documents are joined in the `context` variable of the stuff chain.
+        SummarizeChainType::Stuff => Box::new(StuffDocument::new(summarize_llm_chain(
+            llm,
+            &prompt::DEFAULT_SUMMARIZE_TEMPLATE.replace("{{text}}", "{{context}}"),
+            &["context"],
+        ))),
+        SummarizeChainType::MapReduce => Box::new(load_summarize_map_reduce(llm)),
+        SummarizeChainType::Refine => Box::new(load_summarize_refine(llm)),
+    }
+}
+
+/// A `MapReduceChain` summarizing each document, then the summaries.
+pub fn load_summarize_map_reduce<L: Into<Box<dyn LLM>>>(llm: L) -> MapReduceChain {
+    let llm = llm.into();
+    MapReduceChain::new(
+        summarize_llm_chain(
+            llm.clone_box(),
+            prompt::DEFAULT_SUMMARIZE_TEMPLATE,
+            &[SUMMARIZE_DEFAULT_DOCUMENT_VARIABLE_NAME],
+        ),
+        summarize_llm_chain(
+            llm,
+            prompt::DEFAULT_SUMMARIZE_TEMPLATE,
+            &[SUMMARIZE_DEFAULT_DOCUMENT_VARIABLE_NAME],
+        ),
+    )
+}
+
+/// A `RefineChain` summarizing the first document, then refining the summary with the
+/// next ones.
+pub fn load_summarize_refine<L: Into<Box<dyn LLM>>>(llm: L) -> RefineChain {
+    let llm = llm.into();
+    RefineChain::new(
+        summarize_llm_chain(
+            llm.clone_box(),
+            prompt::DEFAULT_SUMMARIZE_TEMPLATE,
+            &[SUMMARIZE_DEFAULT_DOCUMENT_VARIABLE_NAME],
+        ),
+        summarize_llm_chain(
+            llm,
+            prompt::DEFAULT_REFINE_TEMPLATE,
+            &[SUMMARIZE_DEFAULT_DOCUMENT_VARIABLE_NAME, "existing_answer"],
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{llm::FakeLLM, prompt_args, schemas::Document};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_summarize_chain() {
+        let documents = json!([
+            Document::new("The sky is blue"),
+            Document::new("Grass is green")
+        ]);
+        for chain_type in [
+            SummarizeChainType::Stuff,
+            SummarizeChainType::MapReduce,
+            SummarizeChainType::Refine,
+        ] {
+            let chain = load_summarize_chain(FakeLLM::echo(), chain_type);
+            let summary = chain
+                .invoke(prompt_args! {"input_documents" => documents.clone()})
+                .await
+                .unwrap();
+            assert!(summary.contains("The sky is blue"), "{:?}", chain_type);
+            assert!(summary.contains("Grass is green"), "{:?}", chain_type);
+        }
+    }
+}
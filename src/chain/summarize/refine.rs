@@ -0,0 +1,145 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    chain::{Chain, ChainError, LLMChain},
+    language_models::{GenerateResult, TokenUsage},
+    prompt::PromptArgs,
+    schemas::Document,
+};
+
+use super::{SUMMARIZE_DEFAULT_DOCUMENT_VARIABLE_NAME, SUMMARIZE_DEFAULT_INPUT_KEY};
+
+const DEFAULT_EXISTING_ANSWER_VARIABLE_NAME: &str = "existing_answer";
+
+/// Runs the initial chain on the first document of the `Vec<Document>` input, then the
+/// refine chain on each next document with the answer so far, e.g. to refine a summary
+/// document by document.
+///
+/// Both chains get the other input variables too, the document being under the document
+/// variable (`text` by default) and the answer so far under `existing_answer`.
+pub struct RefineChain {
+    initial_chain: LLMChain,
+    refine_chain: LLMChain,
+    input_key: String,
+    document_variable_name: String,
+    existing_answer_variable_name: String,
+}
+
+impl RefineChain {
+    pub fn new(initial_chain: LLMChain, refine_chain: LLMChain) -> Self {
+        Self {
+            initial_chain,
+            refine_chain,
+            input_key: SUMMARIZE_DEFAULT_INPUT_KEY.to_string(),
+            document_variable_name: SUMMARIZE_DEFAULT_DOCUMENT_VARIABLE_NAME.to_string(),
+            existing_answer_variable_name: DEFAULT_EXISTING_ANSWER_VARIABLE_NAME.to_string(),
+        }
+    }
+
+    /// The input variable with the documents. Default: `input_documents`
+    pub fn with_input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = input_key.into();
+        self
+    }
+
+    /// The variable of the prompts with the document. Default: `text`
+    pub fn with_document_variable_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.document_variable_name = name.into();
+        self
+    }
+
+    /// The variable of the refine prompt with the answer so far. Default: `existing_answer`
+    pub fn with_existing_answer_variable_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.existing_answer_variable_name = name.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Chain for RefineChain {
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        let run = CallbackManager::new().start_run("RefineChain", RunType::Chain);
+        let inputs = input_variables.clone();
+        run.trace_chain(&inputs, async move {
+            let docs = input_variables
+                .get(&self.input_key)
+                .ok_or_else(|| ChainError::MissingInputVariable(self.input_key.clone()))?;
+            let documents: Vec<Document> = serde_json::from_value(docs.clone()).map_err(|e| {
+                ChainError::IncorrectInputVariable {
+                    source: e,
+                    expected_type: "Vec<Document>".to_string(),
+                }
+            })?;
+
+            let mut tokens: Option<TokenUsage> = None;
+            let mut result: Option<GenerateResult> = None;
+            for document in documents {
+                let mut input_values = input_variables.clone();
+                input_values.insert(
+                    self.document_variable_name.clone(),
+                    Value::String(document.page_content),
+                );
+                let step = match &result {
+                    None => self.initial_chain.call(input_values).await?,
+                    Some(existing) => {
+                        input_values.insert(
+                            self.existing_answer_variable_name.clone(),
+                            Value::String(existing.generation.clone()),
+                        );
+                        self.refine_chain.call(input_values).await?
+                    }
+                };
+                if let Some(usage) = &step.tokens {
+                    tokens.get_or_insert_with(TokenUsage::default).add(usage);
+                }
+                result = Some(step);
+            }
+
+            let mut result = result.unwrap_or_default();
+            result.tokens = tokens;
+            Ok(result)
+        })
+        .await
+    }
+
+    fn get_input_keys(&self) -> Vec<String> {
+        vec![self.input_key.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{chain::LLMChainBuilder, llm::FakeLLM, prompt_args, template_fstring};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_refine_chain() {
+        let initial_chain = LLMChainBuilder::new()
+            .prompt(template_fstring!("{text}", "text"))
+            .llm(FakeLLM::echo())
+            .build()
+            .unwrap();
+        let refine_chain = LLMChainBuilder::new()
+            .prompt(template_fstring!(
+                "{existing_answer}+{text}",
+                "existing_answer",
+                "text"
+            ))
+            .llm(FakeLLM::echo())
+            .build()
+            .unwrap();
+        let chain = RefineChain::new(initial_chain, refine_chain);
+
+        let documents: Vec<Document> = ["a", "b", "c"].into_iter().map(Document::new).collect();
+        let summary = chain
+            .invoke(prompt_args! {"input_documents" => json!(documents)})
+            .await
+            .unwrap();
+        assert_eq!(summary, "a+b+c");
+    }
+}
@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+
+use crate::schemas::Document;
+
+use super::DocstoreError;
+
+/// Key-value store of documents by id, e.g. the full documents of the chunks indexed in
+/// a vector store.
+#[async_trait]
+pub trait Docstore: Send + Sync {
+    /// Returns the documents with the given ids, `None` for the ids that are not stored.
+    async fn get(&self, ids: &[String]) -> Result<Vec<Option<Document>>, DocstoreError>;
+
+    /// Stores the documents, replacing the ones already stored with the same ids.
+    async fn set(&self, documents: Vec<(String, Document)>) -> Result<(), DocstoreError>;
+
+    /// Deletes the documents with the given ids, ignoring the ids that are not stored.
+    async fn delete(&self, ids: &[String]) -> Result<(), DocstoreError>;
+}
+
+impl<D> From<D> for Box<dyn Docstore>
+where
+    D: Docstore + 'static,
+{
+    fn from(docstore: D) -> Self {
+        Box::new(docstore)
+    }
+}
@@ -0,0 +1,23 @@
+use std::io;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DocstoreError {
+    #[error("Invalid document id: {0}")]
+    InvalidId(String),
+
+    #[error(transparent)]
+    IOError(#[from] io::Error),
+
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    SqlxError(#[from] sqlx::Error),
+
+    #[cfg(feature = "redis")]
+    #[error(transparent)]
+    RedisError(#[from] redis::RedisError),
+}
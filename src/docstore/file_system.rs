@@ -0,0 +1,97 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+
+use crate::schemas::Document;
+
+use super::{Docstore, DocstoreError};
+
+/// Docstore keeping each document as a json file named after its id in a directory.
+pub struct FileSystemDocstore {
+    root: PathBuf,
+}
+
+impl FileSystemDocstore {
+    /// Stores the documents in `root`, created on the first write if needed.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf, DocstoreError> {
+        let is_valid = !id.is_empty()
+            && id != "."
+            && id != ".."
+            && !id.contains(['/', '\\'])
+            && !id.contains('\0');
+        if !is_valid {
+            return Err(DocstoreError::InvalidId(id.to_string()));
+        }
+        Ok(self.root.join(format!("{}.json", id)))
+    }
+}
+
+#[async_trait]
+impl Docstore for FileSystemDocstore {
+    async fn get(&self, ids: &[String]) -> Result<Vec<Option<Document>>, DocstoreError> {
+        let mut documents = Vec::with_capacity(ids.len());
+        for id in ids {
+            match tokio::fs::read(self.path(id)?).await {
+                Ok(content) => documents.push(Some(serde_json::from_slice(&content)?)),
+                Err(e) if e.kind() == ErrorKind::NotFound => documents.push(None),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(documents)
+    }
+
+    async fn set(&self, documents: Vec<(String, Document)>) -> Result<(), DocstoreError> {
+        tokio::fs::create_dir_all(&self.root).await?;
+        for (id, document) in documents {
+            tokio::fs::write(self.path(&id)?, serde_json::to_vec(&document)?).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, ids: &[String]) -> Result<(), DocstoreError> {
+        for id in ids {
+            match tokio::fs::remove_file(self.path(id)?).await {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_system_docstore() {
+        let root = std::env::temp_dir().join(format!("docstore-{}", uuid::Uuid::new_v4()));
+        let docstore = FileSystemDocstore::new(&root);
+        docstore
+            .set(vec![("a".to_string(), Document::new("first"))])
+            .await
+            .unwrap();
+
+        let documents = docstore
+            .get(&["a".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(documents[0].as_ref().unwrap().page_content, "first");
+        assert!(documents[1].is_none());
+
+        docstore.delete(&["a".to_string()]).await.unwrap();
+        assert!(docstore.get(&["a".to_string()]).await.unwrap()[0].is_none());
+        assert!(docstore.get(&["../a".to_string()]).await.is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
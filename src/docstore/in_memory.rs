@@ -0,0 +1,84 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+
+use crate::schemas::Document;
+
+use super::{Docstore, DocstoreError};
+
+/// Docstore keeping the documents in memory. Clones share the same documents.
+#[derive(Clone, Default)]
+pub struct InMemoryDocstore {
+    documents: Arc<RwLock<HashMap<String, Document>>>,
+}
+
+impl InMemoryDocstore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl Docstore for InMemoryDocstore {
+    async fn get(&self, ids: &[String]) -> Result<Vec<Option<Document>>, DocstoreError> {
+        let documents = self.documents.read().unwrap_or_else(|e| e.into_inner());
+        Ok(ids.iter().map(|id| documents.get(id).cloned()).collect())
+    }
+
+    async fn set(&self, documents: Vec<(String, Document)>) -> Result<(), DocstoreError> {
+        self.documents
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(documents);
+        Ok(())
+    }
+
+    async fn delete(&self, ids: &[String]) -> Result<(), DocstoreError> {
+        let mut documents = self.documents.write().unwrap_or_else(|e| e.into_inner());
+        for id in ids {
+            documents.remove(id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_docstore() {
+        let docstore = InMemoryDocstore::new();
+        docstore
+            .set(vec![
+                ("a".to_string(), Document::new("first")),
+                ("b".to_string(), Document::new("second")),
+            ])
+            .await
+            .unwrap();
+
+        let documents = docstore
+            .get(&["b".to_string(), "c".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(documents[0].as_ref().unwrap().page_content, "second");
+        assert!(documents[1].is_none());
+
+        docstore.delete(&["a".to_string()]).await.unwrap();
+        assert_eq!(docstore.len(), 1);
+    }
+}
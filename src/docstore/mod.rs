@@ -0,0 +1,23 @@
+mod docstore_trait;
+pub use docstore_trait::*;
+
+mod error;
+pub use error::*;
+
+mod in_memory;
+pub use in_memory::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file_system;
+#[cfg(not(target_arch = "wasm32"))]
+pub use file_system::*;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
+
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "redis")]
+pub use redis::*;
@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+use redis::{aio::MultiplexedConnection, AsyncCommands};
+
+use crate::schemas::Document;
+
+use super::{Docstore, DocstoreError};
+
+/// Docstore keeping the documents, as json, in Redis under `{prefix}{id}`.
+pub struct RedisDocstore {
+    client: redis::Client,
+    prefix: String,
+}
+
+impl RedisDocstore {
+    pub fn new(client: redis::Client) -> Self {
+        Self {
+            client,
+            prefix: "docstore:".to_string(),
+        }
+    }
+
+    pub fn from_url(url: &str) -> Result<Self, DocstoreError> {
+        Ok(Self::new(redis::Client::open(url)?))
+    }
+
+    pub fn with_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn key(&self, id: &str) -> String {
+        format!("{}{}", self.prefix, id)
+    }
+
+    async fn connection(&self) -> Result<MultiplexedConnection, DocstoreError> {
+        Ok(self.client.get_multiplexed_async_connection().await?)
+    }
+}
+
+#[async_trait]
+impl Docstore for RedisDocstore {
+    async fn get(&self, ids: &[String]) -> Result<Vec<Option<Document>>, DocstoreError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Vec<String> = ids.iter().map(|id| self.key(id)).collect();
+        let values: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(&mut self.connection().await?)
+            .await?;
+        values
+            .into_iter()
+            .map(|value| {
+                value
+                    .map(|value| serde_json::from_str(&value))
+                    .transpose()
+                    .map_err(DocstoreError::from)
+            })
+            .collect()
+    }
+
+    async fn set(&self, documents: Vec<(String, Document)>) -> Result<(), DocstoreError> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let items = documents
+            .iter()
+            .map(|(id, document)| Ok((self.key(id), serde_json::to_string(document)?)))
+            .collect::<Result<Vec<_>, DocstoreError>>()?;
+        let _: () = self.connection().await?.mset(&items).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, ids: &[String]) -> Result<(), DocstoreError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let keys: Vec<String> = ids.iter().map(|id| self.key(id)).collect();
+        let _: () = self.connection().await?.del(keys).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_redis_docstore() {
+        let docstore = RedisDocstore::from_url("redis://127.0.0.1/")
+            .unwrap()
+            .with_prefix("test-docstore:");
+        docstore
+            .set(vec![("a".to_string(), Document::new("first"))])
+            .await
+            .unwrap();
+
+        let documents = docstore
+            .get(&["a".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(documents[0].as_ref().unwrap().page_content, "first");
+        assert!(documents[1].is_none());
+
+        docstore.delete(&["a".to_string()]).await.unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+use async_trait::async_trait;
+use sqlx::{Pool, Row, Sqlite};
+
+use crate::schemas::Document;
+
+use super::{Docstore, DocstoreError};
+
+/// Docstore keeping the documents, as json, in a SQLite table.
+pub struct SqliteDocstore {
+    pool: Pool<Sqlite>,
+    table: String,
+}
+
+impl SqliteDocstore {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self {
+            pool,
+            table: "documents".to_string(),
+        }
+    }
+
+    pub fn with_table<S: Into<String>>(mut self, table: S) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Creates the table if it doesn't exist.
+    pub async fn initialize(&self) -> Result<(), DocstoreError> {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (id TEXT PRIMARY KEY, document TEXT NOT NULL)",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Docstore for SqliteDocstore {
+    async fn get(&self, ids: &[String]) -> Result<Vec<Option<Document>>, DocstoreError> {
+        let query = format!("SELECT document FROM {} WHERE id = ?", self.table);
+        let mut documents = Vec::with_capacity(ids.len());
+        for id in ids {
+            let row = sqlx::query(&query)
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+            let document = match row {
+                Some(row) => Some(serde_json::from_str(row.try_get("document")?)?),
+                None => None,
+            };
+            documents.push(document);
+        }
+        Ok(documents)
+    }
+
+    async fn set(&self, documents: Vec<(String, Document)>) -> Result<(), DocstoreError> {
+        let query = format!(
+            "INSERT INTO {} (id, document) VALUES (?, ?) \
+             ON CONFLICT(id) DO UPDATE SET document = excluded.document",
+            self.table
+        );
+        let mut tx = self.pool.begin().await?;
+        for (id, document) in documents {
+            sqlx::query(&query)
+                .bind(id)
+                .bind(serde_json::to_string(&document)?)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete(&self, ids: &[String]) -> Result<(), DocstoreError> {
+        let query = format!("DELETE FROM {} WHERE id = ?", self.table);
+        for id in ids {
+            sqlx::query(&query).bind(id).execute(&self.pool).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sqlite_docstore() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let docstore = SqliteDocstore::new(pool);
+        docstore.initialize().await.unwrap();
+
+        docstore
+            .set(vec![("a".to_string(), Document::new("first"))])
+            .await
+            .unwrap();
+        docstore
+            .set(vec![("a".to_string(), Document::new("updated"))])
+            .await
+            .unwrap();
+
+        let documents = docstore
+            .get(&["a".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(documents[0].as_ref().unwrap().page_content, "updated");
+        assert!(documents[1].is_none());
+
+        docstore.delete(&["a".to_string()]).await.unwrap();
+        assert!(docstore.get(&["a".to_string()]).await.unwrap()[0].is_none());
+    }
+}
@@ -0,0 +1,530 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+use async_openai::{
+    config::OpenAIConfig,
+    types::{AudioResponseFormat, CreateTranscriptionRequestArgs, TimestampGranularity},
+    Client,
+};
+use async_stream::stream;
+use async_trait::async_trait;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::process::Command;
+
+use crate::{
+    document_loaders::{process_doc_stream, Loader, LoaderError},
+    schemas::Document,
+    text_splitter::TextSplitter,
+};
+
+/// The size of the largest file the transcriptions API of OpenAI takes.
+const OPENAI_MAX_FILE_SIZE: u64 = 25 * 1024 * 1024;
+
+/// The service transcribing the audio files.
+#[derive(Debug, Clone)]
+pub enum TranscriptionBackend {
+    /// The transcriptions API of OpenAI, or of a compatible server, e.g. Groq. The API
+    /// takes files of up to 25 MB, the longer recordings can be transcribed locally with
+    /// whisper.cpp.
+    OpenAI { config: OpenAIConfig, model: String },
+    /// The `whisper-cli` program of [whisper.cpp](https://github.com/ggerganov/whisper.cpp),
+    /// with a GGML model, run locally. whisper.cpp reads 16 kHz WAV files, unless it is
+    /// built with ffmpeg.
+    WhisperCpp {
+        binary: PathBuf,
+        model_path: PathBuf,
+    },
+}
+
+impl TranscriptionBackend {
+    /// `whisper-1` with the OpenAI API.
+    pub fn openai(config: OpenAIConfig) -> Self {
+        TranscriptionBackend::OpenAI {
+            config,
+            model: "whisper-1".to_string(),
+        }
+    }
+
+    /// The model with `whisper-cli`, found in the `PATH`.
+    pub fn whisper_cpp<P: Into<PathBuf>>(model_path: P) -> Self {
+        TranscriptionBackend::WhisperCpp {
+            binary: PathBuf::from("whisper-cli"),
+            model_path: model_path.into(),
+        }
+    }
+}
+
+/// A part of the transcript, with its start and end in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start: f32,
+    pub end: f32,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transcript {
+    pub text: String,
+    pub language: Option<String>,
+    /// The duration of the audio in seconds.
+    pub duration: Option<f32>,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+/// Loads audio files, e.g. podcast episodes, as documents of their transcripts, with
+/// OpenAI Whisper or a local whisper.cpp.
+///
+/// Each file is a document with its `source`, `language`, `duration` and timestamped
+/// `segments` in the metadata, or one document per segment with its `start` and `end`
+/// with [`AudioLoader::with_document_per_segment`]. The files failing to be transcribed
+/// are errors of the stream, the other files being loaded.
+///
+/// # Example
+/// ```rust,ignore
+/// let documents = AudioLoader::from_paths(vec!["episodes/001.mp3", "episodes/002.mp3"])
+///     .with_language("en")
+///     .with_document_per_segment(true)
+///     .load()
+///     .await?
+///     .filter_map(|document| async { document.ok() })
+///     .collect::<Vec<_>>()
+///     .await;
+/// ```
+#[derive(Debug, Clone)]
+pub struct AudioLoader {
+    paths: Vec<PathBuf>,
+    backend: TranscriptionBackend,
+    language: Option<String>,
+    prompt: Option<String>,
+    document_per_segment: bool,
+}
+
+impl AudioLoader {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self::from_paths(vec![path])
+    }
+
+    pub fn from_paths<P: Into<PathBuf>>(paths: Vec<P>) -> Self {
+        Self {
+            paths: paths.into_iter().map(Into::into).collect(),
+            backend: TranscriptionBackend::openai(OpenAIConfig::default()),
+            language: None,
+            prompt: None,
+            document_per_segment: false,
+        }
+    }
+
+    /// Default: `whisper-1` with the OpenAI API.
+    pub fn with_backend(mut self, backend: TranscriptionBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// The ISO-639-1 code of the language of the audio, detected by default.
+    pub fn with_language<S: Into<String>>(mut self, language: S) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// A text guiding the transcription, e.g. the spelling of the names of the speakers.
+    pub fn with_prompt<S: Into<String>>(mut self, prompt: S) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    /// Loads a document per segment of the transcripts, instead of per file.
+    pub fn with_document_per_segment(mut self, document_per_segment: bool) -> Self {
+        self.document_per_segment = document_per_segment;
+        self
+    }
+
+    pub async fn transcribe(&self, path: &Path) -> Result<Transcript, LoaderError> {
+        match &self.backend {
+            TranscriptionBackend::OpenAI { config, model } => {
+                self.transcribe_openai(config, model, path).await
+            }
+            TranscriptionBackend::WhisperCpp { binary, model_path } => {
+                self.transcribe_whisper_cpp(binary, model_path, path).await
+            }
+        }
+    }
+
+    async fn transcribe_openai(
+        &self,
+        config: &OpenAIConfig,
+        model: &str,
+        path: &Path,
+    ) -> Result<Transcript, LoaderError> {
+        let size = tokio::fs::metadata(path).await?.len();
+        if size > OPENAI_MAX_FILE_SIZE {
+            return Err(LoaderError::LoadDocumentError(format!(
+                "{} is {:.1} MB, the OpenAI API takes files of up to 25 MB: split it, or \
+                 transcribe it with TranscriptionBackend::whisper_cpp",
+                path.display(),
+                size as f64 / (1024.0 * 1024.0)
+            )));
+        }
+        let mut request = CreateTranscriptionRequestArgs::default();
+        request
+            .file(path)
+            .model(model)
+            .response_format(AudioResponseFormat::VerboseJson)
+            .timestamp_granularities(vec![TimestampGranularity::Segment]);
+        if let Some(language) = &self.language {
+            request.language(language);
+        }
+        if let Some(prompt) = &self.prompt {
+            request.prompt(prompt);
+        }
+        let response = Client::with_config(config.clone())
+            .audio()
+            .transcribe_verbose_json(request.build()?)
+            .await?;
+        Ok(Transcript {
+            text: response.text.trim().to_string(),
+            language: Some(response.language),
+            duration: Some(response.duration),
+            segments: response
+                .segments
+                .unwrap_or_default()
+                .into_iter()
+                .map(|segment| TranscriptSegment {
+                    start: segment.start,
+                    end: segment.end,
+                    text: segment.text.trim().to_string(),
+                })
+                .collect(),
+        })
+    }
+
+    async fn transcribe_whisper_cpp(
+        &self,
+        binary: &Path,
+        model_path: &Path,
+        path: &Path,
+    ) -> Result<Transcript, LoaderError> {
+        // whisper.cpp writes the JSON transcript to `<output>.json`.
+        let output = std::env::temp_dir().join(format!("whisper-{}", uuid::Uuid::new_v4()));
+        let mut command = Command::new(binary);
+        command
+            .arg("--model")
+            .arg(model_path)
+            .arg("--file")
+            .arg(path)
+            .arg("--output-json")
+            .arg("--output-file")
+            .arg(&output)
+            .arg("--no-prints")
+            .arg("--language")
+            .arg(self.language.as_deref().unwrap_or("auto"));
+        if let Some(prompt) = &self.prompt {
+            command.arg("--prompt").arg(prompt);
+        }
+        let result = command.output().await?;
+        if !result.status.success() {
+            return Err(LoaderError::LoadDocumentError(format!(
+                "whisper.cpp failed to transcribe {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&result.stderr).trim()
+            )));
+        }
+        let json_path = output.with_extension("json");
+        let json = tokio::fs::read_to_string(&json_path).await;
+        let _ = tokio::fs::remove_file(&json_path).await;
+        let output: WhisperCppOutput = serde_json::from_str(&json?).map_err(|e| {
+            LoaderError::LoadDocumentError(format!("invalid whisper.cpp output: {}", e))
+        })?;
+
+        let segments: Vec<TranscriptSegment> = output
+            .transcription
+            .into_iter()
+            .map(|segment| TranscriptSegment {
+                start: segment.offsets.from as f32 / 1000.0,
+                end: segment.offsets.to as f32 / 1000.0,
+                text: segment.text.trim().to_string(),
+            })
+            .collect();
+        Ok(Transcript {
+            text: segments
+                .iter()
+                .map(|segment| segment.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            language: output.result.map(|result| result.language),
+            duration: segments.last().map(|segment| segment.end),
+            segments,
+        })
+    }
+
+    fn documents(&self, path: &Path, transcript: Transcript) -> Vec<Document> {
+        let mut metadata = HashMap::from([(
+            "source".to_string(),
+            Value::from(path.to_string_lossy().as_ref()),
+        )]);
+        if let Some(language) = &transcript.language {
+            metadata.insert("language".to_string(), Value::from(language.as_str()));
+        }
+        if self.document_per_segment {
+            return transcript
+                .segments
+                .into_iter()
+                .map(|segment| {
+                    let mut metadata = metadata.clone();
+                    metadata.insert("start".to_string(), Value::from(segment.start));
+                    metadata.insert("end".to_string(), Value::from(segment.end));
+                    Document::new(segment.text).with_metadata(metadata)
+                })
+                .collect();
+        }
+        if let Some(duration) = transcript.duration {
+            metadata.insert("duration".to_string(), Value::from(duration));
+        }
+        metadata.insert(
+            "segments".to_string(),
+            serde_json::to_value(&transcript.segments).unwrap_or_default(),
+        );
+        vec![Document::new(transcript.text).with_metadata(metadata)]
+    }
+}
+
+#[derive(Deserialize)]
+struct WhisperCppOutput {
+    result: Option<WhisperCppResult>,
+    #[serde(default)]
+    transcription: Vec<WhisperCppSegment>,
+}
+
+#[derive(Deserialize)]
+struct WhisperCppResult {
+    language: String,
+}
+
+#[derive(Deserialize)]
+struct WhisperCppSegment {
+    /// In milliseconds.
+    offsets: WhisperCppOffsets,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct WhisperCppOffsets {
+    from: u64,
+    to: u64,
+}
+
+#[async_trait]
+impl Loader for AudioLoader {
+    async fn load(
+        self,
+    ) -> Result<
+        Pin<Box<dyn Stream<Item = Result<Document, LoaderError>> + Send + 'static>>,
+        LoaderError,
+    > {
+        let stream = stream! {
+            for path in &self.paths {
+                match self.transcribe(path).await {
+                    Ok(transcript) => {
+                        for document in self.documents(path, transcript) {
+                            yield Ok(document);
+                        }
+                    }
+                    Err(e) => yield Err(e),
+                }
+            }
+        };
+        Ok(Box::pin(stream))
+    }
+
+    async fn load_and_split<TS: TextSplitter + 'static>(
+        self,
+        splitter: TS,
+    ) -> Result<
+        Pin<Box<dyn Stream<Item = Result<Document, LoaderError>> + Send + 'static>>,
+        LoaderError,
+    > {
+        let doc_stream = self.load().await?;
+        let stream = process_doc_stream(doc_stream, splitter).await;
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+
+    fn audio_file(dir: &std::path::Path) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join("episode.wav");
+        std::fs::write(&path, b"RIFF").unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_audio_loader_openai() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/audio/transcriptions")
+            .match_body(mockito::Matcher::Regex("verbose_json".to_string()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{
+                    "language": "english",
+                    "duration": 4.5,
+                    "text": " Welcome to the show. Today, Rust.",
+                    "segments": [
+                        {"id": 0, "seek": 0, "start": 0.0, "end": 2.0, "text": " Welcome to the show.",
+                         "tokens": [], "temperature": 0.0, "avg_logprob": -0.2,
+                         "compression_ratio": 1.1, "no_speech_prob": 0.01},
+                        {"id": 1, "seek": 0, "start": 2.0, "end": 4.5, "text": " Today, Rust.",
+                         "tokens": [], "temperature": 0.0, "avg_logprob": -0.2,
+                         "compression_ratio": 1.1, "no_speech_prob": 0.01}
+                    ]
+                }"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let path = audio_file(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        let backend = TranscriptionBackend::openai(
+            OpenAIConfig::new()
+                .with_api_base(server.url())
+                .with_api_key("key"),
+        );
+
+        let documents = AudioLoader::new(&path)
+            .with_backend(backend.clone())
+            .load()
+            .await
+            .unwrap()
+            .map(|document| document.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(documents.len(), 1);
+        assert_eq!(
+            documents[0].page_content,
+            "Welcome to the show. Today, Rust."
+        );
+        assert_eq!(documents[0].metadata["language"], "english");
+        assert_eq!(documents[0].metadata["duration"], 4.5);
+        assert_eq!(
+            documents[0].metadata["segments"][1],
+            serde_json::json!({"start": 2.0, "end": 4.5, "text": "Today, Rust."})
+        );
+
+        let documents = AudioLoader::new(&path)
+            .with_backend(backend)
+            .with_document_per_segment(true)
+            .load()
+            .await
+            .unwrap()
+            .map(|document| document.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].page_content, "Welcome to the show.");
+        assert_eq!(documents[1].metadata["start"], 2.0);
+        assert_eq!(documents[1].metadata["end"], 4.5);
+        assert_eq!(
+            documents[1].metadata["source"],
+            path.to_string_lossy().as_ref()
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_audio_loader_openai_file_size() {
+        let path = audio_file(&std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+        // A sparse file, larger than the limit.
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(OPENAI_MAX_FILE_SIZE + 1)
+            .unwrap();
+        let backend = TranscriptionBackend::openai(
+            OpenAIConfig::new()
+                .with_api_base("http://localhost:1")
+                .with_api_key("key"),
+        );
+
+        let result = AudioLoader::new(&path)
+            .with_backend(backend)
+            .transcribe(&path)
+            .await;
+        let Err(LoaderError::LoadDocumentError(message)) = result else {
+            panic!("Expected a file size error, got {:?}", result);
+        };
+        assert!(message.contains("25 MB"));
+        assert!(message.contains("whisper_cpp"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_audio_loader_whisper_cpp() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("whisper-cpp-{}", uuid::Uuid::new_v4()));
+        let path = audio_file(&dir);
+        // A fake whisper-cli writing the transcript to the `--output-file`.
+        let binary = dir.join("whisper-cli");
+        std::fs::write(
+            &binary,
+            r#"#!/bin/sh
+while [ $# -gt 0 ]; do
+  [ "$1" = "--output-file" ] && output="$2"
+  shift
+done
+printf '{"result":{"language":"en"},"transcription":[
+  {"offsets":{"from":0,"to":1500},"text":" Hello"},
+  {"offsets":{"from":1500,"to":3000},"text":" world."}]}' > "$output.json"
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let backend = TranscriptionBackend::WhisperCpp {
+            binary,
+            model_path: dir.join("ggml-base.en.bin"),
+        };
+
+        let transcript = AudioLoader::new(&path)
+            .with_backend(backend)
+            .transcribe(&path)
+            .await
+            .unwrap();
+        assert_eq!(transcript.text, "Hello world.");
+        assert_eq!(transcript.language.as_deref(), Some("en"));
+        assert_eq!(transcript.duration, Some(3.0));
+        assert_eq!(
+            transcript.segments[1],
+            TranscriptSegment {
+                start: 1.5,
+                end: 3.0,
+                text: "world.".to_string()
+            }
+        );
+
+        let failing = TranscriptionBackend::WhisperCpp {
+            binary: PathBuf::from("false"),
+            model_path: dir.join("ggml-base.en.bin"),
+        };
+        let documents = AudioLoader::new(&path)
+            .with_backend(failing)
+            .load()
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        assert!(matches!(
+            documents[0],
+            Err(LoaderError::LoadDocumentError(_))
+        ));
+    }
+}
@@ -0,0 +1,2 @@
+mod audio_loader;
+pub use audio_loader::*;
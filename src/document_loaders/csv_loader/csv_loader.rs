@@ -16,11 +16,23 @@ use std::pin::Pin;
 pub struct CsvLoader<R> {
     reader: R,
     columns: Vec<String>,
+    metadata_columns: Vec<String>,
 }
 
 impl<R: Read> CsvLoader<R> {
     pub fn new(reader: R, columns: Vec<String>) -> Self {
-        Self { reader, columns }
+        Self {
+            reader,
+            columns,
+            metadata_columns: Vec::new(),
+        }
+    }
+
+    /// Columns copied to the metadata of the documents, keyed by their header.
+    /// The content only has the `columns`, so a column can be in both.
+    pub fn with_metadata_columns(mut self, metadata_columns: Vec<String>) -> Self {
+        self.metadata_columns = metadata_columns;
+        self
     }
 }
 
@@ -54,14 +66,19 @@ impl<R: Read + Send + Sync + 'static> Loader for CsvLoader<R> {
         // Initialize rown to track row number
         let mut row_number: i64 = 0;
         let columns = self.columns.clone();
+        let metadata_columns = self.metadata_columns.clone();
 
         let stream = stream! {
             for result in reader.records() {
                 let record = result?;
                 let mut content = String::new();
+                let mut metadata = HashMap::new();
 
                 for (i, field) in record.iter().enumerate() {
                     let header = &headers[i];
+                    if metadata_columns.iter().any(|column| column == header) {
+                        metadata.insert(header.to_string(), Value::from(field));
+                    }
                     if !columns.contains(&header.to_string()) {
                         continue;
                     }
@@ -75,7 +92,6 @@ impl<R: Read + Send + Sync + 'static> Loader for CsvLoader<R> {
 
                 // Generate document with the content and metadata
                 let mut document = Document::new(content);
-                let mut metadata = HashMap::new();
                 metadata.insert("row".to_string(), Value::from(row_number));
 
                 // Attach the metadata to the document
@@ -170,4 +186,34 @@ Jane Smith,32,London,United Kingdom";
         assert_eq!(documents[1].metadata.get("row").unwrap(), &Value::from(2));
         assert_eq!(documents[1].page_content, expected2);
     }
+
+    #[tokio::test]
+    async fn test_csv_loader_metadata_columns() {
+        let input = "name,age,city
+John Doe,25,New York
+Jane Smith,32,London";
+
+        let csv_loader = CsvLoader::new(input.as_bytes(), vec!["name".to_string()])
+            .with_metadata_columns(vec!["city".to_string()]);
+
+        let documents = csv_loader
+            .load()
+            .await
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(documents[0].page_content, "name: John Doe\n");
+        assert_eq!(
+            documents[0].metadata.get("city").unwrap(),
+            &Value::from("New York")
+        );
+        assert_eq!(documents[1].metadata.get("row").unwrap(), &Value::from(2));
+        assert_eq!(
+            documents[1].metadata.get("city").unwrap(),
+            &Value::from("London")
+        );
+        assert!(!documents[1].metadata.contains_key("age"));
+    }
 }
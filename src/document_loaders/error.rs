@@ -1,5 +1,6 @@
 use std::io;
 
+use async_openai::error::OpenAIError;
 use thiserror::Error;
 
 use crate::text_splitter::TextSplitterError;
@@ -18,12 +19,28 @@ pub enum LoaderError {
     #[error(transparent)]
     CSVError(#[from] csv::Error),
 
+    #[cfg(feature = "pdf")]
     #[error(transparent)]
     LoPdfError(#[from] lopdf::Error),
 
+    #[cfg(feature = "pdf")]
+    #[error("The pdf is encrypted and can't be decrypted: {0}")]
+    PdfEncrypted(lopdf::Error),
+
+    #[cfg(feature = "pdf")]
+    #[error("Error extracting the text of page {page}: {source}")]
+    PdfPageError { page: u32, source: lopdf::Error },
+
+    #[cfg(not(target_arch = "wasm32"))]
     #[error(transparent)]
     ReadabilityError(#[from] readability::error::Error),
 
+    #[error(transparent)]
+    RequestError(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    OpenAIError(Box<OpenAIError>),
+
     #[error(transparent)]
     JoinError(#[from] tokio::task::JoinError),
 
@@ -34,3 +51,9 @@ pub enum LoaderError {
     #[error("Error: {0}")]
     OtherError(String),
 }
+
+impl From<OpenAIError> for LoaderError {
+    fn from(e: OpenAIError) -> Self {
+        LoaderError::OpenAIError(Box::new(e))
+    }
+}
@@ -12,19 +12,37 @@ mod git_commit_loader;
 #[cfg(feature = "git")]
 pub use git_commit_loader::*;
 
+#[cfg(not(target_arch = "wasm32"))]
 mod pandoc_loader;
+#[cfg(not(target_arch = "wasm32"))]
 pub use pandoc_loader::*;
 
+#[cfg(feature = "pdf")]
 mod pdf_loader;
+#[cfg(feature = "pdf")]
 pub use pdf_loader::*;
 
+#[cfg(not(target_arch = "wasm32"))]
 mod html_loader;
+#[cfg(not(target_arch = "wasm32"))]
 pub use html_loader::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod web_loader;
+#[cfg(not(target_arch = "wasm32"))]
+pub use web_loader::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod audio_loader;
+#[cfg(not(target_arch = "wasm32"))]
+pub use audio_loader::*;
+
 mod error;
 pub use error::*;
 
+#[cfg(not(target_arch = "wasm32"))]
 mod dir_loader;
+#[cfg(not(target_arch = "wasm32"))]
 pub use dir_loader::*;
 
 #[cfg(feature = "tree-sitter")]
@@ -11,9 +11,15 @@ use crate::{
     text_splitter::TextSplitter,
 };
 
+/// Loads a pdf with `lopdf`, one document per page with its `page_number` in the metadata.
+///
+/// An encrypted pdf is decrypted with the password set with `with_password`, or the empty
+/// password otherwise. A page whose text can't be extracted is yielded as a
+/// `LoaderError::PdfPageError` and the following pages are still loaded.
 #[derive(Debug, Clone)]
 pub struct LoPdfLoader {
     document: lopdf::Document,
+    password: Option<String>,
 }
 
 impl LoPdfLoader {
@@ -30,7 +36,10 @@ impl LoPdfLoader {
     ///
     pub fn new<R: Read>(reader: R) -> Result<Self, LoaderError> {
         let document = lopdf::Document::load_from(reader)?;
-        Ok(Self { document })
+        Ok(Self {
+            document,
+            password: None,
+        })
     }
     /// Creates a new PdfLoader from a path to a PDF file.
     /// This loads the PDF document and creates a PdfLoader from it.
@@ -43,7 +52,16 @@ impl LoPdfLoader {
     ///
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, LoaderError> {
         let document = lopdf::Document::load(path)?;
-        Ok(Self { document })
+        Ok(Self {
+            document,
+            password: None,
+        })
+    }
+
+    /// Password of an encrypted PDF.
+    pub fn with_password<S: Into<String>>(mut self, password: S) -> Self {
+        self.password = Some(password.into());
+        self
     }
 }
 
@@ -55,16 +73,28 @@ impl Loader for LoPdfLoader {
         Pin<Box<dyn Stream<Item = Result<Document, LoaderError>> + Send + 'static>>,
         LoaderError,
     > {
+        if self.document.is_encrypted() {
+            let password = self.password.take().unwrap_or_default();
+            self.document
+                .decrypt(password)
+                .map_err(LoaderError::PdfEncrypted)?;
+        }
+
         let stream = stream! {
             let pages = self.document.get_pages();
-            for (i, _) in pages.iter().enumerate() {
-                let page_number = (i + 1) as u32;
-                let text = self.document.extract_text(&[page_number])?;
-                let mut metadata = HashMap::new();
-                metadata.insert("page_number".to_string(), Value::from(page_number));
-                let doc=Document::new(text).with_metadata(metadata);
-                yield Ok(doc);
-
+            for page_number in pages.keys() {
+                match self.document.extract_text(&[*page_number]) {
+                    Ok(text) => {
+                        let mut metadata = HashMap::new();
+                        metadata.insert("page_number".to_string(), Value::from(*page_number));
+                        let doc = Document::new(text).with_metadata(metadata);
+                        yield Ok(doc);
+                    }
+                    Err(source) => yield Err(LoaderError::PdfPageError {
+                        page: *page_number,
+                        source,
+                    }),
+                }
             }
         };
 
@@ -137,4 +167,13 @@ mod tests {
         );
         assert_eq!(docs.len(), 10);
     }
+
+    #[test]
+    fn test_lo_pdf_loader_malformed() {
+        let reader = Cursor::new(b"%PDF-1.4 this is not a pdf".to_vec());
+        assert!(matches!(
+            LoPdfLoader::new(reader),
+            Err(LoaderError::LoPdfError(_))
+        ));
+    }
 }
@@ -1 +1,4 @@
 pub mod lo_loader;
+
+/// The default pdf loader, one document per page.
+pub type PdfLoader = lo_loader::LoPdfLoader;
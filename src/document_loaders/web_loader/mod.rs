@@ -0,0 +1,2 @@
+mod web_loader;
+pub use web_loader::*;
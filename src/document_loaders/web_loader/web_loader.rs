@@ -0,0 +1,549 @@
+use std::{
+    collections::{HashMap, HashSet},
+    pin::Pin,
+};
+
+use async_stream::stream;
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use scraper::{Html, Selector};
+use serde_json::Value;
+use url::Url;
+
+use crate::{
+    document_loaders::{process_doc_stream, Loader, LoaderError},
+    schemas::Document,
+    text_splitter::TextSplitter,
+};
+
+const MAX_REDIRECTS: usize = 10;
+
+struct Page {
+    url: Url,
+    html: String,
+    depth: usize,
+}
+
+/// Loads web pages, crawling the links of the pages, as documents of the main content of
+/// the pages, extracted with readability.
+///
+/// The crawl starts from the given urls, or from those of a sitemap, following the links
+/// up to `max_depth` (by default 0, only the start pages are loaded) on the allowed
+/// domains, by default only the hosts of the urls given to the loader. The redirects and
+/// the pages of a sitemap off the allowed domains aren't followed, and the responses
+/// larger than `max_response_size` are not read. The pages of each depth are fetched
+/// concurrently. The pages failing to load are errors of the stream, the crawl going on
+/// with the other pages.
+///
+/// # Example
+/// ```rust,ignore
+/// let documents = WebLoader::from_sitemap(Url::parse("https://docs.rs/sitemap.xml")?)
+///     .with_max_pages(100)
+///     .load()
+///     .await?
+///     .filter_map(|document| async { document.ok() })
+///     .collect::<Vec<_>>()
+///     .await;
+/// ```
+#[derive(Debug, Clone)]
+pub struct WebLoader {
+    urls: Vec<Url>,
+    sitemap: Option<Url>,
+    max_depth: usize,
+    allowed_domains: Option<Vec<String>>,
+    max_concurrency: usize,
+    max_pages: Option<usize>,
+    max_response_size: usize,
+    client: reqwest::Client,
+}
+
+/// The domains the loader fetches pages from.
+struct AllowedDomains {
+    domains: Vec<String>,
+    subdomains: bool,
+}
+
+impl AllowedDomains {
+    fn contains(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str().map(str::to_lowercase) else {
+            return false;
+        };
+        self.domains.iter().any(|domain| {
+            host == *domain || (self.subdomains && host.ends_with(&format!(".{}", domain)))
+        })
+    }
+}
+
+impl WebLoader {
+    pub fn new(url: Url) -> Self {
+        Self::from_urls(vec![url])
+    }
+
+    pub fn from_urls(urls: Vec<Url>) -> Self {
+        Self {
+            urls,
+            sitemap: None,
+            max_depth: 0,
+            allowed_domains: None,
+            max_concurrency: 4,
+            max_pages: None,
+            max_response_size: 10 * 1024 * 1024,
+            // The redirects are followed by the loader, to check their domain.
+            client: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Starts from the pages of the sitemap, following the nested sitemaps of a sitemap
+    /// index.
+    pub fn from_sitemap(sitemap: Url) -> Self {
+        Self {
+            sitemap: Some(sitemap),
+            ..Self::from_urls(Vec::new())
+        }
+    }
+
+    /// How many links away from the start pages are loaded. Default: 0
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// The domains of the followed links, their subdomains included.
+    /// Default: the hosts of the urls given to the loader, without their subdomains
+    pub fn with_allowed_domains<S: AsRef<str>>(mut self, allowed_domains: &[S]) -> Self {
+        self.allowed_domains = Some(
+            allowed_domains
+                .iter()
+                .map(|domain| domain.as_ref().to_lowercase())
+                .collect(),
+        );
+        self
+    }
+
+    /// The maximum number of pages fetched at the same time. Default: 4
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// The maximum number of pages loaded.
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// The maximum size in bytes of a page or a sitemap. Default: 10 MiB
+    pub fn with_max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+
+    /// The client fetching the pages. Its redirects should be disabled, with
+    /// `reqwest::redirect::Policy::none()`, for the loader to check their domain before
+    /// following them, the pages it redirects to off the allowed domains are dropped
+    /// anyway.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    async fn fetch(
+        &self,
+        url: &Url,
+        allowed_domains: &AllowedDomains,
+    ) -> Result<(Url, String, bool), LoaderError> {
+        let mut url = url.clone();
+        let mut redirects = 0;
+        let mut response = loop {
+            let response = self.client.get(url.clone()).send().await?;
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|location| location.to_str().ok());
+            let (true, Some(location)) = (response.status().is_redirection(), location) else {
+                break response;
+            };
+            let next = url.join(location).map_err(|e| {
+                LoaderError::LoadDocumentError(format!("{}: invalid redirect: {}", url, e))
+            })?;
+            if redirects == MAX_REDIRECTS {
+                return Err(LoaderError::LoadDocumentError(format!(
+                    "{}: too many redirects",
+                    url
+                )));
+            }
+            if !allowed_domains.contains(&next) {
+                return Err(LoaderError::LoadDocumentError(format!(
+                    "{}: redirect to {} off the allowed domains",
+                    url, next
+                )));
+            }
+            url = next;
+            redirects += 1;
+        };
+        let url = response.url().clone();
+        if !allowed_domains.contains(&url) {
+            return Err(LoaderError::LoadDocumentError(format!(
+                "{}: off the allowed domains",
+                url
+            )));
+        }
+        let status = response.status();
+        if !status.is_success() {
+            return Err(LoaderError::LoadDocumentError(format!(
+                "{}: HTTP {}",
+                url,
+                status.as_u16()
+            )));
+        }
+        let is_html = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_none_or(|content_type| content_type.contains("html"));
+
+        let too_large = || {
+            LoaderError::LoadDocumentError(format!(
+                "{}: larger than {} bytes",
+                url, self.max_response_size
+            ))
+        };
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_response_size as u64)
+        {
+            return Err(too_large());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > self.max_response_size {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok((
+            url.clone(),
+            String::from_utf8_lossy(&body).into_owned(),
+            is_html,
+        ))
+    }
+
+    async fn fetch_page(
+        &self,
+        url: Url,
+        depth: usize,
+        allowed_domains: &AllowedDomains,
+    ) -> Result<Option<Page>, LoaderError> {
+        let (url, html, is_html) = self.fetch(&url, allowed_domains).await?;
+        if !is_html {
+            log::debug!("WebLoader skipping {}: not an html page", url);
+            return Ok(None);
+        }
+        Ok(Some(Page { url, html, depth }))
+    }
+
+    /// The page urls of the sitemap and of its nested sitemaps.
+    async fn sitemap_urls(
+        &self,
+        sitemap: &Url,
+        allowed_domains: &AllowedDomains,
+    ) -> Result<Vec<Url>, LoaderError> {
+        let mut urls = Vec::new();
+        let mut sitemaps = vec![sitemap.clone()];
+        let mut seen_sitemaps = HashSet::new();
+        while let Some(sitemap) = sitemaps.pop() {
+            if !seen_sitemaps.insert(sitemap.clone()) {
+                continue;
+            }
+            let (_, xml, _) = self.fetch(&sitemap, allowed_domains).await?;
+            let (pages, nested) = parse_sitemap(&xml, &sitemap);
+            urls.extend(
+                pages
+                    .into_iter()
+                    .filter(|url| allowed_domains.contains(url)),
+            );
+            sitemaps.extend(
+                nested
+                    .into_iter()
+                    .filter(|url| allowed_domains.contains(url)),
+            );
+        }
+        Ok(urls)
+    }
+
+    fn allowed_domains(&self) -> AllowedDomains {
+        match &self.allowed_domains {
+            Some(domains) => AllowedDomains {
+                domains: domains.clone(),
+                subdomains: true,
+            },
+            None => AllowedDomains {
+                domains: self
+                    .urls
+                    .iter()
+                    .chain(self.sitemap.as_ref())
+                    .filter_map(|url| url.host_str().map(str::to_lowercase))
+                    .collect(),
+                subdomains: false,
+            },
+        }
+    }
+}
+
+/// The urls of the links of the page, without their fragment.
+fn extract_links(page: &Page) -> Vec<Url> {
+    let document = Html::parse_document(&page.html);
+    let selector = Selector::parse("a[href]").unwrap();
+    document
+        .select(&selector)
+        .filter_map(|link| link.value().attr("href"))
+        .filter_map(|href| page.url.join(href).ok())
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|mut url| {
+            url.set_fragment(None);
+            url
+        })
+        .collect()
+}
+
+/// The page urls and the nested sitemap urls of the sitemap.
+fn parse_sitemap(xml: &str, sitemap: &Url) -> (Vec<Url>, Vec<Url>) {
+    let document = Html::parse_document(xml);
+    let locations = |selector: &str| -> Vec<Url> {
+        let selector = Selector::parse(selector).unwrap();
+        document
+            .select(&selector)
+            .filter_map(|loc| sitemap.join(loc.text().collect::<String>().trim()).ok())
+            .collect()
+    };
+    (locations("url > loc"), locations("sitemap > loc"))
+}
+
+fn page_to_document(page: Page) -> Result<Document, LoaderError> {
+    let product = readability::extractor::extract(&mut page.html.as_bytes(), &page.url)?;
+    Ok(
+        Document::new(format!("{}\n{}", product.title, product.text)).with_metadata(HashMap::from(
+            [
+                ("source".to_string(), Value::from(page.url.as_str())),
+                ("title".to_string(), Value::from(product.title)),
+                ("depth".to_string(), Value::from(page.depth)),
+            ],
+        )),
+    )
+}
+
+#[async_trait]
+impl Loader for WebLoader {
+    async fn load(
+        self,
+    ) -> Result<
+        Pin<Box<dyn Stream<Item = Result<Document, LoaderError>> + Send + 'static>>,
+        LoaderError,
+    > {
+        let allowed_domains = self.allowed_domains();
+        let start_urls = match &self.sitemap {
+            Some(sitemap) => {
+                let mut urls = self.urls.clone();
+                urls.extend(self.sitemap_urls(sitemap, &allowed_domains).await?);
+                urls
+            }
+            None => self.urls.clone(),
+        };
+        let max_pages = self.max_pages.unwrap_or(usize::MAX);
+
+        let loader = self;
+        let stream = stream! {
+            let mut visited: HashSet<Url> = HashSet::new();
+            let mut level: Vec<Url> = Vec::new();
+            for mut url in start_urls {
+                url.set_fragment(None);
+                if visited.len() < max_pages && visited.insert(url.clone()) {
+                    level.push(url);
+                }
+            }
+
+            for depth in 0..=loader.max_depth {
+                if level.is_empty() {
+                    break;
+                }
+                let mut next_level = Vec::new();
+                let mut pages = futures::stream::iter(level)
+                    .map(|url| loader.fetch_page(url, depth, &allowed_domains))
+                    .buffer_unordered(loader.max_concurrency);
+                while let Some(page) = pages.next().await {
+                    match page {
+                        Ok(Some(page)) => {
+                            if depth < loader.max_depth {
+                                for link in extract_links(&page) {
+                                    if visited.len() < max_pages
+                                        && allowed_domains.contains(&link)
+                                        && visited.insert(link.clone())
+                                    {
+                                        next_level.push(link);
+                                    }
+                                }
+                            }
+                            yield page_to_document(page);
+                        }
+                        Ok(None) => {}
+                        Err(e) => yield Err(e),
+                    }
+                }
+                level = next_level;
+            }
+        };
+        Ok(Box::pin(stream))
+    }
+
+    async fn load_and_split<TS: TextSplitter + 'static>(
+        self,
+        splitter: TS,
+    ) -> Result<
+        Pin<Box<dyn Stream<Item = Result<Document, LoaderError>> + Send + 'static>>,
+        LoaderError,
+    > {
+        let doc_stream = self.load().await?;
+        let stream = process_doc_stream(doc_stream, splitter).await;
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn mock_page(server: &mut mockito::ServerGuard, path: &str, body: &str) -> mockito::Mock {
+        server
+            .mock("GET", path)
+            .with_header("content-type", "text/html; charset=utf-8")
+            .with_body(format!(
+                "<html><head><title>{path}</title></head><body><p>{body}</p></body></html>"
+            ))
+            .create_async()
+            .await
+    }
+
+    async fn load_sources(loader: WebLoader) -> Vec<String> {
+        let mut sources: Vec<String> = loader
+            .load()
+            .await
+            .unwrap()
+            .filter_map(|document| async { document.ok() })
+            .map(|document| document.metadata["source"].as_str().unwrap().to_string())
+            .collect()
+            .await;
+        sources.sort();
+        sources
+    }
+
+    #[tokio::test]
+    async fn test_web_loader_crawl() {
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
+        mock_page(
+            &mut server,
+            "/",
+            r#"Home <a href="/a#section">A</a> <a href="https://example.com/">External</a>"#,
+        )
+        .await;
+        mock_page(
+            &mut server,
+            "/a",
+            r#"Page A <a href="/b">B</a> <a href="/">Home</a>"#,
+        )
+        .await;
+        let b = mock_page(&mut server, "/b", "Page B").await;
+
+        let start = Url::parse(&url).unwrap();
+        let sources = load_sources(WebLoader::new(start.clone()).with_max_depth(1)).await;
+        assert_eq!(sources, vec![format!("{url}/"), format!("{url}/a")]);
+
+        let sources = load_sources(WebLoader::new(start).with_max_depth(5)).await;
+        assert_eq!(sources.len(), 3);
+        b.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_web_loader_sitemap() {
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
+        server
+            .mock("GET", "/sitemap.xml")
+            .with_header("content-type", "application/xml")
+            .with_body(format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>{url}/pages.xml</loc></sitemap>
+</sitemapindex>"#
+            ))
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/pages.xml")
+            .with_body(format!(
+                r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>{url}/a</loc></url>
+  <url><loc>{url}/missing</loc></url>
+</urlset>"#
+            ))
+            .create_async()
+            .await;
+        mock_page(&mut server, "/a", "Page A").await;
+        server
+            .mock("GET", "/missing")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let loader = WebLoader::from_sitemap(Url::parse(&format!("{url}/sitemap.xml")).unwrap());
+        let results: Vec<Result<Document, LoaderError>> =
+            loader.load().await.unwrap().collect().await;
+        assert_eq!(results.len(), 2);
+        let document = results.iter().find_map(|r| r.as_ref().ok()).unwrap();
+        assert!(document.page_content.contains("Page A"));
+        assert_eq!(document.metadata["title"], "/a");
+        assert!(results.iter().any(|r| r.is_err()));
+    }
+
+    #[tokio::test]
+    async fn test_web_loader_redirects_and_size() {
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
+        let port = Url::parse(&url).unwrap().port().unwrap();
+        server
+            .mock("GET", "/moved")
+            .with_status(302)
+            .with_header("location", "/a")
+            .create_async()
+            .await;
+        // The same server under another host, off the allowed domains.
+        server
+            .mock("GET", "/away")
+            .with_status(302)
+            .with_header("location", &format!("http://localhost:{port}/b"))
+            .create_async()
+            .await;
+        mock_page(&mut server, "/a", "Page A").await;
+        let b = mock_page(&mut server, "/b", "Page B").await.expect(0);
+        mock_page(&mut server, "/large", &"large ".repeat(1000)).await;
+
+        let start = |path: &str| Url::parse(&format!("{url}{path}")).unwrap();
+        let sources = load_sources(WebLoader::new(start("/moved"))).await;
+        assert_eq!(sources, vec![format!("{url}/a")]);
+
+        let results: Vec<Result<Document, LoaderError>> =
+            WebLoader::from_urls(vec![start("/away"), start("/large")])
+                .with_max_response_size(1000)
+                .load()
+                .await
+                .unwrap()
+                .collect()
+                .await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.is_err()));
+        b.assert_async().await;
+    }
+}
@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+
+use crate::schemas::Document;
+
+use super::DocumentTransformerError;
+
+/// Transforms documents, e.g. between loading and storing them in an ingestion pipeline.
+#[async_trait]
+pub trait DocumentTransformer: Send + Sync {
+    async fn transform_documents(
+        &self,
+        documents: &[Document],
+    ) -> Result<Vec<Document>, DocumentTransformerError>;
+}
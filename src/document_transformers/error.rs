@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+use crate::chain::ChainError;
+
+#[derive(Error, Debug)]
+pub enum DocumentTransformerError {
+    #[error("Chain error: {0}")]
+    ChainError(#[from] ChainError),
+
+    #[error("Serde json error: {0}")]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error("Error: {0}")]
+    OtherError(String),
+}
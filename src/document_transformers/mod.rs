@@ -0,0 +1,8 @@
+mod error;
+pub use error::*;
+
+mod document_transformer;
+pub use document_transformer::*;
+
+mod pii;
+pub use pii::*;
@@ -0,0 +1,225 @@
+use async_trait::async_trait;
+use regex::Regex;
+
+use crate::{
+    chain::{Chain, ChainError, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt_args, template_jinja2,
+};
+
+use super::{PiiEntity, PiiKind};
+
+/// Finds personally identifiable information in texts.
+#[async_trait]
+pub trait PiiDetector: Send + Sync {
+    async fn detect(&self, text: &str) -> Result<Vec<PiiEntity>, ChainError>;
+}
+
+const EMAIL_PATTERN: &str = r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b";
+const CREDIT_CARD_PATTERN: &str = r"\b(?:\d[ -]?){12,18}\d\b";
+const PHONE_PATTERN: &str =
+    r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{1,4}\)[\s.-]?)?\d{2,4}(?:[\s.-]?\d{2,4}){1,4}\b";
+// Only the names following a title, in the first group: finding every name needs a model,
+// see `LLMNameDetector`.
+const NAME_PATTERN: &str = r"\b(?:Mr|Mrs|Ms|Miss|Dr|Prof)\.?\s+([A-Z][a-z]+(?:\s+[A-Z][a-z]+)*)";
+
+/// Whether the digits of `text` pass the Luhn checksum of the credit card numbers.
+// `is_multiple_of` is only stable since Rust 1.87.
+#[allow(clippy::manual_is_multiple_of)]
+fn luhn(text: &str) -> bool {
+    let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2 == 1, digit * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => digit,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+fn is_valid(kind: &PiiKind, text: &str) -> bool {
+    match kind {
+        PiiKind::CreditCard => luhn(text),
+        PiiKind::Phone => (9..=15).contains(&text.chars().filter(char::is_ascii_digit).count()),
+        _ => true,
+    }
+}
+
+/// Finds emails, phone numbers, credit card numbers (checked with the Luhn checksum) and
+/// the names following a title (`Mr.`, `Dr.`...) with regular expressions.
+///
+/// The patterns with a capture group detect the text of their first group.
+pub struct RegexPiiDetector {
+    patterns: Vec<(PiiKind, Regex)>,
+}
+
+impl RegexPiiDetector {
+    pub fn new() -> Self {
+        let patterns = [
+            (PiiKind::Email, EMAIL_PATTERN),
+            (PiiKind::CreditCard, CREDIT_CARD_PATTERN),
+            (PiiKind::Phone, PHONE_PATTERN),
+            (PiiKind::Name, NAME_PATTERN),
+        ];
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(|(kind, pattern)| (kind, Regex::new(pattern).unwrap())) //The default patterns are valid
+                .collect(),
+        }
+    }
+
+    /// Only detects the kinds in `kinds`.
+    pub fn with_kinds(mut self, kinds: &[PiiKind]) -> Self {
+        self.patterns.retain(|(kind, _)| kinds.contains(kind));
+        self
+    }
+
+    /// Detects the matches of `pattern` as `kind`, e.g. the national id numbers.
+    pub fn with_pattern(mut self, kind: PiiKind, pattern: &str) -> Result<Self, regex::Error> {
+        self.patterns.push((kind, Regex::new(pattern)?));
+        Ok(self)
+    }
+
+    fn find(&self, text: &str) -> Vec<PiiEntity> {
+        self.patterns
+            .iter()
+            .flat_map(|(kind, pattern)| {
+                pattern.captures_iter(text).filter_map(move |captures| {
+                    let found = captures.get(1).or_else(|| captures.get(0))?;
+                    is_valid(kind, found.as_str()).then(|| PiiEntity {
+                        kind: kind.clone(),
+                        start: found.start(),
+                        end: found.end(),
+                        text: found.as_str().to_string(),
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+impl Default for RegexPiiDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl PiiDetector for RegexPiiDetector {
+    async fn detect(&self, text: &str) -> Result<Vec<PiiEntity>, ChainError> {
+        Ok(self.find(text))
+    }
+}
+
+const DEFAULT_NAMES_TEMPLATE: &str = r#"List the names of the people mentioned in the following text, exactly as they are written.
+
+Text: {{text}}
+
+Answer only with a json array of strings, or [] if there are no names."#;
+
+/// Finds the names of the people with an LLM used as a named entity recognition model.
+pub struct LLMNameDetector {
+    chain: LLMChain,
+}
+
+impl LLMNameDetector {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L) -> Self {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(DEFAULT_NAMES_TEMPLATE, "text"))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        Self { chain }
+    }
+}
+
+/// Parses the json array answered by the LLM, which can be wrapped in a markdown code block.
+fn parse_names(output: &str) -> Result<Vec<String>, serde_json::Error> {
+    let output = output.trim();
+    let start = output.find('[').unwrap_or_default();
+    let end = output.rfind(']').map(|end| end + 1).unwrap_or(output.len());
+    serde_json::from_str(&output[start..end.max(start)])
+}
+
+/// Finds every occurrence of the `names` in `text`.
+fn find_names(text: &str, names: &[String]) -> Vec<PiiEntity> {
+    names
+        .iter()
+        .filter(|name| !name.trim().is_empty())
+        .flat_map(|name| {
+            text.match_indices(name.as_str())
+                .map(|(start, name)| PiiEntity {
+                    kind: PiiKind::Name,
+                    start,
+                    end: start + name.len(),
+                    text: name.to_string(),
+                })
+        })
+        .collect()
+}
+
+#[async_trait]
+impl PiiDetector for LLMNameDetector {
+    async fn detect(&self, text: &str) -> Result<Vec<PiiEntity>, ChainError> {
+        let output = self.chain.invoke(prompt_args! {"text" => text}).await?;
+        Ok(find_names(text, &parse_names(&output)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regex_pii_detector() {
+        let text = "Contact Dr. Jane Smith at jane.smith@example.com or +1 (555) 123-4567, card 4111 1111 1111 1111, order 2024-01-15.";
+        let entities = RegexPiiDetector::new().find(text);
+        let found: Vec<(PiiKind, &str)> = entities
+            .iter()
+            .map(|entity| (entity.kind.clone(), &text[entity.start..entity.end]))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (PiiKind::Email, "jane.smith@example.com"),
+                (PiiKind::CreditCard, "4111 1111 1111 1111"),
+                (PiiKind::Phone, "+1 (555) 123-4567"),
+                (PiiKind::Name, "Jane Smith"),
+            ]
+        );
+
+        let entities = RegexPiiDetector::new().find("card 4111 1111 1111 1112");
+        assert!(entities
+            .iter()
+            .all(|entity| entity.kind != PiiKind::CreditCard));
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let detector = RegexPiiDetector::new()
+            .with_kinds(&[])
+            .with_pattern(PiiKind::Custom("ssn".into()), r"\b\d{3}-\d{2}-\d{4}\b")
+            .unwrap();
+        let entities = detector.find("My SSN is 123-45-6789");
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].text, "123-45-6789");
+        assert_eq!(entities[0].kind.label(), "SSN");
+    }
+
+    #[test]
+    fn test_parse_names() {
+        let names = parse_names("```json\n[\"Jane Smith\", \"Bob\"]\n```").unwrap();
+        let entities = find_names("Bob met Jane Smith and Bob", &names);
+        assert_eq!(entities.len(), 3);
+        assert!(parse_names("[]").unwrap().is_empty());
+    }
+}
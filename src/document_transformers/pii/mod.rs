@@ -0,0 +1,41 @@
+mod detector;
+pub use detector::*;
+
+mod redactor;
+pub use redactor::*;
+
+use serde::{Deserialize, Serialize};
+
+/// A kind of personally identifiable information.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Email,
+    Phone,
+    CreditCard,
+    Name,
+    /// Detected by a custom pattern, e.g. `Custom("ssn".into())`.
+    Custom(String),
+}
+
+impl PiiKind {
+    /// The label of the kind in the placeholders, e.g. `EMAIL`.
+    pub fn label(&self) -> String {
+        match self {
+            PiiKind::Email => "EMAIL".into(),
+            PiiKind::Phone => "PHONE".into(),
+            PiiKind::CreditCard => "CREDIT_CARD".into(),
+            PiiKind::Name => "NAME".into(),
+            PiiKind::Custom(name) => name.to_uppercase(),
+        }
+    }
+}
+
+/// Personally identifiable information found in a text, at the byte range `start..end`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PiiEntity {
+    pub kind: PiiKind,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chain::ChainError,
+    document_transformers::{DocumentTransformer, DocumentTransformerError},
+    guardrails::Validator,
+    schemas::Document,
+};
+
+use super::{PiiDetector, PiiEntity, RegexPiiDetector};
+
+/// How a `PiiRedactor` replaces the information it finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionMode {
+    /// Replaces it by its kind, e.g. `[EMAIL]`.
+    Redact,
+    /// Replaces it by a placeholder numbered per value, e.g. `<EMAIL_1>`, so the same value
+    /// has the same placeholder and the text can be restored with its `PiiMapping`.
+    Pseudonymize,
+}
+
+/// The original values of the placeholders of a pseudonymized text.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PiiMapping {
+    pub placeholders: HashMap<String, String>,
+}
+
+impl PiiMapping {
+    /// Replaces the placeholders in `text` by their original values, e.g. in the answer of
+    /// an LLM called with a pseudonymized text.
+    pub fn restore(&self, text: &str) -> String {
+        self.placeholders
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, original)| {
+                text.replace(placeholder, original)
+            })
+    }
+}
+
+/// A text redacted by a `PiiRedactor`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactedText {
+    pub text: String,
+    pub entities: Vec<PiiEntity>,
+    /// Empty unless the mode is `RedactionMode::Pseudonymize`.
+    pub mapping: PiiMapping,
+}
+
+/// Detects personally identifiable information and redacts or pseudonymizes it.
+///
+/// As a `DocumentTransformer` it redacts the documents of an ingestion pipeline, and as a
+/// `Validator` it guards the outputs of a chain, fixing them without an LLM. Detects with a
+/// `RegexPiiDetector` by default, add a model with `with_detector`.
+///
+/// # Example
+/// ```rust,ignore
+/// let redactor = PiiRedactor::new()
+///     .with_detector(LLMNameDetector::new(OpenAI::default()))
+///     .with_mode(RedactionMode::Pseudonymize);
+///
+/// let redacted = redactor.redact("Write to jane@example.com").await?;
+/// let answer = chain.invoke(prompt_args! {"input" => redacted.text}).await?;
+/// println!("{}", redacted.mapping.restore(&answer));
+/// ```
+pub struct PiiRedactor {
+    detectors: Vec<Box<dyn PiiDetector>>,
+    mode: RedactionMode,
+}
+
+/// The placeholders given so far, shared by the texts redacted together.
+#[derive(Default)]
+struct Placeholders {
+    by_value: HashMap<(String, String), String>,
+    counts: HashMap<String, usize>,
+    mapping: PiiMapping,
+}
+
+impl Placeholders {
+    fn get(&mut self, entity: &PiiEntity) -> String {
+        let label = entity.kind.label();
+        let key = (label.clone(), entity.text.clone());
+        if let Some(placeholder) = self.by_value.get(&key) {
+            return placeholder.clone();
+        }
+        let count = self.counts.entry(label.clone()).or_default();
+        *count += 1;
+        let placeholder = format!("<{}_{}>", label, count);
+        self.mapping
+            .placeholders
+            .insert(placeholder.clone(), entity.text.clone());
+        self.by_value.insert(key, placeholder.clone());
+        placeholder
+    }
+}
+
+impl PiiRedactor {
+    pub fn new() -> Self {
+        Self {
+            detectors: vec![Box::new(RegexPiiDetector::new())],
+            mode: RedactionMode::Redact,
+        }
+    }
+
+    pub fn with_detector<D: PiiDetector + 'static>(mut self, detector: D) -> Self {
+        self.detectors.push(Box::new(detector));
+        self
+    }
+
+    /// Replaces the default detectors.
+    pub fn with_detectors(mut self, detectors: Vec<Box<dyn PiiDetector>>) -> Self {
+        self.detectors = detectors;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: RedactionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// The information found by every detector, in the order of the text. Of the
+    /// overlapping entities only the first and longest is kept.
+    pub async fn detect(&self, text: &str) -> Result<Vec<PiiEntity>, ChainError> {
+        let mut entities = Vec::new();
+        for detector in &self.detectors {
+            entities.extend(detector.detect(text).await?);
+        }
+        entities.sort_by_key(|entity| (entity.start, std::cmp::Reverse(entity.end)));
+
+        let mut end = 0;
+        entities.retain(|entity| {
+            let keep = entity.start >= end;
+            if keep {
+                end = entity.end;
+            }
+            keep
+        });
+        Ok(entities)
+    }
+
+    pub async fn redact(&self, text: &str) -> Result<RedactedText, ChainError> {
+        let mut placeholders = Placeholders::default();
+        let (text, entities) = self.redact_text(text, self.mode, &mut placeholders).await?;
+        Ok(RedactedText {
+            text,
+            entities,
+            mapping: placeholders.mapping,
+        })
+    }
+
+    /// Pseudonymizes the documents, whatever the mode, with the same placeholder for the
+    /// same value in all of them. The mapping restoring them is returned apart, so the
+    /// original values aren't stored with the documents.
+    pub async fn redact_with_mapping(
+        &self,
+        documents: &[Document],
+    ) -> Result<(Vec<Document>, PiiMapping), ChainError> {
+        let mut placeholders = Placeholders::default();
+        let mut redacted = Vec::with_capacity(documents.len());
+        for document in documents {
+            let (text, _) = self
+                .redact_text(
+                    &document.page_content,
+                    RedactionMode::Pseudonymize,
+                    &mut placeholders,
+                )
+                .await?;
+            let mut document = document.clone();
+            document.page_content = text;
+            redacted.push(document);
+        }
+        Ok((redacted, placeholders.mapping))
+    }
+
+    async fn redact_text(
+        &self,
+        text: &str,
+        mode: RedactionMode,
+        placeholders: &mut Placeholders,
+    ) -> Result<(String, Vec<PiiEntity>), ChainError> {
+        let entities = self.detect(text).await?;
+
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        for entity in &entities {
+            redacted.push_str(&text[last..entity.start]);
+            let placeholder = match mode {
+                RedactionMode::Redact => format!("[{}]", entity.kind.label()),
+                RedactionMode::Pseudonymize => placeholders.get(entity),
+            };
+            redacted.push_str(&placeholder);
+            last = entity.end;
+        }
+        redacted.push_str(&text[last..]);
+
+        Ok((redacted, entities))
+    }
+}
+
+impl Default for PiiRedactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl DocumentTransformer for PiiRedactor {
+    async fn transform_documents(
+        &self,
+        documents: &[Document],
+    ) -> Result<Vec<Document>, DocumentTransformerError> {
+        let mut transformed = Vec::with_capacity(documents.len());
+        for document in documents {
+            let redacted = self.redact(&document.page_content).await?;
+            let mut document = document.clone();
+            document.page_content = redacted.text;
+            transformed.push(document);
+        }
+        Ok(transformed)
+    }
+}
+
+#[async_trait]
+impl Validator for PiiRedactor {
+    fn name(&self) -> String {
+        "pii".to_string()
+    }
+
+    async fn validate(&self, text: &str) -> Result<Option<String>, ChainError> {
+        let mut kinds: Vec<String> = Vec::new();
+        for entity in self.detect(text).await? {
+            let label = entity.kind.label();
+            if !kinds.contains(&label) {
+                kinds.push(label);
+            }
+        }
+        Ok((!kinds.is_empty())
+            .then(|| format!("Contains personal information: {}", kinds.join(", "))))
+    }
+
+    async fn fix(&self, text: &str) -> Result<Option<String>, ChainError> {
+        Ok(Some(self.redact(text).await?.text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chain::Chain,
+        guardrails::{Guard, GuardAction},
+        language_models::GenerateResult,
+        prompt::PromptArgs,
+        prompt_args,
+    };
+
+    #[tokio::test]
+    async fn test_redact() {
+        let text = "Mail jane@example.com, then mail jane@example.com and bob@example.com";
+
+        let redacted = PiiRedactor::new().redact(text).await.unwrap();
+        assert_eq!(redacted.text, "Mail [EMAIL], then mail [EMAIL] and [EMAIL]");
+        assert!(redacted.mapping.placeholders.is_empty());
+
+        let redacted = PiiRedactor::new()
+            .with_mode(RedactionMode::Pseudonymize)
+            .redact(text)
+            .await
+            .unwrap();
+        assert_eq!(
+            redacted.text,
+            "Mail <EMAIL_1>, then mail <EMAIL_1> and <EMAIL_2>"
+        );
+        assert_eq!(redacted.mapping.restore(&redacted.text), text);
+    }
+
+    #[tokio::test]
+    async fn test_transform_documents() {
+        let documents = vec![Document::new("Call Mr. Bob Stone at 555-123-4567")];
+        let transformed = PiiRedactor::new()
+            .transform_documents(&documents)
+            .await
+            .unwrap();
+        assert_eq!(transformed[0].page_content, "Call Mr. [NAME] at [PHONE]");
+        assert!(transformed[0].metadata.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_redact_with_mapping() {
+        let documents = vec![
+            Document::new("Call Mr. Bob Stone at 555-123-4567"),
+            Document::new("Mr. Bob Stone wrote from bob@example.com"),
+        ];
+        let (redacted, mapping) = PiiRedactor::new()
+            .redact_with_mapping(&documents)
+            .await
+            .unwrap();
+        assert_eq!(redacted[0].page_content, "Call Mr. <NAME_1> at <PHONE_1>");
+        assert_eq!(
+            redacted[1].page_content,
+            "Mr. <NAME_1> wrote from <EMAIL_1>"
+        );
+        assert!(redacted.iter().all(|document| document.metadata.is_empty()));
+        assert_eq!(mapping.placeholders["<PHONE_1>"], "555-123-4567");
+        assert_eq!(
+            mapping.restore(&redacted[1].page_content),
+            documents[1].page_content
+        );
+    }
+
+    struct EchoChain;
+
+    #[async_trait]
+    impl Chain for EchoChain {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            Ok(GenerateResult {
+                generation: input_variables["input"].as_str().unwrap().to_string(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_guard() {
+        let guard =
+            Guard::new(EchoChain).with_output_validator(PiiRedactor::new(), GuardAction::Fix);
+        let (result, report) = guard
+            .call_with_report(prompt_args! {"input" => "My email is jane@example.com"})
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "My email is [EMAIL]");
+        assert_eq!(
+            report.violations[0].message,
+            "Contains personal information: EMAIL"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use async_trait::async_trait;
+
+use crate::embedding::{embedder_trait::Embedder, EmbedderError};
+
+/// An embedder without model, for deterministic tests of vector stores and retrievers.
+///
+/// The vectors are derived from a hash of the text, so the same text always has the same
+/// vector, and the vectors of different texts are almost orthogonal. Use
+/// [`FakeEmbedder::with_embedding`] or [`FakeEmbedder::with_topics`] to control the
+/// similarities between texts.
+#[derive(Debug, Clone)]
+pub struct FakeEmbedder {
+    dimensions: usize,
+    embeddings: HashMap<String, Vec<f64>>,
+    topics: Vec<Vec<String>>,
+}
+
+impl Default for FakeEmbedder {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+impl FakeEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions,
+            embeddings: HashMap::new(),
+            topics: Vec::new(),
+        }
+    }
+
+    /// Embeds `text` as `embedding` instead of its hash.
+    pub fn with_embedding<S: Into<String>>(mut self, text: S, embedding: Vec<f64>) -> Self {
+        self.embeddings.insert(text.into(), embedding);
+        self
+    }
+
+    /// Embeds the texts by topic instead of their hash, with a dimension per topic: the
+    /// number of occurrences of its words in the lowercased text. The texts about the same
+    /// topics are similar.
+    ///
+    /// ```rust,ignore
+    /// let embedder = FakeEmbedder::default()
+    ///     .with_topics(vec![vec!["refund", "money back"], vec!["shipping", "delivery"]]);
+    /// ```
+    pub fn with_topics<S: Into<String>>(mut self, topics: Vec<Vec<S>>) -> Self {
+        self.topics = topics
+            .into_iter()
+            .map(|words| words.into_iter().map(Into::into).collect())
+            .collect();
+        self
+    }
+
+    /// The normalized vector of the text.
+    pub fn embed(&self, text: &str) -> Vec<f64> {
+        if let Some(embedding) = self.embeddings.get(text) {
+            return embedding.clone();
+        }
+        let vector: Vec<f64> = if self.topics.is_empty() {
+            (0..self.dimensions)
+                .map(|i| {
+                    let mut hasher = DefaultHasher::new();
+                    (text, i).hash(&mut hasher);
+                    (hasher.finish() as f64 / u64::MAX as f64) * 2.0 - 1.0
+                })
+                .collect()
+        } else {
+            let text = text.to_lowercase();
+            self.topics
+                .iter()
+                .map(|words| {
+                    words
+                        .iter()
+                        .map(|word| text.matches(word.as_str()).count() as f64)
+                        .sum()
+                })
+                .collect()
+        };
+        let norm = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            return vector;
+        }
+        vector.into_iter().map(|x| x / norm).collect()
+    }
+}
+
+#[async_trait]
+impl Embedder for FakeEmbedder {
+    async fn embed_documents(&self, documents: &[String]) -> Result<Vec<Vec<f64>>, EmbedderError> {
+        Ok(documents
+            .iter()
+            .map(|document| self.embed(document))
+            .collect())
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f64>, EmbedderError> {
+        Ok(self.embed(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fake_embedder() {
+        let embedder = FakeEmbedder::new(8).with_embedding("cat", vec![1.0; 8]);
+        let embeddings = embedder
+            .embed_documents(&["hello".to_string(), "world".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(embeddings.len(), 2);
+        assert_eq!(embeddings[0].len(), 8);
+        assert_ne!(embeddings[0], embeddings[1]);
+        let norm: f64 = embeddings[0].iter().map(|x| x * x).sum::<f64>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-9);
+
+        assert_eq!(embedder.embed_query("hello").await.unwrap(), embeddings[0]);
+        assert_eq!(embedder.embed_query("cat").await.unwrap(), vec![1.0; 8]);
+    }
+
+    #[test]
+    fn test_fake_embedder_topics() {
+        let embedder = FakeEmbedder::default().with_topics(vec![
+            vec!["space"],
+            vec!["ocean", "sea"],
+            vec!["forest"],
+        ]);
+        assert_eq!(embedder.embed("Space and the sea"), {
+            let norm = 2.0_f64.sqrt();
+            vec![1.0 / norm, 1.0 / norm, 0.0]
+        });
+        assert_eq!(embedder.embed("a city"), vec![0.0; 3]);
+    }
+}
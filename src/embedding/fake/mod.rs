@@ -0,0 +1,2 @@
+mod fake_embedder;
+pub use fake_embedder::*;
@@ -1,9 +1,13 @@
 pub mod embedder_trait;
 pub use embedder_trait::*;
 mod error;
+mod fake;
+pub use fake::*;
 pub mod ollama;
 pub mod openai;
 pub use error::*;
 
+#[cfg(not(target_arch = "wasm32"))]
 mod fastembed;
+#[cfg(not(target_arch = "wasm32"))]
 pub use fastembed::*;
@@ -1,6 +1,9 @@
 #![allow(dead_code)]
 
-use crate::embedding::{embedder_trait::Embedder, EmbedderError};
+use crate::{
+    embedding::{embedder_trait::Embedder, EmbedderError},
+    runtime::send,
+};
 use async_trait::async_trait;
 use reqwest::{Client, Url};
 use serde::Deserialize;
@@ -47,21 +50,53 @@ impl Default for OllamaEmbedder {
 #[async_trait]
 impl Embedder for OllamaEmbedder {
     async fn embed_documents(&self, documents: &[String]) -> Result<Vec<Vec<f64>>, EmbedderError> {
-        log::debug!("Embedding documents: {:?}", documents);
-        let client = Client::new();
-        let url = Url::parse(&format!("{}{}", self.base_url, "/api/embeddings"))?;
+        send(async move {
+            log::debug!("Embedding documents: {:?}", documents);
+            let client = Client::new();
+            let url = Url::parse(&format!("{}{}", self.base_url, "/api/embeddings"))?;
 
-        let mut embeddings = Vec::with_capacity(documents.len());
+            let mut embeddings = Vec::with_capacity(documents.len());
+
+            for doc in documents {
+                let res = client
+                    .post(url.clone())
+                    .json(&json!({
+                        "prompt": doc,
+                        "model": &self.model,
+                    }))
+                    .send()
+                    .await?;
+                if res.status() != 200 {
+                    log::error!("Error from OLLAMA: {}", &res.status());
+                    return Err(EmbedderError::HttpError {
+                        status_code: res.status(),
+                        error_message: format!("Received non-200 response: {}", res.status()),
+                    });
+                }
+                let data: EmbeddingResponse = res.json().await?;
+                embeddings.push(data.embedding);
+            }
+
+            Ok(embeddings)
+        })
+        .await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f64>, EmbedderError> {
+        send(async move {
+            log::debug!("Embedding query: {:?}", text);
+            let client = Client::new();
+            let url = Url::parse(&format!("{}{}", self.base_url, "/api/embeddings"))?;
 
-        for doc in documents {
             let res = client
-                .post(url.clone())
+                .post(url)
                 .json(&json!({
-                    "prompt": doc,
+                    "prompt": text,
                     "model": &self.model,
                 }))
                 .send()
                 .await?;
+
             if res.status() != 200 {
                 log::error!("Error from OLLAMA: {}", &res.status());
                 return Err(EmbedderError::HttpError {
@@ -70,34 +105,8 @@ impl Embedder for OllamaEmbedder {
                 });
             }
             let data: EmbeddingResponse = res.json().await?;
-            embeddings.push(data.embedding);
-        }
-
-        Ok(embeddings)
-    }
-
-    async fn embed_query(&self, text: &str) -> Result<Vec<f64>, EmbedderError> {
-        log::debug!("Embedding query: {:?}", text);
-        let client = Client::new();
-        let url = Url::parse(&format!("{}{}", self.base_url, "/api/embeddings"))?;
-
-        let res = client
-            .post(url)
-            .json(&json!({
-                "prompt": text,
-                "model": &self.model,
-            }))
-            .send()
-            .await?;
-
-        if res.status() != 200 {
-            log::error!("Error from OLLAMA: {}", &res.status());
-            return Err(EmbedderError::HttpError {
-                status_code: res.status(),
-                error_message: format!("Received non-200 response: {}", res.status()),
-            });
-        }
-        let data: EmbeddingResponse = res.json().await?;
-        Ok(data.embedding)
+            Ok(data.embedding)
+        })
+        .await
     }
 }
@@ -1,6 +1,13 @@
 #![allow(dead_code)]
 
-use crate::embedding::{embedder_trait::Embedder, EmbedderError};
+use std::fmt;
+
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    embedding::{embedder_trait::Embedder, EmbedderError},
+    language_models::TokenUsage,
+    runtime::send,
+};
 pub use async_openai::config::{AzureConfig, Config, OpenAIConfig};
 use async_openai::{
     types::{CreateEmbeddingRequestArgs, EmbeddingInput},
@@ -8,10 +15,37 @@ use async_openai::{
 };
 use async_trait::async_trait;
 
+/// The maximum number of inputs of an embeddings request.
+const MAX_BATCH_SIZE: usize = 2048;
+
+pub enum OpenAIEmbeddingModel {
+    TextEmbedding3Small,
+    TextEmbedding3Large,
+    TextEmbeddingAda002,
+}
+
+impl fmt::Display for OpenAIEmbeddingModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenAIEmbeddingModel::TextEmbedding3Small => write!(f, "text-embedding-3-small"),
+            OpenAIEmbeddingModel::TextEmbedding3Large => write!(f, "text-embedding-3-large"),
+            OpenAIEmbeddingModel::TextEmbeddingAda002 => write!(f, "text-embedding-ada-002"),
+        }
+    }
+}
+
+impl From<OpenAIEmbeddingModel> for String {
+    fn from(model: OpenAIEmbeddingModel) -> Self {
+        model.to_string()
+    }
+}
+
 #[derive(Debug)]
 pub struct OpenAiEmbedder<C: Config> {
     config: C,
     model: String,
+    dimensions: Option<u32>,
+    batch_size: usize,
 }
 
 impl<C: Config + Send + Sync + 'static> Into<Box<dyn Embedder>> for OpenAiEmbedder<C> {
@@ -25,6 +59,8 @@ impl<C: Config> OpenAiEmbedder<C> {
         OpenAiEmbedder {
             config,
             model: String::from("text-embedding-ada-002"),
+            dimensions: None,
+            batch_size: MAX_BATCH_SIZE,
         }
     }
 
@@ -37,6 +73,28 @@ impl<C: Config> OpenAiEmbedder<C> {
         self.config = config;
         self
     }
+
+    /// Shortens the embeddings to `dimensions`, only supported by the `text-embedding-3`
+    /// models.
+    pub fn with_dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// The number of documents embedded by request, at most 2048.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.clamp(1, MAX_BATCH_SIZE);
+        self
+    }
+
+    fn request_args(&self) -> CreateEmbeddingRequestArgs {
+        let mut args = CreateEmbeddingRequestArgs::default();
+        args.model(&self.model);
+        if let Some(dimensions) = self.dimensions {
+            args.dimensions(dimensions);
+        }
+        args
+    }
 }
 
 impl Default for OpenAiEmbedder<OpenAIConfig> {
@@ -50,44 +108,125 @@ impl<C: Config + Send + Sync> Embedder for OpenAiEmbedder<C> {
     async fn embed_documents(&self, documents: &[String]) -> Result<Vec<Vec<f64>>, EmbedderError> {
         let client = Client::with_config(self.config.clone());
 
-        let request = CreateEmbeddingRequestArgs::default()
-            .model(&self.model)
-            .input(EmbeddingInput::StringArray(documents.into()))
-            .build()?;
-
-        let response = client.embeddings().create(request).await?;
-
-        let embeddings = response
-            .data
-            .into_iter()
-            .map(|item| item.embedding)
-            .map(|embedding| {
-                embedding
-                    .into_iter()
-                    .map(|x| x as f64)
-                    .collect::<Vec<f64>>()
-            })
-            .collect();
-
-        Ok(embeddings)
+        let run = CallbackManager::new().start_run(&self.model, RunType::Embedding);
+        run.trace_embedding(async {
+            let mut embeddings = Vec::with_capacity(documents.len());
+            let mut tokens = TokenUsage::default();
+            for batch in documents.chunks(self.batch_size) {
+                let request = self
+                    .request_args()
+                    .input(EmbeddingInput::StringArray(batch.into()))
+                    .build()?;
+
+                let mut response = send(client.embeddings().create(request)).await?;
+                response.data.sort_by_key(|item| item.index);
+                tokens.add(&TokenUsage::new(response.usage.prompt_tokens, 0));
+
+                embeddings.extend(response.data.into_iter().map(|item| {
+                    item.embedding
+                        .into_iter()
+                        .map(|x| x as f64)
+                        .collect::<Vec<f64>>()
+                }));
+            }
+            Ok((embeddings, Some(tokens)))
+        })
+        .await
     }
 
     async fn embed_query(&self, text: &str) -> Result<Vec<f64>, EmbedderError> {
         let client = Client::with_config(self.config.clone());
 
-        let request = CreateEmbeddingRequestArgs::default()
-            .model(&self.model)
-            .input(text)
-            .build()?;
+        let run = CallbackManager::new().start_run(&self.model, RunType::Embedding);
+        run.trace_embedding(async {
+            let request = self.request_args().input(text).build()?;
+
+            let mut response = send(client.embeddings().create(request)).await?;
+
+            let item = response.data.swap_remove(0);
+
+            let embedding = item
+                .embedding
+                .into_iter()
+                .map(|x| x as f64)
+                .collect::<Vec<f64>>();
+            Ok((
+                embedding,
+                Some(TokenUsage::new(response.usage.prompt_tokens, 0)),
+            ))
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+    use serde_json::json;
 
-        let mut response = client.embeddings().create(request).await?;
+    use super::*;
+    use crate::callbacks::UsageTracker;
 
-        let item = response.data.swap_remove(0);
+    fn embeddings_response(embeddings: &[(u32, f32)]) -> String {
+        let data: Vec<_> = embeddings
+            .iter()
+            .map(|(index, value)| {
+                json!({"object": "embedding", "index": index, "embedding": [value, value]})
+            })
+            .collect();
+        json!({
+            "object": "list",
+            "data": data,
+            "model": "text-embedding-3-small",
+            "usage": {"prompt_tokens": 3, "total_tokens": 3},
+        })
+        .to_string()
+    }
 
-        Ok(item
-            .embedding
-            .into_iter()
-            .map(|x| x as f64)
-            .collect::<Vec<f64>>())
+    #[tokio::test]
+    async fn test_embed_documents_in_batches() {
+        let mut server = mockito::Server::new_async().await;
+        let first_batch = server
+            .mock("POST", "/embeddings")
+            .match_body(Matcher::Json(json!({
+                "model": "text-embedding-3-small",
+                "input": ["a", "b"],
+                "dimensions": 2,
+            })))
+            .with_body(embeddings_response(&[(1, 2.0), (0, 1.0)]))
+            .create_async()
+            .await;
+        let second_batch = server
+            .mock("POST", "/embeddings")
+            .match_body(Matcher::PartialJson(json!({"input": ["c"]})))
+            .with_body(embeddings_response(&[(0, 3.0)]))
+            .create_async()
+            .await;
+
+        let embedder = OpenAiEmbedder::new(
+            OpenAIConfig::new()
+                .with_api_base(server.url())
+                .with_api_key("key"),
+        )
+        .with_model(OpenAIEmbeddingModel::TextEmbedding3Small)
+        .with_dimensions(2)
+        .with_batch_size(2);
+        let usage = UsageTracker::new();
+        let embeddings = CallbackManager::new()
+            .with_handler(usage.clone())
+            .scope(embedder.embed_documents(&["a".to_string(), "b".to_string(), "c".to_string()]))
+            .await
+            .unwrap();
+
+        first_batch.assert_async().await;
+        second_batch.assert_async().await;
+        assert_eq!(
+            embeddings,
+            vec![vec![1.0, 1.0], vec![2.0, 2.0], vec![3.0, 3.0]]
+        );
+        // The usage of both batches, in a single run.
+        let summary = usage.summary();
+        assert_eq!(summary.successful_requests, 1);
+        assert_eq!(summary.models["text-embedding-3-small"].prompt_tokens, 6);
     }
 }
@@ -0,0 +1,499 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    time::Duration,
+};
+
+use futures::{future::join_all, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    callbacks::{CallbackManager, UsageSummary, UsageTracker},
+    chain::Chain,
+    prompt::PromptArgs,
+    runtime::Instant,
+};
+
+use super::{DatasetError, EvaluationResult, StringEvaluator};
+
+/// An example of a dataset: the inputs of the chain and the reference output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Example {
+    pub inputs: PromptArgs,
+    pub reference: Option<String>,
+}
+
+impl Example {
+    pub fn new(inputs: PromptArgs) -> Self {
+        Self {
+            inputs,
+            reference: None,
+        }
+    }
+
+    pub fn with_reference<S: Into<String>>(mut self, reference: S) -> Self {
+        self.reference = Some(reference.into());
+        self
+    }
+
+    /// The input given to the evaluators: the only input, or the inputs as json.
+    fn input_text(&self) -> String {
+        match self.inputs.values().collect::<Vec<_>>().as_slice() {
+            [Value::String(input)] => input.clone(),
+            [input] => input.to_string(),
+            _ => Value::from(serde_json::Map::from_iter(self.inputs.clone())).to_string(),
+        }
+    }
+
+    fn from_row(mut inputs: PromptArgs, reference_key: &str) -> Self {
+        let reference = inputs
+            .remove(reference_key)
+            .map(|reference| match reference {
+                Value::String(reference) => reference,
+                reference => reference.to_string(),
+            });
+        Self { inputs, reference }
+    }
+}
+
+/// Examples to run a chain on, see `DatasetRunner`.
+///
+/// Every row of a JSONL or CSV file is an example: the column `reference_key` is the
+/// reference, the other ones are the inputs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Dataset {
+    pub examples: Vec<Example>,
+}
+
+impl Dataset {
+    pub fn new(examples: Vec<Example>) -> Self {
+        Self { examples }
+    }
+
+    pub fn from_jsonl<P: AsRef<Path>>(path: P, reference_key: &str) -> Result<Self, DatasetError> {
+        Self::from_jsonl_reader(BufReader::new(File::open(path)?), reference_key)
+    }
+
+    pub fn from_jsonl_reader<R: BufRead>(
+        reader: R,
+        reference_key: &str,
+    ) -> Result<Self, DatasetError> {
+        let mut examples = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Value::Object(row) = serde_json::from_str(&line)? else {
+                return Err(DatasetError::InvalidExample(
+                    index,
+                    "expected a json object".to_string(),
+                ));
+            };
+            examples.push(Example::from_row(row.into_iter().collect(), reference_key));
+        }
+        Ok(Self::new(examples))
+    }
+
+    pub fn from_csv<P: AsRef<Path>>(path: P, reference_key: &str) -> Result<Self, DatasetError> {
+        Self::from_csv_reader(BufReader::new(File::open(path)?), reference_key)
+    }
+
+    pub fn from_csv_reader<R: Read>(reader: R, reference_key: &str) -> Result<Self, DatasetError> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let headers = reader.headers()?.clone();
+        let mut examples = Vec::new();
+        for record in reader.records() {
+            let row = headers
+                .iter()
+                .zip(record?.iter())
+                .map(|(header, value)| (header.to_string(), Value::from(value)))
+                .collect();
+            examples.push(Example::from_row(row, reference_key));
+        }
+        Ok(Self::new(examples))
+    }
+
+    pub fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+}
+
+/// The outcome of running the chain on an example.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExampleResult {
+    /// Position of the example in the dataset.
+    pub index: usize,
+    pub prediction: Option<String>,
+    /// The error of the chain or of an evaluator.
+    pub error: Option<String>,
+    /// The results of the evaluators, by name.
+    pub evaluations: HashMap<String, EvaluationResult>,
+    /// Duration of the call to the chain, without the evaluation.
+    pub latency: Duration,
+    /// Tokens and cost of the call to the chain.
+    pub usage: UsageSummary,
+    pub passed: bool,
+}
+
+/// The aggregated results of a `DatasetRunner`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetReport {
+    pub results: Vec<ExampleResult>,
+    /// Fraction of the examples that passed.
+    pub pass_rate: f64,
+    pub error_count: usize,
+    /// Mean score of each evaluator, over the examples it evaluated.
+    pub mean_scores: HashMap<String, f64>,
+    pub mean_latency: Duration,
+    pub max_latency: Duration,
+    /// Cost in USD of the calls to the models with a known price.
+    pub total_cost: f64,
+    pub mean_cost: f64,
+    pub total_tokens: u32,
+}
+
+impl DatasetReport {
+    pub fn new(results: Vec<ExampleResult>) -> Self {
+        let count = results.len().max(1);
+        let mut scores: HashMap<String, Vec<f64>> = HashMap::new();
+        for result in &results {
+            for (name, evaluation) in &result.evaluations {
+                scores
+                    .entry(name.clone())
+                    .or_default()
+                    .push(evaluation.score);
+            }
+        }
+        let total_latency: Duration = results.iter().map(|result| result.latency).sum();
+        let total_cost: f64 = results.iter().map(|result| result.usage.total_cost).sum();
+
+        Self {
+            pass_rate: results.iter().filter(|result| result.passed).count() as f64 / count as f64,
+            error_count: results
+                .iter()
+                .filter(|result| result.error.is_some())
+                .count(),
+            mean_scores: scores
+                .into_iter()
+                .map(|(name, scores)| {
+                    let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+                    (name, mean)
+                })
+                .collect(),
+            mean_latency: total_latency / count as u32,
+            max_latency: results
+                .iter()
+                .map(|result| result.latency)
+                .max()
+                .unwrap_or_default(),
+            total_cost,
+            mean_cost: total_cost / count as f64,
+            total_tokens: results.iter().map(|result| result.usage.total_tokens).sum(),
+            results,
+        }
+    }
+
+    /// The results of the examples that didn't pass.
+    pub fn failures(&self) -> impl Iterator<Item = &ExampleResult> {
+        self.results.iter().filter(|result| !result.passed)
+    }
+
+    /// Panics, listing the failed examples, when less than `min_pass_rate` of the
+    /// examples passed, e.g. to catch the regressions of a prompt in a test.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// #[tokio::test]
+    /// async fn test_prompt_regressions() {
+    ///     let dataset = Dataset::from_jsonl("tests/qa.jsonl", "answer").unwrap();
+    ///     DatasetRunner::new()
+    ///         .with_evaluator("exact_match", ExactMatchEvaluator::new().with_ignore_case(true))
+    ///         .run(&chain, &dataset)
+    ///         .await
+    ///         .assert_pass_rate(0.9);
+    /// }
+    /// ```
+    pub fn assert_pass_rate(&self, min_pass_rate: f64) {
+        if self.pass_rate >= min_pass_rate {
+            return;
+        }
+        let failures: Vec<String> = self
+            .failures()
+            .map(|result| match (&result.error, &result.prediction) {
+                (Some(error), _) => format!("example {}: {}", result.index, error),
+                (None, prediction) => {
+                    let mut scores: Vec<String> = result
+                        .evaluations
+                        .iter()
+                        .map(|(name, evaluation)| format!("{} {}", name, evaluation.score))
+                        .collect();
+                    scores.sort();
+                    format!(
+                        "example {}: prediction {:?}, {}",
+                        result.index,
+                        prediction.as_deref().unwrap_or_default(),
+                        scores.join(", ")
+                    )
+                }
+            })
+            .collect();
+        panic!(
+            "{:.0}% of the examples passed, expected at least {:.0}%:\n{}",
+            self.pass_rate * 100.0,
+            min_pass_rate * 100.0,
+            failures.join("\n")
+        );
+    }
+}
+
+type PassCriterion = Box<dyn Fn(&ExampleResult) -> bool + Send + Sync>;
+
+/// Runs a chain or an agent on every example of a dataset, evaluates the predictions
+/// and aggregates the results, e.g. to compare prompts or models or to catch regressions.
+///
+/// By default an example passes when neither the chain nor the evaluators failed and
+/// every score is at least 0.5. Set `with_pass_criterion` for the distance evaluators,
+/// where lower is better.
+///
+/// # Example
+/// ```rust,ignore
+/// let dataset = Dataset::from_jsonl("qa.jsonl", "answer")?;
+/// let report = DatasetRunner::new()
+///     .with_evaluator("correctness", QAEvalChain::new(OpenAI::default()))
+///     .with_max_concurrency(8)
+///     .run(&chain, &dataset)
+///     .await;
+/// println!(
+///     "{:.0}% passed, {:?} per example, ${:.4}",
+///     report.pass_rate * 100.0,
+///     report.mean_latency,
+///     report.total_cost
+/// );
+/// ```
+pub struct DatasetRunner {
+    evaluators: Vec<(String, Box<dyn StringEvaluator>)>,
+    max_concurrency: usize,
+    usage: UsageTracker,
+    pass_criterion: PassCriterion,
+}
+
+impl DatasetRunner {
+    pub fn new() -> Self {
+        Self {
+            evaluators: Vec::new(),
+            max_concurrency: 4,
+            usage: UsageTracker::new(),
+            pass_criterion: Box::new(|result| {
+                result.error.is_none()
+                    && result
+                        .evaluations
+                        .values()
+                        .all(|evaluation| evaluation.score >= 0.5)
+            }),
+        }
+    }
+
+    pub fn with_evaluator<S: Into<String>, E: StringEvaluator + 'static>(
+        mut self,
+        name: S,
+        evaluator: E,
+    ) -> Self {
+        self.evaluators.push((name.into(), Box::new(evaluator)));
+        self
+    }
+
+    /// Maximum number of examples running at the same time.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    /// Tracker with the prices used for the cost of the examples.
+    pub fn with_usage_tracker(mut self, usage: UsageTracker) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub fn with_pass_criterion<F>(mut self, pass_criterion: F) -> Self
+    where
+        F: Fn(&ExampleResult) -> bool + Send + Sync + 'static,
+    {
+        self.pass_criterion = Box::new(pass_criterion);
+        self
+    }
+
+    pub async fn run<C: Chain + ?Sized>(&self, chain: &C, dataset: &Dataset) -> DatasetReport {
+        let results = futures::stream::iter(dataset.examples.iter().enumerate())
+            .map(|(index, example)| self.run_example(chain, index, example))
+            .buffered(self.max_concurrency.max(1))
+            .collect()
+            .await;
+        DatasetReport::new(results)
+    }
+
+    async fn run_example<C: Chain + ?Sized>(
+        &self,
+        chain: &C,
+        index: usize,
+        example: &Example,
+    ) -> ExampleResult {
+        let usage = self.usage.fork();
+        let start = Instant::now();
+        let output = chain
+            .call_with_callbacks(
+                example.inputs.clone(),
+                CallbackManager::new().with_handler(usage.clone()),
+            )
+            .await;
+        let mut result = ExampleResult {
+            index,
+            prediction: None,
+            error: None,
+            evaluations: HashMap::new(),
+            latency: start.elapsed(),
+            usage: usage.summary(),
+            passed: false,
+        };
+
+        match output {
+            Ok(output) => {
+                let input = example.input_text();
+                let evaluations = join_all(self.evaluators.iter().map(|(name, evaluator)| async {
+                    let evaluation = evaluator
+                        .evaluate_strings(
+                            &output.generation,
+                            example.reference.as_deref(),
+                            Some(&input),
+                        )
+                        .await;
+                    (name.clone(), evaluation)
+                }))
+                .await;
+                for (name, evaluation) in evaluations {
+                    match evaluation {
+                        Ok(evaluation) => {
+                            result.evaluations.insert(name, evaluation);
+                        }
+                        Err(e) => result.error = Some(format!("Evaluator {}: {}", name, e)),
+                    }
+                }
+                result.prediction = Some(output.generation);
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        result.passed = (self.pass_criterion)(&result);
+        result
+    }
+}
+
+impl Default for DatasetRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{
+        callbacks::{ModelPrice, RunType},
+        chain::ChainError,
+        evaluation::ExactMatchEvaluator,
+        language_models::{GenerateResult, TokenUsage},
+    };
+
+    struct UppercaseChain;
+
+    #[async_trait]
+    impl Chain for UppercaseChain {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            let input = input_variables["input"].as_str().unwrap().to_string();
+            if input.is_empty() {
+                return Err(ChainError::OtherError("Empty input".to_string()));
+            }
+            CallbackManager::new()
+                .start_run("my-model", RunType::Llm)
+                .trace_llm(&[], async {
+                    Ok(GenerateResult {
+                        generation: input.to_uppercase(),
+                        tokens: Some(TokenUsage::new(1_000, 500)),
+                        ..Default::default()
+                    })
+                })
+                .await
+                .map_err(ChainError::from)
+        }
+    }
+
+    #[test]
+    fn test_load_dataset() {
+        let jsonl =
+            "{\"input\": \"hi\", \"answer\": \"HI\"}\n\n{\"input\": \"bye\", \"answer\": 1}\n";
+        let dataset = Dataset::from_jsonl_reader(jsonl.as_bytes(), "answer").unwrap();
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.examples[0].inputs["input"], "hi");
+        assert_eq!(dataset.examples[0].reference.as_deref(), Some("HI"));
+        assert_eq!(dataset.examples[1].reference.as_deref(), Some("1"));
+        assert!(!dataset.examples[1].inputs.contains_key("answer"));
+
+        let csv = "input,answer\nhi,HI\nbye,BYE\n";
+        let dataset = Dataset::from_csv_reader(csv.as_bytes(), "answer").unwrap();
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.examples[1].inputs["input"], "bye");
+        assert_eq!(dataset.examples[1].reference.as_deref(), Some("BYE"));
+
+        assert!(Dataset::from_jsonl_reader("[1]".as_bytes(), "answer").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dataset_runner() {
+        let dataset = Dataset::from_csv_reader(
+            "input,reference\nhi,HI\nbye,bye\n,\n".as_bytes(),
+            "reference",
+        )
+        .unwrap();
+        let report = DatasetRunner::new()
+            .with_evaluator("exact_match", ExactMatchEvaluator::new())
+            .with_usage_tracker(
+                UsageTracker::new().with_price("my-model", ModelPrice::new(1.0, 2.0)),
+            )
+            .run(&UppercaseChain, &dataset)
+            .await;
+
+        assert_eq!(report.results.len(), 3);
+        assert!(report.results[0].passed);
+        assert!(!report.results[1].passed);
+        assert_eq!(report.results[1].prediction.as_deref(), Some("BYE"));
+        assert!(report.results[2].error.is_some());
+        assert!((report.pass_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.error_count, 1);
+        assert_eq!(report.mean_scores["exact_match"], 0.5);
+        assert_eq!(report.total_tokens, 3_000);
+        assert!((report.total_cost - 0.004).abs() < 1e-9);
+        assert_eq!(report.results[0].usage.total_tokens, 1_500);
+
+        assert_eq!(
+            report
+                .failures()
+                .map(|result| result.index)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        report.assert_pass_rate(0.3);
+        let failed = std::panic::catch_unwind(|| report.assert_pass_rate(0.5)).unwrap_err();
+        let message = failed.downcast_ref::<String>().unwrap();
+        assert!(message.contains("33% of the examples passed, expected at least 50%"));
+        assert!(message.contains("example 1: prediction \"BYE\""));
+        assert!(message.contains("example 2: Error: Empty input"));
+    }
+}
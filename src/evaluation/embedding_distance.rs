@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{embedding::Embedder, semantic_router::utils::cosine_similarity};
+
+use super::{EvaluationResult, EvaluatorError, StringEvaluator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmbeddingDistance {
+    Cosine,
+    Euclidean,
+    Manhattan,
+}
+
+impl EmbeddingDistance {
+    pub fn distance(&self, a: &[f64], b: &[f64]) -> f64 {
+        match self {
+            EmbeddingDistance::Cosine => 1.0 - cosine_similarity(a, b),
+            EmbeddingDistance::Euclidean => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| (a - b).powi(2))
+                .sum::<f64>()
+                .sqrt(),
+            EmbeddingDistance::Manhattan => a.iter().zip(b).map(|(a, b)| (a - b).abs()).sum(),
+        }
+    }
+}
+
+/// Scores a prediction with the distance between its embedding and the embedding of the
+/// reference, the lower the closer. A cheap check that doesn't need an LLM judge.
+///
+/// # Example
+/// ```rust,ignore
+/// let evaluator = EmbeddingDistanceEvaluator::new(OpenAiEmbedder::default());
+/// let distance = evaluator.evaluate(&answer, "Lima is in Peru").await?;
+/// assert!(distance < 0.2);
+/// ```
+pub struct EmbeddingDistanceEvaluator {
+    embedder: Arc<dyn Embedder>,
+    distance: EmbeddingDistance,
+}
+
+impl EmbeddingDistanceEvaluator {
+    pub fn new<E: Embedder + 'static>(embedder: E) -> Self {
+        Self {
+            embedder: Arc::new(embedder),
+            distance: EmbeddingDistance::Cosine,
+        }
+    }
+
+    pub fn with_distance(mut self, distance: EmbeddingDistance) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    pub async fn evaluate(&self, prediction: &str, reference: &str) -> Result<f64, EvaluatorError> {
+        let embeddings = self
+            .embedder
+            .embed_documents(&[prediction.to_string(), reference.to_string()])
+            .await?;
+        match embeddings.as_slice() {
+            [prediction, reference] => Ok(self.distance.distance(prediction, reference)),
+            _ => Err(EvaluatorError::ParsingError(format!(
+                "expected 2 embeddings, got {}",
+                embeddings.len()
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl StringEvaluator for EmbeddingDistanceEvaluator {
+    async fn evaluate_strings(
+        &self,
+        prediction: &str,
+        reference: Option<&str>,
+        _input: Option<&str>,
+    ) -> Result<EvaluationResult, EvaluatorError> {
+        let reference = reference.ok_or(EvaluatorError::MissingValue("reference".into()))?;
+        Ok(EvaluationResult {
+            score: self.evaluate(prediction, reference).await?,
+            value: None,
+            reasoning: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::EmbedderError;
+
+    /// Embeds the texts as their number of words and characters.
+    struct CountEmbedder;
+
+    #[async_trait]
+    impl Embedder for CountEmbedder {
+        async fn embed_documents(
+            &self,
+            documents: &[String],
+        ) -> Result<Vec<Vec<f64>>, EmbedderError> {
+            Ok(documents
+                .iter()
+                .map(|document| {
+                    vec![
+                        document.split_whitespace().count() as f64,
+                        document.len() as f64,
+                    ]
+                })
+                .collect())
+        }
+
+        async fn embed_query(&self, text: &str) -> Result<Vec<f64>, EmbedderError> {
+            Ok(self.embed_documents(&[text.to_string()]).await?.remove(0))
+        }
+    }
+
+    #[test]
+    fn test_distances() {
+        assert!(
+            EmbeddingDistance::Cosine
+                .distance(&[1.0, 0.0], &[2.0, 0.0])
+                .abs()
+                < 1e-9
+        );
+        assert_eq!(
+            EmbeddingDistance::Euclidean.distance(&[0.0, 0.0], &[3.0, 4.0]),
+            5.0
+        );
+        assert_eq!(
+            EmbeddingDistance::Manhattan.distance(&[0.0, 0.0], &[3.0, 4.0]),
+            7.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_embedding_distance_evaluator() {
+        let evaluator = EmbeddingDistanceEvaluator::new(CountEmbedder)
+            .with_distance(EmbeddingDistance::Manhattan);
+        let result = evaluator
+            .evaluate_strings("a b", Some("a b c"), None)
+            .await
+            .unwrap();
+        assert_eq!(result.score, 3.0);
+        assert!(evaluator.evaluate_strings("a", None, None).await.is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use thiserror::Error;
+
+use crate::{chain::ChainError, embedding::EmbedderError};
+
+#[derive(Error, Debug)]
+pub enum EvaluatorError {
+    #[error("Chain error: {0}")]
+    ChainError(#[from] ChainError),
+
+    #[error("Embedder error: {0}")]
+    EmbedderError(#[from] EmbedderError),
+
+    #[error("Unexpected output of the judge: {0}")]
+    ParsingError(String),
+
+    #[error("Missing {0}, required by the evaluator")]
+    MissingValue(String),
+}
+
+/// Error loading a `Dataset`.
+#[derive(Error, Debug)]
+pub enum DatasetError {
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Serde json error: {0}")]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
+
+    #[error("Invalid example {0}: {1}")]
+    InvalidExample(usize, String),
+}
@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::EvaluatorError;
+
+/// The result of evaluating a prediction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationResult {
+    /// From 0 to 1 for the judges, the distance for the distance evaluators.
+    pub score: f64,
+    /// The verdict of the judge, e.g `CORRECT`.
+    pub value: Option<String>,
+    pub reasoning: Option<String>,
+}
+
+/// Evaluates a predicted string, e.g. the answer of a chain, optionally against a
+/// reference and the input that produced it.
+#[async_trait]
+pub trait StringEvaluator: Send + Sync {
+    async fn evaluate_strings(
+        &self,
+        prediction: &str,
+        reference: Option<&str>,
+        input: Option<&str>,
+    ) -> Result<EvaluationResult, EvaluatorError>;
+}
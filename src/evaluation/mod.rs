@@ -0,0 +1,29 @@
+mod evaluator;
+pub use evaluator::*;
+
+mod error;
+pub use error::*;
+
+mod qa;
+pub use qa::*;
+
+mod criteria;
+pub use criteria::*;
+
+mod pairwise;
+pub use pairwise::*;
+
+mod trajectory;
+pub use trajectory::*;
+
+mod embedding_distance;
+pub use embedding_distance::*;
+
+mod string_distance;
+pub use string_distance::*;
+
+mod string_match;
+pub use string_match::*;
+
+mod dataset;
+pub use dataset::*;
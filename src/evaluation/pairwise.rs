@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chain::{Chain, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt_args, template_jinja2,
+};
+
+use super::EvaluatorError;
+
+const DEFAULT_PAIRWISE_TEMPLATE: &str = r#"Please act as an impartial judge and evaluate the quality of the responses provided by two AI assistants to the user question displayed below. You should choose the assistant that follows the user's instructions and answers the user's question better. Consider factors such as the helpfulness, relevance, accuracy, depth, creativity, and level of detail of the responses.{{reference}}
+
+Begin your evaluation by comparing the two responses and provide a short explanation. Avoid any position biases and ensure that the order in which the responses were presented does not influence your decision. Do not allow the length of the responses to influence your evaluation. Be as objective as possible. After providing your explanation, output your final verdict by strictly following this format: "[[A]]" if assistant A is better, "[[B]]" if assistant B is better, and "[[C]]" for a tie.
+
+[User Question]
+{{input}}
+
+[The Start of Assistant A's Answer]
+{{prediction_a}}
+[The End of Assistant A's Answer]
+
+[The Start of Assistant B's Answer]
+{{prediction_b}}
+[The End of Assistant B's Answer]"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Preference {
+    A,
+    B,
+    Tie,
+}
+
+/// The comparison of two predictions by a `PairwiseEvalChain`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairwiseResult {
+    pub preference: Preference,
+    /// From 0, when the judge contradicts itself once the predictions are swapped, to 1
+    /// when it prefers the same prediction in both orders.
+    pub confidence: f64,
+    /// The explanations of the judge, with the predictions in the original order and
+    /// swapped.
+    pub reasoning: Vec<String>,
+}
+
+/// Parses the output of the judge: an explanation followed by `[[A]]`, `[[B]]` or
+/// `[[C]]`.
+fn parse_preference(output: &str) -> Option<(Preference, String)> {
+    let (index, preference) = [
+        ("[[A]]", Preference::A),
+        ("[[B]]", Preference::B),
+        ("[[C]]", Preference::Tie),
+    ]
+    .into_iter()
+    .filter_map(|(verdict, preference)| output.rfind(verdict).map(|i| (i, preference)))
+    .max_by_key(|(index, _)| *index)?;
+    Some((preference, output[..index].trim().to_string()))
+}
+
+/// Score of `A` for a preference: 1 if preferred, 0.5 for a tie.
+fn score(preference: Preference) -> f64 {
+    match preference {
+        Preference::A => 1.0,
+        Preference::B => 0.0,
+        Preference::Tie => 0.5,
+    }
+}
+
+/// Compares two predictions for the same input with an LLM as judge, e.g. to compare
+/// prompts or models.
+///
+/// The judge is asked twice, with the predictions swapped the second time, to mitigate
+/// its bias for the first or second position.
+///
+/// # Example
+/// ```rust,ignore
+/// let evaluator = PairwiseEvalChain::new(OpenAI::default());
+/// let result = evaluator
+///     .compare(&question, &answer_of_gpt, &answer_of_claude, None)
+///     .await?;
+/// println!("{:?} ({:.0}%)", result.preference, result.confidence * 100.0);
+/// ```
+pub struct PairwiseEvalChain {
+    chain: LLMChain,
+}
+
+impl PairwiseEvalChain {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L) -> Self {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(
+                DEFAULT_PAIRWISE_TEMPLATE,
+                "reference",
+                "input",
+                "prediction_a",
+                "prediction_b"
+            ))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        Self { chain }
+    }
+
+    /// Compares `prediction_a` and `prediction_b`, answers to `input`, optionally against
+    /// a reference answer.
+    pub async fn compare(
+        &self,
+        input: &str,
+        prediction_a: &str,
+        prediction_b: &str,
+        reference: Option<&str>,
+    ) -> Result<PairwiseResult, EvaluatorError> {
+        let (original, swapped) = futures::try_join!(
+            self.judge(input, prediction_a, prediction_b, reference),
+            self.judge(input, prediction_b, prediction_a, reference),
+        )?;
+
+        // Score of prediction A averaged over both orders.
+        let score_a = (score(original.0) + 1.0 - score(swapped.0)) / 2.0;
+        let preference = if score_a > 0.5 {
+            Preference::A
+        } else if score_a < 0.5 {
+            Preference::B
+        } else {
+            Preference::Tie
+        };
+        let confidence = if preference == Preference::Tie && original.0 == Preference::Tie {
+            1.0
+        } else {
+            (score_a - 0.5).abs() * 2.0
+        };
+        Ok(PairwiseResult {
+            preference,
+            confidence,
+            reasoning: vec![original.1, swapped.1],
+        })
+    }
+
+    async fn judge(
+        &self,
+        input: &str,
+        prediction_a: &str,
+        prediction_b: &str,
+        reference: Option<&str>,
+    ) -> Result<(Preference, String), EvaluatorError> {
+        let reference = reference
+            .map(|reference| {
+                format!(
+                    "\nYour evaluation should consider the correctness of the responses against this reference answer:\n{}",
+                    reference
+                )
+            })
+            .unwrap_or_default();
+        let output = self
+            .chain
+            .invoke(prompt_args! {
+                "reference" => reference,
+                "input" => input,
+                "prediction_a" => prediction_a,
+                "prediction_b" => prediction_b,
+            })
+            .await?;
+        parse_preference(&output).ok_or(EvaluatorError::ParsingError(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::openai::OpenAI;
+
+    #[test]
+    fn test_parse_preference() {
+        let (preference, reasoning) =
+            parse_preference("A is more accurate than B [[B]] would be worse.\nVerdict: [[A]]")
+                .unwrap();
+        assert_eq!(preference, Preference::A);
+        assert_eq!(
+            reasoning,
+            "A is more accurate than B [[B]] would be worse.\nVerdict:"
+        );
+        assert_eq!(parse_preference("[[C]]").unwrap().0, Preference::Tie);
+        assert!(parse_preference("Both are good").is_none());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_pairwise_eval_chain() {
+        let evaluator = PairwiseEvalChain::new(OpenAI::default());
+        let result = evaluator
+            .compare("Where is Lima?", "Lima is in Peru", "I don't know", None)
+            .await
+            .unwrap();
+        assert_eq!(result.preference, Preference::A);
+    }
+}
@@ -0,0 +1,198 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chain::{Chain, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt_args, template_jinja2,
+};
+
+use super::{EvaluationResult, EvaluatorError, StringEvaluator};
+
+const DEFAULT_QA_EVAL_TEMPLATE: &str = r#"You are a teacher grading a quiz.
+You are given a question, the student's answer, and the true answer, and are asked to score the student answer as either CORRECT or INCORRECT.
+
+Grade the student answers based ONLY on their factual accuracy. Ignore differences in punctuation and phrasing between the student answer and true answer. It is OK if the student answer contains more information than the true answer, as long as it does not contain any conflicting statements.
+
+Explain your reasoning in a step-by-step manner, then write the grade on the last line, in the format:
+GRADE: CORRECT or INCORRECT
+
+QUESTION: {{query}}
+STUDENT ANSWER: {{result}}
+TRUE ANSWER: {{answer}}
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum QAVerdict {
+    Correct,
+    Incorrect,
+}
+
+/// The grade of an answer by a `QAEvalChain`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QAEvalResult {
+    pub verdict: QAVerdict,
+    pub reasoning: String,
+    /// 1 for correct answers, 0 for incorrect ones.
+    pub score: f64,
+}
+
+impl From<QAEvalResult> for EvaluationResult {
+    fn from(result: QAEvalResult) -> Self {
+        let value = match result.verdict {
+            QAVerdict::Correct => "CORRECT",
+            QAVerdict::Incorrect => "INCORRECT",
+        };
+        Self {
+            score: result.score,
+            value: Some(value.to_string()),
+            reasoning: Some(result.reasoning),
+        }
+    }
+}
+
+/// A question with the answer predicted by the pipeline under test and the reference
+/// answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QAExample {
+    pub question: String,
+    pub prediction: String,
+    pub reference: String,
+}
+
+/// Parses the output of the judge: the grade on the last line, after the reasoning.
+fn parse_grade(output: &str) -> Option<QAEvalResult> {
+    let (reasoning, grade) = match output.rfind("GRADE:") {
+        Some(index) => (&output[..index], &output[index + "GRADE:".len()..]),
+        None => ("", output),
+    };
+    let grade = grade.trim().trim_matches(|c: char| !c.is_alphabetic());
+    let verdict = if grade.to_uppercase().starts_with("INCORRECT") {
+        QAVerdict::Incorrect
+    } else if grade.to_uppercase().starts_with("CORRECT") {
+        QAVerdict::Correct
+    } else {
+        return None;
+    };
+    Some(QAEvalResult {
+        verdict,
+        reasoning: reasoning.trim().to_string(),
+        score: if verdict == QAVerdict::Correct {
+            1.0
+        } else {
+            0.0
+        },
+    })
+}
+
+/// Grades predicted answers against reference answers with an LLM as judge, e.g. to
+/// regression-test a RAG pipeline.
+///
+/// # Example
+/// ```rust,ignore
+/// let evaluator = QAEvalChain::new(OpenAI::default());
+/// let result = evaluator
+///     .evaluate("Where is Lima?", &chain.invoke(input).await?, "Lima is in Peru")
+///     .await?;
+/// assert_eq!(result.verdict, QAVerdict::Correct);
+/// ```
+pub struct QAEvalChain {
+    chain: LLMChain,
+}
+
+impl QAEvalChain {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L) -> Self {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(
+                DEFAULT_QA_EVAL_TEMPLATE,
+                "query",
+                "result",
+                "answer"
+            ))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        Self { chain }
+    }
+
+    pub async fn evaluate(
+        &self,
+        question: &str,
+        prediction: &str,
+        reference: &str,
+    ) -> Result<QAEvalResult, EvaluatorError> {
+        let output = self
+            .chain
+            .invoke(prompt_args! {
+                "query" => question,
+                "result" => prediction,
+                "answer" => reference,
+            })
+            .await?;
+        parse_grade(&output).ok_or(EvaluatorError::ParsingError(output))
+    }
+
+    /// Grades the examples, up to `max_concurrency` at the same time. The results are in
+    /// the order of the examples.
+    pub async fn evaluate_examples(
+        &self,
+        examples: &[QAExample],
+        max_concurrency: usize,
+    ) -> Vec<Result<QAEvalResult, EvaluatorError>> {
+        futures::stream::iter(examples)
+            .map(|example| {
+                self.evaluate(&example.question, &example.prediction, &example.reference)
+            })
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+#[async_trait]
+impl StringEvaluator for QAEvalChain {
+    async fn evaluate_strings(
+        &self,
+        prediction: &str,
+        reference: Option<&str>,
+        input: Option<&str>,
+    ) -> Result<EvaluationResult, EvaluatorError> {
+        let reference = reference.ok_or(EvaluatorError::MissingValue("reference".into()))?;
+        let input = input.ok_or(EvaluatorError::MissingValue("input".into()))?;
+        Ok(self.evaluate(input, prediction, reference).await?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::openai::OpenAI;
+
+    #[test]
+    fn test_parse_grade() {
+        let result = parse_grade("The student says Lima is in Peru.\nGRADE: CORRECT").unwrap();
+        assert_eq!(result.verdict, QAVerdict::Correct);
+        assert_eq!(result.reasoning, "The student says Lima is in Peru.");
+        assert_eq!(result.score, 1.0);
+
+        let result = parse_grade("GRADE: **INCORRECT**").unwrap();
+        assert_eq!(result.verdict, QAVerdict::Incorrect);
+        assert_eq!(result.score, 0.0);
+
+        assert!(parse_grade("I am not sure").is_none());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_qa_eval_chain() {
+        let evaluator = QAEvalChain::new(OpenAI::default());
+        let result = evaluator
+            .evaluate("Where is Lima?", "In Peru", "Lima is the capital of Peru")
+            .await
+            .unwrap();
+        assert_eq!(result.verdict, QAVerdict::Correct);
+    }
+}
@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{EvaluationResult, EvaluatorError, StringEvaluator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StringDistance {
+    Levenshtein,
+    DamerauLevenshtein,
+    Jaro,
+    JaroWinkler,
+}
+
+impl StringDistance {
+    /// Distance between `a` and `b`, from 0 for equal strings to 1.
+    pub fn distance(&self, a: &str, b: &str) -> f64 {
+        let similarity = match self {
+            StringDistance::Levenshtein => strsim::normalized_levenshtein(a, b),
+            StringDistance::DamerauLevenshtein => strsim::normalized_damerau_levenshtein(a, b),
+            StringDistance::Jaro => strsim::jaro(a, b),
+            StringDistance::JaroWinkler => strsim::jaro_winkler(a, b),
+        };
+        1.0 - similarity
+    }
+}
+
+/// Scores a prediction with its string distance to the reference, from 0 for equal
+/// strings to 1.
+///
+/// # Example
+/// ```rust,ignore
+/// let evaluator = StringDistanceEvaluator::new(StringDistance::JaroWinkler);
+/// assert!(evaluator.evaluate(&answer, "Lima") < 0.1);
+/// ```
+pub struct StringDistanceEvaluator {
+    distance: StringDistance,
+    ignore_case: bool,
+}
+
+impl StringDistanceEvaluator {
+    pub fn new(distance: StringDistance) -> Self {
+        Self {
+            distance,
+            ignore_case: false,
+        }
+    }
+
+    pub fn with_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    pub fn evaluate(&self, prediction: &str, reference: &str) -> f64 {
+        let (prediction, reference) = (prediction.trim(), reference.trim());
+        if self.ignore_case {
+            self.distance
+                .distance(&prediction.to_lowercase(), &reference.to_lowercase())
+        } else {
+            self.distance.distance(prediction, reference)
+        }
+    }
+}
+
+impl Default for StringDistanceEvaluator {
+    fn default() -> Self {
+        Self::new(StringDistance::Levenshtein)
+    }
+}
+
+#[async_trait]
+impl StringEvaluator for StringDistanceEvaluator {
+    async fn evaluate_strings(
+        &self,
+        prediction: &str,
+        reference: Option<&str>,
+        _input: Option<&str>,
+    ) -> Result<EvaluationResult, EvaluatorError> {
+        let reference = reference.ok_or(EvaluatorError::MissingValue("reference".into()))?;
+        Ok(EvaluationResult {
+            score: self.evaluate(prediction, reference),
+            value: None,
+            reasoning: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_distance() {
+        let evaluator = StringDistanceEvaluator::default();
+        assert_eq!(evaluator.evaluate("kitten", "kitten"), 0.0);
+        assert!((evaluator.evaluate("kitten", "sitting") - 3.0 / 7.0).abs() < 1e-9);
+        assert_eq!(evaluator.evaluate("abc", "xyz"), 1.0);
+
+        let evaluator = StringDistanceEvaluator::new(StringDistance::JaroWinkler);
+        assert!(evaluator.evaluate("Lima", "LIMA") > 0.0);
+        assert_eq!(
+            evaluator.with_ignore_case(true).evaluate("Lima", "LIMA"),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use async_trait::async_trait;
+use regex::Regex;
+
+use super::{EvaluationResult, EvaluatorError, StringEvaluator};
+
+/// Scores 1 a prediction equal to the reference, 0 otherwise, optionally ignoring the
+/// case, the punctuation and the numbers. The surrounding whitespace is ignored.
+///
+/// # Example
+/// ```rust,ignore
+/// let evaluator = ExactMatchEvaluator::new().with_ignore_case(true);
+/// let result = evaluator.evaluate_strings(&answer, Some("Lima"), None).await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExactMatchEvaluator {
+    ignore_case: bool,
+    ignore_punctuation: bool,
+    ignore_numbers: bool,
+}
+
+impl ExactMatchEvaluator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    pub fn with_ignore_punctuation(mut self, ignore_punctuation: bool) -> Self {
+        self.ignore_punctuation = ignore_punctuation;
+        self
+    }
+
+    pub fn with_ignore_numbers(mut self, ignore_numbers: bool) -> Self {
+        self.ignore_numbers = ignore_numbers;
+        self
+    }
+
+    fn normalize(&self, text: &str) -> String {
+        let text: String = text
+            .chars()
+            .filter(|c| !(self.ignore_punctuation && c.is_ascii_punctuation()))
+            .filter(|c| !(self.ignore_numbers && c.is_ascii_digit()))
+            .collect();
+        let text = text.trim();
+        if self.ignore_case {
+            text.to_lowercase()
+        } else {
+            text.to_string()
+        }
+    }
+
+    pub fn evaluate(&self, prediction: &str, reference: &str) -> bool {
+        self.normalize(prediction) == self.normalize(reference)
+    }
+}
+
+#[async_trait]
+impl StringEvaluator for ExactMatchEvaluator {
+    async fn evaluate_strings(
+        &self,
+        prediction: &str,
+        reference: Option<&str>,
+        _input: Option<&str>,
+    ) -> Result<EvaluationResult, EvaluatorError> {
+        let reference = reference.ok_or(EvaluatorError::MissingValue("reference".into()))?;
+        Ok(match_result(self.evaluate(prediction, reference)))
+    }
+}
+
+/// Scores 1 a prediction matching the regular expression of the reference, 0 otherwise.
+/// Anchor the expression with `^` and `$` to match the whole prediction.
+///
+/// # Example
+/// ```rust,ignore
+/// let evaluator = RegexMatchEvaluator::new().with_ignore_case(true);
+/// let result = evaluator
+///     .evaluate_strings(&answer, Some(r"^\d{4}-\d{2}-\d{2}$"), None)
+///     .await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RegexMatchEvaluator {
+    ignore_case: bool,
+}
+
+impl RegexMatchEvaluator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    pub fn evaluate(&self, prediction: &str, pattern: &str) -> Result<bool, regex::Error> {
+        let pattern = if self.ignore_case {
+            format!("(?i){}", pattern)
+        } else {
+            pattern.to_string()
+        };
+        Ok(Regex::new(&pattern)?.is_match(prediction))
+    }
+}
+
+#[async_trait]
+impl StringEvaluator for RegexMatchEvaluator {
+    async fn evaluate_strings(
+        &self,
+        prediction: &str,
+        reference: Option<&str>,
+        _input: Option<&str>,
+    ) -> Result<EvaluationResult, EvaluatorError> {
+        let reference = reference.ok_or(EvaluatorError::MissingValue("reference".into()))?;
+        let matched = self.evaluate(prediction, reference).map_err(|e| {
+            EvaluatorError::ParsingError(format!("invalid reference pattern: {}", e))
+        })?;
+        Ok(match_result(matched))
+    }
+}
+
+fn match_result(matched: bool) -> EvaluationResult {
+    EvaluationResult {
+        score: if matched { 1.0 } else { 0.0 },
+        value: Some(if matched { "MATCH" } else { "NO_MATCH" }.to_string()),
+        reasoning: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_string_match() {
+        let evaluator = ExactMatchEvaluator::new();
+        assert!(evaluator.evaluate(" Lima\n", "Lima"));
+        assert!(!evaluator.evaluate("lima.", "Lima"));
+        let evaluator = evaluator
+            .with_ignore_case(true)
+            .with_ignore_punctuation(true);
+        assert!(evaluator.evaluate("lima.", "Lima"));
+        assert!(!evaluator.evaluate("Lima 2024", "Lima"));
+        assert!(evaluator
+            .with_ignore_numbers(true)
+            .evaluate("Lima 2024", "Lima"));
+
+        let result = RegexMatchEvaluator::new()
+            .evaluate_strings("Due on 2024-05-01.", Some(r"\d{4}-\d{2}-\d{2}"), None)
+            .await
+            .unwrap();
+        assert_eq!(result.score, 1.0);
+        assert_eq!(result.value.as_deref(), Some("MATCH"));
+        assert!(RegexMatchEvaluator::new()
+            .with_ignore_case(true)
+            .evaluate("YES", "^yes$")
+            .unwrap());
+        assert!(matches!(
+            RegexMatchEvaluator::new()
+                .evaluate_strings("answer", None, None)
+                .await,
+            Err(EvaluatorError::MissingValue(_))
+        ));
+    }
+}
@@ -0,0 +1,460 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    callbacks::{CallbackHandler, CallbackManager, RunInfo, RunType},
+    chain::{Chain, ChainError, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt::PromptArgs,
+    prompt_args, template_jinja2,
+    tools::Tool,
+};
+
+use super::EvaluatorError;
+
+const DEFAULT_TRAJECTORY_TEMPLATE: &str = r#"An AI language model has been given access to the following set of tools to help answer a user's question.
+
+The tools given to the AI model are:
+[TOOL_DESCRIPTIONS]
+{{tools}}
+[END_TOOL_DESCRIPTIONS]
+
+The question the human asked the AI model was:
+[QUESTION]
+{{input}}
+[END_QUESTION]
+
+The AI language model decided to use the following set of tools to answer the question:
+[AGENT_TRAJECTORY]
+{{trajectory}}
+[END_AGENT_TRAJECTORY]
+
+The AI language model's final answer to the question was:
+[RESPONSE]
+{{output}}
+[END_RESPONSE]{{reference}}
+
+Let's do a detailed evaluation of the AI language model's answer step by step.
+
+We consider the following criteria before giving a score from 1 to 5:
+
+i. Is the final answer helpful?
+ii. Does the AI language model use a logical sequence of tools to answer the question?
+iii. Does the AI language model use the tools in a helpful way?
+iv. Does the AI language model use too many steps to answer the question?
+v. Are the appropriate tools used to answer the question?
+
+Explain your reasoning, then write the score on the last line, in the format:
+Score: <1 to 5>"#;
+
+/// A tool call of an agent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrajectoryStep {
+    pub tool: String,
+    pub tool_input: String,
+    /// The output of the tool, or its error.
+    pub observation: String,
+}
+
+/// The tool calls of an agent while answering an input, see `TrajectoryRecorder`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentTrajectory {
+    pub input: String,
+    pub steps: Vec<TrajectoryStep>,
+    pub output: String,
+}
+
+impl AgentTrajectory {
+    /// Calls `agent` (e.g. an `AgentExecutor`) recording the tools it calls.
+    pub async fn record<C: Chain + ?Sized>(
+        agent: &C,
+        input_variables: PromptArgs,
+    ) -> Result<Self, ChainError> {
+        let input = match input_variables.get("input") {
+            Some(Value::String(input)) => input.clone(),
+            _ => Value::from(serde_json::Map::from_iter(input_variables.clone())).to_string(),
+        };
+        let recorder = TrajectoryRecorder::new();
+        let result = agent
+            .call_with_callbacks(
+                input_variables,
+                CallbackManager::new().with_handler(recorder.clone()),
+            )
+            .await?;
+        Ok(Self {
+            input,
+            steps: recorder.steps(),
+            output: result.generation,
+        })
+    }
+
+    /// Number of tool calls.
+    pub fn iterations(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Number of tool calls repeating a previous call with the same input.
+    pub fn repeated_steps(&self) -> usize {
+        let mut seen = HashSet::new();
+        self.steps
+            .iter()
+            .filter(|step| !seen.insert((&step.tool, &step.tool_input)))
+            .count()
+    }
+
+    /// Number of calls of each tool.
+    pub fn tool_counts(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for step in &self.steps {
+            *counts.entry(step.tool.clone()).or_default() += 1;
+        }
+        counts
+    }
+
+    fn format_steps(&self) -> String {
+        if self.steps.is_empty() {
+            return "No tools were used.".to_string();
+        }
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                format!(
+                    "Step {}:\nTool used: {}\nTool input: {}\nTool output: {}",
+                    i + 1,
+                    step.tool,
+                    step.tool_input,
+                    step.observation
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Handler recording the tool calls of the runs it is attached to, in order.
+#[derive(Clone, Default)]
+pub struct TrajectoryRecorder {
+    steps: Arc<Mutex<Vec<TrajectoryStep>>>,
+    /// Index of the step of the running tool runs.
+    running: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl TrajectoryRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn steps(&self) -> Vec<TrajectoryStep> {
+        self.steps.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn end(&self, run: &RunInfo, observation: &str) {
+        let index = self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&run.run_id);
+        if let Some(index) = index {
+            let mut steps = self.steps.lock().unwrap_or_else(|e| e.into_inner());
+            steps[index].observation = observation.to_string();
+        }
+    }
+}
+
+impl CallbackHandler for TrajectoryRecorder {
+    fn on_tool_start(&self, run: &RunInfo, input: &str) {
+        let mut steps = self.steps.lock().unwrap_or_else(|e| e.into_inner());
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(run.run_id.clone(), steps.len());
+        steps.push(TrajectoryStep {
+            tool: run.name.clone(),
+            tool_input: input.to_string(),
+            observation: String::new(),
+        });
+    }
+
+    fn on_tool_end(&self, run: &RunInfo, output: &str) {
+        self.end(run, output);
+    }
+
+    fn on_error(&self, run: &RunInfo, error: &str) {
+        if run.run_type == RunType::Tool {
+            self.end(run, error);
+        }
+    }
+}
+
+/// The evaluation of a trajectory by a `TrajectoryEvalChain`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrajectoryEvalResult {
+    /// The score of the judge, from 0 (1 out of 5) to 1 (5 out of 5).
+    pub score: f64,
+    pub reasoning: String,
+    /// Fraction of the tool calls that don't repeat a previous call, 1 without tool
+    /// calls.
+    pub efficiency: f64,
+    /// How much the tools called match the expected ones, in order, from 0 to 1. `None`
+    /// without expected tools.
+    pub tool_accuracy: Option<f64>,
+}
+
+/// Parses the output of the judge: the reasoning followed by the score from 1 to 5.
+fn parse_score(output: &str) -> Option<(f64, String)> {
+    let index = output.rfind("Score:")?;
+    let score = output[index + "Score:".len()..]
+        .trim()
+        .chars()
+        .next()
+        .and_then(|c| c.to_digit(10))
+        .filter(|score| (1..=5).contains(score))?;
+    Some(((score - 1) as f64 / 4.0, output[..index].trim().to_string()))
+}
+
+/// Length of the longest common subsequence of the tools called and the expected ones,
+/// over the length of the longest of both.
+fn tool_accuracy(steps: &[TrajectoryStep], expected_tools: &[String]) -> f64 {
+    let max_len = steps.len().max(expected_tools.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+    let mut lengths = vec![vec![0; expected_tools.len() + 1]; steps.len() + 1];
+    for (i, step) in steps.iter().enumerate() {
+        for (j, tool) in expected_tools.iter().enumerate() {
+            lengths[i + 1][j + 1] = if &step.tool == tool {
+                lengths[i][j] + 1
+            } else {
+                lengths[i][j + 1].max(lengths[i + 1][j])
+            };
+        }
+    }
+    lengths[steps.len()][expected_tools.len()] as f64 / max_len as f64
+}
+
+/// Evaluates the full trajectory of an agent, not just its final answer: whether the
+/// tools it chose, their inputs and the number of iterations were reasonable for the
+/// question, with an LLM as judge.
+///
+/// # Example
+/// ```rust,ignore
+/// let trajectory = AgentTrajectory::record(&executor, prompt_args! {"input" => question}).await?;
+/// let evaluator = TrajectoryEvalChain::new(OpenAI::default()).with_tools(&tools);
+/// let result = evaluator
+///     .evaluate(&trajectory, Some(&["search".to_string(), "calculator".to_string()]))
+///     .await?;
+/// println!("{} ({} repeated calls)", result.score, trajectory.repeated_steps());
+/// ```
+pub struct TrajectoryEvalChain {
+    chain: LLMChain,
+    tools: String,
+}
+
+impl TrajectoryEvalChain {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L) -> Self {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(
+                DEFAULT_TRAJECTORY_TEMPLATE,
+                "tools",
+                "input",
+                "trajectory",
+                "output",
+                "reference"
+            ))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        Self {
+            chain,
+            tools: "Unknown".to_string(),
+        }
+    }
+
+    /// The tools available to the agent, described to the judge.
+    pub fn with_tools(mut self, tools: &[Arc<dyn Tool>]) -> Self {
+        self.tools = tools
+            .iter()
+            .enumerate()
+            .map(|(i, tool)| {
+                format!(
+                    "Tool {}: {}\nDescription: {}",
+                    i + 1,
+                    tool.name(),
+                    tool.description()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        self
+    }
+
+    /// Evaluates `trajectory`, optionally against the tools the agent is expected to call,
+    /// in order.
+    pub async fn evaluate(
+        &self,
+        trajectory: &AgentTrajectory,
+        expected_tools: Option<&[String]>,
+    ) -> Result<TrajectoryEvalResult, EvaluatorError> {
+        let reference = expected_tools
+            .map(|tools| {
+                format!(
+                    "\n\nThe expected sequence of tools was:\n[REFERENCE]\n{}\n[END_REFERENCE]",
+                    tools.join(", ")
+                )
+            })
+            .unwrap_or_default();
+        let output = self
+            .chain
+            .invoke(prompt_args! {
+                "tools" => self.tools,
+                "input" => trajectory.input,
+                "trajectory" => trajectory.format_steps(),
+                "output" => trajectory.output,
+                "reference" => reference,
+            })
+            .await?;
+        let (score, reasoning) =
+            parse_score(&output).ok_or(EvaluatorError::ParsingError(output))?;
+
+        let efficiency = if trajectory.steps.is_empty() {
+            1.0
+        } else {
+            1.0 - trajectory.repeated_steps() as f64 / trajectory.iterations() as f64
+        };
+        Ok(TrajectoryEvalResult {
+            score,
+            reasoning,
+            efficiency,
+            tool_accuracy: expected_tools.map(|tools| tool_accuracy(&trajectory.steps, tools)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{
+        agent::{Agent, AgentError, AgentExecutor},
+        llm::openai::OpenAI,
+        schemas::agent::{AgentAction, AgentEvent, AgentFinish},
+    };
+
+    struct Calculator;
+
+    #[async_trait]
+    impl Tool for Calculator {
+        fn name(&self) -> String {
+            "calculator".to_string()
+        }
+
+        fn description(&self) -> String {
+            "Useful to make calculations".to_string()
+        }
+
+        async fn run(&self, _input: Value) -> Result<String, Box<dyn Error>> {
+            Ok("4".to_string())
+        }
+    }
+
+    /// Calls the calculator twice with the same input, then answers.
+    struct RepetitiveAgent;
+
+    #[async_trait]
+    impl Agent for RepetitiveAgent {
+        async fn plan(
+            &self,
+            intermediate_steps: &[(AgentAction, String)],
+            _inputs: PromptArgs,
+        ) -> Result<AgentEvent, AgentError> {
+            if intermediate_steps.len() < 2 {
+                return Ok(AgentEvent::Action(vec![AgentAction {
+                    tool: "calculator".to_string(),
+                    tool_input: "2+2".to_string(),
+                    log: String::new(),
+                }]));
+            }
+            Ok(AgentEvent::Finish(AgentFinish {
+                output: "2+2 is 4".to_string(),
+            }))
+        }
+
+        fn get_tools(&self) -> Vec<Arc<dyn Tool>> {
+            vec![Arc::new(Calculator)]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_trajectory() {
+        let executor = AgentExecutor::from_agent(RepetitiveAgent);
+        let trajectory = AgentTrajectory::record(&executor, prompt_args! {"input" => "2+2?"})
+            .await
+            .unwrap();
+
+        assert_eq!(trajectory.input, "2+2?");
+        assert_eq!(trajectory.output, "2+2 is 4");
+        assert_eq!(trajectory.iterations(), 2);
+        assert_eq!(trajectory.repeated_steps(), 1);
+        assert_eq!(trajectory.tool_counts()["calculator"], 2);
+        assert_eq!(
+            trajectory.steps[0],
+            TrajectoryStep {
+                tool: "calculator".to_string(),
+                tool_input: "2+2".to_string(),
+                observation: "4".to_string(),
+            }
+        );
+
+        assert_eq!(
+            tool_accuracy(&trajectory.steps, &["calculator".to_string()]),
+            0.5
+        );
+        assert_eq!(
+            tool_accuracy(
+                &trajectory.steps,
+                &["calculator".to_string(), "calculator".to_string()]
+            ),
+            1.0
+        );
+        assert_eq!(
+            tool_accuracy(&trajectory.steps, &["search".to_string()]),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_parse_score() {
+        let (score, reasoning) = parse_score("The agent repeats a call.\nScore: 4").unwrap();
+        assert_eq!(score, 0.75);
+        assert_eq!(reasoning, "The agent repeats a call.");
+        assert_eq!(parse_score("Score: 1/5").unwrap().0, 0.0);
+        assert!(parse_score("Score: 9").is_none());
+        assert!(parse_score("Good").is_none());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_trajectory_eval_chain() {
+        let executor = AgentExecutor::from_agent(RepetitiveAgent);
+        let trajectory = AgentTrajectory::record(&executor, prompt_args! {"input" => "2+2?"})
+            .await
+            .unwrap();
+        let result = TrajectoryEvalChain::new(OpenAI::default())
+            .with_tools(&RepetitiveAgent.get_tools())
+            .evaluate(&trajectory, Some(&["calculator".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(result.efficiency, 0.5);
+        assert_eq!(result.tool_accuracy, Some(0.5));
+    }
+}
@@ -0,0 +1,178 @@
+use super::GraphStoreError;
+
+/// The clauses and commands of Cypher which modify the graph or the database.
+const WRITE_KEYWORDS: [&str; 13] = [
+    "CREATE", "MERGE", "DELETE", "DETACH", "SET", "REMOVE", "DROP", "FOREACH", "LOAD", "ALTER",
+    "GRANT", "DENY", "REVOKE",
+];
+
+/// The procedures which only read, the ones ending with a dot are namespaces. The names
+/// are uppercased, as the words of `cypher_keywords`.
+const READ_PROCEDURES: [&str; 13] = [
+    "DB.LABELS",
+    "DB.RELATIONSHIPTYPES",
+    "DB.PROPERTYKEYS",
+    "DB.SCHEMA.",
+    "DB.INDEXES",
+    "DB.CONSTRAINTS",
+    "DB.INFO",
+    "DB.PING",
+    "DB.INDEX.FULLTEXT.QUERYNODES",
+    "DB.INDEX.FULLTEXT.QUERYRELATIONSHIPS",
+    "DB.INDEX.VECTOR.QUERYNODES",
+    "DBMS.COMPONENTS",
+    "APOC.META.",
+];
+
+/// Rejects the Cypher queries which could modify the graph. The string literals, the
+/// quoted names, the comments, the labels, the properties and the parameters are
+/// ignored, e.g. `n.set` or `'Set theory'` are allowed.
+///
+/// Only the procedures known to read can be called, e.g. `db.labels` or `db.schema.*`,
+/// as some procedures commit their writes themselves, e.g. `apoc.periodic.iterate`.
+pub fn check_read_only_cypher(query: &str) -> Result<(), GraphStoreError> {
+    let words = cypher_keywords(query)?;
+    for procedure in words
+        .windows(2)
+        .filter(|words| words[0] == "CALL" && words[1] != "{" && words[1] != "(")
+        .map(|words| &words[1])
+    {
+        if !READ_PROCEDURES.iter().any(|allowed| {
+            procedure == allowed || (allowed.ends_with('.') && procedure.starts_with(allowed))
+        }) {
+            return Err(GraphStoreError::WriteQuery(format!(
+                "the query calls the procedure {}, which is not known to be read-only",
+                procedure.to_lowercase()
+            )));
+        }
+    }
+    if let Some(keyword) = WRITE_KEYWORDS
+        .iter()
+        .find(|keyword| words.iter().any(|word| word == *keyword))
+    {
+        return Err(GraphStoreError::WriteQuery(format!(
+            "the query contains the clause {}",
+            keyword
+        )));
+    }
+    if words
+        .windows(2)
+        .any(|words| words[0] == "IN" && words[1] == "TRANSACTIONS")
+    {
+        return Err(GraphStoreError::WriteQuery(
+            "the query runs in transactions".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The uppercased words of the query which can be keywords, with the dotted names, e.g.
+/// the procedures, as one word, and the `{` and `(` which can follow `CALL`.
+fn cypher_keywords(query: &str) -> Result<Vec<String>, GraphStoreError> {
+    let mut words: Vec<String> = Vec::new();
+    let mut chars = query.chars().peekable();
+    let mut previous = ' ';
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                let mut escaped = false;
+                for next in chars.by_ref() {
+                    match next {
+                        '\\' if !escaped => escaped = true,
+                        next if next == c && !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut star = false;
+                for next in chars.by_ref() {
+                    if star && next == '/' {
+                        break;
+                    }
+                    star = next == '*';
+                }
+            }
+            ';' if chars.clone().any(|next| !next.is_whitespace()) => {
+                return Err(GraphStoreError::WriteQuery(
+                    "only a single statement can be executed".to_string(),
+                ));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(next) = chars.next_if(|next| next.is_alphanumeric() || *next == '_')
+                {
+                    word.push(next);
+                }
+                match (previous, words.last_mut()) {
+                    ('.', Some(last)) => {
+                        last.push('.');
+                        last.push_str(&word.to_uppercase());
+                    }
+                    ('$' | ':' | '.', _) => {}
+                    _ => words.push(word.to_uppercase()),
+                }
+                previous = 'a';
+                continue;
+            }
+            '{' | '(' => words.push(c.to_string()),
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            previous = c;
+        }
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_read_only_cypher() {
+        assert!(check_read_only_cypher(
+            "MATCH (p:Person)-[:WON]->(a) WHERE a.id = 'Set theory' RETURN p.set, $delete"
+        )
+        .is_ok());
+        assert!(check_read_only_cypher("MATCH (n) RETURN n // DELETE n").is_ok());
+        assert!(check_read_only_cypher("MATCH (n:`CREATE`) RETURN n;").is_ok());
+        assert!(check_read_only_cypher("CALL db.labels() YIELD label RETURN label").is_ok());
+        assert!(check_read_only_cypher("call db.schema.visualization()").is_ok());
+        assert!(
+            check_read_only_cypher("MATCH (n) CALL { WITH n RETURN n.id AS id } RETURN id").is_ok()
+        );
+        assert!(
+            check_read_only_cypher("MATCH (n) CALL (n) { RETURN n.id AS id } RETURN id").is_ok()
+        );
+
+        for query in [
+            "MATCH (n) DETACH DELETE n",
+            "match (n) set n.name = 'x'",
+            "MERGE (n:Person {id: 'x'})",
+            "MATCH (n) RETURN n; MATCH (m) DELETE m",
+            "LOAD CSV FROM 'file:///x.csv' AS row RETURN row",
+            "MATCH (n) CALL { WITH n RETURN n } IN TRANSACTIONS RETURN n",
+            "CALL apoc.periodic.iterate('MATCH (n) RETURN n', 'DETACH DELETE n', {})",
+            "CALL apoc.export.csv.all('graph.csv', {})",
+            "MATCH (n) CALL { CALL apoc.create.node(['Person'], {}) YIELD node RETURN node } RETURN n",
+        ] {
+            assert!(
+                matches!(
+                    check_read_only_cypher(query),
+                    Err(GraphStoreError::WriteQuery(_))
+                ),
+                "{}",
+                query
+            );
+        }
+    }
+}
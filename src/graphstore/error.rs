@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GraphStoreError {
+    #[error("Unsupported query: {0}")]
+    UnsupportedQuery(String),
+
+    #[error("Only read-only queries are allowed: {0}")]
+    WriteQuery(String),
+
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[cfg(feature = "neo4j")]
+    #[error(transparent)]
+    Neo4jError(#[from] neo4rs::Error),
+
+    #[cfg(feature = "neo4j")]
+    #[error(transparent)]
+    Neo4jDeError(#[from] neo4rs::DeError),
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::schemas::Document;
+
+/// An entity of a graph, identified by its `id` and `node_type`, e.g. `("Marie Curie",
+/// "Person")`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Node {
+    pub id: String,
+    pub node_type: String,
+    #[serde(default)]
+    pub properties: HashMap<String, Value>,
+}
+
+impl Node {
+    pub fn new<I: Into<String>, T: Into<String>>(id: I, node_type: T) -> Self {
+        Self {
+            id: id.into(),
+            node_type: node_type.into(),
+            properties: HashMap::new(),
+        }
+    }
+
+    pub fn with_properties(mut self, properties: HashMap<String, Value>) -> Self {
+        self.properties = properties;
+        self
+    }
+}
+
+/// A directed relationship between two nodes, e.g. `(Marie Curie)-[WON]->(Nobel Prize)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Relationship {
+    pub source: Node,
+    pub target: Node,
+    pub relationship_type: String,
+    #[serde(default)]
+    pub properties: HashMap<String, Value>,
+}
+
+impl Relationship {
+    pub fn new<T: Into<String>>(source: Node, relationship_type: T, target: Node) -> Self {
+        Self {
+            source,
+            target,
+            relationship_type: relationship_type.into(),
+            properties: HashMap::new(),
+        }
+    }
+
+    /// The relationship as a sentence, e.g. `Marie Curie WON Nobel Prize`.
+    pub fn to_text(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.source.id, self.relationship_type, self.target.id
+        )
+    }
+}
+
+/// The nodes and relationships extracted from a document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphDocument {
+    pub nodes: Vec<Node>,
+    pub relationships: Vec<Relationship>,
+    pub source: Document,
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::{check_read_only_cypher, GraphDocument, GraphStoreError, Node, Relationship};
+
+/// Store of a knowledge graph, e.g. the entities and relationships extracted from the
+/// documents by a `LLMGraphTransformer`, for graph-augmented retrieval.
+#[async_trait]
+pub trait GraphStore: Send + Sync {
+    /// Stores the nodes and relationships of the documents, merging the nodes with the same
+    /// id and type. With `include_source` the documents are stored too, linked to the
+    /// nodes they mention.
+    async fn add_graph_documents(
+        &self,
+        documents: &[GraphDocument],
+        include_source: bool,
+    ) -> Result<(), GraphStoreError>;
+
+    /// Runs a query in the language of the store, e.g. Cypher, returning its rows.
+    async fn query(
+        &self,
+        query: &str,
+        params: HashMap<String, Value>,
+    ) -> Result<Vec<HashMap<String, Value>>, GraphStoreError>;
+
+    /// Runs a query which can't modify the graph, e.g. written by a LLM. The queries with
+    /// writing clauses are rejected, see `check_read_only_cypher`.
+    async fn query_read_only(
+        &self,
+        query: &str,
+        params: HashMap<String, Value>,
+    ) -> Result<Vec<HashMap<String, Value>>, GraphStoreError> {
+        check_read_only_cypher(query)?;
+        self.query(query, params).await
+    }
+
+    /// Describes the node types and relationships of the graph, e.g. for a LLM writing
+    /// queries.
+    async fn get_schema(&self) -> Result<String, GraphStoreError>;
+
+    /// The nodes whose id is mentioned in `text`, case-insensitively.
+    async fn search_nodes(&self, text: &str, limit: usize) -> Result<Vec<Node>, GraphStoreError>;
+
+    /// The relationships from or to the nodes with the given ids.
+    async fn neighbors(
+        &self,
+        node_ids: &[String],
+        limit: usize,
+    ) -> Result<Vec<Relationship>, GraphStoreError>;
+}
+
+impl<G> From<G> for Box<dyn GraphStore>
+where
+    G: GraphStore + 'static,
+{
+    fn from(store: G) -> Self {
+        Box::new(store)
+    }
+}
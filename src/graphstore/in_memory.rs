@@ -0,0 +1,214 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::RwLock,
+};
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::schemas::Document;
+
+use super::{GraphDocument, GraphStore, GraphStoreError, Node, Relationship};
+
+/// Graph store keeping the graph in memory, e.g. for tests or small graphs. It doesn't
+/// support queries.
+#[derive(Default)]
+pub struct InMemoryGraphStore {
+    nodes: RwLock<Vec<Node>>,
+    relationships: RwLock<Vec<Relationship>>,
+    documents: RwLock<Vec<Document>>,
+}
+
+impl InMemoryGraphStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn nodes(&self) -> Vec<Node> {
+        self.nodes.read().unwrap().clone()
+    }
+
+    pub fn relationships(&self) -> Vec<Relationship> {
+        self.relationships.read().unwrap().clone()
+    }
+
+    /// The source documents stored with `include_source`.
+    pub fn documents(&self) -> Vec<Document> {
+        self.documents.read().unwrap().clone()
+    }
+
+    fn merge_node(nodes: &mut Vec<Node>, node: &Node) {
+        match nodes
+            .iter_mut()
+            .find(|stored| stored.id == node.id && stored.node_type == node.node_type)
+        {
+            Some(stored) => stored.properties.extend(node.properties.clone()),
+            None => nodes.push(node.clone()),
+        }
+    }
+}
+
+fn same_node(a: &Node, b: &Node) -> bool {
+    a.id == b.id && a.node_type == b.node_type
+}
+
+#[async_trait]
+impl GraphStore for InMemoryGraphStore {
+    async fn add_graph_documents(
+        &self,
+        documents: &[GraphDocument],
+        include_source: bool,
+    ) -> Result<(), GraphStoreError> {
+        let mut nodes = self.nodes.write().unwrap();
+        let mut relationships = self.relationships.write().unwrap();
+        for document in documents {
+            for node in &document.nodes {
+                Self::merge_node(&mut nodes, node);
+            }
+            for relationship in &document.relationships {
+                Self::merge_node(&mut nodes, &relationship.source);
+                Self::merge_node(&mut nodes, &relationship.target);
+                match relationships.iter_mut().find(|stored| {
+                    stored.relationship_type == relationship.relationship_type
+                        && same_node(&stored.source, &relationship.source)
+                        && same_node(&stored.target, &relationship.target)
+                }) {
+                    Some(stored) => stored.properties.extend(relationship.properties.clone()),
+                    None => relationships.push(relationship.clone()),
+                }
+            }
+            if include_source {
+                self.documents
+                    .write()
+                    .unwrap()
+                    .push(document.source.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _params: HashMap<String, Value>,
+    ) -> Result<Vec<HashMap<String, Value>>, GraphStoreError> {
+        Err(GraphStoreError::UnsupportedQuery(query.to_string()))
+    }
+
+    async fn get_schema(&self) -> Result<String, GraphStoreError> {
+        let node_types: BTreeSet<String> = self
+            .nodes
+            .read()
+            .unwrap()
+            .iter()
+            .map(|node| node.node_type.clone())
+            .collect();
+        let patterns: BTreeSet<String> = self
+            .relationships
+            .read()
+            .unwrap()
+            .iter()
+            .map(|relationship| {
+                format!(
+                    "(:{})-[:{}]->(:{})",
+                    relationship.source.node_type,
+                    relationship.relationship_type,
+                    relationship.target.node_type
+                )
+            })
+            .collect();
+        Ok(format_schema(node_types, patterns))
+    }
+
+    async fn search_nodes(&self, text: &str, limit: usize) -> Result<Vec<Node>, GraphStoreError> {
+        let text = text.to_lowercase();
+        let mut nodes: Vec<Node> = self
+            .nodes
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|node| !node.id.is_empty() && text.contains(&node.id.to_lowercase()))
+            .cloned()
+            .collect();
+        // The longest ids are the most specific mentions.
+        nodes.sort_by_key(|node| std::cmp::Reverse(node.id.len()));
+        nodes.truncate(limit);
+        Ok(nodes)
+    }
+
+    async fn neighbors(
+        &self,
+        node_ids: &[String],
+        limit: usize,
+    ) -> Result<Vec<Relationship>, GraphStoreError> {
+        Ok(self
+            .relationships
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|relationship| {
+                node_ids.contains(&relationship.source.id)
+                    || node_ids.contains(&relationship.target.id)
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
+
+/// The schema of a graph returned by `GraphStore::get_schema`.
+pub(crate) fn format_schema<N, P>(node_types: N, patterns: P) -> String
+where
+    N: IntoIterator<Item = String>,
+    P: IntoIterator<Item = String>,
+{
+    format!(
+        "Node types: {}\nRelationships:\n{}",
+        node_types.into_iter().collect::<Vec<_>>().join(", "),
+        patterns.into_iter().collect::<Vec<_>>().join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_graph_store() {
+        let curie = Node::new("Marie Curie", "Person");
+        let prize = Node::new("Nobel Prize", "Award");
+        let document = GraphDocument {
+            nodes: vec![curie.clone(), prize.clone()],
+            relationships: vec![
+                Relationship::new(curie.clone(), "WON", prize.clone()),
+                Relationship::new(curie.clone(), "WON", prize.clone()),
+            ],
+            source: Document::new("Marie Curie won the Nobel Prize"),
+        };
+        let store = InMemoryGraphStore::new();
+        store.add_graph_documents(&[document], true).await.unwrap();
+
+        assert_eq!(store.nodes().len(), 2);
+        assert_eq!(store.relationships().len(), 1);
+        assert_eq!(store.documents().len(), 1);
+        assert_eq!(
+            store.get_schema().await.unwrap(),
+            "Node types: Award, Person\nRelationships:\n(:Person)-[:WON]->(:Award)"
+        );
+
+        let nodes = store
+            .search_nodes("What did marie curie win?", 5)
+            .await
+            .unwrap();
+        assert_eq!(nodes, vec![curie]);
+        let relationships = store
+            .neighbors(&["Nobel Prize".to_string()], 5)
+            .await
+            .unwrap();
+        assert_eq!(relationships[0].to_text(), "Marie Curie WON Nobel Prize");
+        assert!(store
+            .query("MATCH (n) RETURN n", HashMap::new())
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use futures::future::try_join_all;
+use serde::Deserialize;
+
+use crate::{
+    chain::{Chain, ChainError, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt_args,
+    schemas::Document,
+    template_jinja2,
+};
+
+use super::{GraphDocument, Node, Relationship};
+
+const DEFAULT_GRAPH_TEMPLATE: &str = r#"You are extracting a knowledge graph from a text. Identify the entities (nodes) and the relationships between them.
+- Use the most complete name of each entity as its id, e.g. "Marie Curie" instead of "she" or "Curie".
+- Use basic and general types, e.g. "Person" instead of "Scientist".
+- Use general relationship types in uppercase, e.g. "WORKS_AT" instead of "BECAME_PROFESSOR_AT".
+{% if node_types %}- Only use the node types: {{node_types}}.
+{% endif %}{% if relationship_types %}- Only use the relationship types: {{relationship_types}}.
+{% endif %}
+Answer only with json with the format:
+{"nodes": [{"id": "Marie Curie", "type": "Person"}], "relationships": [{"source": "Marie Curie", "source_type": "Person", "target": "Nobel Prize", "target_type": "Award", "type": "WON"}]}
+
+Text: {{text}}"#;
+
+#[derive(Deserialize)]
+struct ExtractedNode {
+    id: String,
+    #[serde(rename = "type")]
+    node_type: String,
+}
+
+#[derive(Deserialize)]
+struct ExtractedRelationship {
+    source: String,
+    source_type: String,
+    target: String,
+    target_type: String,
+    #[serde(rename = "type")]
+    relationship_type: String,
+}
+
+#[derive(Deserialize)]
+struct ExtractedGraph {
+    #[serde(default)]
+    nodes: Vec<ExtractedNode>,
+    #[serde(default)]
+    relationships: Vec<ExtractedRelationship>,
+}
+
+/// `Person`, for the node types.
+fn title_case(text: &str) -> String {
+    let mut chars = text.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// `WORKS_AT`, for the relationship types.
+fn upper_snake_case(text: &str) -> String {
+    text.trim()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter(|word| !word.is_empty())
+        .map(str::to_uppercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Extracts the nodes and relationships of documents with an LLM, to store them in a
+/// `GraphStore`.
+///
+/// # Example
+/// ```rust,ignore
+/// let transformer = LLMGraphTransformer::new(OpenAI::default())
+///     .with_allowed_nodes(&["Person", "Organization"])
+///     .with_allowed_relationships(&["WORKS_AT"]);
+/// let graph_documents = transformer.convert_to_graph_documents(&documents).await?;
+/// store.add_graph_documents(&graph_documents, true).await?;
+/// ```
+pub struct LLMGraphTransformer {
+    chain: LLMChain,
+    allowed_nodes: Vec<String>,
+    allowed_relationships: Vec<String>,
+}
+
+impl LLMGraphTransformer {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L) -> Self {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(
+                DEFAULT_GRAPH_TEMPLATE,
+                "text",
+                "node_types",
+                "relationship_types"
+            ))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        Self {
+            chain,
+            allowed_nodes: Vec::new(),
+            allowed_relationships: Vec::new(),
+        }
+    }
+
+    /// Only extracts the nodes of these types, and the relationships between them.
+    pub fn with_allowed_nodes<S: AsRef<str>>(mut self, node_types: &[S]) -> Self {
+        self.allowed_nodes = node_types
+            .iter()
+            .map(|node_type| title_case(node_type.as_ref()))
+            .collect();
+        self
+    }
+
+    /// Only extracts the relationships of these types.
+    pub fn with_allowed_relationships<S: AsRef<str>>(mut self, relationship_types: &[S]) -> Self {
+        self.allowed_relationships = relationship_types
+            .iter()
+            .map(|relationship_type| upper_snake_case(relationship_type.as_ref()))
+            .collect();
+        self
+    }
+
+    /// Extracts the graphs of the documents, concurrently.
+    pub async fn convert_to_graph_documents(
+        &self,
+        documents: &[Document],
+    ) -> Result<Vec<GraphDocument>, ChainError> {
+        try_join_all(
+            documents
+                .iter()
+                .map(|document| self.convert_to_graph_document(document)),
+        )
+        .await
+    }
+
+    pub async fn convert_to_graph_document(
+        &self,
+        document: &Document,
+    ) -> Result<GraphDocument, ChainError> {
+        let output = self
+            .chain
+            .invoke(prompt_args! {
+                "text" => document.page_content,
+                "node_types" => self.allowed_nodes.join(", "),
+                "relationship_types" => self.allowed_relationships.join(", "),
+            })
+            .await?;
+        Ok(self.parse_graph(&output, document)?)
+    }
+
+    fn is_allowed_node(&self, node: &Node) -> bool {
+        !node.id.is_empty()
+            && (self.allowed_nodes.is_empty() || self.allowed_nodes.contains(&node.node_type))
+    }
+
+    /// Parses the json answered by the LLM, which can be surrounded by text. The nodes of
+    /// the relationships are added to the nodes, and the types are normalized.
+    fn parse_graph(
+        &self,
+        output: &str,
+        document: &Document,
+    ) -> Result<GraphDocument, serde_json::Error> {
+        let start = output.find('{').unwrap_or_default();
+        let end = output.rfind('}').map(|end| end + 1).unwrap_or(output.len());
+        let graph: ExtractedGraph = serde_json::from_str(&output[start..end.max(start)])?;
+
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut add_node = |node: Node| {
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        };
+        for node in graph.nodes {
+            let node = Node::new(node.id.trim(), title_case(&node.node_type));
+            if self.is_allowed_node(&node) {
+                add_node(node);
+            }
+        }
+
+        let mut relationships = Vec::new();
+        for relationship in graph.relationships {
+            let relationship = Relationship::new(
+                Node::new(
+                    relationship.source.trim(),
+                    title_case(&relationship.source_type),
+                ),
+                upper_snake_case(&relationship.relationship_type),
+                Node::new(
+                    relationship.target.trim(),
+                    title_case(&relationship.target_type),
+                ),
+            );
+            let allowed = self.is_allowed_node(&relationship.source)
+                && self.is_allowed_node(&relationship.target)
+                && (self.allowed_relationships.is_empty()
+                    || self
+                        .allowed_relationships
+                        .contains(&relationship.relationship_type));
+            if allowed {
+                add_node(relationship.source.clone());
+                add_node(relationship.target.clone());
+                relationships.push(relationship);
+            }
+        }
+
+        Ok(GraphDocument {
+            nodes,
+            relationships,
+            source: document.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::openai::OpenAI;
+
+    #[test]
+    fn test_parse_graph() {
+        let transformer = LLMGraphTransformer::new(OpenAI::default())
+            .with_allowed_nodes(&["person", "award"])
+            .with_allowed_relationships(&["won"]);
+        let output = r#"Here is the graph:
+{"nodes": [{"id": "Marie Curie", "type": "person"}, {"id": "Paris", "type": "City"}],
+ "relationships": [
+    {"source": "Marie Curie", "source_type": "Person", "target": "Nobel Prize", "target_type": "Award", "type": "won"},
+    {"source": "Marie Curie", "source_type": "Person", "target": "Paris", "target_type": "City", "type": "LIVED_IN"}
+ ]}"#;
+        let graph = transformer
+            .parse_graph(output, &Document::new("Marie Curie won the Nobel Prize"))
+            .unwrap();
+        assert_eq!(
+            graph.nodes,
+            vec![
+                Node::new("Marie Curie", "Person"),
+                Node::new("Nobel Prize", "Award")
+            ]
+        );
+        assert_eq!(graph.relationships.len(), 1);
+        assert_eq!(graph.relationships[0].relationship_type, "WON");
+        assert_eq!(upper_snake_case("works at"), "WORKS_AT");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_llm_graph_transformer() {
+        let transformer = LLMGraphTransformer::new(OpenAI::default());
+        let graph = transformer
+            .convert_to_graph_document(&Document::new(
+                "Marie Curie was a physicist who won the Nobel Prize in 1903.",
+            ))
+            .await
+            .unwrap();
+        assert!(!graph.relationships.is_empty());
+    }
+}
@@ -0,0 +1,25 @@
+mod graph_document;
+pub use graph_document::*;
+
+mod graphstore_trait;
+pub use graphstore_trait::*;
+
+mod cypher;
+pub use cypher::*;
+
+mod error;
+pub use error::*;
+
+mod in_memory;
+pub use in_memory::*;
+
+mod llm_graph_transformer;
+pub use llm_graph_transformer::*;
+
+mod retriever;
+pub use retriever::*;
+
+#[cfg(feature = "neo4j")]
+mod neo4j;
+#[cfg(feature = "neo4j")]
+pub use neo4j::*;
@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use neo4rs::{query, BoltType, Graph, Query, Txn};
+use serde_json::{json, Value};
+
+use crate::indexing::document_key;
+
+use super::{
+    check_read_only_cypher, format_schema, GraphDocument, GraphStore, GraphStoreError, Node,
+    Relationship,
+};
+
+const DOCUMENT_LABEL: &str = "Document";
+const MENTIONS_RELATIONSHIP: &str = "MENTIONS";
+
+/// Cypher can't take the labels and relationship types as parameters, so they are quoted
+/// with only their alphanumeric characters.
+fn quote(label: &str) -> String {
+    let label: String = label
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    if label.is_empty() {
+        "`Node`".to_string()
+    } else {
+        format!("`{}`", label)
+    }
+}
+
+/// The rows of the query run in the transaction, which is left open.
+async fn txn_rows(txn: &mut Txn, q: Query) -> Result<Vec<HashMap<String, Value>>, GraphStoreError> {
+    let mut stream = txn.execute(q).await?;
+    let mut rows = Vec::new();
+    while let Some(row) = stream.next(txn.handle()).await? {
+        rows.push(row.to::<HashMap<String, Value>>()?);
+    }
+    Ok(rows)
+}
+
+fn string(row: &HashMap<String, Value>, key: &str) -> String {
+    row.get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn properties(row: &HashMap<String, Value>, key: &str) -> HashMap<String, Value> {
+    let mut properties: HashMap<String, Value> = row
+        .get(key)
+        .and_then(|properties| serde_json::from_value(properties.clone()).ok())
+        .unwrap_or_default();
+    properties.remove("id");
+    properties
+}
+
+/// Graph store keeping the graph in Neo4j. The nodes are stored with their type as label
+/// and their id in the `id` property. With `include_source` the documents are stored as
+/// `Document` nodes, with a `MENTIONS` relationship to their nodes.
+///
+/// # Example
+/// ```rust,ignore
+/// let store = Neo4jGraphStore::connect("127.0.0.1:7687", "neo4j", "password").await?;
+/// store.add_graph_documents(&graph_documents, true).await?;
+/// let rows = store
+///     .query("MATCH (p:Person)-[:WON]->(a) RETURN p.id AS person", HashMap::new())
+///     .await?;
+/// ```
+pub struct Neo4jGraphStore {
+    graph: Graph,
+}
+
+impl Neo4jGraphStore {
+    pub fn new(graph: Graph) -> Self {
+        Self { graph }
+    }
+
+    pub async fn connect(uri: &str, user: &str, password: &str) -> Result<Self, GraphStoreError> {
+        Ok(Self::new(Graph::new(uri, user, password).await?))
+    }
+
+    /// The id of the source document, stable across the builds, unlike `DefaultHasher`.
+    fn document_id(document: &GraphDocument) -> String {
+        document_key(&document.source)
+    }
+}
+
+fn bolt(value: Value) -> Result<BoltType, GraphStoreError> {
+    Ok(BoltType::try_from(value)?)
+}
+
+#[async_trait]
+impl GraphStore for Neo4jGraphStore {
+    async fn add_graph_documents(
+        &self,
+        documents: &[GraphDocument],
+        include_source: bool,
+    ) -> Result<(), GraphStoreError> {
+        let mut txn = self.graph.start_txn().await?;
+        for document in documents {
+            for node in &document.nodes {
+                txn.run(
+                    query(&format!(
+                        "MERGE (n:{} {{id: $id}}) SET n += $properties",
+                        quote(&node.node_type)
+                    ))
+                    .param("id", node.id.clone())
+                    .param("properties", bolt(json!(node.properties))?),
+                )
+                .await?;
+            }
+            for relationship in &document.relationships {
+                txn.run(
+                    query(&format!(
+                        "MERGE (s:{} {{id: $source}}) MERGE (t:{} {{id: $target}}) \
+                         MERGE (s)-[r:{}]->(t) SET r += $properties",
+                        quote(&relationship.source.node_type),
+                        quote(&relationship.target.node_type),
+                        quote(&relationship.relationship_type)
+                    ))
+                    .param("source", relationship.source.id.clone())
+                    .param("target", relationship.target.id.clone())
+                    .param("properties", bolt(json!(relationship.properties))?),
+                )
+                .await?;
+            }
+            if include_source {
+                let document_id = Self::document_id(document);
+                txn.run(
+                    query(&format!(
+                        "MERGE (d:{} {{id: $id}}) SET d.text = $text, d.metadata = $metadata",
+                        DOCUMENT_LABEL
+                    ))
+                    .param("id", document_id.clone())
+                    .param("text", document.source.page_content.clone())
+                    .param(
+                        "metadata",
+                        serde_json::to_string(&document.source.metadata)?,
+                    ),
+                )
+                .await?;
+                let mentioned = document.nodes.iter().chain(
+                    document
+                        .relationships
+                        .iter()
+                        .flat_map(|relationship| [&relationship.source, &relationship.target]),
+                );
+                for node in mentioned {
+                    txn.run(
+                        query(&format!(
+                            "MATCH (d:{} {{id: $document}}), (n:{} {{id: $id}}) \
+                             MERGE (d)-[:{}]->(n)",
+                            DOCUMENT_LABEL,
+                            quote(&node.node_type),
+                            MENTIONS_RELATIONSHIP
+                        ))
+                        .param("document", document_id.clone())
+                        .param("id", node.id.clone()),
+                    )
+                    .await?;
+                }
+            }
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        cypher: &str,
+        params: HashMap<String, Value>,
+    ) -> Result<Vec<HashMap<String, Value>>, GraphStoreError> {
+        let mut q = query(cypher);
+        for (key, value) in params {
+            q = q.param(&key, bolt(value)?);
+        }
+        let mut stream = self.graph.execute(q).await?;
+        let mut rows = Vec::new();
+        while let Some(row) = stream.next().await? {
+            rows.push(row.to::<HashMap<String, Value>>()?);
+        }
+        Ok(rows)
+    }
+
+    /// The query is also run in a transaction which is always rolled back, so whatever
+    /// `check_read_only_cypher` lets through can't write either. neo4rs 0.8 can't start
+    /// a transaction in the read access mode.
+    async fn query_read_only(
+        &self,
+        cypher: &str,
+        params: HashMap<String, Value>,
+    ) -> Result<Vec<HashMap<String, Value>>, GraphStoreError> {
+        check_read_only_cypher(cypher)?;
+        let mut q = query(cypher);
+        for (key, value) in params {
+            q = q.param(&key, bolt(value)?);
+        }
+        let mut txn = self.graph.start_txn().await?;
+        let rows = txn_rows(&mut txn, q).await;
+        txn.rollback().await?;
+        rows
+    }
+
+    async fn get_schema(&self) -> Result<String, GraphStoreError> {
+        let labels = self
+            .query(
+                "CALL db.labels() YIELD label RETURN label ORDER BY label",
+                HashMap::new(),
+            )
+            .await?;
+        let patterns = self
+            .query(
+                "MATCH (s)-[r]->(t) \
+                 RETURN DISTINCT labels(s)[0] AS source, type(r) AS type, labels(t)[0] AS target \
+                 ORDER BY source, type, target LIMIT 1000",
+                HashMap::new(),
+            )
+            .await?;
+        Ok(format_schema(
+            labels.iter().map(|row| string(row, "label")),
+            patterns.iter().map(|row| {
+                format!(
+                    "(:{})-[:{}]->(:{})",
+                    string(row, "source"),
+                    string(row, "type"),
+                    string(row, "target")
+                )
+            }),
+        ))
+    }
+
+    async fn search_nodes(&self, text: &str, limit: usize) -> Result<Vec<Node>, GraphStoreError> {
+        let rows = self
+            .query(
+                &format!(
+                    "MATCH (n) WHERE n.id IS NOT NULL AND NOT n:{} \
+                     AND toLower($text) CONTAINS toLower(n.id) \
+                     RETURN n.id AS id, labels(n)[0] AS type, properties(n) AS properties \
+                     ORDER BY size(n.id) DESC LIMIT $limit",
+                    DOCUMENT_LABEL
+                ),
+                HashMap::from([
+                    ("text".to_string(), json!(text)),
+                    ("limit".to_string(), json!(limit)),
+                ]),
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                Node::new(string(row, "id"), string(row, "type"))
+                    .with_properties(properties(row, "properties"))
+            })
+            .collect())
+    }
+
+    async fn neighbors(
+        &self,
+        node_ids: &[String],
+        limit: usize,
+    ) -> Result<Vec<Relationship>, GraphStoreError> {
+        let rows = self
+            .query(
+                &format!(
+                    "MATCH (s)-[r]->(t) WHERE (s.id IN $ids OR t.id IN $ids) AND type(r) <> '{}' \
+                     RETURN s.id AS source, labels(s)[0] AS source_type, type(r) AS type, \
+                     t.id AS target, labels(t)[0] AS target_type, properties(r) AS properties \
+                     LIMIT $limit",
+                    MENTIONS_RELATIONSHIP
+                ),
+                HashMap::from([
+                    ("ids".to_string(), json!(node_ids)),
+                    ("limit".to_string(), json!(limit)),
+                ]),
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let mut relationship = Relationship::new(
+                    Node::new(string(row, "source"), string(row, "source_type")),
+                    string(row, "type"),
+                    Node::new(string(row, "target"), string(row, "target_type")),
+                );
+                relationship.properties = properties(row, "properties");
+                relationship
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::Document;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("Person"), "`Person`");
+        assert_eq!(
+            quote("WORKS_AT`) DETACH DELETE n //"),
+            "`WORKS_ATDETACHDELETEn`"
+        );
+        assert_eq!(quote(""), "`Node`");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_neo4j_graph_store() {
+        let store = Neo4jGraphStore::connect("127.0.0.1:7687", "neo4j", "password")
+            .await
+            .unwrap();
+        let curie = Node::new("Marie Curie", "Person");
+        store
+            .add_graph_documents(
+                &[GraphDocument {
+                    nodes: vec![curie.clone()],
+                    relationships: vec![Relationship::new(
+                        curie,
+                        "WON",
+                        Node::new("Nobel Prize", "Award"),
+                    )],
+                    source: Document::new("Marie Curie won the Nobel Prize"),
+                }],
+                true,
+            )
+            .await
+            .unwrap();
+
+        let nodes = store.search_nodes("Who is Marie Curie?", 5).await.unwrap();
+        assert_eq!(nodes[0].id, "Marie Curie");
+        let relationships = store
+            .neighbors(&["Marie Curie".to_string()], 5)
+            .await
+            .unwrap();
+        assert_eq!(relationships[0].to_text(), "Marie Curie WON Nobel Prize");
+        assert!(store
+            .get_schema()
+            .await
+            .unwrap()
+            .contains("(:Person)-[:WON]->(:Award)"));
+    }
+}
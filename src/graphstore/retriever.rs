@@ -0,0 +1,103 @@
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::schemas::{Document, Retriever};
+
+use super::GraphStore;
+
+/// Retrieves the relationships of the entities mentioned in the query from a
+/// `GraphStore`, one document per relationship, e.g. `Marie Curie WON Nobel Prize`. Combine
+/// it with a vector store retriever for graph-augmented retrieval.
+pub struct GraphRetriever {
+    store: Box<dyn GraphStore>,
+    max_nodes: usize,
+    max_relationships: usize,
+}
+
+impl GraphRetriever {
+    pub fn new<G: Into<Box<dyn GraphStore>>>(store: G) -> Self {
+        Self {
+            store: store.into(),
+            max_nodes: 5,
+            max_relationships: 20,
+        }
+    }
+
+    /// The maximum number of entities of the query, 5 by default.
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// The maximum number of relationships retrieved, 20 by default.
+    pub fn with_max_relationships(mut self, max_relationships: usize) -> Self {
+        self.max_relationships = max_relationships;
+        self
+    }
+}
+
+#[async_trait]
+impl Retriever for GraphRetriever {
+    async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        let nodes = self.store.search_nodes(query, self.max_nodes).await?;
+        if nodes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<String> = nodes.into_iter().map(|node| node.id).collect();
+        let relationships = self.store.neighbors(&ids, self.max_relationships).await?;
+        Ok(relationships
+            .iter()
+            .map(|relationship| {
+                Document::new(relationship.to_text()).with_metadata(HashMap::from([
+                    ("source".to_string(), json!(relationship.source.id)),
+                    ("target".to_string(), json!(relationship.target.id)),
+                    (
+                        "relationship_type".to_string(),
+                        json!(relationship.relationship_type),
+                    ),
+                ]))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphstore::{GraphDocument, InMemoryGraphStore, Node, Relationship};
+
+    #[tokio::test]
+    async fn test_graph_retriever() {
+        let store = InMemoryGraphStore::new();
+        let curie = Node::new("Marie Curie", "Person");
+        store
+            .add_graph_documents(
+                &[GraphDocument {
+                    nodes: vec![],
+                    relationships: vec![
+                        Relationship::new(curie.clone(), "WON", Node::new("Nobel Prize", "Award")),
+                        Relationship::new(curie, "BORN_IN", Node::new("Warsaw", "City")),
+                    ],
+                    source: Document::new(""),
+                }],
+                false,
+            )
+            .await
+            .unwrap();
+
+        let retriever = GraphRetriever::new(store);
+        let documents = retriever
+            .get_relevant_documents("Where was Marie Curie born?")
+            .await
+            .unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1].page_content, "Marie Curie BORN_IN Warsaw");
+        assert!(retriever
+            .get_relevant_documents("Who is Ada Lovelace?")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
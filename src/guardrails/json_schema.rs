@@ -0,0 +1,291 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::chain::ChainError;
+
+use super::Validator;
+
+/// Fails on the texts that are not json conforming to a JSON Schema, e.g. the structured
+/// outputs of a chain.
+///
+/// Supports the usual subset of the keywords: `type`, `enum`, `const`, `required`,
+/// `properties`, `additionalProperties: false`, `items`, `minItems`, `maxItems`,
+/// `minLength`, `maxLength`, `minimum`, `maximum`, `anyOf`, `oneOf`, `allOf` and the
+/// `$ref`s to the definitions of the schema. The json can be wrapped in a markdown code
+/// block.
+pub struct JsonSchemaValidator {
+    schema: Value,
+}
+
+impl JsonSchemaValidator {
+    pub fn new(schema: Value) -> Self {
+        Self { schema }
+    }
+}
+
+#[async_trait]
+impl Validator for JsonSchemaValidator {
+    fn name(&self) -> String {
+        "json_schema".to_string()
+    }
+
+    async fn validate(&self, text: &str) -> Result<Option<String>, ChainError> {
+        let value: Value = match serde_json::from_str(strip_code_block(text)) {
+            Ok(value) => value,
+            Err(e) => return Ok(Some(format!("Is not valid json: {}", e))),
+        };
+        Ok(validate_json(&self.schema, &value))
+    }
+}
+
+fn strip_code_block(text: &str) -> &str {
+    let text = text.trim();
+    match text.strip_prefix("```") {
+        Some(block) => block
+            .trim_start_matches("json")
+            .trim_end_matches("```")
+            .trim(),
+        None => text,
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Describes the first place where `value` doesn't conform to `schema`, with its json
+/// path.
+pub(crate) fn validate_json(schema: &Value, value: &Value) -> Option<String> {
+    validate_value(schema, schema, value, "$")
+}
+
+fn validate_value(root: &Value, schema: &Value, value: &Value, path: &str) -> Option<String> {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+        {
+            Some(referenced) => {
+                if let Some(violation) = validate_value(root, referenced, value, path) {
+                    return Some(violation);
+                }
+            }
+            None => return Some(format!("{} refers to the unknown {}", path, reference)),
+        }
+    }
+    if let Some(Value::Array(schemas)) = schema.get("allOf") {
+        if let Some(violation) = schemas
+            .iter()
+            .find_map(|schema| validate_value(root, schema, value, path))
+        {
+            return Some(violation);
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(Value::Array(schemas)) = schema.get(keyword) {
+            let violations = schemas
+                .iter()
+                .map(|schema| validate_value(root, schema, value, path))
+                .collect::<Vec<_>>();
+            let matches = violations.iter().filter(|v| v.is_none()).count();
+            if matches == 0 {
+                return violations.into_iter().flatten().next();
+            }
+            if keyword == "oneOf" && matches > 1 {
+                return Some(format!("{} matches more than one schema of oneOf", path));
+            }
+        }
+    }
+    match schema.get("type") {
+        Some(Value::String(expected)) if !type_matches(expected, value) => {
+            return Some(format!("{} is not of type {}", path, expected));
+        }
+        Some(Value::Array(expected))
+            if !expected
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|expected| type_matches(expected, value)) =>
+        {
+            return Some(format!(
+                "{} is not of the types {}",
+                path,
+                Value::from(expected.clone())
+            ));
+        }
+        _ => {}
+    }
+    if let Some(Value::Array(values)) = schema.get("enum") {
+        if !values.contains(value) {
+            return Some(format!(
+                "{} is not one of {}",
+                path,
+                Value::from(values.clone())
+            ));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            return Some(format!("{} is not {}", path, constant));
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                if let Some(missing) = required
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .find(|key| !object.contains_key(*key))
+                {
+                    return Some(format!("{} is missing the property {}", path, missing));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, property) in object {
+                let property_path = format!("{}.{}", path, key);
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(property_schema) => {
+                        if let Some(violation) =
+                            validate_value(root, property_schema, property, &property_path)
+                        {
+                            return Some(violation);
+                        }
+                    }
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        return Some(format!("{} is not allowed", property_path));
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    return Some(format!("{} has less than {} items", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if items.len() as u64 > max {
+                    return Some(format!("{} has more than {} items", path, max));
+                }
+            }
+            if let Some(items_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    if let Some(violation) =
+                        validate_value(root, items_schema, item, &format!("{}[{}]", path, i))
+                    {
+                        return Some(violation);
+                    }
+                }
+            }
+        }
+        Value::String(string) => {
+            let chars = string.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if chars < min {
+                    return Some(format!("{} is shorter than {} characters", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if chars > max {
+                    return Some(format!("{} is longer than {} characters", path, max));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < min {
+                    return Some(format!("{} is less than {}", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > max {
+                    return Some(format!("{} is greater than {}", path, max));
+                }
+            }
+        }
+        _ => {}
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_json_schema_validator() {
+        let validator = JsonSchemaValidator::new(json!({
+            "type": "object",
+            "required": ["name", "tags"],
+            "additionalProperties": false,
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "age": {"type": "integer", "minimum": 0},
+                "tags": {"type": "array", "items": {"enum": ["a", "b"]}},
+            },
+        }));
+
+        let valid = "```json\n{\"name\": \"Ana\", \"age\": 30, \"tags\": [\"a\"]}\n```";
+        assert_eq!(validator.validate(valid).await.unwrap(), None);
+
+        for (text, expected) in [
+            ("not json", "Is not valid json"),
+            (r#"{"name": "Ana"}"#, "$ is missing the property tags"),
+            (
+                r#"{"name": "Ana", "tags": ["c"]}"#,
+                "$.tags[0] is not one of",
+            ),
+            (
+                r#"{"name": "Ana", "tags": [], "age": 1.5}"#,
+                "$.age is not of type integer",
+            ),
+            (
+                r#"{"name": "Ana", "tags": [], "city": "Lima"}"#,
+                "$.city is not allowed",
+            ),
+        ] {
+            let violation = validator.validate(text).await.unwrap().unwrap();
+            assert!(violation.starts_with(expected), "{}", violation);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_schema_validator_refs() {
+        let validator = JsonSchemaValidator::new(json!({
+            "type": "object",
+            "properties": {
+                "address": {"anyOf": [{"$ref": "#/$defs/Address"}, {"type": "null"}]},
+            },
+            "$defs": {
+                "Address": {
+                    "type": "object",
+                    "required": ["city"],
+                    "properties": {"city": {"type": "string"}},
+                },
+            },
+        }));
+
+        for valid in [r#"{"address": null}"#, r#"{"address": {"city": "Lima"}}"#] {
+            assert_eq!(validator.validate(valid).await.unwrap(), None);
+        }
+        let violation = validator
+            .validate(r#"{"address": {"city": 1}}"#)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(violation, "$.address.city is not of type string");
+    }
+}
@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+
+use crate::chain::ChainError;
+
+use super::Validator;
+
+/// Fails on the texts longer than a number of characters.
+pub struct MaxLength {
+    max_chars: usize,
+}
+
+impl MaxLength {
+    pub fn new(max_chars: usize) -> Self {
+        Self { max_chars }
+    }
+}
+
+#[async_trait]
+impl Validator for MaxLength {
+    fn name(&self) -> String {
+        "max_length".to_string()
+    }
+
+    async fn validate(&self, text: &str) -> Result<Option<String>, ChainError> {
+        let chars = text.chars().count();
+        Ok((chars > self.max_chars).then(|| {
+            format!(
+                "Has {} characters, the maximum is {}",
+                chars, self.max_chars
+            )
+        }))
+    }
+}
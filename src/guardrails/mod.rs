@@ -0,0 +1,23 @@
+mod validator;
+pub use validator::*;
+
+mod patterns;
+pub use patterns::*;
+
+mod length;
+pub use length::*;
+
+mod topic;
+pub use topic::*;
+
+mod json_schema;
+pub use json_schema::*;
+
+mod guard;
+pub use guard::*;
+
+mod injection_classifier;
+pub use injection_classifier::*;
+
+mod sanitizer;
+pub use sanitizer::*;
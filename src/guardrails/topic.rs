@@ -0,0 +1,147 @@
+use async_trait::async_trait;
+
+use crate::{
+    chain::{Chain, ChainError, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt_args, template_jinja2,
+};
+
+use super::Validator;
+
+const DEFAULT_TOPIC_TEMPLATE: &str = r#"Classify the text in one of the following topics:
+{{topics}}
+
+Text: {{text}}
+
+Answer only with the name of the topic, or NONE if the text is not about any of them."#;
+
+/// Classifies the texts by topic with an LLM. Fails on the texts about a denied topic, and,
+/// when allowed topics are set, on the texts not about any of them.
+///
+/// # Example
+/// ```rust,ignore
+/// let validator = TopicClassifier::new(OpenAI::default())
+///     .with_allowed_topic("billing")
+///     .with_allowed_topic("shipping")
+///     .with_denied_topic("politics");
+/// ```
+pub struct TopicClassifier {
+    chain: LLMChain,
+    allowed_topics: Vec<String>,
+    denied_topics: Vec<String>,
+}
+
+impl TopicClassifier {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L) -> Self {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(DEFAULT_TOPIC_TEMPLATE, "topics", "text"))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        Self {
+            chain,
+            allowed_topics: Vec::new(),
+            denied_topics: Vec::new(),
+        }
+    }
+
+    pub fn with_allowed_topic<S: Into<String>>(mut self, topic: S) -> Self {
+        self.allowed_topics.push(topic.into());
+        self
+    }
+
+    pub fn with_denied_topic<S: Into<String>>(mut self, topic: S) -> Self {
+        self.denied_topics.push(topic.into());
+        self
+    }
+
+    fn topics(&self) -> impl Iterator<Item = &String> {
+        self.allowed_topics.iter().chain(self.denied_topics.iter())
+    }
+
+    /// Finds the topic answered by the LLM, `None` when it answered NONE or an unknown topic.
+    fn parse_topic(&self, output: &str) -> Option<&String> {
+        let output = output
+            .trim()
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        self.topics()
+            .find(|topic| topic.to_lowercase() == output)
+            .or_else(|| {
+                self.topics()
+                    .find(|topic| output.contains(&topic.to_lowercase()))
+            })
+    }
+
+    /// Whether a text classified in `topic` is valid.
+    fn check(&self, topic: Option<&String>) -> Option<String> {
+        match topic {
+            Some(topic) if self.denied_topics.contains(topic) => {
+                Some(format!("Is about the denied topic {}", topic))
+            }
+            None if !self.allowed_topics.is_empty() => Some(format!(
+                "Is not about the allowed topics {}",
+                self.allowed_topics.join(", ")
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl Validator for TopicClassifier {
+    fn name(&self) -> String {
+        "topic_classifier".to_string()
+    }
+
+    async fn validate(&self, text: &str) -> Result<Option<String>, ChainError> {
+        let topics: Vec<String> = self.topics().map(|topic| format!("- {}", topic)).collect();
+        let output = self
+            .chain
+            .invoke(prompt_args! {
+                "topics" => topics.join("\n"),
+                "text" => text,
+            })
+            .await?;
+        Ok(self.check(self.parse_topic(&output)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::openai::OpenAI;
+
+    #[test]
+    fn test_parse_topic() {
+        let validator = TopicClassifier::new(OpenAI::default())
+            .with_allowed_topic("billing")
+            .with_denied_topic("politics");
+
+        let topic = validator.parse_topic("Billing.");
+        assert_eq!(topic.map(String::as_str), Some("billing"));
+        assert!(validator.check(topic).is_none());
+
+        let topic = validator.parse_topic("The topic is politics");
+        assert_eq!(topic.map(String::as_str), Some("politics"));
+        assert!(validator.check(topic).is_some());
+
+        let topic = validator.parse_topic("NONE");
+        assert!(topic.is_none());
+        assert!(validator.check(topic).is_some());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_topic_classifier() {
+        let validator = TopicClassifier::new(OpenAI::default())
+            .with_allowed_topic("billing")
+            .with_denied_topic("politics");
+        assert!(validator
+            .validate("Who should I vote for in the next election?")
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
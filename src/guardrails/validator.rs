@@ -0,0 +1,83 @@
+use std::fmt;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::chain::ChainError;
+
+/// Checks a text, e.g. the input or the output of a chain, see `Guard`.
+#[async_trait]
+pub trait Validator: Send + Sync {
+    fn name(&self) -> String;
+
+    /// Describes the problem when `text` is invalid, `None` when it is valid.
+    ///
+    /// A validator can also fail with `ChainError::ContentBlocked`, which the `Guard`
+    /// returns as it is with the `Block` action instead of a `GuardrailViolation`.
+    async fn validate(&self, text: &str) -> Result<Option<String>, ChainError>;
+
+    /// Fixes an invalid `text` without an LLM, e.g. by masking the denied content. `None`
+    /// when the validator can't fix it, then the `Guard` uses its fixer.
+    async fn fix(&self, _text: &str) -> Result<Option<String>, ChainError> {
+        Ok(None)
+    }
+}
+
+/// What a `Guard` does when a validator fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardAction {
+    /// Fails the call with `ChainError::GuardrailViolation`.
+    Block,
+    /// Fixes the text with the validator, or asks the fixer LLM to rewrite it.
+    Fix,
+    /// Logs a warning and continues.
+    Log,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardStage {
+    Input,
+    Output,
+}
+
+/// A failed validation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    pub validator: String,
+    pub stage: GuardStage,
+    /// The input variable with the text, `None` for the output.
+    pub key: Option<String>,
+    pub action: GuardAction,
+    pub message: String,
+}
+
+/// The violations found by a `Guard` during a call.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GuardReport {
+    pub violations: Vec<Violation>,
+}
+
+impl GuardReport {
+    pub fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.violations
+            .iter()
+            .any(|violation| violation.action == GuardAction::Block)
+    }
+}
+
+impl fmt::Display for GuardReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let violations: Vec<_> = self
+            .violations
+            .iter()
+            .map(|violation| format!("{}: {}", violation.validator, violation.message))
+            .collect();
+        write!(f, "{}", violations.join("; "))
+    }
+}
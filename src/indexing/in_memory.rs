@@ -0,0 +1,70 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+
+use super::{Record, RecordManager, RecordManagerError};
+
+/// Record manager keeping the records in memory, for tests and single runs. Clones share
+/// the same records.
+#[derive(Clone, Default)]
+pub struct InMemoryRecordManager {
+    records: Arc<RwLock<HashMap<String, Record>>>,
+}
+
+impl InMemoryRecordManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl RecordManager for InMemoryRecordManager {
+    async fn get(&self, keys: &[String]) -> Result<Vec<Option<Record>>, RecordManagerError> {
+        let records = self.records.read().unwrap_or_else(|e| e.into_inner());
+        Ok(keys.iter().map(|key| records.get(key).cloned()).collect())
+    }
+
+    async fn update(&self, records: Vec<Record>) -> Result<(), RecordManagerError> {
+        self.records
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(
+                records
+                    .into_iter()
+                    .map(|record| (record.key.clone(), record)),
+            );
+        Ok(())
+    }
+
+    async fn list(&self, group_ids: Option<&[String]>) -> Result<Vec<Record>, RecordManagerError> {
+        let records = self.records.read().unwrap_or_else(|e| e.into_inner());
+        Ok(records
+            .values()
+            .filter(|record| match (group_ids, &record.group_id) {
+                (None, _) => true,
+                (Some(group_ids), Some(group_id)) => group_ids.contains(group_id),
+                (Some(_), None) => false,
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn delete(&self, keys: &[String]) -> Result<(), RecordManagerError> {
+        let mut records = self.records.write().unwrap_or_else(|e| e.into_inner());
+        for key in keys {
+            records.remove(key);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    schemas::Document,
+    vectorstore::{VecStoreOptions, VectorStore},
+};
+
+use super::{Record, RecordManager};
+
+/// Which of the previously indexed documents `index` deletes from the vector store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CleanupMode {
+    /// Only adds the new documents.
+    #[default]
+    None,
+    /// Deletes the documents of the indexed sources which are not indexed anymore, e.g.
+    /// the previous versions of the updated documents. The sources missing from the run
+    /// are kept. Requires the `source_id_key`.
+    Incremental,
+    /// Deletes all the documents which are not indexed in the run, so the run must index
+    /// the whole corpus.
+    Full,
+}
+
+/// The options of `index`.
+///
+/// # Usage
+/// ```rust,ignore
+/// let options = IndexingOptions::new()
+///     .with_cleanup(CleanupMode::Incremental)
+///     .with_source_id_key("source")
+///     .with_batch_size(50);
+/// ```
+#[derive(Clone)]
+pub struct IndexingOptions {
+    pub cleanup: CleanupMode,
+    pub source_id_key: Option<String>,
+    pub batch_size: usize,
+    pub vector_store_options: VecStoreOptions,
+}
+
+impl Default for IndexingOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IndexingOptions {
+    pub fn new() -> Self {
+        IndexingOptions {
+            cleanup: CleanupMode::None,
+            source_id_key: None,
+            batch_size: 100,
+            vector_store_options: VecStoreOptions::default(),
+        }
+    }
+
+    pub fn with_cleanup(mut self, cleanup: CleanupMode) -> Self {
+        self.cleanup = cleanup;
+        self
+    }
+
+    /// The metadata key of the source of the documents, e.g. the path of the file they
+    /// were loaded from.
+    pub fn with_source_id_key<S: Into<String>>(mut self, source_id_key: S) -> Self {
+        self.source_id_key = Some(source_id_key.into());
+        self
+    }
+
+    /// The number of documents added to the vector store at once. Default: 100
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// The options of the calls to the vector store, e.g. its namespace.
+    pub fn with_vector_store_options(mut self, vector_store_options: VecStoreOptions) -> Self {
+        self.vector_store_options = vector_store_options;
+        self
+    }
+}
+
+/// The changes of the vector store made by `index`. An updated document is added, and its
+/// previous version deleted by the cleanup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IndexingResult {
+    pub num_added: usize,
+    /// The documents already indexed, including the duplicates of the run.
+    pub num_skipped: usize,
+    pub num_deleted: usize,
+}
+
+/// The key of a document in the record manager, the hash of its content and metadata.
+pub fn document_key(document: &Document) -> String {
+    let metadata: BTreeMap<&String, &Value> = document.metadata.iter().collect();
+    let mut hasher = Sha256::new();
+    hasher.update(document.page_content.as_bytes());
+    hasher.update([0]);
+    hasher.update(
+        serde_json::to_string(&metadata)
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Indexes the documents in the vector store, skipping the ones the record manager knows
+/// are already indexed, so repeated ingestion runs only embed the new and the changed
+/// documents. The outdated documents are then deleted according to the cleanup mode,
+/// which needs a vector store supporting `delete`.
+///
+/// # Example
+/// ```rust,ignore
+/// let record_manager = SqliteRecordManager::new(pool);
+/// record_manager.initialize().await?;
+/// let options = IndexingOptions::new()
+///     .with_cleanup(CleanupMode::Incremental)
+///     .with_source_id_key("source");
+/// let result = index(&documents, &record_manager, &store, &options).await?;
+/// println!("{} added, {} deleted", result.num_added, result.num_deleted);
+/// ```
+pub async fn index(
+    documents: &[Document],
+    record_manager: &dyn RecordManager,
+    vector_store: &dyn VectorStore,
+    options: &IndexingOptions,
+) -> Result<IndexingResult, Box<dyn Error>> {
+    if options.cleanup == CleanupMode::Incremental && options.source_id_key.is_none() {
+        return Err("The incremental cleanup requires a source_id_key".into());
+    }
+
+    let mut result = IndexingResult::default();
+    let mut indexed_keys = HashSet::new();
+    let mut source_ids = HashSet::new();
+    for batch in documents.chunks(options.batch_size.max(1)) {
+        let mut new_documents = Vec::new();
+        let mut new_records = Vec::new();
+        for document in batch {
+            let group_id = match &options.source_id_key {
+                Some(source_id_key) => Some(source_id(document, source_id_key)?),
+                None => None,
+            };
+            let key = document_key(document);
+            if !indexed_keys.insert(key.clone()) {
+                result.num_skipped += 1;
+                continue;
+            }
+            if let Some(group_id) = &group_id {
+                source_ids.insert(group_id.clone());
+            }
+            new_documents.push(document.clone());
+            new_records.push((key, group_id));
+        }
+
+        let keys: Vec<String> = new_records.iter().map(|(key, _)| key.clone()).collect();
+        let existing = record_manager.get(&keys).await?;
+        let (new_documents, new_records): (Vec<Document>, Vec<(String, Option<String>)>) =
+            new_documents
+                .into_iter()
+                .zip(new_records)
+                .zip(existing)
+                .filter_map(|(new, existing)| existing.is_none().then_some(new))
+                .unzip();
+        result.num_skipped += keys.len() - new_documents.len();
+        if new_documents.is_empty() {
+            continue;
+        }
+
+        let ids = vector_store
+            .add_documents(&new_documents, &options.vector_store_options)
+            .await?;
+        if ids.len() != new_documents.len() {
+            return Err("The vector store returned a wrong number of ids".into());
+        }
+        result.num_added += ids.len();
+        record_manager
+            .update(
+                new_records
+                    .into_iter()
+                    .zip(ids)
+                    .map(|((key, group_id), id)| Record { key, id, group_id })
+                    .collect(),
+            )
+            .await?;
+    }
+
+    let outdated = match options.cleanup {
+        CleanupMode::None => return Ok(result),
+        CleanupMode::Incremental => {
+            let source_ids: Vec<String> = source_ids.into_iter().collect();
+            record_manager.list(Some(&source_ids)).await?
+        }
+        CleanupMode::Full => record_manager.list(None).await?,
+    };
+    let (keys, ids): (Vec<String>, Vec<String>) = outdated
+        .into_iter()
+        .filter(|record| !indexed_keys.contains(&record.key))
+        .map(|record| (record.key, record.id))
+        .unzip();
+    if !ids.is_empty() {
+        vector_store
+            .delete(&ids, &options.vector_store_options)
+            .await?;
+        record_manager.delete(&keys).await?;
+    }
+    result.num_deleted = ids.len();
+    Ok(result)
+}
+
+fn source_id(document: &Document, source_id_key: &str) -> Result<String, Box<dyn Error>> {
+    match document.metadata.get(source_id_key) {
+        Some(Value::String(source_id)) => Ok(source_id.clone()),
+        Some(Value::Null) | None => Err(format!(
+            "The document has no source id in its metadata key {}",
+            source_id_key
+        )
+        .into()),
+        Some(source_id) => Ok(source_id.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        embedding::FakeEmbedder, indexing::InMemoryRecordManager, vectorstore::InMemoryVectorStore,
+    };
+
+    fn document(content: &str, source: &str) -> Document {
+        Document::new(content).with_metadata(HashMap::from([("source".to_string(), json!(source))]))
+    }
+
+    #[tokio::test]
+    async fn test_index() {
+        let store = InMemoryVectorStore::new(FakeEmbedder::default());
+        let record_manager = InMemoryRecordManager::new();
+        let options = IndexingOptions::new()
+            .with_cleanup(CleanupMode::Incremental)
+            .with_source_id_key("source")
+            .with_batch_size(2);
+
+        let documents = vec![
+            document("a1", "a.txt"),
+            document("a2", "a.txt"),
+            document("b1", "b.txt"),
+            document("b1", "b.txt"),
+        ];
+        let result = index(&documents, &record_manager, &store, &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            IndexingResult {
+                num_added: 3,
+                num_skipped: 1,
+                num_deleted: 0,
+            }
+        );
+        assert_eq!(store.len().await, 3);
+
+        // a.txt changed, b.txt isn't in the run and is kept.
+        let documents = vec![document("a1", "a.txt"), document("a2 updated", "a.txt")];
+        let result = index(&documents, &record_manager, &store, &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            IndexingResult {
+                num_added: 1,
+                num_skipped: 1,
+                num_deleted: 1,
+            }
+        );
+        assert_eq!(store.len().await, 3);
+        assert_eq!(record_manager.len(), 3);
+
+        let options = options.with_cleanup(CleanupMode::Full);
+        let result = index(&documents, &record_manager, &store, &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            IndexingResult {
+                num_added: 0,
+                num_skipped: 2,
+                num_deleted: 1,
+            }
+        );
+        assert_eq!(store.len().await, 2);
+
+        let error = index(
+            &[Document::new("no source")],
+            &record_manager,
+            &store,
+            &options,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The document has no source id in its metadata key source"
+        );
+    }
+}
@@ -0,0 +1,21 @@
+mod record_manager;
+pub use record_manager::*;
+
+mod error;
+pub use error::*;
+
+mod in_memory;
+pub use in_memory::*;
+
+mod index;
+pub use index::*;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
+
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "postgres")]
+pub use postgres::*;
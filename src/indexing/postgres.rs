@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use sqlx::{postgres::PgRow, Pool, Postgres, Row};
+
+use super::{Record, RecordManager, RecordManagerError};
+
+/// Record manager keeping the records in a Postgres table.
+pub struct PostgresRecordManager {
+    pool: Pool<Postgres>,
+    table: String,
+}
+
+impl PostgresRecordManager {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self {
+            pool,
+            table: "records".to_string(),
+        }
+    }
+
+    pub fn with_table<S: Into<String>>(mut self, table: S) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Creates the table if it doesn't exist.
+    pub async fn initialize(&self) -> Result<(), RecordManagerError> {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (key TEXT PRIMARY KEY, id TEXT NOT NULL, group_id TEXT)",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS {0}_group_id ON {0} (group_id)",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+fn record_from_row(row: &PgRow) -> Result<Record, RecordManagerError> {
+    Ok(Record {
+        key: row.try_get("key")?,
+        id: row.try_get("id")?,
+        group_id: row.try_get("group_id")?,
+    })
+}
+
+#[async_trait]
+impl RecordManager for PostgresRecordManager {
+    async fn get(&self, keys: &[String]) -> Result<Vec<Option<Record>>, RecordManagerError> {
+        let rows = sqlx::query(&format!(
+            "SELECT key, id, group_id FROM {} WHERE key = ANY($1)",
+            self.table
+        ))
+        .bind(keys)
+        .fetch_all(&self.pool)
+        .await?;
+        let records = rows
+            .iter()
+            .map(record_from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(keys
+            .iter()
+            .map(|key| records.iter().find(|record| &record.key == key).cloned())
+            .collect())
+    }
+
+    async fn update(&self, records: Vec<Record>) -> Result<(), RecordManagerError> {
+        let query = format!(
+            "INSERT INTO {} (key, id, group_id) VALUES ($1, $2, $3) \
+             ON CONFLICT (key) DO UPDATE SET id = EXCLUDED.id, group_id = EXCLUDED.group_id",
+            self.table
+        );
+        let mut tx = self.pool.begin().await?;
+        for record in records {
+            sqlx::query(&query)
+                .bind(record.key)
+                .bind(record.id)
+                .bind(record.group_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list(&self, group_ids: Option<&[String]>) -> Result<Vec<Record>, RecordManagerError> {
+        let rows = match group_ids {
+            None => {
+                sqlx::query(&format!("SELECT key, id, group_id FROM {}", self.table))
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+            Some(group_ids) => {
+                sqlx::query(&format!(
+                    "SELECT key, id, group_id FROM {} WHERE group_id = ANY($1)",
+                    self.table
+                ))
+                .bind(group_ids)
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+        rows.iter().map(record_from_row).collect()
+    }
+
+    async fn delete(&self, keys: &[String]) -> Result<(), RecordManagerError> {
+        sqlx::query(&format!("DELETE FROM {} WHERE key = ANY($1)", self.table))
+            .bind(keys)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+
+use super::RecordManagerError;
+
+/// A document indexed in a vector store by `index`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The hash of the content and the metadata of the document, see `document_key`.
+    pub key: String,
+    /// The id of the document in the vector store, returned by `add_documents`.
+    pub id: String,
+    /// The source of the document, from its metadata, used by the incremental cleanup.
+    pub group_id: Option<String>,
+}
+
+/// Keeps track of the documents indexed in a vector store, so that `index` only adds the
+/// new documents and deletes the outdated ones across ingestion runs.
+///
+/// A record manager should be used with a single vector store and namespace.
+#[async_trait]
+pub trait RecordManager: Send + Sync {
+    /// Returns the records with the given keys, `None` for the keys that are not stored.
+    async fn get(&self, keys: &[String]) -> Result<Vec<Option<Record>>, RecordManagerError>;
+
+    /// Stores the records, replacing the ones already stored with the same keys.
+    async fn update(&self, records: Vec<Record>) -> Result<(), RecordManagerError>;
+
+    /// Returns the records of the given groups, or all the records if `group_ids` is
+    /// `None`.
+    async fn list(&self, group_ids: Option<&[String]>) -> Result<Vec<Record>, RecordManagerError>;
+
+    /// Deletes the records with the given keys, ignoring the keys that are not stored.
+    async fn delete(&self, keys: &[String]) -> Result<(), RecordManagerError>;
+}
+
+impl<R> From<R> for Box<dyn RecordManager>
+where
+    R: RecordManager + 'static,
+{
+    fn from(record_manager: R) -> Self {
+        Box::new(record_manager)
+    }
+}
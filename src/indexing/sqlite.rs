@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite};
+
+use super::{Record, RecordManager, RecordManagerError};
+
+/// Record manager keeping the records in a SQLite table.
+pub struct SqliteRecordManager {
+    pool: Pool<Sqlite>,
+    table: String,
+}
+
+impl SqliteRecordManager {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self {
+            pool,
+            table: "records".to_string(),
+        }
+    }
+
+    pub fn with_table<S: Into<String>>(mut self, table: S) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Creates the table if it doesn't exist.
+    pub async fn initialize(&self) -> Result<(), RecordManagerError> {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (key TEXT PRIMARY KEY, id TEXT NOT NULL, group_id TEXT)",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS {0}_group_id ON {0} (group_id)",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+fn record_from_row(row: &SqliteRow) -> Result<Record, RecordManagerError> {
+    Ok(Record {
+        key: row.try_get("key")?,
+        id: row.try_get("id")?,
+        group_id: row.try_get("group_id")?,
+    })
+}
+
+#[async_trait]
+impl RecordManager for SqliteRecordManager {
+    async fn get(&self, keys: &[String]) -> Result<Vec<Option<Record>>, RecordManagerError> {
+        let query = format!("SELECT key, id, group_id FROM {} WHERE key = ?", self.table);
+        let mut records = Vec::with_capacity(keys.len());
+        for key in keys {
+            let row = sqlx::query(&query)
+                .bind(key)
+                .fetch_optional(&self.pool)
+                .await?;
+            records.push(row.as_ref().map(record_from_row).transpose()?);
+        }
+        Ok(records)
+    }
+
+    async fn update(&self, records: Vec<Record>) -> Result<(), RecordManagerError> {
+        let query = format!(
+            "INSERT INTO {} (key, id, group_id) VALUES (?, ?, ?) \
+             ON CONFLICT(key) DO UPDATE SET id = excluded.id, group_id = excluded.group_id",
+            self.table
+        );
+        let mut tx = self.pool.begin().await?;
+        for record in records {
+            sqlx::query(&query)
+                .bind(record.key)
+                .bind(record.id)
+                .bind(record.group_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list(&self, group_ids: Option<&[String]>) -> Result<Vec<Record>, RecordManagerError> {
+        let rows = match group_ids {
+            None => {
+                sqlx::query(&format!("SELECT key, id, group_id FROM {}", self.table))
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+            Some(group_ids) => {
+                let query = format!(
+                    "SELECT key, id, group_id FROM {} WHERE group_id = ?",
+                    self.table
+                );
+                let mut rows = Vec::new();
+                for group_id in group_ids {
+                    rows.extend(
+                        sqlx::query(&query)
+                            .bind(group_id)
+                            .fetch_all(&self.pool)
+                            .await?,
+                    );
+                }
+                rows
+            }
+        };
+        rows.iter().map(record_from_row).collect()
+    }
+
+    async fn delete(&self, keys: &[String]) -> Result<(), RecordManagerError> {
+        let query = format!("DELETE FROM {} WHERE key = ?", self.table);
+        for key in keys {
+            sqlx::query(&query).bind(key).execute(&self.pool).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sqlite_record_manager() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let record_manager = SqliteRecordManager::new(pool);
+        record_manager.initialize().await.unwrap();
+
+        let record = |key: &str, group_id: &str| Record {
+            key: key.to_string(),
+            id: format!("id-{}", key),
+            group_id: Some(group_id.to_string()),
+        };
+        record_manager
+            .update(vec![record("a", "x.txt"), record("b", "y.txt")])
+            .await
+            .unwrap();
+
+        let records = record_manager
+            .get(&["a".to_string(), "c".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(records, vec![Some(record("a", "x.txt")), None]);
+        assert_eq!(
+            record_manager
+                .list(Some(&["y.txt".to_string()]))
+                .await
+                .unwrap(),
+            vec![record("b", "y.txt")]
+        );
+
+        record_manager.delete(&["a".to_string()]).await.unwrap();
+        assert_eq!(record_manager.list(None).await.unwrap().len(), 1);
+    }
+}
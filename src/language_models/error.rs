@@ -1,21 +1,83 @@
-use async_openai::error::OpenAIError;
+use std::time::Duration;
+
+use async_openai::error::{ApiError, OpenAIError};
 use reqwest::Error as ReqwestError;
 use serde_json::Error as SerdeJsonError;
 use thiserror::Error;
 use tokio::time::error::Elapsed;
 
-use crate::llm::AnthropicError;
+#[cfg(feature = "bedrock")]
+use crate::llm::BedrockError;
+#[cfg(feature = "candle")]
+use crate::llm::CandleError;
+#[cfg(feature = "ollama")]
+use crate::llm::OllamaError;
+use crate::llm::{AnthropicError, GeminiError};
 
+/// The errors of the LLMs. The failures common to the providers, e.g. a rate limit or a
+/// prompt too long, are mapped by the clients to their own variants, so they can be
+/// handled the same way whatever the provider. The provider variants are the other
+/// errors of the providers.
 #[derive(Error, Debug)]
 pub enum LLMError {
+    /// The requests or tokens quota of the provider is exhausted, the request can be made
+    /// again after `retry_after` when the provider tells it.
+    #[error("Rate limited{}: {message}", retry_after.map(|d| format!(", retry after {:?}", d)).unwrap_or_default())]
+    RateLimited {
+        retry_after: Option<Duration>,
+        message: String,
+    },
+
+    /// The prompt doesn't fit in the context window of the model. The numbers of tokens are
+    /// known when the provider tells them.
+    #[error("Context length exceeded: {message}")]
+    ContextLengthExceeded {
+        needed: Option<usize>,
+        limit: Option<usize>,
+        message: String,
+    },
+
+    /// The credentials are invalid, or don't give access to the model.
+    #[error("Authentication failed: {0}")]
+    AuthenticationFailed(String),
+
+    /// The prompt was blocked by the content filters of the provider.
+    #[error("Content filtered: {0}")]
+    ContentFiltered(String),
+
+    #[error("Operation timed out")]
+    Timeout,
+
+    /// The provider is overloaded or failing, with the status of its response if any.
+    #[error("Provider unavailable{}: {message}", status.map(|s| format!(" ({})", s)).unwrap_or_default())]
+    ProviderUnavailable {
+        status: Option<u16>,
+        message: String,
+    },
+
     #[error("OpenAI error: {0}")]
-    OpenAIError(#[from] OpenAIError),
+    OpenAIError(OpenAIError),
 
     #[error("Anthropic error: {0}")]
     AnthropicError(#[from] AnthropicError),
 
+    #[error("Gemini error: {0}")]
+    GeminiError(#[from] GeminiError),
+
+    #[cfg(feature = "bedrock")]
+    #[error("Bedrock error: {0}")]
+    BedrockError(#[from] BedrockError),
+
+    #[cfg(feature = "ollama")]
+    #[error("Ollama error: {0}")]
+    OllamaError(#[from] OllamaError),
+
+    #[cfg(feature = "candle")]
+    #[error("Candle error: {0}")]
+    CandleError(#[from] CandleError),
+
     #[error("Network request failed: {0}")]
-    RequestError(#[from] ReqwestError),
+    RequestError(ReqwestError),
 
     #[error("JSON serialization/deserialization error: {0}")]
     SerdeError(#[from] SerdeJsonError),
@@ -23,15 +85,351 @@ pub enum LLMError {
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
-    #[error("Operation timed out")]
-    Timeout(#[from] Elapsed),
-
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
 
+    /// An option of the call the provider has no parameter for, see
+    /// `CallOptions::check_supported`.
+    #[error("{provider} doesn't support the option {option}")]
+    UnsupportedOption {
+        provider: String,
+        option: &'static str,
+    },
+
     #[error("Content not found in response: Expected at {0}")]
     ContentNotFound(String),
 
     #[error("Error: {0}")]
     OtherError(String),
 }
+
+/// The classes of the transient errors, the ones worth retrying or falling back on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LLMErrorClass {
+    RateLimit,
+    Timeout,
+    ServerError,
+    Connection,
+}
+
+impl LLMErrorClass {
+    pub const ALL: [LLMErrorClass; 4] = [
+        LLMErrorClass::RateLimit,
+        LLMErrorClass::Timeout,
+        LLMErrorClass::ServerError,
+        LLMErrorClass::Connection,
+    ];
+
+    /// The class of the errors of the responses with this status.
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            429 => Some(LLMErrorClass::RateLimit),
+            408 => Some(LLMErrorClass::Timeout),
+            // 529 is the overloaded status of Anthropic.
+            500 | 502 | 503 | 504 | 529 => Some(LLMErrorClass::ServerError),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_request_error(error: &ReqwestError) -> Option<Self> {
+        if error.is_timeout() {
+            return Some(LLMErrorClass::Timeout);
+        }
+        // The errors of fetch don't tell the failed connections apart.
+        #[cfg(not(target_arch = "wasm32"))]
+        if error.is_connect() {
+            return Some(LLMErrorClass::Connection);
+        }
+        error
+            .status()
+            .and_then(|status| Self::from_status(status.as_u16()))
+    }
+}
+
+impl LLMError {
+    /// The error of a response failing with this status, when the status has a variant of
+    /// its own, e.g. `RateLimited` for 429. `None` for the client to report the other errors
+    /// as errors of its provider.
+    pub(crate) fn from_status(
+        status: u16,
+        retry_after: Option<Duration>,
+        message: &str,
+    ) -> Option<LLMError> {
+        match status {
+            401 | 403 => Some(LLMError::AuthenticationFailed(message.to_string())),
+            408 => Some(LLMError::Timeout),
+            429 => Some(LLMError::RateLimited {
+                retry_after,
+                message: message.to_string(),
+            }),
+            500..=599 => Some(LLMError::ProviderUnavailable {
+                status: Some(status),
+                message: message.to_string(),
+            }),
+            400 | 413 => Self::from_context_length_message(message),
+            _ => None,
+        }
+    }
+
+    /// The `ContextLengthExceeded` error of a message of a provider rejecting a prompt too
+    /// long, with the numbers of tokens of the message if any, e.g. `prompt is too long:
+    /// 250000 tokens > 200000 maximum`.
+    pub(crate) fn from_context_length_message(message: &str) -> Option<LLMError> {
+        let lower = message.to_lowercase();
+        if ![
+            "context length",
+            "context_length",
+            "context window",
+            "prompt is too long",
+            "input is too long",
+            "maximum number of tokens",
+        ]
+        .iter()
+        .any(|pattern| lower.contains(pattern))
+        {
+            return None;
+        }
+
+        let numbers: Vec<usize> = lower
+            .split(|c: char| !c.is_ascii_digit() && c != ',')
+            .filter_map(|number| number.replace(',', "").parse().ok())
+            .collect();
+        // OpenAI tells the limit first, then the tokens of the prompt and their parts.
+        let limit = lower
+            .split_once("maximum context length is ")
+            .and_then(|(_, rest)| rest.split(|c: char| !c.is_ascii_digit()).next())
+            .and_then(|limit| limit.parse().ok());
+        let (needed, limit) = match (limit, numbers.as_slice()) {
+            (Some(limit), numbers) => (
+                numbers.iter().copied().max().filter(|n| *n > limit),
+                Some(limit),
+            ),
+            (None, [a, b]) => (Some(*a.max(b)), Some(*a.min(b))),
+            (None, _) => (None, None),
+        };
+        Some(LLMError::ContextLengthExceeded {
+            needed,
+            limit,
+            message: message.to_string(),
+        })
+    }
+
+    /// The class of the error if it is transient.
+    pub fn class(&self) -> Option<LLMErrorClass> {
+        match self {
+            LLMError::RateLimited { .. } => Some(LLMErrorClass::RateLimit),
+            LLMError::Timeout => Some(LLMErrorClass::Timeout),
+            LLMError::ProviderUnavailable { .. } => Some(LLMErrorClass::ServerError),
+            LLMError::RequestError(e) | LLMError::OpenAIError(OpenAIError::Reqwest(e)) => {
+                LLMErrorClass::from_request_error(e)
+            }
+            LLMError::GeminiError(GeminiError::ApiError { status, .. }) => {
+                LLMErrorClass::from_status(*status)
+            }
+            #[cfg(feature = "bedrock")]
+            LLMError::BedrockError(BedrockError::ApiError { status, .. }) => {
+                LLMErrorClass::from_status(*status)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the error is likely to go away by making the request again: rate limits,
+    /// errors of the servers, timeouts and connection errors.
+    pub fn is_transient(&self) -> bool {
+        self.class().is_some()
+    }
+
+    /// How long the provider asked to wait before making the request again.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LLMError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl From<ReqwestError> for LLMError {
+    fn from(error: ReqwestError) -> Self {
+        if error.is_timeout() {
+            LLMError::Timeout
+        } else {
+            LLMError::RequestError(error)
+        }
+    }
+}
+
+impl From<Elapsed> for LLMError {
+    fn from(_: Elapsed) -> Self {
+        LLMError::Timeout
+    }
+}
+
+/// The errors of the APIs compatible with OpenAI's are mapped from their code and type,
+/// their status being unknown.
+impl From<OpenAIError> for LLMError {
+    fn from(error: OpenAIError) -> Self {
+        match error {
+            OpenAIError::ApiError(e) => from_openai_api_error(e),
+            OpenAIError::Reqwest(e) if e.is_timeout() => LLMError::Timeout,
+            e => LLMError::OpenAIError(e),
+        }
+    }
+}
+
+fn from_openai_api_error(error: ApiError) -> LLMError {
+    let code = error.code.as_ref().and_then(|code| code.as_str());
+    let error_type = error.r#type.as_deref();
+    match (code, error_type) {
+        (Some("rate_limit_exceeded"), _) | (_, Some("tokens" | "requests")) => {
+            LLMError::RateLimited {
+                retry_after: retry_after_in_message(&error.message),
+                message: error.message,
+            }
+        }
+        (Some("invalid_api_key"), _) | (_, Some("authentication_error")) => {
+            LLMError::AuthenticationFailed(error.message)
+        }
+        (Some("content_filter" | "content_policy_violation"), _) => {
+            LLMError::ContentFiltered(error.message)
+        }
+        (_, Some("server_error")) => LLMError::ProviderUnavailable {
+            status: None,
+            message: error.message,
+        },
+        (Some("context_length_exceeded"), _) => LLMError::from_context_length_message(
+            &error.message,
+        )
+        .unwrap_or(LLMError::ContextLengthExceeded {
+            needed: None,
+            limit: None,
+            message: error.message,
+        }),
+        _ => LLMError::from_context_length_message(&error.message)
+            .unwrap_or(LLMError::OpenAIError(OpenAIError::ApiError(error))),
+    }
+}
+
+/// The delay of the messages of OpenAI's rate limits, e.g. `Please try again in 1.5s` or
+/// `in 20ms`.
+fn retry_after_in_message(message: &str) -> Option<Duration> {
+    let (_, rest) = message.split_once("try again in ")?;
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+    let value: f64 = rest[..end].parse().ok()?;
+    match &rest[end..] {
+        unit if unit.starts_with("ms") => Some(Duration::from_secs_f64(value / 1000.0)),
+        unit if unit.starts_with('s') => Some(Duration::from_secs_f64(value)),
+        unit if unit.starts_with('m') => Some(Duration::from_secs_f64(value * 60.0)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_status() {
+        let error = LLMError::from_status(429, Some(Duration::from_secs(2)), "Slow down").unwrap();
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(2)));
+        assert_eq!(error.to_string(), "Rate limited, retry after 2s: Slow down");
+        assert!(matches!(
+            LLMError::from_status(401, None, "Invalid API key"),
+            Some(LLMError::AuthenticationFailed(_))
+        ));
+        assert_eq!(
+            LLMError::from_status(529, None, "Overloaded")
+                .unwrap()
+                .class(),
+            Some(LLMErrorClass::ServerError)
+        );
+        assert!(LLMError::from_status(400, None, "Invalid role").is_none());
+        assert!(LLMError::from_status(404, None, "Not found").is_none());
+
+        let context_length = |message: &str| match LLMError::from_context_length_message(message) {
+            Some(LLMError::ContextLengthExceeded { needed, limit, .. }) => Some((needed, limit)),
+            _ => None,
+        };
+        assert_eq!(
+            context_length("prompt is too long: 250000 tokens > 200000 maximum"),
+            Some((Some(250000), Some(200000)))
+        );
+        assert_eq!(
+            context_length(
+                "This model's maximum context length is 128000 tokens. However, you requested \
+                 130000 tokens (129000 in the messages, 1000 in the completion)."
+            ),
+            Some((Some(130000), Some(128000)))
+        );
+        assert_eq!(
+            context_length(
+                "The input token count (1,048,600) exceeds the maximum number of tokens allowed \
+                 (1,048,576)."
+            ),
+            Some((Some(1048600), Some(1048576)))
+        );
+        assert_eq!(
+            context_length("Input is too long for requested model."),
+            Some((None, None))
+        );
+        assert_eq!(context_length("Invalid role"), None);
+    }
+
+    #[test]
+    fn test_from_openai_error() {
+        let api_error = |message: &str, r#type: Option<&str>, code: Option<&str>| {
+            LLMError::from(OpenAIError::ApiError(ApiError {
+                message: message.to_string(),
+                r#type: r#type.map(String::from),
+                param: None,
+                code: code.map(|code| serde_json::json!(code)),
+            }))
+        };
+        let error = api_error(
+            "Rate limit reached for gpt-4o. Please try again in 1.5s.",
+            Some("tokens"),
+            Some("rate_limit_exceeded"),
+        );
+        assert_eq!(error.retry_after(), Some(Duration::from_millis(1500)));
+        assert!(matches!(
+            api_error("Please try again in 20ms.", Some("requests"), None).retry_after(),
+            Some(delay) if delay == Duration::from_millis(20)
+        ));
+        assert!(matches!(
+            api_error(
+                "This model's maximum context length is 8192 tokens. However, your messages \
+                 resulted in 9000 tokens.",
+                Some("invalid_request_error"),
+                Some("context_length_exceeded"),
+            ),
+            LLMError::ContextLengthExceeded {
+                needed: Some(9000),
+                limit: Some(8192),
+                ..
+            }
+        ));
+        assert!(matches!(
+            api_error(
+                "Incorrect API key",
+                Some("invalid_request_error"),
+                Some("invalid_api_key")
+            ),
+            LLMError::AuthenticationFailed(_)
+        ));
+        assert!(matches!(
+            api_error(
+                "Flagged",
+                Some("invalid_request_error"),
+                Some("content_filter")
+            ),
+            LLMError::ContentFiltered(_)
+        ));
+        assert!(api_error("The server had an error", Some("server_error"), None).is_transient());
+        assert!(matches!(
+            api_error("You exceeded your quota", Some("insufficient_quota"), None),
+            LLMError::OpenAIError(OpenAIError::ApiError(_))
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use futures::Stream;
+
+use crate::schemas::{Message, StreamData};
+
+use super::{llm::LLM, options::CallOptions, GenerateResult, LLMError, LLMErrorClass};
+
+/// Wraps an LLM to call the fallbacks, in order, when it fails with a transient error:
+/// a rate limit, a timeout, an error of the server or of the connection. The other errors,
+/// e.g. an invalid request, are returned as is, as the fallbacks would fail the same way.
+///
+/// The `model` of the result tells which one answered, for the LLMs reporting it.
+///
+/// # Example
+/// ```rust,ignore
+/// let llm = LLMWithFallbacks::new(OpenAI::default())
+///     .with_fallback(Claude::default())
+///     .with_error_classes(&[LLMErrorClass::RateLimit, LLMErrorClass::ServerError]);
+/// let result = llm.generate(&[Message::new_human_message("Hi")]).await?;
+/// println!("Answered by {:?}", result.model);
+/// ```
+pub struct LLMWithFallbacks {
+    llms: Vec<Box<dyn LLM>>,
+    error_classes: Vec<LLMErrorClass>,
+}
+
+impl LLMWithFallbacks {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L) -> Self {
+        Self {
+            llms: vec![llm.into()],
+            error_classes: LLMErrorClass::ALL.to_vec(),
+        }
+    }
+
+    pub fn with_fallback<L: Into<Box<dyn LLM>>>(mut self, fallback: L) -> Self {
+        self.llms.push(fallback.into());
+        self
+    }
+
+    pub fn with_fallbacks(mut self, fallbacks: Vec<Box<dyn LLM>>) -> Self {
+        self.llms.extend(fallbacks);
+        self
+    }
+
+    /// The classes of the errors switching to the next LLM. Default: all of them.
+    pub fn with_error_classes(mut self, error_classes: &[LLMErrorClass]) -> Self {
+        self.error_classes = error_classes.to_vec();
+        self
+    }
+
+    fn should_fallback(&self, error: &LLMError) -> bool {
+        error
+            .class()
+            .is_some_and(|class| self.error_classes.contains(&class))
+    }
+}
+
+impl Clone for LLMWithFallbacks {
+    fn clone(&self) -> Self {
+        Self {
+            llms: self.llms.iter().map(|llm| llm.clone_box()).collect(),
+            error_classes: self.error_classes.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl LLM for LLMWithFallbacks {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let (last, llms) = self.llms.split_last().expect("there is at least one llm");
+        for (i, llm) in llms.iter().enumerate() {
+            match llm.generate(messages).await {
+                Err(e) if self.should_fallback(&e) => {
+                    log::warn!("LLM {} failed, falling back to the next one: {}", i, e);
+                }
+                result => return result,
+            }
+        }
+        last.generate(messages).await
+    }
+
+    /// Falls back when the stream can't be started, the errors of a started stream are
+    /// returned as is.
+    async fn stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
+        let (last, llms) = self.llms.split_last().expect("there is at least one llm");
+        for (i, llm) in llms.iter().enumerate() {
+            match llm.stream(messages).await {
+                Err(e) if self.should_fallback(&e) => {
+                    log::warn!("LLM {} failed, falling back to the next one: {}", i, e);
+                }
+                result => return result,
+            }
+        }
+        last.stream(messages).await
+    }
+
+    fn add_options(&mut self, options: CallOptions) {
+        for llm in self.llms.iter_mut() {
+            llm.add_options(options.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::llm::{AnthropicError, FakeLLM};
+
+    use super::*;
+
+    fn rate_limit() -> LLMError {
+        LLMError::RateLimited {
+            retry_after: None,
+            message: "Rate Limit Exceeded".to_string(),
+        }
+    }
+
+    fn invalid_request() -> LLMError {
+        AnthropicError::InvalidRequestError("Bad request".to_string()).into()
+    }
+
+    #[tokio::test]
+    async fn test_llm_with_fallbacks() {
+        let messages = [Message::new_human_message("Hi")];
+        let llm = LLMWithFallbacks::new(FakeLLM::new("primary").with_errors(vec![rate_limit()]))
+            .with_fallback(FakeLLM::new("fallback"));
+        let result = llm.generate(&messages).await.unwrap();
+        assert_eq!(result.generation, "fallback");
+
+        let llm =
+            LLMWithFallbacks::new(FakeLLM::new("primary").with_errors(vec![invalid_request()]))
+                .with_fallback(FakeLLM::new("fallback"));
+        assert!(matches!(
+            llm.generate(&messages).await,
+            Err(LLMError::AnthropicError(
+                AnthropicError::InvalidRequestError(_)
+            ))
+        ));
+
+        let fallback = FakeLLM::new("fallback");
+        let llm = LLMWithFallbacks::new(FakeLLM::new("primary").with_errors(vec![rate_limit()]))
+            .with_fallback(fallback.clone())
+            .with_error_classes(&[LLMErrorClass::ServerError]);
+        assert!(llm.generate(&messages).await.is_err());
+        assert_eq!(fallback.call_count(), 0);
+    }
+}
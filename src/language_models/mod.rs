@@ -2,18 +2,51 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::schemas::ToolCall;
+
 pub mod llm;
 pub mod options;
 
 mod error;
 pub use error::*;
 
-//TODO: check if its this should have a data:serde::Value to save all other things, like OpenAI
-//function responses
+mod retry;
+pub use retry::*;
+
+mod fallbacks;
+pub use fallbacks::*;
+
+mod rate_limiter;
+pub use rate_limiter::*;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GenerateResult {
     pub tokens: Option<TokenUsage>,
     pub generation: String,
+    /// Why the model stopped generating, for the providers reporting it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<FinishReason>,
+    /// The tools the model asked to call, for the providers supporting tool calling.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// The model which generated the result, for the LLMs reporting it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// The model finished its answer, or reached a stop sequence.
+    Stop,
+    /// The answer reached the maximum number of tokens.
+    Length,
+    /// The answer was blocked by the safety filters of the provider.
+    ContentFilter,
+    /// The model called tools.
+    ToolCalls,
+    /// A reason specific to the provider.
+    Other(String),
 }
 
 impl GenerateResult {
@@ -88,6 +121,9 @@ impl Default for GenerateResult {
         Self {
             tokens: Default::default(),
             generation: Default::default(),
+            finish_reason: None,
+            tool_calls: Vec::new(),
+            model: None,
         }
     }
 }
@@ -1,8 +1,13 @@
 use futures::Future;
-use std::{pin::Pin, sync::Arc};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
 use tokio::sync::Mutex;
 
-use crate::schemas::{FunctionCallBehavior, FunctionDefinition};
+use crate::{
+    callbacks::CallbackManager,
+    schemas::{FunctionCallBehavior, FunctionDefinition, ResponseFormat},
+};
+
+use super::{LLMError, LLMRetryPolicy};
 
 #[derive(Clone)]
 pub struct CallOptions {
@@ -24,8 +29,13 @@ pub struct CallOptions {
     pub repetition_penalty: Option<f32>,
     pub frequency_penalty: Option<f32>,
     pub presence_penalty: Option<f32>,
+    /// The biases added to the logits of tokens, by token id.
+    pub logit_bias: Option<HashMap<u32, f32>>,
     pub functions: Option<Vec<FunctionDefinition>>,
     pub function_call_behavior: Option<FunctionCallBehavior>,
+    pub response_format: Option<ResponseFormat>,
+    pub callbacks: Option<CallbackManager>,
+    pub retry: Option<LLMRetryPolicy>,
 }
 
 impl Default for CallOptions {
@@ -50,8 +60,12 @@ impl CallOptions {
             repetition_penalty: None,
             frequency_penalty: None,
             presence_penalty: None,
+            logit_bias: None,
             functions: None,
             function_call_behavior: None,
+            response_format: None,
+            callbacks: None,
+            retry: None,
         }
     }
 
@@ -137,6 +151,12 @@ impl CallOptions {
         self
     }
 
+    /// Biases the sampling of tokens, by token id, e.g. -100 to ban a token on OpenAI.
+    pub fn with_logit_bias(mut self, logit_bias: HashMap<u32, f32>) -> Self {
+        self.logit_bias = Some(logit_bias);
+        self
+    }
+
     pub fn with_functions(mut self, functions: Vec<FunctionDefinition>) -> Self {
         self.functions = Some(functions);
         self
@@ -147,6 +167,63 @@ impl CallOptions {
         self
     }
 
+    /// The format of the answers, e.g. a JSON of a schema, for the models supporting it.
+    pub fn with_response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.response_format = Some(response_format);
+        self
+    }
+
+    pub fn with_callbacks(mut self, callbacks: CallbackManager) -> Self {
+        self.callbacks = Some(callbacks);
+        self
+    }
+
+    /// Retries the requests failing transiently, see `LLMRetryPolicy`.
+    pub fn with_retry(mut self, retry: LLMRetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// The names of the sampling options which are set.
+    pub fn sampling_options(&self) -> Vec<&'static str> {
+        [
+            ("temperature", self.temperature.is_some()),
+            ("stop_words", self.stop_words.is_some()),
+            ("top_k", self.top_k.is_some()),
+            ("top_p", self.top_p.is_some()),
+            ("seed", self.seed.is_some()),
+            ("min_length", self.min_length.is_some()),
+            ("max_length", self.max_length.is_some()),
+            ("n", self.n.is_some()),
+            ("candidate_count", self.candidate_count.is_some()),
+            ("repetition_penalty", self.repetition_penalty.is_some()),
+            ("frequency_penalty", self.frequency_penalty.is_some()),
+            ("presence_penalty", self.presence_penalty.is_some()),
+            ("logit_bias", self.logit_bias.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, is_set)| is_set.then_some(name))
+        .collect()
+    }
+
+    /// Fails with `LLMError::UnsupportedOption` if a sampling option not in `supported`
+    /// is set, rather than silently ignoring it. Called by the providers before their
+    /// requests.
+    #[allow(clippy::result_large_err)]
+    pub fn check_supported(&self, provider: &str, supported: &[&str]) -> Result<(), LLMError> {
+        match self
+            .sampling_options()
+            .into_iter()
+            .find(|option| !supported.contains(option))
+        {
+            Some(option) => Err(LLMError::UnsupportedOption {
+                provider: provider.to_string(),
+                option,
+            }),
+            None => Ok(()),
+        }
+    }
+
     pub fn merge_options(&mut self, incoming_options: CallOptions) {
         // For simple scalar types wrapped in Option, prefer incoming option if it is Some
         self.candidate_count = incoming_options.candidate_count.or(self.candidate_count);
@@ -165,9 +242,14 @@ impl CallOptions {
             .frequency_penalty
             .or(self.frequency_penalty);
         self.presence_penalty = incoming_options.presence_penalty.or(self.presence_penalty);
+        self.logit_bias = incoming_options.logit_bias.or(self.logit_bias.take());
         self.function_call_behavior = incoming_options
             .function_call_behavior
             .or(self.function_call_behavior);
+        self.retry = incoming_options.retry.or(self.retry.take());
+        self.response_format = incoming_options
+            .response_format
+            .or(self.response_format.take());
 
         // For `Vec<String>`, merge if both are Some; prefer incoming if only incoming is Some
         if let Some(mut new_stop_words) = incoming_options.stop_words {
@@ -192,5 +274,13 @@ impl CallOptions {
         self.streaming_func = incoming_options
             .streaming_func
             .or_else(|| self.streaming_func.clone());
+
+        // Handlers are added up, so both sets are notified.
+        if let Some(incoming_callbacks) = incoming_options.callbacks {
+            match &mut self.callbacks {
+                Some(callbacks) => callbacks.merge(&incoming_callbacks),
+                None => self.callbacks = Some(incoming_callbacks),
+            }
+        }
     }
 }
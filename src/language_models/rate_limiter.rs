@@ -0,0 +1,291 @@
+use std::{pin::Pin, sync::Arc, sync::Mutex, time::Duration};
+
+use crate::{
+    runtime::{sleep, Instant},
+    schemas::{Message, StreamData},
+    tokenizers::{count_text_tokens, count_tokens},
+};
+use async_stream::stream;
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+
+use super::{llm::LLM, options::CallOptions, GenerateResult, LLMError};
+
+/// A token bucket refilled continuously, up to a minute of its rate.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    per_second: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn per_minute(limit: u32, now: Instant) -> Self {
+        let capacity = f64::from(limit.max(1));
+        Self {
+            capacity,
+            available: capacity,
+            per_second: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.per_second).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// How long until `amount` is available. The amounts above the capacity only wait for
+    /// a full bucket, they would wait forever otherwise.
+    fn wait_time(&self, amount: f64) -> Duration {
+        let missing = amount.min(self.capacity) - self.available;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.per_second)
+        }
+    }
+
+    /// Takes `amount`, the bucket can go negative when charging the amounts known after
+    /// the request, so the next requests wait for it.
+    fn take(&mut self, amount: f64) {
+        self.available -= amount;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    requests: Option<TokenBucket>,
+    tokens: Option<TokenBucket>,
+}
+
+/// Client-side limits on the requests and the tokens per minute, shared with an `Arc` by
+/// all the `RateLimitedLLM`s using the same quota, e.g. the LLMs of several chains calling
+/// the same provider.
+///
+/// The limits are token buckets, a minute of requests or tokens can be used at once.
+///
+/// # Example
+/// ```rust,ignore
+/// let limiter = Arc::new(
+///     RateLimiter::new()
+///         .with_requests_per_minute(500)
+///         .with_tokens_per_minute(30_000),
+/// );
+/// let summarizer = RateLimitedLLM::new(OpenAI::default(), limiter.clone());
+/// let translator = RateLimitedLLM::new(OpenAI::default(), limiter);
+/// ```
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// A limiter without limits, see `with_requests_per_minute` and
+    /// `with_tokens_per_minute`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.buckets_mut().requests =
+            Some(TokenBucket::per_minute(requests_per_minute, Instant::now()));
+        self
+    }
+
+    /// The tokens of the prompts and of the answers.
+    pub fn with_tokens_per_minute(mut self, tokens_per_minute: u32) -> Self {
+        self.buckets_mut().tokens =
+            Some(TokenBucket::per_minute(tokens_per_minute, Instant::now()));
+        self
+    }
+
+    fn buckets_mut(&mut self) -> &mut Buckets {
+        self.buckets
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Waits until a request of `tokens` is allowed, and counts it.
+    pub async fn acquire(&self, tokens: usize) {
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                let Buckets {
+                    requests,
+                    tokens: token_bucket,
+                } = &mut *buckets;
+                let mut wait = Duration::ZERO;
+                if let Some(bucket) = requests.as_mut() {
+                    bucket.refill(now);
+                    wait = wait.max(bucket.wait_time(1.0));
+                }
+                if let Some(bucket) = token_bucket.as_mut() {
+                    bucket.refill(now);
+                    wait = wait.max(bucket.wait_time(tokens as f64));
+                }
+                if wait.is_zero() {
+                    if let Some(bucket) = requests.as_mut() {
+                        bucket.take(1.0);
+                    }
+                    if let Some(bucket) = token_bucket.as_mut() {
+                        bucket.take(tokens as f64);
+                    }
+                    return;
+                }
+                wait
+            };
+            log::debug!("Rate limited, waiting {:?}", wait);
+            sleep(wait).await;
+        }
+    }
+
+    /// Counts the tokens known once the request is done, e.g. the tokens of the answer.
+    pub fn record_tokens(&self, tokens: usize) {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(bucket) = buckets.tokens.as_mut() {
+            bucket.refill(Instant::now());
+            bucket.take(tokens as f64);
+        }
+    }
+}
+
+/// Wraps an LLM to wait for the limits of a `RateLimiter` before each call, instead of
+/// failing with the rate limit errors of the provider.
+///
+/// The tokens of the prompt are estimated with the `cl100k_base` encoding before the call,
+/// the tokens of the answer are counted after it, from the usage the provider reports or
+/// estimated from the answer.
+///
+/// # Example
+/// ```rust,ignore
+/// let limiter = Arc::new(RateLimiter::new().with_requests_per_minute(60));
+/// let llm = RateLimitedLLM::new(OpenAI::default(), limiter);
+/// let chain = LLMChainBuilder::new().prompt(prompt).llm(llm).build()?;
+/// ```
+pub struct RateLimitedLLM {
+    llm: Box<dyn LLM>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitedLLM {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L, limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            llm: llm.into(),
+            limiter,
+        }
+    }
+}
+
+impl Clone for RateLimitedLLM {
+    fn clone(&self) -> Self {
+        Self {
+            llm: self.llm.clone_box(),
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl LLM for RateLimitedLLM {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let prompt_tokens = count_tokens("", messages);
+        self.limiter.acquire(prompt_tokens).await;
+        let result = self.llm.generate(messages).await?;
+        let completion_tokens = match &result.tokens {
+            Some(usage) => (usage.total_tokens as usize).saturating_sub(prompt_tokens),
+            None => count_text_tokens("", &result.generation),
+        };
+        self.limiter.record_tokens(completion_tokens);
+        Ok(result)
+    }
+
+    /// The tokens of the answer are counted once the stream completes.
+    async fn stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
+        self.limiter.acquire(count_tokens("", messages)).await;
+        let mut llm_stream = self.llm.stream(messages).await?;
+        let limiter = self.limiter.clone();
+        let output_stream = stream! {
+            let mut answer = String::new();
+            while let Some(result) = llm_stream.next().await {
+                if let Ok(data) = &result {
+                    answer.push_str(&data.content);
+                }
+                yield result;
+            }
+            limiter.record_tokens(count_text_tokens("", &answer));
+        };
+        Ok(Box::pin(output_stream))
+    }
+
+    fn add_options(&mut self, options: CallOptions) {
+        self.llm.add_options(options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::FakeLLM;
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::per_minute(60, start);
+        assert_eq!(bucket.wait_time(60.0), Duration::ZERO);
+        bucket.take(60.0);
+        assert_eq!(bucket.wait_time(2.0), Duration::from_secs(2));
+        // Above the capacity only waits for a full bucket.
+        assert_eq!(bucket.wait_time(1000.0), Duration::from_secs(60));
+
+        bucket.refill(start + Duration::from_secs(1));
+        assert_eq!(bucket.wait_time(1.0), Duration::ZERO);
+        bucket.take(11.0);
+        assert_eq!(bucket.wait_time(1.0), Duration::from_secs(11));
+
+        bucket.refill(start + Duration::from_secs(600));
+        assert_eq!(bucket.available, 60.0);
+    }
+
+    fn available(limiter: &RateLimiter, bucket: fn(&Buckets) -> &Option<TokenBucket>) -> f64 {
+        bucket(&limiter.buckets.lock().unwrap())
+            .as_ref()
+            .unwrap()
+            .available
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_llm() {
+        let limiter = Arc::new(RateLimiter::new().with_tokens_per_minute(1200));
+        let llm = RateLimitedLLM::new(FakeLLM::new("Hi there"), limiter.clone());
+        llm.invoke("Hello").await.unwrap();
+        // The message with its role, the start of the reply and the answer.
+        let used = 1200.0 - available(&limiter, |buckets| &buckets.tokens);
+        assert!((used - 10.0).abs() < 0.5, "{}", used);
+
+        // A request every 50ms, none left.
+        let limiter = Arc::new(RateLimiter::new().with_requests_per_minute(1200));
+        limiter
+            .buckets
+            .lock()
+            .unwrap()
+            .requests
+            .as_mut()
+            .unwrap()
+            .available = 0.0;
+        let llm = RateLimitedLLM::new(FakeLLM::new("Hi"), limiter.clone());
+        let other = llm.clone();
+
+        let start = std::time::Instant::now();
+        let (first, second) = tokio::join!(llm.invoke("Hello"), other.invoke("Hello"));
+        assert_eq!(first.unwrap(), "Hi");
+        assert_eq!(second.unwrap(), "Hi");
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+}
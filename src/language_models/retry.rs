@@ -0,0 +1,288 @@
+use std::{
+    future::Future,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use reqwest::{header::HeaderMap, RequestBuilder, Response};
+
+use crate::runtime;
+
+use super::{LLMError, LLMErrorClass};
+
+/// How the LLM clients retry the requests failing transiently: the rate limited ones
+/// (429), the errors of the servers (5xx), the timeouts and the connection errors.
+///
+/// The delay between the attempts grows exponentially, from `initial_delay` up to
+/// `max_delay`. The `Retry-After` of the responses is honored when the provider sends it.
+/// With `jitter` the delays are spread randomly over the second half of their range, so
+/// that the clients rate limited together don't retry together.
+///
+/// # Example
+/// ```rust,ignore
+/// let options = CallOptions::new().with_retry(LLMRetryPolicy::new().with_max_attempts(5));
+/// let llm = Claude::new().with_options(options);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LLMRetryPolicy {
+    pub max_attempts: usize,
+    pub initial_delay: Duration,
+    pub multiplier: f64,
+    pub max_delay: Duration,
+    pub jitter: bool,
+}
+
+impl LLMRetryPolicy {
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(500),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+
+    /// Number of attempts, including the first one.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Factor the delay is multiplied by after each attempt.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// The longest delay between two attempts, `Retry-After` included.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Delay before the attempt following `attempt` (starting at 1), the `retry_after` of
+    /// the response if any.
+    pub fn delay(&self, attempt: usize, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+        let delay = self.backoff(attempt);
+        if self.jitter {
+            delay.mul_f64(0.5 + random_fraction() / 2.0)
+        } else {
+            delay
+        }
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        let factor = self.multiplier.powi(attempt.saturating_sub(1) as i32);
+        self.initial_delay.mul_f64(factor).min(self.max_delay)
+    }
+
+    /// The equivalent backoff for the clients of `async-openai`, which retry the rate
+    /// limited requests themselves without telling how many times.
+    pub(crate) fn to_exponential_backoff(&self) -> backoff::ExponentialBackoff {
+        backoff::ExponentialBackoff {
+            current_interval: self.initial_delay,
+            initial_interval: self.initial_delay,
+            randomization_factor: if self.jitter { 0.25 } else { 0.0 },
+            multiplier: self.multiplier,
+            max_interval: self.max_delay,
+            max_elapsed_time: Some((1..self.max_attempts).map(|a| self.backoff(a)).sum()),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for LLMRetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a response with this status is worth retrying.
+pub fn is_retryable_status(status: u16) -> bool {
+    LLMErrorClass::from_status(status).is_some()
+}
+
+/// The delay asked by the `retry-after-ms` or `Retry-After` headers, in seconds or as a date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    if let Some(millis) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return Some(Duration::from_secs_f64(millis.max(0.0) / 1000.0));
+    }
+    let value = header("retry-after")?.trim();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Some(Duration::from_secs_f64(seconds.max(0.0)));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+fn random_fraction() -> f64 {
+    let nanos = runtime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    (nanos % 1000) as f64 / 1000.0
+}
+
+/// Sends the request, again while its responses are retryable and attempts remain. The
+/// last response is returned as is, for the client to turn it into its error.
+pub(crate) async fn send_with_retry(
+    policy: Option<&LLMRetryPolicy>,
+    request: RequestBuilder,
+) -> Result<Response, reqwest::Error> {
+    let Some(policy) = policy else {
+        return request.send().await;
+    };
+    let mut attempt = 1;
+    loop {
+        // Requests with a streamed body can't be sent twice.
+        let Some(next) = request.try_clone() else {
+            return request.send().await;
+        };
+        let last = attempt >= policy.max_attempts;
+        let delay = match next.send().await {
+            Ok(res) if !last && is_retryable_status(res.status().as_u16()) => {
+                policy.delay(attempt, retry_after(res.headers()))
+            }
+            Err(e) if !last && LLMErrorClass::from_request_error(&e).is_some() => {
+                policy.delay(attempt, None)
+            }
+            res => return res,
+        };
+        log::warn!("Attempt {} failed, retrying in {:?}", attempt, delay);
+        runtime::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Calls `f` again while it fails with a transient error and attempts remain.
+pub(crate) async fn retry<T, F, Fut>(policy: Option<&LLMRetryPolicy>, f: F) -> Result<T, LLMError>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, LLMError>>,
+{
+    let Some(policy) = policy else {
+        return f().await;
+    };
+    let mut attempt = 1;
+    loop {
+        match f().await {
+            Err(e) if attempt < policy.max_attempts && e.is_transient() => {
+                let delay = policy.delay(attempt, e.retry_after());
+                log::warn!("Attempt {} failed, retrying in {:?}: {}", attempt, delay, e);
+                runtime::sleep(delay).await;
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_retry_policy_delay() {
+        let policy = LLMRetryPolicy::new()
+            .with_initial_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(3))
+            .with_jitter(false);
+        assert_eq!(policy.delay(1, None), Duration::from_secs(1));
+        assert_eq!(policy.delay(2, None), Duration::from_secs(2));
+        assert_eq!(policy.delay(3, None), Duration::from_secs(3));
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(2))),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(60))),
+            Duration::from_secs(3)
+        );
+
+        let delay = policy.with_jitter(true).delay(2, None);
+        assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert("retry-after", HeaderValue::from_static("2"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+        headers.insert("retry-after-ms", HeaderValue::from_static("150"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(150)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_send_with_retry() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server
+            .mock("GET", "/")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(2)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("GET", "/")
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let policy = LLMRetryPolicy::new().with_initial_delay(Duration::from_millis(1));
+        let res = send_with_retry(Some(&policy), reqwest::Client::new().get(server.url()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        limited.assert_async().await;
+        ok.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_send_with_retry_gives_up() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/")
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let policy = LLMRetryPolicy::new()
+            .with_max_attempts(2)
+            .with_initial_delay(Duration::from_millis(1));
+        let res = send_with_retry(Some(&policy), reqwest::Client::new().get(server.url()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 503);
+        unavailable.assert_async().await;
+    }
+}
@@ -1,17 +1,41 @@
 #![allow(dead_code)]
+extern crate self as langchain_rust;
+
 pub mod agent;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod blocking;
+pub mod cache;
+pub mod callbacks;
 pub mod chain;
+pub mod docstore;
 pub mod document_loaders;
+pub mod document_transformers;
 pub mod embedding;
+pub mod evaluation;
+pub mod graphstore;
+pub mod guardrails;
+pub mod indexing;
 pub mod language_models;
 pub mod llm;
 pub mod memory;
 pub mod output_parsers;
 pub mod prompt;
+pub mod retrievers;
+pub mod runnable;
+pub(crate) mod runtime;
 pub mod schemas;
 pub mod semantic_router;
+pub mod streaming;
 pub mod text_splitter;
+pub mod tokenizers;
 pub mod tools;
 pub mod vectorstore;
 
+pub use schemars;
 pub use url;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait::async_trait;
+    pub use serde;
+}
@@ -0,0 +1,645 @@
+use std::{
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_credential_types::{
+    provider::{ProvideCredentials, SharedCredentialsProvider},
+    Credentials,
+};
+use futures::{Stream, StreamExt};
+use reqwest::{Client, Response};
+use serde_json::{json, Value};
+use tokio::sync::{Mutex, OnceCell};
+use url::Url;
+
+use crate::{
+    callbacks::{RunManager, RunType},
+    language_models::{
+        llm::LLM, options::CallOptions, retry_after, send_with_retry, GenerateResult, LLMError,
+    },
+    llm::BedrockError,
+    runtime,
+    schemas::{Message, StreamData, ToolCall},
+};
+
+use super::{
+    event_stream::decode_message,
+    models::{finish_reason, ApiResponse, Payload, Usage},
+    signing,
+};
+
+/// The region when none is given nor configured in the environment.
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// The sampling options the inference config of the Converse API has a parameter for.
+const SUPPORTED_OPTIONS: &[&str] = &["temperature", "stop_words", "top_p"];
+
+pub enum BedrockModel {
+    Claude3Haiku,
+    Claude3Sonnet,
+    Claude35Sonnet,
+    Llama3_8bInstruct,
+    Llama3_70bInstruct,
+    TitanTextExpress,
+    TitanTextPremier,
+}
+
+impl fmt::Display for BedrockModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BedrockModel::Claude3Haiku => write!(f, "anthropic.claude-3-haiku-20240307-v1:0"),
+            BedrockModel::Claude3Sonnet => write!(f, "anthropic.claude-3-sonnet-20240229-v1:0"),
+            BedrockModel::Claude35Sonnet => {
+                write!(f, "anthropic.claude-3-5-sonnet-20240620-v1:0")
+            }
+            BedrockModel::Llama3_8bInstruct => write!(f, "meta.llama3-8b-instruct-v1:0"),
+            BedrockModel::Llama3_70bInstruct => write!(f, "meta.llama3-70b-instruct-v1:0"),
+            BedrockModel::TitanTextExpress => write!(f, "amazon.titan-text-express-v1"),
+            BedrockModel::TitanTextPremier => write!(f, "amazon.titan-text-premier-v1:0"),
+        }
+    }
+}
+
+/// Models of AWS Bedrock, called with the Converse API.
+///
+/// Unless `with_credentials` is used, the requests are signed with the credentials of
+/// the default chain of aws-config: the environment variables, the profiles of the shared
+/// files (with SSO, `credential_process` and assumed roles), web identity tokens, and the
+/// container and instance metadata endpoints. The region is the one of the environment
+/// unless `with_region` is used, `us-east-1` if there is none. The model can also be an
+/// inference profile or the ARN of a provisioned model.
+///
+/// # Example
+/// ```rust,ignore
+/// let bedrock = Bedrock::new()
+///     .with_region("eu-central-1")
+///     .with_model(BedrockModel::Claude3Haiku.to_string());
+/// let response = bedrock.invoke("Why is the sky blue?").await?;
+/// ```
+#[derive(Clone)]
+pub struct Bedrock {
+    model: String,
+    options: CallOptions,
+    region: Option<String>,
+    endpoint: Option<String>,
+    credentials: Option<SharedCredentialsProvider>,
+    sdk_config: Arc<OnceCell<SdkConfig>>,
+    cached_credentials: Arc<Mutex<Option<Credentials>>>,
+}
+
+impl Default for Bedrock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bedrock {
+    pub fn new() -> Self {
+        Self {
+            model: BedrockModel::Claude3Haiku.to_string(),
+            options: CallOptions::default(),
+            region: None,
+            endpoint: None,
+            credentials: None,
+            sdk_config: Arc::new(OnceCell::new()),
+            cached_credentials: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn with_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn with_options(mut self, options: CallOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_region<S: Into<String>>(mut self, region: S) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// The endpoint of the runtime API, e.g. a VPC endpoint. Defaults to the one of the
+    /// region.
+    pub fn with_endpoint<S: Into<String>>(mut self, endpoint: S) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// The credentials, or a provider of them, e.g. `aws_config::sts::AssumeRoleProvider`.
+    pub fn with_credentials(mut self, credentials: impl ProvideCredentials + 'static) -> Self {
+        self.credentials = Some(SharedCredentialsProvider::new(credentials));
+        self
+    }
+
+    fn start_run(&self) -> RunManager {
+        self.options
+            .callbacks
+            .clone()
+            .unwrap_or_default()
+            .start_run(self.model.clone(), RunType::Llm)
+    }
+
+    /// The config of the environment, loaded once.
+    async fn sdk_config(&self) -> &SdkConfig {
+        self.sdk_config
+            .get_or_init(|| aws_config::defaults(BehaviorVersion::latest()).load())
+            .await
+    }
+
+    async fn region(&self) -> String {
+        match &self.region {
+            Some(region) => region.clone(),
+            None => self
+                .sdk_config()
+                .await
+                .region()
+                .map(ToString::to_string)
+                .unwrap_or_else(|| DEFAULT_REGION.to_string()),
+        }
+    }
+
+    /// The credentials are provided once, and again when they are about to expire.
+    async fn credentials(&self) -> Result<Credentials, BedrockError> {
+        let mut cached = self.cached_credentials.lock().await;
+        if let Some(credentials) = cached.as_ref().filter(|c| !expires_soon(c)) {
+            return Ok(credentials.clone());
+        }
+        let provider = match &self.credentials {
+            Some(provider) => provider.clone(),
+            None => self
+                .sdk_config()
+                .await
+                .credentials_provider()
+                .ok_or_else(|| {
+                    BedrockError::CredentialsError("no credentials provider".to_string())
+                })?,
+        };
+        let credentials = provider
+            .provide_credentials()
+            .await
+            .map_err(|e| BedrockError::CredentialsError(e.to_string()))?;
+        *cached = Some(credentials.clone());
+        Ok(credentials)
+    }
+
+    async fn send(&self, action: &str, messages: &[Message]) -> Result<Response, LLMError> {
+        self.options.check_supported("Bedrock", SUPPORTED_OPTIONS)?;
+        let region = self.region().await;
+        let endpoint = self
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://bedrock-runtime.{}.amazonaws.com", region));
+        let url = Url::parse(&format!(
+            "{}/model/{}/{}",
+            endpoint.trim_end_matches('/'),
+            urlencoding::encode(&self.model),
+            action
+        ))
+        .map_err(|e| LLMError::InvalidUrl(e.to_string()))?;
+        let body = serde_json::to_vec(&Payload::new(messages, &self.options))?;
+
+        let credentials = self.credentials().await?;
+        let signed_headers = signing::sign(
+            &credentials,
+            &region,
+            "POST",
+            url.as_str(),
+            &[("content-type", "application/json")],
+            &body,
+            runtime::now(),
+        )?;
+        let mut request = Client::new()
+            .post(url)
+            .header("content-type", "application/json")
+            .body(body);
+        for (name, value) in signed_headers {
+            request = request.header(name, value);
+        }
+        let res = send_with_retry(self.options.retry.as_ref(), request).await?;
+        check_status(res).await
+    }
+
+    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let res: ApiResponse = self.send("converse", messages).await?.json().await?;
+        Ok(GenerateResult {
+            generation: res.text(),
+            tool_calls: res.tool_calls(),
+            finish_reason: res.stop_reason.as_deref().map(finish_reason),
+            tokens: res.usage.map(Into::into),
+            model: Some(self.model.clone()),
+        })
+    }
+}
+
+/// Whether the credentials expire in less than 5 minutes.
+fn expires_soon(credentials: &Credentials) -> bool {
+    credentials
+        .expiry()
+        .is_some_and(|expiry| expiry < runtime::now() + Duration::from_secs(300))
+}
+
+async fn check_status(res: Response) -> Result<Response, LLMError> {
+    let status = res.status().as_u16();
+    if res.status().is_success() {
+        return Ok(res);
+    }
+    let retry_after = retry_after(res.headers());
+    let body: Value = res.json().await.unwrap_or_default();
+    let message = body["message"]
+        .as_str()
+        .or_else(|| body["Message"].as_str())
+        .unwrap_or_default()
+        .to_string();
+    if let Some(error) = LLMError::from_status(status, retry_after, &message) {
+        return Err(error);
+    }
+    Err(BedrockError::ApiError { status, message })?
+}
+
+/// The error of an exception of the event stream, by its type.
+fn stream_exception(exception_type: &str, message: String) -> LLMError {
+    match exception_type {
+        "throttlingException" => LLMError::RateLimited {
+            retry_after: None,
+            message,
+        },
+        "internalServerException" | "serviceUnavailableException" | "modelStreamErrorException" => {
+            LLMError::ProviderUnavailable {
+                status: None,
+                message,
+            }
+        }
+        "validationException" => {
+            LLMError::from_context_length_message(&message).unwrap_or_else(|| {
+                BedrockError::StreamException {
+                    exception_type: exception_type.to_string(),
+                    message,
+                }
+                .into()
+            })
+        }
+        _ => BedrockError::StreamException {
+            exception_type: exception_type.to_string(),
+            message,
+        }
+        .into(),
+    }
+}
+
+/// The text, tool calls, stop reason and usage of the events of `converse-stream`.
+#[derive(Default)]
+struct StreamedResponse {
+    generation: String,
+    // The id, name and input of the tool use blocks, by index.
+    tool_calls: Vec<(u64, String, String, String)>,
+    stop_reason: Option<String>,
+    usage: Option<Usage>,
+}
+
+impl StreamedResponse {
+    fn push(&mut self, event: &Value) {
+        if let Some(tool_use) = event.pointer("/contentBlockStart/start/toolUse") {
+            self.tool_calls.push((
+                event["contentBlockStart"]["contentBlockIndex"]
+                    .as_u64()
+                    .unwrap_or_default(),
+                tool_use["toolUseId"].as_str().unwrap_or_default().into(),
+                tool_use["name"].as_str().unwrap_or_default().into(),
+                String::new(),
+            ));
+        }
+        if let Some(input) = event
+            .pointer("/contentBlockDelta/delta/toolUse/input")
+            .and_then(Value::as_str)
+        {
+            let index = event["contentBlockDelta"]["contentBlockIndex"]
+                .as_u64()
+                .unwrap_or_default();
+            if let Some(call) = self.tool_calls.iter_mut().find(|call| call.0 == index) {
+                call.3.push_str(input);
+            }
+        }
+        if let Some(reason) = event
+            .pointer("/messageStop/stopReason")
+            .and_then(Value::as_str)
+        {
+            self.stop_reason = Some(reason.to_string());
+        }
+        if let Some(usage) = event.pointer("/metadata/usage") {
+            self.usage = serde_json::from_value(usage.clone()).ok();
+        }
+    }
+
+    fn into_result(self, model: String) -> GenerateResult {
+        GenerateResult {
+            generation: self.generation,
+            tool_calls: self
+                .tool_calls
+                .into_iter()
+                .map(|(_, id, name, input)| ToolCall::new(id, name, &input))
+                .collect(),
+            finish_reason: self.stop_reason.as_deref().map(finish_reason),
+            tokens: self.usage.map(Into::into),
+            model: Some(model),
+        }
+    }
+}
+
+#[async_trait]
+impl LLM for Bedrock {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        match &self.options.streaming_func {
+            Some(func) => {
+                let mut response = StreamedResponse::default();
+                let mut stream = self.stream(messages).await?;
+                while let Some(data) = stream.next().await {
+                    let data = data?;
+                    response.push(&data.value);
+                    if !data.content.is_empty() {
+                        response.generation.push_str(&data.content);
+                        let mut func = func.lock().await;
+                        let _ = func(data.content).await;
+                    }
+                }
+                Ok(response.into_result(self.model.clone()))
+            }
+            None => {
+                self.start_run()
+                    .trace_llm(messages, self.generate(messages))
+                    .await
+            }
+        }
+    }
+
+    /// The values of the stream are the events of `converse-stream` by type, e.g.
+    /// `{"contentBlockDelta": {"delta": {"text": "Hi"}, "contentBlockIndex": 0}}`.
+    async fn stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
+        let mut bytes = self.send("converse-stream", messages).await?.bytes_stream();
+
+        let stream = async_stream::stream! {
+            let mut buffer = Vec::new();
+            while let Some(chunk) = bytes.next().await {
+                match chunk {
+                    Ok(chunk) => buffer.extend_from_slice(&chunk),
+                    Err(e) => {
+                        yield Err(LLMError::RequestError(e));
+                        return;
+                    }
+                }
+                loop {
+                    let message = match decode_message(&mut buffer) {
+                        Ok(Some(message)) => message,
+                        Ok(None) => break,
+                        Err(e) => {
+                            yield Err(e.into());
+                            return;
+                        }
+                    };
+                    let payload: Value = serde_json::from_slice(&message.payload).unwrap_or_default();
+                    if message.header(":message-type") != Some("event") {
+                        yield Err(stream_exception(
+                            message
+                                .header(":exception-type")
+                                .or_else(|| message.header(":error-code"))
+                                .unwrap_or_default(),
+                            payload["message"].as_str().unwrap_or_default().to_string(),
+                        ));
+                        return;
+                    }
+                    let event_type = message.header(":event-type").unwrap_or_default();
+                    let content = payload
+                        .pointer("/delta/text")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string();
+                    yield Ok(StreamData::new(json!({ event_type: payload }), content));
+                }
+            }
+        };
+
+        Ok(self
+            .start_run()
+            .trace_llm_stream(messages, Box::pin(stream)))
+    }
+
+    fn add_options(&mut self, options: CallOptions) {
+        self.options.merge_options(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+    use serde_json::json;
+    use tokio::test;
+
+    use super::*;
+    use crate::{
+        language_models::{FinishReason, LLMErrorClass},
+        llm::bedrock::event_stream::encode_message,
+        schemas::FunctionDefinition,
+    };
+
+    fn bedrock(server: &mockito::Server) -> Bedrock {
+        Bedrock::new()
+            .with_region("us-east-1")
+            .with_endpoint(server.url())
+            .with_credentials(Credentials::new("AKID", "secret", None, None, "test"))
+    }
+
+    #[test]
+    async fn test_bedrock_converse() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock(
+                "POST",
+                "/model/anthropic.claude-3-haiku-20240307-v1%3A0/converse",
+            )
+            .match_header(
+                "authorization",
+                Matcher::Regex(
+                    "^AWS4-HMAC-SHA256 Credential=AKID/\\d{8}/us-east-1/bedrock/aws4_request, \
+                     SignedHeaders=content-type;host;x-amz-date, Signature=[0-9a-f]{64}$"
+                        .into(),
+                ),
+            )
+            .match_body(Matcher::Json(json!({
+                "system": [{"text": "Be brief"}],
+                "messages": [
+                    {"role": "user", "content": [{"text": "What's 2+2?"}]},
+                    {"role": "assistant", "content": [
+                        {"toolUse": {"toolUseId": "t1", "name": "calculator", "input": {"expression": "2+2"}}},
+                    ]},
+                    {"role": "user", "content": [
+                        {"toolResult": {"toolUseId": "t1", "content": [{"text": "4"}]}},
+                    ]},
+                ],
+                "inferenceConfig": {"maxTokens": 100},
+                "toolConfig": {
+                    "tools": [{"toolSpec": {
+                        "name": "calculator",
+                        "description": "Makes calculations",
+                        "inputSchema": {"json": {"type": "object"}},
+                    }}],
+                    "toolChoice": {"auto": {}},
+                },
+            })))
+            .with_body(
+                json!({
+                    "output": {"message": {"role": "assistant", "content": [
+                        {"text": "Let me check"},
+                        {"toolUse": {"toolUseId": "t2", "name": "calculator", "input": {"expression": "4*1"}}},
+                    ]}},
+                    "stopReason": "tool_use",
+                    "usage": {"inputTokens": 20, "outputTokens": 5, "totalTokens": 25},
+                    "metrics": {"latencyMs": 100},
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let llm =
+            bedrock(&server).with_options(CallOptions::new().with_max_tokens(100).with_functions(
+                vec![FunctionDefinition::new(
+                    "calculator",
+                    "Makes calculations",
+                    json!({"type": "object"}),
+                )],
+            ));
+        let result = LLM::generate(
+            &llm,
+            &[
+                Message::new_system_message("Be brief"),
+                Message::new_human_message("What's 2+2?"),
+                Message::new_ai_message("").with_tool_calls(json!([{
+                    "id": "t1",
+                    "type": "function",
+                    "function": {"name": "calculator", "arguments": "{\"expression\":\"2+2\"}"},
+                }])),
+                Message::new_tool_message("4", "t1"),
+            ],
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.generation, "Let me check");
+        assert_eq!(result.finish_reason, Some(FinishReason::ToolCalls));
+        assert_eq!(result.tool_calls[0].arguments, json!({"expression": "4*1"}));
+        assert_eq!(result.tokens.unwrap().total_tokens, 25);
+    }
+
+    #[test]
+    async fn test_bedrock_converse_stream() {
+        let mut body = Vec::new();
+        for (event_type, payload) in [
+            ("messageStart", json!({"role": "assistant"})),
+            (
+                "contentBlockDelta",
+                json!({"contentBlockIndex": 0, "delta": {"text": "Hello"}}),
+            ),
+            (
+                "contentBlockDelta",
+                json!({"contentBlockIndex": 0, "delta": {"text": " world"}}),
+            ),
+            ("messageStop", json!({"stopReason": "end_turn"})),
+            (
+                "metadata",
+                json!({"usage": {"inputTokens": 3, "outputTokens": 2, "totalTokens": 5}}),
+            ),
+        ] {
+            body.extend(encode_message(
+                &[(":event-type", event_type), (":message-type", "event")],
+                payload.to_string().as_bytes(),
+            ));
+        }
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", Matcher::Regex("/converse-stream$".into()))
+            .with_header("content-type", "application/vnd.amazon.eventstream")
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let tokens = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let streamed = tokens.clone();
+        let llm = bedrock(&server).with_options(CallOptions::new().with_streaming_func(
+            move |token: String| {
+                streamed.lock().unwrap().push(token);
+                async { Ok(()) }
+            },
+        ));
+        let result = LLM::generate(&llm, &[Message::new_human_message("Hi")])
+            .await
+            .unwrap();
+
+        assert_eq!(result.generation, "Hello world");
+        assert_eq!(*tokens.lock().unwrap(), vec!["Hello", " world"]);
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(result.tokens.unwrap().total_tokens, 5);
+    }
+
+    #[test]
+    async fn test_bedrock_stream_exception() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", Matcher::Any)
+            .with_body(encode_message(
+                &[
+                    (":message-type", "exception"),
+                    (":exception-type", "throttlingException"),
+                ],
+                br#"{"message": "Too many requests"}"#,
+            ))
+            .create_async()
+            .await;
+
+        let mut stream = bedrock(&server)
+            .stream(&[Message::new_human_message("Hi")])
+            .await
+            .unwrap();
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(error.class(), Some(LLMErrorClass::RateLimit));
+        assert!(error.to_string().contains("Too many requests"));
+    }
+
+    #[test]
+    async fn test_bedrock_api_error() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", Matcher::Any)
+            .with_status(403)
+            .with_body(r#"{"message": "The security token included in the request is invalid."}"#)
+            .create_async()
+            .await;
+
+        let error = LLM::generate(&bedrock(&server), &[Message::new_human_message("Hi")])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, LLMError::AuthenticationFailed(_)));
+    }
+
+    #[test]
+    async fn test_expires_soon() {
+        let credentials = |expiry| Credentials::new("AKID", "secret", None, expiry, "test");
+        assert!(!expires_soon(&credentials(None)));
+        assert!(expires_soon(&credentials(Some(
+            SystemTime::now() + Duration::from_secs(60)
+        ))));
+        assert!(!expires_soon(&credentials(Some(
+            SystemTime::now() + Duration::from_secs(3600)
+        ))));
+    }
+}
@@ -0,0 +1,24 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BedrockError {
+    #[error("Bedrock API error {status}: {message}")]
+    ApiError { status: u16, message: String },
+
+    /// An exception sent in the event stream of `converse-stream`, e.g.
+    /// `throttlingException`.
+    #[error("Bedrock stream exception {exception_type}: {message}")]
+    StreamException {
+        exception_type: String,
+        message: String,
+    },
+
+    #[error("AWS credentials error: {0}")]
+    CredentialsError(String),
+
+    #[error("AWS signing error: {0}")]
+    SigningError(String),
+
+    #[error("Invalid event stream: {0}")]
+    EventStreamError(String),
+}
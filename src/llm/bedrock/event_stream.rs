@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use super::BedrockError;
+
+/// A message of the `application/vnd.amazon.eventstream` encoding of the streamed
+/// responses, with its string headers, e.g. `:event-type`.
+#[derive(Debug)]
+pub(crate) struct EventStreamMessage {
+    pub headers: HashMap<String, String>,
+    pub payload: Vec<u8>,
+}
+
+impl EventStreamMessage {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+// Total length, headers length and prelude checksum.
+const PRELUDE_LENGTH: usize = 12;
+const CHECKSUM_LENGTH: usize = 4;
+
+/// Takes the first complete message out of `buffer`, `None` until it is received.
+///
+/// The CRC32 checksums of the prelude and of the message are validated.
+pub(crate) fn decode_message(
+    buffer: &mut Vec<u8>,
+) -> Result<Option<EventStreamMessage>, BedrockError> {
+    if buffer.len() < PRELUDE_LENGTH {
+        return Ok(None);
+    }
+    if crc32fast::hash(&buffer[..8]) != read_u32(&buffer[8..12]) {
+        return Err(BedrockError::EventStreamError(
+            "invalid prelude checksum".to_string(),
+        ));
+    }
+    let total_length = read_u32(&buffer[0..4]) as usize;
+    let headers_length = read_u32(&buffer[4..8]) as usize;
+    if total_length < PRELUDE_LENGTH + headers_length + CHECKSUM_LENGTH {
+        return Err(BedrockError::EventStreamError(format!(
+            "invalid message length {}",
+            total_length
+        )));
+    }
+    if buffer.len() < total_length {
+        return Ok(None);
+    }
+    let message: Vec<u8> = buffer.drain(..total_length).collect();
+    let checksum_start = total_length - CHECKSUM_LENGTH;
+    if crc32fast::hash(&message[..checksum_start]) != read_u32(&message[checksum_start..]) {
+        return Err(BedrockError::EventStreamError(
+            "invalid message checksum".to_string(),
+        ));
+    }
+    let headers_end = PRELUDE_LENGTH + headers_length;
+    Ok(Some(EventStreamMessage {
+        headers: decode_headers(&message[PRELUDE_LENGTH..headers_end])?,
+        payload: message[headers_end..checksum_start].to_vec(),
+    }))
+}
+
+/// The headers with a string value, the other ones are skipped.
+fn decode_headers(mut bytes: &[u8]) -> Result<HashMap<String, String>, BedrockError> {
+    let truncated = || BedrockError::EventStreamError("truncated headers".to_string());
+    let mut headers = HashMap::new();
+    while !bytes.is_empty() {
+        let name_length = bytes[0] as usize;
+        let name = bytes.get(1..1 + name_length).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).to_string();
+        let value_type = *bytes.get(1 + name_length).ok_or_else(truncated)?;
+        let rest = &bytes[2 + name_length..];
+        let value_length = match value_type {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            6 | 7 => {
+                let length = rest.get(..2).ok_or_else(truncated)?;
+                2 + u16::from_be_bytes([length[0], length[1]]) as usize
+            }
+            value_type => {
+                return Err(BedrockError::EventStreamError(format!(
+                    "unknown header type {}",
+                    value_type
+                )))
+            }
+        };
+        let value = rest.get(..value_length).ok_or_else(truncated)?;
+        if value_type == 7 {
+            headers.insert(name, String::from_utf8_lossy(&value[2..]).to_string());
+        }
+        bytes = &rest[value_length..];
+    }
+    Ok(headers)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+pub(crate) fn encode_message(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+    let mut encoded_headers = Vec::new();
+    for (name, value) in headers {
+        encoded_headers.push(name.len() as u8);
+        encoded_headers.extend_from_slice(name.as_bytes());
+        encoded_headers.push(7);
+        encoded_headers.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        encoded_headers.extend_from_slice(value.as_bytes());
+    }
+    let total_length = PRELUDE_LENGTH + encoded_headers.len() + payload.len() + CHECKSUM_LENGTH;
+    let mut message = Vec::new();
+    message.extend_from_slice(&(total_length as u32).to_be_bytes());
+    message.extend_from_slice(&(encoded_headers.len() as u32).to_be_bytes());
+    message.extend_from_slice(&crc32fast::hash(&message).to_be_bytes());
+    message.extend_from_slice(&encoded_headers);
+    message.extend_from_slice(payload);
+    message.extend_from_slice(&crc32fast::hash(&message).to_be_bytes());
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_message() {
+        let mut buffer = encode_message(
+            &[
+                (":event-type", "contentBlockDelta"),
+                (":message-type", "event"),
+            ],
+            br#"{"delta":{"text":"Hi"}}"#,
+        );
+        buffer.extend(encode_message(&[(":event-type", "messageStop")], b"{}"));
+        let second = buffer.split_off(buffer.len() - 10);
+
+        let message = decode_message(&mut buffer).unwrap().unwrap();
+        assert_eq!(message.header(":event-type"), Some("contentBlockDelta"));
+        assert_eq!(message.header(":message-type"), Some("event"));
+        assert_eq!(message.payload, br#"{"delta":{"text":"Hi"}}"#);
+
+        // The second message is incomplete until the rest of it is received.
+        assert!(decode_message(&mut buffer).unwrap().is_none());
+        buffer.extend(second);
+        let message = decode_message(&mut buffer).unwrap().unwrap();
+        assert_eq!(message.header(":event-type"), Some("messageStop"));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_decode_message_checksums() {
+        let message = encode_message(&[(":event-type", "messageStop")], b"{}");
+
+        let mut prelude = message.clone();
+        prelude[3] ^= 1;
+        assert!(decode_message(&mut prelude)
+            .unwrap_err()
+            .to_string()
+            .contains("prelude checksum"));
+
+        let mut payload = message;
+        let last = payload.len() - CHECKSUM_LENGTH - 1;
+        payload[last] ^= 1;
+        assert!(decode_message(&mut payload)
+            .unwrap_err()
+            .to_string()
+            .contains("message checksum"));
+    }
+}
@@ -0,0 +1,10 @@
+mod models;
+
+mod client;
+pub use client::*;
+
+mod error;
+pub use error::*;
+
+mod event_stream;
+mod signing;
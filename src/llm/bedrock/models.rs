@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    language_models::{options::CallOptions, FinishReason, TokenUsage},
+    schemas::{FunctionCallBehavior, Message, ToolCall},
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Payload {
+    pub messages: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inference_config: Option<InferenceConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_config: Option<Value>,
+}
+
+impl Payload {
+    pub fn new(messages: &[Message], options: &CallOptions) -> Self {
+        let (system, messages) = converse_messages(messages);
+        Self {
+            messages,
+            system: system.map(|system| vec![json!({ "text": system })]),
+            inference_config: InferenceConfig::from_options(options),
+            tool_config: tool_config(options),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InferenceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+}
+
+impl InferenceConfig {
+    fn from_options(options: &CallOptions) -> Option<Self> {
+        let config = Self {
+            max_tokens: options.max_tokens,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            stop_sequences: options.stop_words.clone(),
+        };
+        let is_empty = config.max_tokens.is_none()
+            && config.temperature.is_none()
+            && config.top_p.is_none()
+            && config.stop_sequences.is_none();
+        (!is_empty).then_some(config)
+    }
+}
+
+fn tool_config(options: &CallOptions) -> Option<Value> {
+    let functions = options.functions.as_ref().filter(|f| !f.is_empty())?;
+    // The Converse API has no choice to disable the tools, they are not sent instead.
+    if matches!(
+        options.function_call_behavior,
+        Some(FunctionCallBehavior::None)
+    ) {
+        return None;
+    }
+    let tools: Vec<Value> = functions
+        .iter()
+        .map(|f| {
+            json!({
+                "toolSpec": {
+                    "name": f.name,
+                    "description": f.description,
+                    "inputSchema": { "json": f.parameters },
+                }
+            })
+        })
+        .collect();
+    Some(json!({ "tools": tools, "toolChoice": { "auto": {} } }))
+}
+
+/// The system prompt and the messages of the Converse API, from the ones of the
+/// Anthropic Messages API which have the same structure. The consecutive messages of a
+/// role are merged, e.g. the results of the tools called at once.
+fn converse_messages(messages: &[Message]) -> (Option<String>, Vec<Value>) {
+    let (system, messages) = Message::to_anthropic_messages(messages);
+    let mut converse: Vec<Value> = Vec::new();
+    for message in messages {
+        let role = message["role"].clone();
+        let content: Vec<Value> = match &message["content"] {
+            Value::String(text) => vec![json!({ "text": text })],
+            Value::Array(blocks) => blocks.iter().filter_map(converse_block).collect(),
+            _ => Vec::new(),
+        };
+        match converse.last_mut() {
+            Some(last) if last["role"] == role => {
+                if let Some(blocks) = last["content"].as_array_mut() {
+                    blocks.extend(content);
+                }
+            }
+            _ => converse.push(json!({ "role": role, "content": content })),
+        }
+    }
+    (system, converse)
+}
+
+fn converse_block(block: &Value) -> Option<Value> {
+    match block["type"].as_str()? {
+        "text" => Some(json!({ "text": block["text"] })),
+        "image" => {
+            let source = &block["source"];
+            if source["type"] != "base64" {
+                log::warn!("Bedrock only supports base64 encoded images");
+                return None;
+            }
+            let format = source["media_type"]
+                .as_str()
+                .and_then(|media_type| media_type.strip_prefix("image/"))
+                .unwrap_or("png");
+            Some(json!({
+                "image": { "format": format, "source": { "bytes": source["data"] } }
+            }))
+        }
+        "tool_use" => Some(json!({
+            "toolUse": {
+                "toolUseId": block["id"],
+                "name": block["name"],
+                "input": block["input"],
+            }
+        })),
+        "tool_result" => Some(json!({
+            "toolResult": {
+                "toolUseId": block["tool_use_id"],
+                "content": [{ "text": block["content"] }],
+            }
+        })),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApiResponse {
+    pub output: Output,
+    pub stop_reason: Option<String>,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Output {
+    pub message: Option<OutputMessage>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct OutputMessage {
+    #[serde(default)]
+    pub content: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Usage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub total_tokens: u32,
+}
+
+impl From<Usage> for TokenUsage {
+    fn from(usage: Usage) -> Self {
+        TokenUsage {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+            total_tokens: usage.total_tokens,
+        }
+    }
+}
+
+impl ApiResponse {
+    pub fn content(&self) -> &[Value] {
+        self.output
+            .message
+            .as_ref()
+            .map(|message| message.content.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn text(&self) -> String {
+        self.content()
+            .iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect()
+    }
+
+    pub fn tool_calls(&self) -> Vec<ToolCall> {
+        self.content()
+            .iter()
+            .filter_map(|block| block.get("toolUse"))
+            .map(|tool_use| ToolCall {
+                id: tool_use["toolUseId"].as_str().unwrap_or_default().into(),
+                name: tool_use["name"].as_str().unwrap_or_default().into(),
+                arguments: tool_use["input"].clone(),
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn finish_reason(stop_reason: &str) -> FinishReason {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => FinishReason::Stop,
+        "max_tokens" => FinishReason::Length,
+        "tool_use" => FinishReason::ToolCalls,
+        "guardrail_intervened" | "content_filtered" => FinishReason::ContentFilter,
+        reason => FinishReason::Other(reason.to_string()),
+    }
+}
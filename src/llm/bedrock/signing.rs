@@ -0,0 +1,96 @@
+use std::time::SystemTime;
+
+use aws_credential_types::Credentials;
+use aws_sigv4::{
+    http_request::{self, SignableBody, SignableRequest, SigningSettings},
+    sign::v4,
+};
+
+use super::BedrockError;
+
+const SERVICE: &str = "bedrock";
+
+/// Signs a request to Bedrock with AWS Signature Version 4, returning the headers to add
+/// to it.
+///
+/// `headers` are the headers to sign besides `host` and the `x-amz-*` ones added by the
+/// signature.
+pub(crate) fn sign(
+    credentials: &Credentials,
+    region: &str,
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    time: SystemTime,
+) -> Result<Vec<(String, String)>, BedrockError> {
+    let identity = credentials.clone().into();
+    let params = v4::SigningParams::builder()
+        .identity(&identity)
+        .region(region)
+        .name(SERVICE)
+        .time(time)
+        .settings(SigningSettings::default())
+        .build()
+        .map_err(|e| BedrockError::SigningError(e.to_string()))?
+        .into();
+    let request = SignableRequest::new(
+        method,
+        url,
+        headers.iter().copied(),
+        SignableBody::Bytes(body),
+    )
+    .map_err(|e| BedrockError::SigningError(e.to_string()))?;
+    let (instructions, _) = http_request::sign(request, &params)
+        .map_err(|e| BedrockError::SigningError(e.to_string()))?
+        .into_parts();
+    Ok(instructions
+        .headers()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        let credentials = Credentials::new(
+            "AKIDEXAMPLE",
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            Some("session".to_string()),
+            None,
+            "test",
+        );
+        let mut headers = sign(
+            &credentials,
+            "us-east-1",
+            "POST",
+            "https://bedrock-runtime.us-east-1.amazonaws.com/model/anthropic.claude-3-haiku-20240307-v1%3A0/converse",
+            &[("content-type", "application/json")],
+            br#"{"messages":[]}"#,
+            // 2024-05-01T12:00:00Z
+            UNIX_EPOCH + Duration::from_secs(1_714_564_800),
+        )
+        .unwrap();
+        headers.sort();
+
+        assert_eq!(
+            headers,
+            vec![
+                (
+                    "authorization".to_string(),
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240501/us-east-1/bedrock/aws4_request, \
+                     SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, \
+                     Signature=683577821767bcf3f69438fd546e009651587eaf69c8806b668d3c8a89b49190"
+                        .to_string()
+                ),
+                ("x-amz-date".to_string(), "20240501T120000Z".to_string()),
+                ("x-amz-security-token".to_string(), "session".to_string()),
+            ]
+        );
+    }
+}
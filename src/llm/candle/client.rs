@@ -0,0 +1,797 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use async_trait::async_trait;
+use candle_core::{
+    quantized::{gguf_file, tokenizer::TokenizerFromGguf},
+    DType, Device, Tensor,
+};
+use candle_transformers::{
+    generation::{LogitsProcessor, Sampling},
+    models::{quantized_llama, quantized_qwen2, quantized_qwen3},
+};
+use futures::{Stream, StreamExt};
+use serde_json::json;
+use tokenizers::Tokenizer;
+use tokio::sync::{mpsc, OnceCell};
+
+use crate::{
+    callbacks::{RunManager, RunType},
+    language_models::{
+        llm::LLM, options::CallOptions, FinishReason, GenerateResult, LLMError, TokenUsage,
+    },
+    llm::CandleError,
+    schemas::{Message, StreamData},
+};
+
+use super::ChatTemplate;
+
+/// The sampling options the generation has a parameter for.
+const SUPPORTED_OPTIONS: &[&str] = &[
+    "temperature",
+    "stop_words",
+    "top_p",
+    "top_k",
+    "seed",
+    "repetition_penalty",
+    "frequency_penalty",
+    "presence_penalty",
+    "logit_bias",
+];
+
+/// The temperature of llama.cpp, when the options have none.
+const DEFAULT_TEMPERATURE: f32 = 0.8;
+
+/// GGUF models run in process by [candle](https://github.com/huggingface/candle), without
+/// any network access.
+///
+/// The architectures are the ones candle has a quantized model for: `llama`, which
+/// includes Mistral and the other models converted as Llama, `qwen2` and `qwen3`. The
+/// tokenizer is the one of the GGUF file, which candle reads for the byte-level BPE
+/// tokenizers, e.g. Qwen or Llama 3; the other models, e.g. Mistral, need their
+/// `tokenizer.json` given with `with_tokenizer`.
+///
+/// The model is loaded by the first call, once for the clones of the LLM, which share it
+/// and generate one at a time. The options of `CallOptions` are `max_tokens`,
+/// `temperature`, 0.8 by default and the most likely tokens when 0, `top_p`, `top_k`,
+/// `seed`, the penalties of the tokens generated and the logit bias. The stop words end
+/// the generation, and are not part of it.
+///
+/// # Example
+/// ```rust,ignore
+/// let llm = Candle::new("models/qwen2.5-7b-instruct-q4_k_m.gguf")
+///     .with_chat_template(ChatTemplate::ChatMl);
+/// let response = llm.invoke("Why is the sky blue?").await?;
+/// ```
+#[derive(Clone)]
+pub struct Candle {
+    model_path: PathBuf,
+    tokenizer_path: Option<PathBuf>,
+    device: Device,
+    options: CallOptions,
+    chat_template: ChatTemplate,
+    context_size: Option<usize>,
+    model: Arc<OnceCell<Arc<Model>>>,
+}
+
+impl Candle {
+    pub fn new<P: Into<PathBuf>>(model_path: P) -> Self {
+        Self {
+            model_path: model_path.into(),
+            tokenizer_path: None,
+            device: Device::Cpu,
+            options: CallOptions::default(),
+            chat_template: ChatTemplate::ChatMl,
+            context_size: None,
+            model: Arc::default(),
+        }
+    }
+
+    /// The `tokenizer.json` of the model, for the tokenizers candle can't read from the
+    /// GGUF file.
+    pub fn with_tokenizer<P: Into<PathBuf>>(mut self, tokenizer_path: P) -> Self {
+        self.tokenizer_path = Some(tokenizer_path.into());
+        self.model = Arc::default();
+        self
+    }
+
+    /// The device running the model, the CPU by default. The GPUs need the `cuda` or
+    /// `metal` feature of `candle-core`.
+    pub fn with_device(mut self, device: Device) -> Self {
+        self.device = device;
+        self.model = Arc::default();
+        self
+    }
+
+    pub fn with_options(mut self, options: CallOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_chat_template(mut self, chat_template: ChatTemplate) -> Self {
+        self.chat_template = chat_template;
+        self
+    }
+
+    /// The maximum number of tokens of the prompt and the answer, the context length of the
+    /// model by default.
+    pub fn with_context_size(mut self, context_size: usize) -> Self {
+        self.context_size = Some(context_size);
+        self
+    }
+
+    fn model_name(&self) -> String {
+        self.model_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    fn start_run(&self) -> RunManager {
+        self.options
+            .callbacks
+            .clone()
+            .unwrap_or_default()
+            .start_run(self.model_name(), RunType::Llm)
+    }
+
+    async fn model(&self) -> Result<Arc<Model>, LLMError> {
+        self.model
+            .get_or_try_init(|| async {
+                let path = self.model_path.clone();
+                let tokenizer = self.tokenizer_path.clone();
+                let device = self.device.clone();
+                // Reading the weights blocks for a while.
+                let model = tokio::task::spawn_blocking(move || {
+                    Model::load(&path, tokenizer.as_deref(), &device)
+                })
+                .await
+                .map_err(|e| LLMError::OtherError(e.to_string()))??;
+                Ok::<_, LLMError>(Arc::new(model))
+            })
+            .await
+            .cloned()
+    }
+
+    fn generation_options(&self) -> GenerationOptions {
+        let options = &self.options;
+        let mut stops = options.stop_words.clone().unwrap_or_default();
+        stops.extend(self.chat_template.stop_sequence().map(str::to_string));
+        GenerationOptions {
+            max_tokens: options.max_tokens.map(usize::from),
+            context_size: self.context_size,
+            stops,
+            sampling: sampling(options),
+            seed: options
+                .seed
+                .map(|seed| seed as u64)
+                .unwrap_or_else(|| uuid::Uuid::new_v4().as_u64_pair().0),
+            penalties: Penalties {
+                logit_bias: options.logit_bias.clone().unwrap_or_default(),
+                repetition: options.repetition_penalty,
+                frequency: options.frequency_penalty,
+                presence: options.presence_penalty,
+            },
+        }
+    }
+}
+
+fn sampling(options: &CallOptions) -> Sampling {
+    let temperature = f64::from(options.temperature.unwrap_or(DEFAULT_TEMPERATURE));
+    if temperature <= 0.0 {
+        return Sampling::ArgMax;
+    }
+    match (options.top_k, options.top_p.map(f64::from)) {
+        (None, None) => Sampling::All { temperature },
+        (Some(k), None) => Sampling::TopK { k, temperature },
+        (None, Some(p)) => Sampling::TopP { p, temperature },
+        (Some(k), Some(p)) => Sampling::TopKThenTopP { k, p, temperature },
+    }
+}
+
+/// The weights of the architectures candle has a quantized model for.
+enum Weights {
+    Llama(quantized_llama::ModelWeights),
+    Qwen2(quantized_qwen2::ModelWeights),
+    Qwen3(quantized_qwen3::ModelWeights),
+}
+
+impl Weights {
+    /// The logits of the token following `input`, the tokens from `index_pos`.
+    fn forward(&mut self, input: &Tensor, index_pos: usize) -> candle_core::Result<Tensor> {
+        match self {
+            Weights::Llama(weights) => weights.forward(input, index_pos),
+            Weights::Qwen2(weights) => weights.forward(input, index_pos),
+            Weights::Qwen3(weights) => weights.forward(input, index_pos),
+        }
+    }
+
+    fn clear_kv_cache(&mut self) {
+        match self {
+            Weights::Llama(weights) => weights.clear_kv_cache(),
+            Weights::Qwen2(weights) => weights.clear_kv_cache(),
+            Weights::Qwen3(weights) => weights.clear_kv_cache(),
+        }
+    }
+}
+
+struct Model {
+    weights: Mutex<Weights>,
+    tokenizer: Tokenizer,
+    eos_token: Option<u32>,
+    context_size: usize,
+    device: Device,
+}
+
+impl Model {
+    fn load(path: &Path, tokenizer: Option<&Path>, device: &Device) -> Result<Self, CandleError> {
+        if !path.exists() {
+            return Err(CandleError::ModelNotFound(path.display().to_string()));
+        }
+        let mut file = File::open(path).map_err(candle_core::Error::from)?;
+        let content = gguf_file::Content::read(&mut file)?;
+        let metadata = |key: &str| content.metadata.get(key);
+        let architecture = metadata("general.architecture")
+            .and_then(|value| value.to_string().ok())
+            .cloned()
+            .unwrap_or_default();
+        let mut context_size = metadata(&format!("{}.context_length", architecture))
+            .and_then(|value| value.to_u32().ok())
+            .map(|value| value as usize)
+            .unwrap_or(quantized_llama::MAX_SEQ_LEN);
+        let eos_token =
+            metadata("tokenizer.ggml.eos_token_id").and_then(|value| value.to_u32().ok());
+        let tokenizer = match tokenizer {
+            Some(tokenizer) => Tokenizer::from_file(tokenizer),
+            None => Tokenizer::from_gguf(&content).map_err(Into::into),
+        }
+        .map_err(|e| CandleError::TokenizerError(e.to_string()))?;
+
+        let weights = match architecture.as_str() {
+            "llama" => {
+                // The rotary embeddings of candle's Llama stop at MAX_SEQ_LEN.
+                context_size = context_size.min(quantized_llama::MAX_SEQ_LEN);
+                Weights::Llama(quantized_llama::ModelWeights::from_gguf(
+                    content, &mut file, device,
+                )?)
+            }
+            "qwen2" => Weights::Qwen2(quantized_qwen2::ModelWeights::from_gguf(
+                content, &mut file, device,
+            )?),
+            "qwen3" => Weights::Qwen3(quantized_qwen3::ModelWeights::from_gguf(
+                content, &mut file, device,
+            )?),
+            _ => return Err(CandleError::UnsupportedArchitecture(architecture)),
+        };
+        Ok(Self {
+            weights: Mutex::new(weights),
+            tokenizer,
+            eos_token,
+            context_size,
+            device: device.clone(),
+        })
+    }
+
+    /// Generates the answer to `prompt`, sending its text until the receiver is dropped.
+    fn generate(
+        &self,
+        prompt: &str,
+        options: GenerationOptions,
+        sender: &mpsc::UnboundedSender<String>,
+    ) -> Result<(FinishReason, TokenUsage), LLMError> {
+        let prompt = self
+            .tokenizer
+            .encode(prompt, true)
+            .map_err(|e| CandleError::TokenizerError(e.to_string()))?
+            .get_ids()
+            .to_vec();
+        if prompt.is_empty() {
+            Err(CandleError::TokenizerError(
+                "the prompt has no tokens".to_string(),
+            ))?
+        }
+        let context_size = options.context_size.unwrap_or(self.context_size);
+        if prompt.len() >= context_size {
+            return Err(LLMError::ContextLengthExceeded {
+                needed: Some(prompt.len()),
+                limit: Some(context_size),
+                message: format!(
+                    "the prompt has {} tokens, the context {}",
+                    prompt.len(),
+                    context_size
+                ),
+            });
+        }
+
+        let (finish_reason, generated) =
+            self.sample(&prompt, context_size, options, |text| match text {
+                text if text.is_empty() => !sender.is_closed(),
+                text => sender.send(text).is_ok(),
+            })?;
+        let usage = TokenUsage {
+            prompt_tokens: prompt.len() as u32,
+            completion_tokens: generated as u32,
+            total_tokens: (prompt.len() + generated) as u32,
+        };
+        Ok((finish_reason, usage))
+    }
+
+    /// Samples the tokens following `prompt`, giving their text to `emit` until it returns
+    /// false. Returns why the generation stopped, and the number of tokens generated.
+    fn sample(
+        &self,
+        prompt: &[u32],
+        context_size: usize,
+        options: GenerationOptions,
+        mut emit: impl FnMut(String) -> bool,
+    ) -> Result<(FinishReason, usize), CandleError> {
+        // The weights of a generation which panicked are still fine, their cache is
+        // cleared below.
+        let mut weights = self.weights.lock().unwrap_or_else(PoisonError::into_inner);
+        weights.clear_kv_cache();
+        let mut logits_processor = LogitsProcessor::from_sampling(options.seed, options.sampling);
+        let mut stops = StopSequences::new(options.stops);
+        let mut detokenizer = Detokenizer::new(&self.tokenizer);
+        let mut generated: Vec<u32> = Vec::new();
+        let mut input = prompt.to_vec();
+
+        let mut finish_reason = loop {
+            if options.max_tokens.is_some_and(|max| generated.len() >= max)
+                || prompt.len() + generated.len() >= context_size
+            {
+                break FinishReason::Length;
+            }
+            let index_pos = prompt.len() + generated.len() - input.len();
+            let logits = weights.forward(
+                &Tensor::new(input.as_slice(), &self.device)?.unsqueeze(0)?,
+                index_pos,
+            )?;
+            let mut logits = logits.squeeze(0)?.to_dtype(DType::F32)?.to_vec1::<f32>()?;
+            options.penalties.apply(&mut logits, &generated);
+            let token = logits_processor.sample(&Tensor::new(logits.as_slice(), &Device::Cpu)?)?;
+            if Some(token) == self.eos_token {
+                break FinishReason::Stop;
+            }
+            generated.push(token);
+            input = vec![token];
+
+            let text = stops.push(&detokenizer.push(token)?);
+            if !emit(text) || stops.stopped {
+                break FinishReason::Stop;
+            }
+        };
+
+        if !stops.stopped {
+            let mut text = stops.push(&detokenizer.finish()?);
+            if stops.stopped {
+                finish_reason = FinishReason::Stop;
+            } else {
+                text.push_str(&stops.finish());
+            }
+            emit(text);
+        }
+        Ok((finish_reason, generated.len()))
+    }
+}
+
+/// The options of a generation, taken from the `CallOptions` of the LLM.
+struct GenerationOptions {
+    max_tokens: Option<usize>,
+    context_size: Option<usize>,
+    stops: Vec<String>,
+    sampling: Sampling,
+    seed: u64,
+    penalties: Penalties,
+}
+
+/// The logit bias, and the penalties of the tokens already generated.
+#[derive(Default)]
+struct Penalties {
+    logit_bias: HashMap<u32, f32>,
+    /// Divides the positive logits and multiplies the negative ones, as llama.cpp does.
+    repetition: Option<f32>,
+    /// Subtracted once per occurrence of the token.
+    frequency: Option<f32>,
+    /// Subtracted once when the token occurs.
+    presence: Option<f32>,
+}
+
+impl Penalties {
+    fn apply(&self, logits: &mut [f32], generated: &[u32]) {
+        for (token, bias) in &self.logit_bias {
+            if let Some(logit) = logits.get_mut(*token as usize) {
+                *logit += bias;
+            }
+        }
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for token in generated {
+            *counts.entry(*token).or_default() += 1;
+        }
+        for (token, count) in counts {
+            let Some(logit) = logits.get_mut(token as usize) else {
+                continue;
+            };
+            if let Some(penalty) = self.repetition {
+                *logit = if *logit > 0.0 {
+                    *logit / penalty
+                } else {
+                    *logit * penalty
+                };
+            }
+            if let Some(penalty) = self.frequency {
+                *logit -= count as f32 * penalty;
+            }
+            if let Some(penalty) = self.presence {
+                *logit -= penalty;
+            }
+        }
+    }
+}
+
+/// Decodes the tokens as they are generated, holding back the ones ending in the middle of
+/// a character.
+struct Detokenizer<'a> {
+    tokenizer: &'a Tokenizer,
+    tokens: Vec<u32>,
+    /// The start of the tokens decoded with the new ones, for the decoders depending on
+    /// the previous tokens.
+    prev_index: usize,
+    /// The end of the tokens whose text has been returned.
+    current_index: usize,
+}
+
+impl<'a> Detokenizer<'a> {
+    fn new(tokenizer: &'a Tokenizer) -> Self {
+        Self {
+            tokenizer,
+            tokens: Vec::new(),
+            prev_index: 0,
+            current_index: 0,
+        }
+    }
+
+    fn decode(&self, tokens: &[u32]) -> Result<String, CandleError> {
+        self.tokenizer
+            .decode(tokens, false)
+            .map_err(|e| CandleError::TokenizerError(e.to_string()))
+    }
+
+    /// The text which can be emitted after receiving `token`.
+    fn push(&mut self, token: u32) -> Result<String, CandleError> {
+        let prev_text = self.decode(&self.tokens[self.prev_index..self.current_index])?;
+        self.tokens.push(token);
+        let text = self.decode(&self.tokens[self.prev_index..])?;
+        if text.ends_with('\u{FFFD}') {
+            return Ok(String::new());
+        }
+        match text.get(prev_text.len()..) {
+            Some(new) if !new.is_empty() => {
+                self.prev_index = self.current_index;
+                self.current_index = self.tokens.len();
+                Ok(new.to_string())
+            }
+            _ => Ok(String::new()),
+        }
+    }
+
+    fn finish(&mut self) -> Result<String, CandleError> {
+        let prev_text = self.decode(&self.tokens[self.prev_index..self.current_index])?;
+        let text = self.decode(&self.tokens[self.prev_index..])?;
+        self.current_index = self.tokens.len();
+        Ok(text.get(prev_text.len()..).unwrap_or_default().to_string())
+    }
+}
+
+/// Holds back the end of the generation while it may be the start of a stop sequence.
+struct StopSequences {
+    stops: Vec<String>,
+    pending: String,
+    stopped: bool,
+}
+
+impl StopSequences {
+    fn new(stops: Vec<String>) -> Self {
+        Self {
+            stops: stops.into_iter().filter(|stop| !stop.is_empty()).collect(),
+            pending: String::new(),
+            stopped: false,
+        }
+    }
+
+    /// The text which can be emitted after receiving `text`.
+    fn push(&mut self, text: &str) -> String {
+        self.pending.push_str(text);
+        if let Some(index) = self
+            .stops
+            .iter()
+            .filter_map(|stop| self.pending.find(stop.as_str()))
+            .min()
+        {
+            self.stopped = true;
+            let text = self.pending[..index].to_string();
+            self.pending.clear();
+            return text;
+        }
+        let held = self
+            .stops
+            .iter()
+            .flat_map(|stop| stop.char_indices().skip(1).map(|(end, _)| &stop[..end]))
+            .filter(|prefix| self.pending.ends_with(prefix))
+            .map(str::len)
+            .max()
+            .unwrap_or_default();
+        self.pending.drain(..self.pending.len() - held).collect()
+    }
+
+    fn finish(&mut self) -> String {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[async_trait]
+impl LLM for Candle {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let mut result = GenerateResult {
+            model: Some(self.model_name()),
+            ..Default::default()
+        };
+        let mut stream = self.stream(messages).await?;
+        while let Some(data) = stream.next().await {
+            let data = data?;
+            if let Some(finish_reason) = data.value.get("finish_reason") {
+                result.finish_reason = serde_json::from_value(finish_reason.clone()).ok();
+                result.tokens = serde_json::from_value(data.value["usage"].clone()).ok();
+            }
+            if data.content.is_empty() {
+                continue;
+            }
+            result.generation.push_str(&data.content);
+            if let Some(func) = &self.options.streaming_func {
+                let mut func = func.lock().await;
+                let _ = func(data.content).await;
+            }
+        }
+        Ok(result)
+    }
+
+    /// The values of the stream are `{"content": ...}`, then an empty content with the
+    /// `finish_reason` and the `usage` of the generation.
+    async fn stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
+        self.options.check_supported("candle", SUPPORTED_OPTIONS)?;
+        let model = self.model().await?;
+        let prompt = self.chat_template.format(messages);
+        let options = self.generation_options();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let generation =
+            tokio::task::spawn_blocking(move || model.generate(&prompt, options, &sender));
+
+        let stream = async_stream::stream! {
+            while let Some(text) = receiver.recv().await {
+                yield Ok(StreamData::new(json!({ "content": text }), text));
+            }
+            match generation.await {
+                Ok(Ok((finish_reason, usage))) => yield Ok(StreamData::new(
+                    json!({ "content": "", "finish_reason": finish_reason, "usage": usage }),
+                    "",
+                )),
+                Ok(Err(e)) => yield Err(e),
+                Err(e) => yield Err(LLMError::OtherError(e.to_string())),
+            }
+        };
+
+        Ok(self
+            .start_run()
+            .trace_llm_stream(messages, Box::pin(stream)))
+    }
+
+    fn add_options(&mut self, options: CallOptions) {
+        self.options.merge_options(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use candle_core::quantized::{gguf_file::Value, GgmlDType, QTensor};
+    use tokio::test;
+
+    use super::*;
+
+    /// The vocabulary of the test model, "Ã" and "©" being the bytes of "é".
+    const TOKENS: &[&str] = &["ab", "a", "b", "H", "i", "Ã", "©", "<|im_end|>"];
+    const EOS: u32 = 7;
+
+    /// A tiny Qwen2 model whose weights are zeros, so that its logits are zeros and the
+    /// logit bias chooses the tokens.
+    fn model_file() -> PathBuf {
+        let tensor = |shape: &[usize]| {
+            let zeros = Tensor::zeros(shape.to_vec(), DType::F32, &Device::Cpu).unwrap();
+            QTensor::quantize(&zeros, GgmlDType::F32).unwrap()
+        };
+        let (vocab, hidden, ff) = (TOKENS.len(), 4, 8);
+        let mut tensors = vec![
+            ("token_embd.weight".to_string(), tensor(&[vocab, hidden])),
+            ("output_norm.weight".to_string(), tensor(&[hidden])),
+        ];
+        for (name, shape) in [
+            ("attn_q.weight", vec![hidden, hidden]),
+            ("attn_k.weight", vec![hidden, hidden]),
+            ("attn_v.weight", vec![hidden, hidden]),
+            ("attn_q.bias", vec![hidden]),
+            ("attn_k.bias", vec![hidden]),
+            ("attn_v.bias", vec![hidden]),
+            ("attn_output.weight", vec![hidden, hidden]),
+            ("ffn_gate.weight", vec![ff, hidden]),
+            ("ffn_up.weight", vec![ff, hidden]),
+            ("ffn_down.weight", vec![hidden, ff]),
+            ("attn_norm.weight", vec![hidden]),
+            ("ffn_norm.weight", vec![hidden]),
+        ] {
+            tensors.push((format!("blk.0.{}", name), tensor(&shape)));
+        }
+        let string = |s: &str| Value::String(s.to_string());
+        let metadata = [
+            ("general.architecture", string("qwen2")),
+            ("qwen2.attention.head_count", Value::U32(1)),
+            ("qwen2.attention.head_count_kv", Value::U32(1)),
+            ("qwen2.embedding_length", Value::U32(hidden as u32)),
+            ("qwen2.context_length", Value::U32(16)),
+            ("qwen2.block_count", Value::U32(1)),
+            ("qwen2.attention.layer_norm_rms_epsilon", Value::F32(1e-6)),
+            ("tokenizer.ggml.model", string("gpt2")),
+            ("tokenizer.ggml.pre", string("qwen2")),
+            (
+                "tokenizer.ggml.tokens",
+                Value::Array(TOKENS.iter().map(|token| string(token)).collect()),
+            ),
+            ("tokenizer.ggml.merges", Value::Array(vec![string("a b")])),
+            ("tokenizer.ggml.eos_token_id", Value::U32(EOS)),
+        ];
+
+        let dir = std::env::temp_dir().join(format!("candle-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("model.gguf");
+        let mut file = File::create(&path).unwrap();
+        gguf_file::write(
+            &mut file,
+            &metadata
+                .iter()
+                .map(|(key, value)| (*key, value))
+                .collect::<Vec<_>>(),
+            &tensors
+                .iter()
+                .map(|(name, tensor)| (name.as_str(), tensor))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        path
+    }
+
+    fn options(logit_bias: &[(u32, f32)]) -> CallOptions {
+        CallOptions::new()
+            .with_temperature(0.0)
+            .with_logit_bias(logit_bias.iter().copied().collect())
+    }
+
+    #[test]
+    async fn test_candle_generate() {
+        let tokens = Arc::new(Mutex::new(Vec::new()));
+        let streamed = tokens.clone();
+        let llm = Candle::new(model_file())
+            .with_chat_template(ChatTemplate::Raw)
+            .with_options(options(&[(0, 1.0)]).with_max_tokens(3).with_streaming_func(
+                move |token: String| {
+                    streamed.lock().unwrap().push(token);
+                    async { Ok(()) }
+                },
+            ));
+
+        let result = llm
+            .generate(&[Message::new_human_message("Hi")])
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "ababab");
+        assert_eq!(result.finish_reason, Some(FinishReason::Length));
+        assert_eq!(result.model.as_deref(), Some("model"));
+        let usage = result.tokens.unwrap();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (2, 3));
+        assert_eq!(*tokens.lock().unwrap(), ["ab", "ab", "ab"]);
+
+        // The clones share the loaded model.
+        assert!(llm.clone().model.get().is_some());
+        let llm = llm.with_context_size(4);
+        assert_eq!(llm.invoke("Hi").await.unwrap(), "abab");
+    }
+
+    #[test]
+    async fn test_candle_stop() {
+        let llm = Candle::new(model_file()).with_chat_template(ChatTemplate::Raw);
+
+        let result = llm
+            .clone()
+            .with_options(options(&[(0, 1.0)]).with_stop_words(vec!["ba".to_string()]))
+            .generate(&[Message::new_human_message("Hi")])
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "a");
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+
+        let result = llm
+            .with_options(options(&[(EOS, 1.0)]))
+            .generate(&[Message::new_human_message("Hi")])
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "");
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(result.tokens.unwrap().completion_tokens, 0);
+    }
+
+    #[test]
+    async fn test_candle_errors() {
+        let error = Candle::new(model_file())
+            .with_chat_template(ChatTemplate::Raw)
+            .with_context_size(2)
+            .invoke("Hi")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            LLMError::ContextLengthExceeded {
+                needed: Some(2),
+                limit: Some(2),
+                ..
+            }
+        ));
+
+        let error = Candle::new("missing.gguf").invoke("Hi").await.unwrap_err();
+        assert!(matches!(
+            error,
+            LLMError::CandleError(CandleError::ModelNotFound(_))
+        ));
+    }
+
+    #[test]
+    async fn test_penalties() {
+        let penalties = Penalties {
+            logit_bias: HashMap::from([(0, 1.0)]),
+            repetition: Some(2.0),
+            frequency: Some(0.5),
+            presence: Some(0.25),
+        };
+        let mut logits = vec![1.0, 1.0, -1.0, 1.0];
+        penalties.apply(&mut logits, &[0, 1, 1, 2]);
+        assert_eq!(
+            logits,
+            [1.0 - 0.5 - 0.25, 0.5 - 1.0 - 0.25, -2.0 - 0.5 - 0.25, 1.0]
+        );
+    }
+
+    #[test]
+    async fn test_stop_sequences() {
+        let mut stops = StopSequences::new(vec!["STOP".to_string()]);
+        assert_eq!(stops.push("a S"), "a ");
+        assert_eq!(stops.push("Tb"), "STb");
+        assert_eq!(stops.push("cST"), "c");
+        assert_eq!(stops.push("OPd"), "");
+        assert!(stops.stopped);
+
+        let path = model_file();
+        let content = gguf_file::Content::read(&mut File::open(path).unwrap()).unwrap();
+        let tokenizer = Tokenizer::from_gguf(&content).unwrap();
+        let mut detokenizer = Detokenizer::new(&tokenizer);
+        assert_eq!(detokenizer.push(1).unwrap(), "a");
+        assert_eq!(detokenizer.push(5).unwrap(), "");
+        assert_eq!(detokenizer.push(6).unwrap(), "é");
+        assert_eq!(detokenizer.push(5).unwrap(), "");
+        assert_eq!(detokenizer.finish().unwrap(), "\u{FFFD}");
+    }
+}
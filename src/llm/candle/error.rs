@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CandleError {
+    #[error("Model file not found: {0}")]
+    ModelNotFound(String),
+
+    /// The `general.architecture` of the GGUF file, when candle has no quantized model for
+    /// it.
+    #[error("Unsupported model architecture: {0}")]
+    UnsupportedArchitecture(String),
+
+    #[error("Tokenizer error: {0}")]
+    TokenizerError(String),
+
+    #[error("{0}")]
+    ModelError(#[from] candle_core::Error),
+}
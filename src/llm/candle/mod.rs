@@ -0,0 +1,8 @@
+mod template;
+pub use template::*;
+
+mod client;
+pub use client::*;
+
+mod error;
+pub use error::*;
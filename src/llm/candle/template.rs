@@ -0,0 +1,121 @@
+use crate::schemas::{Message, MessageType};
+
+/// The chat template of a model, formatting the messages into its prompt. Use the one the
+/// model was trained with, written in the `tokenizer.chat_template` of the GGUF file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatTemplate {
+    /// `<|im_start|>` and `<|im_end|>`, e.g. Qwen, Hermes or Phi 3.5.
+    ChatMl,
+    Llama3,
+    /// `[INST]` and `[/INST]`, the system prompt being prepended to the first instruction.
+    Mistral,
+    /// The contents of the messages, one per line.
+    Raw,
+}
+
+impl ChatTemplate {
+    /// The prompt of the messages, ending with the start of the answer. The beginning of
+    /// sequence token is added by the tokenizer.
+    pub fn format(&self, messages: &[Message]) -> String {
+        match self {
+            ChatTemplate::ChatMl => {
+                let mut prompt: String = messages
+                    .iter()
+                    .map(|m| format!("<|im_start|>{}\n{}<|im_end|>\n", role(m), m.content.text()))
+                    .collect();
+                prompt.push_str("<|im_start|>assistant\n");
+                prompt
+            }
+            ChatTemplate::Llama3 => {
+                let mut prompt: String = messages
+                    .iter()
+                    .map(|m| {
+                        format!(
+                            "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                            role(m),
+                            m.content.text()
+                        )
+                    })
+                    .collect();
+                prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+                prompt
+            }
+            ChatTemplate::Mistral => {
+                let mut prompt = String::new();
+                let mut system = Vec::new();
+                for message in messages {
+                    match message.message_type {
+                        MessageType::SystemMessage => system.push(message.content.text()),
+                        MessageType::AIMessage => {
+                            prompt.push_str(&format!("{}</s>", message.content.text()))
+                        }
+                        MessageType::HumanMessage | MessageType::ToolMessage => {
+                            let mut instruction = std::mem::take(&mut system);
+                            instruction.push(message.content.text());
+                            prompt
+                                .push_str(&format!("[INST] {} [/INST]", instruction.join("\n\n")));
+                        }
+                    }
+                }
+                prompt
+            }
+            ChatTemplate::Raw => messages
+                .iter()
+                .map(|m| m.content.text())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// The token ending the answers, a stop sequence for the models not configured
+    /// with it as their end of sequence token.
+    pub fn stop_sequence(&self) -> Option<&'static str> {
+        match self {
+            ChatTemplate::ChatMl => Some("<|im_end|>"),
+            ChatTemplate::Llama3 => Some("<|eot_id|>"),
+            ChatTemplate::Mistral => Some("</s>"),
+            ChatTemplate::Raw => None,
+        }
+    }
+}
+
+fn role(message: &Message) -> &'static str {
+    match message.message_type {
+        MessageType::SystemMessage => "system",
+        MessageType::HumanMessage => "user",
+        MessageType::AIMessage => "assistant",
+        MessageType::ToolMessage => "tool",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Message> {
+        vec![
+            Message::new_system_message("Be brief"),
+            Message::new_human_message("Hi"),
+            Message::new_ai_message("Hello"),
+            Message::new_human_message("How are you?"),
+        ]
+    }
+
+    #[test]
+    fn test_chat_templates() {
+        assert_eq!(
+            ChatTemplate::ChatMl.format(&messages()[..2]),
+            "<|im_start|>system\nBe brief<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n\
+             <|im_start|>assistant\n"
+        );
+        assert_eq!(
+            ChatTemplate::Llama3.format(&messages()[1..2]),
+            "<|start_header_id|>user<|end_header_id|>\n\nHi<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\n"
+        );
+        assert_eq!(
+            ChatTemplate::Mistral.format(&messages()),
+            "[INST] Be brief\n\nHi [/INST]Hello</s>[INST] How are you? [/INST]"
+        );
+    }
+}
@@ -1,16 +1,24 @@
 use crate::{
-    language_models::{llm::LLM, options::CallOptions, GenerateResult, LLMError, TokenUsage},
-    llm::AnthropicError,
-    schemas::{Message, StreamData},
+    callbacks::{RunManager, RunType},
+    language_models::{
+        llm::LLM, options::CallOptions, retry_after, send_with_retry, FinishReason, GenerateResult,
+        LLMError, TokenUsage,
+    },
+    llm::{gemini::take_event, AnthropicError},
+    runtime::{send, send_stream},
+    schemas::{Message, MessageType, StreamData},
 };
 use async_trait::async_trait;
 use futures::{Stream, StreamExt};
-use reqwest::Client;
+use reqwest::{Client, Response};
 use serde_json::Value;
 use std::{collections::HashMap, pin::Pin};
 
 use super::models::{ApiResponse, ClaudeMessage, Payload};
 
+/// The sampling options the Messages API has a parameter for.
+const SUPPORTED_OPTIONS: &[&str] = &["temperature", "stop_words", "top_p", "top_k"];
+
 pub enum ClaudeModel {
     Claude3pus20240229,
     Claude3sonnet20240229,
@@ -33,6 +41,7 @@ pub struct Claude {
     options: CallOptions,
     api_key: String,
     anthropic_version: String,
+    api_base: String,
 }
 
 impl Default for Claude {
@@ -48,6 +57,7 @@ impl Claude {
             options: CallOptions::default(),
             api_key: std::env::var("CLAUDE_API_KEY").unwrap_or_default(),
             anthropic_version: "2023-06-01".to_string(),
+            api_base: "https://api.anthropic.com/v1".to_string(),
         }
     }
 
@@ -71,37 +81,34 @@ impl Claude {
         self
     }
 
-    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
-        let client = Client::new();
-        let is_stream = self.options.streaming_func.is_some();
+    /// The base url of the API, e.g. a proxy or a gateway.
+    pub fn with_api_base<S: Into<String>>(mut self, api_base: S) -> Self {
+        self.api_base = api_base.into();
+        self
+    }
 
-        let payload = self.build_payload(messages, is_stream);
-        let res = client
-            .post("https://api.anthropic.com/v1/messages")
+    fn start_run(&self) -> RunManager {
+        self.options
+            .callbacks
+            .clone()
+            .unwrap_or_default()
+            .start_run(self.model.clone(), RunType::Llm)
+    }
+
+    async fn generate_content(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let client = Client::new();
+        let payload = self.build_payload(messages, false)?;
+        let request = client
+            .post(format!("{}/messages", self.api_base))
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", self.anthropic_version.clone())
             .header("content-type", "application/json; charset=utf-8")
-            .json(&payload)
-            .send()
-            .await?;
-        let res = match res.status().as_u16() {
-            401 => Err(LLMError::AnthropicError(
-                AnthropicError::AuthenticationError("Invalid API Key".to_string()),
-            )),
-            403 => Err(LLMError::AnthropicError(AnthropicError::PermissionError(
-                "Permission Denied".to_string(),
-            ))),
-            404 => Err(LLMError::AnthropicError(AnthropicError::NotFoundError(
-                "Not Found".to_string(),
-            ))),
-            429 => Err(LLMError::AnthropicError(AnthropicError::RateLimitError(
-                "Rate Limit Exceeded".to_string(),
-            ))),
-            503 => Err(LLMError::AnthropicError(AnthropicError::OverloadedError(
-                "Service Unavailable".to_string(),
-            ))),
-            _ => Ok(res.json::<ApiResponse>().await?),
-        }?;
+            .json(&payload);
+        let res: ApiResponse = send(async {
+            let res = send_with_retry(self.options.retry.as_ref(), request).await?;
+            Ok::<_, LLMError>(check_status(res).await?.json().await?)
+        })
+        .await?;
 
         let generation = res
             .content
@@ -115,12 +122,38 @@ impl Claude {
             total_tokens: res.usage.input_tokens + res.usage.output_tokens,
         });
 
-        Ok(GenerateResult { tokens, generation })
+        let finish_reason = res.stop_reason.as_deref().map(|reason| match reason {
+            "end_turn" | "stop_sequence" => FinishReason::Stop,
+            "max_tokens" => FinishReason::Length,
+            "tool_use" => FinishReason::ToolCalls,
+            reason => FinishReason::Other(reason.to_string()),
+        });
+
+        Ok(GenerateResult {
+            tokens,
+            generation,
+            finish_reason,
+            model: Some(res.model),
+            ..Default::default()
+        })
     }
 
-    fn build_payload(&self, messages: &[Message], stream: bool) -> Payload {
+    #[allow(clippy::result_large_err)]
+    fn build_payload(&self, messages: &[Message], stream: bool) -> Result<Payload, LLMError> {
+        self.options
+            .check_supported("Anthropic", SUPPORTED_OPTIONS)?;
+        // The system prompt is a field of the request in the Messages API, not a message.
+        let (system, messages): (Vec<&Message>, Vec<&Message>) = messages
+            .iter()
+            .partition(|m| matches!(m.message_type, MessageType::SystemMessage));
+        let system = system
+            .iter()
+            .map(|m| m.content.text())
+            .collect::<Vec<_>>()
+            .join("\n\n");
         let mut payload = Payload {
             model: self.model.clone(),
+            system: (!system.is_empty()).then_some(system),
             messages: messages
                 .iter()
                 .map(|m| ClaudeMessage::from_message(m))
@@ -135,71 +168,120 @@ impl Claude {
         if stream {
             payload.stream = Some(true);
         }
-        payload
+        Ok(payload)
+    }
+
+    /// The events of a streamed answer, not traced.
+    async fn content_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
+        let client = Client::new();
+        let payload = self.build_payload(messages, true)?;
+        let request = client
+            .post(format!("{}/messages", self.api_base))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", &self.anthropic_version)
+            .header("content-type", "application/json; charset=utf-8")
+            .json(&payload);
+        let bytes = send(async {
+            let res = send_with_retry(self.options.retry.as_ref(), request).await?;
+            Ok::<_, LLMError>(check_status(res).await?.bytes_stream())
+        })
+        .await?;
+        let mut bytes = send_stream(bytes);
+
+        #[allow(clippy::result_large_err)]
+        let stream_data = |event: &[u8]| {
+            let value = parse_sse_to_json(&String::from_utf8_lossy(event))?;
+            if value["type"].as_str().unwrap_or("") == "content_block_delta" {
+                let content = value["delta"]["text"].as_str().unwrap_or("").to_string();
+                Ok(StreamData::new(value, content))
+            } else {
+                Ok(StreamData::new(value, ""))
+            }
+        };
+
+        // The server-sent events may be split across chunks, so they are parsed once
+        // complete.
+        let stream = async_stream::stream! {
+            let mut buffer = Vec::new();
+            while let Some(chunk) = bytes.next().await {
+                match chunk {
+                    Ok(chunk) => buffer.extend_from_slice(&chunk),
+                    Err(e) => {
+                        yield Err(LLMError::RequestError(e));
+                        return;
+                    }
+                }
+                while let Some(event) = take_event(&mut buffer) {
+                    if !String::from_utf8_lossy(&event).trim().is_empty() {
+                        yield stream_data(&event);
+                    }
+                }
+            }
+            if !String::from_utf8_lossy(&buffer).trim().is_empty() {
+                yield stream_data(&buffer);
+            }
+        };
+        Ok(Box::pin(stream))
     }
 }
 
 #[async_trait]
 impl LLM for Claude {
     async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let run = self.start_run();
         match &self.options.streaming_func {
             Some(func) => {
-                let mut complete_response = String::new();
-                let mut stream = self.stream(messages).await?;
-                while let Some(data) = stream.next().await {
-                    match data {
-                        Ok(value) => {
-                            let mut func = func.lock().await;
-                            complete_response.push_str(&value.content);
-                            let _ = func(value.content).await;
+                run.trace_llm(messages, async {
+                    let mut stream = self.content_stream(messages).await?;
+                    let mut generation = String::new();
+                    let mut tokens = TokenUsage::default();
+                    while let Some(data) = stream.next().await {
+                        let data = data?;
+                        let usage = match data.value["type"].as_str() {
+                            Some("message_start") => &data.value["message"]["usage"],
+                            _ => &data.value["usage"],
+                        };
+                        let count = |key: &str| usage[key].as_u64().map(|count| count as u32);
+                        if let Some(input_tokens) = count("input_tokens") {
+                            tokens.prompt_tokens = input_tokens;
+                        }
+                        if let Some(output_tokens) = count("output_tokens") {
+                            tokens.completion_tokens = output_tokens;
                         }
-                        Err(e) => return Err(e),
+                        if data.content.is_empty() {
+                            continue;
+                        }
+                        run.on_llm_new_token(&data.content);
+                        generation.push_str(&data.content);
+                        let mut func = func.lock().await;
+                        let _ = func(data.content).await;
                     }
-                }
-                let mut generate_result = GenerateResult::default();
-                generate_result.generation = complete_response;
-                Ok(generate_result)
+                    tokens.total_tokens = tokens.prompt_tokens + tokens.completion_tokens;
+                    Ok(GenerateResult {
+                        generation,
+                        tokens: Some(tokens),
+                        model: Some(self.model.clone()),
+                        ..Default::default()
+                    })
+                })
+                .await
+            }
+            None => {
+                run.trace_llm(messages, self.generate_content(messages))
+                    .await
             }
-            None => self.generate(messages).await,
         }
     }
+
     async fn stream(
         &self,
         messages: &[Message],
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
-        let client = Client::new();
-        let payload = self.build_payload(messages, true);
-        let request = client
-            .post("https://api.anthropic.com/v1/messages")
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", &self.anthropic_version)
-            .header("content-type", "application/json; charset=utf-8")
-            .json(&payload)
-            .build()?;
-
-        // Instead of sending the request directly, return a stream wrapper
-        let stream = client.execute(request).await?.bytes_stream();
-
-        // Process each chunk as it arrives
-        let processed_stream = stream.then(move |result| {
-            async move {
-                match result {
-                    Ok(bytes) => {
-                        let value: Value = parse_sse_to_json(&String::from_utf8_lossy(&bytes))?;
-                        if value["type"].as_str().unwrap_or("") == "content_block_delta" {
-                            let content = value["delta"]["text"].clone();
-                            // Return StreamData based on the parsed content
-                            Ok(StreamData::new(value, content.as_str().unwrap_or("")))
-                        } else {
-                            Ok(StreamData::new(value, ""))
-                        }
-                    }
-                    Err(e) => Err(LLMError::RequestError(e)),
-                }
-            }
-        });
-
-        Ok(Box::pin(processed_stream))
+        let stream = self.content_stream(messages).await?;
+        Ok(self.start_run().trace_llm_stream(messages, stream))
     }
 
     fn add_options(&mut self, options: CallOptions) {
@@ -235,23 +317,274 @@ fn parse_sse_to_json(sse_data: &str) -> Result<Value, LLMError> {
 fn parse_error(json: &Value) -> Result<Value, LLMError> {
     let error_type = json["error"]["type"].as_str().unwrap_or("");
     let message = json["error"]["message"].as_str().unwrap_or("").to_string();
+    Err(api_error(error_type, message))
+}
+
+fn api_error(error_type: &str, message: String) -> LLMError {
     match error_type {
-        "invalid_request_error" => Err(AnthropicError::InvalidRequestError(message))?,
-        "authentication_error" => Err(AnthropicError::AuthenticationError(message))?,
-        "permission_error" => Err(AnthropicError::PermissionError(message))?,
-        "not_found_error" => Err(AnthropicError::NotFoundError(message))?,
-        "rate_limit_error" => Err(AnthropicError::RateLimitError(message))?,
-        "api_error" => Err(AnthropicError::ApiError(message))?,
-        "overloaded_error" => Err(AnthropicError::OverloadedError(message))?,
-        _ => Err(LLMError::OtherError("Unknown error".to_string())),
+        "invalid_request_error" => LLMError::from_context_length_message(&message)
+            .unwrap_or_else(|| AnthropicError::InvalidRequestError(message).into()),
+        "authentication_error" | "permission_error" => LLMError::AuthenticationFailed(message),
+        "not_found_error" => AnthropicError::NotFoundError(message).into(),
+        "rate_limit_error" => LLMError::RateLimited {
+            retry_after: None,
+            message,
+        },
+        "api_error" | "overloaded_error" => LLMError::ProviderUnavailable {
+            status: None,
+            message,
+        },
+        _ => LLMError::OtherError("Unknown error".to_string()),
     }
 }
 
+async fn check_status(res: Response) -> Result<Response, LLMError> {
+    let status = res.status().as_u16();
+    if res.status().is_success() {
+        return Ok(res);
+    }
+    let retry_after = retry_after(res.headers());
+    let body: Value = res.json().await.unwrap_or_default();
+    let message = body["error"]["message"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    if let Some(error) = LLMError::from_status(status, retry_after, &message) {
+        return Err(error);
+    }
+    Err(match (status, body["error"]["type"].as_str()) {
+        (_, Some(error_type)) => api_error(error_type, message),
+        (404, None) => AnthropicError::NotFoundError(message).into(),
+        (_, None) => AnthropicError::InvalidRequestError(message).into(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::language_models::LLMRetryPolicy;
+    use mockito::Matcher;
+    use serde_json::json;
     use tokio::test;
 
+    #[test]
+    async fn test_claude_system_prompt_and_options() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/messages")
+            .match_header("x-api-key", "key")
+            .match_body(Matcher::Json(json!({
+                "model": "claude-3-haiku-20240307",
+                "system": "You are a pirate",
+                "messages": [{"role": "user", "content": "Hi"}],
+                "max_tokens": 100,
+                "temperature": 0.5,
+            })))
+            .with_body(
+                json!({
+                    "id": "msg_1",
+                    "type": "message",
+                    "role": "assistant",
+                    "model": "claude-3-haiku-20240307",
+                    "content": [{"type": "text", "text": "Ahoy"}],
+                    "stop_reason": "end_turn",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 10, "output_tokens": 2},
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let mut claude = Claude::new()
+            .with_api_base(server.url())
+            .with_api_key("key")
+            .with_model(ClaudeModel::Claude3haiku20240307.to_string());
+        claude.add_options(
+            CallOptions::new()
+                .with_max_tokens(100)
+                .with_temperature(0.5),
+        );
+        let result = claude
+            .generate(&[
+                Message::new_system_message("You are a pirate"),
+                Message::new_human_message("Hi"),
+            ])
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.generation, "Ahoy");
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(result.tokens.unwrap().total_tokens, 12);
+
+        claude.add_options(CallOptions::new().with_seed(42));
+        assert!(matches!(
+            claude.generate(&[Message::new_human_message("Hi")]).await,
+            Err(LLMError::UnsupportedOption { option: "seed", .. })
+        ));
+    }
+
+    #[test]
+    async fn test_claude_streaming_func_is_traced() {
+        use std::sync::Arc;
+
+        use tokio::sync::Mutex;
+
+        use crate::callbacks::{tests::RecordingHandler, CallbackManager};
+
+        let events = [
+            json!({"type": "message_start", "message": {"usage": {"input_tokens": 5, "output_tokens": 1}}}),
+            json!({"type": "content_block_delta", "delta": {"type": "text_delta", "text": "Ahoy"}}),
+            json!({"type": "content_block_delta", "delta": {"type": "text_delta", "text": " matey"}}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 3}}),
+            json!({"type": "message_stop"}),
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| {
+                format!(
+                    "event: {}\ndata: {}\n\n",
+                    event["type"].as_str().unwrap(),
+                    event
+                )
+            })
+            .collect();
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/messages")
+            .match_body(Matcher::PartialJson(json!({"stream": true})))
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let streamed = Arc::new(Mutex::new(Vec::new()));
+        let handler = Arc::new(RecordingHandler::default());
+        let claude = Claude::new().with_api_base(server.url()).with_options(
+            CallOptions::new()
+                .with_callbacks(CallbackManager::new().with_shared_handler(handler.clone()))
+                .with_streaming_func({
+                    let streamed = streamed.clone();
+                    move |token: String| {
+                        let streamed = streamed.clone();
+                        async move {
+                            streamed.lock().await.push(token);
+                            Ok(())
+                        }
+                    }
+                }),
+        );
+        let result = claude
+            .generate(&[Message::new_human_message("Hi")])
+            .await
+            .unwrap();
+
+        assert_eq!(result.generation, "Ahoy matey");
+        assert_eq!(result.tokens.unwrap().total_tokens, 8);
+        assert_eq!(*streamed.lock().await, vec!["Ahoy", " matey"]);
+        let model = "claude-3-opus-20240229";
+        assert_eq!(
+            handler.names(),
+            vec![
+                format!("llm_start:{}", model),
+                format!("llm_new_token:{}", model),
+                format!("llm_new_token:{}", model),
+                format!("llm_end:{}", model),
+            ]
+        );
+    }
+
+    #[test]
+    async fn test_claude_retry_rate_limit() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server
+            .mock("POST", "/messages")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(1)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("POST", "/messages")
+            .with_body(
+                json!({
+                    "id": "msg_1",
+                    "type": "message",
+                    "role": "assistant",
+                    "model": "claude-3-opus-20240229",
+                    "content": [{"type": "text", "text": "Hi"}],
+                    "stop_reason": "end_turn",
+                    "usage": {"input_tokens": 1, "output_tokens": 1},
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let claude = Claude::new()
+            .with_api_base(server.url())
+            .with_options(CallOptions::new().with_retry(LLMRetryPolicy::new()));
+        let result = claude
+            .generate(&[Message::new_human_message("Hi")])
+            .await
+            .unwrap();
+
+        limited.assert_async().await;
+        ok.assert_async().await;
+        assert_eq!(result.generation, "Hi");
+    }
+
+    #[test]
+    async fn test_claude_errors() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/messages")
+            .with_status(400)
+            .with_body(
+                json!({
+                    "type": "error",
+                    "error": {
+                        "type": "invalid_request_error",
+                        "message": "prompt is too long: 250000 tokens > 200000 maximum",
+                    },
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let claude = Claude::new().with_api_base(server.url());
+        let error = claude.invoke("Hi").await.unwrap_err();
+        assert!(matches!(
+            error,
+            LLMError::ContextLengthExceeded {
+                needed: Some(250000),
+                limit: Some(200000),
+                ..
+            }
+        ));
+
+        server.reset();
+        server
+            .mock("POST", "/messages")
+            .with_status(529)
+            .with_body(r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#)
+            .create_async()
+            .await;
+        let error = claude
+            .stream(&[Message::new_human_message("Hi")])
+            .await
+            .err();
+        assert!(matches!(
+            error,
+            Some(LLMError::ProviderUnavailable {
+                status: Some(529),
+                ..
+            })
+        ));
+    }
+
     #[test]
     #[ignore]
     async fn test_cloudia_generate() {
@@ -1,17 +1,36 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::schemas::{Message, MessageType};
+use crate::schemas::{anthropic_block, Message, MessageContent, MessageType};
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ClaudeContent {
+    Text(String),
+    Blocks(Vec<Value>),
+}
+
+impl ClaudeContent {
+    pub fn from_content(content: &MessageContent) -> Self {
+        match content {
+            MessageContent::Text(text) => Self::Text(text.clone()),
+            MessageContent::Parts(parts) => {
+                Self::Blocks(parts.iter().filter_map(anthropic_block).collect())
+            }
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize)]
 pub(crate) struct ClaudeMessage {
     pub role: String,
-    pub content: String,
+    pub content: ClaudeContent,
 }
 impl ClaudeMessage {
-    pub fn new<S: Into<String>>(role: S, content: S) -> Self {
+    pub fn new<S: Into<String>>(role: S, content: &MessageContent) -> Self {
         Self {
             role: role.into(),
-            content: content.into(),
+            content: ClaudeContent::from_content(content),
         }
     }
 
@@ -20,7 +39,8 @@ impl ClaudeMessage {
             MessageType::SystemMessage => Self::new("system", &message.content),
             MessageType::AIMessage => Self::new("assistant", &message.content),
             MessageType::HumanMessage => Self::new("user", &message.content),
-            MessageType::ToolMessage => Self::new("tool", &message.content),
+            // The results of the tools are sent by the user in the Messages API.
+            MessageType::ToolMessage => Self::new("user", &message.content),
         }
     }
 }
@@ -28,6 +48,8 @@ impl ClaudeMessage {
 #[derive(Serialize, Deserialize)]
 pub(crate) struct Payload {
     pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
     pub messages: Vec<ClaudeMessage>,
     pub max_tokens: u16,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -67,3 +89,37 @@ pub(crate) struct Usage {
     pub input_tokens: u32,
     pub output_tokens: u32,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::schemas::ContentPart;
+
+    #[test]
+    fn test_claude_content_blocks() {
+        let message = Message::new_human_message_with_parts(vec![
+            ContentPart::text("Describe both"),
+            ContentPart::image_base64("image/jpeg", "aGVsbG8="),
+            ContentPart::image_url("https://example.com/cat.png"),
+        ]);
+        let value = serde_json::to_value(ClaudeMessage::from_message(&message)).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "Describe both"},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "aGVsbG8="}},
+                    {"type": "image", "source": {"type": "url", "url": "https://example.com/cat.png"}},
+                ],
+            })
+        );
+
+        let value =
+            serde_json::to_value(ClaudeMessage::from_message(&Message::new_ai_message("Hi")))
+                .unwrap();
+        assert_eq!(value, json!({"role": "assistant", "content": "Hi"}));
+    }
+}
@@ -0,0 +1,369 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::Stream;
+use serde_json::json;
+
+use crate::{
+    callbacks::{RunManager, RunType},
+    language_models::{
+        llm::LLM, options::CallOptions, FinishReason, GenerateResult, LLMError, TokenUsage,
+    },
+    runtime,
+    schemas::{Message, StreamData, ToolCall},
+};
+
+/// An LLM answering scripted responses, for deterministic tests of chains and agents
+/// without network access.
+///
+/// The responses are answered in order, starting over after the last one, or the prompts
+/// are echoed, see [`FakeLLM::echo`]. The prompts received are recorded, see
+/// [`FakeLLM::calls`]. The streamed responses are split in
+/// words, and the callbacks and the streaming function of the options are called as
+/// with a real model.
+///
+/// # Example
+/// ```rust,ignore
+/// let llm = FakeLLM::from_responses(vec!["Thought: I know the answer", "Final Answer: 42"])
+///     .with_latency(Duration::from_millis(10));
+/// let chain = LLMChainBuilder::new().prompt(prompt).llm(llm.clone()).build()?;
+/// chain.invoke(prompt_args! {"input" => "What is the answer?"}).await?;
+/// assert_eq!(llm.call_count(), 1);
+/// ```
+#[derive(Clone)]
+pub struct FakeLLM {
+    responses: Arc<Vec<GenerateResult>>,
+    next: Arc<AtomicUsize>,
+    calls: Arc<Mutex<Vec<Vec<Message>>>>,
+    errors: Arc<Mutex<VecDeque<LLMError>>>,
+    echo: bool,
+    tokens: Option<TokenUsage>,
+    running: Arc<AtomicUsize>,
+    max_running: Arc<AtomicUsize>,
+    latency: Option<Duration>,
+    stream_delay: Option<Duration>,
+    options: CallOptions,
+}
+
+impl FakeLLM {
+    /// Always answers `response`.
+    pub fn new<S: Into<String>>(response: S) -> Self {
+        Self::from_responses(vec![response])
+    }
+
+    pub fn from_responses<S: Into<String>>(responses: Vec<S>) -> Self {
+        Self::from_results(
+            responses
+                .into_iter()
+                .map(|response| GenerateResult {
+                    generation: response.into(),
+                    finish_reason: Some(FinishReason::Stop),
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    }
+
+    /// Answers the results, e.g. with tool calls or token usages.
+    pub fn from_results(results: Vec<GenerateResult>) -> Self {
+        Self {
+            responses: Arc::new(results),
+            next: Arc::new(AtomicUsize::new(0)),
+            calls: Arc::new(Mutex::new(Vec::new())),
+            errors: Arc::new(Mutex::new(VecDeque::new())),
+            echo: false,
+            tokens: None,
+            running: Arc::new(AtomicUsize::new(0)),
+            max_running: Arc::new(AtomicUsize::new(0)),
+            latency: None,
+            stream_delay: None,
+            options: CallOptions::default(),
+        }
+    }
+
+    /// Answers calls of the tools, e.g. for the tool calling agents.
+    pub fn from_tool_calls(tool_calls: Vec<ToolCall>) -> Self {
+        Self::from_results(vec![GenerateResult {
+            tool_calls,
+            finish_reason: Some(FinishReason::ToolCalls),
+            ..Default::default()
+        }])
+    }
+
+    /// Answers the text of the messages received, joined by new lines, e.g. to test the
+    /// prompts built by a chain.
+    pub fn echo() -> Self {
+        Self {
+            echo: true,
+            ..Self::from_results(Vec::new())
+        }
+    }
+
+    /// Fails the first calls with the errors, in order, before answering, e.g. to test
+    /// retries and fallbacks.
+    pub fn with_errors(self, errors: Vec<LLMError>) -> Self {
+        self.errors.lock().unwrap().extend(errors);
+        self
+    }
+
+    /// Reports `tokens` as the usage of every answer.
+    pub fn with_token_usage(mut self, tokens: TokenUsage) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Waits before answering, e.g. to test timeouts.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Waits between the streamed words.
+    pub fn with_stream_delay(mut self, stream_delay: Duration) -> Self {
+        self.stream_delay = Some(stream_delay);
+        self
+    }
+
+    pub fn with_options(mut self, options: CallOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The messages of the calls received, shared by the clones of the LLM.
+    pub fn calls(&self) -> Vec<Vec<Message>> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+
+    /// The most calls answered at the same time, e.g. to test a limit of concurrency.
+    pub fn max_concurrent_calls(&self) -> usize {
+        self.max_running.load(Ordering::SeqCst)
+    }
+
+    /// The next response, after recording the call.
+    async fn respond(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        self.calls.lock().unwrap().push(messages.to_vec());
+        let running = Running::start(&self.running);
+        self.max_running.fetch_max(running.count, Ordering::SeqCst);
+        if let Some(latency) = self.latency {
+            runtime::sleep(latency).await;
+        }
+        drop(running);
+
+        if let Some(error) = self.errors.lock().unwrap().pop_front() {
+            return Err(error);
+        }
+        let mut result = if self.echo {
+            GenerateResult {
+                generation: messages
+                    .iter()
+                    .map(|message| message.content.text())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                finish_reason: Some(FinishReason::Stop),
+                ..Default::default()
+            }
+        } else if self.responses.is_empty() {
+            return Err(LLMError::OtherError("FakeLLM has no responses".to_string()));
+        } else {
+            let index = self.next.fetch_add(1, Ordering::SeqCst) % self.responses.len();
+            self.responses[index].clone()
+        };
+        if let Some(tokens) = &self.tokens {
+            result.tokens = Some(tokens.clone());
+        }
+        result.model.get_or_insert_with(|| "fake".to_string());
+        Ok(result)
+    }
+
+    fn start_run(&self) -> RunManager {
+        self.options
+            .callbacks
+            .clone()
+            .unwrap_or_default()
+            .start_run("fake", RunType::Llm)
+    }
+}
+
+/// Counts a call as running until dropped, even when the call is cancelled.
+struct Running<'a> {
+    running: &'a AtomicUsize,
+    count: usize,
+}
+
+impl<'a> Running<'a> {
+    fn start(running: &'a AtomicUsize) -> Self {
+        let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+        Self { running, count }
+    }
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The words of the text with their trailing whitespace.
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if !c.is_whitespace() && word.ends_with(char::is_whitespace) {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+#[async_trait]
+impl LLM for FakeLLM {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let run = self.start_run();
+        run.trace_llm(messages, async {
+            let result = self.respond(messages).await?;
+            if let Some(func) = &self.options.streaming_func {
+                for (i, word) in words(&result.generation).into_iter().enumerate() {
+                    if let Some(delay) = self.stream_delay.filter(|_| i > 0) {
+                        runtime::sleep(delay).await;
+                    }
+                    run.on_llm_new_token(&word);
+                    let mut func = func.lock().await;
+                    let _ = func(word).await;
+                }
+            }
+            Ok(result)
+        })
+        .await
+    }
+
+    /// The values of the stream are `{"content": ...}`, then the tool calls if any.
+    async fn stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
+        let result = self.respond(messages).await?;
+        let stream_delay = self.stream_delay;
+        let stream = async_stream::stream! {
+            for (i, word) in words(&result.generation).into_iter().enumerate() {
+                if let Some(delay) = stream_delay.filter(|_| i > 0) {
+                    runtime::sleep(delay).await;
+                }
+                yield Ok(StreamData::new(json!({ "content": word }), word));
+            }
+            if !result.tool_calls.is_empty() {
+                yield Ok(StreamData::new(json!({ "tool_calls": result.tool_calls }), ""));
+            }
+        };
+        Ok(self
+            .start_run()
+            .trace_llm_stream(messages, Box::pin(stream)))
+    }
+
+    fn add_options(&mut self, options: CallOptions) {
+        self.options.merge_options(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::language_models::TokenUsage;
+
+    #[tokio::test]
+    async fn test_fake_llm() {
+        let llm = FakeLLM::from_responses(vec!["first", "second answer"]);
+        let clone = llm.clone();
+        assert_eq!(llm.invoke("1").await.unwrap(), "first");
+        assert_eq!(clone.invoke("2").await.unwrap(), "second answer");
+        assert_eq!(llm.invoke("3").await.unwrap(), "first");
+        assert_eq!(clone.call_count(), 3);
+        assert_eq!(llm.calls()[1][0].content, "2");
+
+        let tokens = Arc::new(Mutex::new(Vec::new()));
+        let streamed = tokens.clone();
+        let result = FakeLLM::from_results(vec![GenerateResult {
+            generation: "Hello  world\n!".to_string(),
+            tokens: Some(TokenUsage::new(3, 2)),
+            ..Default::default()
+        }])
+        .with_options(
+            CallOptions::new().with_streaming_func(move |token: String| {
+                streamed.lock().unwrap().push(token);
+                async { Ok(()) }
+            }),
+        )
+        .generate(&[Message::new_human_message("Hi")])
+        .await
+        .unwrap();
+        assert_eq!(result.tokens.unwrap().total_tokens, 5);
+        assert_eq!(result.model.as_deref(), Some("fake"));
+        assert_eq!(*tokens.lock().unwrap(), vec!["Hello  ", "world\n", "!"]);
+    }
+
+    #[tokio::test]
+    async fn test_fake_llm_stream() {
+        let llm = FakeLLM::new("one two three").with_stream_delay(Duration::from_millis(1));
+        let content: Vec<String> = llm
+            .stream(&[Message::new_human_message("Count")])
+            .await
+            .unwrap()
+            .map(|data| data.unwrap().content)
+            .collect()
+            .await;
+        assert_eq!(content, vec!["one ", "two ", "three"]);
+
+        let llm = FakeLLM::from_tool_calls(vec![ToolCall::new("call_1", "search", "{}")]);
+        let result = llm.generate(&[]).await.unwrap();
+        assert_eq!(result.tool_calls[0].name, "search");
+        assert_eq!(result.finish_reason, Some(FinishReason::ToolCalls));
+
+        let llm = FakeLLM::new("late").with_latency(Duration::from_millis(50));
+        let timeout = tokio::time::timeout(Duration::from_millis(5), llm.invoke("Hi")).await;
+        assert!(timeout.is_err());
+
+        let llm = FakeLLM::new("ok").with_errors(vec![LLMError::OtherError("down".into())]);
+        assert!(matches!(
+            llm.invoke("Hi").await,
+            Err(LLMError::OtherError(message)) if message == "down"
+        ));
+        assert_eq!(llm.invoke("Hi").await.unwrap(), "ok");
+        assert_eq!(llm.call_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fake_llm_echo() {
+        let llm = FakeLLM::echo()
+            .with_token_usage(TokenUsage::new(1, 1))
+            .with_latency(Duration::from_millis(10));
+        let result = llm
+            .generate(&[
+                Message::new_system_message("Be brief"),
+                Message::new_human_message("Hi"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(result.generation, "Be brief\nHi");
+        assert_eq!(result.tokens.unwrap().total_tokens, 2);
+
+        futures::future::join_all((0..3).map(|_| llm.invoke("Hi"))).await;
+        assert_eq!(llm.max_concurrent_calls(), 3);
+    }
+}
@@ -0,0 +1,466 @@
+use std::{fmt, pin::Pin};
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use reqwest::{Client, RequestBuilder, Response};
+use serde_json::Value;
+
+use crate::{
+    callbacks::{RunManager, RunType},
+    language_models::{
+        llm::LLM, options::CallOptions, retry_after, send_with_retry, GenerateResult, LLMError,
+    },
+    llm::GeminiError,
+    runtime::{send, send_stream},
+    schemas::{Message, MessageType, StreamData},
+};
+
+use super::models::{
+    ApiResponse, Content, GenerationConfig, HarmBlockThreshold, HarmCategory, Part, Payload,
+    SafetySetting,
+};
+
+/// The sampling options the generation config has a parameter for.
+const SUPPORTED_OPTIONS: &[&str] = &[
+    "temperature",
+    "stop_words",
+    "top_p",
+    "top_k",
+    "seed",
+    "candidate_count",
+    "frequency_penalty",
+    "presence_penalty",
+];
+
+pub enum GeminiModel {
+    Gemini15Pro,
+    Gemini15Flash,
+    Gemini20Flash,
+}
+
+impl fmt::Display for GeminiModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeminiModel::Gemini15Pro => write!(f, "gemini-1.5-pro"),
+            GeminiModel::Gemini15Flash => write!(f, "gemini-1.5-flash"),
+            GeminiModel::Gemini20Flash => write!(f, "gemini-2.0-flash"),
+        }
+    }
+}
+
+/// Gemini models of the Google Generative Language API.
+///
+/// # Example
+/// ```rust,ignore
+/// let gemini = Gemini::new()
+///     .with_model(GeminiModel::Gemini15Flash.to_string())
+///     .with_safety_setting(HarmCategory::DangerousContent, HarmBlockThreshold::BlockOnlyHigh);
+/// let response = gemini.invoke("Hi").await?;
+/// ```
+#[derive(Clone)]
+pub struct Gemini {
+    model: String,
+    options: CallOptions,
+    api_key: String,
+    api_base: String,
+    safety_settings: Vec<SafetySetting>,
+}
+
+impl Default for Gemini {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gemini {
+    pub fn new() -> Self {
+        Self {
+            model: GeminiModel::Gemini15Flash.to_string(),
+            options: CallOptions::default(),
+            api_key: std::env::var("GEMINI_API_KEY").unwrap_or_default(),
+            api_base: "https://generativelanguage.googleapis.com/v1beta".to_string(),
+            safety_settings: Vec::new(),
+        }
+    }
+
+    pub fn with_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn with_options(mut self, options: CallOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    pub fn with_api_base<S: Into<String>>(mut self, api_base: S) -> Self {
+        self.api_base = api_base.into();
+        self
+    }
+
+    /// Blocks the answers of `category` from `threshold`, replacing the default threshold
+    /// of the API.
+    pub fn with_safety_setting(
+        mut self,
+        category: HarmCategory,
+        threshold: HarmBlockThreshold,
+    ) -> Self {
+        self.safety_settings
+            .retain(|setting| setting.category != category);
+        self.safety_settings.push(SafetySetting {
+            category,
+            threshold,
+        });
+        self
+    }
+
+    fn start_run(&self) -> RunManager {
+        self.options
+            .callbacks
+            .clone()
+            .unwrap_or_default()
+            .start_run(self.model.clone(), RunType::Llm)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn build_payload(&self, messages: &[Message]) -> Result<Payload, LLMError> {
+        self.options.check_supported("Gemini", SUPPORTED_OPTIONS)?;
+        // The system prompt is an instruction of the request, not a message.
+        let (system, messages): (Vec<&Message>, Vec<&Message>) = messages
+            .iter()
+            .partition(|m| matches!(m.message_type, MessageType::SystemMessage));
+        let system_instruction = (!system.is_empty()).then(|| Content {
+            role: None,
+            parts: system
+                .iter()
+                .map(|m| Part::Text(m.content.text()))
+                .collect(),
+        });
+        Ok(Payload {
+            contents: messages.iter().map(|m| Content::from_message(m)).collect(),
+            system_instruction,
+            generation_config: GenerationConfig::from_options(&self.options),
+            safety_settings: self.safety_settings.clone(),
+        })
+    }
+
+    fn request(&self, method: &str) -> RequestBuilder {
+        Client::new()
+            .post(format!(
+                "{}/models/{}:{}",
+                self.api_base, self.model, method
+            ))
+            .header("x-goog-api-key", &self.api_key)
+    }
+
+    async fn generate_content(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let request = self
+            .request("generateContent")
+            .json(&self.build_payload(messages)?);
+        let res: ApiResponse = send(async {
+            let res = send_with_retry(self.options.retry.as_ref(), request).await?;
+            Ok::<_, LLMError>(check_status(res).await?.json().await?)
+        })
+        .await?;
+        check_prompt_feedback(&res)?;
+
+        Ok(GenerateResult {
+            tokens: res.tokens(),
+            generation: res.text(),
+            finish_reason: res
+                .candidates
+                .first()
+                .and_then(|candidate| candidate.finish_reason()),
+            model: Some(self.model.clone()),
+            ..Default::default()
+        })
+    }
+}
+
+async fn check_status(res: Response) -> Result<Response, LLMError> {
+    let status = res.status().as_u16();
+    if res.status().is_success() {
+        return Ok(res);
+    }
+    let retry_after = retry_after(res.headers());
+    let body: Value = res.json().await.unwrap_or_default();
+    let message = body["error"]["message"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    if let Some(error) = LLMError::from_status(status, retry_after, &message) {
+        return Err(error);
+    }
+    Err(GeminiError::ApiError { status, message })?
+}
+
+/// Prompts blocked by the safety filters have no candidates, only the reason.
+#[allow(clippy::result_large_err)]
+fn check_prompt_feedback(res: &ApiResponse) -> Result<(), LLMError> {
+    match res
+        .prompt_feedback
+        .as_ref()
+        .and_then(|feedback| feedback.block_reason.as_ref())
+    {
+        Some(reason) => Err(LLMError::ContentFiltered(reason.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Takes the first complete server-sent event out of `buffer`, `None` until its blank
+/// line is received.
+pub(crate) fn take_event(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    let (index, end) = (0..buffer.len()).find_map(|index| {
+        let rest = &buffer[index..];
+        if rest.starts_with(b"\n\n") {
+            Some((index, 2))
+        } else if rest.starts_with(b"\r\n\r\n") {
+            Some((index, 4))
+        } else {
+            None
+        }
+    })?;
+    let event = buffer[..index].to_vec();
+    buffer.drain(..index + end);
+    Some(event)
+}
+
+/// The data of an event, its `data` lines joined by new lines.
+fn event_data(event: &[u8]) -> Option<String> {
+    let event = String::from_utf8_lossy(event);
+    let data: Vec<&str> = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect();
+    (!data.is_empty()).then(|| data.join("\n"))
+}
+
+fn parse_event(data: &str) -> Result<(Value, ApiResponse), serde_json::Error> {
+    let value: Value = serde_json::from_str(data)?;
+    let res = serde_json::from_value(value.clone())?;
+    Ok((value, res))
+}
+
+#[async_trait]
+impl LLM for Gemini {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        match &self.options.streaming_func {
+            Some(func) => {
+                let mut complete_response = String::new();
+                let mut stream = self.stream(messages).await?;
+                while let Some(data) = stream.next().await {
+                    let data = data?;
+                    let mut func = func.lock().await;
+                    complete_response.push_str(&data.content);
+                    let _ = func(data.content).await;
+                }
+                Ok(GenerateResult {
+                    generation: complete_response,
+                    model: Some(self.model.clone()),
+                    ..Default::default()
+                })
+            }
+            None => {
+                self.start_run()
+                    .trace_llm(messages, self.generate_content(messages))
+                    .await
+            }
+        }
+    }
+
+    async fn stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
+        let request = self
+            .request("streamGenerateContent")
+            .query(&[("alt", "sse")])
+            .json(&self.build_payload(messages)?);
+        let bytes = send(async {
+            let res = send_with_retry(self.options.retry.as_ref(), request).await?;
+            Ok::<_, LLMError>(check_status(res).await?.bytes_stream())
+        })
+        .await?;
+        let mut bytes = send_stream(bytes);
+
+        let stream_data = |data: &str| {
+            let (value, res) = parse_event(data)?;
+            check_prompt_feedback(&res)?;
+            Ok(StreamData::new(value, res.text()))
+        };
+
+        // Server sent events, which may be split across chunks, as may be their
+        // characters, so the events are decoded once complete.
+        let stream = async_stream::stream! {
+            let mut buffer = Vec::new();
+            while let Some(chunk) = bytes.next().await {
+                match chunk {
+                    Ok(chunk) => buffer.extend_from_slice(&chunk),
+                    Err(e) => {
+                        yield Err(LLMError::RequestError(e));
+                        return;
+                    }
+                }
+                while let Some(event) = take_event(&mut buffer) {
+                    if let Some(data) = event_data(&event) {
+                        yield stream_data(&data);
+                    }
+                }
+            }
+            if let Some(data) = event_data(&buffer) {
+                yield stream_data(&data);
+            }
+        };
+
+        Ok(self
+            .start_run()
+            .trace_llm_stream(messages, Box::pin(stream)))
+    }
+
+    fn add_options(&mut self, options: CallOptions) {
+        self.options.merge_options(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+    use serde_json::json;
+    use tokio::test;
+
+    use super::*;
+    use crate::language_models::FinishReason;
+
+    #[test]
+    async fn test_gemini_generate() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/models/gemini-1.5-flash:generateContent")
+            .match_header("x-goog-api-key", "key")
+            .match_body(Matcher::Json(json!({
+                "systemInstruction": {"parts": [{"text": "Be brief"}]},
+                "contents": [
+                    {"role": "user", "parts": [{"text": "Hi"}]},
+                    {"role": "model", "parts": [{"text": "Hello"}]},
+                    {"role": "user", "parts": [{"text": "How are you?"}]},
+                ],
+                "generationConfig": {"temperature": 0.5, "maxOutputTokens": 10},
+                "safetySettings": [
+                    {"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_NONE"},
+                ],
+            })))
+            .with_body(
+                json!({
+                    "candidates": [{
+                        "content": {"role": "model", "parts": [{"text": "Fine"}]},
+                        "finishReason": "MAX_TOKENS",
+                    }],
+                    "usageMetadata": {
+                        "promptTokenCount": 12,
+                        "candidatesTokenCount": 10,
+                        "totalTokenCount": 22,
+                    },
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let gemini = Gemini::new()
+            .with_api_base(server.url())
+            .with_api_key("key")
+            .with_options(CallOptions::new().with_temperature(0.5).with_max_tokens(10))
+            .with_safety_setting(HarmCategory::Harassment, HarmBlockThreshold::BlockOnlyHigh)
+            .with_safety_setting(HarmCategory::Harassment, HarmBlockThreshold::BlockNone);
+        let result = LLM::generate(
+            &gemini,
+            &[
+                Message::new_system_message("Be brief"),
+                Message::new_human_message("Hi"),
+                Message::new_ai_message("Hello"),
+                Message::new_human_message("How are you?"),
+            ],
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.generation, "Fine");
+        assert_eq!(result.finish_reason, Some(FinishReason::Length));
+        assert_eq!(result.tokens.unwrap().total_tokens, 22);
+
+        let gemini = gemini.with_options(CallOptions::new().with_repetition_penalty(1.1));
+        assert_eq!(
+            LLM::generate(&gemini, &[Message::new_human_message("Hi")])
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Gemini doesn't support the option repetition_penalty"
+        );
+    }
+
+    #[test]
+    async fn test_gemini_stream_and_blocked_prompt() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/models/gemini-1.5-flash:streamGenerateContent")
+            .match_query(Matcher::UrlEncoded("alt".into(), "sse".into()))
+            .with_body(concat!(
+                "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hel\"}]}}]}\r\n\r\n",
+                "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"lo\"}]}, \"finishReason\": \"STOP\"}]}\r\n\r\n",
+            ))
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/models/gemini-1.5-flash:generateContent")
+            .with_body(r#"{"promptFeedback": {"blockReason": "SAFETY"}}"#)
+            .create_async()
+            .await;
+
+        let gemini = Gemini::new().with_api_base(server.url());
+        let mut stream = gemini
+            .stream(&[Message::new_human_message("Hi")])
+            .await
+            .unwrap();
+        let mut content = String::new();
+        while let Some(data) = stream.next().await {
+            content.push_str(&data.unwrap().content);
+        }
+        assert_eq!(content, "Hello");
+
+        let error = gemini.invoke("Hi").await.unwrap_err();
+        assert!(matches!(
+            error,
+            LLMError::ContentFiltered(reason) if reason == "SAFETY"
+        ));
+    }
+
+    #[test]
+    #[ignore]
+    async fn test_gemini_invoke() {
+        let gemini = Gemini::new();
+        let res = gemini.invoke("Hi, how are you doing").await.unwrap();
+        println!("{:?}", res)
+    }
+
+    #[test]
+    async fn test_take_event() {
+        let event = "data: {\"text\": \"été\"}\r\n\r\n".as_bytes();
+        // Split in the middle of the "é".
+        let mut buffer = event[..17].to_vec();
+        assert!(take_event(&mut buffer).is_none());
+        buffer.extend_from_slice(&event[17..]);
+        buffer.extend_from_slice(b"data: {}\n");
+        let event = take_event(&mut buffer).unwrap();
+        assert_eq!(event_data(&event).unwrap(), r#"{"text": "été"}"#);
+        assert!(take_event(&mut buffer).is_none());
+        assert_eq!(event_data(&buffer).unwrap(), "{}");
+        assert_eq!(event_data(b": comment"), None);
+    }
+}
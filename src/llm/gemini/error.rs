@@ -0,0 +1,7 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GeminiError {
+    #[error("Gemini API error {status}: {message}")]
+    ApiError { status: u16, message: String },
+}
@@ -0,0 +1,8 @@
+mod models;
+pub use models::{HarmBlockThreshold, HarmCategory, SafetySetting};
+
+mod client;
+pub use client::*;
+
+mod error;
+pub use error::*;
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    language_models::{options::CallOptions, FinishReason, TokenUsage},
+    schemas::{ContentPart, Message, MessageType},
+};
+
+/// The categories of the safety filters of Gemini.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HarmCategory {
+    #[serde(rename = "HARM_CATEGORY_HARASSMENT")]
+    Harassment,
+    #[serde(rename = "HARM_CATEGORY_HATE_SPEECH")]
+    HateSpeech,
+    #[serde(rename = "HARM_CATEGORY_SEXUALLY_EXPLICIT")]
+    SexuallyExplicit,
+    #[serde(rename = "HARM_CATEGORY_DANGEROUS_CONTENT")]
+    DangerousContent,
+    #[serde(rename = "HARM_CATEGORY_CIVIC_INTEGRITY")]
+    CivicIntegrity,
+}
+
+/// The probability of harm from which the answers are blocked.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HarmBlockThreshold {
+    BlockLowAndAbove,
+    BlockMediumAndAbove,
+    BlockOnlyHigh,
+    BlockNone,
+    Off,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SafetySetting {
+    pub category: HarmCategory,
+    pub threshold: HarmBlockThreshold,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum Part {
+    Text(String),
+    InlineData {
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        data: String,
+    },
+    FileData {
+        #[serde(rename = "fileUri")]
+        file_uri: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Content {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub parts: Vec<Part>,
+}
+
+impl Content {
+    pub fn from_message(message: &Message) -> Self {
+        let role = match message.message_type {
+            MessageType::AIMessage => "model",
+            _ => "user",
+        };
+        let parts = message
+            .content
+            .parts()
+            .into_iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text } => Some(Part::Text(text)),
+                ContentPart::Image { url, .. } => Some(
+                    match url
+                        .strip_prefix("data:")
+                        .and_then(|data| data.split_once(";base64,"))
+                    {
+                        Some((mime_type, data)) => Part::InlineData {
+                            mime_type: mime_type.to_string(),
+                            data: data.to_string(),
+                        },
+                        None => Part::FileData { file_uri: url },
+                    },
+                ),
+                ContentPart::Audio { data, format } => Some(Part::InlineData {
+                    mime_type: format!("audio/{}", format),
+                    data,
+                }),
+                ContentPart::ToolResult { content, .. } => Some(Part::Text(content)),
+                ContentPart::ToolCall { .. } => None,
+            })
+            .collect();
+        Self {
+            role: Some(role.to_string()),
+            parts,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidate_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+}
+
+impl GenerationConfig {
+    pub fn from_options(options: &CallOptions) -> Option<Self> {
+        let config = Self {
+            temperature: options.temperature,
+            max_output_tokens: options.max_tokens,
+            top_p: options.top_p,
+            top_k: options.top_k,
+            candidate_count: options.candidate_count,
+            seed: options.seed,
+            stop_sequences: options.stop_words.clone(),
+            presence_penalty: options.presence_penalty,
+            frequency_penalty: options.frequency_penalty,
+        };
+        (config != Self::default()).then_some(config)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Payload {
+    pub contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_config: Option<GenerationConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub safety_settings: Vec<SafetySetting>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Candidate {
+    pub content: Option<Content>,
+    pub finish_reason: Option<String>,
+}
+
+impl Candidate {
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.finish_reason.as_deref().map(|reason| match reason {
+            "STOP" => FinishReason::Stop,
+            "MAX_TOKENS" => FinishReason::Length,
+            "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => {
+                FinishReason::ContentFilter
+            }
+            reason => FinishReason::Other(reason.to_string()),
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PromptFeedback {
+    pub block_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UsageMetadata {
+    #[serde(default)]
+    pub prompt_token_count: u32,
+    #[serde(default)]
+    pub candidates_token_count: u32,
+}
+
+/// A response of `generateContent`, or an event of `streamGenerateContent`.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApiResponse {
+    #[serde(default)]
+    pub candidates: Vec<Candidate>,
+    pub prompt_feedback: Option<PromptFeedback>,
+    pub usage_metadata: Option<UsageMetadata>,
+}
+
+impl ApiResponse {
+    pub fn text(&self) -> String {
+        self.candidates
+            .first()
+            .and_then(|candidate| candidate.content.as_ref())
+            .map(Content::text)
+            .unwrap_or_default()
+    }
+
+    pub fn tokens(&self) -> Option<TokenUsage> {
+        self.usage_metadata
+            .as_ref()
+            .map(|usage| TokenUsage::new(usage.prompt_token_count, usage.candidates_token_count))
+    }
+}
@@ -3,3 +3,24 @@ pub use openai::*;
 
 pub mod claude;
 pub use claude::*;
+
+pub mod gemini;
+pub use gemini::*;
+
+#[cfg(feature = "bedrock")]
+pub mod bedrock;
+#[cfg(feature = "bedrock")]
+pub use bedrock::*;
+
+pub mod fake;
+pub use fake::*;
+
+#[cfg(feature = "ollama")]
+pub mod ollama;
+#[cfg(feature = "ollama")]
+pub use ollama::*;
+
+#[cfg(feature = "candle")]
+pub mod candle;
+#[cfg(feature = "candle")]
+pub use candle::*;
@@ -0,0 +1,446 @@
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use reqwest::{Client, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    callbacks::{RunManager, RunType},
+    language_models::{
+        llm::LLM, options::CallOptions, retry_after, FinishReason, GenerateResult, LLMError,
+        TokenUsage,
+    },
+    llm::OllamaError,
+    schemas::{Message, StreamData},
+};
+
+use super::models::{
+    ChatRequest, ChatResponse, GenerateRequest, GenerateResponse, ListResponse, ModelOptions,
+    OllamaMessage,
+};
+
+/// The sampling options the model parameters of Ollama have a name for.
+const SUPPORTED_OPTIONS: &[&str] = &[
+    "temperature",
+    "stop_words",
+    "top_k",
+    "top_p",
+    "seed",
+    "repetition_penalty",
+    "frequency_penalty",
+    "presence_penalty",
+];
+
+/// A model installed in the Ollama server, see `Ollama::list_models`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OllamaModel {
+    pub name: String,
+    #[serde(default)]
+    pub modified_at: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub digest: String,
+}
+
+/// Local models served by [Ollama](https://ollama.com), through its native API.
+///
+/// # Example
+/// ```rust,ignore
+/// let ollama = Ollama::default().with_model("llama3");
+/// ollama.pull_model("llama3").await?;
+/// let response = ollama.invoke("Hi").await?;
+/// ```
+#[derive(Clone)]
+pub struct Ollama {
+    model: String,
+    options: CallOptions,
+    base_url: String,
+}
+
+impl Default for Ollama {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ollama {
+    pub fn new() -> Self {
+        Self {
+            model: "llama3".to_string(),
+            options: CallOptions::default(),
+            base_url: "http://localhost:11434".to_string(),
+        }
+    }
+
+    pub fn with_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn with_options(mut self, options: CallOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_api_base<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    fn start_run(&self) -> RunManager {
+        self.options
+            .callbacks
+            .clone()
+            .unwrap_or_default()
+            .start_run(self.model.clone(), RunType::Llm)
+    }
+
+    fn chat_request(&self, messages: &[Message], stream: bool) -> ChatRequest {
+        ChatRequest {
+            model: self.model.clone(),
+            messages: messages.iter().map(OllamaMessage::from_message).collect(),
+            stream,
+            options: ModelOptions::from_options(&self.options),
+        }
+    }
+
+    async fn post<T: Serialize>(&self, path: &str, body: &T) -> Result<Response, LLMError> {
+        self.options.check_supported("Ollama", SUPPORTED_OPTIONS)?;
+        let res = Client::new()
+            .post(format!("{}{}", self.base_url, path))
+            .json(body)
+            .send()
+            .await?;
+        check_status(res).await
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        let res: ChatResponse = self
+            .post("/api/chat", &self.chat_request(messages, false))
+            .await?
+            .json()
+            .await?;
+        Ok(GenerateResult {
+            tokens: token_usage(res.prompt_eval_count, res.eval_count),
+            generation: res.message.map(|m| m.content).unwrap_or_default(),
+            finish_reason: res.done_reason.as_deref().map(finish_reason),
+            model: Some(self.model.clone()),
+            ..Default::default()
+        })
+    }
+
+    /// Completes `prompt` with `/api/generate`, without the chat template of the model.
+    pub async fn complete(&self, prompt: &str) -> Result<GenerateResult, LLMError> {
+        let request = GenerateRequest {
+            model: self.model.clone(),
+            prompt: prompt.to_string(),
+            stream: false,
+            options: ModelOptions::from_options(&self.options),
+        };
+        let res: GenerateResponse = self.post("/api/generate", &request).await?.json().await?;
+        Ok(GenerateResult {
+            tokens: token_usage(res.prompt_eval_count, res.eval_count),
+            generation: res.response,
+            finish_reason: res.done_reason.as_deref().map(finish_reason),
+            model: Some(self.model.clone()),
+            ..Default::default()
+        })
+    }
+
+    /// The models installed in the server.
+    pub async fn list_models(&self) -> Result<Vec<OllamaModel>, LLMError> {
+        let res = Client::new()
+            .get(format!("{}/api/tags", self.base_url))
+            .send()
+            .await?;
+        let res: ListResponse = check_status(res).await?.json().await?;
+        Ok(res.models)
+    }
+
+    /// Downloads `model` from the Ollama library, waiting for the download to finish.
+    pub async fn pull_model(&self, model: &str) -> Result<(), LLMError> {
+        let res: Value = self
+            .post("/api/pull", &json!({"model": model, "stream": false}))
+            .await?
+            .json()
+            .await?;
+        match res["status"].as_str() {
+            Some("success") => Ok(()),
+            _ => Err(OllamaError::PullError(res.to_string()))?,
+        }
+    }
+}
+
+async fn check_status(res: Response) -> Result<Response, LLMError> {
+    let status = res.status().as_u16();
+    match status {
+        200..=299 => Ok(res),
+        _ => {
+            let retry_after = retry_after(res.headers());
+            let body: Value = res.json().await.unwrap_or_default();
+            let message = body["error"].as_str().unwrap_or_default().to_string();
+            if let Some(error) = LLMError::from_status(status, retry_after, &message) {
+                return Err(error);
+            }
+            match status {
+                404 => Err(OllamaError::ModelNotFound(message))?,
+                _ => Err(OllamaError::ApiError { status, message })?,
+            }
+        }
+    }
+}
+
+fn finish_reason(reason: &str) -> FinishReason {
+    match reason {
+        "stop" => FinishReason::Stop,
+        "length" => FinishReason::Length,
+        reason => FinishReason::Other(reason.to_string()),
+    }
+}
+
+fn token_usage(prompt_tokens: Option<u32>, completion_tokens: Option<u32>) -> Option<TokenUsage> {
+    let (prompt_tokens, completion_tokens) = (prompt_tokens?, completion_tokens.unwrap_or(0));
+    Some(TokenUsage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+    })
+}
+
+#[async_trait]
+impl LLM for Ollama {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerateResult, LLMError> {
+        match &self.options.streaming_func {
+            Some(func) => {
+                let mut complete_response = String::new();
+                let mut stream = self.stream(messages).await?;
+                while let Some(data) = stream.next().await {
+                    let data = data?;
+                    let mut func = func.lock().await;
+                    complete_response.push_str(&data.content);
+                    let _ = func(data.content).await;
+                }
+                Ok(GenerateResult {
+                    generation: complete_response,
+                    model: Some(self.model.clone()),
+                    ..Default::default()
+                })
+            }
+            None => {
+                self.start_run()
+                    .trace_llm(messages, self.chat(messages))
+                    .await
+            }
+        }
+    }
+
+    async fn stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
+        let mut bytes = self
+            .post("/api/chat", &self.chat_request(messages, true))
+            .await?
+            .bytes_stream();
+
+        let parse_chunk = |line: &str| {
+            let value: Value = serde_json::from_str(line)?;
+            if let Some(error) = value["error"].as_str() {
+                Err(OllamaError::ApiError {
+                    status: 200,
+                    message: error.to_string(),
+                })?
+            }
+            let chunk: ChatResponse = serde_json::from_value(value.clone())?;
+            let content = chunk.message.map(|m| m.content).unwrap_or_default();
+            Ok(StreamData::new(value, content))
+        };
+
+        // The stream is made of json lines, which may be split across chunks, as may be
+        // their characters, so the lines are decoded once complete.
+        let stream = async_stream::stream! {
+            let mut buffer = Vec::new();
+            while let Some(chunk) = bytes.next().await {
+                match chunk {
+                    Ok(chunk) => buffer.extend_from_slice(&chunk),
+                    Err(e) => {
+                        yield Err(LLMError::RequestError(e));
+                        return;
+                    }
+                }
+                for line in take_lines(&mut buffer) {
+                    yield parse_chunk(&line);
+                }
+            }
+            let line = String::from_utf8_lossy(&buffer);
+            if !line.trim().is_empty() {
+                yield parse_chunk(line.trim());
+            }
+        };
+
+        Ok(self
+            .start_run()
+            .trace_llm_stream(messages, Box::pin(stream)))
+    }
+
+    fn add_options(&mut self, options: CallOptions) {
+        self.options.merge_options(options)
+    }
+}
+
+/// Takes the complete lines out of `buffer`, without the blank ones.
+fn take_lines(buffer: &mut Vec<u8>) -> Vec<String> {
+    let mut lines = Vec::new();
+    while let Some(index) = buffer.iter().position(|byte| *byte == b'\n') {
+        let line: Vec<u8> = buffer.drain(..=index).collect();
+        let line = String::from_utf8_lossy(&line);
+        if !line.trim().is_empty() {
+            lines.push(line.trim().to_string());
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+    use tokio::test;
+
+    use super::*;
+
+    #[test]
+    async fn test_ollama_chat() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/chat")
+            .match_body(Matcher::Json(json!({
+                "model": "llama3",
+                "messages": [
+                    {"role": "system", "content": "Be brief"},
+                    {"role": "user", "content": "Hi"},
+                ],
+                "stream": false,
+                "options": {"temperature": 0.5, "num_predict": 20},
+            })))
+            .with_body(
+                json!({
+                    "model": "llama3",
+                    "message": {"role": "assistant", "content": "Hello"},
+                    "done": true,
+                    "done_reason": "stop",
+                    "prompt_eval_count": 8,
+                    "eval_count": 2,
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let ollama = Ollama::new()
+            .with_api_base(server.url())
+            .with_options(CallOptions::new().with_temperature(0.5).with_max_tokens(20));
+        let result = ollama
+            .generate(&[
+                Message::new_system_message("Be brief"),
+                Message::new_human_message("Hi"),
+            ])
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.generation, "Hello");
+        assert_eq!(result.tokens.unwrap().total_tokens, 10);
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+    }
+
+    #[test]
+    async fn test_ollama_stream() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/api/chat")
+            .match_body(Matcher::PartialJson(json!({"stream": true})))
+            .with_body(concat!(
+                r#"{"message":{"role":"assistant","content":"Hel"},"done":false}"#,
+                "\n",
+                r#"{"message":{"role":"assistant","content":"lo"},"done":false}"#,
+                "\n",
+                r#"{"message":{"role":"assistant","content":""},"done":true,"eval_count":2}"#,
+                "\n",
+            ))
+            .create_async()
+            .await;
+
+        let ollama = Ollama::new().with_api_base(server.url());
+        let mut stream = ollama
+            .stream(&[Message::new_human_message("Hi")])
+            .await
+            .unwrap();
+        let mut content = String::new();
+        while let Some(data) = stream.next().await {
+            content.push_str(&data.unwrap().content);
+        }
+        assert_eq!(content, "Hello");
+    }
+
+    #[test]
+    async fn test_ollama_models() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models":[{"name":"llama3:latest","size":4661224676}]}"#)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/api/pull")
+            .match_body(Matcher::Json(json!({"model": "llama3", "stream": false})))
+            .with_body(r#"{"status":"success"}"#)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/api/generate")
+            .with_status(404)
+            .with_body(r#"{"error":"model 'mistral' not found"}"#)
+            .create_async()
+            .await;
+
+        let ollama = Ollama::new().with_api_base(server.url());
+        let models = ollama.list_models().await.unwrap();
+        assert_eq!(models[0].name, "llama3:latest");
+        ollama.pull_model("llama3").await.unwrap();
+        let error = ollama
+            .with_model("mistral")
+            .complete("Hi")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            LLMError::OllamaError(OllamaError::ModelNotFound(_))
+        ));
+    }
+
+    #[test]
+    #[ignore]
+    async fn test_ollama_generate() {
+        let ollama = Ollama::default();
+        let res = ollama.invoke("Hi, how are you doing").await.unwrap();
+        println!("{:?}", res)
+    }
+
+    #[test]
+    async fn test_take_lines() {
+        let line = r#"{"message":{"content":"été"}}"#.as_bytes();
+        // Split in the middle of the "é".
+        let mut buffer = line[..24].to_vec();
+        assert!(take_lines(&mut buffer).is_empty());
+        buffer.extend_from_slice(&line[24..]);
+        buffer.extend_from_slice(b"\n\n{");
+        assert_eq!(
+            take_lines(&mut buffer),
+            vec![r#"{"message":{"content":"été"}}"#]
+        );
+        assert_eq!(buffer, b"{");
+    }
+}
@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum OllamaError {
+    #[error("Ollama API error {status}: {message}")]
+    ApiError { status: u16, message: String },
+
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+
+    #[error("Pull failed: {0}")]
+    PullError(String),
+}
@@ -0,0 +1,7 @@
+mod models;
+
+mod client;
+pub use client::*;
+
+mod error;
+pub use error::*;
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    language_models::options::CallOptions,
+    schemas::{ContentPart, Message, MessageContent, MessageType},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct OllamaMessage {
+    pub role: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+}
+
+impl OllamaMessage {
+    pub fn from_message(message: &Message) -> Self {
+        let role = match message.message_type {
+            MessageType::SystemMessage => "system",
+            MessageType::AIMessage => "assistant",
+            MessageType::HumanMessage => "user",
+            MessageType::ToolMessage => "tool",
+        };
+        Self {
+            role: role.to_string(),
+            content: message.content.text(),
+            images: images(&message.content),
+        }
+    }
+}
+
+/// The base64 images of a message, Ollama doesn't download the images from urls.
+fn images(content: &MessageContent) -> Vec<String> {
+    content
+        .parts()
+        .iter()
+        .filter_map(|part| match part {
+            ContentPart::Image { url, .. } => match url.split_once(";base64,") {
+                Some((_, data)) => Some(data.to_string()),
+                None => {
+                    log::warn!("Ollama only supports base64 images, skipping {}", url);
+                    None
+                }
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// The model parameters of the requests.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct ModelOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+}
+
+impl ModelOptions {
+    pub fn from_options(options: &CallOptions) -> Option<Self> {
+        let model_options = Self {
+            temperature: options.temperature,
+            num_predict: options.max_tokens,
+            top_k: options.top_k,
+            top_p: options.top_p,
+            seed: options.seed,
+            repeat_penalty: options.repetition_penalty,
+            presence_penalty: options.presence_penalty,
+            frequency_penalty: options.frequency_penalty,
+            stop: options.stop_words.clone(),
+        };
+        (model_options != Self::default()).then_some(model_options)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct ChatRequest {
+    pub model: String,
+    pub messages: Vec<OllamaMessage>,
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<ModelOptions>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct GenerateRequest {
+    pub model: String,
+    pub prompt: String,
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<ModelOptions>,
+}
+
+/// A response of `/api/chat`, or a chunk of its stream.
+#[derive(Deserialize, Debug)]
+pub(crate) struct ChatResponse {
+    pub message: Option<OllamaMessage>,
+    #[serde(default)]
+    pub done: bool,
+    pub done_reason: Option<String>,
+    pub prompt_eval_count: Option<u32>,
+    pub eval_count: Option<u32>,
+}
+
+/// A response of `/api/generate`.
+#[derive(Deserialize, Debug)]
+pub(crate) struct GenerateResponse {
+    pub response: String,
+    pub done_reason: Option<String>,
+    pub prompt_eval_count: Option<u32>,
+    pub eval_count: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct ListResponse {
+    pub models: Vec<super::OllamaModel>,
+}
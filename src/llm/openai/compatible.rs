@@ -0,0 +1,184 @@
+use std::fmt;
+
+use async_openai::config::OpenAIConfig;
+
+use super::OpenAIModel;
+
+const MISTRAL_API_BASE: &str = "https://api.mistral.ai/v1";
+const GROQ_API_BASE: &str = "https://api.groq.com/openai/v1";
+
+/// Chat models of Mistral AI.
+#[derive(Clone)]
+pub enum MistralModel {
+    MistralLarge,
+    MistralSmall,
+    OpenMistralNemo,
+    Codestral,
+}
+
+impl fmt::Display for MistralModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MistralModel::MistralLarge => write!(f, "mistral-large-latest"),
+            MistralModel::MistralSmall => write!(f, "mistral-small-latest"),
+            MistralModel::OpenMistralNemo => write!(f, "open-mistral-nemo"),
+            MistralModel::Codestral => write!(f, "codestral-latest"),
+        }
+    }
+}
+
+impl From<MistralModel> for String {
+    fn from(model: MistralModel) -> Self {
+        model.to_string()
+    }
+}
+
+/// Chat models served by Groq.
+#[derive(Clone)]
+pub enum GroqModel {
+    Llama3_70b,
+    Llama3_8b,
+    Mixtral8x7b,
+    Gemma2_9b,
+}
+
+impl fmt::Display for GroqModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroqModel::Llama3_70b => write!(f, "llama3-70b-8192"),
+            GroqModel::Llama3_8b => write!(f, "llama3-8b-8192"),
+            GroqModel::Mixtral8x7b => write!(f, "mixtral-8x7b-32768"),
+            GroqModel::Gemma2_9b => write!(f, "gemma2-9b-it"),
+        }
+    }
+}
+
+impl From<GroqModel> for String {
+    fn from(model: GroqModel) -> Self {
+        model.to_string()
+    }
+}
+
+/// Configurations of the providers with an API compatible with OpenAI.
+///
+/// # Example
+/// ```rust,ignore
+/// let mistral = OpenAI::new(OpenAIConfig::mistral()).with_model(MistralModel::MistralSmall);
+/// let groq = OpenAI::new(OpenAIConfig::groq().with_api_key("gsk_..."));
+/// ```
+pub trait CompatibleConfig {
+    /// Mistral AI, authenticated with the `MISTRAL_API_KEY` environment variable.
+    fn mistral() -> Self;
+
+    /// Groq, authenticated with the `GROQ_API_KEY` environment variable.
+    fn groq() -> Self;
+}
+
+impl CompatibleConfig for OpenAIConfig {
+    fn mistral() -> Self {
+        OpenAIConfig::new()
+            .with_api_base(MISTRAL_API_BASE)
+            .with_api_key(std::env::var("MISTRAL_API_KEY").unwrap_or_default())
+    }
+
+    fn groq() -> Self {
+        OpenAIConfig::new()
+            .with_api_base(GROQ_API_BASE)
+            .with_api_key(std::env::var("GROQ_API_KEY").unwrap_or_default())
+    }
+}
+
+/// The provider behind an api base, for the parameters they don't support.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Provider {
+    OpenAI,
+    Mistral,
+    Groq,
+}
+
+impl Provider {
+    pub(crate) fn from_api_base(api_base: &str) -> Self {
+        if api_base.starts_with(MISTRAL_API_BASE) {
+            Provider::Mistral
+        } else if api_base.starts_with(GROQ_API_BASE) {
+            Provider::Groq
+        } else {
+            Provider::OpenAI
+        }
+    }
+
+    pub(crate) fn default_model(&self) -> String {
+        match self {
+            Provider::OpenAI => OpenAIModel::Gpt35.to_string(),
+            Provider::Mistral => MistralModel::MistralSmall.to_string(),
+            Provider::Groq => GroqModel::Llama3_70b.to_string(),
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Provider::OpenAI => "OpenAI",
+            Provider::Mistral => "Mistral",
+            Provider::Groq => "Groq",
+        }
+    }
+
+    /// The sampling options of `CallOptions` the provider has a parameter for.
+    pub(crate) fn supported_options(&self) -> &'static [&'static str] {
+        match self {
+            Provider::OpenAI => &[
+                "temperature",
+                "stop_words",
+                "top_p",
+                "seed",
+                "frequency_penalty",
+                "presence_penalty",
+                "logit_bias",
+            ],
+            // Mistral names its seed `random_seed`.
+            Provider::Mistral => &[
+                "temperature",
+                "stop_words",
+                "top_p",
+                "frequency_penalty",
+                "presence_penalty",
+            ],
+            Provider::Groq => &[
+                "temperature",
+                "stop_words",
+                "top_p",
+                "seed",
+                "frequency_penalty",
+                "presence_penalty",
+            ],
+        }
+    }
+
+    /// Mistral rejects the requests with a `user`.
+    pub(crate) fn supports_user(&self) -> bool {
+        !matches!(self, Provider::Mistral)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_openai::config::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_provider_from_api_base() {
+        assert_eq!(
+            Provider::from_api_base(OpenAIConfig::mistral().api_base()),
+            Provider::Mistral
+        );
+        assert_eq!(
+            Provider::from_api_base(OpenAIConfig::groq().api_base()),
+            Provider::Groq
+        );
+        assert_eq!(
+            Provider::from_api_base(OpenAIConfig::new().api_base()),
+            Provider::OpenAI
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! The requests with the `json_schema` response format, which async-openai doesn't
+//! support, are sent as JSON with the response format added.
+
+use async_openai::{
+    config::Config,
+    error::{ApiError, OpenAIError},
+    types::{CreateChatCompletionRequest, CreateChatCompletionResponse},
+};
+use futures::StreamExt;
+use reqwest_eventsource::{Event, EventSource};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    runtime::{send, send_stream},
+    schemas::ResponseFormat,
+};
+
+use super::ChatCompletionStream;
+
+#[derive(Deserialize)]
+struct WrappedError {
+    error: ApiError,
+}
+
+fn api_error(body: &[u8]) -> OpenAIError {
+    match serde_json::from_slice::<WrappedError>(body) {
+        Ok(wrapped) => OpenAIError::ApiError(wrapped.error),
+        Err(_) => OpenAIError::StreamError(String::from_utf8_lossy(body).to_string()),
+    }
+}
+
+fn request<C: Config>(
+    config: &C,
+    request: &CreateChatCompletionRequest,
+    response_format: &ResponseFormat,
+    stream: bool,
+) -> Result<reqwest::RequestBuilder, serde_json::Error> {
+    let mut body = serde_json::to_value(request)?;
+    body["response_format"] = response_format.to_openai();
+    if stream {
+        body["stream"] = Value::Bool(true);
+    }
+    Ok(reqwest::Client::new()
+        .post(config.url("/chat/completions"))
+        .query(&config.query())
+        .headers(config.headers())
+        .json(&body))
+}
+
+pub(crate) async fn create<C: Config>(
+    config: &C,
+    chat_request: &CreateChatCompletionRequest,
+    response_format: &ResponseFormat,
+) -> Result<CreateChatCompletionResponse, OpenAIError> {
+    let request = request(config, chat_request, response_format, false)
+        .map_err(|e| OpenAIError::InvalidArgument(e.to_string()))?;
+    send(async move {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            return Err(api_error(&body));
+        }
+        serde_json::from_slice(&body).map_err(OpenAIError::JSONDeserialize)
+    })
+    .await
+}
+
+pub(crate) async fn create_stream<C: Config>(
+    config: &C,
+    chat_request: &CreateChatCompletionRequest,
+    response_format: &ResponseFormat,
+) -> Result<ChatCompletionStream, OpenAIError> {
+    let request = request(config, chat_request, response_format, true)
+        .map_err(|e| OpenAIError::InvalidArgument(e.to_string()))?;
+    let mut events =
+        EventSource::new(request).map_err(|e| OpenAIError::StreamError(e.to_string()))?;
+    Ok(Box::pin(send_stream(async_stream::stream! {
+        while let Some(event) = events.next().await {
+            match event {
+                Ok(Event::Open) => continue,
+                Ok(Event::Message(message)) => {
+                    if message.data == "[DONE]" {
+                        break;
+                    }
+                    yield serde_json::from_str(&message.data).map_err(OpenAIError::JSONDeserialize);
+                }
+                Err(reqwest_eventsource::Error::InvalidStatusCode(_, response)) => {
+                    match response.bytes().await {
+                        Ok(body) => yield Err(api_error(&body)),
+                        Err(e) => yield Err(OpenAIError::Reqwest(e)),
+                    }
+                    break;
+                }
+                // The event source would reconnect at the end of the stream.
+                Err(reqwest_eventsource::Error::StreamEnded) => break,
+                Err(e) => {
+                    yield Err(OpenAIError::StreamError(e.to_string()));
+                    break;
+                }
+            }
+        }
+        events.close();
+    })))
+}
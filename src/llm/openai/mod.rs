@@ -1,27 +1,48 @@
-use std::pin::Pin;
+use std::{collections::HashMap, pin::Pin};
+
+mod compatible;
+pub use compatible::*;
+
+mod json_schema;
 
 pub use async_openai::config::{AzureConfig, Config, OpenAIConfig};
 use async_openai::{
+    error::OpenAIError,
     types::{
-        ChatChoiceStream, ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessageArgs,
-        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs,
+        ChatChoiceStream, ChatCompletionMessageToolCall, ChatCompletionMessageToolCallChunk,
+        ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage,
+        ChatCompletionRequestMessageContentPart, ChatCompletionRequestMessageContentPartImage,
+        ChatCompletionRequestMessageContentPartText, ChatCompletionRequestSystemMessageArgs,
         ChatCompletionRequestToolMessageArgs, ChatCompletionRequestUserMessageArgs,
-        ChatCompletionToolArgs, ChatCompletionToolType, CreateChatCompletionRequest,
-        CreateChatCompletionRequestArgs, FunctionObjectArgs,
+        ChatCompletionRequestUserMessageContent, ChatCompletionResponseFormat,
+        ChatCompletionResponseFormatType, ChatCompletionToolArgs, ChatCompletionToolChoiceOption,
+        ChatCompletionToolType, CreateChatCompletionRequest, CreateChatCompletionRequestArgs,
+        CreateChatCompletionResponse, CreateChatCompletionStreamResponse,
+        FinishReason as OpenAIFinishReason, FunctionObjectArgs, ImageUrl, ImageUrlDetail,
     },
     Client,
 };
 use async_trait::async_trait;
-use futures::{Stream, StreamExt};
+use futures::{future, Stream, StreamExt};
+use serde_json::Value;
 
 use crate::{
-    language_models::{llm::LLM, options::CallOptions, GenerateResult, LLMError, TokenUsage},
+    callbacks::{RunConfig, RunType},
+    language_models::{
+        llm::LLM, options::CallOptions, retry, FinishReason, GenerateResult, LLMError, TokenUsage,
+    },
+    runtime::{send, send_stream},
     schemas::{
         messages::{Message, MessageType},
-        FunctionCallBehavior, StreamData,
+        ContentPart, FunctionCallBehavior, ImageDetail, MessageContent, ResponseFormat, StreamData,
+        ToolCall,
     },
 };
 
+/// The chunks of a streamed completion, from async-openai or `json_schema`.
+type ChatCompletionStream =
+    Pin<Box<dyn Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>> + Send>>;
+
 #[derive(Clone)]
 pub enum OpenAIModel {
     Gpt35,
@@ -45,6 +66,26 @@ impl Into<String> for OpenAIModel {
     }
 }
 
+/// Chat models of OpenAI, or of any API compatible with it.
+///
+/// Azure OpenAI deployments are called with an `AzureConfig`: the requests are routed to
+/// the deployment instead of the model, with the `api-version` of the endpoint, and
+/// authenticated with the `api-key` header.
+///
+/// # Example
+/// ```rust,ignore
+/// let azure = OpenAI::new(
+///     AzureConfig::new()
+///         .with_api_base("https://my-resource.openai.azure.com")
+///         .with_api_version("2024-02-15-preview")
+///         .with_deployment_id("gpt-4o")
+///         .with_api_key(std::env::var("AZURE_OPENAI_API_KEY")?),
+/// );
+/// let response = azure.invoke("Why is the sky blue?").await?;
+/// ```
+///
+/// Mistral AI and Groq have their configuration in `CompatibleConfig`, the model defaults
+/// to one of theirs and the parameters they reject are not sent.
 #[derive(Clone)]
 pub struct OpenAI<C: Config> {
     config: C,
@@ -55,9 +96,9 @@ pub struct OpenAI<C: Config> {
 impl<C: Config> OpenAI<C> {
     pub fn new(config: C) -> Self {
         Self {
+            model: Provider::from_api_base(config.api_base()).default_model(),
             config,
             options: CallOptions::default(),
-            model: OpenAIModel::Gpt35.to_string(),
         }
     }
 
@@ -66,7 +107,12 @@ impl<C: Config> OpenAI<C> {
         self
     }
 
+    /// Sets the config, and the default model of its provider if the model was the default
+    /// one.
     pub fn with_config(mut self, config: C) -> Self {
+        if self.model == self.provider().default_model() {
+            self.model = Provider::from_api_base(config.api_base()).default_model();
+        }
         self.config = config;
         self
     }
@@ -77,6 +123,14 @@ impl<C: Config> OpenAI<C> {
     }
 }
 
+impl OpenAI<OpenAIConfig> {
+    /// The base url of the API, e.g. of a proxy or of a compatible provider.
+    pub fn with_api_base<S: Into<String>>(self, api_base: S) -> Self {
+        let config = self.config.clone().with_api_base(api_base);
+        self.with_config(config)
+    }
+}
+
 impl Default for OpenAI<OpenAIConfig> {
     fn default() -> Self {
         Self::new(OpenAIConfig::default())
@@ -86,63 +140,102 @@ impl Default for OpenAI<OpenAIConfig> {
 #[async_trait]
 impl<C: Config + Send + Sync + 'static> LLM for OpenAI<C> {
     async fn generate(&self, prompt: &[Message]) -> Result<GenerateResult, LLMError> {
-        let client = Client::with_config(self.config.clone());
-        let request = self.generate_request(prompt)?;
-        match &self.options.streaming_func {
-            Some(func) => {
-                let mut stream = client.chat().create_stream(request).await?;
-                let mut complete_response = String::new();
-                while let Some(result) = stream.next().await {
-                    match result {
-                        Ok(response) => {
-                            for chat_choice in response.choices.iter() {
-                                let chat_choice: ChatChoiceStream = chat_choice.clone();
-                                {
-                                    let mut func = func.lock().await;
-                                    let _ = func(
-                                        serde_json::to_string(&chat_choice).unwrap_or("".into()),
-                                    )
-                                    .await;
-                                }
-                                if let Some(content) = chat_choice.delta.content {
-                                    complete_response.push_str(&content);
+        let run = self
+            .options
+            .callbacks
+            .clone()
+            .unwrap_or_default()
+            .start_run(self.model.clone(), RunType::Llm);
+        run.trace_llm(prompt, async {
+            let client = self.client();
+            let request = self.generate_request(prompt)?;
+            match &self.options.streaming_func {
+                Some(func) => {
+                    let mut stream = self.create_stream(&client, request).await?;
+                    let mut complete_response = String::new();
+                    let mut tool_calls = ToolCallChunks::default();
+                    let mut finish_reason = None;
+                    while let Some(result) = stream.next().await {
+                        match result {
+                            Ok(response) => {
+                                for chat_choice in response.choices.iter() {
+                                    let chat_choice: ChatChoiceStream = chat_choice.clone();
+                                    {
+                                        let mut func = func.lock().await;
+                                        let _ = func(
+                                            serde_json::to_string(&chat_choice)
+                                                .unwrap_or("".into()),
+                                        )
+                                        .await;
+                                    }
+                                    if let Some(content) = chat_choice.delta.content {
+                                        run.on_llm_new_token(&content);
+                                        complete_response.push_str(&content);
+                                    }
+                                    if let Some(chunks) = &chat_choice.delta.tool_calls {
+                                        tool_calls.push(chunks);
+                                    }
+                                    if let Some(reason) = &chat_choice.finish_reason {
+                                        finish_reason = Some(openai_finish_reason(reason));
+                                    }
                                 }
                             }
-                        }
-                        Err(err) => {
-                            eprintln!("Error from streaming response: {:?}", err);
+                            Err(err) => {
+                                eprintln!("Error from streaming response: {:?}", err);
+                            }
                         }
                     }
+                    Ok(GenerateResult {
+                        generation: complete_response,
+                        finish_reason,
+                        tool_calls: tool_calls.into_tool_calls(),
+                        model: Some(self.model.clone()),
+                        ..Default::default()
+                    })
                 }
-                let mut generate_result = GenerateResult::default();
-                generate_result.generation = complete_response;
-                Ok(generate_result)
-            }
-            None => {
-                let response = client.chat().create(request).await?;
-                let mut generate_result = GenerateResult::default();
-
-                if let Some(usage) = response.usage {
-                    generate_result.tokens = Some(TokenUsage {
-                        prompt_tokens: usage.prompt_tokens,
-                        completion_tokens: usage.completion_tokens,
-                        total_tokens: usage.total_tokens,
-                    });
-                }
+                None => {
+                    let response = retry(self.options.retry.as_ref(), || async {
+                        self.create(&client, request.clone()).await
+                    })
+                    .await?;
+                    let mut generate_result = GenerateResult {
+                        model: Some(response.model.clone()),
+                        ..Default::default()
+                    };
+
+                    if let Some(usage) = response.usage {
+                        generate_result.tokens = Some(TokenUsage {
+                            prompt_tokens: usage.prompt_tokens,
+                            completion_tokens: usage.completion_tokens,
+                            total_tokens: usage.total_tokens,
+                        });
+                    }
 
-                if let Some(choice) = &response.choices.first() {
-                    generate_result.generation = choice.message.content.clone().unwrap_or_default();
-                    if let Some(function) = &choice.message.tool_calls {
+                    if let Some(choice) = &response.choices.first() {
                         generate_result.generation =
-                            serde_json::to_string(&function).unwrap_or_default();
+                            choice.message.content.clone().unwrap_or_default();
+                        generate_result.finish_reason =
+                            choice.finish_reason.as_ref().map(openai_finish_reason);
+                        generate_result.tool_calls = choice
+                            .message
+                            .tool_calls
+                            .iter()
+                            .flatten()
+                            .map(|tool_call| {
+                                ToolCall::new(
+                                    tool_call.id.as_str(),
+                                    tool_call.function.name.as_str(),
+                                    &tool_call.function.arguments,
+                                )
+                            })
+                            .collect();
                     }
-                } else {
-                    generate_result.generation = "".to_string();
-                }
 
-                Ok(generate_result)
+                    Ok(generate_result)
+                }
             }
-        }
+        })
+        .await
     }
 
     async fn invoke(&self, prompt: &str) -> Result<String, LLMError> {
@@ -155,10 +248,17 @@ impl<C: Config + Send + Sync + 'static> LLM for OpenAI<C> {
         &self,
         messages: &[Message],
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, LLMError>> + Send>>, LLMError> {
-        let client = Client::with_config(self.config.clone());
+        let client = self.client();
         let request = self.generate_request(messages)?;
 
-        let original_stream = client.chat().create_stream(request).await?;
+        // Some providers send chunks without any choice, e.g. with the usage or the content
+        // filter results only.
+        let original_stream = self
+            .create_stream(&client, request)
+            .await?
+            .filter(|result| {
+                future::ready(!matches!(result, Ok(chunk) if chunk.choices.is_empty()))
+            });
 
         let new_stream = original_stream.map(|result| match result {
             Ok(completion) => {
@@ -178,7 +278,13 @@ impl<C: Config + Send + Sync + 'static> LLM for OpenAI<C> {
             Err(e) => Err(LLMError::from(e)),
         });
 
-        Ok(Box::pin(new_stream))
+        let run = self
+            .options
+            .callbacks
+            .clone()
+            .unwrap_or_default()
+            .start_run(self.model.clone(), RunType::Llm);
+        Ok(run.trace_llm_stream(messages, Box::pin(new_stream)))
     }
 
     fn add_options(&mut self, options: CallOptions) {
@@ -186,7 +292,101 @@ impl<C: Config + Send + Sync + 'static> LLM for OpenAI<C> {
     }
 }
 
+fn openai_finish_reason(reason: &OpenAIFinishReason) -> FinishReason {
+    match reason {
+        OpenAIFinishReason::Stop => FinishReason::Stop,
+        OpenAIFinishReason::Length => FinishReason::Length,
+        OpenAIFinishReason::ToolCalls | OpenAIFinishReason::FunctionCall => FinishReason::ToolCalls,
+        OpenAIFinishReason::ContentFilter => FinishReason::ContentFilter,
+    }
+}
+
+/// The tool calls of a streamed response, whose ids, names and arguments come in chunks
+/// indexed by the call they belong to.
+#[derive(Default)]
+struct ToolCallChunks {
+    calls: Vec<(i32, String, String, String)>,
+}
+
+impl ToolCallChunks {
+    fn push(&mut self, chunks: &[ChatCompletionMessageToolCallChunk]) {
+        for chunk in chunks {
+            let position = match self.calls.iter().position(|call| call.0 == chunk.index) {
+                Some(position) => position,
+                None => {
+                    self.calls
+                        .push((chunk.index, String::new(), String::new(), String::new()));
+                    self.calls.len() - 1
+                }
+            };
+            let call = &mut self.calls[position];
+            if let Some(id) = &chunk.id {
+                call.1.push_str(id);
+            }
+            if let Some(function) = &chunk.function {
+                if let Some(name) = &function.name {
+                    call.2.push_str(name);
+                }
+                if let Some(arguments) = &function.arguments {
+                    call.3.push_str(arguments);
+                }
+            }
+        }
+    }
+
+    fn into_tool_calls(self) -> Vec<ToolCall> {
+        self.calls
+            .into_iter()
+            .map(|(_, id, name, arguments)| ToolCall::new(id, name, &arguments))
+            .collect()
+    }
+}
+
+/// The content of a user message, with its images. OpenAI only supports text and image
+/// parts in the user messages, the other parts are skipped.
+fn user_message_content(content: &MessageContent) -> ChatCompletionRequestUserMessageContent {
+    let parts = match content {
+        MessageContent::Text(text) => return text.clone().into(),
+        MessageContent::Parts(parts) => parts,
+    };
+    let parts = parts
+        .iter()
+        .filter_map(|part| match part {
+            ContentPart::Text { text } => Some(ChatCompletionRequestMessageContentPart::Text(
+                ChatCompletionRequestMessageContentPartText {
+                    r#type: "text".into(),
+                    text: text.clone(),
+                },
+            )),
+            ContentPart::Image { url, detail } => {
+                Some(ChatCompletionRequestMessageContentPart::Image(
+                    ChatCompletionRequestMessageContentPartImage {
+                        r#type: "image_url".into(),
+                        image_url: ImageUrl {
+                            url: url.clone(),
+                            detail: match detail {
+                                Some(ImageDetail::Low) => ImageUrlDetail::Low,
+                                Some(ImageDetail::High) => ImageUrlDetail::High,
+                                Some(ImageDetail::Auto) | None => ImageUrlDetail::Auto,
+                            },
+                        },
+                    },
+                ))
+            }
+            part => {
+                log::warn!("OpenAI doesn't support the content part {:?}", part);
+                None
+            }
+        })
+        .collect();
+    ChatCompletionRequestUserMessageContent::Array(parts)
+}
+
 impl<C: Config> OpenAI<C> {
+    fn provider(&self) -> Provider {
+        Provider::from_api_base(self.config.api_base())
+    }
+
     fn to_openai_messages(
         &self,
         messages: &[Message],
@@ -200,31 +400,31 @@ impl<C: Config> OpenAI<C> {
                             serde_json::from_value(value.clone())?;
                         ChatCompletionRequestAssistantMessageArgs::default()
                             .tool_calls(function)
-                            .content(m.content.clone())
+                            .content(m.content.text())
                             .build()?
                             .into()
                     }
                     None => ChatCompletionRequestAssistantMessageArgs::default()
-                        .content(m.content.clone())
+                        .content(m.content.text())
                         .build()?
                         .into(),
                 }),
                 MessageType::HumanMessage => openai_messages.push(
                     ChatCompletionRequestUserMessageArgs::default()
-                        .content(m.content.clone())
+                        .content(user_message_content(&m.content))
                         .build()?
                         .into(),
                 ),
                 MessageType::SystemMessage => openai_messages.push(
                     ChatCompletionRequestSystemMessageArgs::default()
-                        .content(m.content.clone())
+                        .content(m.content.text())
                         .build()?
                         .into(),
                 ),
                 MessageType::ToolMessage => {
                     openai_messages.push(
                         ChatCompletionRequestToolMessageArgs::default()
-                            .content(m.content.clone())
+                            .content(m.content.text())
                             .tool_call_id(m.id.clone().unwrap_or_default())
                             .build()?
                             .into(),
@@ -235,19 +435,104 @@ impl<C: Config> OpenAI<C> {
         Ok(openai_messages)
     }
 
+    /// The rate limited requests are retried by the client itself, with the backoff of the
+    /// retry policy if any. On wasm32, where the client has no backoff, `generate` retries
+    /// them, and the streams aren't retried.
+    fn client(&self) -> Client<C> {
+        let client = Client::with_config(self.config.clone());
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(retry) = &self.options.retry {
+            return client.with_backoff(retry.to_exponential_backoff());
+        }
+        client
+    }
+
+    /// The response format sent as JSON by the client itself, see `json_schema`.
+    fn json_schema_format(&self) -> Option<&ResponseFormat> {
+        self.options
+            .response_format
+            .as_ref()
+            .filter(|format| matches!(format, ResponseFormat::JsonSchema { .. }))
+    }
+
+    async fn create(
+        &self,
+        client: &Client<C>,
+        request: CreateChatCompletionRequest,
+    ) -> Result<CreateChatCompletionResponse, LLMError> {
+        match self.json_schema_format() {
+            Some(format) => Ok(json_schema::create(&self.config, &request, format).await?),
+            None => Ok(send(client.chat().create(request)).await?),
+        }
+    }
+
+    async fn create_stream(
+        &self,
+        client: &Client<C>,
+        request: CreateChatCompletionRequest,
+    ) -> Result<ChatCompletionStream, LLMError> {
+        match self.json_schema_format() {
+            Some(format) => Ok(json_schema::create_stream(&self.config, &request, format).await?),
+            None => Ok(Box::pin(send_stream(
+                send(client.chat().create_stream(request)).await?,
+            ))),
+        }
+    }
+
     fn generate_request(
         &self,
         messages: &[Message],
     ) -> Result<CreateChatCompletionRequest, LLMError> {
+        let provider = self.provider();
+        self.options
+            .check_supported(provider.name(), provider.supported_options())?;
         let messages: Vec<ChatCompletionRequestMessage> = self.to_openai_messages(messages)?;
         let mut request_builder = CreateChatCompletionRequestArgs::default();
         if let Some(max_tokens) = self.options.max_tokens {
             request_builder.max_tokens(max_tokens);
         }
         request_builder.model(self.model.to_string());
+        if let Some(user) = RunConfig::current()
+            .user()
+            .filter(|_| provider.supports_user())
+        {
+            request_builder.user(user);
+        }
         if let Some(stop_words) = &self.options.stop_words {
             request_builder.stop(stop_words);
         }
+        if let Some(temperature) = self.options.temperature {
+            request_builder.temperature(temperature);
+        }
+        if let Some(top_p) = self.options.top_p {
+            request_builder.top_p(top_p);
+        }
+        if let Some(seed) = self.options.seed {
+            request_builder.seed(seed as i64);
+        }
+        if let Some(frequency_penalty) = self.options.frequency_penalty {
+            request_builder.frequency_penalty(frequency_penalty);
+        }
+        if let Some(presence_penalty) = self.options.presence_penalty {
+            request_builder.presence_penalty(presence_penalty);
+        }
+        if let Some(logit_bias) = &self.options.logit_bias {
+            // OpenAI takes integer biases, keyed by the token ids as strings.
+            request_builder.logit_bias(
+                logit_bias
+                    .iter()
+                    .map(|(token, bias)| (token.to_string(), Value::from(bias.round() as i64)))
+                    .collect::<HashMap<_, _>>(),
+            );
+        }
+        let response_format_type = match &self.options.response_format {
+            Some(ResponseFormat::Text) => Some(ChatCompletionResponseFormatType::Text),
+            Some(ResponseFormat::JsonObject) => Some(ChatCompletionResponseFormatType::JsonObject),
+            _ => None,
+        };
+        if let Some(r#type) = response_format_type {
+            request_builder.response_format(ChatCompletionResponseFormat { r#type });
+        }
 
         if let Some(behavior) = &self.options.functions {
             let mut functions = Vec::new();
@@ -269,8 +554,12 @@ impl<C: Config> OpenAI<C> {
 
         if let Some(behavior) = self.options.function_call_behavior {
             match behavior {
-                FunctionCallBehavior::Auto => request_builder.function_call("auto"),
-                FunctionCallBehavior::None => request_builder.function_call("none"),
+                FunctionCallBehavior::Auto => {
+                    request_builder.tool_choice(ChatCompletionToolChoiceOption::Auto)
+                }
+                FunctionCallBehavior::None => {
+                    request_builder.tool_choice(ChatCompletionToolChoiceOption::None)
+                }
             };
         }
         request_builder.messages(messages);
@@ -288,6 +577,260 @@ mod tests {
     use tokio::sync::Mutex;
     use tokio::test;
 
+    #[test]
+    async fn test_azure_deployment() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/openai/deployments/gpt-4o/chat/completions")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "api-version".into(),
+                "2024-02-15-preview".into(),
+            ))
+            .match_header("api-key", "key")
+            .with_body(
+                json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion",
+                    "created": 1700000000,
+                    "model": "gpt-4o",
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": "Rayleigh scattering"},
+                        "finish_reason": "stop",
+                    }],
+                    "usage": {"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15},
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let azure = OpenAI::new(
+            AzureConfig::new()
+                .with_api_base(server.url())
+                .with_api_version("2024-02-15-preview")
+                .with_deployment_id("gpt-4o")
+                .with_api_key("key"),
+        );
+        let result = azure
+            .generate(&[Message::new_human_message("Why is the sky blue?")])
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.generation, "Rayleigh scattering");
+        assert_eq!(result.tokens.unwrap().total_tokens, 15);
+    }
+
+    #[test]
+    async fn test_generate_tool_calls() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "tools": [{"type": "function", "function": {"name": "calculator"}}],
+                "tool_choice": "auto",
+            })))
+            .with_body(
+                json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion",
+                    "created": 1700000000,
+                    "model": "gpt-4o",
+                    "choices": [{
+                        "index": 0,
+                        "message": {
+                            "role": "assistant",
+                            "content": null,
+                            "tool_calls": [{
+                                "id": "call_1",
+                                "type": "function",
+                                "function": {
+                                    "name": "calculator",
+                                    "arguments": "{\"expression\": \"2+2\"}",
+                                },
+                            }],
+                        },
+                        "finish_reason": "tool_calls",
+                    }],
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let llm = OpenAI::new(OpenAIConfig::new().with_api_base(server.url())).with_options(
+            CallOptions::new()
+                .with_functions(vec![FunctionDefinition::new(
+                    "calculator",
+                    "Useful to make calculations",
+                    json!({"type": "object", "properties": {"expression": {"type": "string"}}}),
+                )])
+                .with_function_call_behavior(FunctionCallBehavior::Auto),
+        );
+        let result = llm
+            .generate(&[Message::new_human_message("What's 2+2?")])
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.generation, "");
+        assert_eq!(result.finish_reason, Some(FinishReason::ToolCalls));
+        assert_eq!(
+            result.tool_calls,
+            vec![ToolCall::new(
+                "call_1",
+                "calculator",
+                r#"{"expression": "2+2"}"#
+            )]
+        );
+    }
+
+    #[test]
+    async fn test_compatible_providers() {
+        assert_eq!(OpenAI::new(OpenAIConfig::groq()).model, "llama3-70b-8192");
+        assert_eq!(
+            OpenAI::default().with_config(OpenAIConfig::mistral()).model,
+            "mistral-small-latest"
+        );
+        let codestral = OpenAI::default()
+            .with_model(MistralModel::Codestral)
+            .with_config(OpenAIConfig::mistral());
+        assert_eq!(codestral.model, "codestral-latest");
+
+        let config = RunConfig::new().with_user("user-1");
+        let messages = [Message::new_human_message("Hi")];
+        let request = config
+            .scope(async { codestral.generate_request(&messages) })
+            .await
+            .unwrap();
+        assert_eq!(request.user, None);
+        let request = config
+            .scope(async { OpenAI::new(OpenAIConfig::groq()).generate_request(&messages) })
+            .await
+            .unwrap();
+        assert_eq!(request.user.as_deref(), Some("user-1"));
+    }
+
+    #[test]
+    async fn test_sampling_options() {
+        let options = CallOptions::new()
+            .with_temperature(0.2)
+            .with_top_p(0.9)
+            .with_seed(42)
+            .with_stop_words(vec!["END".to_string()])
+            .with_frequency_penalty(0.5)
+            .with_logit_bias(HashMap::from([(50256, -100.0)]));
+        let messages = [Message::new_human_message("Hi")];
+        let request = OpenAI::default()
+            .with_options(options.clone())
+            .generate_request(&messages)
+            .unwrap();
+        assert_eq!(request.temperature, Some(0.2));
+        assert_eq!(request.top_p, Some(0.9));
+        assert_eq!(request.seed, Some(42));
+        assert_eq!(request.frequency_penalty, Some(0.5));
+        assert_eq!(
+            request.logit_bias,
+            Some(HashMap::from([("50256".to_string(), json!(-100))]))
+        );
+
+        let error = OpenAI::new(OpenAIConfig::groq())
+            .with_options(options)
+            .generate_request(&messages)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Groq doesn't support the option logit_bias"
+        );
+        assert!(matches!(
+            OpenAI::default()
+                .with_options(CallOptions::new().with_top_k(40))
+                .generate_request(&messages),
+            Err(LLMError::UnsupportedOption {
+                option: "top_k",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    async fn test_json_schema_stream() {
+        let chunk = |content: &str| {
+            json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion.chunk",
+                "created": 1700000000,
+                "model": "gpt-4o",
+                "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}],
+            })
+        };
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "stream": true,
+                "response_format": {"type": "json_schema", "json_schema": {"name": "answer"}},
+            })))
+            .with_header("content-type", "text/event-stream")
+            .with_body(format!(
+                "data: {}\n\ndata: {}\n\ndata: [DONE]\n\n",
+                chunk("{\"answer\":"),
+                chunk(" 42}")
+            ))
+            .create_async()
+            .await;
+
+        let llm = OpenAI::new(OpenAIConfig::new().with_api_base(server.url())).with_options(
+            CallOptions::new().with_response_format(ResponseFormat::json_schema(
+                "answer",
+                json!({
+                    "type": "object",
+                    "properties": {"answer": {"type": "integer"}},
+                    "required": ["answer"],
+                    "additionalProperties": false,
+                }),
+            )),
+        );
+        let content: Vec<String> = llm
+            .stream(&[Message::new_human_message("What is the answer?")])
+            .await
+            .unwrap()
+            .map(|data| data.unwrap().content)
+            .collect()
+            .await;
+        mock.assert_async().await;
+        assert_eq!(content.concat(), "{\"answer\": 42}");
+
+        let request = OpenAI::default()
+            .with_options(CallOptions::new().with_response_format(ResponseFormat::JsonObject))
+            .generate_request(&[Message::new_human_message("Hi")])
+            .unwrap();
+        assert_eq!(
+            request.response_format.map(|format| format.r#type),
+            Some(ChatCompletionResponseFormatType::JsonObject)
+        );
+    }
+
+    #[test]
+    async fn test_tool_call_chunks() {
+        let chunks: Vec<ChatCompletionMessageToolCallChunk> = serde_json::from_value(json!([
+            {"index": 0, "id": "call_1", "type": "function", "function": {"name": "calculator", "arguments": ""}},
+            {"index": 0, "function": {"arguments": "{\"expression\":"}},
+            {"index": 1, "id": "call_2", "function": {"name": "search", "arguments": "{}"}},
+            {"index": 0, "function": {"arguments": " \"2+2\"}"}},
+        ]))
+        .unwrap();
+        let mut tool_calls = ToolCallChunks::default();
+        tool_calls.push(&chunks[..2]);
+        tool_calls.push(&chunks[2..]);
+
+        let tool_calls = tool_calls.into_tool_calls();
+        assert_eq!(tool_calls.len(), 2);
+        assert_eq!(tool_calls[0].arguments, json!({"expression": "2+2"}));
+        assert_eq!(tool_calls[1].name, "search");
+    }
+
     #[test]
     #[ignore]
     async fn test_ivoke() {
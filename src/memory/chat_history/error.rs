@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ChatHistoryError {
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error("Invalid table name: {0}")]
+    InvalidTableName(String),
+
+    #[error("Failed to save the messages: {0}")]
+    WriteError(String),
+
+    #[cfg(any(feature = "sqlite", feature = "postgres"))]
+    #[error(transparent)]
+    SqlxError(#[from] sqlx::Error),
+
+    #[cfg(feature = "redis")]
+    #[error(transparent)]
+    RedisError(#[from] redis::RedisError),
+}
@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+
+use crate::schemas::Message;
+
+use super::ChatHistoryError;
+
+/// The messages of a conversation, kept in a store under its session id so that they
+/// survive restarts and are shared by every process serving the session. Load it in a
+/// chain with `ChatHistoryMemory`.
+#[async_trait]
+pub trait ChatMessageHistory: Send + Sync {
+    /// Returns the messages of the session, oldest first.
+    async fn messages(&self) -> Result<Vec<Message>, ChatHistoryError>;
+
+    /// Appends the messages to the session.
+    async fn add_messages(&self, messages: &[Message]) -> Result<(), ChatHistoryError>;
+
+    async fn add_message(&self, message: Message) -> Result<(), ChatHistoryError> {
+        self.add_messages(&[message]).await
+    }
+
+    /// Deletes the messages of the session.
+    async fn clear(&self) -> Result<(), ChatHistoryError>;
+}
+
+impl<H> From<H> for Box<dyn ChatMessageHistory>
+where
+    H: ChatMessageHistory + 'static,
+{
+    fn from(history: H) -> Self {
+        Box::new(history)
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+
+use crate::schemas::Message;
+
+use super::{ChatHistoryError, ChatMessageHistory};
+
+/// Chat history kept in memory, e.g. for tests. Clones share the same messages.
+#[derive(Clone, Default)]
+pub struct InMemoryChatMessageHistory {
+    messages: Arc<RwLock<Vec<Message>>>,
+}
+
+impl InMemoryChatMessageHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ChatMessageHistory for InMemoryChatMessageHistory {
+    async fn messages(&self) -> Result<Vec<Message>, ChatHistoryError> {
+        Ok(self
+            .messages
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone())
+    }
+
+    async fn add_messages(&self, messages: &[Message]) -> Result<(), ChatHistoryError> {
+        self.messages
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend_from_slice(messages);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), ChatHistoryError> {
+        self.messages
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde_json::json;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use crate::{
+    memory::save_turn,
+    prompt::PromptArgs,
+    runtime,
+    schemas::{
+        memory::{BaseMemory, MemoryUpdate, DEFAULT_MEMORY_KEY},
+        messages::Message,
+    },
+};
+
+use super::{ChatHistoryError, ChatMessageHistory};
+
+/// A memory loading the conversation of a `ChatMessageHistory`, and saving every message
+/// added to it, in the order they are added, from a background task. The chains wait for
+/// their turn to be saved, `flush` waits for the messages added so far. Load it for each
+/// request of the session, so that it has the turns saved by the other processes.
+///
+/// # Example
+/// ```rust,ignore
+/// let history = RedisChatMessageHistory::from_url("redis://127.0.0.1/", session_id)?;
+/// let memory = ChatHistoryMemory::load(history).await?.with_return_messages(true);
+/// let chain = LLMChainBuilder::new()
+///     .prompt(prompt)
+///     .llm(OpenAI::default())
+///     .memory(memory.into())
+///     .build()?;
+/// ```
+pub struct ChatHistoryMemory {
+    writes: mpsc::UnboundedSender<Write>,
+    messages: Vec<Message>,
+    memory_key: String,
+    input_key: Option<String>,
+    return_messages: bool,
+}
+
+/// A change of the memory to save to the history.
+enum Write {
+    Add(Message),
+    Clear,
+    /// Answered once the previous writes are saved.
+    Flush(oneshot::Sender<Result<(), ChatHistoryError>>),
+}
+
+/// Saves the writes to the history in order. The messages which fail to be saved are
+/// saved with the next write.
+async fn write_history(
+    history: Box<dyn ChatMessageHistory>,
+    mut writes: mpsc::UnboundedReceiver<Write>,
+) {
+    let mut pending = Vec::new();
+    let mut clear = false;
+    while let Some(write) = writes.recv().await {
+        let mut flushes = Vec::new();
+        let mut next = Some(write);
+        // Saves the writes queued meanwhile at once, e.g. the two messages of a turn.
+        while let Some(write) = next {
+            match write {
+                Write::Add(message) => pending.push(message),
+                Write::Clear => {
+                    pending.clear();
+                    clear = true;
+                }
+                Write::Flush(flush) => flushes.push(flush),
+            }
+            next = writes.try_recv().ok();
+        }
+
+        let mut result = Ok(());
+        if clear {
+            result = history.clear().await;
+            clear = result.is_err();
+        }
+        if result.is_ok() && !pending.is_empty() {
+            result = history.add_messages(&pending).await;
+            if result.is_ok() {
+                pending.clear();
+            }
+        }
+        let result = result.map_err(|e| e.to_string());
+        if let Err(e) = &result {
+            if flushes.is_empty() {
+                log::warn!("Failed to save the conversation: {}", e);
+            }
+        }
+        for flush in flushes {
+            let _ = flush.send(result.clone().map_err(ChatHistoryError::WriteError));
+        }
+    }
+}
+
+/// Waits for the writes sent before it to be saved.
+async fn flush_writes(writes: &mpsc::UnboundedSender<Write>) -> Result<(), ChatHistoryError> {
+    let (flush, flushed) = oneshot::channel();
+    writes
+        .send(Write::Flush(flush))
+        .map_err(|_| ChatHistoryError::WriteError("the history writer stopped".to_string()))?;
+    flushed
+        .await
+        .map_err(|_| ChatHistoryError::WriteError("the history writer stopped".to_string()))?
+}
+
+impl ChatHistoryMemory {
+    /// Loads the messages of the history, and starts the task saving the new ones.
+    pub async fn load<H: Into<Box<dyn ChatMessageHistory>>>(
+        history: H,
+    ) -> Result<Self, ChatHistoryError> {
+        let history = history.into();
+        let messages = history.messages().await?;
+        let (writes, receiver) = mpsc::unbounded_channel();
+        runtime::spawn(write_history(history, receiver));
+        Ok(Self {
+            writes,
+            messages,
+            memory_key: DEFAULT_MEMORY_KEY.to_string(),
+            input_key: None,
+            return_messages: false,
+        })
+    }
+
+    pub fn with_memory_key<S: Into<String>>(mut self, memory_key: S) -> Self {
+        self.memory_key = memory_key.into();
+        self
+    }
+
+    /// The input variable saved as the human message, when the prompts have several.
+    pub fn with_input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = Some(input_key.into());
+        self
+    }
+
+    /// Loads the history as messages instead of text.
+    pub fn with_return_messages(mut self, return_messages: bool) -> Self {
+        self.return_messages = return_messages;
+        self
+    }
+
+    /// Waits for the messages added so far to be saved to the history, with the error of
+    /// the history if they couldn't be.
+    pub async fn flush(&self) -> Result<(), ChatHistoryError> {
+        flush_writes(&self.writes).await
+    }
+
+    fn write(&self, write: Write) {
+        if self.writes.send(write).is_err() {
+            log::warn!("Failed to save the conversation: the history writer stopped");
+        }
+    }
+}
+
+impl From<ChatHistoryMemory> for Arc<Mutex<dyn BaseMemory>> {
+    fn from(memory: ChatHistoryMemory) -> Self {
+        Arc::new(Mutex::new(memory))
+    }
+}
+
+#[async_trait]
+impl BaseMemory for ChatHistoryMemory {
+    fn messages(&self) -> Vec<Message> {
+        self.messages.clone()
+    }
+
+    fn add_message(&mut self, message: Message) {
+        self.write(Write::Add(message.clone()));
+        self.messages.push(message);
+    }
+
+    fn clear(&mut self) {
+        self.write(Write::Clear);
+        self.messages.clear();
+    }
+
+    fn memory_key(&self) -> String {
+        self.memory_key.clone()
+    }
+
+    fn load_memory_variables(&self) -> PromptArgs {
+        let history = if self.return_messages {
+            json!(self.messages)
+        } else {
+            json!(self.to_string())
+        };
+        PromptArgs::from([(self.memory_key.clone(), history)])
+    }
+
+    fn save_context(&mut self, inputs: &PromptArgs, output: &str) {
+        let input_key = self.input_key.clone();
+        save_turn(self, input_key.as_deref(), inputs, output);
+    }
+
+    /// Waits for the turn to be saved. If the history fails, it is saved with the next
+    /// write.
+    fn prepare_update(&self) -> Option<BoxFuture<'static, Option<MemoryUpdate>>> {
+        let writes = self.writes.clone();
+        Some(Box::pin(async move {
+            if let Err(e) = flush_writes(&writes).await {
+                log::warn!("Failed to save the conversation: {}", e);
+            }
+            None
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{memory::InMemoryChatMessageHistory, prompt_args};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_chat_history_memory() {
+        let history = InMemoryChatMessageHistory::new();
+        history
+            .add_message(Message::new_human_message("Hi, I'm Ana"))
+            .await
+            .unwrap();
+
+        let mut memory = ChatHistoryMemory::load(history.clone()).await.unwrap();
+        assert_eq!(memory.messages().len(), 1);
+        memory.add_message(Message::new_ai_message("Hello Ana"));
+        memory
+            .save_context_async(&prompt_args! {"input" => "What's my name?"}, "Ana")
+            .await;
+        assert_eq!(history.messages().await.unwrap().len(), 4);
+
+        // A new memory of the session has the whole conversation.
+        let mut memory = ChatHistoryMemory::load(history.clone()).await.unwrap();
+        assert_eq!(
+            memory.load_memory_variables()["history"],
+            "human: Hi, I'm Ana\nai: Hello Ana\nhuman: What's my name?\nai: Ana"
+        );
+
+        memory.clear();
+        memory.flush().await.unwrap();
+        assert!(history.messages().await.unwrap().is_empty());
+
+        // The messages are saved without waiting for them.
+        memory.add_message(Message::new_human_message("Bye"));
+        drop(memory);
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert_eq!(history.messages().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,41 @@
+mod history_trait;
+pub use history_trait::*;
+
+mod error;
+pub use error::*;
+
+mod in_memory;
+pub use in_memory::*;
+
+mod memory;
+pub use memory::*;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
+
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "postgres")]
+pub use postgres::*;
+
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "redis")]
+pub use redis::*;
+
+/// Checks that the table name is an identifier, optionally qualified by a schema, as it
+/// can't be a parameter of the queries.
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+fn check_table_name(table: &str) -> Result<(), ChatHistoryError> {
+    let is_identifier = |part: &str| {
+        part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    if table.split('.').count() <= 2 && table.split('.').all(is_identifier) {
+        Ok(())
+    } else {
+        Err(ChatHistoryError::InvalidTableName(table.to_string()))
+    }
+}
@@ -0,0 +1,114 @@
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres, Row};
+
+use crate::schemas::Message;
+
+use super::{check_table_name, ChatHistoryError, ChatMessageHistory};
+
+/// Chat history keeping the messages of a session, as json, in a Postgres table.
+pub struct PostgresChatMessageHistory {
+    pool: Pool<Postgres>,
+    session_id: String,
+    table: String,
+}
+
+impl PostgresChatMessageHistory {
+    pub fn new<S: Into<String>>(pool: Pool<Postgres>, session_id: S) -> Self {
+        Self {
+            pool,
+            session_id: session_id.into(),
+            table: "message_store".to_string(),
+        }
+    }
+
+    /// The table of the messages, an identifier as it is put in the queries as is.
+    pub fn with_table<S: Into<String>>(mut self, table: S) -> Result<Self, ChatHistoryError> {
+        let table = table.into();
+        check_table_name(&table)?;
+        self.table = table;
+        Ok(self)
+    }
+
+    /// Creates the table if it doesn't exist.
+    pub async fn initialize(&self) -> Result<(), ChatHistoryError> {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (id BIGSERIAL PRIMARY KEY, \
+             session_id TEXT NOT NULL, message TEXT NOT NULL)",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChatMessageHistory for PostgresChatMessageHistory {
+    async fn messages(&self) -> Result<Vec<Message>, ChatHistoryError> {
+        let rows = sqlx::query(&format!(
+            "SELECT message FROM {} WHERE session_id = $1 ORDER BY id",
+            self.table
+        ))
+        .bind(&self.session_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| Ok(serde_json::from_str(row.try_get("message")?)?))
+            .collect()
+    }
+
+    async fn add_messages(&self, messages: &[Message]) -> Result<(), ChatHistoryError> {
+        let query = format!(
+            "INSERT INTO {} (session_id, message) VALUES ($1, $2)",
+            self.table
+        );
+        let mut tx = self.pool.begin().await?;
+        for message in messages {
+            sqlx::query(&query)
+                .bind(&self.session_id)
+                .bind(serde_json::to_string(message)?)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), ChatHistoryError> {
+        sqlx::query(&format!("DELETE FROM {} WHERE session_id = $1", self.table))
+            .bind(&self.session_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_postgres_chat_message_history() {
+        let dsn = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let pool = PgPoolOptions::new().connect(&dsn).await.unwrap();
+        let history = PostgresChatMessageHistory::new(pool, "test-session");
+        history.initialize().await.unwrap();
+        history.clear().await.unwrap();
+
+        history
+            .add_messages(&[
+                Message::new_human_message("Hi"),
+                Message::new_ai_message("Hello"),
+            ])
+            .await
+            .unwrap();
+        let messages = history.messages().await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content, "Hello");
+
+        history.clear().await.unwrap();
+    }
+}
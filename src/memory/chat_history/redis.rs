@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+
+use crate::schemas::Message;
+
+use super::{ChatHistoryError, ChatMessageHistory};
+
+/// Chat history keeping the messages of a session, as json, in a Redis list under
+/// `{prefix}{session_id}`.
+pub struct RedisChatMessageHistory {
+    client: redis::Client,
+    session_id: String,
+    prefix: String,
+    ttl: Option<Duration>,
+}
+
+impl RedisChatMessageHistory {
+    pub fn new<S: Into<String>>(client: redis::Client, session_id: S) -> Self {
+        Self {
+            client,
+            session_id: session_id.into(),
+            prefix: "message_store:".to_string(),
+            ttl: None,
+        }
+    }
+
+    pub fn from_url<S: Into<String>>(url: &str, session_id: S) -> Result<Self, ChatHistoryError> {
+        Ok(Self::new(redis::Client::open(url)?, session_id))
+    }
+
+    pub fn with_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Expires the session `ttl` after its last message.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn key(&self) -> String {
+        format!("{}{}", self.prefix, self.session_id)
+    }
+}
+
+#[async_trait]
+impl ChatMessageHistory for RedisChatMessageHistory {
+    async fn messages(&self) -> Result<Vec<Message>, ChatHistoryError> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        let values: Vec<String> = connection.lrange(self.key(), 0, -1).await?;
+        values
+            .iter()
+            .map(|value| Ok(serde_json::from_str(value)?))
+            .collect()
+    }
+
+    async fn add_messages(&self, messages: &[Message]) -> Result<(), ChatHistoryError> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+        let values = messages
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        let _: () = connection.rpush(self.key(), values).await?;
+        if let Some(ttl) = self.ttl {
+            let _: () = connection.expire(self.key(), ttl.as_secs() as i64).await?;
+        }
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), ChatHistoryError> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        let _: () = connection.del(self.key()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_redis_chat_message_history() {
+        let history = RedisChatMessageHistory::from_url("redis://127.0.0.1/", "test-session")
+            .unwrap()
+            .with_ttl(Duration::from_secs(60));
+        history.clear().await.unwrap();
+
+        history
+            .add_messages(&[
+                Message::new_human_message("Hi"),
+                Message::new_ai_message("Hello"),
+            ])
+            .await
+            .unwrap();
+        let messages = history.messages().await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content, "Hello");
+
+        history.clear().await.unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use sqlx::{Pool, Row, Sqlite};
+
+use crate::schemas::Message;
+
+use super::{check_table_name, ChatHistoryError, ChatMessageHistory};
+
+/// Chat history keeping the messages of a session, as json, in a SQLite table.
+pub struct SqliteChatMessageHistory {
+    pool: Pool<Sqlite>,
+    session_id: String,
+    table: String,
+}
+
+impl SqliteChatMessageHistory {
+    pub fn new<S: Into<String>>(pool: Pool<Sqlite>, session_id: S) -> Self {
+        Self {
+            pool,
+            session_id: session_id.into(),
+            table: "message_store".to_string(),
+        }
+    }
+
+    /// The table of the messages, an identifier as it is put in the queries as is.
+    pub fn with_table<S: Into<String>>(mut self, table: S) -> Result<Self, ChatHistoryError> {
+        let table = table.into();
+        check_table_name(&table)?;
+        self.table = table;
+        Ok(self)
+    }
+
+    /// Creates the table if it doesn't exist.
+    pub async fn initialize(&self) -> Result<(), ChatHistoryError> {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (id INTEGER PRIMARY KEY AUTOINCREMENT, \
+             session_id TEXT NOT NULL, message TEXT NOT NULL)",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChatMessageHistory for SqliteChatMessageHistory {
+    async fn messages(&self) -> Result<Vec<Message>, ChatHistoryError> {
+        let rows = sqlx::query(&format!(
+            "SELECT message FROM {} WHERE session_id = ? ORDER BY id",
+            self.table
+        ))
+        .bind(&self.session_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| Ok(serde_json::from_str(row.try_get("message")?)?))
+            .collect()
+    }
+
+    async fn add_messages(&self, messages: &[Message]) -> Result<(), ChatHistoryError> {
+        let query = format!(
+            "INSERT INTO {} (session_id, message) VALUES (?, ?)",
+            self.table
+        );
+        let mut tx = self.pool.begin().await?;
+        for message in messages {
+            sqlx::query(&query)
+                .bind(&self.session_id)
+                .bind(serde_json::to_string(message)?)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), ChatHistoryError> {
+        sqlx::query(&format!("DELETE FROM {} WHERE session_id = ?", self.table))
+            .bind(&self.session_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sqlite_chat_message_history() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let history = SqliteChatMessageHistory::new(pool.clone(), "session-1");
+        history.initialize().await.unwrap();
+        let other = SqliteChatMessageHistory::new(pool, "session-2");
+
+        history
+            .add_messages(&[
+                Message::new_human_message("Hi"),
+                Message::new_ai_message("Hello"),
+            ])
+            .await
+            .unwrap();
+        other
+            .add_message(Message::new_human_message("Bye"))
+            .await
+            .unwrap();
+
+        let messages = history.messages().await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content, "Hello");
+
+        history.clear().await.unwrap();
+        assert!(history.messages().await.unwrap().is_empty());
+        assert_eq!(other.messages().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_with_table() {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let history = SqliteChatMessageHistory::new(pool.clone(), "session-1");
+        assert!(history.with_table("main.chat_messages").is_ok());
+        for table in ["messages; DROP TABLE users", "1messages", "a.b.c", ""] {
+            assert!(matches!(
+                SqliteChatMessageHistory::new(pool.clone(), "session-1").with_table(table),
+                Err(ChatHistoryError::InvalidTableName(_))
+            ));
+        }
+    }
+}
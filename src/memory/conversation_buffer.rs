@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use crate::{
+    prompt::PromptArgs,
+    schemas::{
+        memory::{memory_input, BaseMemory, DEFAULT_MEMORY_KEY},
+        messages::Message,
+    },
+};
+
+/// A memory keeping the whole conversation, loaded in the prompts as text or, with
+/// `with_return_messages`, as messages for a `MessagesPlaceholder`.
+///
+/// # Example
+/// ```rust,ignore
+/// let memory = ConversationBufferMemory::new().with_return_messages(true);
+/// let chain = LLMChainBuilder::new()
+///     .prompt(message_formatter![
+///         fmt_placeholder!("history"),
+///         fmt_template!(HumanMessagePromptTemplate::new(template_fstring!("{input}", "input"))),
+///     ])
+///     .llm(OpenAI::default())
+///     .memory(memory.into())
+///     .build()?;
+/// ```
+pub struct ConversationBufferMemory {
+    messages: Vec<Message>,
+    memory_key: String,
+    input_key: Option<String>,
+    return_messages: bool,
+}
+
+impl Default for ConversationBufferMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConversationBufferMemory {
+    pub fn new() -> Self {
+        Self {
+            messages: Vec::new(),
+            memory_key: DEFAULT_MEMORY_KEY.to_string(),
+            input_key: None,
+            return_messages: false,
+        }
+    }
+
+    pub fn with_memory_key<S: Into<String>>(mut self, memory_key: S) -> Self {
+        self.memory_key = memory_key.into();
+        self
+    }
+
+    /// The input variable saved as the human message, when the prompts have several.
+    pub fn with_input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = Some(input_key.into());
+        self
+    }
+
+    /// Loads the history as messages instead of text.
+    pub fn with_return_messages(mut self, return_messages: bool) -> Self {
+        self.return_messages = return_messages;
+        self
+    }
+
+    pub fn with_messages(mut self, messages: Vec<Message>) -> Self {
+        self.messages = messages;
+        self
+    }
+}
+
+impl From<ConversationBufferMemory> for Arc<Mutex<dyn BaseMemory>> {
+    fn from(memory: ConversationBufferMemory) -> Self {
+        Arc::new(Mutex::new(memory))
+    }
+}
+
+impl BaseMemory for ConversationBufferMemory {
+    fn messages(&self) -> Vec<Message> {
+        self.messages.clone()
+    }
+
+    fn add_message(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    fn memory_key(&self) -> String {
+        self.memory_key.clone()
+    }
+
+    fn load_memory_variables(&self) -> PromptArgs {
+        let history = if self.return_messages {
+            json!(self.messages)
+        } else {
+            json!(self.to_string())
+        };
+        PromptArgs::from([(self.memory_key.clone(), history)])
+    }
+
+    fn save_context(&mut self, inputs: &PromptArgs, output: &str) {
+        let input_key = self.input_key.clone();
+        save_turn(self, input_key.as_deref(), inputs, output);
+    }
+}
+
+/// Saves a turn, the human message being the `input_key` variable when given.
+pub(super) fn save_turn<M: BaseMemory + ?Sized>(
+    memory: &mut M,
+    input_key: Option<&str>,
+    inputs: &PromptArgs,
+    output: &str,
+) {
+    let input = match input_key {
+        Some(input_key) => inputs.get(input_key).and_then(Value::as_str),
+        None => memory_input(inputs, &memory.memory_key()),
+    };
+    match input {
+        Some(input) => memory.add_user_message(&input),
+        None => log::warn!("No input variable to save in the memory"),
+    }
+    memory.add_ai_message(&output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt_args;
+
+    #[test]
+    fn test_conversation_buffer_memory() {
+        let mut memory = ConversationBufferMemory::new();
+        memory.save_context(&prompt_args! {"question" => "Hi, I'm Ana"}, "Hello Ana");
+        assert_eq!(
+            memory.load_memory_variables()["history"],
+            "human: Hi, I'm Ana\nai: Hello Ana"
+        );
+
+        let mut memory = ConversationBufferMemory::new()
+            .with_memory_key("chat_history")
+            .with_input_key("question")
+            .with_return_messages(true);
+        memory.save_context(
+            &prompt_args! {"question" => "What's my name?", "context" => "none"},
+            "Ana",
+        );
+        let messages =
+            Message::messages_from_value(&memory.load_memory_variables()["chat_history"]).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "What's my name?");
+    }
+}
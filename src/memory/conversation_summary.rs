@@ -0,0 +1,293 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use crate::{
+    language_models::{llm::LLM, LLMError},
+    prompt::{PromptArgs, PromptFromatter, PromptTemplate},
+    prompt_args,
+    schemas::{
+        memory::{BaseMemory, MemoryUpdate, DEFAULT_MEMORY_KEY},
+        messages::Message,
+    },
+    template_fstring,
+};
+
+use super::{last_turns_start, save_turn};
+
+const DEFAULT_SUMMARY_TEMPLATE: &str = "Progressively summarize the lines of conversation provided, adding onto the previous summary and returning a new summary.
+
+Current summary:
+{summary}
+
+New lines of conversation:
+{new_lines}
+
+New summary:";
+
+/// A memory summarizing the conversation with an LLM, so that long sessions fit in the
+/// context window. Each time a turn is saved, the turns older than the last
+/// `with_recent_turns` ones (none by default) are added to the summary, which is loaded
+/// first in the history as a system message.
+///
+/// The summary is updated when the chains save a turn, or by `summarize`. The LLM is
+/// called without the memory locked, see `save_memory_context`.
+///
+/// # Example
+/// ```rust,ignore
+/// let memory = ConversationSummaryMemory::new(OpenAI::default()).with_recent_turns(2);
+/// let chain = LLMChainBuilder::new()
+///     .prompt(prompt)
+///     .llm(OpenAI::default())
+///     .memory(memory.into())
+///     .build()?;
+/// ```
+pub struct ConversationSummaryMemory {
+    llm: Box<dyn LLM>,
+    prompt: PromptTemplate,
+    summary: String,
+    messages: Vec<Message>,
+    recent_turns: usize,
+    memory_key: String,
+    input_key: Option<String>,
+    return_messages: bool,
+    // Incremented by `clear`, so the summaries of the cleared turns are dropped.
+    version: u64,
+}
+
+/// A summary of the first `summarized` messages, computed at `version`.
+struct SummaryUpdate {
+    version: u64,
+    summarized: usize,
+    summary: String,
+}
+
+impl ConversationSummaryMemory {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L) -> Self {
+        Self {
+            llm: llm.into(),
+            prompt: template_fstring!(DEFAULT_SUMMARY_TEMPLATE, "summary", "new_lines"),
+            summary: String::new(),
+            messages: Vec::new(),
+            recent_turns: 0,
+            memory_key: DEFAULT_MEMORY_KEY.to_string(),
+            input_key: None,
+            return_messages: false,
+            version: 0,
+        }
+    }
+
+    /// The prompt of the summary, with the `summary` and `new_lines` variables.
+    pub fn with_prompt(mut self, prompt: PromptTemplate) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    /// The number of last turns kept as they are, the older ones being summarized.
+    pub fn with_recent_turns(mut self, recent_turns: usize) -> Self {
+        self.recent_turns = recent_turns;
+        self
+    }
+
+    /// Starts from a summary, e.g. of a previous session.
+    pub fn with_summary<S: Into<String>>(mut self, summary: S) -> Self {
+        self.summary = summary.into();
+        self
+    }
+
+    pub fn with_memory_key<S: Into<String>>(mut self, memory_key: S) -> Self {
+        self.memory_key = memory_key.into();
+        self
+    }
+
+    /// The input variable saved as the human message, when the prompts have several.
+    pub fn with_input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = Some(input_key.into());
+        self
+    }
+
+    /// Loads the history as messages instead of text.
+    pub fn with_return_messages(mut self, return_messages: bool) -> Self {
+        self.return_messages = return_messages;
+        self
+    }
+
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+
+    /// Adds the turns older than the recent ones to the summary.
+    pub async fn summarize(&mut self) -> Result<(), LLMError> {
+        let update = self.summary_update().await?;
+        if let Some(update) = update {
+            self.apply_summary(update);
+        }
+        Ok(())
+    }
+
+    /// Summarizes the turns older than the recent ones, without borrowing the memory.
+    fn summary_update(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Option<SummaryUpdate>, LLMError>> + Send + 'static
+    {
+        let start = last_turns_start(&self.messages, self.recent_turns);
+        let new_lines = self.messages[..start]
+            .iter()
+            .map(|message| format!("{}: {}", message.message_type.to_string(), message.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = self
+            .prompt
+            .format(prompt_args! {"summary" => self.summary, "new_lines" => new_lines})
+            .map_err(|e| LLMError::OtherError(e.to_string()));
+        let llm = self.llm.clone_box();
+        let version = self.version;
+        async move {
+            if start == 0 {
+                return Ok(None);
+            }
+            let result = llm.generate(&[Message::new_human_message(prompt?)]).await?;
+            Ok(Some(SummaryUpdate {
+                version,
+                summarized: start,
+                summary: result.generation.trim().to_string(),
+            }))
+        }
+    }
+
+    fn apply_summary(&mut self, update: SummaryUpdate) {
+        if update.version != self.version || update.summarized > self.messages.len() {
+            return;
+        }
+        self.summary = update.summary;
+        self.messages.drain(..update.summarized);
+    }
+}
+
+impl From<ConversationSummaryMemory> for Arc<Mutex<dyn BaseMemory>> {
+    fn from(memory: ConversationSummaryMemory) -> Self {
+        Arc::new(Mutex::new(memory))
+    }
+}
+
+#[async_trait]
+impl BaseMemory for ConversationSummaryMemory {
+    fn messages(&self) -> Vec<Message> {
+        let mut messages = Vec::with_capacity(self.messages.len() + 1);
+        if !self.summary.is_empty() {
+            messages.push(Message::new_system_message(&self.summary));
+        }
+        messages.extend(self.messages.iter().cloned());
+        messages
+    }
+
+    fn add_message(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    fn clear(&mut self) {
+        self.summary.clear();
+        self.messages.clear();
+        self.version += 1;
+    }
+
+    fn memory_key(&self) -> String {
+        self.memory_key.clone()
+    }
+
+    fn load_memory_variables(&self) -> PromptArgs {
+        let history = if self.return_messages {
+            json!(self.messages())
+        } else {
+            json!(self.to_string())
+        };
+        PromptArgs::from([(self.memory_key.clone(), history)])
+    }
+
+    fn save_context(&mut self, inputs: &PromptArgs, output: &str) {
+        let input_key = self.input_key.clone();
+        save_turn(self, input_key.as_deref(), inputs, output);
+    }
+
+    /// Summarizes the turns older than the recent ones. If the LLM fails, they are kept
+    /// as they are, to be summarized with the next turn.
+    fn prepare_update(&self) -> Option<BoxFuture<'static, Option<MemoryUpdate>>> {
+        let update = self.summary_update();
+        Some(Box::pin(async move {
+            match update.await {
+                Ok(update) => update.map(|update| Box::new(update) as MemoryUpdate),
+                Err(e) => {
+                    log::warn!("Failed to summarize the conversation: {}", e);
+                    None
+                }
+            }
+        }))
+    }
+
+    fn apply_update(&mut self, update: MemoryUpdate) {
+        if let Ok(update) = update.downcast::<SummaryUpdate>() {
+            self.apply_summary(*update);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{llm::FakeLLM, schemas::memory::save_memory_context};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_conversation_summary_memory() {
+        let llm = FakeLLM::new("human: Hi, I'm Ana | ai: Hello Ana");
+        let mut memory = ConversationSummaryMemory::new(llm.clone()).with_recent_turns(1);
+        memory
+            .save_context_async(&prompt_args! {"input" => "Hi, I'm Ana"}, "Hello Ana")
+            .await;
+        assert_eq!(memory.summary(), "");
+        assert_eq!(memory.messages().len(), 2);
+
+        memory
+            .save_context_async(&prompt_args! {"input" => "What's my name?"}, "Ana")
+            .await;
+        assert_eq!(memory.summary(), "human: Hi, I'm Ana | ai: Hello Ana");
+        assert_eq!(llm.call_count(), 1);
+        assert!(llm.calls()[0][0]
+            .content
+            .text()
+            .contains("New lines of conversation:\nhuman: Hi, I'm Ana\nai: Hello Ana\n"));
+        assert_eq!(
+            memory.load_memory_variables()["history"],
+            "system: human: Hi, I'm Ana | ai: Hello Ana\nhuman: What's my name?\nai: Ana"
+        );
+
+        memory.clear();
+        assert!(memory.messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_summarize_without_the_lock() {
+        let llm = FakeLLM::new("human: Hi | ai: Hello").with_latency(Duration::from_millis(50));
+        let memory: Arc<Mutex<dyn BaseMemory>> = ConversationSummaryMemory::new(llm).into();
+        let saving = tokio::spawn({
+            let memory = memory.clone();
+            async move {
+                save_memory_context(&memory, &prompt_args! {"input" => "Hi"}, "Hello").await;
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(memory.try_lock().unwrap().messages().len(), 2);
+
+        saving.await.unwrap();
+        assert_eq!(
+            memory.lock().await.to_string(),
+            "system: human: Hi | ai: Hello"
+        );
+    }
+}
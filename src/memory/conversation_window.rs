@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use crate::{
+    prompt::PromptArgs,
+    schemas::{
+        memory::{BaseMemory, DEFAULT_MEMORY_KEY},
+        messages::{Message, MessageType},
+    },
+};
+
+use super::save_turn;
+
+/// A memory keeping the last `k` turns of the conversation, a turn starting with a human
+/// message, so that the history stays within the context window in long sessions.
+/// Like `ConversationBufferMemory`, it is loaded as text or, with `with_return_messages`,
+/// as messages.
+///
+/// # Example
+/// ```rust,ignore
+/// let chain = LLMChainBuilder::new()
+///     .prompt(prompt)
+///     .llm(OpenAI::default())
+///     .memory(ConversationBufferWindowMemory::new(3).into())
+///     .build()?;
+/// ```
+pub struct ConversationBufferWindowMemory {
+    k: usize,
+    messages: Vec<Message>,
+    memory_key: String,
+    input_key: Option<String>,
+    return_messages: bool,
+}
+
+impl Default for ConversationBufferWindowMemory {
+    fn default() -> Self {
+        Self::new(5)
+    }
+}
+
+impl ConversationBufferWindowMemory {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            messages: Vec::new(),
+            memory_key: DEFAULT_MEMORY_KEY.to_string(),
+            input_key: None,
+            return_messages: false,
+        }
+    }
+
+    pub fn with_memory_key<S: Into<String>>(mut self, memory_key: S) -> Self {
+        self.memory_key = memory_key.into();
+        self
+    }
+
+    /// The input variable saved as the human message, when the prompts have several.
+    pub fn with_input_key<S: Into<String>>(mut self, input_key: S) -> Self {
+        self.input_key = Some(input_key.into());
+        self
+    }
+
+    /// Loads the history as messages instead of text.
+    pub fn with_return_messages(mut self, return_messages: bool) -> Self {
+        self.return_messages = return_messages;
+        self
+    }
+}
+
+impl From<ConversationBufferWindowMemory> for Arc<Mutex<dyn BaseMemory>> {
+    fn from(memory: ConversationBufferWindowMemory) -> Self {
+        Arc::new(Mutex::new(memory))
+    }
+}
+
+/// The index of the first message of the last `k` turns of `messages`.
+pub(super) fn last_turns_start(messages: &[Message], k: usize) -> usize {
+    if k == 0 {
+        return messages.len();
+    }
+    messages
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, message)| matches!(message.message_type, MessageType::HumanMessage))
+        .nth(k - 1)
+        .map_or(0, |(index, _)| index)
+}
+
+impl BaseMemory for ConversationBufferWindowMemory {
+    fn messages(&self) -> Vec<Message> {
+        self.messages.clone()
+    }
+
+    fn add_message(&mut self, message: Message) {
+        self.messages.push(message);
+        let start = last_turns_start(&self.messages, self.k);
+        self.messages.drain(..start);
+    }
+
+    fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    fn memory_key(&self) -> String {
+        self.memory_key.clone()
+    }
+
+    fn load_memory_variables(&self) -> PromptArgs {
+        let history = if self.return_messages {
+            json!(self.messages)
+        } else {
+            json!(self.to_string())
+        };
+        PromptArgs::from([(self.memory_key.clone(), history)])
+    }
+
+    fn save_context(&mut self, inputs: &PromptArgs, output: &str) {
+        let input_key = self.input_key.clone();
+        save_turn(self, input_key.as_deref(), inputs, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt_args;
+
+    #[test]
+    fn test_conversation_buffer_window_memory() {
+        let mut memory = ConversationBufferWindowMemory::new(2);
+        for i in 0..4 {
+            memory.save_context(
+                &prompt_args! {"input" => format!("question {}", i)},
+                "answer",
+            );
+        }
+        assert_eq!(
+            memory.load_memory_variables()["history"],
+            "human: question 2\nai: answer\nhuman: question 3\nai: answer"
+        );
+
+        let mut memory = ConversationBufferWindowMemory::new(0);
+        memory.save_context(&prompt_args! {"input" => "Hi"}, "Hello");
+        assert!(memory.messages().is_empty());
+    }
+}
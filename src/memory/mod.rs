@@ -1,7 +1,17 @@
+mod chat_history;
+mod conversation_buffer;
+mod conversation_summary;
+mod conversation_window;
 mod dummy_memory;
 mod simple_memory;
+mod token_buffer;
 mod window_buffer;
 
+pub use chat_history::*;
+pub use conversation_buffer::*;
+pub use conversation_summary::*;
+pub use conversation_window::*;
 pub use dummy_memory::*;
 pub use simple_memory::*;
+pub use token_buffer::*;
 pub use window_buffer::*;
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::schemas::{memory::BaseMemory, messages::Message, MessageTrimmer};
+
+/// A memory keeping the most recent messages within a token budget, trimmed with a
+/// `MessageTrimmer`.
+pub struct TokenBufferMemory {
+    trimmer: MessageTrimmer,
+    messages: Vec<Message>,
+}
+
+impl Default for TokenBufferMemory {
+    fn default() -> Self {
+        Self::new(2000)
+    }
+}
+
+impl TokenBufferMemory {
+    pub fn new(max_tokens: usize) -> Self {
+        Self::with_trimmer(MessageTrimmer::new(max_tokens))
+    }
+
+    /// Trims the messages with `trimmer`, e.g. keeping the first ones.
+    pub fn with_trimmer(trimmer: MessageTrimmer) -> Self {
+        Self {
+            trimmer,
+            messages: Vec::new(),
+        }
+    }
+}
+
+impl From<TokenBufferMemory> for Arc<Mutex<dyn BaseMemory>> {
+    fn from(memory: TokenBufferMemory) -> Self {
+        Arc::new(Mutex::new(memory))
+    }
+}
+
+impl BaseMemory for TokenBufferMemory {
+    fn messages(&self) -> Vec<Message> {
+        self.messages.clone()
+    }
+    fn add_message(&mut self, message: Message) {
+        self.messages.push(message);
+        self.messages = self.trimmer.trim(&self.messages);
+    }
+    fn clear(&mut self) {
+        self.messages.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_buffer_memory() {
+        let mut memory =
+            TokenBufferMemory::with_trimmer(MessageTrimmer::new(3).with_token_counter(|_| 1));
+        memory.add_message(Message::new_system_message("system"));
+        for i in 0..5 {
+            memory.add_user_message(&i);
+        }
+        let contents: Vec<String> = memory
+            .messages()
+            .into_iter()
+            .map(|message| message.content.text())
+            .collect();
+        assert_eq!(contents, vec!["system", "3", "4"]);
+    }
+}
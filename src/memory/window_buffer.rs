@@ -1,9 +1,17 @@
+// The impls of the deprecated memory itself.
+#![allow(deprecated)]
+
 use std::sync::Arc;
 
 use tokio::sync::Mutex;
 
 use crate::schemas::{memory::BaseMemory, messages::Message};
 
+/// A memory keeping the last `window_size` messages.
+#[deprecated(
+    since = "4.2.0",
+    note = "use ConversationBufferWindowMemory, which keeps whole turns"
+)]
 pub struct WindowBufferMemory {
     window_size: usize,
     messages: Vec<Message>,
@@ -1,6 +1,8 @@
 use regex::Error as RegexError;
 use thiserror::Error;
 
+use crate::language_models::LLMError;
+
 #[derive(Error, Debug)]
 pub enum OutputParserError {
     #[error("Regex error: {0}")]
@@ -8,4 +10,14 @@ pub enum OutputParserError {
 
     #[error("Parsing error: {0}")]
     ParsingError(String),
+
+    /// The LLM asked to fix an output failed, boxed as it is much larger than the others.
+    #[error("LLM error: {0}")]
+    LLMError(Box<LLMError>),
+}
+
+impl From<LLMError> for OutputParserError {
+    fn from(error: LLMError) -> Self {
+        OutputParserError::LLMError(Box::new(error))
+    }
 }
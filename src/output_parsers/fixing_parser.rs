@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+
+use crate::{language_models::llm::LLM, prompt::PromptFromatter, prompt_args, template_jinja2};
+
+use super::{OutputParser, OutputParserError};
+
+const DEFAULT_FIX_TEMPLATE: &str = r#"Instructions:
+--------------
+{{instructions}}
+--------------
+Completion:
+--------------
+{{completion}}
+--------------
+
+Above, the Completion did not satisfy the constraints given in the Instructions.
+Error:
+--------------
+{{error}}
+--------------
+
+Please try again. Please only respond with an answer that satisfies the constraints laid out in the Instructions:"#;
+
+/// Wraps a parser and, when it fails, asks the LLM to fix the output with the error and the
+/// format instructions, then parses the fixed output, up to `max_retries` times.
+///
+/// # Example
+/// ```rust,ignore
+/// let structured = StructuredOutputParser::<Person>::new();
+/// let instructions = structured.get_format_instructions();
+/// let parser = OutputFixingParser::new(structured, llm)
+///     .with_instructions(instructions)
+///     .with_max_retries(2);
+/// let person = parser.parse(&output).await?;
+/// ```
+pub struct OutputFixingParser<O> {
+    parser: Box<dyn OutputParser<O>>,
+    llm: Box<dyn LLM>,
+    instructions: String,
+    max_retries: usize,
+}
+
+impl<O> OutputFixingParser<O> {
+    pub fn new<P: OutputParser<O> + 'static, L: Into<Box<dyn LLM>>>(parser: P, llm: L) -> Self {
+        Self {
+            parser: Box::new(parser),
+            llm: llm.into(),
+            instructions: String::new(),
+            max_retries: 1,
+        }
+    }
+
+    /// The format instructions of the parser, given to the LLM to fix the output.
+    pub fn with_instructions<S: Into<String>>(mut self, instructions: S) -> Self {
+        self.instructions = instructions.into();
+        self
+    }
+
+    /// Number of times the LLM is asked to fix the output. Default: 1
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+}
+
+#[async_trait]
+impl<O: Send> OutputParser<O> for OutputFixingParser<O> {
+    async fn parse(&self, output: &str) -> Result<O, OutputParserError> {
+        let mut completion = output.to_string();
+        let mut retries = 0;
+        loop {
+            let error = match self.parser.parse(&completion).await {
+                Ok(parsed) => return Ok(parsed),
+                Err(error) if retries < self.max_retries => error,
+                Err(error) => return Err(error),
+            };
+            retries += 1;
+            log::debug!("Fixing the output, attempt {}: {}", retries, error);
+
+            let prompt =
+                template_jinja2!(DEFAULT_FIX_TEMPLATE, "instructions", "completion", "error")
+                    .format(prompt_args! {
+                        "instructions" => self.instructions,
+                        "completion" => completion,
+                        "error" => error.to_string(),
+                    })
+                    .map_err(|e| OutputParserError::ParsingError(e.to_string()))?;
+            completion = self.llm.invoke(&prompt).await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{llm::FakeLLM, output_parsers::MarkdownParser};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_output_fixing_parser() {
+        let llm = FakeLLM::new("```json\n{\"answer\": 42}\n```");
+        let parser =
+            OutputFixingParser::new(MarkdownParser::new(), llm.clone()).with_instructions("json");
+
+        assert_eq!(
+            parser.parse("The answer is 42").await.unwrap(),
+            "{\"answer\": 42}"
+        );
+        let calls = llm.calls();
+        assert_eq!(calls.len(), 1);
+        assert!(calls[0][0].content.text().contains("The answer is 42"));
+        assert!(calls[0][0].content.text().contains("No code block found"));
+    }
+
+    #[tokio::test]
+    async fn test_output_fixing_parser_retry_budget() {
+        let llm = FakeLLM::from_responses(vec!["still wrong", "wrong again"]);
+        let parser =
+            OutputFixingParser::new(MarkdownParser::new(), llm.clone()).with_max_retries(2);
+
+        assert!(matches!(
+            parser.parse("wrong").await,
+            Err(OutputParserError::ParsingError(_))
+        ));
+        assert_eq!(llm.call_count(), 2);
+
+        let never_fixed: OutputFixingParser<String> =
+            OutputFixingParser::new(MarkdownParser::new(), llm.clone()).with_max_retries(0);
+        assert!(never_fixed.parse("wrong").await.is_err());
+        assert_eq!(llm.call_count(), 2);
+    }
+}
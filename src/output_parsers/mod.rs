@@ -7,5 +7,14 @@ pub use markdown_parser::*;
 mod simple_parser;
 pub use simple_parser::*;
 
+mod structured_parser;
+pub use structured_parser::*;
+
+mod fixing_parser;
+pub use fixing_parser::*;
+
+mod retry_parser;
+pub use retry_parser::*;
+
 mod error;
 pub use error::*;
@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+
+use crate::{language_models::llm::LLM, prompt::PromptFromatter, prompt_args, template_jinja2};
+
+use super::{OutputParser, OutputParserError};
+
+const DEFAULT_RETRY_TEMPLATE: &str = r#"Prompt:
+{{prompt}}
+Completion:
+{{completion}}
+
+Above, the Completion did not satisfy the constraints given in the Prompt.
+Details: {{error}}
+Please try again:"#;
+
+/// Wraps a parser and, when it fails, asks the LLM the original prompt again, with the failed
+/// completion and the error, then parses the new completion, up to `max_retries` times.
+///
+/// Unlike the [`super::OutputFixingParser`], the LLM sees the whole prompt, which helps
+/// when the output is not only malformed but also incomplete.
+///
+/// # Example
+/// ```rust,ignore
+/// let parser = RetryOutputParser::new(StructuredOutputParser::<Person>::new(), llm)
+///     .with_max_retries(2);
+/// let person = parser.parse_with_prompt(&output, &prompt).await?;
+/// ```
+pub struct RetryOutputParser<O> {
+    parser: Box<dyn OutputParser<O>>,
+    llm: Box<dyn LLM>,
+    prompt: String,
+    max_retries: usize,
+}
+
+impl<O> RetryOutputParser<O> {
+    pub fn new<P: OutputParser<O> + 'static, L: Into<Box<dyn LLM>>>(parser: P, llm: L) -> Self {
+        Self {
+            parser: Box::new(parser),
+            llm: llm.into(),
+            prompt: String::new(),
+            max_retries: 1,
+        }
+    }
+
+    /// The prompt that produced the outputs given to `parse`, used to ask the LLM again.
+    pub fn with_prompt<S: Into<String>>(mut self, prompt: S) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Number of times the LLM is asked again. Default: 1
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+}
+
+impl<O: Send> RetryOutputParser<O> {
+    /// Parses the completion of the given prompt, asking the LLM the prompt again on failure.
+    pub async fn parse_with_prompt(
+        &self,
+        completion: &str,
+        prompt: &str,
+    ) -> Result<O, OutputParserError> {
+        let mut completion = completion.to_string();
+        let mut retries = 0;
+        loop {
+            let error = match self.parser.parse(&completion).await {
+                Ok(parsed) => return Ok(parsed),
+                Err(error) if retries < self.max_retries => error,
+                Err(error) => return Err(error),
+            };
+            retries += 1;
+            log::debug!("Retrying the prompt, attempt {}: {}", retries, error);
+
+            let retry_prompt =
+                template_jinja2!(DEFAULT_RETRY_TEMPLATE, "prompt", "completion", "error")
+                    .format(prompt_args! {
+                        "prompt" => prompt,
+                        "completion" => completion,
+                        "error" => error.to_string(),
+                    })
+                    .map_err(|e| OutputParserError::ParsingError(e.to_string()))?;
+            completion = self.llm.invoke(&retry_prompt).await?;
+        }
+    }
+}
+
+#[async_trait]
+impl<O: Send> OutputParser<O> for RetryOutputParser<O> {
+    async fn parse(&self, output: &str) -> Result<O, OutputParserError> {
+        self.parse_with_prompt(output, &self.prompt).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{llm::FakeLLM, output_parsers::MarkdownParser};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_retry_output_parser() {
+        let llm = FakeLLM::from_responses(vec!["no block", "```\nfixed\n```"]);
+        let parser = RetryOutputParser::new(MarkdownParser::new(), llm.clone())
+            .with_prompt("Answer in a code block")
+            .with_max_retries(2);
+
+        assert_eq!(parser.parse("wrong").await.unwrap(), "fixed");
+        let calls = llm.calls();
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0][0]
+            .content
+            .text()
+            .contains("Answer in a code block"));
+        assert!(calls[1][0].content.text().contains("no block"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_output_parser_retry_budget() {
+        let llm = FakeLLM::new("still no block");
+        let parser = RetryOutputParser::new(MarkdownParser::new(), llm.clone());
+
+        assert!(matches!(
+            parser
+                .parse_with_prompt("wrong", "Answer in a code block")
+                .await,
+            Err(OutputParserError::ParsingError(_))
+        ));
+        assert_eq!(llm.call_count(), 1);
+    }
+}
@@ -0,0 +1,159 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::guardrails::validate_json;
+
+use super::{OutputParser, OutputParserError};
+
+/// Parses the json answer of an LLM into `T`, with format instructions generated from the
+/// json schema of `T` to add to the prompt.
+///
+/// The json can be in a fenced code block or surrounded by text. It's validated against
+/// the schema before being deserialized, with the subset of the keywords supported by
+/// [`JsonSchemaValidator`](crate::guardrails::JsonSchemaValidator), so constraints serde
+/// doesn't check, like the ranges of the numbers, are enforced too.
+///
+/// # Example
+/// ```rust,ignore
+/// #[derive(Deserialize, JsonSchema)]
+/// struct Person {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let parser = StructuredOutputParser::<Person>::new();
+/// let prompt = format!("Tell me about Luis.\n{}", parser.get_format_instructions());
+/// let person = parser.parse(&llm.invoke(&prompt).await?).await?;
+/// ```
+pub struct StructuredOutputParser<T> {
+    schema: Value,
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned + JsonSchema> StructuredOutputParser<T> {
+    pub fn new() -> Self {
+        let mut schema = schemars::schema_for!(T).to_value();
+        if let Some(schema) = schema.as_object_mut() {
+            schema.remove("$schema");
+        }
+        Self {
+            schema,
+            _output: PhantomData,
+        }
+    }
+
+    /// The json schema of `T`.
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    pub fn get_format_instructions(&self) -> String {
+        format!(
+            r#"The output should be formatted as a JSON instance that conforms to the JSON schema below.
+
+As an example, for the schema {{"properties": {{"foo": {{"description": "a list of strings", "type": "array", "items": {{"type": "string"}}}}}}, "required": ["foo"]}}
+the object {{"foo": ["bar", "baz"]}} is a well-formatted instance of the schema. The object {{"properties": {{"foo": ["bar", "baz"]}}}} is not well-formatted.
+
+Here is the output schema:
+```json
+{}
+```"#,
+            self.schema
+        )
+    }
+}
+
+impl<T: DeserializeOwned + JsonSchema> Default for StructuredOutputParser<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Extracts the json of an answer: the content of its first fenced code block, otherwise
+/// the text from its first `{` or `[` to its last `}` or `]`.
+pub(crate) fn extract_json(output: &str) -> Result<&str, OutputParserError> {
+    let re = Regex::new(r"```(?:json)?\s*([\s\S]*?)\s*```")?;
+    if let Some(json) = re.captures(output).and_then(|cap| cap.get(1)) {
+        return Ok(json.as_str());
+    }
+
+    let start = output.find(['{', '[']);
+    let end = output.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => Ok(&output[start..=end]),
+        _ => Err(OutputParserError::ParsingError(format!(
+            "No json found in the output: {}",
+            output
+        ))),
+    }
+}
+
+#[async_trait]
+impl<T: DeserializeOwned + JsonSchema> OutputParser<T> for StructuredOutputParser<T> {
+    async fn parse(&self, output: &str) -> Result<T, OutputParserError> {
+        let json = extract_json(output)?;
+        let value: Value = serde_json::from_str(json).map_err(|e| {
+            OutputParserError::ParsingError(format!("Invalid json: {}. Output: {}", e, json))
+        })?;
+        if let Some(violation) = validate_json(&self.schema, &value) {
+            return Err(OutputParserError::ParsingError(format!(
+                "The output doesn't match the schema: {}. Output: {}",
+                violation, json
+            )));
+        }
+        serde_json::from_value(value).map_err(|e| {
+            OutputParserError::ParsingError(format!(
+                "The output doesn't match the schema: {}. Output: {}",
+                e, json
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize, JsonSchema)]
+    struct Person {
+        name: String,
+        #[schemars(range(max = 150))]
+        age: u32,
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_parser() {
+        let parser = StructuredOutputParser::<Person>::new();
+        let expected = Person {
+            name: "Luis".into(),
+            age: 24,
+        };
+
+        assert!(parser
+            .get_format_instructions()
+            .contains(r#""required":["name","age"]"#));
+        assert!(parser.schema().get("$schema").is_none());
+
+        let fenced = "Here you go:\n```json\n{\"name\": \"Luis\", \"age\": 24}\n```";
+        assert_eq!(parser.parse(fenced).await.unwrap(), expected);
+
+        let inline = "Sure! {\"name\": \"Luis\", \"age\": 24} Anything else?";
+        assert_eq!(parser.parse(inline).await.unwrap(), expected);
+
+        assert!(parser.parse(r#"{"name": "Luis"}"#).await.is_err());
+        // Deserializable, but out of the range of the schema.
+        let error = parser
+            .parse(r#"{"name": "Luis", "age": 200}"#)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("$.age is greater than 150"));
+        assert!(parser.parse("I don't know").await.is_err());
+    }
+}
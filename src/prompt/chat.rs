@@ -1,4 +1,7 @@
-use crate::schemas::{messages::Message, prompt::PromptValue};
+use crate::schemas::{
+    messages::{ContentPart, ImageDetail, Message},
+    prompt::PromptValue,
+};
 
 use super::{
     FormatPrompter, MessageFormatter, PromptArgs, PromptError, PromptFromatter, PromptTemplate,
@@ -7,31 +10,71 @@ use super::{
 /// Struct `HumanMessagePromptTemplate` defines a template for creating human (user) messages.
 /// `PromptTemplate` is used to generate the message template.
 ///
+/// Images are added with templates of their urls, e.g. `data:` urls of base64 encoded
+/// data, formatting a multimodal message for vision models.
+///
 /// # Usage
 /// ```rust,ignore
 /// let human_message_prompt = HumanMessagePromptTemplate::new(template_fstring!(
 ///    "User says: {content}",
 ///    "content",
-/// ));
+/// ))
+/// .with_image(template_fstring!("{image_url}", "image_url"));
 /// ```
 #[derive(Clone)]
 pub struct HumanMessagePromptTemplate {
     prompt: PromptTemplate,
+    images: Vec<PromptTemplate>,
+    image_detail: Option<ImageDetail>,
 }
 
 impl HumanMessagePromptTemplate {
     pub fn new(prompt: PromptTemplate) -> Self {
-        Self { prompt }
+        Self {
+            prompt,
+            images: Vec::new(),
+            image_detail: None,
+        }
+    }
+
+    /// Adds an image to the message, the template formatting its url.
+    pub fn with_image(mut self, url: PromptTemplate) -> Self {
+        self.images.push(url);
+        self
+    }
+
+    /// The detail level of the images, for the providers supporting it.
+    pub fn with_image_detail(mut self, detail: ImageDetail) -> Self {
+        self.image_detail = Some(detail);
+        self
     }
 }
 impl MessageFormatter for HumanMessagePromptTemplate {
     fn format_messages(&self, input_variables: PromptArgs) -> Result<Vec<Message>, PromptError> {
-        let message = Message::new_human_message(&self.prompt.format(input_variables)?);
+        let text = self.prompt.format(input_variables.clone())?;
+        let message = if self.images.is_empty() {
+            Message::new_human_message(&text)
+        } else {
+            let mut parts = vec![ContentPart::text(text)];
+            for image in &self.images {
+                parts.push(ContentPart::Image {
+                    url: image.format(input_variables.clone())?,
+                    detail: self.image_detail,
+                });
+            }
+            Message::new_human_message_with_parts(parts)
+        };
         log::debug!("message: {:?}", message);
         Ok(vec![message])
     }
     fn input_variables(&self) -> Vec<String> {
-        self.prompt.variables().clone()
+        let mut variables = self.prompt.variables().clone();
+        for variable in self.images.iter().flat_map(|image| image.variables()) {
+            if !variables.contains(&variable) {
+                variables.push(variable);
+            }
+        }
+        variables
     }
 }
 
@@ -216,8 +259,10 @@ impl MessageFormatterStruct {
                     result.extend(tmpl.format_messages(input_variables.clone())?)
                 }
                 MessageOrTemplate::MessagesPlaceholder(placeholder) => {
-                    let messages = input_variables[placeholder].clone();
-                    result.extend(Message::messages_from_value(&messages)?);
+                    let messages = input_variables
+                        .get(placeholder)
+                        .ok_or_else(|| PromptError::MissingVariable(placeholder.clone()))?;
+                    result.extend(Message::messages_from_value(messages)?);
                 }
             }
         }
@@ -296,10 +341,12 @@ macro_rules! message_formatter {
 #[cfg(test)]
 mod tests {
     use crate::{
-        message_formatter,
-        prompt::{chat::AIMessagePromptTemplate, FormatPrompter},
+        prompt::{
+            chat::{AIMessagePromptTemplate, HumanMessagePromptTemplate},
+            FormatPrompter, MessageFormatter, PromptError,
+        },
         prompt_args,
-        schemas::messages::Message,
+        schemas::messages::{ContentPart, ImageDetail, Message, MessageContent},
         template_fstring,
     };
 
@@ -352,4 +399,60 @@ mod tests {
         assert_eq!(formatted_messages[2].content, "Placeholder message 1");
         assert_eq!(formatted_messages[3].content, "Placeholder message 2");
     }
+
+    #[test]
+    fn test_human_message_with_images() {
+        let template =
+            HumanMessagePromptTemplate::new(template_fstring!("What is in {subject}?", "subject"))
+                .with_image(template_fstring!("{image_url}", "image_url"))
+                .with_image(template_fstring!(
+                    "data:image/png;base64,{image_data}",
+                    "image_data"
+                ))
+                .with_image_detail(ImageDetail::Low);
+        assert_eq!(
+            template.input_variables(),
+            vec!["subject", "image_url", "image_data"]
+        );
+
+        let messages = template
+            .format_messages(prompt_args! {
+                "subject" => "these images",
+                "image_url" => "https://example.com/cat.png",
+                "image_data" => "aGVsbG8=",
+            })
+            .unwrap();
+        assert_eq!(
+            messages[0].content,
+            MessageContent::Parts(vec![
+                ContentPart::text("What is in these images?"),
+                ContentPart::Image {
+                    url: "https://example.com/cat.png".to_string(),
+                    detail: Some(ImageDetail::Low),
+                },
+                ContentPart::Image {
+                    url: "data:image/png;base64,aGVsbG8=".to_string(),
+                    detail: Some(ImageDetail::Low),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_messages_placeholder_missing() {
+        let formatter = message_formatter![
+            fmt_placeholder!("history"),
+            fmt_message!(Message::new_human_message("Hi")),
+        ];
+        assert!(matches!(
+            formatter.format_prompt(prompt_args! {}),
+            Err(PromptError::MissingVariable(variable)) if variable == "history"
+        ));
+
+        let messages = formatter
+            .format_prompt(prompt_args! {"history" => Vec::<Message>::new()})
+            .unwrap()
+            .to_chat_messages();
+        assert_eq!(messages.len(), 1);
+    }
 }
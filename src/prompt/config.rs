@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AIMessagePromptTemplate, FormatPrompter, HumanMessagePromptTemplate, MessageFormatterStruct,
+    PromptTemplate, SystemMessagePromptTemplate,
+};
+
+/// A message of a chat prompt defined in a config file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum MessageConfig {
+    System(PromptTemplate),
+    Human(PromptTemplate),
+    Ai(PromptTemplate),
+    /// The messages of the variable, e.g. the chat history.
+    Placeholder {
+        variable: String,
+    },
+}
+
+/// A prompt defined in a config file, e.g. in json:
+///
+/// ```json
+/// {"type": "template", "template": "Tell me a joke about {topic}", "input_variables": ["topic"]}
+/// ```
+///
+/// or, for a chat prompt:
+///
+/// ```json
+/// {
+///   "type": "chat",
+///   "messages": [
+///     {"role": "system", "template": "You are a {style} assistant", "input_variables": ["style"]},
+///     {"role": "placeholder", "variable": "history"},
+///     {"role": "human", "template": "{{ input }}", "input_variables": ["input"], "template_format": "jinja2"}
+///   ]
+/// }
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PromptConfig {
+    /// A template formatted as a human message.
+    Template(PromptTemplate),
+    Chat {
+        messages: Vec<MessageConfig>,
+    },
+}
+
+impl PromptConfig {
+    pub fn into_prompt(self) -> Box<dyn FormatPrompter> {
+        match self {
+            PromptConfig::Template(template) => Box::new(template),
+            PromptConfig::Chat { messages } => {
+                let mut formatter = MessageFormatterStruct::new();
+                for message in messages {
+                    match message {
+                        MessageConfig::System(template) => formatter
+                            .add_template(Box::new(SystemMessagePromptTemplate::new(template))),
+                        MessageConfig::Human(template) => formatter
+                            .add_template(Box::new(HumanMessagePromptTemplate::new(template))),
+                        MessageConfig::Ai(template) => {
+                            formatter.add_template(Box::new(AIMessagePromptTemplate::new(template)))
+                        }
+                        MessageConfig::Placeholder { variable } => {
+                            formatter.add_messages_placeholder(&variable)
+                        }
+                    }
+                }
+                Box::new(formatter)
+            }
+        }
+    }
+}
+
+impl From<PromptTemplate> for PromptConfig {
+    fn from(template: PromptTemplate) -> Self {
+        PromptConfig::Template(template)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{prompt_args, schemas::Message};
+
+    use super::*;
+
+    #[test]
+    fn test_prompt_config() {
+        let config: PromptConfig = serde_json::from_value(json!({
+            "type": "chat",
+            "messages": [
+                {"role": "system", "template": "You are a {style} assistant", "input_variables": ["style"]},
+                {"role": "placeholder", "variable": "history"},
+                {"role": "human", "template": "{{ input }}", "input_variables": ["input"], "template_format": "jinja2"}
+            ]
+        }))
+        .unwrap();
+        let prompt = config.clone().into_prompt();
+        assert_eq!(
+            prompt.get_input_variables(),
+            vec!["style", "history", "input"]
+        );
+
+        let messages = prompt
+            .format_prompt(prompt_args! {
+                "style" => "funny",
+                "history" => vec![Message::new_ai_message("Hello")],
+                "input" => "Hi",
+            })
+            .unwrap()
+            .to_chat_messages();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].content.text(), "You are a funny assistant");
+        assert_eq!(messages[2].content.text(), "Hi");
+
+        // Serializing gives back the same config.
+        let value = serde_json::to_value(&config).unwrap();
+        assert_eq!(value["messages"][2]["template_format"], "jinja2");
+        assert_eq!(value["messages"][0]["template_format"], "f-string");
+    }
+}
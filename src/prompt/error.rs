@@ -9,6 +9,9 @@ pub enum PromptError {
     #[error("Serialization error: {0}")]
     SerializationError(#[from] SerdeJsonError),
 
+    #[error("Template error: {0}")]
+    TemplateError(#[from] minijinja::Error),
+
     #[error("Error: {0}")]
     OtherError(String),
 }
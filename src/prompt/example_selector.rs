@@ -0,0 +1,243 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{
+    schemas::Document,
+    vectorstore::{VecStoreOptions, VectorStore},
+};
+
+use super::{PromptArgs, PromptError, PromptFromatter, PromptTemplate};
+
+/// Picks the examples of a `FewShotPromptTemplate` suiting the input.
+#[async_trait]
+pub trait ExampleSelector: Send + Sync {
+    async fn select_examples(
+        &self,
+        input_variables: &PromptArgs,
+    ) -> Result<Vec<PromptArgs>, PromptError>;
+}
+
+impl<ES> From<ES> for Box<dyn ExampleSelector>
+where
+    ES: ExampleSelector + 'static,
+{
+    fn from(selector: ES) -> Self {
+        Box::new(selector)
+    }
+}
+
+/// The text of the values of the variables, sorted by name.
+fn values_text<'a>(values: impl Iterator<Item = (&'a String, &'a Value)>) -> String {
+    let mut values: Vec<_> = values.collect();
+    values.sort_by(|a, b| a.0.cmp(b.0));
+    values
+        .into_iter()
+        .map(|(_, value)| match value {
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// Selects the first examples fitting, with the input, in a maximum number of words.
+pub struct LengthBasedExampleSelector {
+    examples: Vec<PromptArgs>,
+    example_prompt: PromptTemplate,
+    max_length: usize,
+}
+
+impl LengthBasedExampleSelector {
+    pub fn new(examples: Vec<PromptArgs>, example_prompt: PromptTemplate) -> Self {
+        Self {
+            examples,
+            example_prompt,
+            max_length: 2048,
+        }
+    }
+
+    /// The maximum number of words of the input and the formatted examples. Default: 2048
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    pub fn add_example(&mut self, example: PromptArgs) {
+        self.examples.push(example);
+    }
+}
+
+#[async_trait]
+impl ExampleSelector for LengthBasedExampleSelector {
+    async fn select_examples(
+        &self,
+        input_variables: &PromptArgs,
+    ) -> Result<Vec<PromptArgs>, PromptError> {
+        let mut remaining = self
+            .max_length
+            .saturating_sub(words(&values_text(input_variables.iter())));
+        let mut selected = Vec::new();
+        for example in &self.examples {
+            let length = words(&self.example_prompt.format(example.clone())?);
+            if length > remaining {
+                break;
+            }
+            remaining -= length;
+            selected.push(example.clone());
+        }
+        Ok(selected)
+    }
+}
+
+/// Selects the examples most similar to the input, searching them in a vector store.
+///
+/// The examples are stored as documents whose content is the text of their values and
+/// whose metadata is the example itself.
+pub struct SemanticSimilarityExampleSelector {
+    vector_store: Box<dyn VectorStore>,
+    k: usize,
+    input_keys: Option<Vec<String>>,
+    options: VecStoreOptions,
+}
+
+impl SemanticSimilarityExampleSelector {
+    pub fn new<V: Into<Box<dyn VectorStore>>>(vector_store: V) -> Self {
+        Self {
+            vector_store: vector_store.into(),
+            k: 4,
+            input_keys: None,
+            options: VecStoreOptions::default(),
+        }
+    }
+
+    /// Adds the examples to the vector store.
+    pub async fn from_examples<V: Into<Box<dyn VectorStore>>>(
+        examples: Vec<PromptArgs>,
+        vector_store: V,
+    ) -> Result<Self, PromptError> {
+        let selector = Self::new(vector_store);
+        selector.add_examples(examples).await?;
+        Ok(selector)
+    }
+
+    /// The number of examples selected. Default: 4
+    pub fn with_k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Only the values of these variables are compared, for the examples and the input.
+    pub fn with_input_keys<S: Into<String>>(mut self, input_keys: Vec<S>) -> Self {
+        self.input_keys = Some(input_keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn with_options(mut self, options: VecStoreOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn text(&self, variables: &PromptArgs) -> String {
+        values_text(variables.iter().filter(|(key, _)| {
+            self.input_keys
+                .as_ref()
+                .is_none_or(|input_keys| input_keys.contains(key))
+        }))
+    }
+
+    pub async fn add_examples(&self, examples: Vec<PromptArgs>) -> Result<(), PromptError> {
+        let documents: Vec<Document> = examples
+            .into_iter()
+            .map(|example| Document::new(self.text(&example)).with_metadata(example))
+            .collect();
+        self.vector_store
+            .add_documents(&documents, &self.options)
+            .await
+            .map_err(|e| PromptError::OtherError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExampleSelector for SemanticSimilarityExampleSelector {
+    async fn select_examples(
+        &self,
+        input_variables: &PromptArgs,
+    ) -> Result<Vec<PromptArgs>, PromptError> {
+        let documents = self
+            .vector_store
+            .similarity_search(&self.text(input_variables), self.k, &self.options)
+            .await
+            .map_err(|e| PromptError::OtherError(e.to_string()))?;
+        Ok(documents
+            .into_iter()
+            .map(|document| document.metadata)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        embedding::FakeEmbedder, prompt_args, template_fstring, vectorstore::InMemoryVectorStore,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_length_based_example_selector() {
+        let examples = vec![
+            prompt_args! {"input" => "happy", "output" => "sad"},
+            prompt_args! {"input" => "tall", "output" => "short"},
+            prompt_args! {"input" => "energetic", "output" => "lethargic"},
+        ];
+        let selector = LengthBasedExampleSelector::new(
+            examples,
+            template_fstring!("Input: {input}\nOutput: {output}", "input", "output"),
+        )
+        .with_max_length(10);
+
+        // Each example is 4 words long.
+        let selected = selector
+            .select_examples(&prompt_args! {"adjective" => "big"})
+            .await
+            .unwrap();
+        assert_eq!(selected.len(), 2);
+
+        let selected = selector
+            .select_examples(&prompt_args! {"adjective" => "big and huge and massive"})
+            .await
+            .unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0]["input"], "happy");
+    }
+
+    #[tokio::test]
+    async fn test_semantic_similarity_example_selector() {
+        let examples = vec![
+            prompt_args! {"question" => "Is a dog a pet?", "answer" => "Yes"},
+            prompt_args! {"question" => "Is pizza tasty?", "answer" => "Yes"},
+            prompt_args! {"question" => "Can a bird fly?", "answer" => "Yes"},
+        ];
+        let selector = SemanticSimilarityExampleSelector::new(InMemoryVectorStore::new(
+            FakeEmbedder::default().with_topics(vec![
+                vec!["dog", "cat", "bird"],
+                vec!["pizza", "bread", "apple"],
+            ]),
+        ))
+        .with_k(1)
+        .with_input_keys(vec!["question"]);
+        selector.add_examples(examples).await.unwrap();
+
+        let selected = selector
+            .select_examples(&prompt_args! {"question" => "Do you like bread?"})
+            .await
+            .unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0]["question"], "Is pizza tasty?");
+    }
+}
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+
+use crate::schemas::{messages::Message, prompt::PromptValue};
+
+use super::{
+    ExampleSelector, FormatPrompter, PromptArgs, PromptError, PromptFromatter, PromptTemplate,
+};
+
+/// A prompt made of a prefix, examples formatted with the example prompt and a suffix,
+/// e.g. to show the model a few questions and their answers before the question of the
+/// input. Like `PromptTemplate`, it is a human message when used as a `FormatPrompter`.
+///
+/// The examples are either fixed, or picked for each input by an `ExampleSelector`. The
+/// selectors being async, a template with a selector must be formatted with
+/// `format_async` or `FormatPrompter::format_prompt_async`, as `LLMChain` does.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let prompt = FewShotPromptTemplate::new(
+///     template_fstring!("Word: {word}\nAntonym: {antonym}", "word", "antonym"),
+///     template_fstring!("Word: {input}\nAntonym:", "input"),
+/// )
+/// .with_prefix(template_fstring!("Give the antonym of every word.",))
+/// .with_examples(vec![
+///     prompt_args! {"word" => "happy", "antonym" => "sad"},
+///     prompt_args! {"word" => "tall", "antonym" => "short"},
+/// ]);
+/// ```
+pub struct FewShotPromptTemplate {
+    example_prompt: PromptTemplate,
+    suffix: PromptTemplate,
+    prefix: Option<PromptTemplate>,
+    examples: Vec<PromptArgs>,
+    example_selector: Option<Box<dyn ExampleSelector>>,
+    example_separator: String,
+}
+
+impl FewShotPromptTemplate {
+    /// The input variables of the prompt are those of the suffix and of the prefix.
+    pub fn new(example_prompt: PromptTemplate, suffix: PromptTemplate) -> Self {
+        Self {
+            example_prompt,
+            suffix,
+            prefix: None,
+            examples: Vec::new(),
+            example_selector: None,
+            example_separator: "\n\n".to_string(),
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: PromptTemplate) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    pub fn with_examples(mut self, examples: Vec<PromptArgs>) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    /// Picks the examples for each input, instead of the fixed examples.
+    pub fn with_example_selector<S: Into<Box<dyn ExampleSelector>>>(mut self, selector: S) -> Self {
+        self.example_selector = Some(selector.into());
+        self
+    }
+
+    /// The separator of the prefix, the examples and the suffix. Default: `"\n\n"`
+    pub fn with_example_separator<S: Into<String>>(mut self, separator: S) -> Self {
+        self.example_separator = separator.into();
+        self
+    }
+
+    /// Formats the prompt with the given examples.
+    pub fn format_with_examples(
+        &self,
+        examples: &[PromptArgs],
+        input_variables: PromptArgs,
+    ) -> Result<String, PromptError> {
+        let mut parts = Vec::with_capacity(examples.len() + 2);
+        if let Some(prefix) = &self.prefix {
+            parts.push(prefix.format(input_variables.clone())?);
+        }
+        for example in examples {
+            parts.push(self.example_prompt.format(example.clone())?);
+        }
+        parts.push(self.suffix.format(input_variables)?);
+        Ok(parts
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(&self.example_separator))
+    }
+
+    /// Formats the prompt with the fixed examples, failing if there is an example selector.
+    pub fn format(&self, input_variables: PromptArgs) -> Result<String, PromptError> {
+        if self.example_selector.is_some() {
+            return Err(PromptError::OtherError(
+                "A FewShotPromptTemplate with an example selector must be formatted with format_async".into(),
+            ));
+        }
+        self.format_with_examples(&self.examples, input_variables)
+    }
+
+    /// Formats the prompt with the examples picked by the selector, or the fixed examples.
+    pub async fn format_async(&self, input_variables: PromptArgs) -> Result<String, PromptError> {
+        match &self.example_selector {
+            Some(selector) => {
+                let examples = selector.select_examples(&input_variables).await?;
+                self.format_with_examples(&examples, input_variables)
+            }
+            None => self.format_with_examples(&self.examples, input_variables),
+        }
+    }
+}
+
+#[async_trait]
+impl FormatPrompter for FewShotPromptTemplate {
+    fn format_prompt(&self, input_variables: PromptArgs) -> Result<PromptValue, PromptError> {
+        let messages = vec![Message::new_human_message(self.format(input_variables)?)];
+        Ok(PromptValue::from_messages(messages))
+    }
+
+    fn get_input_variables(&self) -> Vec<String> {
+        let mut variables = self
+            .prefix
+            .as_ref()
+            .map(|prefix| prefix.variables())
+            .unwrap_or_default();
+        for variable in self.suffix.variables() {
+            if !variables.contains(&variable) {
+                variables.push(variable);
+            }
+        }
+        variables
+    }
+
+    async fn format_prompt_async(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<PromptValue, PromptError> {
+        let messages = vec![Message::new_human_message(
+            self.format_async(input_variables).await?,
+        )];
+        Ok(PromptValue::from_messages(messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prompt::LengthBasedExampleSelector, prompt_args, template_fstring};
+
+    use super::*;
+
+    fn examples() -> Vec<PromptArgs> {
+        vec![
+            prompt_args! {"word" => "happy", "antonym" => "sad"},
+            prompt_args! {"word" => "tall", "antonym" => "short"},
+        ]
+    }
+
+    fn example_prompt() -> PromptTemplate {
+        template_fstring!("Word: {word}\nAntonym: {antonym}", "word", "antonym")
+    }
+
+    #[test]
+    fn test_few_shot_prompt_template() {
+        let prompt = FewShotPromptTemplate::new(
+            example_prompt(),
+            template_fstring!("Word: {input}\nAntonym:", "input"),
+        )
+        .with_prefix(template_fstring!(
+            "Give the antonym of every {kind}.",
+            "kind"
+        ))
+        .with_examples(examples());
+        assert_eq!(prompt.get_input_variables(), vec!["kind", "input"]);
+
+        let formatted = prompt
+            .format(prompt_args! {"kind" => "word", "input" => "big"})
+            .unwrap();
+        assert_eq!(
+            formatted,
+            "Give the antonym of every word.\n\nWord: happy\nAntonym: sad\n\nWord: tall\nAntonym: short\n\nWord: big\nAntonym:"
+        );
+        assert!(prompt.format(prompt_args! {"kind" => "word"}).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_few_shot_prompt_template_with_selector() {
+        let prompt = FewShotPromptTemplate::new(
+            example_prompt(),
+            template_fstring!("Word: {input}\nAntonym:", "input"),
+        )
+        .with_example_selector(
+            LengthBasedExampleSelector::new(examples(), example_prompt()).with_max_length(5),
+        )
+        .with_example_separator("\n");
+
+        assert!(prompt.format(prompt_args! {"input" => "big"}).is_err());
+        let formatted = prompt
+            .format_prompt_async(prompt_args! {"input" => "big"})
+            .await
+            .unwrap()
+            .to_chat_messages();
+        assert_eq!(
+            formatted[0].content.text(),
+            "Word: happy\nAntonym: sad\nWord: big\nAntonym:"
+        );
+    }
+}
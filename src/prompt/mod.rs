@@ -1,13 +1,26 @@
 mod chat;
+mod config;
 mod error;
+mod example_selector;
+mod few_shot;
+mod partial;
+mod pipeline;
 mod prompt;
+mod typed;
 
 use std::collections::HashMap;
 
+use async_trait::async_trait;
 pub use chat::*;
+pub use config::*;
 pub use error::*;
+pub use example_selector::*;
+pub use few_shot::*;
+pub use partial::*;
+pub use pipeline::*;
 pub use prompt::*;
 use serde_json::Value;
+pub use typed::*;
 
 use crate::schemas::{messages::Message, prompt::PromptValue};
 
@@ -43,9 +56,19 @@ where
     }
 }
 
+#[async_trait]
 pub trait FormatPrompter: Send + Sync {
     fn format_prompt(&self, input_variables: PromptArgs) -> Result<PromptValue, PromptError>;
     fn get_input_variables(&self) -> Vec<String>;
+
+    /// Like `format_prompt`, for the prompts which have to await to be formatted, e.g. to
+    /// select their examples. Chains format their prompts with it.
+    async fn format_prompt_async(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<PromptValue, PromptError> {
+        self.format_prompt(input_variables)
+    }
 }
 impl<FP> From<FP> for Box<dyn FormatPrompter>
 where
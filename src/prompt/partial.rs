@@ -0,0 +1,222 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::schemas::prompt::PromptValue;
+
+use super::{FormatPrompter, PromptArgs, PromptError, PromptFromatter};
+
+/// The value of a partial variable, fixed or computed each time the prompt is formatted.
+#[derive(Clone)]
+pub enum PartialVariable {
+    Value(Value),
+    Fn(Arc<dyn Fn() -> Value + Send + Sync>),
+}
+
+impl PartialVariable {
+    fn value(&self) -> Value {
+        match self {
+            PartialVariable::Value(value) => value.clone(),
+            PartialVariable::Fn(f) => f(),
+        }
+    }
+}
+
+impl fmt::Debug for PartialVariable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartialVariable::Value(value) => f.debug_tuple("Value").field(value).finish(),
+            PartialVariable::Fn(_) => f.write_str("Fn"),
+        }
+    }
+}
+
+/// A prompt with some of its variables already given, so that they don't have to be
+/// passed on every call, e.g. the instructions of an output parser or the current date.
+/// The inputs of the call take precedence over the partial variables.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let prompt = template_fstring!(
+///     "Today is {today}. Answer in {language}. {input}",
+///     "today",
+///     "language",
+///     "input"
+/// )
+///     .partial_fn("today", || chrono::Utc::now().format("%Y-%m-%d"))
+///     .partial("language", "Spanish");
+/// assert_eq!(prompt.get_input_variables(), vec!["input"]);
+/// ```
+#[derive(Clone)]
+pub struct PartialPrompt<P> {
+    prompt: P,
+    partial_variables: HashMap<String, PartialVariable>,
+}
+
+impl<P> PartialPrompt<P> {
+    pub fn new(prompt: P) -> Self {
+        Self {
+            prompt,
+            partial_variables: HashMap::new(),
+        }
+    }
+
+    /// Gives the variable a fixed value.
+    pub fn partial<S: Into<String>, V: Serialize>(mut self, name: S, value: V) -> Self {
+        self.partial_variables
+            .insert(name.into(), PartialVariable::Value(json!(value)));
+        self
+    }
+
+    /// Gives the variable the value returned by `f`, called each time the prompt is
+    /// formatted.
+    pub fn partial_fn<S, F, T>(mut self, name: S, f: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn() -> T + Send + Sync + 'static,
+        T: ToString,
+    {
+        self.partial_variables.insert(
+            name.into(),
+            PartialVariable::Fn(Arc::new(move || Value::String(f().to_string()))),
+        );
+        self
+    }
+
+    /// The inputs with the partial variables they don't set.
+    fn merge(&self, mut input_variables: PromptArgs) -> PromptArgs {
+        for (name, variable) in &self.partial_variables {
+            if !input_variables.contains_key(name) {
+                input_variables.insert(name.clone(), variable.value());
+            }
+        }
+        input_variables
+    }
+
+    fn without_partials(&self, variables: Vec<String>) -> Vec<String> {
+        variables
+            .into_iter()
+            .filter(|variable| !self.partial_variables.contains_key(variable))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<P: FormatPrompter> FormatPrompter for PartialPrompt<P> {
+    fn format_prompt(&self, input_variables: PromptArgs) -> Result<PromptValue, PromptError> {
+        self.prompt.format_prompt(self.merge(input_variables))
+    }
+
+    fn get_input_variables(&self) -> Vec<String> {
+        self.without_partials(self.prompt.get_input_variables())
+    }
+
+    async fn format_prompt_async(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<PromptValue, PromptError> {
+        self.prompt
+            .format_prompt_async(self.merge(input_variables))
+            .await
+    }
+}
+
+impl<P: PromptFromatter> PromptFromatter for PartialPrompt<P> {
+    fn template(&self) -> String {
+        self.prompt.template()
+    }
+
+    fn variables(&self) -> Vec<String> {
+        self.without_partials(self.prompt.variables())
+    }
+
+    fn format(&self, input_variables: PromptArgs) -> Result<String, PromptError> {
+        self.prompt.format(self.merge(input_variables))
+    }
+}
+
+/// Gives some of the variables of a prompt, see `PartialPrompt`.
+pub trait PartialPrompter: FormatPrompter + Sized {
+    /// Gives the variable a fixed value.
+    fn partial<S: Into<String>, V: Serialize>(self, name: S, value: V) -> PartialPrompt<Self> {
+        PartialPrompt::new(self).partial(name, value)
+    }
+
+    /// Gives the variable the value returned by `f`, called each time the prompt is
+    /// formatted.
+    fn partial_fn<S, F, T>(self, name: S, f: F) -> PartialPrompt<Self>
+    where
+        S: Into<String>,
+        F: Fn() -> T + Send + Sync + 'static,
+        T: ToString,
+    {
+        PartialPrompt::new(self).partial_fn(name, f)
+    }
+}
+
+impl<P: FormatPrompter> PartialPrompter for P {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{
+        fmt_template, message_formatter,
+        prompt::{HumanMessagePromptTemplate, SystemMessagePromptTemplate},
+        prompt_args, template_fstring,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_partial_prompt_template() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let prompt = template_fstring!(
+            "{greeting} {name}, call {count}",
+            "greeting",
+            "name",
+            "count"
+        )
+        .partial("greeting", "Hello")
+        .partial_fn("count", move || counter.fetch_add(1, Ordering::SeqCst) + 1);
+        assert_eq!(prompt.variables(), vec!["name"]);
+
+        assert_eq!(
+            prompt.format(prompt_args! {"name" => "Luis"}).unwrap(),
+            "Hello Luis, call 1"
+        );
+        assert_eq!(
+            prompt
+                .format(prompt_args! {"name" => "Ana", "greeting" => "Hi"})
+                .unwrap(),
+            "Hi Ana, call 2"
+        );
+        assert!(prompt.format(prompt_args! {}).is_err());
+    }
+
+    #[test]
+    fn test_partial_message_formatter() {
+        let prompt = message_formatter![
+            fmt_template!(SystemMessagePromptTemplate::new(template_fstring!(
+                "Answer in {language}.",
+                "language"
+            ))),
+            fmt_template!(HumanMessagePromptTemplate::new(template_fstring!(
+                "{input}", "input"
+            ))),
+        ]
+        .partial("language", "Spanish");
+        assert_eq!(prompt.get_input_variables(), vec!["input"]);
+
+        let messages = prompt
+            .format_prompt(prompt_args! {"input" => "Hi"})
+            .unwrap()
+            .to_chat_messages();
+        assert_eq!(messages[0].content.text(), "Answer in Spanish.");
+        assert_eq!(messages[1].content.text(), "Hi");
+    }
+}
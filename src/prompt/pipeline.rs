@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::schemas::prompt::PromptValue;
+
+use super::{FormatPrompter, PromptArgs, PromptError, PromptFromatter};
+
+/// Composes prompts: each nested prompt is formatted in turn, its output becoming the
+/// variable of its name for the next nested prompts and for the final prompt.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let prompt = PipelinePromptTemplate::new(template_fstring!(
+///     "{introduction}\n\n{example}\n\n{start}",
+///     "introduction",
+///     "example",
+///     "start"
+/// ))
+/// .with_prompt("introduction", template_fstring!("You are impersonating {person}.", "person"))
+/// .with_prompt("example", template_fstring!("Q: {example_q}\nA: {example_a}", "example_q", "example_a"))
+/// .with_prompt("start", template_fstring!("Q: {input}\nA:", "input"));
+/// ```
+pub struct PipelinePromptTemplate {
+    final_prompt: Box<dyn FormatPrompter>,
+    pipeline_prompts: Vec<(String, Box<dyn PromptFromatter>)>,
+}
+
+impl PipelinePromptTemplate {
+    pub fn new<P: Into<Box<dyn FormatPrompter>>>(final_prompt: P) -> Self {
+        Self {
+            final_prompt: final_prompt.into(),
+            pipeline_prompts: Vec::new(),
+        }
+    }
+
+    /// Adds a nested prompt, formatted after those already added.
+    pub fn with_prompt<S: Into<String>, P: Into<Box<dyn PromptFromatter>>>(
+        mut self,
+        name: S,
+        prompt: P,
+    ) -> Self {
+        self.pipeline_prompts.push((name.into(), prompt.into()));
+        self
+    }
+
+    /// The inputs with the outputs of the nested prompts.
+    fn format_pipeline(&self, mut input_variables: PromptArgs) -> Result<PromptArgs, PromptError> {
+        for (name, prompt) in &self.pipeline_prompts {
+            let output = prompt.format(input_variables.clone())?;
+            input_variables.insert(name.clone(), Value::String(output));
+        }
+        Ok(input_variables)
+    }
+}
+
+#[async_trait]
+impl FormatPrompter for PipelinePromptTemplate {
+    fn format_prompt(&self, input_variables: PromptArgs) -> Result<PromptValue, PromptError> {
+        self.final_prompt
+            .format_prompt(self.format_pipeline(input_variables)?)
+    }
+
+    fn get_input_variables(&self) -> Vec<String> {
+        let mut variables: Vec<String> = Vec::new();
+        let mut produced: Vec<&String> = Vec::new();
+        let prompts_variables = self
+            .pipeline_prompts
+            .iter()
+            .map(|(name, prompt)| (Some(name), prompt.variables()))
+            .chain(std::iter::once((
+                None,
+                self.final_prompt.get_input_variables(),
+            )));
+        for (name, prompt_variables) in prompts_variables {
+            for variable in prompt_variables {
+                if !produced.contains(&&variable) && !variables.contains(&variable) {
+                    variables.push(variable);
+                }
+            }
+            produced.extend(name);
+        }
+        variables
+    }
+
+    async fn format_prompt_async(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<PromptValue, PromptError> {
+        self.final_prompt
+            .format_prompt_async(self.format_pipeline(input_variables)?)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prompt_args, template_fstring};
+
+    use super::*;
+
+    #[test]
+    fn test_pipeline_prompt_template() {
+        let prompt = PipelinePromptTemplate::new(template_fstring!(
+            "{introduction}\n{start}",
+            "introduction",
+            "start"
+        ))
+        .with_prompt(
+            "introduction",
+            template_fstring!("You are impersonating {person}.", "person"),
+        )
+        .with_prompt(
+            "start",
+            template_fstring!("{introduction} Q: {input}", "introduction", "input"),
+        );
+        assert_eq!(prompt.get_input_variables(), vec!["person", "input"]);
+
+        let messages = prompt
+            .format_prompt(prompt_args! {"person" => "Elon Musk", "input" => "Hi"})
+            .unwrap()
+            .to_chat_messages();
+        assert_eq!(
+            messages[0].content.text(),
+            "You are impersonating Elon Musk.\nYou are impersonating Elon Musk. Q: Hi"
+        );
+        assert!(prompt
+            .format_prompt(prompt_args! {"input" => "Hi"})
+            .is_err());
+    }
+}
@@ -1,17 +1,28 @@
+use serde::{Deserialize, Serialize};
+
 use crate::schemas::{messages::Message, prompt::PromptValue};
 
 use super::{FormatPrompter, PromptArgs, PromptError, PromptFromatter};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub enum TemplateFormat {
+    /// `{variable}` placeholders, replaced by the values of the variables.
+    #[default]
+    #[serde(rename = "f-string")]
     FString,
+    /// Jinja2 templates, with loops, conditionals and filters, rendered with minijinja.
+    #[serde(rename = "jinja2")]
     Jinja2,
 }
 
-#[derive(Clone)]
+/// A template of a prompt, serialized as
+/// `{"template": "...", "input_variables": [...], "template_format": "f-string"}`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PromptTemplate {
     template: String,
+    #[serde(rename = "input_variables", default)]
     variables: Vec<String>,
+    #[serde(rename = "template_format", default)]
     format: TemplateFormat,
 }
 
@@ -55,16 +66,17 @@ impl PromptFromatter for PromptTemplate {
             }
         }
 
-        for (key, value) in input_variables {
-            let key = match self.format {
-                TemplateFormat::FString => format!("{{{}}}", key),
-                TemplateFormat::Jinja2 => format!("{{{{{}}}}}", key),
-            };
-            let value_str = match &value {
-                serde_json::Value::String(s) => s.clone(),
-                _ => value.to_string(),
-            };
-            prompt = prompt.replace(&key, &value_str);
+        match self.format {
+            TemplateFormat::FString => {
+                for (key, value) in input_variables {
+                    let value_str = match &value {
+                        serde_json::Value::String(s) => s.clone(),
+                        _ => value.to_string(),
+                    };
+                    prompt = prompt.replace(&format!("{{{}}}", key), &value_str);
+                }
+            }
+            TemplateFormat::Jinja2 => prompt = render_jinja2(&prompt, &input_variables)?,
         }
 
         log::debug!("Formatted prompt: {}", prompt);
@@ -72,6 +84,16 @@ impl PromptFromatter for PromptTemplate {
     }
 }
 
+/// Renders the template with minijinja, the variables being available with their json
+/// structure, e.g. to loop over documents with `{% for doc in docs %}{{ doc.page_content }}`.
+fn render_jinja2(template: &str, input_variables: &PromptArgs) -> Result<String, PromptError> {
+    let mut env = minijinja::Environment::new();
+    // Templates are prompts, not html: keep them as they are written.
+    env.set_keep_trailing_newline(true);
+    env.set_auto_escape_callback(|_| minijinja::AutoEscape::None);
+    Ok(env.render_str(template, input_variables)?)
+}
+
 /// `prompt_args!` is a utility macro used for creating a `std::collections::HashMap<String, serde_json::Value>`.
 /// This HashMap can then be passed as arguments to a function or method.
 ///
@@ -155,7 +177,6 @@ macro_rules! template_jinja2 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::prompt_args;
 
     #[test]
     fn should_format_jinja2_template() {
@@ -178,6 +199,35 @@ mod tests {
         assert_eq!(result.unwrap(), "Hello world!");
     }
 
+    #[test]
+    fn should_format_jinja2_loops_conditionals_and_filters() {
+        let template = template_jinja2!(
+            "{% for doc in docs %}- {{ doc.page_content | upper }}\n{% endfor %}{% if question %}Q: {{ question }}{% endif %}\n",
+            "docs",
+            "question"
+        );
+        let docs = vec![
+            crate::schemas::Document::new("first"),
+            crate::schemas::Document::new("second"),
+        ];
+
+        let result = template
+            .format(prompt_args! {"docs" => docs, "question" => "Which?"})
+            .unwrap();
+        assert_eq!(result, "- FIRST\n- SECOND\nQ: Which?\n");
+
+        let result = template
+            .format(prompt_args! {"docs" => Vec::<String>::new(), "question" => ""})
+            .unwrap();
+        assert_eq!(result, "\n");
+
+        let template = template_jinja2!("{% for doc in docs %}", "docs");
+        assert!(matches!(
+            template.format(prompt_args! {"docs" => Vec::<String>::new()}),
+            Err(PromptError::TemplateError(_))
+        ));
+    }
+
     #[test]
     fn should_format_fstring_template() {
         let template = PromptTemplate::new(
@@ -0,0 +1,237 @@
+use std::marker::PhantomData;
+
+use crate::schemas::prompt::PromptValue;
+
+use super::{FormatPrompter, PromptArgs, PromptError, PromptFromatter, PromptTemplate};
+
+/// A typed set of input variables, converted into `PromptArgs`. Implemented by the
+/// structs declared with `prompt_input!`.
+pub trait PromptInput: Send + Sync {
+    /// Names of the variables, the fields of the struct.
+    const VARIABLES: &'static [&'static str];
+
+    fn to_prompt_args(&self) -> PromptArgs;
+}
+
+const fn is_identifier(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+const fn bytes_eq(template: &[u8], start: usize, end: usize, variable: &[u8]) -> bool {
+    if end - start != variable.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < variable.len() {
+        if template[start + i] != variable[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Returns `true` if the `{variable}`s of the f-string `template` are exactly
+/// `variables`. Braces not enclosing an identifier, e.g. of an example json, are ignored.
+///
+/// It is a `const fn` so `typed_template!` checks the templates at compile time.
+pub const fn template_matches_variables(template: &str, variables: &[&str]) -> bool {
+    let template = template.as_bytes();
+    let mut used = [false; 64];
+    if variables.len() > used.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < template.len() {
+        if template[i] != b'{' {
+            i += 1;
+            continue;
+        }
+        let start = i + 1;
+        let mut end = start;
+        while end < template.len() && is_identifier(template[end]) {
+            end += 1;
+        }
+        if end == start || end == template.len() || template[end] != b'}' {
+            i += 1;
+            continue;
+        }
+
+        let mut found = false;
+        let mut v = 0;
+        while v < variables.len() {
+            if bytes_eq(template, start, end, variables[v].as_bytes()) {
+                used[v] = true;
+                found = true;
+            }
+            v += 1;
+        }
+        if !found {
+            return false;
+        }
+        i = end + 1;
+    }
+
+    let mut v = 0;
+    while v < variables.len() {
+        if !used[v] {
+            return false;
+        }
+        v += 1;
+    }
+    true
+}
+
+/// An f-string `PromptTemplate` formatted from a `PromptInput`. Build it with
+/// `typed_template!`, which checks that the variables of the template are the fields of
+/// the input.
+#[derive(Clone)]
+pub struct TypedPromptTemplate<T> {
+    template: PromptTemplate,
+    _input: PhantomData<fn(T)>,
+}
+
+impl<T: PromptInput> TypedPromptTemplate<T> {
+    /// Prefer `typed_template!`, this constructor doesn't check the template.
+    pub fn new(template: PromptTemplate) -> Self {
+        Self {
+            template,
+            _input: PhantomData,
+        }
+    }
+
+    pub fn format(&self, input: &T) -> Result<String, PromptError> {
+        self.template.format(input.to_prompt_args())
+    }
+
+    pub fn format_prompt(&self, input: &T) -> Result<PromptValue, PromptError> {
+        FormatPrompter::format_prompt(&self.template, input.to_prompt_args())
+    }
+
+    /// The untyped template, e.g. to build an `LLMChain`.
+    pub fn template(&self) -> &PromptTemplate {
+        &self.template
+    }
+}
+
+/// `prompt_input!` declares a struct implementing `PromptInput`, its fields are the
+/// variables. The fields have to implement `Serialize`.
+///
+/// # Usage
+/// ```rust,ignore
+/// prompt_input! {
+///     #[derive(Debug, Clone)]
+///     pub struct QaInput {
+///         pub question: String,
+///         pub context: String,
+///     }
+/// }
+///
+/// let template = typed_template!(QaInput, "Answer {question} using {context}");
+/// ```
+#[macro_export]
+macro_rules! prompt_input {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $( $(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ty ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $( $(#[$field_meta])* $field_vis $field: $ty ),*
+        }
+
+        impl $crate::prompt::PromptInput for $name {
+            const VARIABLES: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn to_prompt_args(&self) -> $crate::prompt::PromptArgs {
+                #[allow(unused_mut)]
+                let mut args = $crate::prompt::PromptArgs::new();
+                $(
+                    args.insert(stringify!($field).to_string(), serde_json::json!(self.$field));
+                )*
+                args
+            }
+        }
+    };
+}
+
+/// `typed_template!` creates a `TypedPromptTemplate` for a `PromptInput`, failing to
+/// compile when the variables of the template are not exactly the fields of the input.
+///
+/// # Usage
+/// ```rust,ignore
+/// let template = typed_template!(QaInput, "Answer {question} using {context}");
+/// let prompt = template.format(&QaInput { question, context })?;
+///
+/// // Doesn't compile, QaInput has no `history` field.
+/// let template = typed_template!(QaInput, "{history} Answer {question} using {context}");
+/// ```
+#[macro_export]
+macro_rules! typed_template {
+    ($input:ty, $template:expr $(,)?) => {{
+        const _: () = assert!(
+            $crate::prompt::template_matches_variables(
+                $template,
+                <$input as $crate::prompt::PromptInput>::VARIABLES,
+            ),
+            concat!(
+                "The variables of the template are not the fields of ",
+                stringify!($input)
+            ),
+        );
+        $crate::prompt::TypedPromptTemplate::<$input>::new($crate::prompt::PromptTemplate::new(
+            $template.to_string(),
+            <$input as $crate::prompt::PromptInput>::VARIABLES
+                .iter()
+                .map(|variable| variable.to_string())
+                .collect(),
+            $crate::prompt::TemplateFormat::FString,
+        ))
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    prompt_input! {
+        struct QaInput {
+            question: String,
+            context: Vec<String>,
+        }
+    }
+
+    #[test]
+    fn test_template_matches_variables() {
+        let variables = &["question", "context"];
+        assert!(template_matches_variables(
+            "Answer {question} using {context}",
+            variables
+        ));
+        assert!(template_matches_variables(
+            "{context}\nAnswer {question} as {\"answer\": ...}",
+            variables
+        ));
+        assert!(!template_matches_variables("Answer {question}", variables));
+        assert!(!template_matches_variables(
+            "{history} Answer {question} using {context}",
+            variables
+        ));
+    }
+
+    #[test]
+    fn test_typed_template() {
+        let template = typed_template!(QaInput, "Answer {question} using {context}");
+        let input = QaInput {
+            question: "Where is Lima?".to_string(),
+            context: vec!["Lima is in Peru".to_string()],
+        };
+        assert_eq!(
+            template.format(&input).unwrap(),
+            "Answer Where is Lima? using [\"Lima is in Peru\"]"
+        );
+    }
+}
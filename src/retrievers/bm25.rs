@@ -0,0 +1,187 @@
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+
+use crate::schemas::{Document, Retriever};
+
+/// Splits a text in lowercase terms, keeping the identifiers like `ERR_CONN_RESET` or
+/// `E1234` whole.
+pub fn default_bm25_tokenizer(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+type Tokenizer = Box<dyn Fn(&str) -> Vec<String> + Send + Sync>;
+
+/// A lexical retriever ranking its documents with BM25, over an inverted index kept in
+/// memory. It finds the exact terms, like identifiers or error codes, that vector search
+/// misses; combine both with an `EnsembleRetriever`.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = BM25Retriever::new(documents).with_k(4);
+/// let documents = retriever.get_relevant_documents("ERR_CONN_RESET").await?;
+/// ```
+pub struct BM25Retriever {
+    documents: Vec<Document>,
+    // The documents with each term, by index, and the frequency of the term in them.
+    index: HashMap<String, Vec<(usize, usize)>>,
+    lengths: Vec<usize>,
+    average_length: f64,
+    tokenizer: Tokenizer,
+    k: usize,
+    k1: f64,
+    b: f64,
+}
+
+impl BM25Retriever {
+    pub fn new(documents: Vec<Document>) -> Self {
+        let mut retriever = Self {
+            documents: Vec::new(),
+            index: HashMap::new(),
+            lengths: Vec::new(),
+            average_length: 0.0,
+            tokenizer: Box::new(default_bm25_tokenizer),
+            k: 4,
+            k1: 1.5,
+            b: 0.75,
+        };
+        retriever.add_documents(documents);
+        retriever
+    }
+
+    /// The number of documents returned. Default: 4
+    pub fn with_k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// The term frequency saturation `k1` and the length normalization `b` of BM25.
+    /// Default: 1.5 and 0.75
+    pub fn with_parameters(mut self, k1: f64, b: f64) -> Self {
+        self.k1 = k1;
+        self.b = b;
+        self
+    }
+
+    /// Splits the documents and the queries in terms, reindexing the documents.
+    pub fn with_tokenizer<F>(mut self, tokenizer: F) -> Self
+    where
+        F: Fn(&str) -> Vec<String> + Send + Sync + 'static,
+    {
+        self.tokenizer = Box::new(tokenizer);
+        let documents = std::mem::take(&mut self.documents);
+        self.index.clear();
+        self.lengths.clear();
+        self.add_documents(documents);
+        self
+    }
+
+    pub fn add_documents(&mut self, documents: Vec<Document>) {
+        for document in documents {
+            let terms = (self.tokenizer)(&document.page_content);
+            let mut frequencies: HashMap<String, usize> = HashMap::new();
+            for term in &terms {
+                *frequencies.entry(term.clone()).or_default() += 1;
+            }
+            let index = self.documents.len();
+            for (term, frequency) in frequencies {
+                self.index.entry(term).or_default().push((index, frequency));
+            }
+            self.lengths.push(terms.len());
+            self.documents.push(document);
+        }
+        self.average_length = if self.lengths.is_empty() {
+            0.0
+        } else {
+            self.lengths.iter().sum::<usize>() as f64 / self.lengths.len() as f64
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// The BM25 score of every document containing a term of the query, by index.
+    fn scores(&self, query: &str) -> HashMap<usize, f64> {
+        let count = self.documents.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for term in (self.tokenizer)(query) {
+            let Some(postings) = self.index.get(&term) else {
+                continue;
+            };
+            let matching = postings.len() as f64;
+            let idf = ((count - matching + 0.5) / (matching + 0.5) + 1.0).ln();
+            for &(index, frequency) in postings {
+                let frequency = frequency as f64;
+                let length = self.lengths[index] as f64 / self.average_length.max(f64::EPSILON);
+                let score = idf * frequency * (self.k1 + 1.0)
+                    / (frequency + self.k1 * (1.0 - self.b + self.b * length));
+                *scores.entry(index).or_default() += score;
+            }
+        }
+        scores
+    }
+
+    /// Returns the `limit` best documents for the query, with their BM25 score.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Document> {
+        let mut scores: Vec<(usize, f64)> = self.scores(query).into_iter().collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores
+            .into_iter()
+            .take(limit)
+            .map(|(index, score)| {
+                let mut document = self.documents[index].clone();
+                document.score = score;
+                document
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Retriever for BM25Retriever {
+    async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        Ok(self.search(query, self.k))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bm25_retriever() {
+        let retriever = BM25Retriever::new(vec![
+            Document::new("The connection failed with ERR_CONN_RESET after a timeout"),
+            Document::new("Connection pooling keeps the connections open"),
+            Document::new("The cat sat on the mat"),
+        ])
+        .with_k(2);
+        assert_eq!(
+            default_bm25_tokenizer("Error E1234: bad-input"),
+            vec!["error", "e1234", "bad", "input"]
+        );
+
+        let documents = retriever
+            .get_relevant_documents("err_conn_reset")
+            .await
+            .unwrap();
+        assert_eq!(documents.len(), 1);
+        assert!(documents[0].page_content.contains("ERR_CONN_RESET"));
+
+        let documents = retriever
+            .get_relevant_documents("connection")
+            .await
+            .unwrap();
+        assert_eq!(documents.len(), 2);
+        assert!(documents[0].score >= documents[1].score);
+        assert!(retriever.search("dog", 2).is_empty());
+    }
+}
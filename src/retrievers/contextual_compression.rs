@@ -0,0 +1,90 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+
+use crate::schemas::{Document, Retriever};
+
+use super::DocumentCompressor;
+
+/// Retrieves documents with a base retriever and compresses them for the query with a
+/// `DocumentCompressor`, e.g. an `LLMChainExtractor` keeping only their relevant parts or
+/// an `EmbeddingsFilter` dropping the documents that are not similar enough, so that
+/// the context of the prompts isn't filled with irrelevant chunks.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = ContextualCompressionRetriever::new(
+///     Retriever::new(store, 10),
+///     EmbeddingsFilter::new(OpenAiEmbedder::default()).with_k(4),
+/// );
+/// let documents = retriever.get_relevant_documents("What is the capital of Peru?").await?;
+/// ```
+pub struct ContextualCompressionRetriever {
+    base_retriever: Box<dyn Retriever>,
+    compressor: Box<dyn DocumentCompressor>,
+}
+
+impl ContextualCompressionRetriever {
+    pub fn new<R, C>(base_retriever: R, compressor: C) -> Self
+    where
+        R: Into<Box<dyn Retriever>>,
+        C: Into<Box<dyn DocumentCompressor>>,
+    {
+        Self {
+            base_retriever: base_retriever.into(),
+            compressor: compressor.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Retriever for ContextualCompressionRetriever {
+    async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        let documents = self.base_retriever.get_relevant_documents(query).await?;
+        if documents.is_empty() {
+            return Ok(documents);
+        }
+        Ok(self.compressor.compress_documents(documents, query).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::retrievers::{BM25Retriever, DocumentCompressorError};
+
+    use super::*;
+
+    /// Keeps the first sentence of each document.
+    struct FirstSentence;
+
+    #[async_trait]
+    impl DocumentCompressor for FirstSentence {
+        async fn compress_documents(
+            &self,
+            documents: Vec<Document>,
+            _query: &str,
+        ) -> Result<Vec<Document>, DocumentCompressorError> {
+            Ok(documents
+                .into_iter()
+                .map(|document| Document {
+                    page_content: document.page_content.split('.').next().unwrap().to_string(),
+                    ..document
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_contextual_compression_retriever() {
+        let retriever = ContextualCompressionRetriever::new(
+            BM25Retriever::new(vec![
+                Document::new("Lima is the capital of Peru. It is on the coast."),
+                Document::new("The cat sat on the mat."),
+            ]),
+            FirstSentence,
+        );
+        let documents = retriever.get_relevant_documents("capital").await.unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].page_content, "Lima is the capital of Peru");
+    }
+}
@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+
+use crate::schemas::Document;
+
+use super::DocumentCompressorError;
+
+/// Shortens or filters retrieved documents given the query, e.g. dropping the irrelevant
+/// ones, see `ContextualCompressionRetriever`.
+#[async_trait]
+pub trait DocumentCompressor: Send + Sync {
+    async fn compress_documents(
+        &self,
+        documents: Vec<Document>,
+        query: &str,
+    ) -> Result<Vec<Document>, DocumentCompressorError>;
+}
+
+impl<C> From<C> for Box<dyn DocumentCompressor>
+where
+    C: DocumentCompressor + 'static,
+{
+    fn from(compressor: C) -> Self {
+        Box::new(compressor)
+    }
+}
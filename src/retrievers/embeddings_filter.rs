@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{
+    embedding::embedder_trait::Embedder, schemas::Document,
+    semantic_router::utils::cosine_similarity,
+};
+
+use super::{DocumentCompressor, DocumentCompressorError};
+
+/// Drops the documents whose embedding is not similar enough to the one of the query,
+/// without calling an LLM. The kept documents are sorted by similarity, set as their
+/// score.
+///
+/// # Example
+/// ```rust,ignore
+/// let filter = EmbeddingsFilter::new(OpenAiEmbedder::default()).with_similarity_threshold(0.8);
+/// ```
+pub struct EmbeddingsFilter {
+    embedder: Arc<dyn Embedder>,
+    similarity_threshold: f64,
+    k: Option<usize>,
+}
+
+impl EmbeddingsFilter {
+    pub fn new<E: Embedder + 'static>(embedder: E) -> Self {
+        Self {
+            embedder: Arc::new(embedder),
+            similarity_threshold: 0.76,
+            k: None,
+        }
+    }
+
+    /// The minimum cosine similarity with the query. Default: 0.76
+    pub fn with_similarity_threshold(mut self, similarity_threshold: f64) -> Self {
+        self.similarity_threshold = similarity_threshold;
+        self
+    }
+
+    /// Keeps at most the `k` most similar documents.
+    pub fn with_k(mut self, k: usize) -> Self {
+        self.k = Some(k);
+        self
+    }
+}
+
+#[async_trait]
+impl DocumentCompressor for EmbeddingsFilter {
+    async fn compress_documents(
+        &self,
+        documents: Vec<Document>,
+        query: &str,
+    ) -> Result<Vec<Document>, DocumentCompressorError> {
+        if documents.is_empty() {
+            return Ok(documents);
+        }
+        let texts: Vec<String> = documents
+            .iter()
+            .map(|document| document.page_content.clone())
+            .collect();
+        let embeddings = self.embedder.embed_documents(&texts).await?;
+        let query = self.embedder.embed_query(query).await?;
+
+        let mut documents: Vec<Document> = documents
+            .into_iter()
+            .zip(embeddings)
+            .map(|(document, embedding)| {
+                let score = cosine_similarity(&query, &embedding);
+                document.with_score(score)
+            })
+            .filter(|document| document.score >= self.similarity_threshold)
+            .collect();
+        documents.sort_by(|a, b| b.score.total_cmp(&a.score));
+        if let Some(k) = self.k {
+            documents.truncate(k);
+        }
+        Ok(documents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::embedding::FakeEmbedder;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_embeddings_filter() {
+        let documents = EmbeddingsFilter::new(FakeEmbedder::default().with_topics(vec![
+            vec!["space"],
+            vec!["ocean"],
+            vec!["forest"],
+        ]))
+        .with_similarity_threshold(0.5)
+        .compress_documents(
+            vec![
+                Document::new("the ocean and space"),
+                Document::new("a walk in the forest"),
+                Document::new("space travel in space"),
+            ],
+            "space",
+        )
+        .await
+        .unwrap();
+        let contents: Vec<&str> = documents.iter().map(|d| d.page_content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec!["space travel in space", "the ocean and space"]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use crate::schemas::{Document, Retriever};
+
+/// Merges the results of several retrievers with weighted reciprocal rank fusion: a
+/// document gets `weight / (c + rank)` from each retriever returning it, and the documents
+/// are sorted by the sum. The documents with the same content are merged.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = EnsembleRetriever::new()
+///     .with_retriever(BM25Retriever::new(documents), 0.4)
+///     .with_retriever(Retriever::new(store, 4), 0.6);
+/// ```
+pub struct EnsembleRetriever {
+    retrievers: Vec<(Box<dyn Retriever>, f64)>,
+    c: f64,
+    k: Option<usize>,
+}
+
+impl Default for EnsembleRetriever {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnsembleRetriever {
+    pub fn new() -> Self {
+        Self {
+            retrievers: Vec::new(),
+            c: 60.0,
+            k: None,
+        }
+    }
+
+    pub fn with_retriever<R: Into<Box<dyn Retriever>>>(
+        mut self,
+        retriever: R,
+        weight: f64,
+    ) -> Self {
+        self.retrievers.push((retriever.into(), weight));
+        self
+    }
+
+    /// The constant added to the ranks, lowering the weight of the first ranks. Default: 60
+    pub fn with_c(mut self, c: f64) -> Self {
+        self.c = c;
+        self
+    }
+
+    /// The maximum number of documents returned. Default: all of them
+    pub fn with_k(mut self, k: usize) -> Self {
+        self.k = Some(k);
+        self
+    }
+
+    /// Fuses the rankings, setting the fused score of each document.
+    pub fn fuse(&self, rankings: Vec<Vec<Document>>) -> Vec<Document> {
+        let mut fused: Vec<Document> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for (documents, (_, weight)) in rankings.into_iter().zip(&self.retrievers) {
+            for (rank, document) in documents.into_iter().enumerate() {
+                let score = weight / (self.c + rank as f64 + 1.0);
+                match positions.get(&document.page_content) {
+                    Some(&position) => fused[position].score += score,
+                    None => {
+                        positions.insert(document.page_content.clone(), fused.len());
+                        fused.push(Document { score, ..document });
+                    }
+                }
+            }
+        }
+        fused.sort_by(|a, b| b.score.total_cmp(&a.score));
+        if let Some(k) = self.k {
+            fused.truncate(k);
+        }
+        fused
+    }
+}
+
+#[async_trait]
+impl Retriever for EnsembleRetriever {
+    async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        // The errors are converted to strings in each future, `Box<dyn Error>` not being Send.
+        let results = join_all(self.retrievers.iter().map(|(retriever, _)| async move {
+            retriever
+                .get_relevant_documents(query)
+                .await
+                .map_err(|e| e.to_string())
+        }))
+        .await;
+        let rankings = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+        Ok(self.fuse(rankings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrievers::FakeRetriever;
+
+    #[tokio::test]
+    async fn test_ensemble_retriever() {
+        let retriever = EnsembleRetriever::new()
+            .with_retriever(FakeRetriever::from_texts(vec!["a", "b", "c"]), 1.0)
+            .with_retriever(FakeRetriever::from_texts(vec!["c", "d"]), 1.0)
+            .with_k(3);
+
+        let documents = retriever.get_relevant_documents("query").await.unwrap();
+        let contents: Vec<&str> = documents
+            .iter()
+            .map(|document| document.page_content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["c", "a", "b"]);
+        assert!((documents[0].score - (1.0 / 63.0 + 1.0 / 61.0)).abs() < 1e-9);
+
+        // A heavier retriever wins the first rank.
+        let retriever = EnsembleRetriever::new()
+            .with_retriever(FakeRetriever::from_texts(vec!["a"]), 0.2)
+            .with_retriever(FakeRetriever::from_texts(vec!["d"]), 0.8);
+        let documents = retriever.get_relevant_documents("query").await.unwrap();
+        assert_eq!(documents[0].page_content, "d");
+    }
+}
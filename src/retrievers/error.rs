@@ -0,0 +1,24 @@
+use thiserror::Error;
+
+use crate::{chain::ChainError, embedding::EmbedderError};
+
+#[derive(Error, Debug)]
+pub enum DocumentCompressorError {
+    #[error("Chain error: {0}")]
+    ChainError(#[from] ChainError),
+
+    #[error("Embedder error: {0}")]
+    EmbedderError(#[from] EmbedderError),
+}
+
+#[derive(Error, Debug)]
+pub enum RerankerError {
+    #[error("Request error: {0}")]
+    RequestError(#[from] reqwest::Error),
+
+    #[error("API error: {0}")]
+    ApiError(String),
+
+    #[error("FastEmbed error: {0}")]
+    FastEmbedError(String),
+}
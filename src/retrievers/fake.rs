@@ -0,0 +1,30 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+
+use crate::schemas::{Document, Retriever};
+
+/// A retriever returning the same documents for every query, for tests of the chains and
+/// the retrievers wrapping other retrievers.
+#[derive(Debug, Clone)]
+pub struct FakeRetriever {
+    documents: Vec<Document>,
+}
+
+impl FakeRetriever {
+    pub fn new(documents: Vec<Document>) -> Self {
+        Self { documents }
+    }
+
+    /// Returns a document for each text, in order.
+    pub fn from_texts<S: Into<String>>(texts: Vec<S>) -> Self {
+        Self::new(texts.into_iter().map(Document::new).collect())
+    }
+}
+
+#[async_trait]
+impl Retriever for FakeRetriever {
+    async fn get_relevant_documents(&self, _query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        Ok(self.documents.clone())
+    }
+}
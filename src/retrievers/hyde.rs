@@ -0,0 +1,148 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    chain::{Chain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt::PromptTemplate,
+    prompt_args,
+    schemas::{Document, Retriever},
+    template_jinja2,
+    vectorstore::{VecStoreOptions, VectorStore},
+};
+
+const DEFAULT_HYDE_TEMPLATE: &str = r#"Please write a passage to answer the question. Write it as if it was an excerpt of a document answering it, without mentioning the question.
+
+Question: {{question}}
+
+Passage:"#;
+
+/// Hypothetical Document Embeddings: asks an LLM to write a document answering the question
+/// and searches the vector store with it instead of the question. The answer, even if
+/// wrong, is usually closer to the relevant documents than the question is.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = HydeRetriever::new(store, OpenAI::default())
+///     .with_prompt(template_jinja2!(
+///         "Write a scientific paper passage answering the question.\nQuestion: {{question}}",
+///         "question"
+///     ))
+///     .with_num_docs(4);
+/// let documents = retriever.get_relevant_documents("What causes the tides?").await?;
+/// ```
+pub struct HydeRetriever {
+    vstore: Box<dyn VectorStore>,
+    llm: Box<dyn LLM>,
+    prompt: PromptTemplate,
+    num_docs: usize,
+    options: VecStoreOptions,
+}
+
+impl HydeRetriever {
+    pub fn new<V, L>(vstore: V, llm: L) -> Self
+    where
+        V: Into<Box<dyn VectorStore>>,
+        L: Into<Box<dyn LLM>>,
+    {
+        Self {
+            vstore: vstore.into(),
+            llm: llm.into(),
+            prompt: template_jinja2!(DEFAULT_HYDE_TEMPLATE, "question"),
+            num_docs: 4,
+            options: VecStoreOptions::default(),
+        }
+    }
+
+    /// The prompt of the hypothetical document, with the `question` variable.
+    pub fn with_prompt(mut self, prompt: PromptTemplate) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    /// The number of documents returned. Default: 4
+    pub fn with_num_docs(mut self, num_docs: usize) -> Self {
+        self.num_docs = num_docs;
+        self
+    }
+
+    pub fn with_options(mut self, options: VecStoreOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The hypothetical document the vector store is searched with.
+    pub async fn generate_document(&self, question: &str) -> Result<String, Box<dyn Error>> {
+        let chain = LLMChainBuilder::new()
+            .llm(self.llm.clone_box())
+            .prompt(self.prompt.clone())
+            .build()?;
+        let document = chain
+            .invoke(prompt_args! {
+                "question" => question,
+            })
+            .await?;
+        Ok(document.trim().to_string())
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        let document = self.generate_document(query).await?;
+        log::debug!("HydeRetriever document: {}", document);
+
+        let search = if document.is_empty() {
+            query
+        } else {
+            &document
+        };
+        self.vstore
+            .similarity_search(search, self.num_docs, &self.options)
+            .await
+    }
+}
+
+#[async_trait]
+impl Retriever for HydeRetriever {
+    async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        CallbackManager::new()
+            .start_run("HydeRetriever", RunType::Retriever)
+            .trace_retriever(query, self.search(query))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{embedding::FakeEmbedder, llm::FakeLLM, vectorstore::InMemoryVectorStore};
+
+    #[tokio::test]
+    async fn test_hyde_retriever() {
+        let store = InMemoryVectorStore::new(FakeEmbedder::default());
+        store
+            .add_documents(
+                &[
+                    Document::new("The tides are caused by the gravity of the moon"),
+                    Document::new("Paris is the capital of France"),
+                ],
+                &VecStoreOptions::default(),
+            )
+            .await
+            .unwrap();
+        let llm = FakeLLM::new("The tides are caused by the gravity of the moon");
+        let retriever = HydeRetriever::new(store, llm.clone())
+            .with_prompt(template_jinja2!("Answer: {{question}}", "question"))
+            .with_num_docs(1);
+
+        let documents = retriever
+            .get_relevant_documents("Why does the sea rise?")
+            .await
+            .unwrap();
+        assert_eq!(
+            documents[0].page_content,
+            "The tides are caused by the gravity of the moon"
+        );
+        assert_eq!(llm.calls()[0][0].content, "Answer: Why does the sea rise?");
+    }
+}
@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use futures::future::try_join_all;
+
+use crate::{
+    chain::{Chain, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt_args,
+    schemas::Document,
+    template_jinja2,
+};
+
+use super::{DocumentCompressor, DocumentCompressorError};
+
+const NO_OUTPUT: &str = "NO_OUTPUT";
+
+const DEFAULT_EXTRACTOR_TEMPLATE: &str = r#"Given the following question and context, extract any part of the context *AS IS* that is relevant to answer the question. If none of the context is relevant return NO_OUTPUT.
+
+Remember, *DO NOT* edit the extracted parts of the context.
+
+> Question: {{question}}
+> Context:
+>>>
+{{context}}
+>>>
+Extracted relevant parts:"#;
+
+/// Asks an LLM to extract the parts of each document relevant to the query, dropping
+/// the documents without any.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = ContextualCompressionRetriever::new(
+///     Retriever::new(store, 8),
+///     LLMChainExtractor::new(OpenAI::default()),
+/// );
+/// ```
+pub struct LLMChainExtractor {
+    chain: LLMChain,
+}
+
+impl LLMChainExtractor {
+    pub fn new<L: Into<Box<dyn LLM>>>(llm: L) -> Self {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(
+                DEFAULT_EXTRACTOR_TEMPLATE,
+                "question",
+                "context"
+            ))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        Self { chain }
+    }
+
+    async fn extract(
+        &self,
+        document: Document,
+        query: &str,
+    ) -> Result<Option<Document>, DocumentCompressorError> {
+        let output = self
+            .chain
+            .invoke(prompt_args! {
+                "question" => query,
+                "context" => document.page_content,
+            })
+            .await?;
+        let output = output.trim();
+        if output.is_empty() || output == NO_OUTPUT {
+            return Ok(None);
+        }
+        Ok(Some(Document {
+            page_content: output.to_string(),
+            ..document
+        }))
+    }
+}
+
+#[async_trait]
+impl DocumentCompressor for LLMChainExtractor {
+    async fn compress_documents(
+        &self,
+        documents: Vec<Document>,
+        query: &str,
+    ) -> Result<Vec<Document>, DocumentCompressorError> {
+        let extracted = try_join_all(
+            documents
+                .into_iter()
+                .map(|document| self.extract(document, query)),
+        )
+        .await?;
+        Ok(extracted.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::llm::FakeLLM;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_llm_chain_extractor() {
+        let llm = FakeLLM::from_responses(vec!["Lima is its capital.", NO_OUTPUT]);
+        let documents = LLMChainExtractor::new(llm.clone())
+            .compress_documents(
+                vec![
+                    Document::new("Peru is in South America. Lima is its capital. It has a coast."),
+                    Document::new("The cat sat on the mat."),
+                ],
+                "What is the capital of Peru?",
+            )
+            .await
+            .unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].page_content, "Lima is its capital.");
+
+        let prompt = llm.calls()[0][0].content.text();
+        assert!(prompt.contains("What is the capital of Peru?"));
+        assert!(prompt.contains("Lima is its capital. It has a coast."));
+    }
+}
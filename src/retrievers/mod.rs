@@ -0,0 +1,38 @@
+mod bm25;
+pub use bm25::*;
+
+mod ensemble;
+pub use ensemble::*;
+
+mod error;
+pub use error::*;
+
+mod fake;
+pub use fake::*;
+
+mod document_compressor;
+pub use document_compressor::*;
+
+mod llm_extractor;
+pub use llm_extractor::*;
+
+mod embeddings_filter;
+pub use embeddings_filter::*;
+
+mod contextual_compression;
+pub use contextual_compression::*;
+
+mod reranker;
+pub use reranker::*;
+
+mod parent_document;
+pub use parent_document::*;
+
+mod multi_query;
+pub use multi_query::*;
+
+mod self_query;
+pub use self_query::*;
+
+mod hyde;
+pub use hyde::*;
@@ -0,0 +1,175 @@
+use std::{collections::HashSet, error::Error, sync::OnceLock};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use regex::Regex;
+
+use crate::{
+    chain::{Chain, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt_args,
+    schemas::{Document, Retriever},
+    template_jinja2,
+};
+
+const DEFAULT_MULTI_QUERY_TEMPLATE: &str = r#"You are an AI language model assistant. Your task is to generate {{num_queries}} different versions of the given user question to retrieve relevant documents from a vector database. By generating multiple perspectives on the user question, your goal is to help the user overcome some of the limitations of the distance-based similarity search. Provide these alternative questions separated by newlines, without numbering them.
+
+Original question: {{question}}"#;
+
+/// Asks an LLM for reformulations of the question and merges the documents retrieved for
+/// each of them, so that the documents are found even if the question is phrased oddly.
+/// The documents with the same content are only returned once, in the order of the
+/// queries.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = MultiQueryRetriever::new(Retriever::new(store, 4), OpenAI::default())
+///     .with_num_queries(5);
+/// ```
+pub struct MultiQueryRetriever {
+    retriever: Box<dyn Retriever>,
+    chain: LLMChain,
+    num_queries: usize,
+    include_original: bool,
+}
+
+impl MultiQueryRetriever {
+    pub fn new<R, L>(retriever: R, llm: L) -> Self
+    where
+        R: Into<Box<dyn Retriever>>,
+        L: Into<Box<dyn LLM>>,
+    {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(
+                DEFAULT_MULTI_QUERY_TEMPLATE,
+                "num_queries",
+                "question"
+            ))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        Self {
+            retriever: retriever.into(),
+            chain,
+            num_queries: 3,
+            include_original: false,
+        }
+    }
+
+    /// The number of reformulations asked to the LLM. Default: 3
+    pub fn with_num_queries(mut self, num_queries: usize) -> Self {
+        self.num_queries = num_queries;
+        self
+    }
+
+    /// Also retrieves the documents of the original question. Default: false
+    pub fn with_include_original(mut self, include_original: bool) -> Self {
+        self.include_original = include_original;
+        self
+    }
+
+    /// The queries the documents are retrieved for.
+    pub async fn generate_queries(&self, question: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let output = self
+            .chain
+            .invoke(prompt_args! {
+                "num_queries" => self.num_queries,
+                "question" => question,
+            })
+            .await?;
+
+        let mut queries: Vec<String> = Vec::new();
+        if self.include_original {
+            queries.push(question.to_string());
+        }
+        // Only the markers of a list are stripped, not the digits starting a query.
+        static LIST_MARKER: OnceLock<Regex> = OnceLock::new();
+        let list_marker = LIST_MARKER.get_or_init(|| Regex::new(r"^\s*(\d+[.)]|[-*])\s+").unwrap());
+        for line in output.lines() {
+            let query = list_marker.replace(line, "");
+            let query = query.trim();
+            if !query.is_empty() && !queries.iter().any(|q| q == query) {
+                queries.push(query.to_string());
+            }
+        }
+        if queries.is_empty() {
+            queries.push(question.to_string());
+        }
+        Ok(queries)
+    }
+}
+
+#[async_trait]
+impl Retriever for MultiQueryRetriever {
+    async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        let queries = self.generate_queries(query).await?;
+        log::debug!("MultiQueryRetriever queries: {:?}", queries);
+
+        // The errors are converted to strings in each future, `Box<dyn Error>` not being Send.
+        let results = join_all(queries.iter().map(|query| async move {
+            self.retriever
+                .get_relevant_documents(query)
+                .await
+                .map_err(|e| e.to_string())
+        }))
+        .await;
+
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut documents = Vec::new();
+        for result in results {
+            for document in result? {
+                if seen.insert(document.page_content.clone()) {
+                    documents.push(document);
+                }
+            }
+        }
+        Ok(documents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{llm::FakeLLM, retrievers::BM25Retriever};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_multi_query_retriever() {
+        let retriever = MultiQueryRetriever::new(
+            BM25Retriever::new(vec![
+                Document::new("Peru has a capital"),
+                Document::new("Lima is a city by the sea"),
+                Document::new("The weather in Peru"),
+                Document::new("Cats sleep"),
+            ])
+            .with_k(1),
+            FakeLLM::new("1. capital of Peru\n2. Lima city\n\n3. capital of Peru\n- 2024 weather in Peru\n1990s-era cats"),
+        )
+        .with_include_original(true);
+
+        let queries = retriever.generate_queries("Where?").await.unwrap();
+        assert_eq!(
+            queries,
+            vec![
+                "Where?",
+                "capital of Peru",
+                "Lima city",
+                "2024 weather in Peru",
+                "1990s-era cats"
+            ]
+        );
+
+        let documents = retriever.get_relevant_documents("Where?").await.unwrap();
+        let contents: Vec<&str> = documents.iter().map(|d| d.page_content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                "Peru has a capital",
+                "Lima is a city by the sea",
+                "The weather in Peru",
+                "Cats sleep"
+            ]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    docstore::Docstore,
+    schemas::{Document, Retriever},
+    text_splitter::TextSplitter,
+    vectorstore::{VecStoreOptions, VectorStore},
+};
+
+/// Searches small chunks of the documents but returns the documents they come from, for
+/// precise matching with the full context in the answers.
+///
+/// `add_documents` splits the documents, or the parent chunks of the parent splitter if
+/// set, with the child splitter. The children are added to the vector store with the id
+/// of their parent in the `id_key` metadata, the parents to the docstore with that id.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = ParentDocumentRetriever::new(
+///     store,
+///     InMemoryDocstore::new(),
+///     RecursiveCharacterTextSplitter::new(SplitterOptions::new().with_chunk_size(400)),
+/// )
+/// .with_parent_splitter(RecursiveCharacterTextSplitter::new(
+///     SplitterOptions::new().with_chunk_size(2000),
+/// ));
+/// retriever.add_documents(&documents).await?;
+/// let parents = retriever.get_relevant_documents("What is the capital of Peru?").await?;
+/// ```
+pub struct ParentDocumentRetriever {
+    vectorstore: Box<dyn VectorStore>,
+    docstore: Box<dyn Docstore>,
+    child_splitter: Box<dyn TextSplitter>,
+    parent_splitter: Option<Box<dyn TextSplitter>>,
+    id_key: String,
+    k: usize,
+    options: VecStoreOptions,
+}
+
+impl ParentDocumentRetriever {
+    pub fn new<V, D, S>(vectorstore: V, docstore: D, child_splitter: S) -> Self
+    where
+        V: Into<Box<dyn VectorStore>>,
+        D: Into<Box<dyn Docstore>>,
+        S: TextSplitter + 'static,
+    {
+        Self {
+            vectorstore: vectorstore.into(),
+            docstore: docstore.into(),
+            child_splitter: Box::new(child_splitter),
+            parent_splitter: None,
+            id_key: "doc_id".to_string(),
+            k: 4,
+            options: VecStoreOptions::default(),
+        }
+    }
+
+    /// Splits the added documents into the parents, instead of keeping them whole.
+    pub fn with_parent_splitter<S: TextSplitter + 'static>(mut self, parent_splitter: S) -> Self {
+        self.parent_splitter = Some(Box::new(parent_splitter));
+        self
+    }
+
+    /// The metadata of the children with the id of their parent. Default: `doc_id`
+    pub fn with_id_key<S: Into<String>>(mut self, id_key: S) -> Self {
+        self.id_key = id_key.into();
+        self
+    }
+
+    /// The number of children searched, there are as many parents or fewer. Default: 4
+    pub fn with_k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    pub fn with_options(mut self, options: VecStoreOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Indexes the documents, returning the ids of the parents in the docstore.
+    pub async fn add_documents(
+        &self,
+        documents: &[Document],
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let parents = match &self.parent_splitter {
+            Some(splitter) => splitter.split_documents(documents).await?,
+            None => documents.to_vec(),
+        };
+
+        let mut ids = Vec::with_capacity(parents.len());
+        let mut children = Vec::new();
+        for parent in &parents {
+            let id = Uuid::new_v4().to_string();
+            for mut child in self
+                .child_splitter
+                .split_documents(std::slice::from_ref(parent))
+                .await?
+            {
+                child
+                    .metadata
+                    .insert(self.id_key.clone(), Value::String(id.clone()));
+                children.push(child);
+            }
+            ids.push(id);
+        }
+
+        // The parents are stored first, so that a child found by a search always has its
+        // parent; they are removed if the children can't be added.
+        let entries: Vec<(String, Document)> = ids.iter().cloned().zip(parents).collect();
+        self.docstore.set(entries).await?;
+        if let Err(e) = self
+            .vectorstore
+            .add_documents(&children, &self.options)
+            .await
+        {
+            if let Err(e) = self.docstore.delete(&ids).await {
+                log::warn!(
+                    "Error removing the parents of the documents not added: {}",
+                    e
+                );
+            }
+            return Err(e);
+        }
+        Ok(ids)
+    }
+}
+
+#[async_trait]
+impl Retriever for ParentDocumentRetriever {
+    async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        let children = self
+            .vectorstore
+            .similarity_search(query, self.k, &self.options)
+            .await?;
+
+        let mut ids: Vec<String> = Vec::new();
+        for child in &children {
+            if let Some(id) = child.metadata.get(&self.id_key).and_then(Value::as_str) {
+                if !ids.iter().any(|seen| seen == id) {
+                    ids.push(id.to_string());
+                }
+            }
+        }
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .docstore
+            .get(&ids)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        docstore::InMemoryDocstore, embedding::FakeEmbedder, text_splitter::TextSplitterError,
+        vectorstore::InMemoryVectorStore,
+    };
+
+    use super::*;
+
+    /// Splits the texts into sentences.
+    struct SentenceSplitter;
+
+    #[async_trait]
+    impl TextSplitter for SentenceSplitter {
+        async fn split_text(&self, text: &str) -> Result<Vec<String>, TextSplitterError> {
+            Ok(text
+                .split('.')
+                .map(str::trim)
+                .filter(|sentence| !sentence.is_empty())
+                .map(String::from)
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parent_document_retriever() {
+        let docstore = InMemoryDocstore::new();
+        let retriever = ParentDocumentRetriever::new(
+            InMemoryVectorStore::new(FakeEmbedder::default().with_topics(vec![
+                vec!["space"],
+                vec!["ocean"],
+                vec!["forest"],
+            ])),
+            docstore.clone(),
+            SentenceSplitter,
+        )
+        .with_k(2);
+        let ids = retriever
+            .add_documents(&[
+                Document::new("The rocket left. It went to space. Then it came back."),
+                Document::new("The ocean is deep. Fish swim in it."),
+                Document::new("A walk in the forest."),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(docstore.len(), 3);
+
+        let documents = retriever.get_relevant_documents("space").await.unwrap();
+        assert_eq!(
+            documents[0].page_content,
+            "The rocket left. It went to space. Then it came back."
+        );
+        assert!(documents
+            .iter()
+            .all(|document| !document.metadata.contains_key("doc_id")));
+    }
+}
@@ -0,0 +1,189 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{retrievers::RerankerError, runtime::send, schemas::Document};
+
+use super::{sort_by_scores, Reranker};
+
+#[derive(Deserialize)]
+struct CohereRerankResponse {
+    results: Vec<CohereRerankResult>,
+}
+
+#[derive(Deserialize)]
+struct CohereRerankResult {
+    index: usize,
+    relevance_score: f64,
+}
+
+/// Reranks the documents with the Cohere Rerank API.
+///
+/// The default uses the `COHERE_API_KEY` environment variable.
+pub struct CohereRerank {
+    api_key: String,
+    model: String,
+    top_n: Option<usize>,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl CohereRerank {
+    pub fn new<S: Into<String>>(api_key: S) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "rerank-english-v3.0".to_string(),
+            top_n: None,
+            base_url: "https://api.cohere.com".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    /// The rerank model, e.g. `rerank-multilingual-v3.0`. Default: `rerank-english-v3.0`
+    pub fn with_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Only returns the `top_n` most relevant documents.
+    pub fn with_top_n(mut self, top_n: usize) -> Self {
+        self.top_n = Some(top_n);
+        self
+    }
+
+    pub fn with_base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+}
+
+impl Default for CohereRerank {
+    fn default() -> Self {
+        Self::new(std::env::var("COHERE_API_KEY").unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl Reranker for CohereRerank {
+    async fn rerank(
+        &self,
+        query: &str,
+        documents: Vec<Document>,
+    ) -> Result<Vec<Document>, RerankerError> {
+        send(async move {
+            if documents.is_empty() {
+                return Ok(documents);
+            }
+            let texts: Vec<&str> = documents
+                .iter()
+                .map(|document| document.page_content.as_str())
+                .collect();
+            let mut body = json!({
+                "model": self.model,
+                "query": query,
+                "documents": texts,
+            });
+            if let Some(top_n) = self.top_n {
+                body["top_n"] = json!(top_n);
+            }
+
+            let response = self
+                .client
+                .post(format!("{}/v1/rerank", self.base_url))
+                .bearer_auth(&self.api_key)
+                .json(&body)
+                .send()
+                .await?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await?;
+                return Err(RerankerError::ApiError(format!(
+                    "Cohere Error {}: {}",
+                    status.as_u16(),
+                    body
+                )));
+            }
+
+            let response: CohereRerankResponse = response.json().await?;
+            Ok(sort_by_scores(
+                documents,
+                response
+                    .results
+                    .into_iter()
+                    .map(|result| (result.index, result.relevance_score)),
+            ))
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cohere_rerank() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/rerank")
+            .match_header("authorization", "Bearer key")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "model": "rerank-english-v3.0",
+                "query": "capital of Peru",
+                "documents": ["The cat", "Lima is the capital of Peru", "Peru"],
+                "top_n": 2,
+            })))
+            .with_body(
+                json!({
+                    "results": [
+                        {"index": 1, "relevance_score": 0.98},
+                        {"index": 2, "relevance_score": 0.4},
+                    ]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let documents = CohereRerank::new("key")
+            .with_base_url(server.url())
+            .with_top_n(2)
+            .rerank(
+                "capital of Peru",
+                vec![
+                    Document::new("The cat"),
+                    Document::new("Lima is the capital of Peru"),
+                    Document::new("Peru"),
+                ],
+            )
+            .await
+            .unwrap();
+        mock.assert_async().await;
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].page_content, "Lima is the capital of Peru");
+        assert_eq!(documents[0].score, 0.98);
+        assert_eq!(documents[1].page_content, "Peru");
+    }
+
+    #[tokio::test]
+    async fn test_cohere_rerank_error() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/v1/rerank")
+            .with_status(401)
+            .with_body("invalid api token")
+            .create_async()
+            .await;
+
+        let result = CohereRerank::new("key")
+            .with_base_url(server.url())
+            .rerank("query", vec![Document::new("document")])
+            .await;
+        assert!(matches!(result, Err(RerankerError::ApiError(_))));
+    }
+}
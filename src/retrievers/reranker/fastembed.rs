@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+pub use fastembed::{RerankInitOptions, RerankerModel, TextRerank};
+
+use crate::{retrievers::RerankerError, schemas::Document};
+
+use super::{sort_by_scores, Reranker};
+
+/// Reranks the documents locally with an ONNX cross-encoder of FastEmbed, by default
+/// `BAAI/bge-reranker-base`, downloaded on the first use. The model runs on the blocking
+/// threads of tokio, not to block the runtime.
+pub struct FastEmbedReranker {
+    model: Arc<TextRerank>,
+    batch_size: Option<usize>,
+}
+
+impl FastEmbedReranker {
+    pub fn try_new() -> Result<Self, RerankerError> {
+        Ok(Self {
+            model: Arc::new(
+                TextRerank::try_new(Default::default())
+                    .map_err(|e| RerankerError::FastEmbedError(e.to_string()))?,
+            ),
+            batch_size: None,
+        })
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+}
+
+impl From<TextRerank> for FastEmbedReranker {
+    fn from(model: TextRerank) -> Self {
+        Self {
+            model: Arc::new(model),
+            batch_size: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Reranker for FastEmbedReranker {
+    async fn rerank(
+        &self,
+        query: &str,
+        documents: Vec<Document>,
+    ) -> Result<Vec<Document>, RerankerError> {
+        if documents.is_empty() {
+            return Ok(documents);
+        }
+        let texts: Vec<String> = documents
+            .iter()
+            .map(|document| document.page_content.clone())
+            .collect();
+        let model = self.model.clone();
+        let query = query.to_string();
+        let batch_size = self.batch_size;
+        let results =
+            tokio::task::spawn_blocking(move || model.rerank(query, texts, false, batch_size))
+                .await
+                .map_err(|e| RerankerError::FastEmbedError(e.to_string()))?
+                .map_err(|e| RerankerError::FastEmbedError(e.to_string()))?;
+        Ok(sort_by_scores(
+            documents,
+            results
+                .into_iter()
+                .map(|result| (result.index, result.score as f64)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fastembed_reranker() {
+        let reranker = FastEmbedReranker::try_new().unwrap();
+        let documents = reranker
+            .rerank(
+                "What is the capital of Peru?",
+                vec![
+                    Document::new("The cat sat on the mat."),
+                    Document::new("Lima is the capital of Peru."),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(documents[0].page_content, "Lima is the capital of Peru.");
+    }
+}
@@ -0,0 +1,13 @@
+mod reranker_trait;
+pub use reranker_trait::*;
+
+mod reranking_retriever;
+pub use reranking_retriever::*;
+
+mod cohere;
+pub use cohere::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod fastembed;
+#[cfg(not(target_arch = "wasm32"))]
+pub use fastembed::*;
@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+
+use crate::{retrievers::RerankerError, schemas::Document};
+
+/// Scores the relevance of documents to a query, usually with a cross-encoder, more
+/// precise than the similarity of their embeddings, see `RerankingRetriever`.
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// The documents sorted by relevance, most relevant first, with it as their score.
+    async fn rerank(
+        &self,
+        query: &str,
+        documents: Vec<Document>,
+    ) -> Result<Vec<Document>, RerankerError>;
+}
+
+impl<R> From<R> for Box<dyn Reranker>
+where
+    R: Reranker + 'static,
+{
+    fn from(reranker: R) -> Self {
+        Box::new(reranker)
+    }
+}
+
+/// Sets the scores of the documents, given as `(index, score)`, and sorts them by score.
+pub(crate) fn sort_by_scores(
+    documents: Vec<Document>,
+    scores: impl IntoIterator<Item = (usize, f64)>,
+) -> Vec<Document> {
+    let mut documents: Vec<Option<Document>> = documents.into_iter().map(Some).collect();
+    let mut reranked: Vec<Document> = scores
+        .into_iter()
+        .filter_map(|(index, score)| {
+            documents
+                .get_mut(index)
+                .and_then(Option::take)
+                .map(|document| document.with_score(score))
+        })
+        .collect();
+    reranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    reranked
+}
@@ -0,0 +1,101 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+
+use crate::schemas::{Document, Retriever};
+
+use super::Reranker;
+
+/// Reorders the documents of a base retriever with a `Reranker`, so that the final
+/// ordering is by relevance scores instead of raw vector similarity. The usual setup
+/// fetches many documents from the base retriever and keeps the top few after reranking.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = RerankingRetriever::new(Retriever::new(store, 20), CohereRerank::default())
+///     .with_top_n(4);
+/// ```
+pub struct RerankingRetriever {
+    base_retriever: Box<dyn Retriever>,
+    reranker: Box<dyn Reranker>,
+    top_n: Option<usize>,
+}
+
+impl RerankingRetriever {
+    pub fn new<R, RR>(base_retriever: R, reranker: RR) -> Self
+    where
+        R: Into<Box<dyn Retriever>>,
+        RR: Into<Box<dyn Reranker>>,
+    {
+        Self {
+            base_retriever: base_retriever.into(),
+            reranker: reranker.into(),
+            top_n: None,
+        }
+    }
+
+    /// Keeps at most the `top_n` most relevant documents.
+    pub fn with_top_n(mut self, top_n: usize) -> Self {
+        self.top_n = Some(top_n);
+        self
+    }
+}
+
+#[async_trait]
+impl Retriever for RerankingRetriever {
+    async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        let documents = self.base_retriever.get_relevant_documents(query).await?;
+        if documents.is_empty() {
+            return Ok(documents);
+        }
+        let mut documents = self.reranker.rerank(query, documents).await?;
+        if let Some(top_n) = self.top_n {
+            documents.truncate(top_n);
+        }
+        Ok(documents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::retrievers::{sort_by_scores, BM25Retriever, RerankerError};
+
+    use super::*;
+
+    /// Scores the documents by their length, shortest first.
+    struct ShortestReranker;
+
+    #[async_trait]
+    impl Reranker for ShortestReranker {
+        async fn rerank(
+            &self,
+            _query: &str,
+            documents: Vec<Document>,
+        ) -> Result<Vec<Document>, RerankerError> {
+            let scores: Vec<(usize, f64)> = documents
+                .iter()
+                .enumerate()
+                .map(|(index, document)| (index, 1.0 / document.page_content.len() as f64))
+                .collect();
+            Ok(sort_by_scores(documents, scores))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reranking_retriever() {
+        let retriever = RerankingRetriever::new(
+            BM25Retriever::new(vec![
+                Document::new("rust rust rust is a language with a long description"),
+                Document::new("rust is short"),
+                Document::new("python"),
+                Document::new("I like rust"),
+            ]),
+            ShortestReranker,
+        )
+        .with_top_n(2);
+        let documents = retriever.get_relevant_documents("rust").await.unwrap();
+        let contents: Vec<&str> = documents.iter().map(|d| d.page_content.as_str()).collect();
+        assert_eq!(contents, vec!["I like rust", "rust is short"]);
+        assert!(documents[0].score > documents[1].score);
+    }
+}
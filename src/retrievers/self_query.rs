@@ -0,0 +1,295 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    chain::{Chain, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    output_parsers::extract_json,
+    prompt_args,
+    schemas::{Document, Retriever},
+    template_jinja2,
+    vectorstore::{MetadataFilter, VecStoreOptions, VectorStore},
+};
+
+const DEFAULT_SELF_QUERY_TEMPLATE: &str = r#"Your goal is to structure the user's query to match the request schema provided below.
+
+The documents have the following contents: {{document_contents}}
+
+They have the following metadata attributes:
+{{attributes}}
+
+Answer with a JSON object with the following keys:
+- "query": the text to compare with the contents of the documents, without the conditions on the attributes. Use an empty string if there is nothing to compare.
+- "filter": the conditions on the attributes, or null if there are none.
+- "limit": the number of documents requested, or null if not specified.
+
+A condition is one of:
+- {"eq": {"field": <attribute>, "value": <value>}}, and likewise "ne", "gt", "gte", "lt" and "lte"
+- {"in": {"field": <attribute>, "values": [<value>, ...]}}
+- {"and": [<condition>, ...]} or {"or": [<condition>, ...]}
+
+Only use the attributes listed above, with values of their type.
+
+Example: for the query "sci-fi movies after 2010 rated above 8" on movies with the attributes genre, year and rating, the answer is:
+{"query": "", "filter": {"and": [{"eq": {"field": "genre", "value": "sci-fi"}}, {"gt": {"field": "year", "value": 2010}}, {"gt": {"field": "rating", "value": 8}}]}, "limit": null}
+
+User query: {{query}}"#;
+
+/// A metadata attribute of the documents, that the `SelfQueryRetriever` can filter on.
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributeInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub attribute_type: String,
+    pub description: String,
+}
+
+impl AttributeInfo {
+    /// The type is described to the LLM, e.g. `integer`, `float` or `string`.
+    pub fn new<N: Into<String>, T: Into<String>, D: Into<String>>(
+        name: N,
+        attribute_type: T,
+        description: D,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            attribute_type: attribute_type.into(),
+            description: description.into(),
+        }
+    }
+}
+
+/// The search of a query, as translated by the LLM.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StructuredQuery {
+    pub query: String,
+    pub filter: Option<MetadataFilter>,
+    pub limit: Option<usize>,
+}
+
+/// Asks an LLM to translate the query into a search term and a filter on the metadata of
+/// the documents, e.g. "movies after 2010 rated above 8" into a filter on the year and the
+/// rating, then searches the vector store with them. The store must support the metadata
+/// filters, see `VectorStore::supports_metadata_filter`.
+///
+/// The filter can only use the described attributes. Without search term, the original
+/// query is searched.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = SelfQueryRetriever::new(
+///     store,
+///     OpenAI::default(),
+///     "Brief summary of a movie",
+///     vec![
+///         AttributeInfo::new("year", "integer", "The year the movie was released"),
+///         AttributeInfo::new("rating", "float", "A 1-10 rating for the movie"),
+///     ],
+/// )
+/// .with_num_docs(4);
+/// let documents = retriever
+///     .get_relevant_documents("movies after 2010 rated above 8")
+///     .await?;
+/// ```
+pub struct SelfQueryRetriever {
+    vstore: Box<dyn VectorStore>,
+    chain: LLMChain,
+    document_contents: String,
+    attributes: Vec<AttributeInfo>,
+    num_docs: usize,
+    options: VecStoreOptions,
+}
+
+impl SelfQueryRetriever {
+    pub fn new<V, L, S>(
+        vstore: V,
+        llm: L,
+        document_contents: S,
+        attributes: Vec<AttributeInfo>,
+    ) -> Self
+    where
+        V: Into<Box<dyn VectorStore>>,
+        L: Into<Box<dyn LLM>>,
+        S: Into<String>,
+    {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(
+                DEFAULT_SELF_QUERY_TEMPLATE,
+                "document_contents",
+                "attributes",
+                "query"
+            ))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        Self {
+            vstore: vstore.into(),
+            chain,
+            document_contents: document_contents.into(),
+            attributes,
+            num_docs: 4,
+            options: VecStoreOptions::default(),
+        }
+    }
+
+    /// The number of documents returned when the query doesn't ask for a number. Default: 4
+    pub fn with_num_docs(mut self, num_docs: usize) -> Self {
+        self.num_docs = num_docs;
+        self
+    }
+
+    /// The options of the searches, their metadata filter is replaced by the one of the
+    /// query.
+    pub fn with_options(mut self, options: VecStoreOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The search term and the filter of the query, checked against the attributes.
+    pub async fn generate_query(&self, query: &str) -> Result<StructuredQuery, Box<dyn Error>> {
+        let output = self
+            .chain
+            .invoke(prompt_args! {
+                "document_contents" => self.document_contents,
+                "attributes" => serde_json::to_string_pretty(&self.attributes)?,
+                "query" => query,
+            })
+            .await?;
+        let structured_query: StructuredQuery = serde_json::from_str(extract_json(&output)?)
+            .map_err(|e| format!("Invalid structured query: {}. Output: {}", e, output))?;
+        if let Some(filter) = &structured_query.filter {
+            self.check_fields(filter)?;
+        }
+        Ok(structured_query)
+    }
+
+    fn check_fields(&self, filter: &MetadataFilter) -> Result<(), String> {
+        match filter {
+            MetadataFilter::And(filters) | MetadataFilter::Or(filters) => filters
+                .iter()
+                .try_for_each(|filter| self.check_fields(filter)),
+            MetadataFilter::Eq { field, .. }
+            | MetadataFilter::Ne { field, .. }
+            | MetadataFilter::Gt { field, .. }
+            | MetadataFilter::Gte { field, .. }
+            | MetadataFilter::Lt { field, .. }
+            | MetadataFilter::Lte { field, .. }
+            | MetadataFilter::In { field, .. } => {
+                if self
+                    .attributes
+                    .iter()
+                    .any(|attribute| &attribute.name == field)
+                {
+                    Ok(())
+                } else {
+                    Err(format!("The filter uses the unknown attribute {}", field))
+                }
+            }
+        }
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        let structured_query = self.generate_query(query).await?;
+        log::debug!("SelfQueryRetriever query: {:?}", structured_query);
+
+        let mut options = self.options.clone();
+        options.metadata_filter = structured_query.filter;
+        if options.metadata_filter.is_some() && !self.vstore.supports_metadata_filter() {
+            return Err("The vector store doesn't support metadata filters".into());
+        }
+        let search = match structured_query.query.trim() {
+            "" => query,
+            search => search,
+        };
+        let limit = structured_query.limit.unwrap_or(self.num_docs);
+        self.vstore.similarity_search(search, limit, &options).await
+    }
+}
+
+#[async_trait]
+impl Retriever for SelfQueryRetriever {
+    async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
+        CallbackManager::new()
+            .start_run("SelfQueryRetriever", RunType::Retriever)
+            .trace_retriever(query, self.search(query))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{embedding::FakeEmbedder, llm::FakeLLM, vectorstore::InMemoryVectorStore};
+
+    fn movie(title: &str, year: i64, rating: f64) -> Document {
+        Document::new(title).with_metadata(HashMap::from([
+            ("year".to_string(), json!(year)),
+            ("rating".to_string(), json!(rating)),
+        ]))
+    }
+
+    async fn movies_retriever(answer: &str) -> SelfQueryRetriever {
+        let store = InMemoryVectorStore::new(FakeEmbedder::default());
+        store
+            .add_documents(
+                &[
+                    movie("Inception", 2010, 8.8),
+                    movie("Interstellar", 2014, 8.7),
+                    movie("Arrival", 2016, 7.9),
+                    movie("The Matrix", 1999, 8.7),
+                ],
+                &VecStoreOptions::default(),
+            )
+            .await
+            .unwrap();
+        SelfQueryRetriever::new(
+            store,
+            FakeLLM::new(answer),
+            "The title of a movie",
+            vec![
+                AttributeInfo::new("year", "integer", "The year the movie was released"),
+                AttributeInfo::new("rating", "float", "A 1-10 rating for the movie"),
+            ],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_self_query_retriever() {
+        let retriever = movies_retriever(
+            r#"```json
+{"query": "", "filter": {"and": [{"gt": {"field": "year", "value": 2010}}, {"gt": {"field": "rating", "value": 8}}]}, "limit": null}
+```"#,
+        )
+        .await;
+        let documents = retriever
+            .get_relevant_documents("movies after 2010 rated above 8")
+            .await
+            .unwrap();
+        let titles: Vec<&str> = documents
+            .iter()
+            .map(|document| document.page_content.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Interstellar"]);
+
+        let retriever = movies_retriever(
+            r#"{"query": "", "filter": {"eq": {"field": "director", "value": "Nolan"}}, "limit": 2}"#,
+        )
+        .await;
+        let error = retriever
+            .get_relevant_documents("movies by Nolan")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The filter uses the unknown attribute director"
+        );
+    }
+}
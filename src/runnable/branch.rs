@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+
+use crate::chain::ChainError;
+
+use super::{Runnable, RunnableFn};
+
+type Branch<I, O> = (Box<dyn Runnable<I, bool>>, Box<dyn Runnable<I, O>>);
+
+/// Routes the input to the runnable of the first branch whose condition holds, or to the
+/// default runnable.
+///
+/// Conditions are runnables returning a `bool`, so they can be async, e.g. an LLM
+/// classifying the input. Sync closures are added with `with_condition`.
+///
+/// # Example
+/// ```rust,ignore
+/// let is_about_code = prompt
+///     .pipe(llm)
+///     .pipe(RunnableFn::new(|result: GenerateResult| async move {
+///         Ok(result.generation.contains("code"))
+///     }));
+///
+/// let branch = RunnableBranch::new(general_chain)
+///     .with_condition(|input: &PromptArgs| input.contains_key("sql"), sql_chain)
+///     .with_branch(is_about_code, code_chain);
+/// ```
+pub struct RunnableBranch<I, O> {
+    branches: Vec<Branch<I, O>>,
+    default: Box<dyn Runnable<I, O>>,
+}
+
+impl<I, O> RunnableBranch<I, O>
+where
+    I: Clone + Send + Sync + 'static,
+    O: Send + 'static,
+{
+    pub fn new<R: Runnable<I, O> + 'static>(default: R) -> Self {
+        Self {
+            branches: Vec::new(),
+            default: Box::new(default),
+        }
+    }
+
+    /// Adds a branch taken when `condition` returns `true`. Branches are evaluated in
+    /// the order they were added.
+    pub fn with_branch<C, R>(mut self, condition: C, runnable: R) -> Self
+    where
+        C: Runnable<I, bool> + 'static,
+        R: Runnable<I, O> + 'static,
+    {
+        self.branches
+            .push((Box::new(condition), Box::new(runnable)));
+        self
+    }
+
+    /// Adds a branch taken when the sync `condition` returns `true`.
+    pub fn with_condition<F, R>(self, condition: F, runnable: R) -> Self
+    where
+        F: Fn(&I) -> bool + Send + Sync + 'static,
+        R: Runnable<I, O> + 'static,
+    {
+        let condition = RunnableFn::new(move |input: I| {
+            let taken = condition(&input);
+            async move { Ok(taken) }
+        });
+        self.with_branch(condition, runnable)
+    }
+}
+
+#[async_trait]
+impl<I, O> Runnable<I, O> for RunnableBranch<I, O>
+where
+    I: Clone + Send + Sync + 'static,
+    O: Send + 'static,
+{
+    async fn run(&self, input: I) -> Result<O, ChainError> {
+        for (condition, runnable) in &self.branches {
+            if condition.run(input.clone()).await? {
+                return runnable.run(input).await;
+            }
+        }
+        self.default.run(input).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(text: &'static str) -> impl Runnable<String, String> {
+        RunnableFn::new(move |_: String| async move { Ok(text.to_string()) })
+    }
+
+    #[tokio::test]
+    async fn test_branch() {
+        let is_question = RunnableFn::new(|input: String| async move { Ok(input.ends_with('?')) });
+        let branch = RunnableBranch::new(answer("default"))
+            .with_condition(|input: &String| input.is_empty(), answer("empty"))
+            .with_branch(is_question, answer("question"));
+
+        assert_eq!(branch.run("".to_string()).await.unwrap(), "empty");
+        assert_eq!(branch.run("Why?".to_string()).await.unwrap(), "question");
+        assert_eq!(branch.run("Hi".to_string()).await.unwrap(), "default");
+    }
+}
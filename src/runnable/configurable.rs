@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{callbacks::RunConfig, chain::ChainError};
+
+use super::Runnable;
+
+type FieldSetter<R> = Box<dyn Fn(R, &Value) -> R + Send + Sync>;
+
+/// A runnable with fields that can be set per invocation, through the `configurable`
+/// values of the `RunConfig`. Built with `Runnable::configurable_fields`.
+///
+/// # Example
+/// ```rust,ignore
+/// let llm = OpenAI::default()
+///     .configurable_fields()
+///     .with_field("model", |llm, value| llm.with_model(value.as_str().unwrap_or("gpt-4o")));
+/// let pipeline = prompt.pipe(llm);
+///
+/// let config = RunConfig::new().with_configurable("model", "gpt-4o-mini");
+/// let result = pipeline.run_with_config(input, config).await?;
+/// ```
+pub struct ConfigurableFields<R> {
+    runnable: R,
+    fields: Vec<(String, FieldSetter<R>)>,
+}
+
+impl<R: Clone> ConfigurableFields<R> {
+    pub fn new(runnable: R) -> Self {
+        Self {
+            runnable,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a field with id `id`. When the config has a value for it, the runnable is
+    /// run as returned by `setter`.
+    pub fn with_field<S, F>(mut self, id: S, setter: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(R, &Value) -> R + Send + Sync + 'static,
+    {
+        self.fields.push((id.into(), Box::new(setter)));
+        self
+    }
+
+    /// The runnable with the fields set in `config`, `None` if none is.
+    fn configure(&self, config: &RunConfig) -> Option<R> {
+        let mut configured = None;
+        for (id, setter) in self.fields.iter() {
+            if let Some(value) = config.configurable.get(id) {
+                let runnable = configured.unwrap_or_else(|| self.runnable.clone());
+                configured = Some(setter(runnable, value));
+            }
+        }
+        configured
+    }
+}
+
+#[async_trait]
+impl<I, O, R> Runnable<I, O> for ConfigurableFields<R>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+    R: Runnable<I, O> + Clone,
+{
+    async fn run(&self, input: I) -> Result<O, ChainError> {
+        match self.configure(&RunConfig::current()) {
+            Some(runnable) => runnable.run(input).await,
+            None => self.runnable.run(input).await,
+        }
+    }
+}
+
+/// A runnable with alternatives selected per invocation by the `configurable` value
+/// `key` of the `RunConfig`. Built with `Runnable::configurable_alternatives`.
+///
+/// # Example
+/// ```rust,ignore
+/// let chain = fast_chain
+///     .configurable_alternatives("mode")
+///     .with_alternative("accurate", accurate_chain);
+///
+/// let config = RunConfig::new().with_configurable("mode", "accurate");
+/// let result = chain.run_with_config(input, config).await?;
+/// ```
+pub struct ConfigurableAlternatives<I, O> {
+    key: String,
+    default: Box<dyn Runnable<I, O>>,
+    alternatives: HashMap<String, Box<dyn Runnable<I, O>>>,
+}
+
+impl<I, O> ConfigurableAlternatives<I, O>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+{
+    pub fn new<S, R>(key: S, default: R) -> Self
+    where
+        S: Into<String>,
+        R: Runnable<I, O> + 'static,
+    {
+        Self {
+            key: key.into(),
+            default: Box::new(default),
+            alternatives: HashMap::new(),
+        }
+    }
+
+    pub fn with_alternative<S, R>(mut self, name: S, runnable: R) -> Self
+    where
+        S: Into<String>,
+        R: Runnable<I, O> + 'static,
+    {
+        self.alternatives.insert(name.into(), Box::new(runnable));
+        self
+    }
+}
+
+#[async_trait]
+impl<I, O> Runnable<I, O> for ConfigurableAlternatives<I, O>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+{
+    async fn run(&self, input: I) -> Result<O, ChainError> {
+        let config = RunConfig::current();
+        let runnable = match config.configurable.get(&self.key).and_then(|v| v.as_str()) {
+            Some(name) => self.alternatives.get(name).ok_or_else(|| {
+                ChainError::OtherError(format!("Unknown alternative for {}: {}", self.key, name))
+            })?,
+            None => &self.default,
+        };
+        runnable.run(input).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runnable::RunnableFn;
+
+    #[derive(Clone)]
+    struct Greeter {
+        greeting: String,
+    }
+
+    #[async_trait]
+    impl Runnable<String, String> for Greeter {
+        async fn run(&self, input: String) -> Result<String, ChainError> {
+            Ok(format!("{} {}", self.greeting, input))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_configurable_fields() {
+        let greeter = Greeter {
+            greeting: "Hello".to_string(),
+        }
+        .configurable_fields()
+        .with_field("greeting", |mut greeter, value| {
+            greeter.greeting = value.as_str().unwrap_or_default().to_string();
+            greeter
+        });
+
+        assert_eq!(greeter.run("Ana".to_string()).await.unwrap(), "Hello Ana");
+        let config = RunConfig::new().with_configurable("greeting", "Hola");
+        assert_eq!(
+            greeter
+                .run_with_config("Ana".to_string(), config)
+                .await
+                .unwrap(),
+            "Hola Ana"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_configurable_alternatives() {
+        let shout = RunnableFn::new(|input: String| async move { Ok(input.to_uppercase()) });
+        let runnable = RunnableFn::new(|input: String| async move { Ok(input) })
+            .configurable_alternatives("mode")
+            .with_alternative("shout", shout);
+
+        assert_eq!(runnable.run("hi".to_string()).await.unwrap(), "hi");
+        let config = RunConfig::new().with_configurable("mode", "shout");
+        assert_eq!(
+            runnable
+                .run_with_config("hi".to_string(), config.clone())
+                .await
+                .unwrap(),
+            "HI"
+        );
+        let config = RunConfig::new().with_configurable("mode", "whisper");
+        assert!(runnable
+            .run_with_config("hi".to_string(), config)
+            .await
+            .is_err());
+    }
+}
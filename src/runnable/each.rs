@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use futures::future::try_join_all;
+
+use crate::chain::ChainError;
+
+use super::Runnable;
+
+/// Runs a runnable on every element of a list, concurrently, built with `Runnable::map`.
+/// The outputs are in the order of the inputs, and the first error fails the whole run.
+///
+/// # Example
+/// ```rust,ignore
+/// let summaries: Vec<GenerateResult> = prompt
+///     .pipe(llm)
+///     .map()
+///     .run(vec![prompt_args! {"input" => first}, prompt_args! {"input" => second}])
+///     .await?;
+/// ```
+pub struct RunnableEach<R> {
+    runnable: R,
+}
+
+impl<R> RunnableEach<R> {
+    pub fn new(runnable: R) -> Self {
+        Self { runnable }
+    }
+}
+
+#[async_trait]
+impl<I, O, R> Runnable<Vec<I>, Vec<O>> for RunnableEach<R>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+    R: Runnable<I, O>,
+{
+    async fn run(&self, input: Vec<I>) -> Result<Vec<O>, ChainError> {
+        try_join_all(input.into_iter().map(|input| self.runnable.run(input))).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runnable::RunnableFn;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_map() {
+        let runnable = RunnableFn::new(|input: u64| async move {
+            if input == 0 {
+                return Err(ChainError::OtherError("zero".to_string()));
+            }
+            Ok(input * 2)
+        })
+        .map();
+
+        assert_eq!(runnable.run(vec![1, 2, 3]).await.unwrap(), vec![2, 4, 6]);
+        assert!(runnable.run(vec![1, 0]).await.is_err());
+    }
+}
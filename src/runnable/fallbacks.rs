@@ -0,0 +1,115 @@
+use async_trait::async_trait;
+
+use crate::chain::ChainError;
+
+use super::Runnable;
+
+type ErrorFilter = Box<dyn Fn(&ChainError) -> bool + Send + Sync>;
+
+/// A runnable trying its fallbacks, in order, when it fails. Built with
+/// `Runnable::with_fallbacks`, e.g. to fall back to another model or a more lenient
+/// parser.
+///
+/// # Example
+/// ```rust,ignore
+/// let llm = OpenAI::default()
+///     .with_fallbacks(vec![Box::new(Claude::default())])
+///     .with_error_filter(|e| matches!(e, ChainError::LLMError(_)));
+/// ```
+pub struct RunnableWithFallbacks<I, O> {
+    runnable: Box<dyn Runnable<I, O>>,
+    fallbacks: Vec<Box<dyn Runnable<I, O>>>,
+    error_filter: Option<ErrorFilter>,
+}
+
+impl<I, O> RunnableWithFallbacks<I, O>
+where
+    I: Clone + Send + Sync + 'static,
+    O: Send + 'static,
+{
+    pub fn new<R: Runnable<I, O> + 'static>(runnable: R) -> Self {
+        Self {
+            runnable: Box::new(runnable),
+            fallbacks: Vec::new(),
+            error_filter: None,
+        }
+    }
+
+    pub fn with_fallback<R: Runnable<I, O> + 'static>(mut self, fallback: R) -> Self {
+        self.fallbacks.push(Box::new(fallback));
+        self
+    }
+
+    /// Only falls back on the errors for which `filter` returns `true`, the other errors
+    /// are returned as they are.
+    pub fn with_error_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&ChainError) -> bool + Send + Sync + 'static,
+    {
+        self.error_filter = Some(Box::new(filter));
+        self
+    }
+
+    fn should_fall_back(&self, error: &ChainError) -> bool {
+        self.error_filter
+            .as_ref()
+            .map(|filter| filter(error))
+            .unwrap_or(true)
+    }
+}
+
+#[async_trait]
+impl<I, O> Runnable<I, O> for RunnableWithFallbacks<I, O>
+where
+    I: Clone + Send + Sync + 'static,
+    O: Send + 'static,
+{
+    async fn run(&self, input: I) -> Result<O, ChainError> {
+        let mut error = match self.runnable.run(input.clone()).await {
+            Ok(output) => return Ok(output),
+            Err(e) => e,
+        };
+        for fallback in &self.fallbacks {
+            if !self.should_fall_back(&error) {
+                break;
+            }
+            log::warn!("Runnable failed, trying the next fallback: {}", error);
+            error = match fallback.run(input.clone()).await {
+                Ok(output) => return Ok(output),
+                Err(e) => e,
+            };
+        }
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runnable::RunnableFn;
+
+    fn fail(error: &'static str) -> Box<dyn Runnable<String, String>> {
+        Box::new(RunnableFn::new(move |_: String| async move {
+            Err(ChainError::OtherError(error.to_string()))
+        }))
+    }
+
+    fn echo() -> Box<dyn Runnable<String, String>> {
+        Box::new(RunnableFn::new(|input: String| async move { Ok(input) }))
+    }
+
+    #[tokio::test]
+    async fn test_fallbacks() {
+        let runnable = fail("first").with_fallbacks(vec![fail("second"), echo()]);
+        assert_eq!(runnable.run("hi".to_string()).await.unwrap(), "hi");
+
+        let runnable = fail("first").with_fallbacks(vec![fail("second")]);
+        let error = runnable.run("hi".to_string()).await.unwrap_err();
+        assert_eq!(error.to_string(), "Error: second");
+
+        let runnable = fail("fatal")
+            .with_fallbacks(vec![echo()])
+            .with_error_filter(|e| !e.to_string().contains("fatal"));
+        assert!(runnable.run("hi".to_string()).await.is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use futures::TryStreamExt;
+
+use crate::{
+    chain::{Chain, ChainError},
+    language_models::{llm::LLM, GenerateResult},
+    output_parsers::{MarkdownParser, OutputParser, SimpleParser},
+    prompt::{FormatPrompter, PromptArgs, PromptInput, TypedPromptTemplate},
+    schemas::{Document, PromptValue, Retriever},
+};
+
+use super::{Runnable, RunnableStream};
+
+#[async_trait]
+impl<C: Chain + ?Sized> Runnable<PromptArgs, GenerateResult> for C {
+    async fn run(&self, input: PromptArgs) -> Result<GenerateResult, ChainError> {
+        self.call(input).await
+    }
+}
+
+#[async_trait]
+impl<P: FormatPrompter + ?Sized> Runnable<PromptArgs, PromptValue> for P {
+    async fn run(&self, input: PromptArgs) -> Result<PromptValue, ChainError> {
+        Ok(self.format_prompt_async(input).await?)
+    }
+}
+
+#[async_trait]
+impl<T: PromptInput + 'static> Runnable<T, PromptValue> for TypedPromptTemplate<T> {
+    async fn run(&self, input: T) -> Result<PromptValue, ChainError> {
+        Ok(self.format_prompt(&input)?)
+    }
+}
+
+#[async_trait]
+impl<L: LLM + ?Sized> Runnable<PromptValue, GenerateResult> for L {
+    async fn run(&self, input: PromptValue) -> Result<GenerateResult, ChainError> {
+        Ok(self.generate(&input.to_chat_messages()).await?)
+    }
+
+    /// Streams the chunks of the generation, each one as a `GenerateResult`.
+    async fn run_stream(
+        &self,
+        input: PromptValue,
+    ) -> Result<RunnableStream<GenerateResult>, ChainError> {
+        let stream = self.stream(&input.to_chat_messages()).await?;
+        Ok(Box::pin(
+            stream
+                .map_ok(|chunk| GenerateResult {
+                    generation: chunk.content,
+                    ..Default::default()
+                })
+                .map_err(ChainError::from),
+        ))
+    }
+}
+
+#[async_trait]
+impl Runnable<GenerateResult, String> for SimpleParser {
+    async fn run(&self, input: GenerateResult) -> Result<String, ChainError> {
+        Ok(self.parse(&input.generation).await?)
+    }
+}
+
+#[async_trait]
+impl Runnable<GenerateResult, String> for MarkdownParser {
+    async fn run(&self, input: GenerateResult) -> Result<String, ChainError> {
+        Ok(self.parse(&input.generation).await?)
+    }
+}
+
+#[async_trait]
+impl<O: Send + 'static> Runnable<GenerateResult, O> for dyn OutputParser<O> {
+    async fn run(&self, input: GenerateResult) -> Result<O, ChainError> {
+        Ok(self.parse(&input.generation).await?)
+    }
+}
+
+#[async_trait]
+impl<R: Retriever + ?Sized> Runnable<String, Vec<Document>> for R {
+    async fn run(&self, input: String) -> Result<Vec<Document>, ChainError> {
+        self.get_relevant_documents(&input)
+            .await
+            .map_err(|e| ChainError::RetrieverError(e.to_string()))
+    }
+}
@@ -0,0 +1,158 @@
+use std::{future::Future, pin::Pin};
+
+use async_trait::async_trait;
+use futures::Stream;
+
+use crate::{
+    chain::{Chain, ChainError},
+    language_models::GenerateResult,
+    prompt::PromptArgs,
+    schemas::StreamData,
+};
+
+use super::Runnable;
+
+/// Wraps an async function in a runnable, e.g. to adapt the output of a step to the input
+/// of the next one.
+///
+/// # Example
+/// ```rust,ignore
+/// let pipeline = retriever
+///     .pipe(RunnableFn::new(|documents: Vec<Document>| async move {
+///         Ok(prompt_args! {"context" => join_documents(&documents)})
+///     }))
+///     .pipe(chain);
+/// ```
+pub struct RunnableFn<F> {
+    function: F,
+}
+
+impl<F> RunnableFn<F> {
+    pub fn new(function: F) -> Self {
+        Self { function }
+    }
+}
+
+#[async_trait]
+impl<I, O, F, Fut> Runnable<I, O> for RunnableFn<F>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+    F: Fn(I) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<O, ChainError>> + Send,
+{
+    async fn run(&self, input: I) -> Result<O, ChainError> {
+        (self.function)(input).await
+    }
+}
+
+/// Wraps a sync function that cannot fail in a runnable, the sync variant of `RunnableFn`.
+///
+/// # Example
+/// ```rust,ignore
+/// let pipeline = RunnableLambda::new(|question: String| prompt_args! {"input" => question})
+///     .pipe(chain);
+/// ```
+pub struct RunnableLambda<F> {
+    function: F,
+}
+
+impl<F> RunnableLambda<F> {
+    pub fn new(function: F) -> Self {
+        Self { function }
+    }
+}
+
+#[async_trait]
+impl<I, O, F> Runnable<I, O> for RunnableLambda<F>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+    F: Fn(I) -> O + Send + Sync,
+{
+    async fn run(&self, input: I) -> Result<O, ChainError> {
+        Ok((self.function)(input))
+    }
+}
+
+/// Turns a runnable from `PromptArgs` to `GenerateResult`, like a pipeline or a lambda,
+/// into a `Chain`, so it can be used wherever a chain is expected.
+///
+/// # Example
+/// ```rust,ignore
+/// let chain = RunnableChain::new(prompt.pipe(llm)).with_input_keys(vec!["input".into()]);
+/// let sequential_chain = sequential_chain!(chain, summary_chain)?;
+/// ```
+pub struct RunnableChain<R> {
+    runnable: R,
+    input_keys: Vec<String>,
+}
+
+impl<R: Runnable<PromptArgs, GenerateResult>> RunnableChain<R> {
+    pub fn new(runnable: R) -> Self {
+        Self {
+            runnable,
+            input_keys: Vec::new(),
+        }
+    }
+
+    /// Input keys reported by `Chain::get_input_keys`.
+    pub fn with_input_keys(mut self, input_keys: Vec<String>) -> Self {
+        self.input_keys = input_keys;
+        self
+    }
+}
+
+#[async_trait]
+impl<R: Runnable<PromptArgs, GenerateResult>> Chain for RunnableChain<R> {
+    async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+        self.runnable.run(input_variables).await
+    }
+
+    async fn stream(
+        &self,
+        input_variables: PromptArgs,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamData, ChainError>> + Send>>, ChainError>
+    {
+        let result = self.runnable.run(input_variables).await?;
+        let data = StreamData::new(serde_json::to_value(&result)?, &result.generation);
+        Ok(Box::pin(futures::stream::once(async { Ok(data) })))
+    }
+
+    fn get_input_keys(&self) -> Vec<String> {
+        self.input_keys.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt_args;
+
+    #[tokio::test]
+    async fn test_lambda_chain() {
+        let name = RunnableLambda::new(|input: PromptArgs| {
+            input
+                .get("name")
+                .and_then(|name| name.as_str())
+                .unwrap_or_default()
+                .to_string()
+        });
+        let greet = RunnableFn::new(|name: String| async move {
+            if name.is_empty() {
+                return Err(ChainError::MissingInputVariable("name".to_string()));
+            }
+            Ok(GenerateResult {
+                generation: format!("Hello {}", name),
+                ..Default::default()
+            })
+        });
+        let chain = RunnableChain::new(name.pipe(greet));
+
+        assert_eq!(
+            chain.invoke(prompt_args! {"name" => "Ana"}).await.unwrap(),
+            "Hello Ana"
+        );
+        assert!(chain.invoke(PromptArgs::new()).await.is_err());
+    }
+}
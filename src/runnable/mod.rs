@@ -0,0 +1,25 @@
+mod runnable_trait;
+pub use runnable_trait::*;
+
+mod lambda;
+pub use lambda::*;
+
+mod each;
+pub use each::*;
+
+mod parallel;
+pub use parallel::*;
+
+mod branch;
+pub use branch::*;
+
+mod fallbacks;
+pub use fallbacks::*;
+
+mod retry;
+pub use retry::*;
+
+mod configurable;
+pub use configurable::*;
+
+mod implementations;
@@ -0,0 +1,122 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{chain::ChainError, prompt::PromptArgs};
+
+use super::Runnable;
+
+/// Erases the output type of a step of a `RunnableParallel`.
+struct SerializeOutput<R, O> {
+    runnable: R,
+    _output: PhantomData<fn() -> O>,
+}
+
+#[async_trait]
+impl<I, O, R> Runnable<I, Value> for SerializeOutput<R, O>
+where
+    I: Send + 'static,
+    O: Serialize + Send + 'static,
+    R: Runnable<I, O>,
+{
+    async fn run(&self, input: I) -> Result<Value, ChainError> {
+        Ok(serde_json::to_value(self.runnable.run(input).await?)?)
+    }
+}
+
+/// Runs several runnables concurrently on the same input, and returns their outputs by
+/// key. The output can be piped directly into a prompt.
+///
+/// # Example
+/// ```rust,ignore
+/// let pipeline = RunnableParallel::new()
+///     .with_runnable("context", retriever)
+///     .with_runnable("question", RunnableFn::new(|question: String| async { Ok(question) }))
+///     .pipe(prompt)
+///     .pipe(llm);
+///
+/// let result = pipeline.run("Where is Lima?".to_string()).await?;
+/// ```
+pub struct RunnableParallel<I> {
+    runnables: Vec<(String, Box<dyn Runnable<I, Value>>)>,
+}
+
+impl<I: Clone + Send + Sync + 'static> RunnableParallel<I> {
+    pub fn new() -> Self {
+        Self {
+            runnables: Vec::new(),
+        }
+    }
+
+    /// Adds a runnable, its output is serialized under `key`.
+    pub fn with_runnable<K, O, R>(mut self, key: K, runnable: R) -> Self
+    where
+        K: Into<String>,
+        O: Serialize + Send + 'static,
+        R: Runnable<I, O> + 'static,
+    {
+        self.runnables.push((
+            key.into(),
+            Box::new(SerializeOutput {
+                runnable,
+                _output: PhantomData,
+            }),
+        ));
+        self
+    }
+}
+
+impl<I: Clone + Send + Sync + 'static> Default for RunnableParallel<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<I: Clone + Send + Sync + 'static> Runnable<I, PromptArgs> for RunnableParallel<I> {
+    async fn run(&self, input: I) -> Result<PromptArgs, ChainError> {
+        let outputs = try_join_all(
+            self.runnables
+                .iter()
+                .map(|(_, runnable)| runnable.run(input.clone())),
+        )
+        .await?;
+        Ok(self
+            .runnables
+            .iter()
+            .map(|(key, _)| key.clone())
+            .zip(outputs)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::runnable::RunnableFn;
+
+    #[tokio::test]
+    async fn test_parallel() {
+        let parallel = RunnableParallel::new()
+            .with_runnable(
+                "upper",
+                RunnableFn::new(|input: String| async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(input.to_uppercase())
+                }),
+            )
+            .with_runnable(
+                "length",
+                RunnableFn::new(|input: String| async move { Ok(input.len()) }),
+            );
+
+        let outputs = parallel.run("hi".to_string()).await.unwrap();
+        assert_eq!(outputs["upper"], "HI");
+        assert_eq!(outputs["length"], 2);
+    }
+}
@@ -0,0 +1,178 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::{chain::ChainError, runtime};
+
+use super::Runnable;
+
+type RetryOn = Arc<dyn Fn(&ChainError) -> bool + Send + Sync>;
+
+/// How a `RunnableWithRetry` retries: up to `max_attempts` attempts, waiting
+/// exponentially longer between them.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: usize,
+    pub initial_delay: Duration,
+    pub multiplier: f64,
+    pub max_delay: Duration,
+    retry_on: Option<RetryOn>,
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(500),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(30),
+            retry_on: None,
+        }
+    }
+
+    /// Number of attempts, including the first one.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Factor the delay is multiplied by after each attempt.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Only retries the errors for which `retry_on` returns `true`, every error is
+    /// retried by default.
+    pub fn with_retry_on<F>(mut self, retry_on: F) -> Self
+    where
+        F: Fn(&ChainError) -> bool + Send + Sync + 'static,
+    {
+        self.retry_on = Some(Arc::new(retry_on));
+        self
+    }
+
+    pub fn should_retry(&self, error: &ChainError) -> bool {
+        self.retry_on
+            .as_ref()
+            .map(|retry_on| retry_on(error))
+            .unwrap_or(true)
+    }
+
+    /// Delay before the attempt following `attempt` (starting at 1).
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let factor = self.multiplier.powi(attempt.saturating_sub(1) as i32);
+        self.initial_delay.mul_f64(factor).min(self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A runnable retried according to a `RetryPolicy`, built with `Runnable::with_retry`.
+///
+/// # Example
+/// ```rust,ignore
+/// let policy = RetryPolicy::new()
+///     .with_max_attempts(5)
+///     .with_retry_on(|e| e.is_transient());
+/// let pipeline = prompt.pipe(llm).with_retry(policy);
+/// ```
+pub struct RunnableWithRetry<R> {
+    runnable: R,
+    policy: RetryPolicy,
+}
+
+impl<R> RunnableWithRetry<R> {
+    pub fn new(runnable: R, policy: RetryPolicy) -> Self {
+        Self { runnable, policy }
+    }
+}
+
+#[async_trait]
+impl<I, O, R> Runnable<I, O> for RunnableWithRetry<R>
+where
+    I: Clone + Send + Sync + 'static,
+    O: Send + 'static,
+    R: Runnable<I, O>,
+{
+    async fn run(&self, input: I) -> Result<O, ChainError> {
+        let mut attempt = 1;
+        loop {
+            match self.runnable.run(input.clone()).await {
+                Ok(output) => return Ok(output),
+                Err(e) if attempt < self.policy.max_attempts && self.policy.should_retry(&e) => {
+                    let delay = self.policy.delay(attempt);
+                    log::warn!("Attempt {} failed, retrying in {:?}: {}", attempt, delay, e);
+                    runtime::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::runnable::RunnableFn;
+
+    fn flaky(failures: usize, calls: Arc<AtomicUsize>) -> impl Runnable<String, String> {
+        RunnableFn::new(move |input: String| {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if call < failures {
+                    Err(ChainError::OtherError("transient".to_string()))
+                } else {
+                    Ok(input)
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_retry_policy_delay() {
+        let policy = RetryPolicy::new()
+            .with_initial_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(3));
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(2), Duration::from_secs(2));
+        assert_eq!(policy.delay(3), Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn test_with_retry() {
+        let policy = RetryPolicy::new().with_initial_delay(Duration::from_millis(1));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let runnable = flaky(2, calls.clone()).with_retry(policy.clone());
+        assert_eq!(runnable.run("hi".to_string()).await.unwrap(), "hi");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let runnable = flaky(3, calls.clone()).with_retry(policy.clone());
+        assert!(runnable.run("hi".to_string()).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let runnable = flaky(1, calls.clone()).with_retry(policy.with_retry_on(|_| false));
+        assert!(runnable.run("hi".to_string()).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,227 @@
+use std::{marker::PhantomData, pin::Pin};
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+
+use crate::{callbacks::RunConfig, chain::ChainError};
+
+use super::{
+    ConfigurableAlternatives, ConfigurableFields, RetryPolicy, RunnableEach, RunnableWithFallbacks,
+    RunnableWithRetry,
+};
+
+/// The stream of outputs of `Runnable::run_stream`.
+pub type RunnableStream<O> = Pin<Box<dyn Stream<Item = Result<O, ChainError>> + Send>>;
+
+/// A step of a pipeline, turning an `I` into an `O`.
+///
+/// Chains, prompts, LLMs, output parsers and retrievers are runnables, and they can be
+/// composed with `pipe`, `map`, `RunnableBranch` and `RunnableParallel`. The output of
+/// each step has to be the input of the next one, which is checked at compile time.
+///
+/// # Example
+/// ```rust,ignore
+/// let pipeline = PromptTemplate::new(template, vec!["input".into()], TemplateFormat::FString)
+///     .pipe(OpenAI::default())
+///     .pipe(SimpleParser::new().with_trim(true));
+///
+/// let output: String = pipeline.run(prompt_args! {"input" => "Hi"}).await?;
+/// ```
+#[async_trait]
+pub trait Runnable<I: Send + 'static, O: Send + 'static>: Send + Sync {
+    async fn run(&self, input: I) -> Result<O, ChainError>;
+
+    /// Runs with `config`: its run id, tags and metadata are surfaced to the callbacks, and
+    /// its configurable values select the configurable fields and alternatives.
+    async fn run_with_config(&self, input: I, config: RunConfig) -> Result<O, ChainError> {
+        config.scope(self.run(input)).await
+    }
+
+    /// Runs and streams the outputs. By default the stream has the single output of `run`;
+    /// LLMs stream their chunks, and pipelines stream the output of their last step.
+    async fn run_stream(&self, input: I) -> Result<RunnableStream<O>, ChainError> {
+        let output = self.run(input).await;
+        Ok(Box::pin(futures::stream::once(async { output })))
+    }
+
+    /// Runs on every input, up to `max_concurrency` at the same time. The results are in
+    /// the order of the inputs.
+    async fn batch(&self, inputs: Vec<I>, max_concurrency: usize) -> Vec<Result<O, ChainError>> {
+        futures::stream::iter(inputs)
+            .map(|input| self.run(input))
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Returns a runnable passing the output of `self` to `next`.
+    fn pipe<R>(self, next: R) -> RunnableSequence<Self, R, O>
+    where
+        Self: Sized,
+    {
+        RunnableSequence::new(self, next)
+    }
+
+    /// Returns a runnable running `self` on every element of a list, see `RunnableEach`.
+    fn map(self) -> RunnableEach<Self>
+    where
+        Self: Sized,
+    {
+        RunnableEach::new(self)
+    }
+
+    /// Returns a runnable trying `fallbacks`, in order, when `self` fails. Use
+    /// `RunnableWithFallbacks::with_error_filter` to only fall back on some errors.
+    fn with_fallbacks(self, fallbacks: Vec<Box<dyn Runnable<I, O>>>) -> RunnableWithFallbacks<I, O>
+    where
+        Self: Sized + 'static,
+        I: Clone + Sync,
+    {
+        fallbacks
+            .into_iter()
+            .fold(RunnableWithFallbacks::new(self), |runnable, fallback| {
+                runnable.with_fallback(fallback)
+            })
+    }
+
+    /// Returns a runnable whose fields can be set per invocation, see `ConfigurableFields`.
+    fn configurable_fields(self) -> ConfigurableFields<Self>
+    where
+        Self: Sized + Clone,
+    {
+        ConfigurableFields::new(self)
+    }
+
+    /// Returns a runnable that can be replaced per invocation by the alternative selected
+    /// with the configurable value `key`, see `ConfigurableAlternatives`.
+    fn configurable_alternatives<S: Into<String>>(self, key: S) -> ConfigurableAlternatives<I, O>
+    where
+        Self: Sized + 'static,
+    {
+        ConfigurableAlternatives::new(key, self)
+    }
+
+    /// Returns a runnable retrying `self` according to `policy`.
+    fn with_retry(self, policy: RetryPolicy) -> RunnableWithRetry<Self>
+    where
+        Self: Sized,
+    {
+        RunnableWithRetry::new(self, policy)
+    }
+}
+
+#[async_trait]
+impl<I, O> Runnable<I, O> for Box<dyn Runnable<I, O>>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+{
+    async fn run(&self, input: I) -> Result<O, ChainError> {
+        self.as_ref().run(input).await
+    }
+
+    async fn run_stream(&self, input: I) -> Result<RunnableStream<O>, ChainError> {
+        self.as_ref().run_stream(input).await
+    }
+}
+
+/// Two runnables executed one after the other, built with `Runnable::pipe`.
+pub struct RunnableSequence<A, B, M> {
+    first: A,
+    second: B,
+    // `fn() -> M` keeps the sequence `Send` and `Sync` whatever `M` is.
+    _intermediate: PhantomData<fn() -> M>,
+}
+
+impl<A, B, M> RunnableSequence<A, B, M> {
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            _intermediate: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<I, M, O, A, B> Runnable<I, O> for RunnableSequence<A, B, M>
+where
+    I: Send + 'static,
+    M: Send + 'static,
+    O: Send + 'static,
+    A: Runnable<I, M>,
+    B: Runnable<M, O>,
+{
+    async fn run(&self, input: I) -> Result<O, ChainError> {
+        let intermediate = self.first.run(input).await?;
+        self.second.run(intermediate).await
+    }
+
+    async fn run_stream(&self, input: I) -> Result<RunnableStream<O>, ChainError> {
+        let intermediate = self.first.run(input).await?;
+        self.second.run_stream(intermediate).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        language_models::GenerateResult, llm::FakeLLM, output_parsers::SimpleParser, prompt_args,
+        runnable::RunnableFn, schemas::PromptValue, template_fstring,
+    };
+
+    #[tokio::test]
+    async fn test_pipe() {
+        let pipeline = template_fstring!("Say {input} ", "input")
+            .pipe(RunnableFn::new(|prompt: PromptValue| async move {
+                Ok(GenerateResult {
+                    generation: prompt.to_chat_messages()[0].content.text().to_uppercase(),
+                    ..Default::default()
+                })
+            }))
+            .pipe(SimpleParser::new().with_trim(true));
+
+        let output = pipeline.run(prompt_args! {"input" => "hi"}).await.unwrap();
+        assert_eq!(output, "SAY HI");
+    }
+
+    #[tokio::test]
+    async fn test_batch() {
+        let runnable = RunnableFn::new(|delay: u64| async move {
+            tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+            if delay == 0 {
+                return Err(ChainError::OtherError("no delay".to_string()));
+            }
+            Ok(delay)
+        });
+
+        let results = runnable.batch(vec![30, 0, 10], 2).await;
+        assert_eq!(results[0].as_ref().unwrap(), &30);
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap(), &10);
+    }
+
+    #[tokio::test]
+    async fn test_run_stream() {
+        let pipeline = template_fstring!("Say {input}", "input").pipe(FakeLLM::echo());
+        let chunks: Vec<String> = pipeline
+            .run_stream(prompt_args! {"input" => "hi"})
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap().generation)
+            .collect()
+            .await;
+        assert_eq!(chunks, vec!["Say ", "hi"]);
+
+        // Runnables that don't stream yield their single output.
+        let chunks: Vec<u64> = RunnableFn::new(|input: u64| async move { Ok(input + 1) })
+            .run_stream(1)
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks, vec![2]);
+    }
+}
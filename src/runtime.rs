@@ -0,0 +1,86 @@
+//! The parts of the async runtime that differ on wasm32. There the futures of the browser
+//! aren't `Send`, and tokio has no timers, clock or tasks: they come from the JavaScript
+//! host instead.
+
+use std::{future::Future, time::SystemTime};
+
+use futures::Stream;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use tokio::time::{sleep, sleep_until, Instant};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use wasm::{sleep, sleep_until, Instant};
+
+/// The future, `Send` on wasm32 too, where it can only be polled by the thread creating it,
+/// the only one.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn send<F: Future>(future: F) -> F {
+    future
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn send<F: Future>(future: F) -> send_wrapper::SendWrapper<F> {
+    send_wrapper::SendWrapper::new(future)
+}
+
+/// The stream, `Send` on wasm32 too, see `send`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn send_stream<S: Stream>(stream: S) -> S {
+    stream
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn send_stream<S: Stream>(stream: S) -> send_wrapper::SendWrapper<S> {
+    send_wrapper::SendWrapper::new(stream)
+}
+
+/// Runs the future in the background, see `can_spawn`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn spawn<F: Future<Output = ()> + Send + 'static>(future: F) {
+    tokio::spawn(future);
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn<F: Future<Output = ()> + 'static>(future: F) {
+    wasm_bindgen_futures::spawn_local(future);
+}
+
+/// Whether `spawn` has a runtime to run the futures: a tokio runtime on the current thread,
+/// the event loop of the JavaScript host on wasm32.
+pub(crate) fn can_spawn() -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        tokio::runtime::Handle::try_current().is_ok()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        true
+    }
+}
+
+/// The current time, which `SystemTime::now` panics reading on wasm32.
+pub(crate) fn now() -> SystemTime {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        SystemTime::now()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use std::time::Duration;
+
+    pub(crate) use web_time::Instant;
+
+    pub(crate) async fn sleep(duration: Duration) {
+        super::send(gloo_timers::future::sleep(duration)).await
+    }
+
+    pub(crate) async fn sleep_until(deadline: Instant) {
+        sleep(deadline.saturating_duration_since(Instant::now())).await
+    }
+}
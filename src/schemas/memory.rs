@@ -1,8 +1,77 @@
+use std::any::Any;
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use crate::prompt::PromptArgs;
+
 use super::messages::Message;
 
+/// The input variable of the chat history in the prompts of the chains with memory.
+pub const DEFAULT_MEMORY_KEY: &str = "history";
+
+/// The human message of a turn: the `input` variable, or the only text variable other
+/// than the history.
+pub fn memory_input<'a>(inputs: &'a PromptArgs, memory_key: &str) -> Option<&'a str> {
+    inputs.get("input").and_then(Value::as_str).or_else(|| {
+        let mut texts = inputs
+            .iter()
+            .filter(|(key, _)| *key != memory_key)
+            .filter_map(|(_, value)| value.as_str());
+        match (texts.next(), texts.next()) {
+            (Some(text), None) => Some(text),
+            _ => None,
+        }
+    })
+}
+
+#[async_trait]
 pub trait BaseMemory: Send + Sync {
     fn messages(&self) -> Vec<Message>;
 
+    /// The input variable the history is loaded in, `history` by default.
+    fn memory_key(&self) -> String {
+        DEFAULT_MEMORY_KEY.to_string()
+    }
+
+    /// The variables added to the inputs of a chain before formatting its prompt: the
+    /// history, as text, under `memory_key`.
+    fn load_memory_variables(&self) -> PromptArgs {
+        PromptArgs::from([(self.memory_key(), json!(self.to_string()))])
+    }
+
+    /// Saves a turn of the conversation once a chain has answered, see `memory_input`.
+    fn save_context(&mut self, inputs: &PromptArgs, output: &str) {
+        match memory_input(inputs, &self.memory_key()) {
+            Some(input) => self.add_user_message(&input),
+            None => log::warn!("No input variable to save in the memory"),
+        }
+        self.add_ai_message(&output);
+    }
+
+    /// Saves a turn like `save_context`, then runs the `prepare_update` of the memory, e.g.
+    /// summarizing the conversation. The chains sharing the memory behind a mutex save with
+    /// `save_memory_context` instead, which doesn't hold the lock during the update.
+    async fn save_context_async(&mut self, inputs: &PromptArgs, output: &str) {
+        self.save_context(inputs, output);
+        if let Some(update) = self.prepare_update() {
+            if let Some(update) = update.await {
+                self.apply_update(update);
+            }
+        }
+    }
+
+    /// The work to do once a turn is saved which doesn't need the memory, e.g. summarizing
+    /// the older turns with an LLM. Its result is given to `apply_update`.
+    fn prepare_update(&self) -> Option<BoxFuture<'static, Option<MemoryUpdate>>> {
+        None
+    }
+
+    /// Applies the result of `prepare_update`, possibly after other turns were saved.
+    fn apply_update(&mut self, _update: MemoryUpdate) {}
+
     // Use a trait object for Display instead of a generic type
     fn add_user_message(&mut self, message: &dyn std::fmt::Display) {
         // Convert the Display trait object to a String and pass it to the constructor
@@ -28,6 +97,39 @@ pub trait BaseMemory: Send + Sync {
     }
 }
 
+/// The inputs of a turn of a chain whose human message is the `input_key` variable, with
+/// that message as `input` too, see `memory_input`.
+pub(crate) fn turn_inputs(inputs: &PromptArgs, input_key: &str) -> PromptArgs {
+    let mut inputs = inputs.clone();
+    if let Some(input) = inputs.get(input_key).cloned() {
+        inputs.entry("input".to_string()).or_insert(input);
+    }
+    inputs
+}
+
+/// The result of `BaseMemory::prepare_update`, only read by the memory which made it.
+pub type MemoryUpdate = Box<dyn Any + Send>;
+
+/// Saves a turn in a memory shared by the chains, like `save_context_async`, but without
+/// holding the lock during `prepare_update`, so the other chains aren't blocked while it
+/// calls a model.
+pub async fn save_memory_context(
+    memory: &Mutex<dyn BaseMemory>,
+    inputs: &PromptArgs,
+    output: &str,
+) {
+    let update = {
+        let mut memory = memory.lock().await;
+        memory.save_context(inputs, output);
+        memory.prepare_update()
+    };
+    if let Some(update) = update {
+        if let Some(update) = update.await {
+            memory.lock().await.apply_update(update);
+        }
+    }
+}
+
 impl<M> From<M> for Box<dyn BaseMemory>
 where
     M: BaseMemory + 'static,
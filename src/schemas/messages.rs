@@ -40,6 +40,160 @@ impl MessageType {
     }
 }
 
+/// The detail level of an image, for the providers supporting it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageDetail {
+    Auto,
+    Low,
+    High,
+}
+
+/// A part of the content of a multimodal message.
+///
+/// # Usage
+/// ```rust,ignore
+/// let message = Message::new_human_message_with_parts(vec![
+///     ContentPart::text("What is in this image?"),
+///     ContentPart::image_url("https://example.com/cat.png"),
+/// ]);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text {
+        text: String,
+    },
+    /// An image by url, or by a `data:` url with its base64 encoded data.
+    Image {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<ImageDetail>,
+    },
+    /// Base64 encoded audio in `format`, e.g. `wav` or `mp3`.
+    Audio {
+        data: String,
+        format: String,
+    },
+    ToolCall {
+        id: String,
+        name: String,
+        arguments: Value,
+    },
+    ToolResult {
+        tool_call_id: String,
+        content: String,
+    },
+}
+
+impl ContentPart {
+    pub fn text<S: Into<String>>(text: S) -> Self {
+        ContentPart::Text { text: text.into() }
+    }
+
+    pub fn image_url<S: Into<String>>(url: S) -> Self {
+        ContentPart::Image {
+            url: url.into(),
+            detail: None,
+        }
+    }
+
+    /// An image from its data, as a `data:` url.
+    pub fn image_base64<M: std::fmt::Display, D: std::fmt::Display>(
+        media_type: M,
+        data: D,
+    ) -> Self {
+        Self::image_url(format!("data:{};base64,{}", media_type, data))
+    }
+}
+
+/// The content of a message: a text, or the parts of a multimodal message. Serialized
+/// as a string or as an array of parts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl MessageContent {
+    /// The text of the content, joining the text parts with new lines.
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// The text, if the content is only a text.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            MessageContent::Text(text) => Some(text),
+            MessageContent::Parts(_) => None,
+        }
+    }
+
+    pub fn parts(&self) -> Vec<ContentPart> {
+        match self {
+            MessageContent::Text(text) => vec![ContentPart::text(text.clone())],
+            MessageContent::Parts(parts) => parts.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            MessageContent::Text(text) => text.is_empty(),
+            MessageContent::Parts(parts) => parts.is_empty(),
+        }
+    }
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        MessageContent::Text(String::new())
+    }
+}
+
+impl std::fmt::Display for MessageContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MessageContent::Text(text) => write!(f, "{}", text),
+            MessageContent::Parts(_) => write!(f, "{}", self.text()),
+        }
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        MessageContent::Parts(parts)
+    }
+}
+
+impl PartialEq<&str> for MessageContent {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_text() == Some(*other)
+    }
+}
+
 /// Struct `Message` represents a message with its content and type.
 ///
 /// # Usage
@@ -50,7 +204,7 @@ impl MessageType {
 /// ```
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Message {
-    pub content: String,
+    pub content: MessageContent,
     pub message_type: MessageType,
     pub id: Option<String>,
     pub tool_calls: Option<Value>,
@@ -60,7 +214,7 @@ impl Message {
     // Function to create a new Human message with a generic type that implements Display
     pub fn new_human_message<T: std::fmt::Display>(content: T) -> Self {
         Message {
-            content: content.to_string(),
+            content: MessageContent::Text(content.to_string()),
             message_type: MessageType::HumanMessage,
             id: None,
             tool_calls: None,
@@ -70,7 +224,7 @@ impl Message {
     // Function to create a new System message with a generic type that implements Display
     pub fn new_system_message<T: std::fmt::Display>(content: T) -> Self {
         Message {
-            content: content.to_string(),
+            content: MessageContent::Text(content.to_string()),
             message_type: MessageType::SystemMessage,
             id: None,
             tool_calls: None,
@@ -80,17 +234,27 @@ impl Message {
     // Function to create a new AI message with a generic type that implements Display
     pub fn new_ai_message<T: std::fmt::Display>(content: T) -> Self {
         Message {
-            content: content.to_string(),
+            content: MessageContent::Text(content.to_string()),
             message_type: MessageType::AIMessage,
             id: None,
             tool_calls: None,
         }
     }
 
+    /// Creates a multimodal Human message, e.g. with a text and images.
+    pub fn new_human_message_with_parts(parts: Vec<ContentPart>) -> Self {
+        Message {
+            content: MessageContent::Parts(parts),
+            message_type: MessageType::HumanMessage,
+            id: None,
+            tool_calls: None,
+        }
+    }
+
     // Function to create a new Tool message with a generic type that implements Display
     pub fn new_tool_message<T: std::fmt::Display, S: Into<String>>(content: T, id: S) -> Self {
         Message {
-            content: content.to_string(),
+            content: MessageContent::Text(content.to_string()),
             message_type: MessageType::ToolMessage,
             id: Some(id.into()),
             tool_calls: None,
@@ -123,3 +287,38 @@ impl Message {
             .join("\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_message_content_serde() {
+        let message: Message = serde_json::from_value(json!({
+            "content": "Hello",
+            "message_type": "human",
+            "id": null,
+            "tool_calls": null,
+        }))
+        .unwrap();
+        assert_eq!(message.content, "Hello");
+
+        let message = Message::new_human_message_with_parts(vec![
+            ContentPart::text("What is in this image?"),
+            ContentPart::image_base64("image/png", "aGVsbG8="),
+        ]);
+        let value = serde_json::to_value(&message).unwrap();
+        assert_eq!(
+            value["content"],
+            json!([
+                {"type": "text", "text": "What is in this image?"},
+                {"type": "image", "url": "data:image/png;base64,aGVsbG8="},
+            ])
+        );
+        let message: Message = serde_json::from_value(value).unwrap();
+        assert_eq!(message.content.text(), "What is in this image?");
+        assert_eq!(message.content.parts().len(), 2);
+    }
+}
@@ -21,3 +21,12 @@ pub use tools_openai_like::*;
 
 mod stream;
 pub use stream::*;
+
+mod response_format;
+pub use response_format::*;
+
+mod trim_messages;
+pub use trim_messages::*;
+
+mod wire_format;
+pub(crate) use wire_format::anthropic_block;
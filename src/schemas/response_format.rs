@@ -0,0 +1,176 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// The format of the answers of the model, with the native JSON modes of the providers
+/// supporting them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    Text,
+    /// Any JSON object. The prompt must still ask for JSON.
+    JsonObject,
+    /// A JSON following `schema`. When `strict`, the model can only generate JSON valid
+    /// against the schema, which must then have all of its properties required and no
+    /// additional properties.
+    JsonSchema {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        schema: Value,
+        strict: bool,
+    },
+}
+
+impl ResponseFormat {
+    /// A JSON following `schema`, in strict mode.
+    pub fn json_schema<S: Into<String>>(name: S, schema: Value) -> Self {
+        ResponseFormat::JsonSchema {
+            name: name.into(),
+            description: None,
+            schema,
+            strict: true,
+        }
+    }
+
+    /// A JSON of `T`, in strict mode. The schema of `T` is made strict: the optional
+    /// fields are required and nullable.
+    pub fn from_type<T: JsonSchema>() -> Self {
+        let name: String = T::schema_name()
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => c,
+                _ => '_',
+            })
+            .collect();
+        let mut schema = schemars::schema_for!(T).to_value();
+        if let Some(schema) = schema.as_object_mut() {
+            schema.remove("$schema");
+        }
+        Self::json_schema(name, strict_schema(schema))
+    }
+
+    /// The `response_format` of the OpenAI chat completions API.
+    pub fn to_openai(&self) -> Value {
+        match self {
+            ResponseFormat::Text => json!({ "type": "text" }),
+            ResponseFormat::JsonObject => json!({ "type": "json_object" }),
+            ResponseFormat::JsonSchema {
+                name,
+                description,
+                schema,
+                strict,
+            } => {
+                let mut json_schema = json!({
+                    "name": name,
+                    "schema": schema,
+                    "strict": strict,
+                });
+                if let Some(description) = description {
+                    json_schema["description"] = Value::from(description.as_str());
+                }
+                json!({ "type": "json_schema", "json_schema": json_schema })
+            }
+        }
+    }
+}
+
+/// Requires all the properties of the objects of the schema and forbids the other ones,
+/// as the strict mode of OpenAI does. The `format` of the numbers generated by schemars,
+/// e.g. `uint32`, is removed, not being a JSON schema format.
+fn strict_schema(schema: Value) -> Value {
+    match schema {
+        Value::Object(object) => {
+            let mut object: Map<String, Value> = object
+                .into_iter()
+                .map(|(key, value)| (key, strict_schema(value)))
+                .collect();
+            if let Some(Value::Object(properties)) = object.get("properties") {
+                // Sorted, the order of the properties depending on serde_json's features.
+                let mut required: Vec<&String> = properties.keys().collect();
+                required.sort();
+                let required: Vec<Value> = required.into_iter().cloned().map(Value::from).collect();
+                object.insert("required".to_string(), Value::from(required));
+                object.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+            let is_number = |t: &Value| t == "integer" || t == "number";
+            let number_type = match object.get("type") {
+                Some(Value::Array(types)) => types.iter().any(is_number),
+                Some(t) => is_number(t),
+                None => false,
+            };
+            if number_type {
+                object.remove("format");
+            }
+            Value::Object(object)
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(strict_schema).collect()),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use schemars::{json_schema, Schema, SchemaGenerator};
+
+    use super::*;
+
+    struct Person;
+
+    impl JsonSchema for Person {
+        fn schema_name() -> Cow<'static, str> {
+            "Person".into()
+        }
+
+        fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+            json_schema!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "age": {"type": "integer", "format": "uint32", "minimum": 0},
+                    "nickname": {"type": ["string", "null"]}
+                },
+                "required": ["name", "age"]
+            })
+        }
+    }
+
+    #[test]
+    fn test_response_format_from_type() {
+        let format = ResponseFormat::from_type::<Person>().to_openai();
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["json_schema"]["name"], "Person");
+        assert_eq!(format["json_schema"]["strict"], true);
+        let schema = &format["json_schema"]["schema"];
+        assert_eq!(schema["required"], json!(["age", "name", "nickname"]));
+        assert_eq!(schema["additionalProperties"], false);
+        assert!(schema["properties"]["age"].get("format").is_none());
+        assert!(schema.get("$schema").is_none());
+
+        assert_eq!(
+            ResponseFormat::JsonObject.to_openai(),
+            json!({ "type": "json_object" })
+        );
+    }
+
+    #[test]
+    fn test_response_format_serde() {
+        let format = ResponseFormat::json_schema("answer", json!({"type": "object"}));
+        let value = serde_json::to_value(&format).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "type": "json_schema",
+                "name": "answer",
+                "schema": {"type": "object"},
+                "strict": true
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<ResponseFormat>(value).unwrap(),
+            format
+        );
+    }
+}
@@ -1,19 +1,79 @@
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::io::{self, Write};
 
+/// What a `StreamData` is. The streams of composed chains and agents interleave the
+/// tokens of the generation with events of their progress, which have an empty content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum StreamKind {
+    /// A token delta of the generation.
+    #[default]
+    Token,
+    /// A step starts, `value` has its `step` name.
+    StepStart,
+    /// A step ends, `value` has its `step` name and its `output`.
+    StepEnd,
+    /// A tool is called, `value` has the `tool` name and its `input`.
+    ToolStart,
+    /// A tool returns, `value` has the `tool` name and its `output`.
+    ToolEnd,
+}
+
+/// A chunk of a stream, built with `StreamData::new` for the tokens or with the
+/// constructors of the events.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct StreamData {
     pub value: Value,
     pub content: String,
+    pub kind: StreamKind,
 }
 impl StreamData {
     pub fn new<S: Into<String>>(value: Value, content: S) -> Self {
         Self {
             value,
             content: content.into(),
+            kind: StreamKind::Token,
         }
     }
 
+    fn event(kind: StreamKind, value: Value) -> Self {
+        Self {
+            value,
+            content: String::new(),
+            kind,
+        }
+    }
+
+    pub fn step_start<S: Into<String>>(step: S) -> Self {
+        Self::event(StreamKind::StepStart, json!({ "step": step.into() }))
+    }
+
+    pub fn step_end<S: Into<String>, O: Into<String>>(step: S, output: O) -> Self {
+        Self::event(
+            StreamKind::StepEnd,
+            json!({ "step": step.into(), "output": output.into() }),
+        )
+    }
+
+    pub fn tool_start<S: Into<String>, I: Into<String>>(tool: S, input: I) -> Self {
+        Self::event(
+            StreamKind::ToolStart,
+            json!({ "tool": tool.into(), "input": input.into() }),
+        )
+    }
+
+    pub fn tool_end<S: Into<String>, O: Into<String>>(tool: S, output: O) -> Self {
+        Self::event(
+            StreamKind::ToolEnd,
+            json!({ "tool": tool.into(), "output": output.into() }),
+        )
+    }
+
+    pub fn is_token(&self) -> bool {
+        self.kind == StreamKind::Token
+    }
+
     pub fn to_stdout(&self) -> io::Result<()> {
         let stdout = io::stdout();
         let mut handle = stdout.lock();
@@ -40,7 +40,49 @@ impl FunctionDefinition {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A call of a tool asked by the model, returned in the `tool_calls` of the `GenerateResult`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// The arguments of the call, as the JSON generated by the model. The arguments that
+    /// aren't valid JSON are kept as a string.
+    pub arguments: Value,
+}
+
+impl ToolCall {
+    pub fn new<S: Into<String>>(id: S, name: S, arguments: &str) -> Self {
+        ToolCall {
+            id: id.into(),
+            name: name.into(),
+            arguments: serde_json::from_str(arguments)
+                .unwrap_or_else(|_| Value::String(arguments.to_string())),
+        }
+    }
+
+    /// The arguments as the string given to the tool.
+    pub fn arguments_string(&self) -> String {
+        match &self.arguments {
+            Value::String(arguments) => arguments.clone(),
+            arguments => arguments.to_string(),
+        }
+    }
+}
+
+impl From<&ToolCall> for FunctionCallResponse {
+    fn from(tool_call: &ToolCall) -> Self {
+        FunctionCallResponse {
+            id: tool_call.id.clone(),
+            type_field: "function".to_string(),
+            function: FunctionDetail {
+                name: tool_call.name.clone(),
+                arguments: tool_call.arguments_string(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FunctionCallResponse {
     pub id: String,
     #[serde(rename = "type")]
@@ -48,7 +90,7 @@ pub struct FunctionCallResponse {
     pub function: FunctionDetail,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FunctionDetail {
     pub name: String,
     ///this should be an string, and this should be passed to the tool, to
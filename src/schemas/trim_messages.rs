@@ -0,0 +1,331 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+    chain::{Chain, ChainError, LLMChain, LLMChainBuilder},
+    language_models::llm::LLM,
+    prompt_args, template_jinja2,
+};
+
+use super::{Message, MessageType};
+
+/// Counts the tokens of a message.
+pub type TokenCounter = Arc<dyn Fn(&Message) -> usize + Send + Sync>;
+
+// The tokens OpenAI adds to every message for its role and delimiters.
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+const DEFAULT_SUMMARY_MAX_TOKENS: usize = 256;
+
+const DEFAULT_SUMMARY_TEMPLATE: &str = r#"Summarize the following conversation in less than {{max_words}} words, keeping the facts needed to continue it.
+
+{{messages}}
+
+Summary:"#;
+
+/// Counts the tokens of the content of a message with the `cl100k_base` encoding of the
+/// OpenAI models, plus the tokens of its role.
+pub fn count_message_tokens(message: &Message) -> usize {
+    let bpe = tiktoken_rs::cl100k_base_singleton();
+    let tokens = bpe
+        .lock()
+        .encode_with_special_tokens(&message.content.text())
+        .len();
+    tokens + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Which messages `MessageTrimmer` keeps within the token budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrimStrategy {
+    /// The most recent messages fitting in the budget.
+    Last,
+    /// At most the `n` most recent messages fitting in the budget.
+    LastN(usize),
+    /// The `first` oldest messages, e.g. the instructions of a task, then the most recent
+    /// messages fitting in the remaining budget.
+    FirstLast { first: usize },
+}
+
+/// Trims chat histories to a token budget, e.g. before sending them to a model with a
+/// small context window.
+///
+/// The system messages are kept by default, and counted in the budget. The trimmed
+/// history never starts with tool messages without the ai message calling them. With a
+/// summarizer the dropped messages can be replaced by a summary, see `trim_with_summary`.
+///
+/// # Example
+/// ```rust,ignore
+/// let trimmer = MessageTrimmer::new(1000)
+///     .with_strategy(TrimStrategy::FirstLast { first: 1 })
+///     .with_summarizer(OpenAI::default());
+///
+/// let messages = trimmer.trim(&history);
+/// let messages = trimmer.trim_with_summary(&history).await?;
+/// ```
+#[derive(Clone)]
+pub struct MessageTrimmer {
+    max_tokens: usize,
+    strategy: TrimStrategy,
+    keep_system: bool,
+    token_counter: TokenCounter,
+    summarizer: Option<Arc<LLMChain>>,
+    summary_max_tokens: usize,
+}
+
+impl MessageTrimmer {
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            strategy: TrimStrategy::Last,
+            keep_system: true,
+            token_counter: Arc::new(count_message_tokens),
+            summarizer: None,
+            summary_max_tokens: DEFAULT_SUMMARY_MAX_TOKENS,
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: TrimStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Whether the system messages are always kept, `true` by default. Otherwise they are
+    /// trimmed like the other messages.
+    pub fn with_keep_system(mut self, keep_system: bool) -> Self {
+        self.keep_system = keep_system;
+        self
+    }
+
+    /// Counts the tokens with `token_counter`, e.g. the tokenizer of another model.
+    pub fn with_token_counter<F: Fn(&Message) -> usize + Send + Sync + 'static>(
+        mut self,
+        token_counter: F,
+    ) -> Self {
+        self.token_counter = Arc::new(token_counter);
+        self
+    }
+
+    /// The LLM summarizing the dropped messages in `trim_with_summary`.
+    pub fn with_summarizer<L: Into<Box<dyn LLM>>>(mut self, llm: L) -> Self {
+        let chain = LLMChainBuilder::new()
+            .llm(llm)
+            .prompt(template_jinja2!(
+                DEFAULT_SUMMARY_TEMPLATE,
+                "max_words",
+                "messages"
+            ))
+            .build()
+            .unwrap(); //Its safe to unwrap here because we are sure that the prompt and the LLM are
+                       //set.
+        self.summarizer = Some(Arc::new(chain));
+        self
+    }
+
+    /// The tokens reserved in the budget for the summary, 256 by default.
+    pub fn with_summary_max_tokens(mut self, summary_max_tokens: usize) -> Self {
+        self.summary_max_tokens = summary_max_tokens;
+        self
+    }
+
+    pub fn count_tokens(&self, messages: &[Message]) -> usize {
+        messages
+            .iter()
+            .map(|message| (self.token_counter)(message))
+            .sum()
+    }
+
+    /// The messages kept within the budget, in their order.
+    pub fn trim(&self, messages: &[Message]) -> Vec<Message> {
+        let kept = self.kept(messages, self.max_tokens);
+        messages
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| kept.contains(i))
+            .map(|(_, message)| message.clone())
+            .collect()
+    }
+
+    /// Trims the messages, replacing the dropped ones by a system message with their
+    /// summary, placed after the kept system messages. Requires a summarizer.
+    pub async fn trim_with_summary(
+        &self,
+        messages: &[Message],
+    ) -> Result<Vec<Message>, ChainError> {
+        let summarizer = self.summarizer.as_ref().ok_or(ChainError::MissingObject(
+            "Summarizer is required, set it with with_summarizer".into(),
+        ))?;
+        if self.count_tokens(messages) <= self.max_tokens {
+            return Ok(messages.to_vec());
+        }
+
+        let kept = self.kept(
+            messages,
+            self.max_tokens.saturating_sub(self.summary_max_tokens),
+        );
+        let (kept, dropped): (Vec<_>, Vec<_>) = messages
+            .iter()
+            .enumerate()
+            .partition(|(i, _)| kept.contains(i));
+        let dropped: Vec<Message> = dropped
+            .into_iter()
+            .map(|(_, message)| message.clone())
+            .collect();
+
+        let summary = summarizer
+            .invoke(prompt_args! {
+                // Roughly 3 words every 4 tokens.
+                "max_words" => self.summary_max_tokens * 3 / 4,
+                "messages" => Message::messages_to_string(&dropped),
+            })
+            .await?;
+        let summary = Message::new_system_message(format!(
+            "Summary of the earlier conversation: {}",
+            summary.trim()
+        ));
+
+        let (system, rest): (Vec<_>, Vec<_>) = kept
+            .into_iter()
+            .map(|(_, message)| message.clone())
+            .partition(|message| self.is_kept_system(message));
+        Ok(system
+            .into_iter()
+            .chain(std::iter::once(summary))
+            .chain(rest)
+            .collect())
+    }
+
+    fn is_kept_system(&self, message: &Message) -> bool {
+        self.keep_system && matches!(message.message_type, MessageType::SystemMessage)
+    }
+
+    /// The indices of the messages kept within `max_tokens`.
+    fn kept(&self, messages: &[Message], max_tokens: usize) -> HashSet<usize> {
+        let tokens: Vec<usize> = messages
+            .iter()
+            .map(|message| (self.token_counter)(message))
+            .collect();
+        let (system, rest): (Vec<usize>, Vec<usize>) =
+            (0..messages.len()).partition(|&i| self.is_kept_system(&messages[i]));
+
+        let mut budget = max_tokens.saturating_sub(system.iter().map(|&i| tokens[i]).sum());
+        let mut kept: HashSet<usize> = system.into_iter().collect();
+        let (head, tail) = match self.strategy {
+            TrimStrategy::Last => (&rest[..0], &rest[..]),
+            TrimStrategy::LastN(n) => (&rest[..0], &rest[rest.len().saturating_sub(n)..]),
+            TrimStrategy::FirstLast { first } => rest.split_at(first.min(rest.len())),
+        };
+
+        for &i in head {
+            if tokens[i] > budget {
+                break;
+            }
+            budget -= tokens[i];
+            kept.insert(i);
+        }
+
+        let mut start = tail.len();
+        while start > 0 && tokens[tail[start - 1]] <= budget {
+            start -= 1;
+            budget -= tokens[tail[start]];
+        }
+        // A tool message can't be sent without the ai message calling the tool.
+        let tail = tail[start..]
+            .iter()
+            .skip_while(|&&i| matches!(messages[i].message_type, MessageType::ToolMessage));
+        kept.extend(tail);
+        kept
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::openai::OpenAI;
+
+    fn history() -> Vec<Message> {
+        vec![
+            Message::new_system_message("system"),
+            Message::new_human_message("h1"),
+            Message::new_ai_message("a1"),
+            Message::new_human_message("h2"),
+            Message::new_ai_message("a2"),
+            Message::new_tool_message("t2", "call_1"),
+            Message::new_human_message("h3"),
+        ]
+    }
+
+    fn contents(messages: &[Message]) -> Vec<String> {
+        messages
+            .iter()
+            .map(|message| message.content.text())
+            .collect()
+    }
+
+    /// Every message counts as one token.
+    fn trimmer(max_tokens: usize) -> MessageTrimmer {
+        MessageTrimmer::new(max_tokens).with_token_counter(|_| 1)
+    }
+
+    #[test]
+    fn test_trim_last() {
+        let messages = trimmer(4).trim(&history());
+        assert_eq!(contents(&messages), vec!["system", "a2", "t2", "h3"]);
+
+        // The tool message would be the first after the system message.
+        let messages = trimmer(3).trim(&history());
+        assert_eq!(contents(&messages), vec!["system", "h3"]);
+
+        let messages = trimmer(3).with_keep_system(false).trim(&history());
+        assert_eq!(contents(&messages), vec!["a2", "t2", "h3"]);
+    }
+
+    #[test]
+    fn test_trim_last_n() {
+        let messages = trimmer(10)
+            .with_strategy(TrimStrategy::LastN(4))
+            .trim(&history());
+        assert_eq!(contents(&messages), vec!["system", "h2", "a2", "t2", "h3"]);
+    }
+
+    #[test]
+    fn test_trim_first_last() {
+        let messages = trimmer(4)
+            .with_strategy(TrimStrategy::FirstLast { first: 1 })
+            .trim(&history());
+        assert_eq!(contents(&messages), vec!["system", "h1", "h3"]);
+
+        let messages = trimmer(10)
+            .with_strategy(TrimStrategy::FirstLast { first: 1 })
+            .trim(&history());
+        assert_eq!(messages.len(), history().len());
+    }
+
+    #[test]
+    fn test_count_message_tokens() {
+        assert_eq!(
+            count_message_tokens(&Message::new_human_message("Hello world")),
+            2 + MESSAGE_OVERHEAD_TOKENS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trim_with_summary_requires_summarizer() {
+        assert!(matches!(
+            trimmer(3).trim_with_summary(&history()).await,
+            Err(ChainError::MissingObject(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_trim_with_summary() {
+        let messages = MessageTrimmer::new(40)
+            .with_summary_max_tokens(20)
+            .with_summarizer(OpenAI::default())
+            .trim_with_summary(&history())
+            .await
+            .unwrap();
+        assert!(messages[1]
+            .content
+            .text()
+            .starts_with("Summary of the earlier conversation"));
+    }
+}
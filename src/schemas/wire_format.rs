@@ -0,0 +1,442 @@
+use serde::de::Error;
+use serde_json::{json, Value};
+
+use super::{ContentPart, ImageDetail, Message, MessageContent, MessageType};
+
+type Result<T> = std::result::Result<T, serde_json::Error>;
+
+fn text_value(content: &MessageContent) -> Value {
+    Value::String(content.text())
+}
+
+fn openai_part(part: &ContentPart) -> Option<Value> {
+    match part {
+        ContentPart::Text { text } => Some(json!({"type": "text", "text": text})),
+        ContentPart::Image { url, detail } => {
+            let mut image_url = json!({"url": url});
+            if let Some(detail) = detail {
+                image_url["detail"] = json!(detail);
+            }
+            Some(json!({"type": "image_url", "image_url": image_url}))
+        }
+        ContentPart::Audio { data, format } => Some(json!({
+            "type": "input_audio",
+            "input_audio": {"data": data, "format": format},
+        })),
+        // Sent in the `tool_calls` of the assistant messages and as tool messages.
+        ContentPart::ToolCall { .. } | ContentPart::ToolResult { .. } => None,
+    }
+}
+
+fn openai_tool_call(id: &str, name: &str, arguments: &Value) -> Value {
+    json!({
+        "id": id,
+        "type": "function",
+        "function": {"name": name, "arguments": arguments.to_string()},
+    })
+}
+
+fn from_openai_part(part: &Value) -> Result<ContentPart> {
+    let field = |value: &Value, key: &str| -> Result<String> {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| serde_json::Error::custom(format!("missing field `{}`", key)))
+    };
+    match part.get("type").and_then(Value::as_str) {
+        Some("text") => Ok(ContentPart::text(field(part, "text")?)),
+        Some("image_url") => {
+            let image_url = &part["image_url"];
+            Ok(ContentPart::Image {
+                url: field(image_url, "url")?,
+                detail: image_url
+                    .get("detail")
+                    .map(|detail| serde_json::from_value::<ImageDetail>(detail.clone()))
+                    .transpose()?,
+            })
+        }
+        Some("input_audio") => Ok(ContentPart::Audio {
+            data: field(&part["input_audio"], "data")?,
+            format: field(&part["input_audio"], "format")?,
+        }),
+        other => Err(serde_json::Error::custom(format!(
+            "unsupported content part type {:?}",
+            other
+        ))),
+    }
+}
+
+fn from_openai_content(content: Option<&Value>) -> Result<MessageContent> {
+    match content {
+        None | Some(Value::Null) => Ok(MessageContent::default()),
+        Some(Value::String(text)) => Ok(MessageContent::Text(text.clone())),
+        Some(Value::Array(parts)) => Ok(MessageContent::Parts(
+            parts.iter().map(from_openai_part).collect::<Result<_>>()?,
+        )),
+        Some(content) => Err(serde_json::Error::custom(format!(
+            "invalid content {}",
+            content
+        ))),
+    }
+}
+
+/// The content block of a part in the Anthropic Messages API, `None` for the parts
+/// Anthropic doesn't support.
+pub(crate) fn anthropic_block(part: &ContentPart) -> Option<Value> {
+    match part {
+        ContentPart::Text { text } => Some(json!({"type": "text", "text": text})),
+        ContentPart::Image { url, .. } => {
+            let source = match url
+                .strip_prefix("data:")
+                .and_then(|data| data.split_once(";base64,"))
+            {
+                Some((media_type, data)) => {
+                    json!({"type": "base64", "media_type": media_type, "data": data})
+                }
+                None => json!({"type": "url", "url": url}),
+            };
+            Some(json!({"type": "image", "source": source}))
+        }
+        ContentPart::ToolCall {
+            id,
+            name,
+            arguments,
+        } => Some(json!({"type": "tool_use", "id": id, "name": name, "input": arguments})),
+        ContentPart::ToolResult {
+            tool_call_id,
+            content,
+        } => Some(json!({
+            "type": "tool_result",
+            "tool_use_id": tool_call_id,
+            "content": content,
+        })),
+        ContentPart::Audio { .. } => {
+            log::warn!("Anthropic doesn't support audio content");
+            None
+        }
+    }
+}
+
+fn anthropic_content(content: &MessageContent) -> Value {
+    match content {
+        MessageContent::Text(text) => Value::String(text.clone()),
+        MessageContent::Parts(parts) => {
+            Value::Array(parts.iter().filter_map(anthropic_block).collect())
+        }
+    }
+}
+
+/// The `(id, name, arguments)` of the tool calls of an assistant message, from its
+/// `tool_calls` in the OpenAI format and its tool call parts.
+fn tool_calls(message: &Message) -> Vec<(String, String, Value)> {
+    let mut calls: Vec<(String, String, Value)> = message
+        .tool_calls
+        .as_ref()
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|call| {
+            let arguments = call["function"]["arguments"].as_str().unwrap_or("{}");
+            (
+                call["id"].as_str().unwrap_or_default().to_string(),
+                call["function"]["name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                serde_json::from_str(arguments).unwrap_or(Value::String(arguments.into())),
+            )
+        })
+        .collect();
+    if let MessageContent::Parts(parts) = &message.content {
+        calls.extend(parts.iter().filter_map(|part| match part {
+            ContentPart::ToolCall {
+                id,
+                name,
+                arguments,
+            } => Some((id.clone(), name.clone(), arguments.clone())),
+            _ => None,
+        }));
+    }
+    calls
+}
+
+impl Message {
+    /// Serializes the messages in the format of the OpenAI Chat Completions API, e.g. to
+    /// persist a conversation or send it with another SDK.
+    pub fn to_openai_messages(messages: &[Message]) -> Vec<Value> {
+        messages
+            .iter()
+            .map(|message| match message.message_type {
+                MessageType::SystemMessage => {
+                    json!({"role": "system", "content": text_value(&message.content)})
+                }
+                MessageType::HumanMessage => {
+                    let content = match &message.content {
+                        MessageContent::Text(text) => Value::String(text.clone()),
+                        MessageContent::Parts(parts) => {
+                            Value::Array(parts.iter().filter_map(openai_part).collect())
+                        }
+                    };
+                    json!({"role": "user", "content": content})
+                }
+                MessageType::AIMessage => {
+                    let mut value =
+                        json!({"role": "assistant", "content": text_value(&message.content)});
+                    let calls = tool_calls(message);
+                    if !calls.is_empty() {
+                        value["tool_calls"] = calls
+                            .iter()
+                            .map(|(id, name, arguments)| openai_tool_call(id, name, arguments))
+                            .collect();
+                    }
+                    value
+                }
+                MessageType::ToolMessage => json!({
+                    "role": "tool",
+                    "tool_call_id": message.id.clone().unwrap_or_default(),
+                    "content": text_value(&message.content),
+                }),
+            })
+            .collect()
+    }
+
+    /// Parses messages in the format of the OpenAI Chat Completions API, e.g. a stored
+    /// transcript.
+    pub fn from_openai_messages(messages: &[Value]) -> Result<Vec<Message>> {
+        messages
+            .iter()
+            .map(|message| {
+                let content = from_openai_content(message.get("content"))?;
+                let message_type = match message.get("role").and_then(Value::as_str) {
+                    Some("system") | Some("developer") => MessageType::SystemMessage,
+                    Some("user") => MessageType::HumanMessage,
+                    Some("assistant") => MessageType::AIMessage,
+                    Some("tool") => MessageType::ToolMessage,
+                    role => {
+                        return Err(serde_json::Error::custom(format!(
+                            "unsupported role {:?}",
+                            role
+                        )))
+                    }
+                };
+                Ok(Message {
+                    content,
+                    message_type,
+                    id: message
+                        .get("tool_call_id")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    tool_calls: message
+                        .get("tool_calls")
+                        .filter(|tool_calls| !tool_calls.is_null())
+                        .cloned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Serializes the messages in the format of the Anthropic Messages API: the system
+    /// prompt, joining the system messages, and the messages. The tool messages are sent
+    /// as user messages with a `tool_result` block.
+    pub fn to_anthropic_messages(messages: &[Message]) -> (Option<String>, Vec<Value>) {
+        let system: Vec<String> = messages
+            .iter()
+            .filter(|message| matches!(message.message_type, MessageType::SystemMessage))
+            .map(|message| message.content.text())
+            .collect();
+        let messages = messages
+            .iter()
+            .filter_map(|message| match message.message_type {
+                MessageType::SystemMessage => None,
+                MessageType::HumanMessage => Some(
+                    json!({"role": "user", "content": anthropic_content(&message.content)}),
+                ),
+                MessageType::AIMessage => {
+                    let calls = tool_calls(message);
+                    let content = if calls.is_empty() {
+                        anthropic_content(&message.content)
+                    } else {
+                        let text = message.content.text();
+                        let text = (!text.is_empty()).then(|| json!({"type": "text", "text": text}));
+                        text.into_iter()
+                            .chain(calls.iter().map(|(id, name, arguments)| {
+                                json!({"type": "tool_use", "id": id, "name": name, "input": arguments})
+                            }))
+                            .collect()
+                    };
+                    Some(json!({"role": "assistant", "content": content}))
+                }
+                MessageType::ToolMessage => Some(json!({
+                    "role": "user",
+                    "content": [{
+                        "type": "tool_result",
+                        "tool_use_id": message.id.clone().unwrap_or_default(),
+                        "content": message.content.text(),
+                    }],
+                })),
+            })
+            .collect();
+        ((!system.is_empty()).then(|| system.join("\n")), messages)
+    }
+
+    /// Parses the system prompt and the messages of the Anthropic Messages API. The
+    /// `tool_result` blocks become tool messages and the `tool_use` blocks the
+    /// `tool_calls` of the ai messages, in the OpenAI format.
+    pub fn from_anthropic_messages(
+        system: Option<&str>,
+        messages: &[Value],
+    ) -> Result<Vec<Message>> {
+        let mut parsed: Vec<Message> = system
+            .map(Message::new_system_message)
+            .into_iter()
+            .collect();
+        for message in messages {
+            let role = message.get("role").and_then(Value::as_str);
+            let blocks = match message.get("content") {
+                Some(Value::String(text)) => {
+                    parsed.push(match role {
+                        Some("user") => Message::new_human_message(text),
+                        Some("assistant") => Message::new_ai_message(text),
+                        role => {
+                            return Err(serde_json::Error::custom(format!(
+                                "unsupported role {:?}",
+                                role
+                            )))
+                        }
+                    });
+                    continue;
+                }
+                Some(Value::Array(blocks)) => blocks,
+                content => {
+                    return Err(serde_json::Error::custom(format!(
+                        "invalid content {:?}",
+                        content
+                    )))
+                }
+            };
+
+            let mut parts = Vec::new();
+            let mut calls = Vec::new();
+            for block in blocks {
+                match block.get("type").and_then(Value::as_str) {
+                    Some("text") => parts.push(ContentPart::text(
+                        block["text"].as_str().unwrap_or_default(),
+                    )),
+                    Some("image") => {
+                        let source = &block["source"];
+                        let url = match source["type"].as_str() {
+                            Some("base64") => format!(
+                                "data:{};base64,{}",
+                                source["media_type"].as_str().unwrap_or_default(),
+                                source["data"].as_str().unwrap_or_default()
+                            ),
+                            _ => source["url"].as_str().unwrap_or_default().to_string(),
+                        };
+                        parts.push(ContentPart::image_url(url));
+                    }
+                    Some("tool_use") => calls.push(openai_tool_call(
+                        block["id"].as_str().unwrap_or_default(),
+                        block["name"].as_str().unwrap_or_default(),
+                        &block["input"],
+                    )),
+                    Some("tool_result") => {
+                        let content = match &block["content"] {
+                            Value::String(text) => text.clone(),
+                            Value::Array(blocks) => blocks
+                                .iter()
+                                .filter_map(|block| block["text"].as_str())
+                                .collect::<Vec<_>>()
+                                .join("\n"),
+                            _ => String::new(),
+                        };
+                        parsed.push(Message::new_tool_message(
+                            content,
+                            block["tool_use_id"].as_str().unwrap_or_default(),
+                        ));
+                    }
+                    other => log::warn!("Skipping the unsupported content block {:?}", other),
+                }
+            }
+
+            let content = match parts.as_slice() {
+                [] if calls.is_empty() => continue,
+                [] => MessageContent::default(),
+                [ContentPart::Text { text }] => MessageContent::Text(text.clone()),
+                _ => MessageContent::Parts(parts),
+            };
+            let mut message = match role {
+                Some("assistant") => Message::new_ai_message(""),
+                _ => Message::new_human_message(""),
+            };
+            message.content = content;
+            if !calls.is_empty() {
+                message.tool_calls = Some(Value::Array(calls));
+            }
+            parsed.push(message);
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<Message> {
+        vec![
+            Message::new_system_message("You are helpful"),
+            Message::new_human_message_with_parts(vec![
+                ContentPart::text("What is in the image?"),
+                ContentPart::image_base64("image/png", "aGVsbG8="),
+            ]),
+            Message::new_ai_message("").with_tool_calls(json!([openai_tool_call(
+                "call_1",
+                "describe",
+                &json!({"detail": "high"})
+            )])),
+            Message::new_tool_message("A cat", "call_1"),
+            Message::new_ai_message("It is a cat"),
+        ]
+    }
+
+    #[test]
+    fn test_openai_messages() {
+        let messages = Message::to_openai_messages(&conversation());
+        assert_eq!(messages[1]["content"][1]["type"], "image_url");
+        assert_eq!(
+            messages[2]["tool_calls"][0]["function"]["arguments"],
+            r#"{"detail":"high"}"#
+        );
+        assert_eq!(
+            messages[3],
+            json!({"role": "tool", "tool_call_id": "call_1", "content": "A cat"})
+        );
+
+        let parsed = Message::from_openai_messages(&messages).unwrap();
+        assert_eq!(Message::to_openai_messages(&parsed), messages);
+        assert!(Message::from_openai_messages(&[json!({"role": "robot", "content": ""})]).is_err());
+    }
+
+    #[test]
+    fn test_anthropic_messages() {
+        let (system, messages) = Message::to_anthropic_messages(&conversation());
+        assert_eq!(system.as_deref(), Some("You are helpful"));
+        assert_eq!(
+            messages[0]["content"][1]["source"]["media_type"],
+            "image/png"
+        );
+        assert_eq!(
+            messages[1]["content"],
+            json!([{"type": "tool_use", "id": "call_1", "name": "describe", "input": {"detail": "high"}}])
+        );
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "call_1");
+
+        let parsed = Message::from_anthropic_messages(system.as_deref(), &messages).unwrap();
+        assert_eq!(
+            Message::to_openai_messages(&parsed),
+            Message::to_openai_messages(&conversation())
+        );
+    }
+}
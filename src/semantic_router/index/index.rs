@@ -3,7 +3,7 @@ use async_trait::async_trait;
 use crate::semantic_router::{IndexError, Router};
 
 #[async_trait]
-pub trait Index {
+pub trait Index: Send + Sync {
     async fn add(&mut self, router: &[Router]) -> Result<(), IndexError>;
 
     async fn delete(&mut self, route_name: &str) -> Result<(), IndexError>;
@@ -13,6 +13,17 @@ pub trait Index {
     /// Result<Vec<(route_name,similarity_score)>>
     async fn query(&self, vector: &[f64], top_k: usize) -> Result<Vec<(String, f64)>, IndexError>;
 
+    /// Query the index with a query and its vector. Indexes searching by text, e.g.
+    /// `VectorStoreIndex`, override it.
+    async fn query_text(
+        &self,
+        _query: &str,
+        vector: &[f64],
+        top_k: usize,
+    ) -> Result<Vec<(String, f64)>, IndexError> {
+        self.query(vector, top_k).await
+    }
+
     async fn get_routers(&self) -> Result<Vec<Router>, IndexError>;
 
     async fn get_router(&self, route_name: &str) -> Result<Router, IndexError>;
@@ -4,5 +4,8 @@ pub use index::*;
 mod memory_index;
 pub use memory_index::*;
 
+mod vector_store_index;
+pub use vector_store_index::*;
+
 mod error;
 pub use error::*;
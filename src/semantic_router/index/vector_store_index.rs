@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::{
+    schemas::Document,
+    semantic_router::{IndexError, Router},
+    vectorstore::{VecStoreOptions, VectorStore},
+};
+
+use super::Index;
+
+const ROUTE_METADATA_KEY: &str = "route";
+
+/// An index storing the utterances of the routes in a `VectorStore`, for route sets too
+/// large for a `MemoryIndex` or shared by several services.
+///
+/// The utterances are embedded by the vector store and stored as documents with the name
+/// of their route in the `route` metadata. The definitions of the routes are kept in
+/// memory: register the routes already stored with `with_routes`, e.g. loaded from the
+/// json of `RouteLayer::to_json`.
+///
+/// The utterances of a deleted route are deleted from the store when it supports it,
+/// otherwise they stay in the store and are ignored by the queries.
+///
+/// # Example
+/// ```rust,ignore
+/// let index = VectorStoreIndex::new(store).with_routes(routes);
+/// let route_layer = RouteLayerBuilder::default().index(index).build().await?;
+/// ```
+pub struct VectorStoreIndex {
+    store: Box<dyn VectorStore>,
+    options: VecStoreOptions,
+    routers: HashMap<String, Router>,
+    /// The ids of the utterances added by this index, by route.
+    ids: HashMap<String, Vec<String>>,
+}
+
+impl VectorStoreIndex {
+    pub fn new<V: Into<Box<dyn VectorStore>>>(store: V) -> Self {
+        Self {
+            store: store.into(),
+            options: VecStoreOptions::default(),
+            routers: HashMap::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Options of the vector store queries, e.g. the namespace of the routes.
+    pub fn with_options(mut self, options: VecStoreOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Registers routes whose utterances are already in the store.
+    pub fn with_routes(mut self, routers: Vec<Router>) -> Self {
+        self.routers.extend(
+            routers
+                .into_iter()
+                .map(|router| (router.name.clone(), router)),
+        );
+        self
+    }
+}
+
+#[async_trait]
+impl Index for VectorStoreIndex {
+    async fn add(&mut self, routers: &[Router]) -> Result<(), IndexError> {
+        for router in routers {
+            let documents: Vec<Document> = router
+                .utterances
+                .iter()
+                .map(|utterance| {
+                    Document::new(utterance).with_metadata(HashMap::from([(
+                        ROUTE_METADATA_KEY.to_string(),
+                        json!(router.name),
+                    )]))
+                })
+                .collect();
+            let ids = self
+                .store
+                .add_documents(&documents, &self.options)
+                .await
+                .map_err(|e| IndexError::OtherError(e.to_string()))?;
+            self.ids.entry(router.name.clone()).or_default().extend(ids);
+            self.routers.insert(router.name.clone(), router.clone());
+        }
+        Ok(())
+    }
+
+    async fn delete(&mut self, route_name: &str) -> Result<(), IndexError> {
+        if self.routers.remove(route_name).is_none() {
+            log::warn!("Router {} not found in the index", route_name);
+        }
+        if let Some(ids) = self.ids.remove(route_name) {
+            if let Err(e) = self.store.delete(&ids, &self.options).await {
+                log::warn!("The utterances of {} stay in the store: {}", route_name, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        _vector: &[f64],
+        _top_k: usize,
+    ) -> Result<Vec<(String, f64)>, IndexError> {
+        Err(IndexError::OtherError(
+            "VectorStoreIndex is queried by text, use query_text".to_string(),
+        ))
+    }
+
+    async fn query_text(
+        &self,
+        query: &str,
+        _vector: &[f64],
+        top_k: usize,
+    ) -> Result<Vec<(String, f64)>, IndexError> {
+        let documents = self
+            .store
+            .similarity_search(query, top_k, &self.options)
+            .await
+            .map_err(|e| IndexError::OtherError(e.to_string()))?;
+        Ok(documents
+            .into_iter()
+            .filter_map(|document| {
+                let route = document.metadata.get(ROUTE_METADATA_KEY)?.as_str()?;
+                self.routers
+                    .contains_key(route)
+                    .then(|| (route.to_string(), document.score))
+            })
+            .collect())
+    }
+
+    async fn get_routers(&self) -> Result<Vec<Router>, IndexError> {
+        Ok(self.routers.values().cloned().collect())
+    }
+
+    async fn get_router(&self, route_name: &str) -> Result<Router, IndexError> {
+        self.routers
+            .get(route_name)
+            .cloned()
+            .ok_or(IndexError::RouterNotFound(route_name.into()))
+    }
+
+    async fn delete_index(&mut self) -> Result<(), IndexError> {
+        let route_names: Vec<String> = self.routers.keys().cloned().collect();
+        for route_name in route_names {
+            self.delete(&route_name).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, sync::Mutex};
+
+    use super::*;
+
+    /// Scores the documents by the number of words they share with the query.
+    #[derive(Default)]
+    struct WordsStore {
+        documents: Mutex<Vec<Document>>,
+    }
+
+    #[async_trait]
+    impl VectorStore for WordsStore {
+        async fn add_documents(
+            &self,
+            docs: &[Document],
+            _opt: &VecStoreOptions,
+        ) -> Result<Vec<String>, Box<dyn Error>> {
+            self.documents.lock().unwrap().extend_from_slice(docs);
+            Ok(vec![])
+        }
+
+        async fn similarity_search(
+            &self,
+            query: &str,
+            limit: usize,
+            _opt: &VecStoreOptions,
+        ) -> Result<Vec<Document>, Box<dyn Error>> {
+            let mut documents: Vec<Document> = self
+                .documents
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|document| {
+                    let score = query
+                        .split_whitespace()
+                        .filter(|word| document.page_content.contains(word))
+                        .count();
+                    document.clone().with_score(score as f64)
+                })
+                .filter(|document| document.score > 0.0)
+                .collect();
+            documents.sort_by(|a, b| b.score.total_cmp(&a.score));
+            documents.truncate(limit);
+            Ok(documents)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vector_store_index() {
+        let mut index = VectorStoreIndex::new(WordsStore::default());
+        index
+            .add(&[
+                Router::new("weather", &["is it raining", "the temperature today"]),
+                Router::new("capital", &["the capital of France"]),
+            ])
+            .await
+            .unwrap();
+
+        let routes = index
+            .query_text("is it raining today", &[], 5)
+            .await
+            .unwrap();
+        assert_eq!(routes[0], ("weather".to_string(), 3.0));
+        assert!(index.query(&[], 5).await.is_err());
+
+        index.delete("weather").await.unwrap();
+        let routes = index
+            .query_text("is it raining today", &[], 5)
+            .await
+            .unwrap();
+        assert!(routes.iter().all(|(route, _)| route != "weather"));
+        assert_eq!(index.get_routers().await.unwrap().len(), 1);
+    }
+}
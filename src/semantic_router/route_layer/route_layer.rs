@@ -32,6 +32,8 @@ pub struct RouteChoise {
     pub route: String,
     pub similarity_score: f64,
     pub tool_input: Option<Value>,
+    /// The metadata of the route.
+    pub metadata: HashMap<String, Value>,
 }
 
 pub struct RouteLayer {
@@ -55,6 +57,10 @@ impl RouteLayer {
         Ok(())
     }
 
+    pub async fn add_route(&mut self, router: Router) -> Result<(), RouteLayerError> {
+        self.add_routes(&mut [router]).await
+    }
+
     pub async fn delete_route<S: Into<String>>(
         &mut self,
         route_name: S,
@@ -68,16 +74,42 @@ impl RouteLayer {
         Ok(routes)
     }
 
+    /// Serializes the routes, with their embeddings, e.g. to save them to a file and load
+    /// them with `add_routes_from_json` without embedding them again.
+    pub async fn to_json(&self) -> Result<String, RouteLayerError> {
+        let mut routes = self.get_routers().await?;
+        routes.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(serde_json::to_string(&routes)?)
+    }
+
+    /// Adds the routes serialized by `to_json`, embedding the ones without embeddings.
+    pub async fn add_routes_from_json(&mut self, json: &str) -> Result<(), RouteLayerError> {
+        let mut routes: Vec<Router> = serde_json::from_str(json)?;
+        self.add_routes(&mut routes).await
+    }
+
+    /// Keeps the routes above their threshold, or the threshold of the route layer, with
+    /// their definitions.
     async fn filter_similar_routes(
         &self,
-        query_vector: &[f64],
-    ) -> Result<Vec<(String, f64)>, RouteLayerError> {
-        let similar_routes = self.index.query(query_vector, self.top_k).await?;
+        similar_routes: Vec<(String, f64)>,
+    ) -> Result<(Vec<(String, f64)>, HashMap<String, Router>), RouteLayerError> {
+        let mut routers: HashMap<String, Router> = HashMap::new();
+        for (route_name, _) in &similar_routes {
+            if !routers.contains_key(route_name) {
+                let router = self.index.get_router(route_name).await?;
+                routers.insert(route_name.clone(), router);
+            }
+        }
 
-        Ok(similar_routes
+        let similar_routes = similar_routes
             .into_iter()
-            .filter(|(_, score)| *score >= self.threshold)
-            .collect())
+            .filter(|(route_name, score)| {
+                let threshold = routers[route_name].threshold.unwrap_or(self.threshold);
+                *score >= threshold
+            })
+            .collect();
+        Ok((similar_routes, routers))
     }
 
     fn compute_total_scores(&self, similar_routes: &[(String, f64)]) -> HashMap<String, f64> {
@@ -128,7 +160,11 @@ impl RouteLayer {
         let query: String = query.into();
         let query_vector = self.embedder.embed_query(&query).await?;
 
-        let route_choise = self.call_embedding(&query_vector).await?;
+        let similar_routes = self
+            .index
+            .query_text(&query, &query_vector, self.top_k)
+            .await?;
+        let route_choise = self.choose_route(similar_routes).await?;
 
         if route_choise.is_none() {
             return Ok(None);
@@ -160,7 +196,15 @@ impl RouteLayer {
         &self,
         embedding: &[f64],
     ) -> Result<Option<RouteChoise>, RouteLayerError> {
-        let similar_routes = self.filter_similar_routes(&embedding).await?;
+        let similar_routes = self.index.query(embedding, self.top_k).await?;
+        self.choose_route(similar_routes).await
+    }
+
+    async fn choose_route(
+        &self,
+        similar_routes: Vec<(String, f64)>,
+    ) -> Result<Option<RouteChoise>, RouteLayerError> {
+        let (similar_routes, routers) = self.filter_similar_routes(similar_routes).await?;
 
         if similar_routes.is_empty() {
             return Ok(None);
@@ -181,6 +225,7 @@ impl RouteLayer {
             self.find_top_route_and_scores(total_scores, &scores_by_route);
 
         Ok(top_route.map(|route| RouteChoise {
+            metadata: routers[&route].metadata.clone(),
             route,
             similarity_score: top_scores[0],
             tool_input: None,
@@ -208,11 +253,127 @@ impl RouteLayer {
 
 #[cfg(test)]
 mod tests {
+    use async_trait::async_trait;
 
-    use crate::{embedding::openai::OpenAiEmbedder, semantic_router::RouteLayerBuilder};
+    use crate::{
+        embedding::{openai::OpenAiEmbedder, EmbedderError},
+        llm::openai::OpenAI,
+        semantic_router::{MemoryIndex, RouteLayerBuilder},
+    };
 
     use super::*;
 
+    /// Embeds the texts by the number of weather and geography words.
+    struct KeywordEmbedder;
+
+    impl KeywordEmbedder {
+        fn embed(text: &str) -> Vec<f64> {
+            let count = |words: &[&str]| {
+                words
+                    .iter()
+                    .filter(|word| text.to_lowercase().contains(*word))
+                    .count() as f64
+            };
+            vec![
+                count(&["rain", "temperature", "weather"]),
+                count(&["capital", "france"]),
+                1.0,
+            ]
+        }
+    }
+
+    #[async_trait]
+    impl Embedder for KeywordEmbedder {
+        async fn embed_documents(
+            &self,
+            documents: &[String],
+        ) -> Result<Vec<Vec<f64>>, EmbedderError> {
+            Ok(documents.iter().map(|text| Self::embed(text)).collect())
+        }
+
+        async fn embed_query(&self, text: &str) -> Result<Vec<f64>, EmbedderError> {
+            Ok(Self::embed(text))
+        }
+    }
+
+    async fn route_layer() -> RouteLayer {
+        RouteLayerBuilder::new()
+            .embedder(KeywordEmbedder)
+            .llm(OpenAI::default())
+            .index(MemoryIndex::new())
+            .add_route(
+                Router::new("weather", &["Is it raining?"]).with_metadata("handler", "forecast"),
+            )
+            .build()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_routes() {
+        let mut route_layer = route_layer().await;
+        let choice = route_layer.call("Is it raining?").await.unwrap().unwrap();
+        assert_eq!(choice.route, "weather");
+        assert_eq!(choice.metadata["handler"], "forecast");
+        assert!(route_layer
+            .call("Capital of France")
+            .await
+            .unwrap()
+            .is_none());
+
+        route_layer
+            .add_route(Router::new("capital", &["What is the capital of France?"]))
+            .await
+            .unwrap();
+        let choice = route_layer
+            .call("Capital of France")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(choice.route, "capital");
+
+        // The threshold of the route overrides the one of the route layer.
+        route_layer
+            .add_route(Router::new("weather", &["Is it raining?"]).with_threshold(1.1))
+            .await
+            .unwrap();
+        assert!(route_layer.call("Is it raining?").await.unwrap().is_none());
+
+        route_layer.delete_route("capital").await.unwrap();
+        assert!(route_layer
+            .call("Capital of France")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_routes_json() {
+        let route_layer = route_layer().await;
+        let json = route_layer.to_json().await.unwrap();
+
+        let mut loaded = RouteLayerBuilder::new()
+            .embedder(KeywordEmbedder)
+            .llm(OpenAI::default())
+            .index(MemoryIndex::new())
+            .build()
+            .await
+            .unwrap();
+        loaded.add_routes_from_json(&json).await.unwrap();
+        let routes = loaded.get_routers().await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].embedding, Some(vec![vec![1.0, 0.0, 1.0]]));
+        assert_eq!(routes[0].metadata["handler"], "forecast");
+
+        // Routes written by hand only need a name and utterances.
+        loaded
+            .add_routes_from_json(r#"[{"name": "capital", "utterances": ["Capital of France"]}]"#)
+            .await
+            .unwrap();
+        let choice = loaded.call("Capital of France").await.unwrap().unwrap();
+        assert_eq!(choice.route, "capital");
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_route_layer_builder() {
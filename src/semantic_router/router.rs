@@ -1,12 +1,29 @@
-use std::hash::{Hash, Hasher};
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A route of a `RouteLayer`. Routes serialize with their embeddings, so they are not
+/// embedded again when loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Router {
     pub name: String,
     pub utterances: Vec<String>,
+    #[serde(default)]
     pub embedding: Option<Vec<Vec<f64>>>,
+    #[serde(default)]
     pub similarity: Option<f64>,
+    #[serde(default)]
     pub tool_description: Option<String>,
+    /// Minimum similarity score of the route, overriding the threshold of the route layer.
+    #[serde(default)]
+    pub threshold: Option<f64>,
+    /// Returned to the caller with the route choice.
+    #[serde(default)]
+    pub metadata: HashMap<String, Value>,
 }
 impl Router {
     pub fn new<S: AsRef<str>>(name: &str, utterances: &[S]) -> Self {
@@ -16,6 +33,8 @@ impl Router {
             embedding: None,
             similarity: None,
             tool_description: None,
+            threshold: None,
+            metadata: HashMap::new(),
         }
     }
 
@@ -33,6 +52,16 @@ impl Router {
         self.similarity = Some(similarity);
         self
     }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    pub fn with_metadata<K: Into<String>, V: Into<Value>>(mut self, key: K, value: V) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
 }
 
 impl Eq for Router {}
@@ -0,0 +1,227 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::ws::{Message, WebSocket},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+
+use crate::{callbacks::RunEvent, chain::ChainError, schemas::StreamData};
+
+use super::{OpenAIChunkFormatter, OPENAI_STREAM_DONE};
+
+fn error_event(error: &ChainError) -> Event {
+    Event::default().event("error").data(error.to_string())
+}
+
+/// Serves the tokens of a stream (e.g. `Chain::stream`) as server-sent events: a
+/// message per token, and an `error` event if the stream fails.
+///
+/// # Example
+/// ```rust,ignore
+/// async fn chat(State(chain): State<Arc<LLMChain>>, Json(request): Json<ChatRequest>) -> Response {
+///     match chain.stream(prompt_args! {"input" => request.message}).await {
+///         Ok(stream) => sse_response(stream).into_response(),
+///         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+///     }
+/// }
+/// ```
+pub fn sse_response<S>(stream: S) -> Sse<impl Stream<Item = Result<Event, Infallible>>>
+where
+    S: Stream<Item = Result<StreamData, ChainError>> + Send + 'static,
+{
+    let events = async_stream::stream! {
+        futures::pin_mut!(stream);
+        while let Some(data) = stream.next().await {
+            match data {
+                // Some providers stream events without content, e.g. the usage.
+                Ok(data) if data.content.is_empty() => continue,
+                Ok(data) => yield Ok(Event::default().data(data.content)),
+                Err(e) => {
+                    yield Ok(error_event(&e));
+                    break;
+                }
+            }
+        }
+    };
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Serves the events of a run (see `Chain::stream_events`) as server-sent events, named
+/// after the type of the event and with the event as json data.
+pub fn events_sse_response<S>(events: S) -> Sse<impl Stream<Item = Result<Event, Infallible>>>
+where
+    S: Stream<Item = Result<RunEvent, ChainError>> + Send + 'static,
+{
+    let events = async_stream::stream! {
+        futures::pin_mut!(events);
+        while let Some(event) = events.next().await {
+            match event {
+                Ok(event) => {
+                    let event = json!(event);
+                    let name = event["event"].as_str().unwrap_or_default().to_string();
+                    yield Ok(Event::default().event(name).data(event.to_string()));
+                }
+                Err(e) => {
+                    yield Ok(error_event(&e));
+                    break;
+                }
+            }
+        }
+    };
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Serves the tokens of a stream as the server-sent events of the OpenAI chat
+/// completions API, ending with `data: [DONE]`, so the endpoint works with the OpenAI
+/// clients and SDKs.
+pub fn openai_sse_response<S, M>(
+    stream: S,
+    model: M,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>>
+where
+    S: Stream<Item = Result<StreamData, ChainError>> + Send + 'static,
+    M: Into<String>,
+{
+    let mut chunks = OpenAIChunkFormatter::new(model);
+    let events = async_stream::stream! {
+        futures::pin_mut!(stream);
+        while let Some(data) = stream.next().await {
+            match data {
+                Ok(data) if data.content.is_empty() => continue,
+                Ok(data) => yield Ok(Event::default().data(chunks.chunk(&data.content).to_string())),
+                Err(e) => {
+                    yield Ok(Event::default().data(chunks.error(&e.to_string()).to_string()));
+                    break;
+                }
+            }
+        }
+        yield Ok(Event::default().data(chunks.finish().to_string()));
+        yield Ok(Event::default().data(OPENAI_STREAM_DONE));
+    };
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+async fn send_json(socket: &mut WebSocket, message: Value) -> Result<(), axum::Error> {
+    socket.send(Message::Text(message.to_string())).await
+}
+
+/// Sends the tokens of a stream through a WebSocket, as json text messages:
+/// `{"type": "token", "content": ...}` for every token, then `{"type": "end"}` or
+/// `{"type": "error", "error": ...}`.
+///
+/// # Example
+/// ```rust,ignore
+/// async fn chat(ws: WebSocketUpgrade, State(chain): State<Arc<LLMChain>>) -> Response {
+///     ws.on_upgrade(move |mut socket| async move {
+///         while let Some(Ok(Message::Text(input))) = socket.recv().await {
+///             if let Ok(stream) = chain.stream(prompt_args! {"input" => input}).await {
+///                 let _ = forward_to_websocket(&mut socket, stream).await;
+///             }
+///         }
+///     })
+/// }
+/// ```
+pub async fn forward_to_websocket<S>(socket: &mut WebSocket, stream: S) -> Result<(), axum::Error>
+where
+    S: Stream<Item = Result<StreamData, ChainError>> + Send,
+{
+    futures::pin_mut!(stream);
+    while let Some(data) = stream.next().await {
+        match data {
+            Ok(data) if data.content.is_empty() => continue,
+            Ok(data) => {
+                send_json(socket, json!({ "type": "token", "content": data.content })).await?
+            }
+            Err(e) => {
+                return send_json(socket, json!({ "type": "error", "error": e.to_string() })).await
+            }
+        }
+    }
+    send_json(socket, json!({ "type": "end" })).await
+}
+
+/// Sends the events of a run through a WebSocket, a json text message per event, see
+/// `RunEvent`. A failure is sent as `{"event": "error", "error": ...}`.
+pub async fn forward_events_to_websocket<S>(
+    socket: &mut WebSocket,
+    events: S,
+) -> Result<(), axum::Error>
+where
+    S: Stream<Item = Result<RunEvent, ChainError>> + Send,
+{
+    futures::pin_mut!(events);
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(event) => socket.send(Message::Text(json!(event).to_string())).await?,
+            Err(e) => {
+                return send_json(socket, json!({ "event": "error", "error": e.to_string() })).await
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+
+    use super::*;
+    use crate::language_models::GenerateResult;
+
+    async fn body(response: impl IntoResponse) -> String {
+        let body = axum::body::to_bytes(response.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    fn tokens(fail: bool) -> impl Stream<Item = Result<StreamData, ChainError>> + Send {
+        let mut items = vec![
+            Ok(StreamData::new(json!({}), "Hello")),
+            Ok(StreamData::new(json!({}), "")),
+            Ok(StreamData::new(json!({}), " world")),
+        ];
+        if fail {
+            items.push(Err(ChainError::OtherError("boom".to_string())));
+        }
+        futures::stream::iter(items)
+    }
+
+    #[tokio::test]
+    async fn test_sse_response() {
+        assert_eq!(
+            body(sse_response(tokens(true))).await,
+            "data: Hello\n\ndata:  world\n\nevent: error\ndata: Error: boom\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_openai_sse_response() {
+        let body = body(openai_sse_response(tokens(false), "my-agent")).await;
+        let data: Vec<_> = body
+            .split("\n\n")
+            .filter_map(|event| event.strip_prefix("data: "))
+            .collect();
+        assert_eq!(data.len(), 4);
+        let first: Value = serde_json::from_str(data[0]).unwrap();
+        assert_eq!(first["choices"][0]["delta"]["content"], "Hello");
+        let last: Value = serde_json::from_str(data[2]).unwrap();
+        assert_eq!(last["choices"][0]["finish_reason"], "stop");
+        assert_eq!(data[3], OPENAI_STREAM_DONE);
+    }
+
+    #[tokio::test]
+    async fn test_events_sse_response() {
+        let events = futures::stream::iter(vec![Ok(RunEvent::Output {
+            output: GenerateResult {
+                generation: "Hi".to_string(),
+                ..Default::default()
+            },
+        })]);
+        let body = body(events_sse_response(events)).await;
+        assert!(body.starts_with("event: output\ndata: {"));
+        assert!(body.contains("\"generation\":\"Hi\""));
+    }
+}
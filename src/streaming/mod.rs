@@ -0,0 +1,9 @@
+//! Helpers to serve the streams of the chains and agents, e.g. from a streaming chat
+//! endpoint. The axum responses require the `axum` feature.
+mod openai_chunks;
+pub use openai_chunks::*;
+
+#[cfg(feature = "axum")]
+mod axum;
+#[cfg(feature = "axum")]
+pub use self::axum::*;
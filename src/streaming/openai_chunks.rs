@@ -0,0 +1,114 @@
+use std::time::UNIX_EPOCH;
+
+use serde_json::{json, Value};
+
+/// The last message of an OpenAI stream, sent as `data: [DONE]`.
+pub const OPENAI_STREAM_DONE: &str = "[DONE]";
+
+/// Formats the tokens of a stream as the `chat.completion.chunk` objects streamed by the
+/// OpenAI chat completions API, so OpenAI clients and SDKs can consume the stream of a
+/// chain.
+///
+/// # Example
+/// ```rust,ignore
+/// let mut chunks = OpenAIChunkFormatter::new("my-agent");
+/// while let Some(data) = stream.next().await {
+///     send(chunks.chunk(&data?.content));
+/// }
+/// send(chunks.finish());
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenAIChunkFormatter {
+    id: String,
+    model: String,
+    created: u64,
+    started: bool,
+}
+
+impl OpenAIChunkFormatter {
+    pub fn new<S: Into<String>>(model: S) -> Self {
+        Self {
+            id: format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+            model: model.into(),
+            created: crate::runtime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since_epoch| since_epoch.as_secs())
+                .unwrap_or_default(),
+            started: false,
+        }
+    }
+
+    /// Id of the completion, the same for every chunk.
+    pub fn with_id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    /// A chunk with the next token. The first chunk also carries the role.
+    pub fn chunk(&mut self, content: &str) -> Value {
+        let delta = if self.started {
+            json!({ "content": content })
+        } else {
+            self.started = true;
+            json!({ "role": "assistant", "content": content })
+        };
+        self.with_choice(delta, Value::Null)
+    }
+
+    /// The last chunk, with the finish reason.
+    pub fn finish(&self) -> Value {
+        self.with_choice(json!({}), json!("stop"))
+    }
+
+    /// An error in the format of the OpenAI API.
+    pub fn error(&self, message: &str) -> Value {
+        json!({
+            "error": {
+                "message": message,
+                "type": "server_error",
+            }
+        })
+    }
+
+    fn with_choice(&self, delta: Value, finish_reason: Value) -> Value {
+        json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason,
+            }],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_chunks() {
+        let mut chunks = OpenAIChunkFormatter::new("my-agent").with_id("chatcmpl-1");
+
+        let first = chunks.chunk("Hel");
+        assert_eq!(first["id"], "chatcmpl-1");
+        assert_eq!(first["object"], "chat.completion.chunk");
+        assert_eq!(first["model"], "my-agent");
+        assert_eq!(
+            first["choices"][0]["delta"],
+            json!({ "role": "assistant", "content": "Hel" })
+        );
+        assert_eq!(first["choices"][0]["finish_reason"], Value::Null);
+
+        let second = chunks.chunk("lo");
+        assert_eq!(second["choices"][0]["delta"], json!({ "content": "lo" }));
+        assert_eq!(second["created"], first["created"]);
+
+        let last = chunks.finish();
+        assert_eq!(last["choices"][0]["delta"], json!({}));
+        assert_eq!(last["choices"][0]["finish_reason"], "stop");
+    }
+}
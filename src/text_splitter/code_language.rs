@@ -0,0 +1,41 @@
+/// A programming language, for `RecursiveCharacterTextSplitter::from_language`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLanguage {
+    Rust,
+    Python,
+    JavaScript,
+}
+
+impl CodeLanguage {
+    /// The separators of the language, as regular expressions matching the start of its
+    /// items with their attributes, decorators and doc comments, then of the items nested
+    /// in them.
+    pub fn separators(&self) -> Vec<&'static str> {
+        match self {
+            CodeLanguage::Rust => vec![
+                r"\n(?:(?:#\[[^\n]*|///[^\n]*|//![^\n]*)\n)*(?:pub(?:\([^)\n]*\))? )?(?:(?:async|const|unsafe|extern \S+) )*(?:fn|struct|enum|union|impl|trait|mod|static|type|macro_rules!)[\s<]",
+                r"\n(?:[ \t]+(?:#\[[^\n]*|///[^\n]*)\n)*[ \t]+(?:pub(?:\([^)\n]*\))? )?(?:(?:async|const|unsafe) )*fn\s",
+                r"\n\n",
+                r"\n",
+                " ",
+                "",
+            ],
+            CodeLanguage::Python => vec![
+                r"\n(?:@[^\n]*\n)*(?:async )?(?:def|class)\s",
+                r"\n(?:[ \t]+@[^\n]*\n)*[ \t]+(?:async )?def\s",
+                r"\n\n",
+                r"\n",
+                " ",
+                "",
+            ],
+            CodeLanguage::JavaScript => vec![
+                r"\n(?:export (?:default )?)?(?:(?:async )?function\*?|class|const|let|var)\s",
+                r"\n[ \t]+(?:(?:static|async|get|set) )*[A-Za-z_$][\w$]*\s*\([^)\n]*\)\s*\{",
+                r"\n\n",
+                r"\n",
+                " ",
+                "",
+            ],
+        }
+    }
+}
@@ -17,6 +17,12 @@ pub enum TextSplitterError {
     #[error("Tokenizer creation failed due to invalid model")]
     InvalidModel,
 
+    #[error("Chunk overlap {overlap} must be smaller than the chunk size {size}")]
+    InvalidChunkOverlap { overlap: usize, size: usize },
+
+    #[error("Invalid separator: {0}")]
+    InvalidSeparator(#[from] regex::Error),
+
     #[error("Error: {0}")]
     OtherError(String),
 }
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::schemas::Document;
+
+use super::{TextSplitter, TextSplitterError};
+
+/// Splits a markdown text in its sections, each chunk with the headers it is under in its
+/// metadata, e.g. `{"Header 1": "Intro", "Header 2": "Install"}`.
+///
+/// The headers in code blocks are ignored. The sections can be split further with another
+/// splitter, whose `split_documents` keeps the metadata of the headers.
+///
+/// # Example
+/// ```rust,ignore
+/// let splitter = MarkdownHeaderTextSplitter::new(vec![("#", "Header 1"), ("##", "Header 2")]);
+/// let documents = splitter.split_markdown("# Intro\nHello\n## Install\ncargo add");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MarkdownHeaderTextSplitter {
+    headers_to_split_on: Vec<(String, String)>,
+    strip_headers: bool,
+}
+
+impl Default for MarkdownHeaderTextSplitter {
+    fn default() -> Self {
+        MarkdownHeaderTextSplitter::new(vec![
+            ("#", "Header 1"),
+            ("##", "Header 2"),
+            ("###", "Header 3"),
+        ])
+    }
+}
+
+impl MarkdownHeaderTextSplitter {
+    /// The headers to split on, as their prefix and the metadata key of their title.
+    pub fn new<S: Into<String>>(headers_to_split_on: Vec<(S, S)>) -> Self {
+        let mut headers_to_split_on: Vec<(String, String)> = headers_to_split_on
+            .into_iter()
+            .map(|(prefix, name)| (prefix.into(), name.into()))
+            .collect();
+        // The longest prefixes first, so that "##" isn't taken for "#".
+        headers_to_split_on.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        Self {
+            headers_to_split_on,
+            strip_headers: true,
+        }
+    }
+
+    /// Whether the header lines are removed from the content of the chunks.
+    /// Default: true
+    pub fn with_strip_headers(mut self, strip_headers: bool) -> Self {
+        self.strip_headers = strip_headers;
+        self
+    }
+
+    /// Splits the markdown in documents with the headers in their metadata.
+    pub fn split_markdown(&self, text: &str) -> Vec<Document> {
+        let mut documents = Vec::new();
+        // The headers the current line is under, as their level, name and title.
+        let mut headers: Vec<(usize, String, String)> = Vec::new();
+        let mut lines: Vec<&str> = Vec::new();
+        let mut code_fence: Option<&str> = None;
+
+        for line in text.lines() {
+            let trimmed = line.trim_start();
+
+            if let Some(fence) = code_fence {
+                if trimmed.starts_with(fence) {
+                    code_fence = None;
+                }
+                lines.push(line);
+                continue;
+            }
+            if let Some(fence) = ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f)) {
+                code_fence = Some(fence);
+                lines.push(line);
+                continue;
+            }
+
+            let header = self.headers_to_split_on.iter().find(|(prefix, _)| {
+                trimmed.starts_with(prefix.as_str())
+                    && trimmed[prefix.len()..]
+                        .chars()
+                        .next()
+                        .is_none_or(|c| c == ' ')
+            });
+            match header {
+                Some((prefix, name)) => {
+                    push_document(&mut documents, &lines, &headers);
+                    lines.clear();
+
+                    let level = prefix.len();
+                    headers.retain(|(header_level, _, _)| *header_level < level);
+                    headers.push((
+                        level,
+                        name.clone(),
+                        trimmed[prefix.len()..].trim().to_string(),
+                    ));
+                    if !self.strip_headers {
+                        lines.push(line);
+                    }
+                }
+                None => lines.push(line),
+            }
+        }
+        push_document(&mut documents, &lines, &headers);
+
+        documents
+    }
+}
+
+fn push_document(
+    documents: &mut Vec<Document>,
+    lines: &[&str],
+    headers: &[(usize, String, String)],
+) {
+    let content = lines.join("\n").trim().to_string();
+    if content.is_empty() {
+        return;
+    }
+    let metadata = headers
+        .iter()
+        .map(|(_, name, title)| (name.clone(), Value::from(title.as_str())))
+        .collect();
+    documents.push(Document::new(content).with_metadata(metadata));
+}
+
+#[async_trait]
+impl TextSplitter for MarkdownHeaderTextSplitter {
+    async fn split_text(&self, text: &str) -> Result<Vec<String>, TextSplitterError> {
+        Ok(self
+            .split_markdown(text)
+            .into_iter()
+            .map(|document| document.page_content)
+            .collect())
+    }
+
+    async fn create_documents(
+        &self,
+        text: &[String],
+        metadatas: &[HashMap<String, Value>],
+    ) -> Result<Vec<Document>, TextSplitterError> {
+        if !metadatas.is_empty() && text.len() != metadatas.len() {
+            return Err(TextSplitterError::MetadataTextMismatch);
+        }
+
+        let mut documents = Vec::new();
+        for (i, text) in text.iter().enumerate() {
+            for mut document in self.split_markdown(text) {
+                if let Some(metadata) = metadatas.get(i) {
+                    for (key, value) in metadata {
+                        document
+                            .metadata
+                            .entry(key.clone())
+                            .or_insert_with(|| value.clone());
+                    }
+                }
+                documents.push(document);
+            }
+        }
+
+        Ok(documents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_markdown_header_splitter() {
+        let markdown = "# Foo\n\n## Bar\n\nHi this is Jim\n\nHi this is Joe\n\n### Boo\n\nHi this is Lance\n\n```\n# not a header\n```\n\n## Baz\n\nHi this is Molly";
+
+        let documents = MarkdownHeaderTextSplitter::default().split_markdown(markdown);
+
+        assert_eq!(documents.len(), 3);
+        assert_eq!(
+            documents[0].page_content,
+            "Hi this is Jim\n\nHi this is Joe"
+        );
+        assert_eq!(
+            documents[0].metadata,
+            HashMap::from([
+                ("Header 1".to_string(), json!("Foo")),
+                ("Header 2".to_string(), json!("Bar")),
+            ])
+        );
+        assert_eq!(
+            documents[1].page_content,
+            "Hi this is Lance\n\n```\n# not a header\n```"
+        );
+        assert_eq!(documents[1].metadata["Header 3"], json!("Boo"));
+        assert_eq!(documents[2].page_content, "Hi this is Molly");
+        assert_eq!(
+            documents[2].metadata,
+            HashMap::from([
+                ("Header 1".to_string(), json!("Foo")),
+                ("Header 2".to_string(), json!("Baz")),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_markdown_header_splitter_documents() {
+        let splitter =
+            MarkdownHeaderTextSplitter::new(vec![("#", "title")]).with_strip_headers(false);
+        let documents = splitter
+            .split_documents(&[Document::new("# Intro\nHello\n#hashtag")
+                .with_metadata(HashMap::from([("source".to_string(), json!("README.md"))]))])
+            .await
+            .unwrap();
+
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].page_content, "# Intro\nHello\n#hashtag");
+        assert_eq!(documents[0].metadata["title"], json!("Intro"));
+        assert_eq!(documents[0].metadata["source"], json!("README.md"));
+    }
+}
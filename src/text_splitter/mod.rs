@@ -1,11 +1,17 @@
+mod code_language;
 mod error;
+mod markdown_header_splitter;
 mod markdown_splitter;
 mod options;
+mod recursive_character_splitter;
 mod text_splitter;
 mod token_splitter;
 
+pub use code_language::*;
 pub use error::*;
+pub use markdown_header_splitter::*;
 pub use markdown_splitter::*;
 pub use options::*;
+pub use recursive_character_splitter::*;
 pub use text_splitter::*;
 pub use token_splitter::*;
@@ -1,6 +1,7 @@
 // Options is a struct that contains options for a text splitter.
 pub struct SplitterOptions {
     pub chunk_size: usize,
+    pub chunk_overlap: usize,
     pub model_name: String,
     pub encoding_name: String,
     pub trim_chunks: bool,
@@ -16,6 +17,7 @@ impl SplitterOptions {
     pub fn new() -> Self {
         SplitterOptions {
             chunk_size: 512,
+            chunk_overlap: 0,
             model_name: String::from("gpt-3.5-turbo"),
             encoding_name: String::from("cl100k_base"),
             trim_chunks: false,
@@ -30,6 +32,12 @@ impl SplitterOptions {
         self
     }
 
+    /// Size shared by consecutive chunks, only used by the `RecursiveCharacterTextSplitter`.
+    pub fn with_chunk_overlap(mut self, chunk_overlap: usize) -> Self {
+        self.chunk_overlap = chunk_overlap;
+        self
+    }
+
     pub fn with_model_name(mut self, model_name: &str) -> Self {
         self.model_name = String::from(model_name);
         self
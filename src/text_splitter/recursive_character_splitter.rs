@@ -0,0 +1,458 @@
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use regex::Regex;
+use tiktoken_rs::{get_bpe_from_model, get_bpe_from_tokenizer, tokenizer::Tokenizer, CoreBPE};
+
+use super::{CodeLanguage, SplitterOptions, TextSplitter, TextSplitterError};
+
+const DEFAULT_SEPARATORS: [&str; 4] = ["\n\n", "\n", " ", ""];
+
+/// Splits the text on the first separator found in it, and recursively splits the pieces
+/// still larger than the chunk size on the next separators. The pieces are then merged
+/// back into chunks of at most `chunk_size`, consecutive chunks sharing up to
+/// `chunk_overlap`.
+///
+/// The sizes are counted in characters, or in tokens for a splitter created with
+/// `from_tiktoken`.
+///
+/// # Example
+/// ```rust,ignore
+/// let splitter = RecursiveCharacterTextSplitter::new(
+///     SplitterOptions::default()
+///         .with_chunk_size(1000)
+///         .with_chunk_overlap(200),
+/// );
+/// let chunks = splitter.split_text(&text).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecursiveCharacterTextSplitter {
+    separators: Vec<String>,
+    separator_regex: bool,
+    keep_separator: bool,
+    chunk_size: usize,
+    chunk_overlap: usize,
+    trim_chunks: bool,
+    count_tokens: bool,
+    model_name: String,
+    encoding_name: String,
+}
+
+impl Default for RecursiveCharacterTextSplitter {
+    fn default() -> Self {
+        RecursiveCharacterTextSplitter::new(SplitterOptions::default())
+    }
+}
+
+impl RecursiveCharacterTextSplitter {
+    /// Creates a splitter counting the size of the chunks in characters.
+    pub fn new(options: SplitterOptions) -> RecursiveCharacterTextSplitter {
+        RecursiveCharacterTextSplitter {
+            separators: DEFAULT_SEPARATORS.iter().map(|s| s.to_string()).collect(),
+            separator_regex: false,
+            keep_separator: false,
+            chunk_size: options.chunk_size,
+            chunk_overlap: options.chunk_overlap,
+            trim_chunks: options.trim_chunks,
+            count_tokens: false,
+            model_name: options.model_name,
+            encoding_name: options.encoding_name,
+        }
+    }
+
+    /// Creates a splitter counting the size of the chunks in tokens, with the encoding or
+    /// the model of the options, so that the chunks fit in the context of the model.
+    pub fn from_tiktoken(options: SplitterOptions) -> RecursiveCharacterTextSplitter {
+        RecursiveCharacterTextSplitter {
+            count_tokens: true,
+            ..RecursiveCharacterTextSplitter::new(options)
+        }
+    }
+
+    /// Creates a splitter for the source code of a language, splitting on the boundaries of
+    /// its functions, classes or types before the blank lines. The separators are kept at
+    /// the start of the chunks.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let splitter = RecursiveCharacterTextSplitter::from_language(
+    ///     CodeLanguage::Rust,
+    ///     SplitterOptions::default().with_chunk_size(2000),
+    /// );
+    /// ```
+    pub fn from_language(
+        language: CodeLanguage,
+        options: SplitterOptions,
+    ) -> RecursiveCharacterTextSplitter {
+        RecursiveCharacterTextSplitter::new(options)
+            .with_separators(language.separators())
+            .with_separator_regex(true)
+            .with_keep_separator(true)
+    }
+
+    /// Separators tried in order, the empty separator splits between characters.
+    /// Default: `["\n\n", "\n", " ", ""]`
+    pub fn with_separators<S: Into<String>>(mut self, separators: Vec<S>) -> Self {
+        self.separators = separators.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether the separators are regular expressions.
+    /// Default: false
+    pub fn with_separator_regex(mut self, separator_regex: bool) -> Self {
+        self.separator_regex = separator_regex;
+        self
+    }
+
+    /// Whether the separators are kept at the start of the pieces they split, instead of
+    /// being dropped at the chunk boundaries.
+    /// Default: false
+    pub fn with_keep_separator(mut self, keep_separator: bool) -> Self {
+        self.keep_separator = keep_separator;
+        self
+    }
+
+    pub fn get_tokenizer_from_str(&self, s: &str) -> Option<Tokenizer> {
+        match s.to_lowercase().as_str() {
+            "cl100k_base" => Some(Tokenizer::Cl100kBase),
+            "p50k_base" => Some(Tokenizer::P50kBase),
+            "r50k_base" => Some(Tokenizer::R50kBase),
+            "p50k_edit" => Some(Tokenizer::P50kEdit),
+            "gpt2" => Some(Tokenizer::Gpt2),
+            _ => None,
+        }
+    }
+
+    fn get_bpe(&self) -> Result<CoreBPE, TextSplitterError> {
+        if !self.encoding_name.is_empty() {
+            let tokenizer = self
+                .get_tokenizer_from_str(&self.encoding_name)
+                .ok_or(TextSplitterError::TokenizerNotFound)?;
+
+            get_bpe_from_tokenizer(tokenizer).map_err(|_| TextSplitterError::InvalidTokenizer)
+        } else {
+            get_bpe_from_model(&self.model_name).map_err(|_| TextSplitterError::InvalidModel)
+        }
+    }
+
+    fn split<F: Fn(&str) -> usize>(
+        &self,
+        text: &str,
+        separators: &[(Regex, String)],
+        length: &F,
+    ) -> Vec<String> {
+        let separator_index = separators
+            .iter()
+            .position(|(re, _)| re.as_str().is_empty() || re.is_match(text))
+            .unwrap_or(separators.len().saturating_sub(1));
+        let (separator_re, separator) = match separators.get(separator_index) {
+            Some((re, separator)) => (Some(re), separator.as_str()),
+            None => (None, ""),
+        };
+        let next_separators = separators.get(separator_index + 1..).unwrap_or(&[]);
+
+        let splits: Vec<&str> = match separator_re {
+            Some(re) if !re.as_str().is_empty() => {
+                if self.keep_separator {
+                    let mut starts: Vec<usize> = re
+                        .find_iter(text)
+                        .map(|m| m.start())
+                        .filter(|start| *start > 0)
+                        .collect();
+                    starts.insert(0, 0);
+                    starts.push(text.len());
+                    starts
+                        .windows(2)
+                        .map(|w| &text[w[0]..w[1]])
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                } else {
+                    re.split(text).filter(|s| !s.is_empty()).collect()
+                }
+            }
+            _ => text
+                .char_indices()
+                .map(|(i, c)| &text[i..i + c.len_utf8()])
+                .collect(),
+        };
+        // The kept separators are already in the pieces, and a regex isn't the text it
+        // matched, so the words of a regex split are joined back with a space.
+        let separator = if self.keep_separator {
+            ""
+        } else if self.separator_regex && !separator.is_empty() {
+            " "
+        } else {
+            separator
+        };
+
+        let mut chunks = Vec::new();
+        let mut small_splits = Vec::new();
+        for split in splits {
+            if length(split) <= self.chunk_size {
+                small_splits.push(split);
+                continue;
+            }
+            if !small_splits.is_empty() {
+                chunks.extend(self.merge(&small_splits, separator, length));
+                small_splits.clear();
+            }
+            if next_separators.is_empty() {
+                chunks.push(split.to_string());
+            } else {
+                chunks.extend(self.split(split, next_separators, length));
+            }
+        }
+        if !small_splits.is_empty() {
+            chunks.extend(self.merge(&small_splits, separator, length));
+        }
+
+        chunks
+    }
+
+    fn merge<F: Fn(&str) -> usize>(
+        &self,
+        splits: &[&str],
+        separator: &str,
+        length: &F,
+    ) -> Vec<String> {
+        let separator_length = length(separator);
+        let mut chunks = Vec::new();
+        let mut current: VecDeque<&str> = VecDeque::new();
+        let mut total = 0;
+
+        for split in splits {
+            let split_length = length(split);
+            let joined_length = |current: &VecDeque<&str>, total: usize| {
+                total
+                    + split_length
+                    + if current.is_empty() {
+                        0
+                    } else {
+                        separator_length
+                    }
+            };
+
+            if joined_length(&current, total) > self.chunk_size && !current.is_empty() {
+                self.push_chunk(&mut chunks, &current, separator);
+                // Keep the end of the chunk as the overlap of the next one.
+                while !current.is_empty()
+                    && (total > self.chunk_overlap
+                        || joined_length(&current, total) > self.chunk_size)
+                {
+                    let removed = current.pop_front().map(length).unwrap_or(0);
+                    total -= removed
+                        + if current.is_empty() {
+                            0
+                        } else {
+                            separator_length
+                        };
+                }
+            }
+
+            total = joined_length(&current, total);
+            current.push_back(split);
+        }
+        self.push_chunk(&mut chunks, &current, separator);
+
+        chunks
+    }
+
+    fn push_chunk(&self, chunks: &mut Vec<String>, current: &VecDeque<&str>, separator: &str) {
+        let chunk = current.iter().copied().collect::<Vec<_>>().join(separator);
+        let chunk = if self.trim_chunks {
+            chunk.trim().to_string()
+        } else {
+            chunk
+        };
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+    }
+}
+
+#[async_trait]
+impl TextSplitter for RecursiveCharacterTextSplitter {
+    async fn split_text(&self, text: &str) -> Result<Vec<String>, TextSplitterError> {
+        if self.chunk_overlap >= self.chunk_size {
+            return Err(TextSplitterError::InvalidChunkOverlap {
+                overlap: self.chunk_overlap,
+                size: self.chunk_size,
+            });
+        }
+
+        let separators = self
+            .separators
+            .iter()
+            .map(|separator| {
+                let pattern = if self.separator_regex {
+                    separator.clone()
+                } else {
+                    regex::escape(separator)
+                };
+                Ok((Regex::new(&pattern)?, separator.clone()))
+            })
+            .collect::<Result<Vec<_>, regex::Error>>()?;
+
+        if self.count_tokens {
+            let bpe = self.get_bpe()?;
+            let length = |s: &str| bpe.encode_ordinary(s).len();
+            Ok(self.split(text, &separators, &length))
+        } else {
+            let length = |s: &str| s.chars().count();
+            Ok(self.split(text, &separators, &length))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_recursive_character_splitter() {
+        let splitter = RecursiveCharacterTextSplitter::new(
+            SplitterOptions::default()
+                .with_chunk_size(10)
+                .with_chunk_overlap(4),
+        );
+
+        let chunks = splitter
+            .split_text("Hi.\n\nI'm Harrison.\n\nHow? Are? You?\nOkay then f f f f.")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            chunks,
+            vec![
+                "Hi.",
+                "I'm",
+                "Harrison.",
+                "How? Are?",
+                "Are? You?",
+                "Okay then",
+                "then f f f",
+                "f f f.",
+            ]
+        );
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 10));
+    }
+
+    #[tokio::test]
+    async fn test_recursive_character_splitter_separators() {
+        let splitter =
+            RecursiveCharacterTextSplitter::new(SplitterOptions::default().with_chunk_size(5))
+                .with_separators(vec![",", ""]);
+
+        let chunks = splitter.split_text("ab,cd,abcdefgh").await.unwrap();
+
+        assert_eq!(chunks, vec!["ab,cd", "abcde", "fgh"]);
+    }
+
+    #[tokio::test]
+    async fn test_recursive_character_splitter_regex_separator() {
+        let splitter =
+            RecursiveCharacterTextSplitter::new(SplitterOptions::default().with_chunk_size(20))
+                .with_separators(vec![r"\s*\n\s*", ""])
+                .with_separator_regex(true);
+
+        let chunks = splitter
+            .split_text("one two\n  three\nfour five six seven")
+            .await
+            .unwrap();
+
+        assert_eq!(chunks, vec!["one two three", "four five six seven"]);
+    }
+
+    #[tokio::test]
+    async fn test_recursive_character_splitter_invalid_overlap() {
+        let splitter = RecursiveCharacterTextSplitter::new(
+            SplitterOptions::default()
+                .with_chunk_size(10)
+                .with_chunk_overlap(10),
+        );
+
+        assert!(matches!(
+            splitter.split_text("text").await,
+            Err(TextSplitterError::InvalidChunkOverlap { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recursive_character_splitter_tiktoken() {
+        let splitter = RecursiveCharacterTextSplitter::from_tiktoken(
+            SplitterOptions::default().with_chunk_size(5),
+        );
+        let bpe = splitter.get_bpe().unwrap();
+
+        let chunks = splitter
+            .split_text("The quick brown fox jumps over the lazy dog. The dog sleeps.")
+            .await
+            .unwrap();
+
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|chunk| bpe.encode_ordinary(chunk).len() <= 5));
+    }
+
+    #[tokio::test]
+    async fn test_recursive_character_splitter_rust() {
+        let code = r#"use std::fmt;
+
+/// A point.
+#[derive(Debug)]
+pub struct Point {
+    x: i32,
+}
+
+impl Point {
+    pub fn new(x: i32) -> Self {
+        Point { x }
+    }
+}
+
+fn main() {
+    println!("{:?}", Point::new(1));
+}"#;
+        let splitter = RecursiveCharacterTextSplitter::from_language(
+            CodeLanguage::Rust,
+            SplitterOptions::default()
+                .with_chunk_size(75)
+                .with_trim_chunks(true),
+        );
+
+        let chunks = splitter.split_text(code).await.unwrap();
+
+        assert_eq!(
+            chunks,
+            vec![
+                "use std::fmt;",
+                "/// A point.\n#[derive(Debug)]\npub struct Point {\n    x: i32,\n}",
+                "impl Point {\n    pub fn new(x: i32) -> Self {\n        Point { x }\n    }\n}",
+                "fn main() {\n    println!(\"{:?}\", Point::new(1));\n}",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recursive_character_splitter_python() {
+        let code = "import os\n\n@dataclass\nclass Foo:\n    def bar(self):\n        return 1\n\n\ndef baz():\n    return 2";
+        let splitter = RecursiveCharacterTextSplitter::from_language(
+            CodeLanguage::Python,
+            SplitterOptions::default()
+                .with_chunk_size(60)
+                .with_trim_chunks(true),
+        );
+
+        let chunks = splitter.split_text(code).await.unwrap();
+
+        assert_eq!(
+            chunks,
+            vec![
+                "import os",
+                "@dataclass\nclass Foo:\n    def bar(self):\n        return 1",
+                "def baz():\n    return 2",
+            ]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use thiserror::Error;
+
+use crate::schemas::{Message, MessageTrimmer, MessageType};
+
+use super::{context_size, count_tokens, message_tokens, TOKENS_PER_REPLY};
+
+const DEFAULT_CONTEXT_SIZE: usize = 4096;
+
+/// The prompt doesn't fit in the context window of the model.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "The prompt has {tokens} tokens, more than the {max_tokens} available in the context window"
+)]
+pub struct ContextLengthExceeded {
+    pub tokens: usize,
+    pub max_tokens: usize,
+}
+
+/// The context window of a model, checked before sending it a prompt so that a prompt too
+/// long fails before the call instead of being rejected by the provider.
+///
+/// With `with_trim_history` the oldest messages are dropped until the prompt fits, the
+/// system messages and the last message are always kept.
+///
+/// # Example
+/// ```rust,ignore
+/// let chain = LLMChainBuilder::new()
+///     .prompt(prompt)
+///     .llm(OpenAI::default().with_model("gpt-4"))
+///     .context_window(
+///         ContextWindow::new("gpt-4")
+///             .with_completion_tokens(500)
+///             .with_trim_history(true),
+///     )
+///     .build()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContextWindow {
+    model: String,
+    max_tokens: usize,
+    completion_tokens: usize,
+    trim_history: bool,
+}
+
+impl ContextWindow {
+    /// The context window of the model, 4096 tokens for the models whose size isn't known.
+    pub fn new<S: Into<String>>(model: S) -> Self {
+        let model = model.into();
+        Self {
+            max_tokens: context_size(&model).unwrap_or(DEFAULT_CONTEXT_SIZE),
+            model,
+            completion_tokens: 0,
+            trim_history: false,
+        }
+    }
+
+    /// The size of the context window, in tokens.
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// The tokens reserved for the completion, usually the `max_tokens` of the call.
+    pub fn with_completion_tokens(mut self, completion_tokens: usize) -> Self {
+        self.completion_tokens = completion_tokens;
+        self
+    }
+
+    /// Whether the oldest messages are dropped to fit the window instead of failing.
+    /// Default: false
+    pub fn with_trim_history(mut self, trim_history: bool) -> Self {
+        self.trim_history = trim_history;
+        self
+    }
+
+    /// The tokens available for the prompt.
+    pub fn prompt_tokens(&self) -> usize {
+        self.max_tokens.saturating_sub(self.completion_tokens)
+    }
+
+    pub fn count_tokens(&self, messages: &[Message]) -> usize {
+        count_tokens(&self.model, messages)
+    }
+
+    /// The messages fitting in the window, trimmed if `trim_history` is set.
+    pub fn fit(&self, messages: Vec<Message>) -> Result<Vec<Message>, ContextLengthExceeded> {
+        let tokens = self.count_tokens(&messages);
+        let max_tokens = self.prompt_tokens();
+        if tokens <= max_tokens {
+            return Ok(messages);
+        }
+        let exceeded = ContextLengthExceeded { tokens, max_tokens };
+        if !self.trim_history {
+            return Err(exceeded);
+        }
+
+        let model = self.model.clone();
+        let trimmed = MessageTrimmer::new(max_tokens.saturating_sub(TOKENS_PER_REPLY))
+            .with_token_counter(move |message| message_tokens(&model, message))
+            .trim(&messages);
+        // The last message is the one being answered, the prompt is useless without it.
+        let is_system =
+            |message: &Message| matches!(message.message_type, MessageType::SystemMessage);
+        let last_kept = trimmed.iter().any(|message| !is_system(message))
+            || messages.last().is_none_or(is_system);
+        if !last_kept {
+            return Err(exceeded);
+        }
+        log::debug!(
+            "Trimmed {} messages to fit the context window",
+            messages.len() - trimmed.len()
+        );
+        Ok(trimmed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> Vec<Message> {
+        vec![
+            Message::new_system_message("You are a helpful assistant"),
+            Message::new_human_message("Hello world ".repeat(20)),
+            Message::new_ai_message("Hello world ".repeat(20)),
+            Message::new_human_message("What did I say?"),
+        ]
+    }
+
+    #[test]
+    fn test_context_window_fit() {
+        let window = ContextWindow::new("gpt-4");
+        assert_eq!(window.prompt_tokens(), 8192);
+        assert_eq!(window.fit(history()).unwrap().len(), 4);
+
+        let window = ContextWindow::new("gpt-4")
+            .with_max_tokens(100)
+            .with_completion_tokens(40);
+        let tokens = window.count_tokens(&history());
+        assert_eq!(
+            window.fit(history()).unwrap_err(),
+            ContextLengthExceeded {
+                tokens,
+                max_tokens: 60
+            }
+        );
+    }
+
+    #[test]
+    fn test_context_window_trim_history() {
+        let window = ContextWindow::new("gpt-4")
+            .with_max_tokens(60)
+            .with_trim_history(true);
+        let messages = window.fit(history()).unwrap();
+        let contents: Vec<String> = messages.iter().map(|m| m.content.text()).collect();
+        assert_eq!(
+            contents,
+            vec!["You are a helpful assistant", "What did I say?"]
+        );
+        assert!(window.count_tokens(&messages) <= 60);
+
+        let window = window.with_max_tokens(10);
+        assert!(window.fit(history()).is_err());
+    }
+}
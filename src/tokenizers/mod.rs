@@ -0,0 +1,5 @@
+mod tokenizer;
+pub use tokenizer::*;
+
+mod context_window;
+pub use context_window::*;
@@ -0,0 +1,100 @@
+use tiktoken_rs::{
+    cl100k_base_singleton,
+    model::get_context_size,
+    o200k_base_singleton, p50k_base_singleton, p50k_edit_singleton, r50k_base_singleton,
+    tokenizer::{get_tokenizer, Tokenizer},
+    CoreBPE,
+};
+
+use crate::schemas::Message;
+
+// The tokens OpenAI adds to every message for its role and delimiters, and to prime the reply.
+const TOKENS_PER_MESSAGE: usize = 4;
+pub(crate) const TOKENS_PER_REPLY: usize = 3;
+
+/// Runs `f` with the encoding of the model, `cl100k_base` for the models unknown to
+/// tiktoken, which is a close enough estimate for most of the other providers.
+fn with_encoding<T>(model: &str, f: impl FnOnce(&CoreBPE) -> T) -> T {
+    let bpe = match get_tokenizer(model) {
+        Some(Tokenizer::O200kBase) => o200k_base_singleton(),
+        Some(Tokenizer::P50kBase) => p50k_base_singleton(),
+        Some(Tokenizer::P50kEdit) => p50k_edit_singleton(),
+        Some(Tokenizer::R50kBase | Tokenizer::Gpt2) => r50k_base_singleton(),
+        Some(Tokenizer::Cl100kBase) | None => cl100k_base_singleton(),
+    };
+    let bpe = bpe.lock();
+    f(&bpe)
+}
+
+/// Counts the tokens of a text with the encoding of the model.
+pub fn count_text_tokens(model: &str, text: &str) -> usize {
+    with_encoding(model, |bpe| bpe.encode_with_special_tokens(text).len())
+}
+
+/// Counts the tokens of the messages sent to the model, with the tokens of their roles
+/// and of the start of the reply.
+///
+/// # Example
+/// ```rust,ignore
+/// let tokens = count_tokens("gpt-4o", &[Message::new_human_message("Hello world")]);
+/// ```
+pub fn count_tokens(model: &str, messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .map(|message| message_tokens(model, message))
+        .sum::<usize>()
+        + TOKENS_PER_REPLY
+}
+
+/// The tokens of a message, its content and its role.
+pub(crate) fn message_tokens(model: &str, message: &Message) -> usize {
+    count_text_tokens(model, &message.content.text()) + TOKENS_PER_MESSAGE
+}
+
+/// The context window of the model in tokens, or `None` for the models it isn't known of.
+pub fn context_size(model: &str) -> Option<usize> {
+    let size = match model {
+        model if model.starts_with("gpt-4.1") => 1_047_576,
+        model if model.starts_with("gpt-4-turbo") || model.starts_with("gpt-4o") => 128_000,
+        model if model.starts_with("o1") || model.starts_with("o3") => 200_000,
+        model if model.starts_with("claude-") => 200_000,
+        model if model.starts_with("gemini-1.5-pro") => 2_097_152,
+        model if model.starts_with("gemini-") => 1_048_576,
+        model
+            if [
+                "gpt-", "text-", "code-", "ada", "babbage", "curie", "davinci",
+            ]
+            .iter()
+            .any(|prefix| model.starts_with(prefix)) =>
+        {
+            get_context_size(model)
+        }
+        _ => return None,
+    };
+    Some(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_tokens() {
+        assert_eq!(count_text_tokens("gpt-4", "Hello world"), 2);
+        assert_eq!(
+            count_tokens("gpt-4", &[Message::new_human_message("Hello world")]),
+            2 + TOKENS_PER_MESSAGE + TOKENS_PER_REPLY
+        );
+        // Unknown models are counted with cl100k_base.
+        assert_eq!(count_text_tokens("llama3", "Hello world"), 2);
+    }
+
+    #[test]
+    fn test_context_size() {
+        assert_eq!(context_size("gpt-4"), Some(8192));
+        assert_eq!(context_size("gpt-4-turbo-preview"), Some(128_000));
+        assert_eq!(context_size("gpt-4o-mini"), Some(128_000));
+        assert_eq!(context_size("claude-3-5-sonnet-20240620"), Some(200_000));
+        assert_eq!(context_size("llama3"), None);
+    }
+}
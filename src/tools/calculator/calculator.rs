@@ -0,0 +1,333 @@
+use std::{error::Error, fmt};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::tools::Tool;
+
+/// Calculator evaluates math expressions, so agents don't have to rely on the model for
+/// arithmetic.
+///
+/// Supports `+`, `-`, `*`, `/`, `%`, `^` (power), parentheses, the scientific notation
+/// (`1.5e-3`), the constants `pi` and `e` and the functions `sqrt`, `abs`, `ln`, `log` (base 10), `exp`, `sin`, `cos`, `tan`,
+/// `floor`, `ceil`, `round`, `min` and `max`.
+///
+/// # Example
+/// ```rust,ignore
+/// let result = Calculator::new().call("2 * (3 + sqrt(16))").await?; // "14"
+/// ```
+#[derive(Default)]
+pub struct Calculator {}
+
+impl Calculator {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Evaluates `expression`.
+    pub fn evaluate(expression: &str) -> Result<f64, CalculatorError> {
+        let mut parser = Parser {
+            chars: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+            position: 0,
+            depth: 0,
+        };
+        let value = parser.expression()?;
+        match parser.peek() {
+            Some(c) => Err(CalculatorError(format!("Unexpected character '{}'", c))),
+            None if value.is_finite() => Ok(value),
+            None => Err(CalculatorError("The result is not a finite number".into())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalculatorError(String);
+
+impl fmt::Display for CalculatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid expression: {}", self.0)
+    }
+}
+
+impl Error for CalculatorError {}
+
+/// How deep the expressions can be nested, e.g. in parentheses, so that a long expression
+/// fails instead of overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Recursive descent parser, from the lowest precedence: terms, factors, unary operators
+/// and powers.
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expression(&mut self) -> Result<f64, CalculatorError> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, CalculatorError> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err(CalculatorError("Division by zero".into()));
+                }
+                value /= divisor;
+            } else if self.eat('%') {
+                value %= self.unary()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Every nested expression goes through `unary`, which counts the depth.
+    fn unary(&mut self) -> Result<f64, CalculatorError> {
+        if self.depth == MAX_DEPTH {
+            return Err(CalculatorError(
+                "The expression is nested too deeply".into(),
+            ));
+        }
+        self.depth += 1;
+        let value = self.signed();
+        self.depth -= 1;
+        value
+    }
+
+    fn signed(&mut self) -> Result<f64, CalculatorError> {
+        if self.eat('-') {
+            Ok(-self.unary()?)
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<f64, CalculatorError> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            // Right associative: 2^3^2 is 2^9, and -2^2 is -4.
+            Ok(base.powf(self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> Result<f64, CalculatorError> {
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let value = self.expression()?;
+                if !self.eat(')') {
+                    return Err(CalculatorError("Missing ')'".into()));
+                }
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.identifier(),
+            Some(c) => Err(CalculatorError(format!("Unexpected character '{}'", c))),
+            None => Err(CalculatorError("Unexpected end of expression".into())),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, CalculatorError> {
+        let start = self.position;
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+            self.position += 1;
+        }
+        // The exponent, told apart from the constant e by the digits following it.
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let digits = match self.chars.get(self.position + 1) {
+                Some('+' | '-') => self.position + 2,
+                _ => self.position + 1,
+            };
+            if self.chars.get(digits).is_some_and(char::is_ascii_digit) {
+                self.position = digits;
+                while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+                    self.position += 1;
+                }
+            }
+        }
+        let number: String = self.chars[start..self.position].iter().collect();
+        number
+            .parse()
+            .map_err(|_| CalculatorError(format!("Invalid number '{}'", number)))
+    }
+
+    fn identifier(&mut self) -> Result<f64, CalculatorError> {
+        let start = self.position;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric()) {
+            self.position += 1;
+        }
+        let name: String = self.chars[start..self.position].iter().collect();
+        match name.to_lowercase().as_str() {
+            "pi" => return Ok(std::f64::consts::PI),
+            "e" => return Ok(std::f64::consts::E),
+            _ => {}
+        }
+
+        if !self.eat('(') {
+            return Err(CalculatorError(format!("Unknown constant '{}'", name)));
+        }
+        let mut args = vec![self.expression()?];
+        while self.eat(',') {
+            args.push(self.expression()?);
+        }
+        if !self.eat(')') {
+            return Err(CalculatorError("Missing ')'".into()));
+        }
+
+        let function: fn(f64) -> f64 = match (name.to_lowercase().as_str(), args.as_slice()) {
+            ("min", [a, b]) => return Ok(a.min(*b)),
+            ("max", [a, b]) => return Ok(a.max(*b)),
+            (_, [_]) => match name.to_lowercase().as_str() {
+                "sqrt" => f64::sqrt,
+                "abs" => f64::abs,
+                "ln" => f64::ln,
+                "log" => f64::log10,
+                "exp" => f64::exp,
+                "sin" => f64::sin,
+                "cos" => f64::cos,
+                "tan" => f64::tan,
+                "floor" => f64::floor,
+                "ceil" => f64::ceil,
+                "round" => f64::round,
+                _ => return Err(CalculatorError(format!("Unknown function '{}'", name))),
+            },
+            _ => {
+                return Err(CalculatorError(format!(
+                    "Wrong number of arguments for '{}'",
+                    name
+                )))
+            }
+        };
+        Ok(function(args[0]))
+    }
+}
+
+#[async_trait]
+impl Tool for Calculator {
+    fn name(&self) -> String {
+        String::from("Calculator")
+    }
+
+    fn description(&self) -> String {
+        String::from(
+            "Useful to make calculations, the input should be a math expression, e.g. \
+             2 * (3 + sqrt(16)). Supports + - * / % ^, parentheses, the scientific notation, \
+             pi, e and the functions sqrt, abs, ln, log, exp, sin, cos, tan, floor, ceil, \
+             round, min and max.",
+        )
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "The math expression to evaluate, e.g. 2 * (3 + sqrt(16))"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    async fn parse_input(&self, input: &str) -> Value {
+        match serde_json::from_str::<Value>(input) {
+            Ok(input) if input["expression"].is_string() => input["expression"].clone(),
+            Ok(input) if input["input"].is_string() => input["input"].clone(),
+            _ => Value::String(input.to_string()),
+        }
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let expression = input.as_str().ok_or("Input should be a string")?;
+        let value = Self::evaluate(expression)?;
+        Ok(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(Calculator::evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(Calculator::evaluate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(Calculator::evaluate("2^3^2").unwrap(), 512.0);
+        assert_eq!(Calculator::evaluate("-2^2").unwrap(), -4.0);
+        assert_eq!(Calculator::evaluate("2^-1").unwrap(), 0.5);
+        assert_eq!(Calculator::evaluate("10 % 4 - 1.5").unwrap(), 0.5);
+        assert_eq!(
+            Calculator::evaluate("sqrt(16) + max(2, abs(-3))").unwrap(),
+            7.0
+        );
+        assert_eq!(Calculator::evaluate("round(pi * 100)").unwrap(), 314.0);
+        assert_eq!(Calculator::evaluate("1e-3 * 2E3").unwrap(), 2.0);
+        assert_eq!(Calculator::evaluate("1.5e+2").unwrap(), 150.0);
+        assert_eq!(Calculator::evaluate("e^0").unwrap(), 1.0);
+
+        assert!(Calculator::evaluate("1 / 0").is_err());
+        assert!(Calculator::evaluate("(1 + 2").is_err());
+        assert!(Calculator::evaluate("2 +").is_err());
+        assert!(Calculator::evaluate("foo(2)").is_err());
+        assert!(Calculator::evaluate("min(2)").is_err());
+        assert!(Calculator::evaluate("ln(-1)").is_err());
+        assert!(Calculator::evaluate("2e").is_err());
+
+        let nested = format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert!(Calculator::evaluate(&nested)
+            .unwrap_err()
+            .to_string()
+            .contains("nested too deeply"));
+        assert!(Calculator::evaluate(&"-".repeat(10_000)).is_err());
+        let nested = format!("{}1{}", "(".repeat(20), ")".repeat(20));
+        assert_eq!(Calculator::evaluate(&nested).unwrap(), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_calculator_tool() {
+        let calculator = Calculator::new();
+        assert_eq!(calculator.call("2 * (3 + 4)").await.unwrap(), "14");
+        assert_eq!(
+            calculator
+                .call(r#"{"expression": "1.5 * 2"}"#)
+                .await
+                .unwrap(),
+            "3"
+        );
+        assert!(calculator.call("2 * x").await.is_err());
+        assert!(!calculator.description().contains('"'));
+    }
+}
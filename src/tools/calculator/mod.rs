@@ -0,0 +1,2 @@
+mod calculator;
+pub use calculator::*;
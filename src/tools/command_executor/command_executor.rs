@@ -6,6 +6,9 @@ use serde_json::{json, Value};
 
 use crate::tools::Tool;
 
+/// Runs the shell commands of the agent on the machine, behind the `command-executor`
+/// feature as the commands come from the LLM. Only give it to the agents running in a
+/// sandbox.
 pub struct CommandExecutor {
     platform: String,
 }
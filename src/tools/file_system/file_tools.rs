@@ -0,0 +1,485 @@
+use std::{
+    error::Error,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::tools::Tool;
+
+const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Configuration shared by the file system tools.
+///
+/// Every path received by the tools is resolved relative to `root`, and any path that
+/// would end up outside of it (through `..` or a symlink) is rejected.
+///
+/// # Example
+/// ```rust,ignore
+/// let jail = FileSystemJail::new("./my_project")?
+///     .with_max_file_size(64 * 1024)
+///     .with_read_only(true);
+/// let tools = file_system_tools(jail);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FileSystemJail {
+    root: PathBuf,
+    max_file_size: u64,
+    read_only: bool,
+}
+
+impl FileSystemJail {
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self, Box<dyn Error>> {
+        let root = std::fs::canonicalize(root)?;
+        if !root.is_dir() {
+            return Err(format!("{} is not a directory", root.display()).into());
+        }
+        Ok(Self {
+            root,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            read_only: false,
+        })
+    }
+
+    /// Maximum size in bytes of a file that can be read or written. Default: 1MB
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// When set, the write tool refuses every request.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolves `path` inside the root, returning an error if it escapes it.
+    pub fn resolve<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf, String> {
+        let path = path.as_ref();
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+
+        let mut resolved = self.root.clone();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::CurDir | Component::RootDir => {}
+                Component::ParentDir => {
+                    if !resolved.pop() || !resolved.starts_with(&self.root) {
+                        return Err(format!("{} is outside of the root", path.display()));
+                    }
+                }
+                Component::Prefix(_) => {
+                    return Err(format!("{} is not a valid path", path.display()));
+                }
+            }
+        }
+
+        // Symlinks could still point outside the root, so every existing component is
+        // checked. A dangling symlink is rejected, a write through it would create its
+        // target wherever it points.
+        let mut current = self.root.clone();
+        for component in resolved
+            .strip_prefix(&self.root)
+            .unwrap_or(&resolved)
+            .components()
+        {
+            current.push(component);
+            match std::fs::symlink_metadata(&current) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    let target = std::fs::canonicalize(&current)
+                        .map_err(|_| format!("{} is a dangling symlink", path.display()))?;
+                    if !target.starts_with(&self.root) {
+                        return Err(format!("{} is outside of the root", path.display()));
+                    }
+                }
+                Ok(_) => {}
+                // The rest of the path doesn't exist either.
+                Err(_) => break,
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    fn display(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if relative.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            relative.display().to_string()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PathInput {
+    #[serde(default)]
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct WriteInput {
+    path: String,
+    content: String,
+    #[serde(default)]
+    append: bool,
+}
+
+fn path_input(input: &str) -> Value {
+    match serde_json::from_str::<Value>(input) {
+        Ok(value) if value.is_object() => value,
+        _ => json!({ "path": input.trim() }),
+    }
+}
+
+/// Reads the content of a file inside the jail.
+pub struct ReadFileTool {
+    jail: FileSystemJail,
+}
+
+impl ReadFileTool {
+    pub fn new(jail: FileSystemJail) -> Self {
+        Self { jail }
+    }
+}
+
+#[async_trait]
+impl Tool for ReadFileTool {
+    fn name(&self) -> String {
+        String::from("read_file")
+    }
+
+    fn description(&self) -> String {
+        String::from("Read a file from the project. Input should be the path of the file, relative to the project root.")
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file, relative to the project root"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn parse_input(&self, input: &str) -> Value {
+        path_input(input)
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let input: PathInput = serde_json::from_value(input)?;
+        let path = match self.jail.resolve(&input.path) {
+            Ok(path) => path,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+
+        let metadata = match tokio::fs::metadata(&path).await {
+            Ok(metadata) => metadata,
+            Err(e) => return Ok(format!("Error: cannot read {}: {}", input.path, e)),
+        };
+        if !metadata.is_file() {
+            return Ok(format!("Error: {} is not a file", input.path));
+        }
+        if metadata.len() > self.jail.max_file_size {
+            return Ok(format!(
+                "Error: {} is {} bytes, the maximum allowed size is {} bytes",
+                input.path,
+                metadata.len(),
+                self.jail.max_file_size
+            ));
+        }
+
+        let bytes = tokio::fs::read(&path).await?;
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+}
+
+/// Writes (or appends) content to a file inside the jail, creating parent directories
+/// when needed.
+pub struct WriteFileTool {
+    jail: FileSystemJail,
+}
+
+impl WriteFileTool {
+    pub fn new(jail: FileSystemJail) -> Self {
+        Self { jail }
+    }
+}
+
+#[async_trait]
+impl Tool for WriteFileTool {
+    fn name(&self) -> String {
+        String::from("write_file")
+    }
+
+    fn description(&self) -> String {
+        String::from(
+            r#"Write content to a file in the project. Input should be a json object with the keys "path" (relative to the project root), "content" and optionally "append" (true to append instead of overwriting)."#,
+        )
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file, relative to the project root"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Content to write"
+                },
+                "append": {
+                    "type": "boolean",
+                    "description": "Append to the file instead of overwriting it",
+                    "default": false
+                }
+            },
+            "required": ["path", "content"]
+        })
+    }
+
+    async fn parse_input(&self, input: &str) -> Value {
+        serde_json::from_str(input).unwrap_or(Value::Null)
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        if self.jail.read_only {
+            return Ok("Error: the file system is read-only".to_string());
+        }
+
+        let input: WriteInput = serde_json::from_value(input)?;
+        let path = match self.jail.resolve(&input.path) {
+            Ok(path) => path,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+
+        let current_size = match tokio::fs::metadata(&path).await {
+            Ok(metadata) if input.append => metadata.len(),
+            _ => 0,
+        };
+        let new_size = current_size + input.content.len() as u64;
+        if new_size > self.jail.max_file_size {
+            return Ok(format!(
+                "Error: the file would be {} bytes, the maximum allowed size is {} bytes",
+                new_size, self.jail.max_file_size
+            ));
+        }
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        if input.append {
+            use tokio::io::AsyncWriteExt;
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await?;
+            file.write_all(input.content.as_bytes()).await?;
+        } else {
+            tokio::fs::write(&path, input.content.as_bytes()).await?;
+        }
+
+        Ok(format!(
+            "Wrote {} bytes to {}",
+            input.content.len(),
+            self.jail.display(&path)
+        ))
+    }
+}
+
+/// Lists the entries of a directory inside the jail.
+pub struct ListDirectoryTool {
+    jail: FileSystemJail,
+}
+
+impl ListDirectoryTool {
+    pub fn new(jail: FileSystemJail) -> Self {
+        Self { jail }
+    }
+}
+
+#[async_trait]
+impl Tool for ListDirectoryTool {
+    fn name(&self) -> String {
+        String::from("list_directory")
+    }
+
+    fn description(&self) -> String {
+        String::from("List the files and directories of a directory in the project. Input should be the path of the directory, relative to the project root. Use . for the root.")
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the directory, relative to the project root"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn parse_input(&self, input: &str) -> Value {
+        path_input(input)
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let input: PathInput = serde_json::from_value(input)?;
+        let path = match self.jail.resolve(&input.path) {
+            Ok(path) => path,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+
+        let mut entries = match tokio::fs::read_dir(&path).await {
+            Ok(entries) => entries,
+            Err(e) => return Ok(format!("Error: cannot list {}: {}", input.path, e)),
+        };
+
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let mut name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type().await?.is_dir() {
+                name.push('/');
+            }
+            names.push(name);
+        }
+        names.sort();
+
+        if names.is_empty() {
+            return Ok(format!("{} is empty", self.jail.display(&path)));
+        }
+        Ok(names.join("\n"))
+    }
+}
+
+/// Returns the read, list and (unless the jail is read-only) write tools.
+pub fn file_system_tools(jail: FileSystemJail) -> Vec<Arc<dyn Tool>> {
+    let mut tools: Vec<Arc<dyn Tool>> = vec![
+        Arc::new(ReadFileTool::new(jail.clone())),
+        Arc::new(ListDirectoryTool::new(jail.clone())),
+    ];
+    if !jail.read_only {
+        tools.push(Arc::new(WriteFileTool::new(jail)));
+    }
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("langchain_rust_fs_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_resolve_stays_in_root() {
+        let root = test_root("resolve");
+        let jail = FileSystemJail::new(&root).unwrap();
+
+        assert!(jail.resolve("src/main.rs").is_ok());
+        assert!(jail.resolve("src/../main.rs").is_ok());
+        assert_eq!(
+            jail.resolve("/etc/passwd").unwrap(),
+            jail.root().join("etc/passwd")
+        );
+        assert!(jail.resolve("../outside").is_err());
+        assert!(jail.resolve("a/../../outside").is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_write_read_list() {
+        let root = test_root("rw");
+        let jail = FileSystemJail::new(&root).unwrap().with_max_file_size(10);
+
+        let write = WriteFileTool::new(jail.clone());
+        let input = json!({"path": "dir/a.txt", "content": "hello"});
+        let result = write.call(&input.to_string()).await.unwrap();
+        assert_eq!(result, "Wrote 5 bytes to dir/a.txt");
+
+        let input = json!({"path": "dir/a.txt", "content": "more", "append": true});
+        write.call(&input.to_string()).await.unwrap();
+
+        let input = json!({"path": "dir/a.txt", "content": "too long", "append": true});
+        let result = write.call(&input.to_string()).await.unwrap();
+        assert!(result.starts_with("Error: the file would be 17 bytes"));
+
+        let read = ReadFileTool::new(jail.clone());
+        assert_eq!(read.call("dir/a.txt").await.unwrap(), "hellomore");
+
+        let list = ListDirectoryTool::new(jail.clone());
+        assert_eq!(list.call(".").await.unwrap(), "dir/");
+        assert_eq!(list.call("dir").await.unwrap(), "a.txt");
+
+        let result = read.call("../../etc/passwd").await.unwrap();
+        assert!(result.contains("outside of the root"));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let root = test_root("ro");
+        let jail = FileSystemJail::new(&root).unwrap().with_read_only(true);
+
+        let write = WriteFileTool::new(jail.clone());
+        let input = json!({"path": "a.txt", "content": "hello"});
+        let result = write.call(&input.to_string()).await.unwrap();
+        assert_eq!(result, "Error: the file system is read-only");
+        assert!(!root.join("a.txt").exists());
+        assert_eq!(file_system_tools(jail).len(), 2);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_through_symlinks() {
+        let root = test_root("symlinks");
+        let outside = test_root("symlinks_outside");
+        let jail = FileSystemJail::new(&root).unwrap();
+        let write = WriteFileTool::new(jail.clone());
+
+        std::os::unix::fs::symlink(outside.join("created.txt"), root.join("dangling")).unwrap();
+        let input = json!({"path": "dangling", "content": "escaped"});
+        let result = write.call(&input.to_string()).await.unwrap();
+        assert!(result.contains("dangling symlink"), "{}", result);
+        assert!(!outside.join("created.txt").exists());
+
+        std::os::unix::fs::symlink(&outside, root.join("out")).unwrap();
+        let input = json!({"path": "out/a.txt", "content": "escaped"});
+        let result = write.call(&input.to_string()).await.unwrap();
+        assert!(result.contains("outside of the root"), "{}", result);
+        assert!(!outside.join("a.txt").exists());
+
+        std::fs::create_dir(root.join("dir")).unwrap();
+        std::os::unix::fs::symlink(root.join("dir"), root.join("inside")).unwrap();
+        assert!(jail.resolve("inside/a.txt").is_ok());
+
+        std::fs::remove_dir_all(root).unwrap();
+        std::fs::remove_dir_all(outside).unwrap();
+    }
+}
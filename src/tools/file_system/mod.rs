@@ -0,0 +1,2 @@
+mod file_tools;
+pub use file_tools::*;
@@ -0,0 +1,5 @@
+mod openai;
+pub use openai::*;
+
+mod stability;
+pub use stability::*;
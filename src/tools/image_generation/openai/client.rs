@@ -0,0 +1,149 @@
+use std::error::Error;
+
+use async_openai::types::{CreateImageRequestArgs, Image, ResponseFormat};
+use async_openai::Client;
+pub use async_openai::{
+    config::{Config, OpenAIConfig},
+    types::{ImageModel, ImageQuality, ImageSize, ImageStyle},
+};
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::tools::Tool;
+
+/// Generates images with DALL-E.
+///
+/// By default the urls of the generated images are returned (they expire after an hour).
+/// With `with_directory` the images are downloaded and the paths of the files are
+/// returned instead.
+#[derive(Clone)]
+pub struct ImageGenerationOpenAI<C: Config> {
+    config: C,
+    model: ImageModel,
+    size: ImageSize,
+    quality: ImageQuality,
+    style: ImageStyle,
+    n: u8,
+    directory: Option<String>,
+}
+
+impl<C: Config> ImageGenerationOpenAI<C> {
+    pub fn new(config: C) -> Self {
+        Self {
+            config,
+            model: ImageModel::DallE3,
+            size: ImageSize::S1024x1024,
+            quality: ImageQuality::Standard,
+            style: ImageStyle::Vivid,
+            n: 1,
+            directory: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: ImageModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_size(mut self, size: ImageSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_quality(mut self, quality: ImageQuality) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    pub fn with_style(mut self, style: ImageStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Number of images generated for each prompt. DALL-E 3 only supports 1.
+    pub fn with_n(mut self, n: u8) -> Self {
+        self.n = n;
+        self
+    }
+
+    /// Save the images in this directory and return their paths instead of the urls.
+    pub fn with_directory<S: Into<String>>(mut self, directory: S) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    pub fn with_config(mut self, config: C) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl Default for ImageGenerationOpenAI<OpenAIConfig> {
+    fn default() -> Self {
+        Self::new(OpenAIConfig::default())
+    }
+}
+
+#[async_trait]
+impl<C: Config + Send + Sync> Tool for ImageGenerationOpenAI<C> {
+    fn name(&self) -> String {
+        "ImageGenerationOpenAI".to_string()
+    }
+
+    fn description(&self) -> String {
+        String::from(
+            "A wrapper around OpenAI DALL-E. \
+            Useful for when you need to generate an image from a description. \
+            Input should be a detailed description of the image",
+        )
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let input = input.as_str().ok_or("Invalid input")?;
+        let client = Client::with_config(self.config.clone());
+
+        let request = CreateImageRequestArgs::default()
+            .prompt(input)
+            .model(self.model.clone())
+            .size(self.size)
+            .quality(self.quality.clone())
+            .style(self.style.clone())
+            .n(self.n)
+            .response_format(ResponseFormat::Url)
+            .build()?;
+
+        let response = client.images().create(request).await?;
+
+        if let Some(directory) = &self.directory {
+            let paths = response.save(directory).await?;
+            return Ok(paths
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+
+        let urls: Vec<String> = response
+            .data
+            .iter()
+            .filter_map(|image| match image.as_ref() {
+                Image::Url { url, .. } => Some(url.clone()),
+                Image::B64Json { .. } => None,
+            })
+            .collect();
+        Ok(urls.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn openai_image_generation_tool() {
+        let dalle = ImageGenerationOpenAI::default();
+        let s = dalle.call("A watercolor of a llama").await.unwrap();
+        println!("{}", s);
+    }
+}
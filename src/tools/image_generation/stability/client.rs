@@ -0,0 +1,200 @@
+use std::{error::Error, path::Path};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::tools::Tool;
+
+#[derive(Deserialize)]
+struct StabilityResponse {
+    artifacts: Vec<StabilityArtifact>,
+}
+
+#[derive(Deserialize)]
+struct StabilityArtifact {
+    base64: String,
+    #[serde(default)]
+    seed: u64,
+}
+
+/// Generates images with Stability AI. The images are saved as png files in the
+/// configured directory and their paths are returned.
+#[derive(Clone)]
+pub struct ImageGenerationStability {
+    api_key: String,
+    engine: String,
+    width: u32,
+    height: u32,
+    samples: u32,
+    steps: u32,
+    cfg_scale: f32,
+    directory: String,
+    base_url: String,
+}
+
+impl ImageGenerationStability {
+    pub fn new<S: Into<String>>(api_key: S) -> Self {
+        Self {
+            api_key: api_key.into(),
+            engine: "stable-diffusion-xl-1024-v1-0".to_string(),
+            width: 1024,
+            height: 1024,
+            samples: 1,
+            steps: 30,
+            cfg_scale: 7.0,
+            directory: "./data/images".to_string(),
+            base_url: "https://api.stability.ai".to_string(),
+        }
+    }
+
+    pub fn with_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    pub fn with_engine<S: Into<String>>(mut self, engine: S) -> Self {
+        self.engine = engine.into();
+        self
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Number of images generated for each prompt.
+    pub fn with_samples(mut self, samples: u32) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Number of diffusion steps, more steps give more detailed images.
+    pub fn with_steps(mut self, steps: u32) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// How strictly the image follows the prompt.
+    pub fn with_cfg_scale(mut self, cfg_scale: f32) -> Self {
+        self.cfg_scale = cfg_scale;
+        self
+    }
+
+    pub fn with_directory<S: Into<String>>(mut self, directory: S) -> Self {
+        self.directory = directory.into();
+        self
+    }
+
+    pub fn with_base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+}
+
+impl Default for ImageGenerationStability {
+    fn default() -> Self {
+        Self::new(std::env::var("STABILITY_API_KEY").unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl Tool for ImageGenerationStability {
+    fn name(&self) -> String {
+        "ImageGenerationStability".to_string()
+    }
+
+    fn description(&self) -> String {
+        String::from(
+            "A wrapper around Stability AI image generation. \
+            Useful for when you need to generate an image from a description. \
+            Input should be a detailed description of the image",
+        )
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let input = input.as_str().ok_or("Invalid input")?;
+
+        let response = reqwest::Client::new()
+            .post(format!(
+                "{}/v1/generation/{}/text-to-image",
+                self.base_url, self.engine
+            ))
+            .bearer_auth(&self.api_key)
+            .header("Accept", "application/json")
+            .json(&json!({
+                "text_prompts": [{ "text": input }],
+                "width": self.width,
+                "height": self.height,
+                "samples": self.samples,
+                "steps": self.steps,
+                "cfg_scale": self.cfg_scale,
+            }))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await?;
+            return Err(format!("Stability Error {}: {}", status.as_u16(), body).into());
+        }
+        let response: StabilityResponse = response.json().await?;
+
+        tokio::fs::create_dir_all(&self.directory).await?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_millis();
+        let mut paths = Vec::new();
+        for (i, artifact) in response.artifacts.iter().enumerate() {
+            let path = Path::new(&self.directory)
+                .join(format!("{}_{}_{}.png", timestamp, artifact.seed, i));
+            tokio::fs::write(&path, STANDARD.decode(&artifact.base64)?).await?;
+            paths.push(path.display().to_string());
+        }
+        Ok(paths.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stability_saves_images() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/generation/sdxl/text-to-image")
+            .match_header("authorization", "Bearer key")
+            .with_body(
+                r#"{"artifacts": [{"base64": "cG5n", "seed": 7, "finishReason": "SUCCESS"}]}"#,
+            )
+            .create_async()
+            .await;
+
+        let directory =
+            std::env::temp_dir().join(format!("langchain_rust_stability_{}", std::process::id()));
+        let tool = ImageGenerationStability::new("key")
+            .with_base_url(server.url())
+            .with_engine("sdxl")
+            .with_directory(directory.display().to_string());
+
+        let path = tool.call("A llama").await.unwrap();
+        assert!(path.ends_with("_7_0.png"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"png");
+        mock.assert_async().await;
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_stability_description() {
+        assert_eq!(
+            ImageGenerationStability::new("key").description(),
+            "A wrapper around Stability AI image generation. Useful for when you need to \
+             generate an image from a description. Input should be a detailed description of \
+             the image"
+        );
+    }
+}
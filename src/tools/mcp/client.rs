@@ -0,0 +1,313 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::tools::Tool;
+
+use super::{McpError, McpResourceTool, McpTool, McpTransport, SseTransport, StdioTransport};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// A tool exposed by an MCP server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpToolInfo {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(rename = "inputSchema", default)]
+    pub input_schema: Value,
+}
+
+/// A resource exposed by an MCP server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpResourceInfo {
+    pub uri: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(rename = "mimeType", default)]
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum McpContent {
+    Text {
+        text: String,
+    },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    Resource {
+        resource: Value,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+impl McpContent {
+    /// A text representation of the content, suitable as an agent observation.
+    pub fn to_text(&self) -> String {
+        match self {
+            McpContent::Text { text } => text.clone(),
+            McpContent::Image { mime_type, .. } => format!("[image: {}]", mime_type),
+            McpContent::Resource { resource } => resource["text"]
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|| format!("[resource: {}]", resource["uri"])),
+            McpContent::Unknown => String::new(),
+        }
+    }
+}
+
+/// The result of calling a tool on an MCP server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpToolResult {
+    #[serde(default)]
+    pub content: Vec<McpContent>,
+    #[serde(rename = "isError", default)]
+    pub is_error: bool,
+}
+
+impl McpToolResult {
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .map(|c| c.to_text())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Client for a Model Context Protocol server.
+///
+/// The client discovers the tools and resources of the server and exposes each one of
+/// them as a `Tool`, so they can be used by any agent.
+///
+/// # Example
+/// ```rust,ignore
+/// let client = Arc::new(McpClient::stdio("npx", &["-y", "@modelcontextprotocol/server-filesystem", "."]).await?);
+/// let tools = client.tools().await?;
+/// let agent = OpenAiToolAgentBuilder::new().tools(&tools).build(llm)?;
+/// ```
+pub struct McpClient {
+    transport: Box<dyn McpTransport>,
+    server_info: Value,
+}
+
+impl McpClient {
+    /// Creates the client and runs the initialization handshake with the server.
+    pub async fn new<T: Into<Box<dyn McpTransport>>>(transport: T) -> Result<Self, McpError> {
+        let transport = transport.into();
+        let server_info = transport
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "langchain-rust",
+                        "version": env!("CARGO_PKG_VERSION")
+                    }
+                }),
+            )
+            .await?;
+        transport
+            .notify("notifications/initialized", json!({}))
+            .await?;
+
+        Ok(Self {
+            transport,
+            server_info,
+        })
+    }
+
+    /// Launches the server as a child process and connects to it through stdio.
+    pub async fn stdio<S: AsRef<str>>(command: &str, args: &[S]) -> Result<Self, McpError> {
+        Self::new(StdioTransport::new(command, args)?).await
+    }
+
+    /// Connects to a server through HTTP with Server-Sent Events.
+    pub async fn sse<S: AsRef<str>>(url: S) -> Result<Self, McpError> {
+        Self::new(SseTransport::connect(url).await?).await
+    }
+
+    /// The result of the initialization, with the server capabilities and information.
+    pub fn server_info(&self) -> &Value {
+        &self.server_info
+    }
+
+    pub async fn list_tools(&self) -> Result<Vec<McpToolInfo>, McpError> {
+        self.list("tools/list", "tools").await
+    }
+
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<McpToolResult, McpError> {
+        let result = self
+            .transport
+            .request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub async fn list_resources(&self) -> Result<Vec<McpResourceInfo>, McpError> {
+        // Servers without resources respond with "method not found".
+        if self.server_info["capabilities"].get("resources").is_none() {
+            return Ok(Vec::new());
+        }
+        self.list("resources/list", "resources").await
+    }
+
+    /// Reads a resource, returning its text contents.
+    pub async fn read_resource(&self, uri: &str) -> Result<String, McpError> {
+        let result = self
+            .transport
+            .request("resources/read", json!({ "uri": uri }))
+            .await?;
+        let contents = result["contents"]
+            .as_array()
+            .map(|contents| {
+                contents
+                    .iter()
+                    .map(|c| match c["text"].as_str() {
+                        Some(text) => text.to_string(),
+                        None => format!(
+                            "[binary content: {}]",
+                            c["mimeType"].as_str().unwrap_or("unknown")
+                        ),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default();
+        Ok(contents)
+    }
+
+    /// Returns a `Tool` for every tool and resource of the server.
+    pub async fn tools(self: &Arc<Self>) -> Result<Vec<Arc<dyn Tool>>, McpError> {
+        let mut tools: Vec<Arc<dyn Tool>> = Vec::new();
+        for info in self.list_tools().await? {
+            tools.push(Arc::new(McpTool::new(self.clone(), info)));
+        }
+        for info in self.list_resources().await? {
+            tools.push(Arc::new(McpResourceTool::new(self.clone(), info)));
+        }
+        Ok(tools)
+    }
+
+    async fn list<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: &str,
+        key: &str,
+    ) -> Result<Vec<T>, McpError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let mut result = self.transport.request(method, params).await?;
+            let page: Vec<T> = serde_json::from_value(result[key].take())?;
+            items.extend(page);
+
+            match result["nextCursor"].as_str() {
+                Some(next) if !next.is_empty() => cursor = Some(next.to_string()),
+                _ => break,
+            }
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+
+    pub(crate) struct MockTransport {}
+
+    #[async_trait]
+    impl McpTransport for MockTransport {
+        async fn request(&self, method: &str, params: Value) -> Result<Value, McpError> {
+            match method {
+                "initialize" => Ok(json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": { "tools": {}, "resources": {} },
+                    "serverInfo": { "name": "mock", "version": "1.0" }
+                })),
+                "tools/list" if params.get("cursor").is_none() => Ok(json!({
+                    "tools": [{
+                        "name": "add",
+                        "description": "Adds two numbers",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": { "a": { "type": "number" }, "b": { "type": "number" } },
+                            "required": ["a", "b"]
+                        }
+                    }],
+                    "nextCursor": "page2"
+                })),
+                "tools/list" => Ok(json!({
+                    "tools": [{ "name": "fail", "inputSchema": { "type": "object" } }]
+                })),
+                "tools/call" if params["name"] == "add" => {
+                    let sum = params["arguments"]["a"].as_f64().unwrap_or_default()
+                        + params["arguments"]["b"].as_f64().unwrap_or_default();
+                    Ok(json!({ "content": [{ "type": "text", "text": sum.to_string() }] }))
+                }
+                "tools/call" => Ok(json!({
+                    "content": [{ "type": "text", "text": "something went wrong" }],
+                    "isError": true
+                })),
+                "resources/list" => Ok(json!({
+                    "resources": [{ "uri": "file:///readme.md", "name": "readme" }]
+                })),
+                "resources/read" => Ok(json!({
+                    "contents": [{ "uri": params["uri"], "text": "# Readme" }]
+                })),
+                _ => Err(McpError::Rpc {
+                    code: -32601,
+                    message: "Method not found".to_string(),
+                }),
+            }
+        }
+
+        async fn notify(&self, _method: &str, _params: Value) -> Result<(), McpError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mcp_client() {
+        let client = McpClient::new(MockTransport {}).await.unwrap();
+        assert_eq!(client.server_info()["serverInfo"]["name"], "mock");
+
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(
+            tools.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+            vec!["add", "fail"]
+        );
+
+        let result = client
+            .call_tool("add", json!({ "a": 1, "b": 2 }))
+            .await
+            .unwrap();
+        assert_eq!(result.text(), "3");
+        assert!(!result.is_error);
+
+        let resources = client.list_resources().await.unwrap();
+        assert_eq!(resources[0].uri, "file:///readme.md");
+        assert_eq!(
+            client.read_resource("file:///readme.md").await.unwrap(),
+            "# Readme"
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum McpError {
+    #[error("MCP IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("MCP serde error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("MCP request error: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("MCP server error {code}: {message}")]
+    Rpc { code: i64, message: String },
+
+    #[error("MCP transport error: {0}")]
+    Transport(String),
+
+    #[error("MCP request timed out")]
+    Timeout,
+}
@@ -0,0 +1,126 @@
+use std::{error::Error, sync::Arc};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::tools::Tool;
+
+use super::{McpClient, McpResourceInfo, McpToolInfo};
+
+/// A tool of an MCP server. The arguments are forwarded as they are, and the parameters
+/// are the input schema published by the server.
+pub struct McpTool {
+    client: Arc<McpClient>,
+    info: McpToolInfo,
+}
+
+impl McpTool {
+    pub fn new(client: Arc<McpClient>, info: McpToolInfo) -> Self {
+        Self { client, info }
+    }
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn name(&self) -> String {
+        self.info.name.clone()
+    }
+
+    fn description(&self) -> String {
+        self.info.description.clone().unwrap_or_default()
+    }
+
+    fn parameters(&self) -> Value {
+        if self.info.input_schema.is_object() {
+            self.info.input_schema.clone()
+        } else {
+            json!({ "type": "object", "properties": {} })
+        }
+    }
+
+    async fn parse_input(&self, input: &str) -> Value {
+        match serde_json::from_str::<Value>(input) {
+            Ok(input) if input.is_object() => input,
+            _ => json!({ "input": input }),
+        }
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let result = self.client.call_tool(&self.info.name, input).await?;
+        if result.is_error {
+            // Returned as an observation so the agent can react to it.
+            return Ok(format!("Error: {}", result.text()));
+        }
+        Ok(result.text())
+    }
+}
+
+/// A resource of an MCP server, exposed as a tool without arguments that reads it.
+pub struct McpResourceTool {
+    client: Arc<McpClient>,
+    info: McpResourceInfo,
+}
+
+impl McpResourceTool {
+    pub fn new(client: Arc<McpClient>, info: McpResourceInfo) -> Self {
+        Self { client, info }
+    }
+}
+
+#[async_trait]
+impl Tool for McpResourceTool {
+    fn name(&self) -> String {
+        let name: String = self
+            .info
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("read_{}", name)
+    }
+
+    fn description(&self) -> String {
+        match &self.info.description {
+            Some(description) => format!("Read the resource {}: {}", self.info.uri, description),
+            None => format!("Read the resource {}", self.info.uri),
+        }
+    }
+
+    fn parameters(&self) -> Value {
+        json!({ "type": "object", "properties": {} })
+    }
+
+    async fn run(&self, _input: Value) -> Result<String, Box<dyn Error>> {
+        Ok(self.client.read_resource(&self.info.uri).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::mcp::client::tests::MockTransport;
+
+    #[tokio::test]
+    async fn test_mcp_tools() {
+        let client = Arc::new(McpClient::new(MockTransport {}).await.unwrap());
+        let tools = client.tools().await.unwrap();
+        assert_eq!(
+            tools.iter().map(|t| t.name()).collect::<Vec<_>>(),
+            vec!["add", "fail", "read_readme"]
+        );
+
+        let add = &tools[0];
+        assert_eq!(add.description(), "Adds two numbers");
+        assert_eq!(add.parameters()["required"], json!(["a", "b"]));
+        assert_eq!(add.call(r#"{"a": 2, "b": 5}"#).await.unwrap(), "7");
+
+        let fail = &tools[1];
+        assert_eq!(
+            fail.call("{}").await.unwrap(),
+            "Error: something went wrong"
+        );
+
+        let readme = &tools[2];
+        assert_eq!(readme.call("").await.unwrap(), "# Readme");
+    }
+}
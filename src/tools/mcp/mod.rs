@@ -0,0 +1,11 @@
+mod client;
+pub use client::*;
+
+mod transport;
+pub use transport::*;
+
+mod mcp_tool;
+pub use mcp_tool::*;
+
+mod error;
+pub use error::*;
@@ -0,0 +1,357 @@
+use std::{
+    collections::HashMap,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest_eventsource::{Event, EventSource};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::oneshot,
+    task::JoinHandle,
+};
+
+use super::McpError;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A transport sends JSON-RPC messages to an MCP server.
+#[async_trait]
+pub trait McpTransport: Send + Sync {
+    /// Sends a request and returns the `result` of the response.
+    async fn request(&self, method: &str, params: Value) -> Result<Value, McpError>;
+
+    /// Sends a notification, which has no response.
+    async fn notify(&self, method: &str, params: Value) -> Result<(), McpError>;
+}
+
+impl<T> From<T> for Box<dyn McpTransport>
+where
+    T: McpTransport + 'static,
+{
+    fn from(transport: T) -> Self {
+        Box::new(transport)
+    }
+}
+
+fn request_message(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn notification_message(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn parse_response(mut response: Value) -> Result<Value, McpError> {
+    if let Some(error) = response.get("error") {
+        return Err(McpError::Rpc {
+            code: error["code"].as_i64().unwrap_or_default(),
+            message: error["message"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+    Ok(response["result"].take())
+}
+
+/// Talks to an MCP server launched as a child process, exchanging newline delimited
+/// JSON-RPC messages over its stdin and stdout.
+///
+/// Requests are sent one at a time. The child process is killed when the transport is
+/// dropped.
+pub struct StdioTransport {
+    io: tokio::sync::Mutex<(ChildStdin, BufReader<ChildStdout>)>,
+    _child: Child,
+    next_id: AtomicU64,
+    timeout: Duration,
+}
+
+impl StdioTransport {
+    pub fn new<S: AsRef<str>>(command: &str, args: &[S]) -> Result<Self, McpError> {
+        let mut command = Command::new(command);
+        command.args(args.iter().map(|a| a.as_ref()));
+        Self::from_command(command)
+    }
+
+    /// Spawns the server from a `Command`, useful to set environment variables or the
+    /// working directory. Stdin and stdout are overridden.
+    pub fn from_command(mut command: Command) -> Result<Self, McpError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| McpError::Transport("cannot open stdin".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| McpError::Transport("cannot open stdout".to_string()))?;
+
+        Ok(Self {
+            io: tokio::sync::Mutex::new((stdin, BufReader::new(stdout))),
+            _child: child,
+            next_id: AtomicU64::new(1),
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn write(stdin: &mut ChildStdin, message: &Value) -> Result<(), McpError> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        stdin.write_all(line.as_bytes()).await?;
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn read_response(
+        stdin: &mut ChildStdin,
+        stdout: &mut BufReader<ChildStdout>,
+        id: u64,
+    ) -> Result<Value, McpError> {
+        loop {
+            let mut line = String::new();
+            if stdout.read_line(&mut line).await? == 0 {
+                return Err(McpError::Transport("the server closed stdout".to_string()));
+            }
+
+            let message: Value = match serde_json::from_str(line.trim()) {
+                Ok(message) => message,
+                Err(_) => {
+                    log::debug!("Ignoring MCP server output: {}", line.trim());
+                    continue;
+                }
+            };
+
+            match (message.get("id"), message.get("method")) {
+                // A request from the server, only pings are supported.
+                (Some(server_id), Some(method)) => {
+                    let response = if method == "ping" {
+                        json!({ "jsonrpc": "2.0", "id": server_id, "result": {} })
+                    } else {
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": server_id,
+                            "error": { "code": -32601, "message": "Method not found" }
+                        })
+                    };
+                    Self::write(stdin, &response).await?;
+                }
+                (Some(response_id), None) if response_id.as_u64() == Some(id) => {
+                    return parse_response(message);
+                }
+                _ => log::debug!("Ignoring MCP message: {}", message),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl McpTransport for StdioTransport {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, McpError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut io = self.io.lock().await;
+        let (stdin, stdout) = &mut *io;
+
+        Self::write(stdin, &request_message(id, method, params)).await?;
+        tokio::time::timeout(self.timeout, Self::read_response(stdin, stdout, id))
+            .await
+            .map_err(|_| McpError::Timeout)?
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<(), McpError> {
+        let mut io = self.io.lock().await;
+        Self::write(&mut io.0, &notification_message(method, params)).await
+    }
+}
+
+type PendingRequests = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+
+/// Talks to an MCP server over HTTP with Server-Sent Events.
+///
+/// The server announces in the `endpoint` event the url where the messages have to be
+/// posted, and sends back the responses as `message` events on the stream.
+pub struct SseTransport {
+    client: reqwest::Client,
+    endpoint: String,
+    pending: PendingRequests,
+    next_id: AtomicU64,
+    timeout: Duration,
+    listener: JoinHandle<()>,
+}
+
+impl SseTransport {
+    pub async fn connect<S: AsRef<str>>(url: S) -> Result<Self, McpError> {
+        Self::connect_with_client(url, reqwest::Client::new()).await
+    }
+
+    /// Connects using a custom client, e.g. one with default authentication headers.
+    pub async fn connect_with_client<S: AsRef<str>>(
+        url: S,
+        client: reqwest::Client,
+    ) -> Result<Self, McpError> {
+        let base = url::Url::parse(url.as_ref())
+            .map_err(|e| McpError::Transport(format!("invalid url: {}", e)))?;
+        let mut source = EventSource::new(client.get(base.clone()))
+            .map_err(|e| McpError::Transport(e.to_string()))?;
+
+        let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+        let (endpoint_tx, endpoint_rx) = oneshot::channel::<String>();
+
+        let listener = tokio::spawn({
+            let pending = pending.clone();
+            async move {
+                let mut endpoint_tx = Some(endpoint_tx);
+                while let Some(event) = source.next().await {
+                    match event {
+                        Ok(Event::Open) => {}
+                        Ok(Event::Message(message)) if message.event == "endpoint" => {
+                            if let Some(tx) = endpoint_tx.take() {
+                                let _ = tx.send(message.data);
+                            }
+                        }
+                        Ok(Event::Message(message)) => {
+                            let Ok(value) = serde_json::from_str::<Value>(&message.data) else {
+                                log::debug!("Ignoring MCP message: {}", message.data);
+                                continue;
+                            };
+                            let Some(id) = value.get("id").and_then(|id| id.as_u64()) else {
+                                continue;
+                            };
+                            if let Some(tx) = pending.lock().unwrap().remove(&id) {
+                                let _ = tx.send(value);
+                            }
+                        }
+                        Err(e) => {
+                            log::warn!("MCP event stream closed: {}", e);
+                            source.close();
+                            break;
+                        }
+                    }
+                }
+                // Dropping the senders wakes up the requests that are still waiting.
+                pending.lock().unwrap().clear();
+            }
+        });
+
+        let endpoint = match tokio::time::timeout(DEFAULT_TIMEOUT, endpoint_rx).await {
+            Ok(Ok(endpoint)) => endpoint,
+            Ok(Err(_)) => {
+                return Err(McpError::Transport(
+                    "the event stream closed before sending the endpoint".to_string(),
+                ))
+            }
+            Err(_) => {
+                listener.abort();
+                return Err(McpError::Timeout);
+            }
+        };
+        let endpoint = base
+            .join(endpoint.trim())
+            .map_err(|e| McpError::Transport(format!("invalid endpoint: {}", e)))?;
+
+        Ok(Self {
+            client,
+            endpoint: endpoint.to_string(),
+            pending,
+            next_id: AtomicU64::new(1),
+            timeout: DEFAULT_TIMEOUT,
+            listener,
+        })
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn post(&self, message: &Value) -> Result<(), McpError> {
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .json(message)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(McpError::Transport(format!(
+                "the server responded with status {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SseTransport {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+#[async_trait]
+impl McpTransport for SseTransport {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, McpError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+
+        if let Err(e) = self.post(&request_message(id, method, params)).await {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(response)) => parse_response(response),
+            Ok(Err(_)) => Err(McpError::Transport(
+                "the event stream closed before the response".to_string(),
+            )),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(McpError::Timeout)
+            }
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<(), McpError> {
+        self.post(&notification_message(method, params)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stdio_transport() {
+        // A fake server answering the first request, ignoring a log line and a
+        // notification on the way.
+        let script = r#"read line
+echo 'not json'
+echo '{"jsonrpc":"2.0","method":"notifications/message","params":{}}'
+echo '{"jsonrpc":"2.0","id":1,"result":{"ok":true}}'
+read line
+echo '{"jsonrpc":"2.0","id":2,"error":{"code":-32602,"message":"Unknown tool"}}'"#;
+        let transport = StdioTransport::new("sh", &["-c", script]).unwrap();
+
+        let result = transport.request("ping", json!({})).await.unwrap();
+        assert_eq!(result, json!({"ok": true}));
+
+        let result = transport.request("tools/call", json!({})).await;
+        assert!(matches!(result, Err(McpError::Rpc { code: -32602, .. })));
+    }
+}
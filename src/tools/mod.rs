@@ -1,6 +1,12 @@
 mod tool;
 pub use tool::*;
 
+mod structured_tool;
+pub use structured_tool::*;
+
+pub(crate) mod tool_macro;
+pub use tool_macro::tool;
+
 pub use wolfram::*;
 mod wolfram;
 
@@ -13,8 +19,47 @@ pub use sql::*;
 mod serpapi;
 pub use serpapi::*;
 
+#[cfg(feature = "command-executor")]
 mod command_executor;
+#[cfg(feature = "command-executor")]
 pub use command_executor::*;
 
+mod calculator;
+pub use calculator::*;
+
+#[cfg(not(target_arch = "wasm32"))]
 mod text2speech;
+#[cfg(not(target_arch = "wasm32"))]
 pub use text2speech::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod requests;
+#[cfg(not(target_arch = "wasm32"))]
+pub use requests::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file_system;
+#[cfg(not(target_arch = "wasm32"))]
+pub use file_system::*;
+
+mod wikipedia;
+pub use wikipedia::*;
+
+mod vectorstore_qa;
+pub use vectorstore_qa::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod mcp;
+#[cfg(not(target_arch = "wasm32"))]
+pub use mcp::*;
+
+mod openapi;
+pub use openapi::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod image_generation;
+#[cfg(not(target_arch = "wasm32"))]
+pub use image_generation::*;
+
+mod webhook;
+pub use webhook::*;
@@ -0,0 +1,2 @@
+mod openapi_toolkit;
+pub use openapi_toolkit::*;
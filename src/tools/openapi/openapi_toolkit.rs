@@ -0,0 +1,599 @@
+use std::{error::Error, sync::Arc};
+
+use async_trait::async_trait;
+use reqwest::Method;
+use serde_json::{json, Map, Value};
+
+use crate::{runtime::send, tools::Tool};
+
+const DEFAULT_MAX_RESPONSE_LENGTH: usize = 4000;
+const MAX_REF_DEPTH: usize = 8;
+const METHODS: [&str; 7] = ["get", "post", "put", "patch", "delete", "head", "options"];
+
+/// Authentication added to every request made by the generated tools.
+#[derive(Clone, Debug)]
+pub enum OpenApiAuth {
+    None,
+    Bearer(String),
+    Basic { username: String, password: String },
+    ApiKeyHeader { name: String, value: String },
+    ApiKeyQuery { name: String, value: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ParameterLocation {
+    Path,
+    Query,
+    Header,
+}
+
+#[derive(Clone, Debug)]
+struct OperationParameter {
+    name: String,
+    location: ParameterLocation,
+}
+
+/// Generates one `Tool` per operation of an OpenAPI 3 document.
+///
+/// The parameters of each tool are built from the `parameters` and the json
+/// `requestBody` of the operation, the body being the `body` parameter, or `request_body`
+/// if the operation has a parameter named `body`. Calling the tool performs the request.
+///
+/// # Example
+/// ```rust,ignore
+/// let tools = OpenApiToolkit::from_json(&std::fs::read_to_string("petstore.json")?)?
+///     .with_auth(OpenApiAuth::Bearer(std::env::var("PETSTORE_TOKEN")?))
+///     .with_operations(&["listPets", "showPetById"])
+///     .tools()?;
+/// ```
+pub struct OpenApiToolkit {
+    spec: Value,
+    base_url: Option<String>,
+    auth: OpenApiAuth,
+    operations: Option<Vec<String>>,
+    tags: Option<Vec<String>>,
+    excluded_operations: Vec<String>,
+    max_response_length: usize,
+    client: reqwest::Client,
+}
+
+impl OpenApiToolkit {
+    pub fn from_value(spec: Value) -> Result<Self, Box<dyn Error>> {
+        if !spec["openapi"]
+            .as_str()
+            .unwrap_or_default()
+            .starts_with('3')
+        {
+            return Err("Only OpenAPI 3 documents are supported".into());
+        }
+        Ok(Self {
+            spec,
+            base_url: None,
+            auth: OpenApiAuth::None,
+            operations: None,
+            tags: None,
+            excluded_operations: Vec::new(),
+            max_response_length: DEFAULT_MAX_RESPONSE_LENGTH,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    pub fn from_json(spec: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_value(serde_json::from_str(spec)?)
+    }
+
+    pub async fn from_url(url: &str) -> Result<Self, Box<dyn Error>> {
+        let spec: Value = reqwest::get(url).await?.json().await?;
+        Self::from_value(spec)
+    }
+
+    /// Overrides the first url of the `servers` section.
+    pub fn with_base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn with_auth(mut self, auth: OpenApiAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Only generate the tools of these operation ids.
+    pub fn with_operations<S: AsRef<str>>(mut self, operations: &[S]) -> Self {
+        self.operations = Some(operations.iter().map(|s| s.as_ref().to_owned()).collect());
+        self
+    }
+
+    /// Only generate the tools of the operations with at least one of these tags.
+    pub fn with_tags<S: AsRef<str>>(mut self, tags: &[S]) -> Self {
+        self.tags = Some(tags.iter().map(|s| s.as_ref().to_owned()).collect());
+        self
+    }
+
+    /// Never generate the tools of these operation ids.
+    pub fn with_excluded_operations<S: AsRef<str>>(mut self, operations: &[S]) -> Self {
+        self.excluded_operations = operations.iter().map(|s| s.as_ref().to_owned()).collect();
+        self
+    }
+
+    pub fn with_max_response_length(mut self, max_response_length: usize) -> Self {
+        self.max_response_length = max_response_length;
+        self
+    }
+
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    pub fn tools(&self) -> Result<Vec<Arc<dyn Tool>>, Box<dyn Error>> {
+        Ok(self
+            .operation_tools()?
+            .into_iter()
+            .map(|tool| Arc::new(tool) as Arc<dyn Tool>)
+            .collect())
+    }
+
+    pub fn operation_tools(&self) -> Result<Vec<OpenApiOperationTool>, Box<dyn Error>> {
+        let base_url = match &self.base_url {
+            Some(base_url) => base_url.clone(),
+            None => self.spec["servers"][0]["url"]
+                .as_str()
+                .ok_or("The document has no servers, set a base url")?
+                .to_string(),
+        };
+        let base_url = base_url.trim_end_matches('/').to_string();
+
+        let paths = self.spec["paths"]
+            .as_object()
+            .ok_or("The document has no paths")?;
+
+        let mut tools = Vec::new();
+        for (path, item) in paths {
+            let item = self.resolve(item, 0);
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let operation_id = operation_id(operation, method, path);
+                if !self.include(&operation_id, operation) {
+                    continue;
+                }
+
+                // Path level parameters apply to every operation, operation level ones
+                // override them.
+                let mut raw_parameters: Vec<Value> =
+                    item["parameters"].as_array().cloned().unwrap_or_default();
+                raw_parameters.extend(
+                    operation["parameters"]
+                        .as_array()
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+
+                let mut properties = Map::new();
+                let mut required = Vec::new();
+                let mut parameters: Vec<OperationParameter> = Vec::new();
+                for parameter in raw_parameters {
+                    let parameter = self.resolve(&parameter, 0);
+                    let Some(name) = parameter["name"].as_str() else {
+                        continue;
+                    };
+                    let location = match parameter["in"].as_str() {
+                        Some("path") => ParameterLocation::Path,
+                        Some("query") => ParameterLocation::Query,
+                        Some("header") => ParameterLocation::Header,
+                        _ => continue,
+                    };
+
+                    let mut schema = self.resolve(&parameter["schema"], 0);
+                    if schema.is_null() {
+                        schema = json!({ "type": "string" });
+                    }
+                    if let Some(description) = parameter["description"].as_str() {
+                        schema["description"] = json!(description);
+                    }
+                    properties.insert(name.to_string(), schema);
+                    parameters.retain(|p| p.name != name);
+                    required.retain(|r| r != name);
+                    parameters.push(OperationParameter {
+                        name: name.to_string(),
+                        location: location.clone(),
+                    });
+                    if location == ParameterLocation::Path
+                        || parameter["required"].as_bool().unwrap_or(false)
+                    {
+                        required.push(json!(name));
+                    }
+                }
+
+                let body = self.resolve(&operation["requestBody"], 0);
+                let body_schema = &body["content"]["application/json"]["schema"];
+                let body_key = (!body_schema.is_null()).then(|| {
+                    let mut key = "body".to_string();
+                    if properties.contains_key(&key) {
+                        key = "request_body".to_string();
+                    }
+                    while properties.contains_key(&key) {
+                        key.insert(0, '_');
+                    }
+                    key
+                });
+                if let Some(body_key) = &body_key {
+                    let mut schema = self.resolve(body_schema, 0);
+                    if let Some(description) = body["description"].as_str() {
+                        schema["description"] = json!(description);
+                    }
+                    properties.insert(body_key.clone(), schema);
+                    if body["required"].as_bool().unwrap_or(false) {
+                        required.push(json!(body_key));
+                    }
+                }
+
+                let description = [&operation["summary"], &operation["description"]]
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                tools.push(OpenApiOperationTool {
+                    name: sanitize_name(&operation_id),
+                    description: if description.is_empty() {
+                        format!("{} {}", method.to_uppercase(), path)
+                    } else {
+                        description
+                    },
+                    method: Method::from_bytes(method.to_uppercase().as_bytes())?,
+                    url: format!("{}{}", base_url, path),
+                    parameters,
+                    body_key,
+                    schema: json!({
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                    }),
+                    auth: self.auth.clone(),
+                    max_response_length: self.max_response_length,
+                    client: self.client.clone(),
+                });
+            }
+        }
+        Ok(tools)
+    }
+
+    fn include(&self, operation_id: &str, operation: &Value) -> bool {
+        if self.excluded_operations.iter().any(|o| o == operation_id) {
+            return false;
+        }
+        if let Some(operations) = &self.operations {
+            if !operations.iter().any(|o| o == operation_id) {
+                return false;
+            }
+        }
+        if let Some(tags) = &self.tags {
+            let operation_tags = operation["tags"].as_array().cloned().unwrap_or_default();
+            if !operation_tags
+                .iter()
+                .filter_map(|t| t.as_str())
+                .any(|t| tags.iter().any(|tag| tag == t))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Replaces the local `$ref`s (`#/components/...`) with their definition.
+    fn resolve(&self, value: &Value, depth: usize) -> Value {
+        match value {
+            Value::Object(map) => {
+                if let Some(reference) = map.get("$ref").and_then(|r| r.as_str()) {
+                    if depth >= MAX_REF_DEPTH {
+                        return json!({});
+                    }
+                    let target = reference
+                        .strip_prefix('#')
+                        .and_then(|pointer| self.spec.pointer(pointer))
+                        .cloned()
+                        .unwrap_or_else(|| json!({}));
+                    return self.resolve(&target, depth + 1);
+                }
+                Value::Object(
+                    map.iter()
+                        .map(|(k, v)| (k.clone(), self.resolve(v, depth)))
+                        .collect(),
+                )
+            }
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|v| self.resolve(v, depth)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+}
+
+fn operation_id(operation: &Value, method: &str, path: &str) -> String {
+    match operation["operationId"].as_str() {
+        Some(id) => id.to_string(),
+        None => format!("{}_{}", method, path),
+    }
+}
+
+// Function names of most providers only accept `[a-zA-Z0-9_-]`.
+fn sanitize_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = name.trim_matches('_').to_string();
+    name.chars().take(64).collect()
+}
+
+/// A single operation of an OpenAPI document. Created by `OpenApiToolkit`.
+pub struct OpenApiOperationTool {
+    name: String,
+    description: String,
+    method: Method,
+    url: String,
+    parameters: Vec<OperationParameter>,
+    /// The parameter of the tool with the json body of the request.
+    body_key: Option<String>,
+    schema: Value,
+    auth: OpenApiAuth,
+    max_response_length: usize,
+    client: reqwest::Client,
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[async_trait]
+impl Tool for OpenApiOperationTool {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn parameters(&self) -> Value {
+        self.schema.clone()
+    }
+
+    async fn parse_input(&self, input: &str) -> Value {
+        serde_json::from_str::<Value>(input).unwrap_or_else(|_| json!({}))
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let mut url = self.url.clone();
+        let mut query = Vec::new();
+        let mut headers = Vec::new();
+        for parameter in &self.parameters {
+            let Some(value) = input.get(&parameter.name).filter(|v| !v.is_null()) else {
+                if parameter.location == ParameterLocation::Path {
+                    return Ok(format!("Error: missing path parameter {}", parameter.name));
+                }
+                continue;
+            };
+            let value = value_to_string(value);
+            match parameter.location {
+                ParameterLocation::Path => {
+                    url = url.replace(
+                        &format!("{{{}}}", parameter.name),
+                        &urlencoding::encode(&value),
+                    )
+                }
+                ParameterLocation::Query => query.push((parameter.name.clone(), value)),
+                ParameterLocation::Header => headers.push((parameter.name.clone(), value)),
+            }
+        }
+
+        let mut request = self.client.request(self.method.clone(), &url).query(&query);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if let Some(body_key) = &self.body_key {
+            if let Some(body) = input.get(body_key).filter(|b| !b.is_null()) {
+                request = request.json(body);
+            }
+        }
+        request = match &self.auth {
+            OpenApiAuth::None => request,
+            OpenApiAuth::Bearer(token) => request.bearer_auth(token),
+            OpenApiAuth::Basic { username, password } => {
+                request.basic_auth(username, Some(password))
+            }
+            OpenApiAuth::ApiKeyHeader { name, value } => request.header(name, value),
+            OpenApiAuth::ApiKeyQuery { name, value } => request.query(&[(name, value)]),
+        };
+
+        let (status, mut body) = send(async {
+            let response = request.send().await?;
+            Ok::<_, reqwest::Error>((response.status(), response.text().await?))
+        })
+        .await?;
+        if let Ok(json) = serde_json::from_str::<Value>(&body) {
+            body = json.to_string();
+        }
+        if body.chars().count() > self.max_response_length {
+            body = body.chars().take(self.max_response_length).collect();
+            body.push_str("... (truncated)");
+        }
+
+        if !status.is_success() {
+            return Ok(format!(
+                "Error: the request failed with status {}: {}",
+                status, body
+            ));
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> Value {
+        json!({
+            "openapi": "3.0.0",
+            "info": { "title": "Petstore", "version": "1.0.0" },
+            "servers": [{ "url": "https://petstore.example.com/v1" }],
+            "paths": {
+                "/pets": {
+                    "get": {
+                        "operationId": "listPets",
+                        "summary": "List all pets",
+                        "tags": ["pets"],
+                        "parameters": [{
+                            "name": "limit",
+                            "in": "query",
+                            "description": "How many items to return",
+                            "schema": { "type": "integer" }
+                        }]
+                    },
+                    "post": {
+                        "operationId": "createPet",
+                        "summary": "Create a pet",
+                        "tags": ["admin"],
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Pet" }
+                                }
+                            }
+                        }
+                    }
+                },
+                "/pets/{petId}": {
+                    "parameters": [{ "$ref": "#/components/parameters/PetId" }],
+                    "get": { "operationId": "showPetById", "tags": ["pets"] }
+                }
+            },
+            "components": {
+                "parameters": {
+                    "PetId": { "name": "petId", "in": "path", "schema": { "type": "string" } }
+                },
+                "schemas": {
+                    "Pet": {
+                        "type": "object",
+                        "properties": { "name": { "type": "string" } },
+                        "required": ["name"]
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_generate_tools() {
+        let tools = OpenApiToolkit::from_value(spec())
+            .unwrap()
+            .operation_tools()
+            .unwrap();
+        let names: Vec<String> = tools.iter().map(|t| t.name()).collect();
+        assert_eq!(names, vec!["listPets", "createPet", "showPetById"]);
+
+        assert_eq!(tools[0].description(), "List all pets");
+        assert_eq!(
+            tools[0].parameters()["properties"]["limit"]["description"],
+            "How many items to return"
+        );
+        assert_eq!(
+            tools[1].parameters()["properties"]["body"]["required"],
+            json!(["name"])
+        );
+        assert_eq!(tools[1].parameters()["required"], json!(["body"]));
+        assert_eq!(tools[2].parameters()["required"], json!(["petId"]));
+
+        let tools = OpenApiToolkit::from_value(spec())
+            .unwrap()
+            .with_tags(&["pets"])
+            .with_excluded_operations(&["listPets"])
+            .operation_tools()
+            .unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name(), "showPetById");
+    }
+
+    #[tokio::test]
+    async fn test_call_operation() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/pets/42")
+            .match_header("authorization", "Bearer secret")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{ "id": 42, "name": "Rex" }"#)
+            .create_async()
+            .await;
+
+        let tools = OpenApiToolkit::from_value(spec())
+            .unwrap()
+            .with_base_url(server.url())
+            .with_auth(OpenApiAuth::Bearer("secret".to_string()))
+            .with_operations(&["showPetById"])
+            .tools()
+            .unwrap();
+
+        let result = tools[0].call(r#"{"petId": 42}"#).await.unwrap();
+        assert_eq!(result, r#"{"id":42,"name":"Rex"}"#);
+        mock.assert_async().await;
+
+        let result = tools[0].call("{}").await.unwrap();
+        assert_eq!(result, "Error: missing path parameter petId");
+    }
+
+    #[tokio::test]
+    async fn test_body_parameter_conflict() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/notes")
+            .match_query(mockito::Matcher::UrlEncoded("body".into(), "short".into()))
+            .match_body(mockito::Matcher::Json(json!({ "text": "Hi" })))
+            .with_body("created")
+            .create_async()
+            .await;
+        let spec = json!({
+            "openapi": "3.0.0",
+            "info": { "title": "Notes", "version": "1.0.0" },
+            "paths": {
+                "/notes": {
+                    "post": {
+                        "operationId": "createNote",
+                        "parameters": [{ "name": "body", "in": "query", "schema": { "type": "string" } }],
+                        "requestBody": {
+                            "required": true,
+                            "content": { "application/json": { "schema": { "type": "object" } } }
+                        }
+                    }
+                }
+            }
+        });
+
+        let tools = OpenApiToolkit::from_value(spec)
+            .unwrap()
+            .with_base_url(server.url())
+            .tools()
+            .unwrap();
+        assert_eq!(tools[0].parameters()["required"], json!(["request_body"]));
+
+        let result = tools[0]
+            .call(r#"{"body": "short", "request_body": {"text": "Hi"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(result, "created");
+        mock.assert_async().await;
+    }
+}
@@ -0,0 +1,2 @@
+mod requests_tool;
+pub use requests_tool::*;
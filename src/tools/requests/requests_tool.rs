@@ -0,0 +1,515 @@
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{CONTENT_TYPE, LOCATION},
+    redirect, Method, StatusCode,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::tools::{html_to_text, Tool};
+
+const DEFAULT_MAX_RESPONSE_LENGTH: usize = 4000;
+const DEFAULT_MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+const MAX_REDIRECTS: usize = 10;
+/// The headers the LLM can't set: the host, which would reach another virtual host than
+/// the allowed one, and the hop-by-hop headers of the connection.
+const FORBIDDEN_HEADERS: [&str; 11] = [
+    "host",
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "content-length",
+];
+
+#[derive(Deserialize, Debug)]
+struct RequestInput {
+    url: String,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: Option<Value>,
+}
+
+/// RequestsTool lets an agent perform HTTP requests (GET, POST, PUT, DELETE) against
+/// a fixed set of allowed hosts.
+///
+/// The values of the headers set with `with_header` may contain `{variable}` placeholders
+/// that are filled with the values set through `with_variable`, so secrets like tokens
+/// never have to go through the LLM. They are only sent to the scheme, host and port of the
+/// requested url, not to those of a redirect. The headers given by the LLM are sent as they
+/// are, except for `Host` and the hop-by-hop headers which are rejected.
+/// Responses are read up to `max_response_bytes`, summarized according to their content
+/// type (html is reduced to its text, json is compacted) and truncated to
+/// `max_response_length` characters.
+///
+/// The redirects are followed by the tool, checking that every host is allowed, so the
+/// client must not follow them itself, see `with_client`.
+///
+/// # Example
+/// ```rust,ignore
+/// let tool = RequestsTool::new(&["api.internal.example.com", "*.example.org"])
+///     .with_header("Authorization", "Bearer {token}")
+///     .with_variable("token", std::env::var("API_TOKEN").unwrap())
+///     .with_max_response_length(2000);
+/// ```
+pub struct RequestsTool {
+    client: reqwest::Client,
+    allowed_hosts: Vec<String>,
+    allowed_methods: Vec<Method>,
+    headers: HashMap<String, String>,
+    variables: HashMap<String, String>,
+    max_response_length: usize,
+    max_response_bytes: usize,
+}
+
+impl RequestsTool {
+    /// Creates a new RequestsTool that can only reach the given hosts.
+    /// A host starting with `*.` matches any of its subdomains.
+    pub fn new<S: AsRef<str>>(allowed_hosts: &[S]) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .redirect(redirect::Policy::none())
+                .build()
+                .unwrap_or_default(),
+            allowed_hosts: allowed_hosts
+                .iter()
+                .map(|h| h.as_ref().to_lowercase())
+                .collect(),
+            allowed_methods: vec![Method::GET, Method::POST, Method::PUT, Method::DELETE],
+            headers: HashMap::new(),
+            variables: HashMap::new(),
+            max_response_length: DEFAULT_MAX_RESPONSE_LENGTH,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+        }
+    }
+
+    pub fn with_allowed_methods(mut self, methods: &[Method]) -> Self {
+        self.allowed_methods = methods.to_vec();
+        self
+    }
+
+    /// Adds a header sent with every request. The value can contain `{variable}` placeholders.
+    pub fn with_header<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the value of a `{variable}` placeholder used in the headers.
+    pub fn with_variable<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.variables.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_max_response_length(mut self, max_response_length: usize) -> Self {
+        self.max_response_length = max_response_length;
+        self
+    }
+
+    /// The maximum number of bytes of a response read, the rest is ignored. Default: 1 MiB
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// The client sending the requests. It must be built with `redirect::Policy::none()`,
+    /// otherwise it would follow redirects to the hosts which are not allowed.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn is_host_allowed(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| match allowed.strip_prefix("*.") {
+                Some(domain) => host.ends_with(&format!(".{}", domain)),
+                None => &host == allowed,
+            })
+    }
+
+    /// The error returned to the LLM when the host of the url is not allowed.
+    fn check_host(&self, url: &Url) -> Option<String> {
+        let host = url.host_str().unwrap_or_default();
+        if self.is_host_allowed(host) {
+            return None;
+        }
+        Some(format!(
+            "Error: the host {} is not allowed. Allowed hosts: {}",
+            host,
+            self.allowed_hosts.join(", ")
+        ))
+    }
+
+    fn render_template(&self, template: &str) -> String {
+        let mut rendered = template.to_string();
+        for (key, value) in self.variables.iter() {
+            rendered = rendered.replace(&format!("{{{}}}", key), value);
+        }
+        rendered
+    }
+
+    fn summarize(&self, content_type: &str, bytes: &[u8]) -> String {
+        let content_type = content_type.to_lowercase();
+        let text = if content_type.contains("json") {
+            match serde_json::from_slice::<Value>(bytes) {
+                Ok(value) => value.to_string(),
+                Err(_) => String::from_utf8_lossy(bytes).to_string(),
+            }
+        } else if content_type.contains("html") {
+            html_to_text(&String::from_utf8_lossy(bytes))
+                .trim()
+                .to_string()
+        } else if content_type.is_empty()
+            || content_type.starts_with("text/")
+            || content_type.contains("xml")
+        {
+            String::from_utf8_lossy(bytes).to_string()
+        } else {
+            return format!(
+                "Binary content of type {} ({} bytes)",
+                content_type,
+                bytes.len()
+            );
+        };
+        truncate(&text, self.max_response_length)
+    }
+}
+
+fn truncate(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(max_length).collect();
+    format!("{}... (truncated)", truncated)
+}
+
+#[async_trait]
+impl Tool for RequestsTool {
+    fn name(&self) -> String {
+        String::from("Requests")
+    }
+
+    fn description(&self) -> String {
+        format!(
+            r#"A portal to the internet. Use this when you need to get specific content from a website or an API.
+            Input should be a json object with the keys "url", "method" (one of {}), and optionally "headers" and "body".
+            Only the following hosts can be reached: {}"#,
+            self.allowed_methods
+                .iter()
+                .map(|m| m.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.allowed_hosts.join(", ")
+        )
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "The url to request"
+                },
+                "method": {
+                    "type": "string",
+                    "enum": self.allowed_methods.iter().map(|m| m.as_str()).collect::<Vec<_>>(),
+                    "description": "The http method, defaults to GET"
+                },
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Extra headers for the request"
+                },
+                "body": {
+                    "description": "The json body of the request"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn parse_input(&self, input: &str) -> Value {
+        match serde_json::from_str::<Value>(input) {
+            Ok(value) if value.is_object() => value,
+            // A raw url is treated as a GET request
+            _ => json!({ "url": input.trim() }),
+        }
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let input: RequestInput = serde_json::from_value(input)?;
+        let mut url = Url::parse(&input.url)?;
+        if let Some(error) = self.check_host(&url) {
+            return Ok(error);
+        }
+
+        let mut method = Method::from_bytes(
+            input
+                .method
+                .unwrap_or_else(|| "GET".to_string())
+                .to_uppercase()
+                .as_bytes(),
+        )?;
+        if !self.allowed_methods.contains(&method) {
+            return Ok(format!("Error: the method {} is not allowed", method));
+        }
+        if let Some(key) = input
+            .headers
+            .keys()
+            .find(|key| FORBIDDEN_HEADERS.contains(&key.to_lowercase().as_str()))
+        {
+            return Ok(format!("Error: the header {} can't be set", key));
+        }
+
+        let origin = url.origin();
+        let mut body = input.body;
+        let mut redirects = 0;
+        let mut response = loop {
+            let mut request = self.client.request(method.clone(), url.clone());
+            // The configured headers may hold secrets for the requested host only.
+            if url.origin() == origin {
+                for (key, value) in self.headers.iter() {
+                    request = request.header(key, self.render_template(value));
+                }
+            }
+            for (key, value) in input.headers.iter() {
+                request = request.header(key, value);
+            }
+            if let Some(body) = &body {
+                request = request.json(body);
+            }
+
+            let response = request.send().await?;
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|v| v.to_str().ok());
+            let (true, Some(location)) = (response.status().is_redirection(), location) else {
+                break response;
+            };
+            redirects += 1;
+            if redirects > MAX_REDIRECTS {
+                return Ok("Error: too many redirects".to_string());
+            }
+            url = url.join(location)?;
+            if let Some(error) = self.check_host(&url) {
+                return Ok(error);
+            }
+            // As the browsers do, only 307 and 308 keep the method and the body.
+            if !matches!(
+                response.status(),
+                StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+            ) {
+                method = Method::GET;
+                body = None;
+            }
+        };
+
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() >= self.max_response_bytes {
+                bytes.truncate(self.max_response_bytes);
+                break;
+            }
+        }
+        let summary = self.summarize(&content_type, &bytes);
+
+        if status.is_success() {
+            Ok(summary)
+        } else {
+            Ok(format!(
+                "Request failed with status {}: {}",
+                status, summary
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requests_tool() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/items")
+            .match_header("authorization", "Bearer secret")
+            .match_body(mockito::Matcher::Json(json!({"name": "apple"})))
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body("{\n  \"id\": 1,\n  \"name\": \"apple\"\n}")
+            .create();
+
+        let tool = RequestsTool::new(&["127.0.0.1"])
+            .with_header("Authorization", "Bearer {token}")
+            .with_variable("token", "secret");
+        // The headers of the LLM are not templated.
+        let leak = server
+            .mock("GET", "/leak")
+            .match_header("x-token", "{token}")
+            .create();
+        let input = json!({
+            "url": format!("{}/leak", server.url()),
+            "headers": {"X-Token": "{token}"}
+        });
+        tool.call(&input.to_string()).await.unwrap();
+        leak.assert();
+
+        let input = json!({
+            "url": format!("{}/items", server.url()),
+            "method": "post",
+            "body": {"name": "apple"}
+        });
+        let result = tool.call(&input.to_string()).await.unwrap();
+        assert_eq!(result, r#"{"id":1,"name":"apple"}"#);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_requests_tool_html_and_truncation() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/")
+            .with_header("content-type", "text/html")
+            .with_body("<html><body><script>x()</script>Hello World</body></html>")
+            .create();
+
+        let tool = RequestsTool::new(&["127.0.0.1"]).with_max_response_length(5);
+        let result = tool.call(&server.url()).await.unwrap();
+        assert_eq!(result, "Hello... (truncated)");
+    }
+
+    #[tokio::test]
+    async fn test_requests_tool_rejects_hosts_and_methods() {
+        let tool = RequestsTool::new(&["*.example.com"]).with_allowed_methods(&[Method::GET]);
+        assert!(tool.is_host_allowed("api.example.com"));
+        assert!(!tool.is_host_allowed("example.com.evil.org"));
+
+        let result = tool.call("https://evil.org/data").await.unwrap();
+        assert!(result.starts_with("Error: the host evil.org is not allowed"));
+
+        let input = json!({"url": "https://api.example.com", "method": "DELETE"});
+        let result = tool.call(&input.to_string()).await.unwrap();
+        assert_eq!(result, "Error: the method DELETE is not allowed");
+    }
+
+    #[tokio::test]
+    async fn test_requests_tool_redirects() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/moved")
+            .with_status(303)
+            .with_header("location", "/items")
+            .create();
+        server
+            .mock("GET", "/items")
+            .with_header("content-type", "text/plain")
+            .with_body("apple")
+            .create();
+        server
+            .mock("GET", "/internal")
+            .with_status(302)
+            .with_header("location", "http://169.254.169.254/latest/meta-data")
+            .create();
+
+        let tool = RequestsTool::new(&["127.0.0.1"]);
+        let input = json!({"url": format!("{}/moved", server.url()), "method": "POST"});
+        assert_eq!(tool.call(&input.to_string()).await.unwrap(), "apple");
+
+        let result = tool
+            .call(&format!("{}/internal", server.url()))
+            .await
+            .unwrap();
+        assert!(
+            result.starts_with("Error: the host 169.254.169.254 is not allowed"),
+            "{}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requests_tool_redirect_to_another_host() {
+        let mut server = mockito::Server::new_async().await;
+        let port = server
+            .host_with_port()
+            .rsplit(':')
+            .next()
+            .unwrap()
+            .to_string();
+        server
+            .mock("GET", "/moved")
+            .match_header("authorization", "Bearer secret")
+            .with_status(302)
+            .with_header("location", &format!("http://localhost:{}/items", port))
+            .create();
+        let items = server
+            .mock("GET", "/items")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .match_header("x-request", "1")
+            .with_header("content-type", "text/plain")
+            .with_body("apple")
+            .create();
+
+        let tool = RequestsTool::new(&["127.0.0.1", "localhost"])
+            .with_header("Authorization", "Bearer {token}")
+            .with_variable("token", "secret");
+        let input = json!({
+            "url": format!("{}/moved", server.url()),
+            "headers": {"X-Request": "1"}
+        });
+        assert_eq!(tool.call(&input.to_string()).await.unwrap(), "apple");
+        items.assert();
+    }
+
+    #[tokio::test]
+    async fn test_requests_tool_rejects_forbidden_headers() {
+        let tool = RequestsTool::new(&["api.example.com"]);
+        for header in ["Host", "connection", "Transfer-Encoding"] {
+            let input = json!({
+                "url": "https://api.example.com",
+                "headers": {header: "internal.example.com"}
+            });
+            assert_eq!(
+                tool.call(&input.to_string()).await.unwrap(),
+                format!("Error: the header {} can't be set", header)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_tool_max_response_bytes() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/")
+            .with_header("content-type", "text/plain")
+            .with_body("a".repeat(100_000))
+            .create();
+
+        let tool = RequestsTool::new(&["127.0.0.1"])
+            .with_max_response_bytes(10)
+            .with_max_response_length(1000);
+        assert_eq!(tool.call(&server.url()).await.unwrap(), "a".repeat(10));
+    }
+}
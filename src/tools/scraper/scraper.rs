@@ -4,7 +4,7 @@ use scraper::{ElementRef, Html, Selector};
 use serde_json::Value;
 use std::{error::Error, sync::Arc};
 
-use crate::tools::Tool;
+use crate::{runtime::send, tools::Tool};
 
 pub struct WebScrapper {}
 
@@ -27,7 +27,7 @@ impl Tool for WebScrapper {
     }
     async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
         let input = input.as_str().ok_or("Invalid input")?;
-        match scrape_url(input).await {
+        match send(scrape_url(input)).await {
             Ok(content) => Ok(content),
             Err(e) => Ok(format!("Error scraping {}: {}\n", input, e)),
         }
@@ -42,8 +42,12 @@ impl Into<Arc<dyn Tool>> for WebScrapper {
 
 async fn scrape_url(url: &str) -> Result<String, Box<dyn Error>> {
     let res = reqwest::get(url).await?.text().await?;
+    Ok(html_to_text(&res))
+}
 
-    let document = Html::parse_document(&res);
+/// Extracts the visible text of an html document, skipping scripts and collapsing whitespace.
+pub(crate) fn html_to_text(html: &str) -> String {
+    let document = Html::parse_document(html);
     let body_selector = Selector::parse("body").unwrap();
 
     let mut text = Vec::new();
@@ -55,7 +59,7 @@ async fn scrape_url(url: &str) -> Result<String, Box<dyn Error>> {
     let cleaned_text = joined_text.replace("\n", " ").replace("\t", " ");
     let re = Regex::new(r"\s+").unwrap();
     let final_text = re.replace_all(&cleaned_text, " ");
-    Ok(final_text.to_string())
+    final_text.to_string()
 }
 
 fn collect_text_not_in_script(element: &ElementRef, text: &mut Vec<String>) {
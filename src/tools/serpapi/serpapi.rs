@@ -3,7 +3,7 @@ use std::error::Error;
 use async_trait::async_trait;
 use serde_json::Value;
 
-use crate::tools::Tool;
+use crate::{runtime::send, tools::Tool};
 
 pub struct SerpApi {
     api_key: String,
@@ -163,7 +163,7 @@ impl Tool for SerpApi {
 
     async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
         let input = input.as_str().ok_or("Input should be a string")?;
-        self.simple_search(input).await
+        send(self.simple_search(input)).await
     }
 }
 
@@ -1,5 +1,14 @@
 #[cfg(feature = "postgres")]
 pub mod postgres;
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+#[cfg(feature = "mysql")]
+pub mod mysql;
 mod sql;
 
 pub use sql::*;
+
+mod toolkit;
+pub use toolkit::*;
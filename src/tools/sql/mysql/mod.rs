@@ -0,0 +1,3 @@
+mod mysql;
+
+pub use mysql::*;
@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use sqlx::{
+    mysql::{MySqlPoolOptions, MySqlRow},
+    Column, Connection, Executor, MySql, Pool, Row, ValueRef,
+};
+use std::error::Error;
+
+use crate::tools::{Dialect, Engine};
+
+pub struct MySQLEngine {
+    pool: Pool<MySql>,
+}
+
+impl MySQLEngine {
+    pub async fn new(dsn: &str) -> Result<Self, Box<dyn Error>> {
+        let pool = MySqlPoolOptions::new()
+            .max_connections(5)
+            .connect(dsn)
+            .await?;
+
+        Ok(MySQLEngine { pool })
+    }
+
+    pub fn from_pool(pool: Pool<MySql>) -> Self {
+        MySQLEngine { pool }
+    }
+}
+
+impl From<MySQLEngine> for Box<dyn Engine> {
+    fn from(engine: MySQLEngine) -> Self {
+        Box::new(engine)
+    }
+}
+
+/// The value of the column as text, "N/A" for the types without a text representation.
+fn value_to_string(row: &MySqlRow, index: usize) -> String {
+    if row.try_get_raw(index).map_or(true, |value| value.is_null()) {
+        return "NULL".to_string();
+    }
+    if let Ok(value) = row.try_get::<i64, _>(index) {
+        return value.to_string();
+    }
+    if let Ok(value) = row.try_get::<u64, _>(index) {
+        return value.to_string();
+    }
+    if let Ok(value) = row.try_get::<f64, _>(index) {
+        return value.to_string();
+    }
+    row.try_get::<String, _>(index)
+        .unwrap_or_else(|_| "N/A".to_string())
+}
+
+fn columns(rows: &[MySqlRow]) -> Vec<String> {
+    rows.first()
+        .map(|row| {
+            row.columns()
+                .iter()
+                .map(|col| col.name().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn row_to_strings(row: &MySqlRow) -> Vec<String> {
+    (0..row.columns().len())
+        .map(|index| value_to_string(row, index))
+        .collect()
+}
+
+#[async_trait]
+impl Engine for MySQLEngine {
+    fn dialect(&self) -> Dialect {
+        Dialect::MySQL
+    }
+
+    async fn query(&self, query: &str) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn Error>> {
+        let rows = sqlx::query(query).fetch_all(&self.pool).await?;
+        Ok((columns(&rows), rows.iter().map(row_to_strings).collect()))
+    }
+
+    async fn query_with_limit(
+        &self,
+        query: &str,
+        max_rows: usize,
+        read_only: bool,
+    ) -> Result<(Vec<String>, Vec<Vec<String>>, bool), Box<dyn Error>> {
+        let mut conn = self.pool.acquire().await?;
+        // MySQL only allows it before the transaction, it applies to the next one.
+        if read_only {
+            conn.execute("SET TRANSACTION READ ONLY").await?;
+        }
+        // Dropping the transaction on an error rolls it back.
+        let mut tx = conn.begin().await?;
+
+        let mut rows = Vec::new();
+        let mut truncated = false;
+        {
+            let mut stream = sqlx::query(query).fetch(&mut *tx);
+            while let Some(row) = stream.try_next().await? {
+                if rows.len() == max_rows {
+                    truncated = true;
+                    break;
+                }
+                rows.push(row);
+            }
+        }
+
+        if read_only {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+
+        Ok((
+            columns(&rows),
+            rows.iter().map(row_to_strings).collect(),
+            truncated,
+        ))
+    }
+
+    async fn table_names(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let query = "SELECT table_name AS table_name FROM information_schema.tables WHERE table_schema = DATABASE()";
+        let rows = sqlx::query(query).fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| row.get::<String, &str>("table_name"))
+            .collect())
+    }
+
+    async fn table_info(&self, table: &str) -> Result<String, Box<dyn Error>> {
+        let query = format!("SHOW CREATE TABLE `{}`", table.replace('`', "``"));
+        let row = sqlx::query(&query).fetch_one(&self.pool).await?;
+
+        Ok(row.get::<String, usize>(1))
+    }
+
+    fn close(&self) -> Result<(), Box<dyn Error>> {
+        // sqlx Pool is automatically closed when it goes out of scope
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_mysql_engine() {
+        let dsn = std::env::var("MYSQL_URL").expect("MYSQL_URL must be set");
+        let engine = MySQLEngine::new(&dsn).await.unwrap();
+
+        let tables = engine.table_names().await.unwrap();
+        println!("{:?}", tables);
+        if let Some(table) = tables.first() {
+            println!("{}", engine.table_info(table).await.unwrap());
+            let (cols, rows) = engine
+                .query(&format!("SELECT * FROM `{}` LIMIT 3", table))
+                .await
+                .unwrap();
+            println!("{:?} {:?}", cols, rows);
+        }
+    }
+}
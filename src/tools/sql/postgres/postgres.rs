@@ -1,5 +1,9 @@
 use async_trait::async_trait;
-use sqlx::{postgres::PgPoolOptions, Column, Pool, Postgres, Row, TypeInfo};
+use futures::TryStreamExt;
+use sqlx::{
+    postgres::{PgPoolOptions, PgRow},
+    Column, Pool, Postgres, Row, TypeInfo,
+};
 use std::error::Error;
 
 use crate::tools::{Dialect, Engine};
@@ -22,6 +26,47 @@ impl Into<Box<dyn Engine>> for PostgreSQLEngine {
     }
 }
 
+fn columns(rows: &[PgRow]) -> Vec<String> {
+    rows.first()
+        .map(|row| {
+            row.columns()
+                .iter()
+                .map(|col| col.name().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn row_to_strings(row: &PgRow) -> Vec<String> {
+    let mut result = Vec::with_capacity(row.columns().len());
+    for index in 0..row.columns().len() {
+        let column_type = row.columns()[index].type_info().name();
+
+        let value_str = match column_type {
+            "TEXT[]" => {
+                // Fetch the TEXT[] column as a vector of strings
+                match row.try_get::<Vec<String>, _>(index) {
+                    Ok(array) => format!("{:?}", array), // Format the vector as a string
+                    Err(_) => "N/A".to_string(),
+                }
+            }
+            _ => {
+                // For other types, attempt to get them as strings
+                match row.try_get::<&str, _>(index) {
+                    Ok(str_val) => str_val.to_string(),
+                    Err(_) => {
+                        // Fallback for types that cannot be directly converted to string
+                        "N/A".to_string()
+                    }
+                }
+            }
+        };
+
+        result.push(value_str);
+    }
+    result
+}
+
 #[async_trait]
 impl Engine for PostgreSQLEngine {
     fn dialect(&self) -> Dialect {
@@ -30,49 +75,47 @@ impl Engine for PostgreSQLEngine {
 
     async fn query(&self, query: &str) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn Error>> {
         let rows = sqlx::query(query).fetch_all(&self.pool).await?;
+        Ok((columns(&rows), rows.iter().map(row_to_strings).collect()))
+    }
 
-        let mut cols = vec![];
-        let mut results = vec![];
-
-        if let Some(row) = rows.first() {
-            cols = row
-                .columns()
-                .iter()
-                .map(|col| col.name().to_string())
-                .collect();
+    async fn query_with_limit(
+        &self,
+        query: &str,
+        max_rows: usize,
+        read_only: bool,
+    ) -> Result<(Vec<String>, Vec<Vec<String>>, bool), Box<dyn Error>> {
+        // Dropping the transaction on an error rolls it back.
+        let mut tx = self.pool.begin().await?;
+        if read_only {
+            sqlx::query("SET TRANSACTION READ ONLY")
+                .execute(&mut *tx)
+                .await?;
         }
 
-        for row in rows {
-            let mut result = Vec::with_capacity(cols.len());
-            for index in 0..cols.len() {
-                let column_type = row.columns()[index].type_info().name();
-
-                let value_str = match column_type {
-                    "TEXT[]" => {
-                        // Fetch the TEXT[] column as a vector of strings
-                        match row.try_get::<Vec<String>, _>(index) {
-                            Ok(array) => format!("{:?}", array), // Format the vector as a string
-                            Err(_) => "N/A".to_string(),
-                        }
-                    }
-                    _ => {
-                        // For other types, attempt to get them as strings
-                        match row.try_get::<&str, _>(index) {
-                            Ok(str_val) => str_val.to_string(),
-                            Err(_) => {
-                                // Fallback for types that cannot be directly converted to string
-                                "N/A".to_string()
-                            }
-                        }
-                    }
-                };
-
-                result.push(value_str);
+        let mut rows = Vec::new();
+        let mut truncated = false;
+        {
+            let mut stream = sqlx::query(query).fetch(&mut *tx);
+            while let Some(row) = stream.try_next().await? {
+                if rows.len() == max_rows {
+                    truncated = true;
+                    break;
+                }
+                rows.push(row);
             }
-            results.push(result);
         }
 
-        Ok((cols, results))
+        if read_only {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+
+        Ok((
+            columns(&rows),
+            rows.iter().map(row_to_strings).collect(),
+            truncated,
+        ))
     }
 
     async fn table_names(&self) -> Result<Vec<String>, Box<dyn Error>> {
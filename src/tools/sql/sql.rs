@@ -28,6 +28,28 @@ pub trait Engine: Send + Sync {
     fn dialect(&self) -> Dialect;
     // Query executes the query and returns the columns and results.
     async fn query(&self, query: &str) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn Error>>;
+    // QueryWithLimit executes the query and returns the columns, at most `max_rows` results
+    // and whether more rows were left out. With `read_only` the query must run in a
+    // read-only transaction, so the database itself rejects any write. Engines which
+    // can't enforce it return an error, the default only supports `read_only = false`.
+    async fn query_with_limit(
+        &self,
+        query: &str,
+        max_rows: usize,
+        read_only: bool,
+    ) -> Result<(Vec<String>, Vec<Vec<String>>, bool), Box<dyn Error>> {
+        if read_only {
+            return Err(format!(
+                "the {} engine does not support read-only queries",
+                self.dialect().to_string()
+            )
+            .into());
+        }
+        let (cols, mut results) = self.query(query).await?;
+        let truncated = results.len() > max_rows;
+        results.truncate(max_rows);
+        Ok((cols, results, truncated))
+    }
     // TableNames returns all the table names of the database.
     async fn table_names(&self) -> Result<Vec<String>, Box<dyn Error>>;
     // TableInfo returns the table information of the database.
@@ -132,10 +154,27 @@ impl SQLDatabase {
     pub async fn query(&self, query: &str) -> Result<String, Box<dyn Error>> {
         log::debug!("Query: {}", query);
         let (cols, results) = self.engine.query(query).await?;
-        let mut str = cols.join("\t") + "\n";
-        for row in results {
-            str += &row.join("\t");
-            str.push('\n');
+        Ok(format_rows(&cols, &results))
+    }
+
+    /// Same as `query`, but only the first `max_rows` rows are fetched. A trailing note
+    /// is added when rows were left out, so the caller knows the result is partial.
+    /// With `read_only` the query runs in a read-only transaction, see
+    /// `Engine::query_with_limit`.
+    pub async fn query_with_limit(
+        &self,
+        query: &str,
+        max_rows: usize,
+        read_only: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        log::debug!("Query: {}", query);
+        let (cols, results, truncated) = self
+            .engine
+            .query_with_limit(query, max_rows, read_only)
+            .await?;
+        let mut str = format_rows(&cols, &results);
+        if truncated {
+            str += &format!("(only the first {} rows are shown)\n", max_rows);
         }
         Ok(str)
     }
@@ -150,3 +189,12 @@ impl SQLDatabase {
         self.query(&query).await
     }
 }
+
+fn format_rows(cols: &[String], rows: &[Vec<String>]) -> String {
+    let mut str = cols.join("\t") + "\n";
+    for row in rows {
+        str += &row.join("\t");
+        str.push('\n');
+    }
+    str
+}
@@ -0,0 +1,3 @@
+mod sqlite;
+
+pub use sqlite::*;
@@ -0,0 +1,287 @@
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePoolOptions, SqliteRow},
+    Column, Connection, Executor, Pool, Row, Sqlite, ValueRef,
+};
+use std::{error::Error, str::FromStr};
+
+use crate::tools::{is_single_statement, Dialect, Engine};
+
+pub struct SQLiteEngine {
+    pool: Pool<Sqlite>,
+}
+
+impl SQLiteEngine {
+    /// Opens the database read-only, use `from_pool` with a writable pool to run queries
+    /// with `read_only = false`.
+    pub async fn new(dsn: &str) -> Result<Self, Box<dyn Error>> {
+        let options = SqliteConnectOptions::from_str(dsn)?.read_only(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await?;
+
+        Ok(SQLiteEngine { pool })
+    }
+
+    /// Uses an existing pool, e.g. one with a single connection to an in-memory database.
+    /// Read-only queries must be a single statement, run with `PRAGMA query_only` in a
+    /// transaction which is rolled back, so they can't write to a writable pool either.
+    pub fn from_pool(pool: Pool<Sqlite>) -> Self {
+        SQLiteEngine { pool }
+    }
+}
+
+impl From<SQLiteEngine> for Box<dyn Engine> {
+    fn from(engine: SQLiteEngine) -> Self {
+        Box::new(engine)
+    }
+}
+
+/// The value of the column as text, whatever its storage class.
+fn value_to_string(row: &SqliteRow, index: usize) -> String {
+    if row.try_get_raw(index).map_or(true, |value| value.is_null()) {
+        return "NULL".to_string();
+    }
+    if let Ok(value) = row.try_get::<i64, _>(index) {
+        return value.to_string();
+    }
+    if let Ok(value) = row.try_get::<f64, _>(index) {
+        return value.to_string();
+    }
+    row.try_get::<String, _>(index)
+        .unwrap_or_else(|_| "N/A".to_string())
+}
+
+fn columns(rows: &[SqliteRow]) -> Vec<String> {
+    rows.first()
+        .map(|row| {
+            row.columns()
+                .iter()
+                .map(|col| col.name().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn row_to_strings(row: &SqliteRow) -> Vec<String> {
+    (0..row.columns().len())
+        .map(|index| value_to_string(row, index))
+        .collect()
+}
+
+/// The first `max_rows` rows of the query run in a transaction, rolled back when
+/// `read_only`, and whether more rows were left out.
+async fn fetch_limited(
+    conn: &mut SqliteConnection,
+    query: &str,
+    max_rows: usize,
+    read_only: bool,
+) -> Result<(Vec<SqliteRow>, bool), sqlx::Error> {
+    let mut tx = conn.begin().await?;
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    {
+        let mut stream = sqlx::query(query).fetch(&mut *tx);
+        while let Some(row) = stream.try_next().await? {
+            if rows.len() == max_rows {
+                truncated = true;
+                break;
+            }
+            rows.push(row);
+        }
+    }
+    if read_only {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+    Ok((rows, truncated))
+}
+
+#[async_trait]
+impl Engine for SQLiteEngine {
+    fn dialect(&self) -> Dialect {
+        Dialect::SQLite
+    }
+
+    async fn query(&self, query: &str) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn Error>> {
+        let rows = sqlx::query(query).fetch_all(&self.pool).await?;
+        Ok((columns(&rows), rows.iter().map(row_to_strings).collect()))
+    }
+
+    async fn query_with_limit(
+        &self,
+        query: &str,
+        max_rows: usize,
+        read_only: bool,
+    ) -> Result<(Vec<String>, Vec<Vec<String>>, bool), Box<dyn Error>> {
+        // sqlx runs every statement of the query, a following one could turn query_only
+        // off and commit.
+        if read_only && !is_single_statement(query) {
+            return Err("only a single statement can be run read-only".into());
+        }
+        let mut conn = self.pool.acquire().await?;
+        if read_only {
+            conn.execute("PRAGMA query_only = ON").await?;
+        }
+        let result = fetch_limited(&mut conn, query, max_rows, read_only).await;
+        if read_only && conn.execute("PRAGMA query_only = OFF").await.is_err() {
+            // Never give a connection stuck in query_only mode back to the pool.
+            drop(conn.detach());
+        }
+        let (rows, truncated) = result?;
+
+        Ok((
+            columns(&rows),
+            rows.iter().map(row_to_strings).collect(),
+            truncated,
+        ))
+    }
+
+    async fn table_names(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let query =
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'";
+        let rows = sqlx::query(query).fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| row.get::<String, &str>("name"))
+            .collect())
+    }
+
+    async fn table_info(&self, table: &str) -> Result<String, Box<dyn Error>> {
+        let row = sqlx::query("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| format!("table {} not found", table))?;
+
+        Ok(row.get::<String, &str>("sql"))
+    }
+
+    fn close(&self) -> Result<(), Box<dyn Error>> {
+        // sqlx Pool is automatically closed when it goes out of scope
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sqlite_engine() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, score REAL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO users (name, score) VALUES ('Luis', 9.5), (NULL, 7)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let engine = SQLiteEngine::from_pool(pool);
+
+        assert_eq!(engine.table_names().await.unwrap(), vec!["users"]);
+        assert!(engine
+            .table_info("users")
+            .await
+            .unwrap()
+            .starts_with("CREATE TABLE users"));
+        assert!(engine.table_info("orders").await.is_err());
+
+        let (cols, rows) = engine
+            .query("SELECT id, name, score FROM users ORDER BY id")
+            .await
+            .unwrap();
+        assert_eq!(cols, vec!["id", "name", "score"]);
+        assert_eq!(rows, vec![vec!["1", "Luis", "9.5"], vec!["2", "NULL", "7"]]);
+
+        let (_, rows, truncated) = engine
+            .query_with_limit("SELECT id FROM users ORDER BY id", 1, true)
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![vec!["1"]]);
+        assert!(truncated);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_engine_read_only() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let engine = SQLiteEngine::from_pool(pool);
+
+        assert!(engine
+            .query_with_limit("INSERT INTO users (name) VALUES ('Luis')", 10, true)
+            .await
+            .is_err());
+        // Even when the query turns query_only off, the transaction is rolled back.
+        let _ = engine
+            .query_with_limit("PRAGMA user_version = 1", 10, true)
+            .await;
+        for query in [
+            "PRAGMA query_only = OFF; INSERT INTO users (name) VALUES ('Luis')",
+            "PRAGMA query_only = OFF; COMMIT; DROP TABLE users",
+            "SELECT ';' ; DROP TABLE users",
+        ] {
+            let error = engine.query_with_limit(query, 10, true).await.unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "only a single statement can be run read-only"
+            );
+        }
+        let (_, rows, _) = engine
+            .query_with_limit("SELECT 'a;b';", 10, true)
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![vec!["a;b"]]);
+        let (_, rows) = engine.query("SELECT count(*) FROM users").await.unwrap();
+        assert_eq!(rows, vec![vec!["0"]]);
+        let (_, rows) = engine.query("PRAGMA user_version").await.unwrap();
+        assert_eq!(rows, vec![vec!["0"]]);
+
+        engine
+            .query_with_limit("INSERT INTO users (name) VALUES ('Luis')", 10, false)
+            .await
+            .unwrap();
+        let (_, rows) = engine.query("SELECT count(*) FROM users").await.unwrap();
+        assert_eq!(rows, vec![vec!["1"]]);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_engine_new_is_read_only() {
+        let path = std::env::temp_dir().join(format!("{}.db", uuid::Uuid::new_v4()));
+        let dsn = format!("sqlite://{}", path.display());
+        let pool = SqlitePoolOptions::new()
+            .connect(&format!("{}?mode=rwc", dsn))
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+
+        let engine = SQLiteEngine::new(&dsn).await.unwrap();
+        assert_eq!(engine.table_names().await.unwrap(), vec!["users"]);
+        assert!(engine
+            .query_with_limit("INSERT INTO users (name) VALUES ('Luis')", 10, false)
+            .await
+            .is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
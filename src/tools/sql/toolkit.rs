@@ -0,0 +1,421 @@
+use std::{error::Error, sync::Arc};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::tools::Tool;
+
+use super::SQLDatabase;
+
+pub(crate) const DEFAULT_MAX_ROWS: usize = 50;
+
+// Keywords that can modify the database or its schema. Queries containing any of them
+// outside of a literal are rejected early by `QuerySQLDatabaseTool` and `SQLDatabaseChain`
+// in read-only mode, the read-only transaction is what actually prevents the writes.
+const WRITE_KEYWORDS: [&str; 15] = [
+    "INSERT", "UPDATE", "DELETE", "DROP", "ALTER", "CREATE", "TRUNCATE", "REPLACE", "MERGE",
+    "GRANT", "REVOKE", "ATTACH", "DETACH", "VACUUM", "INTO",
+];
+
+/// Returns the list of tables of the database as a comma separated string.
+pub struct ListSQLDatabaseTablesTool {
+    database: Arc<SQLDatabase>,
+}
+
+impl ListSQLDatabaseTablesTool {
+    pub fn new(database: Arc<SQLDatabase>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl Tool for ListSQLDatabaseTablesTool {
+    fn name(&self) -> String {
+        String::from("sql_db_list_tables")
+    }
+
+    fn description(&self) -> String {
+        String::from(
+            "Input is an empty string, output is a comma-separated list of tables in the database.",
+        )
+    }
+
+    async fn run(&self, _input: Value) -> Result<String, Box<dyn Error>> {
+        let mut tables = self.database.table_names();
+        tables.sort();
+        Ok(tables.join(", "))
+    }
+}
+
+/// Returns the schema and some sample rows for the requested tables.
+pub struct InfoSQLDatabaseTool {
+    database: Arc<SQLDatabase>,
+}
+
+impl InfoSQLDatabaseTool {
+    pub fn new(database: Arc<SQLDatabase>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl Tool for InfoSQLDatabaseTool {
+    fn name(&self) -> String {
+        String::from("sql_db_schema")
+    }
+
+    fn description(&self) -> String {
+        String::from(
+            "Input to this tool is a comma-separated list of tables, output is the schema and sample rows for those tables.
+            Be sure that the tables actually exist by calling sql_db_list_tables first!
+            Example Input: table1, table2, table3",
+        )
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "input": {
+                    "type": "string",
+                    "description": "A comma-separated list of table names"
+                }
+            },
+            "required": ["input"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let input = input.as_str().ok_or("Input should be a string")?;
+        let known_tables = self.database.table_names();
+        let tables: Vec<String> = input
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let unknown: Vec<&String> = tables
+            .iter()
+            .filter(|t| !known_tables.contains(t))
+            .collect();
+        if !unknown.is_empty() {
+            return Ok(format!(
+                "Error: tables not found in the database: {}. Use sql_db_list_tables to see the available tables.",
+                unknown
+                    .iter()
+                    .map(|t| t.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        match self.database.table_info(&tables).await {
+            Ok(info) => Ok(info),
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+}
+
+/// Executes a SQL query and returns the result.
+///
+/// By default the tool is read-only: the query runs in a read-only transaction, so the
+/// database rejects any write, and at most `max_rows` rows are fetched. Functions with side
+/// effects which aren't writes (e.g. `pg_terminate_backend`) are only prevented by the
+/// privileges of the database user, so connect with a user which can only read. Database errors are returned as the tool output
+/// instead of failing, so the agent can read the error and rewrite the query.
+pub struct QuerySQLDatabaseTool {
+    database: Arc<SQLDatabase>,
+    max_rows: usize,
+    read_only: bool,
+}
+
+impl QuerySQLDatabaseTool {
+    pub fn new(database: Arc<SQLDatabase>) -> Self {
+        Self {
+            database,
+            max_rows: DEFAULT_MAX_ROWS,
+            read_only: true,
+        }
+    }
+
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for QuerySQLDatabaseTool {
+    fn name(&self) -> String {
+        String::from("sql_db_query")
+    }
+
+    fn description(&self) -> String {
+        String::from(
+            "Input to this tool is a detailed and correct SQL query, output is a result from the database.
+            If the query is not correct, an error message will be returned.
+            If an error is returned, rewrite the query, check the query, and try again.
+            If you encounter an issue with Unknown column, use sql_db_schema to query the correct table fields.",
+        )
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "input": {
+                    "type": "string",
+                    "description": "A detailed and correct SQL query"
+                }
+            },
+            "required": ["input"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let query = input.as_str().ok_or("Input should be a string")?.trim();
+        let query = query.trim_end_matches(';').trim();
+
+        if self.read_only {
+            if let Err(reason) = check_read_only(query) {
+                return Ok(format!("Error: {}", reason));
+            }
+        }
+
+        match self
+            .database
+            .query_with_limit(query, self.max_rows, self.read_only)
+            .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Ok(format!(
+                "Error: {}\nRewrite the query, check the query, and try again.",
+                e
+            )),
+        }
+    }
+}
+
+/// Returns the list, schema and query tools sharing the same database, ready to be
+/// handed to an agent.
+///
+/// # Example
+/// ```rust,ignore
+/// let db = SQLDatabaseBuilder::new(engine).build().await?;
+/// let tools = sql_database_tools(Arc::new(db));
+/// let agent = OpenAiToolAgentBuilder::new().tools(&tools).build(llm)?;
+/// ```
+pub fn sql_database_tools(database: Arc<SQLDatabase>) -> Vec<Arc<dyn Tool>> {
+    vec![
+        Arc::new(ListSQLDatabaseTablesTool::new(database.clone())),
+        Arc::new(InfoSQLDatabaseTool::new(database.clone())),
+        Arc::new(QuerySQLDatabaseTool::new(database)),
+    ]
+}
+
+/// Rejects the queries which obviously modify the database, with the reason, so the LLM
+/// gets a clear error. This is not a guarantee, the query has to run in a read-only
+/// transaction too.
+pub(crate) fn check_read_only(query: &str) -> Result<(), String> {
+    let upper = strip_literals(query).to_uppercase();
+    if upper.contains(';') {
+        return Err("only a single statement can be executed".to_string());
+    }
+
+    let first = upper.split_whitespace().next().unwrap_or("");
+    if !["SELECT", "WITH", "EXPLAIN", "SHOW", "DESCRIBE", "PRAGMA"].contains(&first) {
+        return Err("only read-only queries (SELECT) are allowed".to_string());
+    }
+
+    let words: Vec<&str> = upper
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .collect();
+    if let Some(keyword) = WRITE_KEYWORDS.iter().find(|k| words.contains(k)) {
+        return Err(format!(
+            "the query contains the forbidden keyword {}, only read-only queries are allowed",
+            keyword
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether the query is a single statement, maybe ended by a `;`.
+pub(crate) fn is_single_statement(query: &str) -> bool {
+    !strip_literals(query)
+        .trim_end()
+        .trim_end_matches(';')
+        .contains(';')
+}
+
+/// The query with the content of the string literals, of the quoted identifiers and of
+/// the comments blanked out, so the words in them aren't taken for keywords.
+fn strip_literals(query: &str) -> String {
+    let mut stripped = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                stripped.push(c);
+                for next in chars.by_ref() {
+                    if next == c {
+                        stripped.push(c);
+                        break;
+                    }
+                    stripped.push(' ');
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        stripped.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                stripped.push(' ');
+            }
+            _ => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{Dialect, Engine, SQLDatabaseBuilder};
+
+    struct MockEngine {}
+
+    #[async_trait]
+    impl Engine for MockEngine {
+        fn dialect(&self) -> Dialect {
+            Dialect::SQLite
+        }
+
+        async fn query(
+            &self,
+            query: &str,
+        ) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn Error>> {
+            if query.contains("missing") {
+                return Err("no such column: missing".into());
+            }
+            let rows = (0..5).map(|i| vec![i.to_string()]).collect();
+            Ok((vec!["id".to_string()], rows))
+        }
+
+        async fn query_with_limit(
+            &self,
+            query: &str,
+            max_rows: usize,
+            read_only: bool,
+        ) -> Result<(Vec<String>, Vec<Vec<String>>, bool), Box<dyn Error>> {
+            if read_only && query.contains("setval") {
+                return Err("cannot execute setval() in a read-only transaction".into());
+            }
+            let (cols, mut rows) = self.query(query).await?;
+            let truncated = rows.len() > max_rows;
+            rows.truncate(max_rows);
+            Ok((cols, rows, truncated))
+        }
+
+        async fn table_names(&self) -> Result<Vec<String>, Box<dyn Error>> {
+            Ok(vec!["users".to_string(), "orders".to_string()])
+        }
+
+        async fn table_info(&self, table: &str) -> Result<String, Box<dyn Error>> {
+            Ok(format!("CREATE TABLE {} (id integer)", table))
+        }
+
+        fn close(&self) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+    }
+
+    async fn database() -> Arc<SQLDatabase> {
+        Arc::new(
+            SQLDatabaseBuilder::new(MockEngine {})
+                .custom_sample_rows_number(0)
+                .build()
+                .await
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_list_tables() {
+        let tool = ListSQLDatabaseTablesTool::new(database().await);
+        assert_eq!(tool.call("").await.unwrap(), "orders, users");
+    }
+
+    #[tokio::test]
+    async fn test_schema_unknown_table() {
+        let tool = InfoSQLDatabaseTool::new(database().await);
+        let result = tool.call("users, products").await.unwrap();
+        assert!(result.starts_with("Error"));
+        assert!(result.contains("products"));
+
+        let result = tool.call("users").await.unwrap();
+        assert!(result.contains("CREATE TABLE users"));
+    }
+
+    #[tokio::test]
+    async fn test_query_row_limit() {
+        let tool = QuerySQLDatabaseTool::new(database().await).with_max_rows(2);
+        let result = tool.call("SELECT id FROM users").await.unwrap();
+        assert_eq!(result, "id\n0\n1\n(only the first 2 rows are shown)\n");
+    }
+
+    #[tokio::test]
+    async fn test_query_errors_are_returned_as_observation() {
+        let tool = QuerySQLDatabaseTool::new(database().await);
+        let result = tool.call("SELECT missing FROM users").await.unwrap();
+        assert!(result.starts_with("Error: no such column"));
+
+        let result = tool.call("DROP TABLE users").await.unwrap();
+        assert!(result.starts_with("Error: only read-only"));
+
+        let result = tool
+            .call("WITH x AS (DELETE FROM users) SELECT * FROM x")
+            .await
+            .unwrap();
+        assert!(result.contains("DELETE"));
+
+        let result = tool.call("SELECT 1; DROP TABLE users").await.unwrap();
+        assert!(result.contains("single statement"));
+
+        let result = tool.call("SELECT setval('users_id_seq', 1)").await.unwrap();
+        assert!(result.contains("read-only transaction"));
+    }
+
+    #[tokio::test]
+    async fn test_query_keywords_in_literals() {
+        let tool = QuerySQLDatabaseTool::new(database().await).with_max_rows(1);
+        let result = tool
+            .call("SELECT id FROM users WHERE note = 'drop; delete it' -- insert later")
+            .await
+            .unwrap();
+        assert_eq!(result, "id\n0\n(only the first 1 rows are shown)\n");
+
+        let result = tool
+            .call("SELECT id INTO backup FROM \"users\"")
+            .await
+            .unwrap();
+        assert!(result.contains("INTO"));
+    }
+}
@@ -0,0 +1,159 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use super::Tool;
+
+/// A tool taking its arguments as a typed struct. Every structured tool is a [`Tool`]:
+/// its parameters are the JSON schema of the arguments, sent to the function calling
+/// models, and the arguments generated by the model are deserialized into the struct
+/// before running the tool. Invalid arguments are returned as an error, which the agent
+/// reports to the model.
+///
+/// The methods share their names with those of [`Tool`], call them through a
+/// `dyn Tool` or with the fully qualified syntax.
+///
+/// # Example
+/// ```rust,ignore
+/// #[derive(Deserialize, JsonSchema)]
+/// struct WeatherArgs {
+///     /// The city, e.g. Paris
+///     city: String,
+///     days: Option<u32>,
+/// }
+///
+/// struct Weather;
+///
+/// #[async_trait]
+/// impl StructuredTool for Weather {
+///     type Args = WeatherArgs;
+///
+///     fn name(&self) -> String {
+///         "weather".to_string()
+///     }
+///
+///     fn description(&self) -> String {
+///         "Forecasts the weather of a city".to_string()
+///     }
+///
+///     async fn run(&self, args: WeatherArgs) -> Result<String, Box<dyn Error>> {
+///         forecast(&args.city, args.days.unwrap_or(1)).await
+///     }
+/// }
+///
+/// let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(Weather)];
+/// ```
+#[async_trait]
+pub trait StructuredTool: Send + Sync {
+    type Args: DeserializeOwned + JsonSchema + Send;
+
+    fn name(&self) -> String;
+
+    fn description(&self) -> String;
+
+    async fn run(&self, args: Self::Args) -> Result<String, Box<dyn Error>>;
+}
+
+#[async_trait]
+impl<T: StructuredTool> Tool for T {
+    fn name(&self) -> String {
+        StructuredTool::name(self)
+    }
+
+    fn description(&self) -> String {
+        StructuredTool::description(self)
+    }
+
+    fn parameters(&self) -> Value {
+        let mut schema = schemars::schema_for!(T::Args).to_value();
+        if let Some(schema) = schema.as_object_mut() {
+            schema.remove("$schema");
+            schema.remove("title");
+        }
+        schema
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args: T::Args = serde_json::from_value(input).map_err(|e| {
+            format!(
+                "Invalid arguments for the tool {}: {}",
+                StructuredTool::name(self),
+                e
+            )
+        })?;
+        StructuredTool::run(self, args).await
+    }
+
+    /// The arguments are a JSON object. The agents passing a raw string, e.g. ReAct, can
+    /// still call the tools with a single argument, otherwise the string is passed as is
+    /// to fail the deserialization of the arguments with a meaningful error.
+    async fn parse_input(&self, input: &str) -> Value {
+        if let Ok(Value::Object(object)) = serde_json::from_str(input) {
+            return Value::Object(object);
+        }
+        match Tool::parameters(self)["properties"].as_object() {
+            Some(properties) if properties.len() == 1 => {
+                let argument = properties.keys().next().cloned().unwrap_or_default();
+                json!({ argument: input })
+            }
+            _ => Value::String(input.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, JsonSchema)]
+    struct RepeatArgs {
+        text: String,
+        times: Option<usize>,
+    }
+
+    struct Repeat;
+
+    #[async_trait]
+    impl StructuredTool for Repeat {
+        type Args = RepeatArgs;
+
+        fn name(&self) -> String {
+            "repeat".to_string()
+        }
+
+        fn description(&self) -> String {
+            "Repeats a text".to_string()
+        }
+
+        async fn run(&self, args: RepeatArgs) -> Result<String, Box<dyn Error>> {
+            Ok(args.text.repeat(args.times.unwrap_or(1)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_structured_tool() {
+        let tool: Arc<dyn Tool> = Arc::new(Repeat);
+        assert_eq!(tool.name(), "repeat");
+        let parameters = tool.parameters();
+        assert_eq!(parameters["required"], json!(["text"]));
+        assert_eq!(parameters["properties"]["text"]["type"], "string");
+        assert!(parameters.get("$schema").is_none());
+
+        assert_eq!(
+            tool.call(r#"{"text": "ab", "times": 3}"#).await.unwrap(),
+            "ababab"
+        );
+        assert_eq!(tool.call(r#"{"text": "ab"}"#).await.unwrap(), "ab");
+        let error = tool.call("ab").await.unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Invalid arguments for the tool repeat"));
+    }
+}
@@ -0,0 +1,166 @@
+use std::{error::Error, sync::Arc};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::tools::{deliver_speech, SpeechOutput, SpeechStorage, Tool};
+
+#[derive(Clone)]
+pub struct Text2SpeechElevenLabs {
+    api_key: String,
+    voice_id: String,
+    model_id: String,
+    output_format: String,
+    storage: Option<Arc<dyn SpeechStorage>>,
+    path: String,
+    output: SpeechOutput,
+    base_url: String,
+}
+
+impl Text2SpeechElevenLabs {
+    pub fn new<S: Into<String>>(api_key: S) -> Self {
+        Self {
+            api_key: api_key.into(),
+            voice_id: "21m00Tcm4TlvDq8ikWAM".to_string(),
+            model_id: "eleven_multilingual_v2".to_string(),
+            output_format: "mp3_44100_128".to_string(),
+            storage: None,
+            path: "./data/audio.mp3".to_string(),
+            output: SpeechOutput::Path,
+            base_url: "https://api.elevenlabs.io".to_string(),
+        }
+    }
+
+    pub fn with_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    pub fn with_voice_id<S: Into<String>>(mut self, voice_id: S) -> Self {
+        self.voice_id = voice_id.into();
+        self
+    }
+
+    pub fn with_model_id<S: Into<String>>(mut self, model_id: S) -> Self {
+        self.model_id = model_id.into();
+        self
+    }
+
+    /// Output format of the audio, e.g. `mp3_44100_128` or `pcm_16000`.
+    pub fn with_output_format<S: Into<String>>(mut self, output_format: S) -> Self {
+        self.output_format = output_format.into();
+        self
+    }
+
+    pub fn with_storage<SS: SpeechStorage + 'static>(mut self, storage: SS) -> Self {
+        self.storage = Some(Arc::new(storage));
+        self
+    }
+
+    pub fn with_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Return the audio as a path (default) or as a base64 string.
+    pub fn with_output(mut self, output: SpeechOutput) -> Self {
+        self.output = output;
+        self
+    }
+
+    pub fn with_base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+}
+
+impl Default for Text2SpeechElevenLabs {
+    fn default() -> Self {
+        Self::new(std::env::var("ELEVENLABS_API_KEY").unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl Tool for Text2SpeechElevenLabs {
+    fn name(&self) -> String {
+        "Text2SpeechElevenLabs".to_string()
+    }
+
+    fn description(&self) -> String {
+        String::from(
+            "A wrapper around ElevenLabs Text2Speech. \
+            Useful for when you need to convert text to speech. \
+            It supports multiple languages, including English, German, Polish, \
+            Spanish, Italian, French, Portuguese",
+        )
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let input = input.as_str().ok_or("Invalid input")?;
+
+        let response = reqwest::Client::new()
+            .post(format!(
+                "{}/v1/text-to-speech/{}",
+                self.base_url, self.voice_id
+            ))
+            .query(&[("output_format", &self.output_format)])
+            .header("xi-api-key", &self.api_key)
+            .json(&json!({ "text": input, "model_id": self.model_id }))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await?;
+            return Err(format!("ElevenLabs Error {}: {}", status.as_u16(), body).into());
+        }
+
+        let data = response.bytes().await?;
+        deliver_speech(&self.output, &self.storage, &self.path, &data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_elevenlabs_base64() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/text-to-speech/voice")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "output_format".into(),
+                "mp3_44100_128".into(),
+            ))
+            .match_header("xi-api-key", "key")
+            .with_body("audio")
+            .create_async()
+            .await;
+
+        let tts = Text2SpeechElevenLabs::new("key")
+            .with_base_url(server.url())
+            .with_voice_id("voice")
+            .with_output(SpeechOutput::Base64);
+        assert_eq!(tts.call("Hello").await.unwrap(), "YXVkaW8=");
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn test_elevenlabs_description() {
+        assert_eq!(
+            Text2SpeechElevenLabs::new("key").description(),
+            "A wrapper around ElevenLabs Text2Speech. Useful for when you need to convert text \
+             to speech. It supports multiple languages, including English, German, Polish, \
+             Spanish, Italian, French, Portuguese"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn elevenlabs_text2speech_tool() {
+        let tts = Text2SpeechElevenLabs::default();
+        let s = tts.call("Hola como estas").await.unwrap();
+        println!("{}", s);
+    }
+}
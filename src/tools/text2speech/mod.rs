@@ -1,5 +1,8 @@
 mod openai;
 pub use openai::*;
 
+mod elevenlabs;
+pub use elevenlabs::*;
+
 mod speech_storage;
 pub use speech_storage::*;
@@ -9,7 +9,7 @@ pub use async_openai::{
 use async_trait::async_trait;
 use serde_json::Value;
 
-use crate::tools::{SpeechStorage, Tool};
+use crate::tools::{deliver_speech, SpeechOutput, SpeechStorage, Tool};
 
 #[derive(Clone)]
 pub struct Text2SpeechOpenAI<C: Config> {
@@ -19,6 +19,7 @@ pub struct Text2SpeechOpenAI<C: Config> {
     storage: Option<Arc<dyn SpeechStorage>>,
     response_format: SpeechResponseFormat,
     path: String,
+    output: SpeechOutput,
 }
 
 impl<C: Config> Text2SpeechOpenAI<C> {
@@ -30,6 +31,7 @@ impl<C: Config> Text2SpeechOpenAI<C> {
             storage: None,
             response_format: SpeechResponseFormat::Mp3,
             path: "./data/audio.mp3".to_string(),
+            output: SpeechOutput::Path,
         }
     }
 
@@ -58,6 +60,12 @@ impl<C: Config> Text2SpeechOpenAI<C> {
         self
     }
 
+    /// Return the audio as a path (default) or as a base64 string.
+    pub fn with_output(mut self, output: SpeechOutput) -> Self {
+        self.output = output;
+        self
+    }
+
     pub fn with_config(mut self, config: C) -> Self {
         self.config = config;
         self
@@ -77,18 +85,18 @@ impl<C: Config + Send + Sync> Tool for Text2SpeechOpenAI<C> {
     }
 
     fn description(&self) -> String {
-        format!(
-            r#"A wrapper around OpenAI Text2Speech. "
-        "Useful for when you need to convert text to speech. "
-        "It supports multiple languages, including English, German, Polish, "
-        "Spanish, Italian, French, Portuguese""#
+        String::from(
+            "A wrapper around OpenAI Text2Speech. \
+            Useful for when you need to convert text to speech. \
+            It supports multiple languages, including English, German, Polish, \
+            Spanish, Italian, French, Portuguese",
         )
     }
 
     async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
         let input = input.as_str().ok_or("Invalid input")?;
-        let client = Client::new();
-        let response_format: SpeechResponseFormat = self.response_format.clone().into();
+        let client = Client::with_config(self.config.clone());
+        let response_format: SpeechResponseFormat = self.response_format;
 
         let request = CreateSpeechRequestArgs::default()
             .input(input)
@@ -99,15 +107,7 @@ impl<C: Config + Send + Sync> Tool for Text2SpeechOpenAI<C> {
 
         let response = client.audio().speech(request).await?;
 
-        if self.storage.is_some() {
-            let storage = self.storage.as_ref().unwrap(); //safe to unwrap
-            let data = response.bytes;
-            return storage.save(&self.path, &data).await;
-        } else {
-            response.save(&self.path).await?;
-        }
-
-        Ok(self.path.clone())
+        deliver_speech(&self.output, &self.storage, &self.path, &response.bytes).await
     }
 }
 
@@ -1,8 +1,40 @@
-use std::error::Error;
+use std::{error::Error, sync::Arc};
 
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
 
 #[async_trait]
 pub trait SpeechStorage: Send + Sync {
     async fn save(&self, key: &str, data: &[u8]) -> Result<String, Box<dyn Error>>;
 }
+
+/// How the text to speech tools return the generated audio.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SpeechOutput {
+    /// The audio is saved to the path (or the storage) and its location is returned.
+    #[default]
+    Path,
+    /// The audio is returned as a base64 encoded string.
+    Base64,
+}
+
+pub(crate) async fn deliver_speech(
+    output: &SpeechOutput,
+    storage: &Option<Arc<dyn SpeechStorage>>,
+    path: &str,
+    data: &[u8],
+) -> Result<String, Box<dyn Error>> {
+    match output {
+        SpeechOutput::Base64 => Ok(STANDARD.encode(data)),
+        SpeechOutput::Path => match storage {
+            Some(storage) => storage.save(path, data).await,
+            None => {
+                if let Some(parent) = std::path::Path::new(path).parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(path, data).await?;
+                Ok(path.to_string())
+            }
+        },
+    }
+}
@@ -0,0 +1,74 @@
+//! The [`tool`] macro, generating a [`StructuredTool`](super::StructuredTool) from an async
+//! function.
+
+pub use langchain_rust_macros::tool;
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, sync::Arc};
+
+    use serde_json::json;
+
+    use crate::tools::Tool;
+
+    use super::*;
+
+    /// Repeats a text.
+    #[tool]
+    async fn repeat(
+        /// The text to repeat
+        text: String,
+        /// The number of repetitions, 1 by default
+        times: Option<usize>,
+    ) -> Result<String, Box<dyn Error>> {
+        if times == Some(0) {
+            return Err("times must be positive".into());
+        }
+        Ok(text.repeat(times.unwrap_or(1)))
+    }
+
+    #[tool(name = "shout", description = "Uppercases a text")]
+    async fn uppercase(text: String) -> String {
+        text.to_uppercase()
+    }
+
+    #[tokio::test]
+    async fn test_tool_macro() {
+        let tool: Arc<dyn Tool> = Arc::new(RepeatTool);
+        assert_eq!(tool.name(), "repeat");
+        assert_eq!(tool.description(), "Repeats a text.");
+        let parameters = tool.parameters();
+        assert_eq!(parameters["required"], json!(["text"]));
+        assert_eq!(
+            parameters["properties"]["text"],
+            json!({"type": "string", "description": "The text to repeat"})
+        );
+        assert_eq!(
+            parameters["properties"]["times"]["description"],
+            "The number of repetitions, 1 by default"
+        );
+
+        assert_eq!(
+            tool.call(r#"{"text": "ab", "times": 2}"#).await.unwrap(),
+            "abab"
+        );
+        assert_eq!(
+            tool.call(r#"{"text": "ab", "times": 0}"#)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "times must be positive"
+        );
+        assert!(tool
+            .call(r#"{"times": 2}"#)
+            .await
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid arguments for the tool repeat: missing field `text`"));
+
+        let tool = UppercaseTool;
+        assert_eq!(tool.name(), "shout");
+        assert_eq!(tool.description(), "Uppercases a text");
+        assert_eq!(tool.call("hi").await.unwrap(), "HI");
+    }
+}
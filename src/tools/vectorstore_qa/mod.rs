@@ -0,0 +1,2 @@
+mod vectorstore_qa_tool;
+pub use vectorstore_qa_tool::*;
@@ -0,0 +1,206 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{
+    chain::{Chain, StuffDocument, StuffQAPromptBuilder},
+    language_models::llm::LLM,
+    schemas::{Document, Retriever},
+    tools::Tool,
+};
+
+const DEFAULT_MAX_SOURCE_CHARS: usize = 100;
+
+/// Lets an agent consult a knowledge base as just another tool.
+///
+/// The input question is sent to the retriever, the retrieved documents are answered
+/// with a question answering chain, and the observation contains the answer followed by
+/// the sources that were used. The source of a document is its `source` metadata when
+/// present, otherwise the beginning of its content.
+///
+/// # Example
+/// ```rust,ignore
+/// let retriever = Retriever::new(store, 4);
+/// let tool = VectorStoreQATool::new(OpenAI::default(), retriever)
+///     .with_name("internal_docs")
+///     .with_description("Search the internal documentation of the company. Input should be a question.");
+/// ```
+pub struct VectorStoreQATool {
+    name: String,
+    description: String,
+    retriever: Box<dyn Retriever>,
+    chain: Box<dyn Chain>,
+    return_sources: bool,
+}
+
+impl VectorStoreQATool {
+    /// Creates the tool with the default stuff question answering chain.
+    pub fn new<L: Into<Box<dyn LLM>>, R: Into<Box<dyn Retriever>>>(llm: L, retriever: R) -> Self {
+        Self::with_chain(StuffDocument::load_stuff_qa(llm), retriever)
+    }
+
+    /// Creates the tool with a custom chain. The chain receives the retrieved documents
+    /// in `input_documents` and the question in `question`, like `StuffDocument`.
+    pub fn with_chain<C: Chain + 'static, R: Into<Box<dyn Retriever>>>(
+        chain: C,
+        retriever: R,
+    ) -> Self {
+        Self {
+            name: String::from("knowledge_base"),
+            description: String::from(
+                "Useful for when you need to answer questions using the private knowledge base. Input should be a fully formed question.",
+            ),
+            retriever: retriever.into(),
+            chain: Box::new(chain),
+            return_sources: true,
+        }
+    }
+
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Whether the sources are appended to the observation. Default: true
+    pub fn with_return_sources(mut self, return_sources: bool) -> Self {
+        self.return_sources = return_sources;
+        self
+    }
+}
+
+fn document_source(document: &Document) -> String {
+    if let Some(source) = document.metadata.get("source") {
+        return match source {
+            Value::String(source) => source.clone(),
+            other => other.to_string(),
+        };
+    }
+
+    let content = document.page_content.trim().replace('\n', " ");
+    if content.chars().count() > DEFAULT_MAX_SOURCE_CHARS {
+        format!(
+            "{}...",
+            content
+                .chars()
+                .take(DEFAULT_MAX_SOURCE_CHARS)
+                .collect::<String>()
+        )
+    } else {
+        content
+    }
+}
+
+#[async_trait]
+impl Tool for VectorStoreQATool {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let question = input.as_str().ok_or("Input should be a string")?;
+
+        let documents = self.retriever.get_relevant_documents(question).await?;
+        if documents.is_empty() {
+            return Ok("No relevant documents were found in the knowledge base.".to_string());
+        }
+
+        let input = StuffQAPromptBuilder::new()
+            .documents(&documents)
+            .question(question)
+            .build();
+        let answer = self.chain.invoke(input).await?;
+
+        if !self.return_sources {
+            return Ok(answer);
+        }
+
+        let mut sources: Vec<String> = Vec::new();
+        for source in documents.iter().map(document_source) {
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        Ok(format!(
+            "{}\n\nSources:\n{}",
+            answer.trim(),
+            sources
+                .iter()
+                .map(|s| format!("- {}", s))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        chain::ChainError, language_models::GenerateResult, prompt::PromptArgs,
+        retrievers::FakeRetriever,
+    };
+
+    fn fake_retriever() -> FakeRetriever {
+        FakeRetriever::new(vec![
+            Document::new("Vacations are 25 days per year.").with_metadata(HashMap::from([(
+                "source".to_string(),
+                json!("handbook.md"),
+            )])),
+            Document::new("Remote work is allowed on fridays.").with_metadata(HashMap::from([(
+                "source".to_string(),
+                json!("handbook.md"),
+            )])),
+            Document::new("Holidays follow the local calendar."),
+        ])
+    }
+
+    struct MockChain {}
+
+    #[async_trait]
+    impl Chain for MockChain {
+        async fn call(&self, input_variables: PromptArgs) -> Result<GenerateResult, ChainError> {
+            let documents = input_variables["input_documents"].as_array().unwrap();
+            Ok(GenerateResult {
+                generation: format!("Answer from {} documents", documents.len()),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vectorstore_qa_tool() {
+        let tool = VectorStoreQATool::with_chain(MockChain {}, fake_retriever())
+            .with_name("internal_docs");
+        assert_eq!(tool.name(), "internal_docs");
+
+        let result = tool
+            .call("How many vacation days do I have?")
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            "Answer from 3 documents\n\nSources:\n- handbook.md\n- Holidays follow the local calendar."
+        );
+
+        let tool = tool.with_return_sources(false);
+        let result = tool
+            .call("How many vacation days do I have?")
+            .await
+            .unwrap();
+        assert_eq!(result, "Answer from 3 documents");
+    }
+}
@@ -0,0 +1,2 @@
+mod webhook_tool;
+pub use webhook_tool::*;
@@ -0,0 +1,219 @@
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use crate::{runtime::send, tools::Tool};
+
+const DEFAULT_MAX_RESPONSE_LENGTH: usize = 4000;
+const DEFAULT_SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// Posts the arguments chosen by the agent as json to a webhook, so agents can trigger
+/// external automations (Zapier, n8n, internal services...) without provider specific
+/// code.
+///
+/// When a secret is set, the body is signed with HMAC-SHA256 and the signature is sent
+/// in the `X-Signature-256` header as `sha256=<hex digest>`, so the receiver can check
+/// that the request comes from the agent.
+///
+/// # Example
+/// ```rust,ignore
+/// let tool = WebhookTool::new("https://hooks.example.com/create-ticket")
+///     .with_name("create_ticket")
+///     .with_description("Creates a support ticket")
+///     .with_parameters(json!({
+///         "type": "object",
+///         "properties": {
+///             "title": { "type": "string" },
+///             "priority": { "type": "string", "enum": ["low", "high"] }
+///         },
+///         "required": ["title"]
+///     }))
+///     .with_secret(std::env::var("WEBHOOK_SECRET")?);
+/// ```
+pub struct WebhookTool {
+    url: String,
+    name: String,
+    description: String,
+    parameters: Option<Value>,
+    secret: Option<String>,
+    signature_header: String,
+    headers: HashMap<String, String>,
+    max_response_length: usize,
+    client: reqwest::Client,
+}
+
+impl WebhookTool {
+    pub fn new<S: Into<String>>(url: S) -> Self {
+        Self {
+            url: url.into(),
+            name: "webhook".to_string(),
+            description: "Triggers an external automation. Input should be a json object with the arguments of the automation.".to_string(),
+            parameters: None,
+            secret: None,
+            signature_header: DEFAULT_SIGNATURE_HEADER.to_string(),
+            headers: HashMap::new(),
+            max_response_length: DEFAULT_MAX_RESPONSE_LENGTH,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Json schema of the arguments, exposed as the parameters of the tool.
+    pub fn with_parameters(mut self, parameters: Value) -> Self {
+        self.parameters = Some(parameters);
+        self
+    }
+
+    /// Secret used to sign the body of every request.
+    pub fn with_secret<S: Into<String>>(mut self, secret: S) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    pub fn with_signature_header<S: Into<String>>(mut self, signature_header: S) -> Self {
+        self.signature_header = signature_header.into();
+        self
+    }
+
+    pub fn with_header<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_max_response_length(mut self, max_response_length: usize) -> Self {
+        self.max_response_length = max_response_length;
+        self
+    }
+
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+/// Returns the `sha256=<hex digest>` HMAC signature of `body`.
+pub fn sign_webhook_body(secret: &str, body: &[u8]) -> Result<String, Box<dyn Error>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("sha256={}", hex))
+}
+
+#[async_trait]
+impl Tool for WebhookTool {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn parameters(&self) -> Value {
+        match &self.parameters {
+            Some(parameters) => parameters.clone(),
+            None => json!({
+                "type": "object",
+                "properties": {
+                    "input": {
+                        "type": "string",
+                        "description": self.description
+                    }
+                },
+                "required": ["input"]
+            }),
+        }
+    }
+
+    async fn parse_input(&self, input: &str) -> Value {
+        match serde_json::from_str::<Value>(input) {
+            Ok(input) if input.is_object() => input,
+            _ => json!({ "input": input }),
+        }
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let body = serde_json::to_vec(&input)?;
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json");
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        if let Some(secret) = &self.secret {
+            request = request.header(&self.signature_header, sign_webhook_body(secret, &body)?);
+        }
+
+        let (status, mut text) = send(async {
+            let response = request.body(body).send().await?;
+            Ok::<_, reqwest::Error>((response.status(), response.text().await?))
+        })
+        .await?;
+        if text.chars().count() > self.max_response_length {
+            text = text.chars().take(self.max_response_length).collect();
+            text.push_str("... (truncated)");
+        }
+
+        if !status.is_success() {
+            return Ok(format!(
+                "Error: the webhook responded with status {}: {}",
+                status, text
+            ));
+        }
+        if text.trim().is_empty() {
+            return Ok(format!("The webhook accepted the request ({})", status));
+        }
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_webhook_body() {
+        // Reference value from the GitHub webhooks documentation.
+        assert_eq!(
+            sign_webhook_body("It's a Secret to Everybody", b"Hello, World!").unwrap(),
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_tool() {
+        let body = r#"{"title":"Printer is broken"}"#;
+        let signature = sign_webhook_body("secret", body.as_bytes()).unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/hook")
+            .match_header("x-signature-256", signature.as_str())
+            .match_body(body)
+            .with_status(202)
+            .create_async()
+            .await;
+
+        let tool = WebhookTool::new(format!("{}/hook", server.url()))
+            .with_name("create_ticket")
+            .with_secret("secret");
+        let result = tool.call(body).await.unwrap();
+        assert_eq!(result, "The webhook accepted the request (202 Accepted)");
+        mock.assert_async().await;
+    }
+}
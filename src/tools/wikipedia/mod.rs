@@ -0,0 +1,2 @@
+mod wikipedia_tool;
+pub use wikipedia_tool::*;
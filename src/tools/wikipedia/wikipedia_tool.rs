@@ -0,0 +1,363 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{runtime::send, tools::Tool};
+
+const DEFAULT_MAX_CHARS: usize = 4000;
+
+/// Searches Wikipedia and returns the summary of the best matching articles, each one
+/// followed by its source url.
+///
+/// The input can also be `Article title#Section`, or the url of a section of an article,
+/// to fetch that single section. A `#` is only read as the start of a section when a name
+/// follows it, so the titles like `C#` or `F# (programming language)` are searched as is.
+pub struct Wikipedia {
+    language: String,
+    top_k_results: usize,
+    max_chars: usize,
+    full_article: bool,
+    base_url: Option<String>,
+    client: reqwest::Client,
+}
+
+impl Wikipedia {
+    pub fn new() -> Self {
+        Self {
+            language: "en".to_string(),
+            top_k_results: 3,
+            max_chars: DEFAULT_MAX_CHARS,
+            full_article: false,
+            base_url: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Language subdomain of Wikipedia to use, e.g. `en`, `es`, `de`. Default: `en`
+    pub fn with_language<S: Into<String>>(mut self, language: S) -> Self {
+        self.language = language.into();
+        self
+    }
+
+    /// Number of articles returned for a search. Default: 3
+    pub fn with_top_k_results(mut self, top_k_results: usize) -> Self {
+        self.top_k_results = top_k_results;
+        self
+    }
+
+    /// Maximum number of characters of the whole output. Default: 4000
+    pub fn with_max_chars(mut self, max_chars: usize) -> Self {
+        self.max_chars = max_chars;
+        self
+    }
+
+    /// Return the whole article instead of only the introduction.
+    pub fn with_full_article(mut self, full_article: bool) -> Self {
+        self.full_article = full_article;
+        self
+    }
+
+    /// Overrides the base url, e.g. `https://en.wikipedia.org`. Mostly useful for testing.
+    pub fn with_base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    fn base_url(&self) -> String {
+        self.base_url
+            .clone()
+            .unwrap_or_else(|| format!("https://{}.wikipedia.org", self.language))
+    }
+
+    fn page_url(&self, title: &str) -> String {
+        format!(
+            "https://{}.wikipedia.org/wiki/{}",
+            self.language,
+            urlencoding::encode(&title.replace(' ', "_"))
+        )
+    }
+
+    pub async fn search(&self, query: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let response: Value = self
+            .client
+            .get(format!("{}/w/api.php", self.base_url()))
+            .query(&[
+                ("action", "query"),
+                ("list", "search"),
+                ("format", "json"),
+                ("srsearch", query),
+                ("srlimit", &self.top_k_results.to_string()),
+            ])
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        Ok(response["query"]["search"]
+            .as_array()
+            .map(|results| {
+                results
+                    .iter()
+                    .filter_map(|r| r["title"].as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Returns the plain text of the article, or only its introduction when `intro` is set.
+    pub async fn extract(
+        &self,
+        title: &str,
+        intro: bool,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let mut params = vec![
+            ("action", "query"),
+            ("prop", "extracts"),
+            ("explaintext", "1"),
+            ("redirects", "1"),
+            ("format", "json"),
+            ("titles", title),
+        ];
+        if intro {
+            params.push(("exintro", "1"));
+        }
+
+        let response: Value = self
+            .client
+            .get(format!("{}/w/api.php", self.base_url()))
+            .query(&params)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        let extract = response["query"]["pages"]
+            .as_object()
+            .and_then(|pages| pages.values().next())
+            .and_then(|page| page["extract"].as_str())
+            .map(|extract| extract.trim().to_string())
+            .filter(|extract| !extract.is_empty());
+        Ok(extract)
+    }
+
+    async fn section(&self, title: &str, section: &str) -> Result<String, Box<dyn Error>> {
+        let url = format!("{}#{}", self.page_url(title), section.replace(' ', "_"));
+        let article = match self.extract(title, false).await? {
+            Some(article) => article,
+            None => return Ok(format!("No Wikipedia article found for {}", title)),
+        };
+
+        match find_section(&article, section) {
+            Some(text) => Ok(self.truncate(format!(
+                "Page: {}\nSection: {}\n{}\nSource: {}",
+                title, section, text, url
+            ))),
+            None => Ok(format!(
+                "Section {} not found in the Wikipedia article {}",
+                section, title
+            )),
+        }
+    }
+
+    fn truncate(&self, mut output: String) -> String {
+        if output.chars().count() > self.max_chars {
+            output = output.chars().take(self.max_chars).collect();
+            output.push_str("...");
+        }
+        output
+    }
+}
+
+impl Default for Wikipedia {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// MediaWiki plain text extracts mark headings as `== Heading ==`, with more `=` for
+// nested levels. The section ends at the next heading of the same or a higher level.
+fn find_section(article: &str, section: &str) -> Option<String> {
+    let mut level = None;
+    let mut lines = Vec::new();
+    for line in article.lines() {
+        let trimmed = line.trim();
+        let heading_level = trimmed.chars().take_while(|c| *c == '=').count();
+        let is_heading = heading_level >= 2 && trimmed.ends_with('=');
+
+        match level {
+            None => {
+                if is_heading
+                    && trimmed
+                        .trim_matches('=')
+                        .trim()
+                        .eq_ignore_ascii_case(section)
+                {
+                    level = Some(heading_level);
+                }
+            }
+            Some(level) => {
+                if is_heading && heading_level <= level {
+                    break;
+                }
+                lines.push(line);
+            }
+        }
+    }
+    level.map(|_| lines.join("\n").trim().to_string())
+}
+
+/// Splits `Article title#Section`, or the url of an article with a fragment, into the
+/// title and the section. The `#` must be followed right away by the section name.
+fn split_section(input: &str) -> Option<(String, String)> {
+    if let Ok(url) = url::Url::parse(input) {
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+        let title = url.path().strip_prefix("/wiki/")?;
+        let section = url.fragment().filter(|section| !section.is_empty())?;
+        let decode = |text: &str| {
+            urlencoding::decode(text)
+                .map(|text| text.replace('_', " "))
+                .unwrap_or_else(|_| text.replace('_', " "))
+        };
+        return Some((decode(title), decode(section)));
+    }
+
+    input
+        .rmatch_indices('#')
+        .find(|(i, _)| {
+            let title = input[..*i].trim();
+            let next = input[i + 1..].chars().next();
+            !title.is_empty() && next.is_some_and(|c| c.is_alphanumeric())
+        })
+        .map(|(i, _)| {
+            (
+                input[..i].trim().to_string(),
+                input[i + 1..].trim().to_string(),
+            )
+        })
+}
+
+#[async_trait]
+impl Tool for Wikipedia {
+    fn name(&self) -> String {
+        String::from("Wikipedia")
+    }
+
+    fn description(&self) -> String {
+        String::from(
+            "A wrapper around Wikipedia. Useful for when you need to answer general questions about people, places, companies, facts, historical events, or other subjects.
+            Input should be a search query. To read a specific section of an article use the format: Article title#Section",
+        )
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let input = input.as_str().ok_or("Input should be a string")?.trim();
+
+        if let Some((title, section)) = split_section(input) {
+            return send(self.section(&title, &section)).await;
+        }
+
+        let titles = send(self.search(input)).await?;
+        let mut summaries = Vec::new();
+        for title in titles {
+            if let Some(extract) = send(self.extract(&title, !self.full_article)).await? {
+                summaries.push(format!(
+                    "Page: {}\nSummary: {}\nSource: {}",
+                    title,
+                    extract,
+                    self.page_url(&title)
+                ));
+            }
+        }
+
+        if summaries.is_empty() {
+            return Ok("No good Wikipedia search result was found".to_string());
+        }
+        Ok(self.truncate(summaries.join("\n\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_section() {
+        let article = "Intro\n\n== History ==\nOld times.\n=== Early ===\nVery old.\n== Geography ==\nMountains.";
+        assert_eq!(
+            find_section(article, "history").unwrap(),
+            "Old times.\n=== Early ===\nVery old."
+        );
+        assert_eq!(find_section(article, "Geography").unwrap(), "Mountains.");
+        assert!(find_section(article, "Economy").is_none());
+    }
+
+    #[test]
+    fn test_split_section() {
+        let split = |title: &str, section: &str| Some((title.to_string(), section.to_string()));
+        assert_eq!(split_section("Rust#History"), split("Rust", "History"));
+        assert_eq!(
+            split_section("C Sharp#Syntax and semantics"),
+            split("C Sharp", "Syntax and semantics")
+        );
+        assert_eq!(
+            split_section("https://en.wikipedia.org/wiki/C_Sharp_(programming_language)#Syntax"),
+            split("C Sharp (programming language)", "Syntax")
+        );
+        assert_eq!(
+            split_section("https://en.wikipedia.org/wiki/F_Sharp_(programming_language)"),
+            None
+        );
+        assert_eq!(split_section("C#"), None);
+        assert_eq!(split_section("F# programming language"), None);
+        assert_eq!(split_section("C# (programming language)"), None);
+        assert_eq!(split_section("#History"), None);
+    }
+
+    #[tokio::test]
+    async fn test_wikipedia_search() {
+        let mut server = mockito::Server::new_async().await;
+        let search = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("list".into(), "search".into()))
+            .with_body(
+                json!({"query": {"search": [{"title": "Rust (programming language)"}]}})
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let extract = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "prop".into(),
+                "extracts".into(),
+            ))
+            .with_body(
+                json!({"query": {"pages": {"1": {"extract": "Rust is a programming language."}}}})
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let wikipedia = Wikipedia::new().with_base_url(server.url());
+        let result = wikipedia.call("rust language").await.unwrap();
+
+        assert!(result.contains("Page: Rust (programming language)"));
+        assert!(result.contains("Summary: Rust is a programming language."));
+        assert!(result
+            .contains("Source: https://en.wikipedia.org/wiki/Rust_%28programming_language%29"));
+        search.assert_async().await;
+        extract.assert_async().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_wikipedia() {
+        let wikipedia = Wikipedia::default().with_top_k_results(1);
+        let result = wikipedia.call("Alan Turing").await.unwrap();
+        println!("{}", result);
+    }
+}
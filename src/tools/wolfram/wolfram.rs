@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use serde_json::Value;
 
-use crate::tools::Tool;
+use crate::{runtime::send, tools::Tool};
 use std::error::Error;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -77,9 +77,14 @@ impl From<Subpod> for String {
 }
 
 /// When being used within agents GPT4 is recommended
+///
+/// By default the full result (every pod returned by the Full Results API) is returned.
+/// With `with_short_answer(true)` the Short Answers API is used instead, which returns a
+/// single line such as `1.609 kilometers`, cheaper in tokens when only the value matters.
 pub struct Wolfram {
     app_id: String,
     exclude_pods: Vec<String>,
+    short_answer: bool,
     client: reqwest::Client,
 }
 
@@ -88,6 +93,7 @@ impl Wolfram {
         Self {
             app_id,
             exclude_pods: Vec::new(),
+            short_answer: false,
             client: reqwest::Client::new(),
         }
     }
@@ -101,6 +107,28 @@ impl Wolfram {
         self.app_id = app_id.as_ref().to_owned();
         self
     }
+
+    pub fn with_short_answer(mut self, short_answer: bool) -> Self {
+        self.short_answer = short_answer;
+        self
+    }
+
+    async fn short_answer(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        let url = format!(
+            "https://api.wolframalpha.com/v1/result?appid={}&i={}",
+            &self.app_id,
+            urlencoding::encode(input)
+        );
+
+        let response = self.client.get(&url).send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(format!("Wolfram Error {}: {}", status.as_u16(), body).into());
+        }
+
+        Ok(body)
+    }
 }
 
 impl Default for Wolfram {
@@ -108,6 +136,7 @@ impl Default for Wolfram {
         Wolfram {
             app_id: std::env::var("WOLFRAM_APP_ID").unwrap_or_default(),
             exclude_pods: Vec::new(),
+            short_answer: false,
             client: reqwest::Client::new(),
         }
     }
@@ -129,6 +158,10 @@ impl Tool for Wolfram {
     }
     async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
         let input = input.as_str().ok_or("Invalid input")?;
+        if self.short_answer {
+            return send(self.short_answer(input)).await;
+        }
+
         let mut url = format!(
             "https://api.wolframalpha.com/v2/query?appid={}&input={}&output=JSON&format=plaintext&podstate=Result__Step-by-step+solution",
             &self.app_id,
@@ -139,7 +172,8 @@ impl Tool for Wolfram {
             url += &format!("&excludepodid={}", self.exclude_pods.join(","));
         }
 
-        let response: WolframResponse = self.client.get(&url).send().await?.json().await?;
+        let response: WolframResponse =
+            send(async { self.client.get(&url).send().await?.json().await }).await?;
 
         if let WolframErrorStatus::Error(error) = response.queryresult.error {
             return Err(Box::new(std::io::Error::new(
@@ -180,4 +214,14 @@ mod tests {
         assert!(result.is_ok());
         println!("{}", result.unwrap());
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_wolfram_short_answer() {
+        let wolfram = Wolfram::default().with_short_answer(true);
+        let result = wolfram.call("How many kilometers is a mile").await;
+
+        assert!(result.is_ok());
+        println!("{}", result.unwrap());
+    }
 }
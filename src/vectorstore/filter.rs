@@ -0,0 +1,282 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A filter on the metadata of the documents, applied by the vector stores supporting
+/// it, see `VecStoreOptions::with_metadata_filter` and
+/// `VectorStore::supports_metadata_filter`.
+///
+/// The fields are top-level keys of the metadata, a condition on a missing field is false.
+/// The order comparisons are between numbers or between strings, false for the other
+/// values.
+///
+/// It is serialized as `{"gt": {"field": "year", "value": 2010}}`, or
+/// `{"and": [...]}` for the combinations, which is the format the `SelfQueryRetriever`
+/// asks the LLM for.
+///
+/// # Example
+/// ```rust,ignore
+/// let filter = MetadataFilter::and(vec![
+///     MetadataFilter::gt("year", 2010),
+///     MetadataFilter::gt("rating", 8.0),
+/// ]);
+/// let options = VecStoreOptions::new().with_metadata_filter(filter);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataFilter {
+    Eq {
+        field: String,
+        value: Value,
+    },
+    Ne {
+        field: String,
+        value: Value,
+    },
+    Gt {
+        field: String,
+        value: Value,
+    },
+    Gte {
+        field: String,
+        value: Value,
+    },
+    Lt {
+        field: String,
+        value: Value,
+    },
+    Lte {
+        field: String,
+        value: Value,
+    },
+    /// The field is equal to one of the values.
+    In {
+        field: String,
+        values: Vec<Value>,
+    },
+    And(Vec<MetadataFilter>),
+    Or(Vec<MetadataFilter>),
+}
+
+impl MetadataFilter {
+    pub fn eq<S: Into<String>, V: Into<Value>>(field: S, value: V) -> Self {
+        MetadataFilter::Eq {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn ne<S: Into<String>, V: Into<Value>>(field: S, value: V) -> Self {
+        MetadataFilter::Ne {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn gt<S: Into<String>, V: Into<Value>>(field: S, value: V) -> Self {
+        MetadataFilter::Gt {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn gte<S: Into<String>, V: Into<Value>>(field: S, value: V) -> Self {
+        MetadataFilter::Gte {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn lt<S: Into<String>, V: Into<Value>>(field: S, value: V) -> Self {
+        MetadataFilter::Lt {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn lte<S: Into<String>, V: Into<Value>>(field: S, value: V) -> Self {
+        MetadataFilter::Lte {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn is_in<S: Into<String>, V: Into<Value>>(field: S, values: Vec<V>) -> Self {
+        MetadataFilter::In {
+            field: field.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn and(filters: Vec<MetadataFilter>) -> Self {
+        MetadataFilter::And(filters)
+    }
+
+    pub fn or(filters: Vec<MetadataFilter>) -> Self {
+        MetadataFilter::Or(filters)
+    }
+
+    /// Whether the metadata of a document matches the filter.
+    pub fn matches(&self, metadata: &HashMap<String, Value>) -> bool {
+        let compare = |field: &String, value: &Value, accept: fn(Ordering) -> bool| {
+            metadata
+                .get(field)
+                .and_then(|field| compare_values(field, value))
+                .is_some_and(accept)
+        };
+        let equals = |field: &String, value: &Value| {
+            metadata.get(field).map(|field| values_equal(field, value))
+        };
+        match self {
+            MetadataFilter::Eq { field, value } => equals(field, value) == Some(true),
+            MetadataFilter::Ne { field, value } => equals(field, value) == Some(false),
+            MetadataFilter::Gt { field, value } => compare(field, value, Ordering::is_gt),
+            MetadataFilter::Gte { field, value } => compare(field, value, Ordering::is_ge),
+            MetadataFilter::Lt { field, value } => compare(field, value, Ordering::is_lt),
+            MetadataFilter::Lte { field, value } => compare(field, value, Ordering::is_le),
+            MetadataFilter::In { field, values } => values
+                .iter()
+                .any(|value| equals(field, value) == Some(true)),
+            MetadataFilter::And(filters) => filters.iter().all(|filter| filter.matches(metadata)),
+            MetadataFilter::Or(filters) => filters.iter().any(|filter| filter.matches(metadata)),
+        }
+    }
+
+    /// The filter as a SQL/JSON path predicate on the metadata, e.g.
+    /// `$ ? (@."year" > $v0)`, with the values as the variables of the path, to use with
+    /// `jsonb_path_exists(metadata, path, variables)`.
+    pub fn to_jsonpath(&self) -> (String, Value) {
+        let mut variables = Map::new();
+        let predicate = self.jsonpath_predicate(&mut variables);
+        (format!("$ ? ({})", predicate), Value::Object(variables))
+    }
+
+    fn jsonpath_predicate(&self, variables: &mut Map<String, Value>) -> String {
+        let mut comparison = |field: &str, operator: &str, value: &Value| {
+            let name = format!("v{}", variables.len());
+            variables.insert(name.clone(), value.clone());
+            format!("@.{} {} ${}", quote_jsonpath_key(field), operator, name)
+        };
+        match self {
+            MetadataFilter::Eq { field, value } => comparison(field, "==", value),
+            MetadataFilter::Ne { field, value } => comparison(field, "!=", value),
+            MetadataFilter::Gt { field, value } => comparison(field, ">", value),
+            MetadataFilter::Gte { field, value } => comparison(field, ">=", value),
+            MetadataFilter::Lt { field, value } => comparison(field, "<", value),
+            MetadataFilter::Lte { field, value } => comparison(field, "<=", value),
+            MetadataFilter::In { values, .. } if values.is_empty() => FALSE_PREDICATE.to_string(),
+            MetadataFilter::In { field, values } => {
+                let comparisons: Vec<String> = values
+                    .iter()
+                    .map(|value| comparison(field, "==", value))
+                    .collect();
+                format!("({})", comparisons.join(" || "))
+            }
+            MetadataFilter::And(filters) => {
+                combine_jsonpath(filters, " && ", TRUE_PREDICATE, variables)
+            }
+            MetadataFilter::Or(filters) => {
+                combine_jsonpath(filters, " || ", FALSE_PREDICATE, variables)
+            }
+        }
+    }
+}
+
+// The path predicates are comparisons, the filters of the paths don't accept booleans.
+const TRUE_PREDICATE: &str = "(1 == 1)";
+const FALSE_PREDICATE: &str = "(1 == 0)";
+
+fn combine_jsonpath(
+    filters: &[MetadataFilter],
+    operator: &str,
+    empty: &str,
+    variables: &mut Map<String, Value>,
+) -> String {
+    if filters.is_empty() {
+        return empty.to_string();
+    }
+    let predicates: Vec<String> = filters
+        .iter()
+        .map(|filter| filter.jsonpath_predicate(variables))
+        .collect();
+    format!("({})", predicates.join(operator))
+}
+
+fn quote_jsonpath_key(key: &str) -> String {
+    format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The order of two numbers or two strings, `None` for the other values.
+fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// The numbers are equal whatever their representation, e.g. 8 and 8.0.
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match compare_values(a, b) {
+        Some(ordering) => ordering.is_eq(),
+        None => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_metadata_filter() {
+        let filter: MetadataFilter = serde_json::from_value(json!({
+            "and": [
+                {"gt": {"field": "year", "value": 2010}},
+                {"or": [
+                    {"gte": {"field": "rating", "value": 8.5}},
+                    {"in": {"field": "genre", "values": ["sci-fi", "drama"]}}
+                ]}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            filter,
+            MetadataFilter::and(vec![
+                MetadataFilter::gt("year", 2010),
+                MetadataFilter::or(vec![
+                    MetadataFilter::gte("rating", 8.5),
+                    MetadataFilter::is_in("genre", vec!["sci-fi", "drama"]),
+                ]),
+            ])
+        );
+
+        let metadata = |year: i64, rating: f64, genre: &str| {
+            HashMap::from([
+                ("year".to_string(), json!(year)),
+                ("rating".to_string(), json!(rating)),
+                ("genre".to_string(), json!(genre)),
+            ])
+        };
+        assert!(filter.matches(&metadata(2014, 8.6, "comedy")));
+        assert!(filter.matches(&metadata(2014, 7.0, "drama")));
+        assert!(!filter.matches(&metadata(2014, 7.0, "comedy")));
+        assert!(!filter.matches(&metadata(2010, 9.0, "drama")));
+        assert!(!MetadataFilter::ne("year", 2010).matches(&HashMap::new()));
+        assert!(MetadataFilter::ne("genre", "drama").matches(&metadata(2000, 8.0, "comedy")));
+        assert!(MetadataFilter::eq("rating", 8).matches(&metadata(2000, 8.0, "drama")));
+        assert!(!MetadataFilter::gt("genre", 1).matches(&metadata(2000, 8.0, "drama")));
+
+        let (path, variables) = filter.to_jsonpath();
+        assert_eq!(
+            path,
+            r#"$ ? ((@."year" > $v0 && (@."rating" >= $v1 || (@."genre" == $v2 || @."genre" == $v3))))"#
+        );
+        assert_eq!(
+            variables,
+            json!({"v0": 2010, "v1": 8.5, "v2": "sci-fi", "v3": "drama"})
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use std::{error::Error, sync::Arc};
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    embedding::embedder_trait::Embedder, schemas::Document,
+    semantic_router::utils::cosine_similarity,
+};
+
+use super::{maximal_marginal_relevance, VecStoreOptions, VectorStore};
+
+struct StoredDocument {
+    id: String,
+    name_space: Option<String>,
+    document: Document,
+    embedding: Vec<f64>,
+}
+
+/// A vector store keeping the documents and their embeddings in memory, ranked by cosine
+/// similarity. Meant for tests and prototypes: nothing is persisted and the search
+/// compares the query with every document.
+///
+/// The `name_space` of the options partitions the documents, the `filters` are a json
+/// object of metadata the documents must have, e.g. `json!({"genre": "Sci-Fi"})`, and the
+/// `metadata_filter` is supported.
+///
+/// # Example
+/// ```rust,ignore
+/// let store = InMemoryVectorStore::new(OpenAiEmbedder::default());
+/// let ids = add_documents!(store, &documents).await?;
+/// let results = store.similarity_search_with_score("space travel", 4, &VecStoreOptions::default()).await?;
+/// store.delete(&ids, &VecStoreOptions::default()).await?;
+/// ```
+pub struct InMemoryVectorStore {
+    embedder: Arc<dyn Embedder>,
+    documents: RwLock<Vec<StoredDocument>>,
+}
+
+impl InMemoryVectorStore {
+    pub fn new<E: Embedder + 'static>(embedder: E) -> Self {
+        Self {
+            embedder: Arc::new(embedder),
+            documents: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Number of documents in the store.
+    pub async fn len(&self) -> usize {
+        self.documents.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.documents.read().await.is_empty()
+    }
+
+    fn embedder<'a>(&'a self, opt: &'a VecStoreOptions) -> &'a Arc<dyn Embedder> {
+        opt.embedder.as_ref().unwrap_or(&self.embedder)
+    }
+
+    /// The `limit` documents most similar to the query embedding, with their embeddings.
+    async fn search_by_embedding(
+        &self,
+        query: &[f64],
+        limit: usize,
+        opt: &VecStoreOptions,
+    ) -> Vec<(Document, Vec<f64>)> {
+        let documents = self.documents.read().await;
+        let mut results: Vec<(Document, Vec<f64>)> = documents
+            .iter()
+            .filter(|stored| stored.name_space == opt.name_space)
+            .filter(|stored| matches_filters(&stored.document, opt.filters.as_ref()))
+            .filter(|stored| {
+                opt.metadata_filter
+                    .as_ref()
+                    .is_none_or(|filter| filter.matches(&stored.document.metadata))
+            })
+            .map(|stored| {
+                let score = cosine_similarity(query, &stored.embedding);
+                (
+                    stored.document.clone().with_score(score),
+                    stored.embedding.clone(),
+                )
+            })
+            .filter(|(document, _)| {
+                opt.score_threshold
+                    .is_none_or(|threshold| document.score >= threshold as f64)
+            })
+            .collect();
+        results.sort_by(|a, b| b.0.score.total_cmp(&a.0.score));
+        results.truncate(limit);
+        results
+    }
+}
+
+fn matches_filters(document: &Document, filters: Option<&Value>) -> bool {
+    match filters.and_then(Value::as_object) {
+        Some(filters) => filters
+            .iter()
+            .all(|(key, value)| document.metadata.get(key) == Some(value)),
+        None => true,
+    }
+}
+
+#[async_trait]
+impl VectorStore for InMemoryVectorStore {
+    async fn add_documents(
+        &self,
+        docs: &[Document],
+        opt: &VecStoreOptions,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let texts: Vec<String> = docs.iter().map(|d| d.page_content.clone()).collect();
+        let embeddings = self.embedder(opt).embed_documents(&texts).await?;
+        if embeddings.len() != docs.len() {
+            return Err("Number of embeddings and documents do not match".into());
+        }
+
+        let mut documents = self.documents.write().await;
+        let mut ids = Vec::with_capacity(docs.len());
+        for (document, embedding) in docs.iter().zip(embeddings) {
+            let id = Uuid::new_v4().to_string();
+            documents.push(StoredDocument {
+                id: id.clone(),
+                name_space: opt.name_space.clone(),
+                document: document.clone(),
+                embedding,
+            });
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    async fn similarity_search(
+        &self,
+        query: &str,
+        limit: usize,
+        opt: &VecStoreOptions,
+    ) -> Result<Vec<Document>, Box<dyn Error>> {
+        let query = self.embedder(opt).embed_query(query).await?;
+        Ok(self
+            .search_by_embedding(&query, limit, opt)
+            .await
+            .into_iter()
+            .map(|(document, _)| document)
+            .collect())
+    }
+
+    /// Uses the stored embeddings instead of embedding the documents again.
+    async fn max_marginal_relevance_search(
+        &self,
+        query: &str,
+        k: usize,
+        fetch_k: usize,
+        lambda: f64,
+        opt: &VecStoreOptions,
+    ) -> Result<Vec<Document>, Box<dyn Error>> {
+        let query = self.embedder(opt).embed_query(query).await?;
+        let (documents, embeddings): (Vec<_>, Vec<_>) = self
+            .search_by_embedding(&query, fetch_k, opt)
+            .await
+            .into_iter()
+            .unzip();
+        let mut documents: Vec<Option<Document>> = documents.into_iter().map(Some).collect();
+        Ok(maximal_marginal_relevance(&query, &embeddings, k, lambda)
+            .into_iter()
+            .filter_map(|index| documents[index].take())
+            .collect())
+    }
+
+    fn supports_metadata_filter(&self) -> bool {
+        true
+    }
+
+    async fn delete(&self, ids: &[String], _opt: &VecStoreOptions) -> Result<(), Box<dyn Error>> {
+        self.documents
+            .write()
+            .await
+            .retain(|stored| !ids.contains(&stored.id));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::embedding::FakeEmbedder;
+
+    fn words_embedder() -> FakeEmbedder {
+        FakeEmbedder::default().with_topics(vec![vec!["space"], vec!["ocean"], vec!["forest"]])
+    }
+
+    fn document(text: &str, genre: &str) -> Document {
+        Document::new(text).with_metadata(HashMap::from([("genre".to_string(), json!(genre))]))
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_vector_store() {
+        let store = InMemoryVectorStore::new(words_embedder());
+        let options = VecStoreOptions::default();
+        let ids = store
+            .add_documents(
+                &[
+                    document("space travel in space", "sci-fi"),
+                    document("the ocean and space", "sci-fi"),
+                    document("a walk in the forest", "nature"),
+                ],
+                &options,
+            )
+            .await
+            .unwrap();
+        store
+            .add_documents(
+                &[document("space", "sci-fi")],
+                &VecStoreOptions::new().with_name_space("other"),
+            )
+            .await
+            .unwrap();
+
+        let results = store
+            .similarity_search_with_score("space", 2, &options)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0.page_content, "space travel in space");
+        assert!((results[0].1 - 1.0).abs() < 1e-9);
+        assert!(results[1].1 < results[0].1);
+
+        let results = store
+            .similarity_search(
+                "forest",
+                5,
+                &VecStoreOptions::new()
+                    .with_filters(json!({"genre": "sci-fi"}))
+                    .with_score_threshold(0.1),
+            )
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+
+        store.delete(&ids[..1], &options).await.unwrap();
+        let results = store.similarity_search("space", 1, &options).await.unwrap();
+        assert_eq!(results[0].page_content, "the ocean and space");
+        assert_eq!(store.len().await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_max_marginal_relevance_search() {
+        let store = InMemoryVectorStore::new(words_embedder());
+        let options = VecStoreOptions::default();
+        store
+            .add_documents(
+                &[
+                    document("space travel in space", "sci-fi"),
+                    document("space stations in space", "sci-fi"),
+                    document("the ocean and space", "sci-fi"),
+                ],
+                &options,
+            )
+            .await
+            .unwrap();
+
+        let results = store.similarity_search("space", 2, &options).await.unwrap();
+        assert!(results[1].page_content.starts_with("space"));
+
+        // The near-duplicate is replaced by a more diverse document.
+        for options in [
+            options,
+            VecStoreOptions::new().with_embedder(words_embedder()),
+        ] {
+            let results = store
+                .max_marginal_relevance_search("space", 2, 3, 0.3, &options)
+                .await
+                .unwrap();
+            let contents: Vec<&str> = results.iter().map(|d| d.page_content.as_str()).collect();
+            assert_eq!(contents[1], "the ocean and space");
+        }
+    }
+}
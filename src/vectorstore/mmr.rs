@@ -0,0 +1,72 @@
+use crate::semantic_router::utils::cosine_similarity;
+
+fn similarity(a: &[f64], b: &[f64]) -> f64 {
+    let similarity = cosine_similarity(a, b);
+    // The similarity with a zero vector is undefined.
+    if similarity.is_nan() {
+        0.0
+    } else {
+        similarity
+    }
+}
+
+/// Picks `k` of the `embeddings` by maximal marginal relevance: each pick maximizes
+/// `lambda * similarity(query) - (1 - lambda) * max(similarity(picked))`, so a `lambda` of
+/// 1 ranks by similarity only and a `lambda` of 0 by diversity only. Returns the indices of
+/// the picks, in order.
+pub fn maximal_marginal_relevance(
+    query: &[f64],
+    embeddings: &[Vec<f64>],
+    k: usize,
+    lambda: f64,
+) -> Vec<usize> {
+    let query_similarities: Vec<f64> = embeddings
+        .iter()
+        .map(|embedding| similarity(query, embedding))
+        .collect();
+    let mut selected: Vec<usize> = Vec::with_capacity(k.min(embeddings.len()));
+    while selected.len() < k.min(embeddings.len()) {
+        let best = (0..embeddings.len())
+            .filter(|index| !selected.contains(index))
+            .map(|index| {
+                let redundancy = selected
+                    .iter()
+                    .map(|&picked| similarity(&embeddings[index], &embeddings[picked]))
+                    .reduce(f64::max)
+                    .unwrap_or(0.0);
+                let score = lambda * query_similarities[index] - (1.0 - lambda) * redundancy;
+                (index, score)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)));
+        match best {
+            Some((index, _)) => selected.push(index),
+            None => break,
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maximal_marginal_relevance() {
+        let query = vec![1.0, 0.0];
+        let embeddings = vec![vec![1.0, 0.0], vec![1.0, 0.01], vec![0.7, 0.7]];
+
+        assert_eq!(
+            maximal_marginal_relevance(&query, &embeddings, 2, 1.0),
+            vec![0, 1]
+        );
+        // The near-duplicate of the first pick is skipped.
+        assert_eq!(
+            maximal_marginal_relevance(&query, &embeddings, 2, 0.3),
+            vec![0, 2]
+        );
+        assert_eq!(
+            maximal_marginal_relevance(&query, &embeddings, 5, 0.5).len(),
+            3
+        );
+    }
+}
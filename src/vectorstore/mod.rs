@@ -1,3 +1,5 @@
+mod filter;
+mod mmr;
 mod options;
 
 #[cfg(feature = "postgres")]
@@ -17,5 +19,10 @@ pub mod qdrant;
 
 mod vectorstore;
 
+mod in_memory;
+
+pub use filter::*;
+pub use in_memory::*;
+pub use mmr::*;
 pub use options::*;
 pub use vectorstore::*;
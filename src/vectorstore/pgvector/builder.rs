@@ -6,7 +6,8 @@ use sqlx::{postgres::PgPoolOptions, Pool, Postgres, Row, Transaction};
 use crate::{embedding::embedder_trait::Embedder, vectorstore::VecStoreOptions};
 
 use super::{
-    HNSWIndex, Store, PG_LOCKID_EXTENSION, PG_LOCK_ID_COLLECTION_TABLE, PG_LOCK_ID_EMBEDDING_TABLE,
+    HNSWIndex, IVFFlatIndex, Store, PG_LOCKID_EXTENSION, PG_LOCK_ID_COLLECTION_TABLE,
+    PG_LOCK_ID_EMBEDDING_TABLE,
 };
 
 const DEFAULT_COLLECTION_NAME: &str = "langchain";
@@ -27,6 +28,7 @@ pub struct StoreBuilder {
     collection_metadata: HashMap<String, Value>,
     vstore_options: VecStoreOptions,
     hns_index: Option<HNSWIndex>,
+    ivfflat_index: Option<IVFFlatIndex>,
 }
 
 impl StoreBuilder {
@@ -45,6 +47,7 @@ impl StoreBuilder {
             collection_metadata: HashMap::new(),
             vstore_options: VecStoreOptions::default(),
             hns_index: None,
+            ivfflat_index: None,
         }
     }
 
@@ -98,8 +101,17 @@ impl StoreBuilder {
         self
     }
 
+    /// Creates an HNSW index on the embeddings, replacing a previously set IVFFlat index.
     pub fn hns_index(mut self, hns_index: HNSWIndex) -> Self {
         self.hns_index = Some(hns_index);
+        self.ivfflat_index = None;
+        self
+    }
+
+    /// Creates an IVFFlat index on the embeddings, replacing a previously set HNSW index.
+    pub fn ivfflat_index(mut self, ivfflat_index: IVFFlatIndex) -> Self {
+        self.ivfflat_index = Some(ivfflat_index);
+        self.hns_index = None;
         self
     }
 
@@ -134,6 +146,7 @@ impl StoreBuilder {
             vector_dimensions: self.vector_dimensions,
             vstore_options: self.vstore_options,
             hns_index: self.hns_index,
+            ivfflat_index: self.ivfflat_index,
         })
     }
 
@@ -252,7 +265,7 @@ impl StoreBuilder {
         let create_table_sql = format!(
             r#"CREATE TABLE IF NOT EXISTS {} (
         name VARCHAR,
-        cmetadata JSON,
+        cmetadata JSONB,
         "uuid" TEXT NOT NULL,
         UNIQUE (name),
         PRIMARY KEY (uuid)
@@ -283,7 +296,7 @@ impl StoreBuilder {
              (collection_id TEXT,
              embedding VECTOR{},
              document VARCHAR,
-             cmetadata JSON,
+             cmetadata JSONB,
              "uuid" TEXT NOT NULL,
              CONSTRAINT langchain_pg_embedding_collection_id_fkey
              FOREIGN KEY (collection_id) REFERENCES {}("uuid") ON DELETE CASCADE,
@@ -299,24 +312,59 @@ impl StoreBuilder {
         );
         sqlx::query(&sql).execute(&mut **tx).await?;
 
-        // See this for more details on HNWS indexes: https://github.com/pgvector/pgvector#hnsw
-        match &self.hns_index {
-            Some(hns_index) => {
-                let mut sql = format!(
-                    r#"CREATE INDEX IF NOT EXISTS {}_embedding_hnsw ON {} USING hnsw (embedding {})"#,
-                    self.embedder_table_name, self.embedder_table_name, hns_index.distance_function
+        if let Some(sql) = self.embedding_index_sql() {
+            sqlx::query(&sql).execute(&mut **tx).await?;
+        }
+
+        Ok(())
+    }
+
+    // See this for more details on the indexes: https://github.com/pgvector/pgvector#indexing
+    fn embedding_index_sql(&self) -> Option<String> {
+        if let Some(hns_index) = &self.hns_index {
+            let mut sql = format!(
+                r#"CREATE INDEX IF NOT EXISTS {}_embedding_hnsw ON {} USING hnsw (embedding {})"#,
+                self.embedder_table_name, self.embedder_table_name, hns_index.distance_function
+            );
+            if hns_index.m > 0 && hns_index.ef_construction > 0 {
+                sql = format!(
+                    "{} WITH (m={}, ef_construction = {})",
+                    sql, hns_index.m, hns_index.ef_construction
                 );
-                if hns_index.m > 0 && hns_index.ef_construction > 0 {
-                    sql = format!(
-                        "{} WITH (m={}, ef_construction = {})",
-                        sql, hns_index.m, hns_index.ef_construction
-                    );
-                }
-                sqlx::query(&sql).execute(&mut **tx).await?;
             }
-            None => {}
+            return Some(sql);
         }
+        self.ivfflat_index.as_ref().map(|ivfflat_index| {
+            let mut sql = format!(
+                r#"CREATE INDEX IF NOT EXISTS {}_embedding_ivfflat ON {} USING ivfflat (embedding {})"#,
+                self.embedder_table_name, self.embedder_table_name, ivfflat_index.distance_function
+            );
+            if ivfflat_index.lists > 0 {
+                sql = format!("{} WITH (lists = {})", sql, ivfflat_index.lists);
+            }
+            sql
+        })
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedding_index_sql() {
+        assert_eq!(StoreBuilder::new().embedding_index_sql(), None);
+
+        let builder = StoreBuilder::new().hns_index(HNSWIndex::new(16, 64, "vector_cosine_ops"));
+        assert_eq!(
+            builder.embedding_index_sql().unwrap(),
+            "CREATE INDEX IF NOT EXISTS langchain_pg_embedding_embedding_hnsw ON langchain_pg_embedding USING hnsw (embedding vector_cosine_ops) WITH (m=16, ef_construction = 64)"
+        );
+
+        let builder = builder.ivfflat_index(IVFFlatIndex::new(100, "vector_l2_ops"));
+        assert_eq!(
+            builder.embedding_index_sql().unwrap(),
+            "CREATE INDEX IF NOT EXISTS langchain_pg_embedding_embedding_ivfflat ON langchain_pg_embedding USING ivfflat (embedding vector_l2_ops) WITH (lists = 100)"
+        );
     }
 }
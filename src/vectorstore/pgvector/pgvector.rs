@@ -23,6 +23,7 @@ pub struct Store {
     pub(crate) pre_delete_collection: bool,
     pub(crate) vector_dimensions: i32,
     pub(crate) hns_index: Option<HNSWIndex>,
+    pub(crate) ivfflat_index: Option<IVFFlatIndex>,
     pub(crate) vstore_options: VecStoreOptions,
 }
 
@@ -42,6 +43,24 @@ impl HNSWIndex {
     }
 }
 
+/// An IVFFlat index on the embeddings, faster to build and smaller than an HNSW index
+/// but with a lower recall. Build it once the table holds data, pgvector recommends
+/// `rows / 1000` lists up to 1M rows.
+/// See https://github.com/pgvector/pgvector#ivfflat
+pub struct IVFFlatIndex {
+    pub(crate) lists: i32,
+    pub(crate) distance_function: String,
+}
+
+impl IVFFlatIndex {
+    pub fn new(lists: i32, distance_function: &str) -> Self {
+        IVFFlatIndex {
+            lists,
+            distance_function: distance_function.into(),
+        }
+    }
+}
+
 impl Store {
     // getFilters return metadata filters, now only support map[key]value pattern
     // TODO: should support more types like {"key1": {"key2":"values2"}} or {"key": ["value1", "values2"]}.
@@ -166,14 +185,23 @@ impl VectorStore for Store {
     ) -> Result<Vec<Document>, Box<dyn Error>> {
         let collection_name = self.get_name_space(opt);
         let filter = self.get_filters(opt)?;
-        let mut where_querys = filter
-            .iter()
-            .map(|(k, v)| format!("(data.cmetadata ->> '{}') = '{}'", k, v))
-            .collect::<Vec<String>>()
-            .join(" AND ");
-
-        if where_querys.is_empty() {
-            where_querys = "TRUE".to_string();
+        let score_threshold = self.get_score_threshold(opt)?;
+
+        // The metadata is matched with the JSONB containment operator, which also
+        // matches nested objects and arrays and can use a GIN index on cmetadata.
+        let mut where_querys = vec!["data.cmetadata::jsonb @> $5::jsonb".to_string()];
+        // The metadata filter is a SQL/JSON path, with its values bound as its variables.
+        let metadata_filter = opt
+            .metadata_filter
+            .as_ref()
+            .map(|filter| filter.to_jsonpath());
+        if metadata_filter.is_some() {
+            where_querys.push(
+                "jsonb_path_exists(data.cmetadata::jsonb, $6::jsonpath, $7::jsonb)".to_string(),
+            );
+        }
+        if score_threshold > 0.0 {
+            where_querys.push(format!("data.distance <= {}", 1.0 - score_threshold));
         }
 
         let sql = format!(
@@ -196,7 +224,7 @@ impl VectorStore for Store {
                 FROM
                     filtered_embedding_dims
                     JOIN {} ON filtered_embedding_dims.collection_id = {}.uuid
-                WHERE {}.name = '{}'
+                WHERE {}.name = $4
             ) AS data
             WHERE {}
             ORDER BY
@@ -206,25 +234,29 @@ impl VectorStore for Store {
             self.collection_table_name,
             self.collection_table_name,
             self.collection_table_name,
-            collection_name,
-            where_querys,
+            where_querys.join(" AND "),
         );
 
-        let query_vector = self.embedder.embed_query(query).await?;
+        let embedder = opt.embedder.as_ref().unwrap_or(&self.embedder);
+        let query_vector = embedder.embed_query(query).await?;
 
         let vector_dims = query_vector.len();
 
-        let rows = sqlx::query(&sql)
+        let mut query = sqlx::query(&sql)
             .bind(vector_dims as i64)
-            .bind(&Vector::from(
+            .bind(Vector::from(
                 query_vector
                     .into_iter()
                     .map(|x| x as f32)
                     .collect::<Vec<f32>>(),
             ))
             .bind(limit as i32)
-            .fetch_all(&self.pool)
-            .await?;
+            .bind(&collection_name)
+            .bind(json!(filter));
+        if let Some((path, variables)) = metadata_filter {
+            query = query.bind(path).bind(variables);
+        }
+        let rows = query.fetch_all(&self.pool).await?;
 
         let docs = rows
             .into_iter()
@@ -249,4 +281,23 @@ impl VectorStore for Store {
 
         Ok(docs)
     }
+
+    fn supports_metadata_filter(&self) -> bool {
+        true
+    }
+
+    async fn delete(&self, ids: &[String], _opt: &VecStoreOptions) -> Result<(), Box<dyn Error>> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(&format!(
+            r#"DELETE FROM {} WHERE collection_id = $1 AND "uuid" = ANY($2)"#,
+            self.embedder_table_name
+        ))
+        .bind(&self.collection_uuid)
+        .bind(ids)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
 }
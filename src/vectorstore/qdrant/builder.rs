@@ -1,19 +1,22 @@
 use crate::embedding::Embedder;
 use crate::vectorstore::qdrant::Store;
-use qdrant_client::client::QdrantClient;
-use qdrant_client::qdrant::vectors_config::Config;
-use qdrant_client::qdrant::{CreateCollection, Distance, Filter, VectorParams, VectorsConfig};
+use qdrant_client::qdrant::{CreateCollectionBuilder, Distance, Filter, VectorParamsBuilder};
+use qdrant_client::Qdrant;
 use std::error::Error;
 use std::sync::Arc;
 
+const DEFAULT_BATCH_SIZE: usize = 64;
+
 pub struct StoreBuilder {
-    client: Option<QdrantClient>,
+    client: Option<Qdrant>,
     embedder: Option<Arc<dyn Embedder>>,
     collection_name: Option<String>,
     content_field: String,
     metadata_field: String,
     recreate_collection: bool,
     search_filter: Option<Filter>,
+    distance: Distance,
+    batch_size: usize,
 }
 
 impl Default for StoreBuilder {
@@ -33,11 +36,13 @@ impl StoreBuilder {
             content_field: "page_content".to_string(),
             metadata_field: "metadata".to_string(),
             recreate_collection: false,
+            distance: Distance::Cosine,
+            batch_size: DEFAULT_BATCH_SIZE,
         }
     }
 
-    /// An instance of `qdrant_client::QdrantClient` for the Store. REQUIRED.
-    pub fn client(mut self, client: QdrantClient) -> Self {
+    /// An instance of `qdrant_client::Qdrant` for the Store. REQUIRED.
+    pub fn client(mut self, client: Qdrant) -> Self {
         self.client = Some(client);
         self
     }
@@ -53,7 +58,7 @@ impl StoreBuilder {
     /// https://qdrant.tech/documentation/concepts/collections/#create-a-collection
     ///
     /// If the collection doesn't exist, it will be created with the embedding provider's dimension
-    /// and Cosine similarity metric.
+    /// and the `distance` metric.
     pub fn collection_name(mut self, collection_name: &str) -> Self {
         self.collection_name = Some(collection_name.to_string());
         self
@@ -74,12 +79,27 @@ impl StoreBuilder {
     }
 
     /// If set to true, the collection will be deleted and recreated using
-    /// the embedding provider's dimension and Cosine similarity metric.
+    /// the embedding provider's dimension and the `distance` metric.
     pub fn recreate_collection(mut self, recreate_collection: bool) -> Self {
         self.recreate_collection = recreate_collection;
         self
     }
 
+    /// Distance metric of the collection created by the Store.
+    /// It is ignored when the collection already exists.
+    /// Default: `Distance::Cosine`
+    pub fn distance(mut self, distance: Distance) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Maximum number of points sent to Qdrant in one upsert request.
+    /// Default: 64
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
     /// Filter to be applied to the search results.
     /// https://qdrant.tech/documentation/concepts/filtering/
     /// Instance of use `qdrant_client::qdrant::Filter`
@@ -113,17 +133,11 @@ impl StoreBuilder {
             let embeddings_dimension = embeddings.len() as u64;
 
             client
-                .create_collection(&CreateCollection {
-                    collection_name: collection_name.clone(),
-                    vectors_config: Some(VectorsConfig {
-                        config: Some(Config::Params(VectorParams {
-                            size: embeddings_dimension,
-                            distance: Distance::Cosine.into(),
-                            ..Default::default()
-                        })),
-                    }),
-                    ..Default::default()
-                })
+                .create_collection(
+                    CreateCollectionBuilder::new(&collection_name).vectors_config(
+                        VectorParamsBuilder::new(embeddings_dimension, self.distance),
+                    ),
+                )
                 .await?;
         }
 
@@ -134,6 +148,7 @@ impl StoreBuilder {
             search_filter: self.search_filter,
             content_field: self.content_field,
             metadata_field: self.metadata_field,
+            batch_size: self.batch_size,
         })
     }
 }
@@ -1,25 +1,158 @@
 use async_trait::async_trait;
-use qdrant_client::qdrant::{Filter, PointStruct, SearchPoints};
-use serde_json::json;
+use qdrant_client::qdrant::{
+    Condition, DeletePointsBuilder, Filter, PointStruct, PointsIdsList, Range, SearchPointsBuilder,
+    UpsertPointsBuilder,
+};
+use serde_json::{json, Value};
 use std::error::Error;
 use std::sync::Arc;
 
-pub use qdrant_client::client::QdrantClient;
+use qdrant_client::Payload;
+pub use qdrant_client::Qdrant;
 
 use crate::{
     embedding::embedder_trait::Embedder,
     schemas::Document,
-    vectorstore::{VecStoreOptions, VectorStore},
+    vectorstore::{MetadataFilter, VecStoreOptions, VectorStore},
 };
 use uuid::Uuid;
 
 pub struct Store {
-    pub client: QdrantClient,
+    pub client: Qdrant,
     pub embedder: Arc<dyn Embedder>,
     pub collection_name: String,
     pub content_field: String,
     pub metadata_field: String,
     pub search_filter: Option<Filter>,
+    pub batch_size: usize,
+}
+
+impl Store {
+    /// Combines the `search_filter` of the Store with the `filters` and the
+    /// `metadata_filter` of the options.
+    ///
+    /// The filters are a json object of metadata values, e.g. `{"source": "wiki", "page": 3}`,
+    /// and match the documents whose metadata has all of them. An array value matches any
+    /// of its values.
+    fn get_filter(&self, opt: &VecStoreOptions) -> Result<Option<Filter>, Box<dyn Error>> {
+        let mut filter = self.search_filter.clone().unwrap_or_default();
+        match &opt.filters {
+            Some(Value::Object(map)) => {
+                for (key, value) in map {
+                    let field = format!("{}.{}", self.metadata_field, key);
+                    filter.must.push(match_condition(field, value)?);
+                }
+            }
+            Some(_) => return Err("Invalid filters format, expected a json object".into()),
+            None => {}
+        }
+        if let Some(metadata_filter) = &opt.metadata_filter {
+            filter.must.push(self.metadata_condition(metadata_filter)?);
+        }
+
+        if filter == Filter::default() {
+            Ok(None)
+        } else {
+            Ok(Some(filter))
+        }
+    }
+}
+
+impl Store {
+    /// The metadata filter as a condition on the payload. The equalities are matches of
+    /// strings, booleans or integers, the order comparisons are ranges of numbers.
+    fn metadata_condition(&self, filter: &MetadataFilter) -> Result<Condition, Box<dyn Error>> {
+        let field = |field: &str| format!("{}.{}", self.metadata_field, field);
+        let condition = match filter {
+            MetadataFilter::Eq { field: key, value } => match_condition(field(key), value)?,
+            MetadataFilter::Ne { field: key, value } => {
+                Filter::must_not([match_condition(field(key), value)?]).into()
+            }
+            MetadataFilter::Gt { field: key, value } => {
+                range_condition(field(key), value, |x| Range {
+                    gt: Some(x),
+                    ..Default::default()
+                })?
+            }
+            MetadataFilter::Gte { field: key, value } => {
+                range_condition(field(key), value, |x| Range {
+                    gte: Some(x),
+                    ..Default::default()
+                })?
+            }
+            MetadataFilter::Lt { field: key, value } => {
+                range_condition(field(key), value, |x| Range {
+                    lt: Some(x),
+                    ..Default::default()
+                })?
+            }
+            MetadataFilter::Lte { field: key, value } => {
+                range_condition(field(key), value, |x| Range {
+                    lte: Some(x),
+                    ..Default::default()
+                })?
+            }
+            MetadataFilter::In { field: key, values } => {
+                match_condition(field(key), &Value::Array(values.clone()))?
+            }
+            MetadataFilter::And(filters) => Filter::must(
+                filters
+                    .iter()
+                    .map(|filter| self.metadata_condition(filter))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )
+            .into(),
+            MetadataFilter::Or(filters) => Filter::should(
+                filters
+                    .iter()
+                    .map(|filter| self.metadata_condition(filter))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )
+            .into(),
+        };
+        Ok(condition)
+    }
+}
+
+fn range_condition(
+    field: String,
+    value: &Value,
+    range: fn(f64) -> Range,
+) -> Result<Condition, Box<dyn Error>> {
+    let value = value
+        .as_f64()
+        .ok_or_else(|| format!("Unsupported range value for {}: {}", field, value))?;
+    Ok(Condition::range(field, range(value)))
+}
+
+fn match_condition(field: String, value: &Value) -> Result<Condition, Box<dyn Error>> {
+    let condition = match value {
+        Value::String(s) => Condition::matches(field, s.clone()),
+        Value::Bool(b) => Condition::matches(field, *b),
+        Value::Number(n) if n.is_i64() => Condition::matches(field, n.as_i64().unwrap()),
+        Value::Array(values) if values.iter().all(Value::is_string) => Condition::matches(
+            field,
+            values
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect::<Vec<String>>(),
+        ),
+        Value::Array(values) if values.iter().all(Value::is_i64) => Condition::matches(
+            field,
+            values
+                .iter()
+                .filter_map(Value::as_i64)
+                .collect::<Vec<i64>>(),
+        ),
+        _ => {
+            return Err(format!(
+                "Unsupported filter value for {}: {}, use `search_filter` instead",
+                field, value
+            )
+            .into())
+        }
+    };
+    Ok(condition)
 }
 
 #[async_trait]
@@ -34,7 +167,7 @@ impl VectorStore for Store {
         let embedder = opt.embedder.as_ref().unwrap_or(&self.embedder);
         let texts: Vec<String> = docs.iter().map(|d| d.page_content.clone()).collect();
 
-        let ids = docs.iter().map(|_| Uuid::new_v4().to_string());
+        let ids: Vec<String> = docs.iter().map(|_| Uuid::new_v4().to_string()).collect();
         let vectors = embedder.embed_documents(&texts).await?.into_iter();
         let payloads = docs.iter().map(|d| {
             json!({
@@ -45,17 +178,20 @@ impl VectorStore for Store {
 
         let mut points: Vec<PointStruct> = Vec::with_capacity(docs.len());
 
-        for (id, (vector, payload)) in ids.clone().zip(vectors.zip(payloads)) {
+        for (id, (vector, payload)) in ids.iter().zip(vectors.zip(payloads)) {
             let vector: Vec<f32> = vector.into_iter().map(|f| f as f32).collect();
-            let point = PointStruct::new(id, vector, payload.try_into().unwrap());
+            let point = PointStruct::new(id.clone(), vector, Payload::try_from(payload)?);
             points.push(point);
         }
 
         self.client
-            .upsert_points_blocking(self.collection_name.clone(), None, points, None)
+            .upsert_points_chunked(
+                UpsertPointsBuilder::new(&self.collection_name, points).wait(true),
+                self.batch_size,
+            )
             .await?;
 
-        Ok(ids.collect())
+        Ok(ids)
     }
 
     /// Perform a similarity search on the store.
@@ -70,14 +206,6 @@ impl VectorStore for Store {
             return Err("Qdrant doesn't support namespaces".into());
         }
 
-        if opt.filters.is_some() {
-            return Err(
-                "'qdrant_client' doesn't support 'serde_json::Value' filters. 
-            Use `search_filter` when constructing VectorStore instead"
-                    .into(),
-            );
-        }
-
         let embedder = opt.embedder.as_ref().unwrap_or(&self.embedder);
         let query_vector: Vec<f32> = embedder
             .embed_query(query)
@@ -86,29 +214,35 @@ impl VectorStore for Store {
             .map(|f| f as f32)
             .collect();
 
-        let results = self
-            .client
-            .search_points(&SearchPoints {
-                collection_name: self.collection_name.clone(),
-                vector: query_vector,
-                limit: limit as u64,
-                with_payload: Some(true.into()),
-                score_threshold: opt.score_threshold,
-                filter: self.search_filter.clone(),
-                ..Default::default()
-            })
-            .await?;
+        let mut search =
+            SearchPointsBuilder::new(&self.collection_name, query_vector, limit as u64)
+                .with_payload(true);
+        if let Some(filter) = self.get_filter(opt)? {
+            search = search.filter(filter);
+        }
+        if let Some(score_threshold) = opt.score_threshold {
+            search = search.score_threshold(score_threshold);
+        }
+
+        let results = self.client.search_points(search).await?;
 
         let documents = results
             .result
             .into_iter()
             .map(|scored_point| {
-                let payload = scored_point.payload;
+                let mut payload = scored_point.payload;
 
-                let page_content = payload[&self.content_field].to_string();
-                let metadata =
-                    serde_json::from_value(payload[&self.metadata_field].clone().into_json())
-                        .unwrap();
+                let page_content = payload
+                    .remove(&self.content_field)
+                    .map(|value| match value.into_json() {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    })
+                    .unwrap_or_default();
+                let metadata = payload
+                    .remove(&self.metadata_field)
+                    .and_then(|value| serde_json::from_value(value.into_json()).ok())
+                    .unwrap_or_default();
                 let score = scored_point.score as f64;
                 Document {
                     page_content,
@@ -120,4 +254,103 @@ impl VectorStore for Store {
 
         Ok(documents)
     }
+
+    fn supports_metadata_filter(&self) -> bool {
+        true
+    }
+
+    async fn delete(&self, ids: &[String], _opt: &VecStoreOptions) -> Result<(), Box<dyn Error>> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.client
+            .delete_points(
+                DeletePointsBuilder::new(&self.collection_name)
+                    .points(PointsIdsList::from(ids.to_vec()))
+                    .wait(true),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::embedding::FakeEmbedder;
+
+    use super::*;
+
+    fn store() -> Store {
+        // The client connects lazily, so no server is needed to build the filters.
+        Store {
+            client: Qdrant::from_url("http://localhost:6334").build().unwrap(),
+            embedder: Arc::new(FakeEmbedder::new(4)),
+            collection_name: "test".into(),
+            content_field: "page_content".into(),
+            metadata_field: "metadata".into(),
+            search_filter: None,
+            batch_size: 64,
+        }
+    }
+
+    #[test]
+    fn test_get_filter() {
+        let store = store();
+        assert_eq!(store.get_filter(&VecStoreOptions::new()).unwrap(), None);
+
+        let options = VecStoreOptions::new()
+            .with_filters(json!({"source": "wiki"}))
+            .with_metadata_filter(MetadataFilter::and(vec![
+                MetadataFilter::gte("year", 2010),
+                MetadataFilter::or(vec![
+                    MetadataFilter::ne("genre", "horror"),
+                    MetadataFilter::is_in("page", vec![1, 2]),
+                ]),
+            ]));
+        assert_eq!(
+            store.get_filter(&options).unwrap(),
+            Some(Filter::must([
+                Condition::matches("metadata.source", "wiki".to_string()),
+                Filter::must([
+                    Condition::range(
+                        "metadata.year",
+                        Range {
+                            gte: Some(2010.0),
+                            ..Default::default()
+                        }
+                    ),
+                    Filter::should([
+                        Filter::must_not([Condition::matches(
+                            "metadata.genre",
+                            "horror".to_string()
+                        )])
+                        .into(),
+                        Condition::matches("metadata.page", vec![1i64, 2]),
+                    ])
+                    .into(),
+                ])
+                .into(),
+            ]))
+        );
+
+        let options = VecStoreOptions::new().with_metadata_filter(MetadataFilter::gt("year", "x"));
+        assert!(store.get_filter(&options).is_err());
+        assert!(store
+            .get_filter(&VecStoreOptions::new().with_filters(json!(["wiki"])))
+            .is_err());
+    }
+
+    #[test]
+    fn test_match_condition() {
+        assert_eq!(
+            match_condition("metadata.source".into(), &json!("wiki")).unwrap(),
+            Condition::matches("metadata.source", "wiki".to_string())
+        );
+        assert_eq!(
+            match_condition("metadata.page".into(), &json!([1, 2])).unwrap(),
+            Condition::matches("metadata.page", vec![1i64, 2])
+        );
+        assert!(match_condition("metadata.rating".into(), &json!(4.5)).is_err());
+        assert!(match_condition("metadata.nested".into(), &json!({"a": 1})).is_err());
+    }
 }
@@ -2,9 +2,12 @@ use std::error::Error;
 
 use async_trait::async_trait;
 
-use crate::schemas::{self, Document};
+use crate::{
+    callbacks::{CallbackManager, RunType},
+    schemas::{self, Document},
+};
 
-use super::VecStoreOptions;
+use super::{maximal_marginal_relevance, VecStoreOptions};
 
 // VectorStore is the trait for saving and querying documents in the
 // form of vector embeddings.
@@ -22,6 +25,73 @@ pub trait VectorStore: Send + Sync {
         limit: usize,
         opt: &VecStoreOptions,
     ) -> Result<Vec<Document>, Box<dyn Error>>;
+
+    /// Like `similarity_search`, with the score of each document.
+    async fn similarity_search_with_score(
+        &self,
+        query: &str,
+        limit: usize,
+        opt: &VecStoreOptions,
+    ) -> Result<Vec<(Document, f64)>, Box<dyn Error>> {
+        let documents = self.similarity_search(query, limit, opt).await?;
+        Ok(documents
+            .into_iter()
+            .map(|document| {
+                let score = document.score;
+                (document, score)
+            })
+            .collect())
+    }
+
+    /// Diversifies the results: fetches the `fetch_k` documents most similar to the query,
+    /// and returns `k` of them picked by maximal marginal relevance, see
+    /// `maximal_marginal_relevance`. A `lambda` of 1 only ranks by similarity, a `lambda`
+    /// of 0 only by diversity.
+    ///
+    /// By default the documents are embedded again with the embedder of the options,
+    /// which is then required.
+    async fn max_marginal_relevance_search(
+        &self,
+        query: &str,
+        k: usize,
+        fetch_k: usize,
+        lambda: f64,
+        opt: &VecStoreOptions,
+    ) -> Result<Vec<Document>, Box<dyn Error>> {
+        let embedder = opt
+            .embedder
+            .clone()
+            .ok_or("max_marginal_relevance_search requires the embedder of the options")?;
+        let candidates = self
+            .similarity_search_with_score(query, fetch_k, opt)
+            .await?;
+        let texts: Vec<String> = candidates
+            .iter()
+            .map(|(document, _)| document.page_content.clone())
+            .collect();
+        let query = embedder.embed_query(query).await?;
+        let embeddings = embedder.embed_documents(&texts).await?;
+
+        let mut candidates: Vec<Option<Document>> = candidates
+            .into_iter()
+            .map(|(document, _)| Some(document))
+            .collect();
+        Ok(maximal_marginal_relevance(&query, &embeddings, k, lambda)
+            .into_iter()
+            .filter_map(|index| candidates[index].take())
+            .collect())
+    }
+
+    /// Whether the store applies the `metadata_filter` of the options. The other stores
+    /// ignore it.
+    fn supports_metadata_filter(&self) -> bool {
+        false
+    }
+
+    /// Deletes the documents with the ids returned by `add_documents`.
+    async fn delete(&self, _ids: &[String], _opt: &VecStoreOptions) -> Result<(), Box<dyn Error>> {
+        Err("This vector store doesn't support deleting documents".into())
+    }
 }
 impl<VS> From<VS> for Box<dyn VectorStore>
 where
@@ -80,8 +150,13 @@ impl Retriever {
 #[async_trait]
 impl schemas::Retriever for Retriever {
     async fn get_relevant_documents(&self, query: &str) -> Result<Vec<Document>, Box<dyn Error>> {
-        self.vstore
-            .similarity_search(query, self.num_docs, &self.options)
+        CallbackManager::new()
+            .start_run("Retriever", RunType::Retriever)
+            .trace_retriever(
+                query,
+                self.vstore
+                    .similarity_search(query, self.num_docs, &self.options),
+            )
             .await
     }
 }